@@ -0,0 +1,41 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use uorm::bench_fixtures;
+
+fn bench_parse(c: &mut Criterion) {
+    c.bench_function("tpl_parse_nested", |b| {
+        b.iter(|| bench_fixtures::parse(black_box(bench_fixtures::NESTED_TEMPLATE)))
+    });
+}
+
+fn bench_render(c: &mut Criterion) {
+    let params = bench_fixtures::nested_params(20, 10);
+    c.bench_function("tpl_render_nested_for_if", |b| {
+        b.iter(|| {
+            bench_fixtures::render(bench_fixtures::NESTED_TEMPLATE, black_box(&params)).unwrap()
+        })
+    });
+}
+
+fn bench_serialize(c: &mut Criterion) {
+    let large = bench_fixtures::large_struct(200);
+    c.bench_function("serializer_to_value_large_struct", |b| {
+        b.iter(|| bench_fixtures::serialize(black_box(&large)))
+    });
+}
+
+fn bench_deserialize(c: &mut Criterion) {
+    let rows = bench_fixtures::rows(10_000);
+    c.bench_function("row_deserialize_10k", |b| {
+        b.iter(|| bench_fixtures::deserialize_rows(black_box(&rows)).unwrap())
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_parse,
+    bench_render,
+    bench_serialize,
+    bench_deserialize
+);
+criterion_main!(benches);
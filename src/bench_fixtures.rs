@@ -0,0 +1,148 @@
+//! 基准测试夹具
+//!
+//! 提供渲染路径各阶段（模板解析、嵌套 for/if 渲染、参数序列化、行反序列化）使用的
+//! 固定输入，下游项目可用同样的数据在不同版本之间对比自身负载。
+
+use crate::error::DbError;
+use crate::tpl::engine::render_template;
+use crate::tpl::harness::OfflineDriver;
+use crate::udbc::deserializer::RowDeserializer;
+use crate::udbc::serializer::to_value;
+use crate::udbc::value::Value;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 含嵌套 `<for>`/`<if>` 的模板
+pub const NESTED_TEMPLATE: &str = "INSERT INTO order_items (order_id, sku, qty, note) VALUES \
+<for item=\"o\" collection=\"orders\" sep=\",\">\
+<for item=\"i\" collection=\"o.items\" sep=\",\">\
+(#{o.id}, #{i.sku}, #{i.qty}, <if test=\"i.qty > 10\">'bulk'</if><if test=\"i.qty <= 10\">#{i.note}</if>)\
+</for></for>";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FixtureItem {
+    pub sku: String,
+    pub qty: i32,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FixtureOrder {
+    pub id: i64,
+    pub items: Vec<FixtureItem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NestedParams {
+    pub orders: Vec<FixtureOrder>,
+}
+
+/// 生成 `orders` 个订单、每单 `items` 个明细的嵌套参数
+pub fn nested_params(orders: usize, items: usize) -> NestedParams {
+    NestedParams {
+        orders: (0..orders)
+            .map(|o| FixtureOrder {
+                id: o as i64,
+                items: (0..items)
+                    .map(|i| FixtureItem {
+                        sku: format!("SKU-{}-{}", o, i),
+                        qty: (i % 20) as i32,
+                        note: (i % 2 == 0).then(|| "gift".to_string()),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+/// 字段较多的结构体，用于衡量参数序列化开销
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LargeStruct {
+    pub id: i64,
+    pub name: String,
+    pub email: String,
+    pub age: i32,
+    pub score: f64,
+    pub active: bool,
+    pub birthday: NaiveDate,
+    pub tags: Vec<String>,
+    pub attributes: HashMap<String, String>,
+    pub history: Vec<i64>,
+}
+
+/// 构造一个包含 `n` 个标签、属性与历史记录的大结构体
+pub fn large_struct(n: usize) -> LargeStruct {
+    LargeStruct {
+        id: 42,
+        name: "bench user".to_string(),
+        email: "bench@example.com".to_string(),
+        age: 30,
+        score: 99.5,
+        active: true,
+        birthday: NaiveDate::from_ymd_opt(1990, 1, 1).unwrap(),
+        tags: (0..n).map(|i| format!("tag{}", i)).collect(),
+        attributes: (0..n)
+            .map(|i| (format!("k{}", i), format!("v{}", i)))
+            .collect(),
+        history: (0..n as i64).collect(),
+    }
+}
+
+/// 行反序列化的目标类型
+#[derive(Debug, Clone, Deserialize)]
+pub struct FixtureRow {
+    pub id: i64,
+    pub name: String,
+    pub email: String,
+    pub age: i32,
+    pub score: f64,
+    pub active: bool,
+}
+
+/// 生成 `n` 行模拟驱动返回的数据（字符串列以 Bytes 形式给出，与 MySQL 驱动一致）
+pub fn rows(n: usize) -> Vec<HashMap<String, Value>> {
+    (0..n)
+        .map(|i| {
+            let mut row = HashMap::with_capacity(6);
+            row.insert("id".to_string(), Value::I64(i as i64));
+            row.insert(
+                "name".to_string(),
+                Value::Bytes(format!("user{}", i).into_bytes()),
+            );
+            row.insert(
+                "email".to_string(),
+                Value::Bytes(format!("user{}@example.com", i).into_bytes()),
+            );
+            row.insert("age".to_string(), Value::I64((i % 90) as i64));
+            row.insert("score".to_string(), Value::F64(i as f64 * 0.5));
+            row.insert("active".to_string(), Value::Bool(i % 2 == 0));
+            row
+        })
+        .collect()
+}
+
+/// 解析模板（绕过缓存），返回顶层节点数
+pub fn parse(template: &str) -> usize {
+    crate::tpl::parser::parse_template(template).len()
+}
+
+/// 使用 `?` 占位符离线渲染模板
+pub fn render<T: Serialize>(
+    template: &str,
+    params: &T,
+) -> Result<(String, Vec<(String, Value)>), DbError> {
+    render_template(template, template, params, &OfflineDriver::default())
+}
+
+/// 将参数序列化为 `Value`
+pub fn serialize<T: Serialize>(params: &T) -> Value {
+    to_value(params)
+}
+
+/// 将行数据反序列化为目标类型
+pub fn deserialize_rows(rows: &[HashMap<String, Value>]) -> Result<Vec<FixtureRow>, DbError> {
+    rows.iter()
+        .map(|r| FixtureRow::deserialize(RowDeserializer::new(r)))
+        .collect()
+}
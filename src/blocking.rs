@@ -0,0 +1,301 @@
+//! 同步（阻塞）API（`blocking` feature）
+//!
+//! 供命令行工具与尚未迁移到 async 的代码使用：与异步 API 共用 mapper XML 与模板引擎，
+//! 语句在内部的多线程运行时上执行，调用线程阻塞等待结果。
+//!
+//! 不能在异步运行时中调用这些方法（会 panic），异步代码应直接使用
+//! [`crate::executor::session::Session`] 与 [`crate::executor::mapper::Mapper`]。
+//!
+//! ```ignore
+//! use uorm::blocking;
+//!
+//! // mysql_async 的连接池须在运行时上下文中创建
+//! let driver = {
+//!     let _guard = blocking::runtime().enter();
+//!     MysqlDriver::new(url).build()?
+//! };
+//! UORM.register(driver)?;
+//! let mapper = blocking::Mapper::from(UORM.mapper("default").unwrap());
+//! let users: Vec<User> = mapper.list("user.list", &())?;
+//! ```
+
+use crate::error::DbError;
+use crate::executor::insert::ColumnMap;
+use crate::executor::mapper::Mapper as AsyncMapper;
+use crate::executor::multi::ResultSets;
+use crate::executor::options::QueryOptions;
+use crate::executor::session::Session as AsyncSession;
+use crate::transaction::{StatementRecord, TransactionContext};
+use crate::udbc::driver::Driver;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::sync::{Arc, LazyLock};
+use tokio::runtime::Runtime;
+
+static RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .thread_name("uorm-blocking")
+        .enable_all()
+        .build()
+        .expect("failed to start uorm blocking runtime")
+});
+
+/// 同步 API 使用的内部运行时
+///
+/// 创建依赖运行时的连接池（如 `MysqlDriver::build`）时应先 `runtime().enter()`。
+pub fn runtime() -> &'static Runtime {
+    &RUNTIME
+}
+
+fn block_on<F: Future>(fut: F) -> F::Output {
+    RUNTIME.block_on(fut)
+}
+
+/// [`AsyncSession`] 的同步版本，克隆同样只复制连接池句柄
+#[derive(Clone)]
+pub struct Session {
+    inner: AsyncSession,
+}
+
+impl From<AsyncSession> for Session {
+    fn from(inner: AsyncSession) -> Self {
+        Self { inner }
+    }
+}
+
+impl Session {
+    pub fn new(pool: Arc<dyn Driver>) -> Self {
+        Self {
+            inner: AsyncSession::new(pool),
+        }
+    }
+
+    /// 所用连接池的名称
+    pub fn database_name(&self) -> &str {
+        self.inner.database_name()
+    }
+
+    pub fn begin(&self) -> Result<Transaction, DbError> {
+        let inner = block_on(self.inner.begin())?;
+        Ok(Transaction { inner: Some(inner) })
+    }
+
+    pub fn execute<T: Serialize>(&self, sql: &str, args: &T) -> Result<u64, DbError> {
+        block_on(self.inner.execute(sql, args))
+    }
+
+    pub fn execute_named<T: Serialize>(
+        &self,
+        stmt_id: &str,
+        sql: &str,
+        args: &T,
+    ) -> Result<u64, DbError> {
+        block_on(self.inner.execute_named(stmt_id, sql, args))
+    }
+
+    pub fn query<R, T>(&self, sql: &str, args: &T) -> Result<Vec<R>, DbError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        block_on(self.inner.query(sql, args))
+    }
+
+    pub fn query_named<R, T>(&self, stmt_id: &str, sql: &str, args: &T) -> Result<Vec<R>, DbError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        block_on(self.inner.query_named(stmt_id, sql, args))
+    }
+
+    pub fn insert_into<T: Serialize>(&self, table: &str, entity: &T) -> Result<u64, DbError> {
+        block_on(self.inner.insert_into(table, entity))
+    }
+
+    pub fn insert_into_with<T: Serialize>(
+        &self,
+        table: &str,
+        entity: &T,
+        columns: &ColumnMap,
+    ) -> Result<u64, DbError> {
+        block_on(self.inner.insert_into_with(table, entity, columns))
+    }
+
+    pub fn exists<T: Serialize>(&self, sql: &str, args: &T) -> Result<bool, DbError> {
+        block_on(self.inner.exists(sql, args))
+    }
+
+    pub fn count<T: Serialize>(&self, sql: &str, args: &T) -> Result<u64, DbError> {
+        block_on(self.inner.count(sql, args))
+    }
+
+    pub fn query_multi<T: Serialize>(
+        &self,
+        statements: &[(&str, T)],
+    ) -> Result<ResultSets, DbError> {
+        block_on(self.inner.query_multi(statements))
+    }
+
+    pub fn last_insert_id(&self) -> Result<u64, DbError> {
+        block_on(self.inner.last_insert_id())
+    }
+}
+
+/// [`TransactionContext`] 的同步版本；未提交即释放时回滚
+pub struct Transaction {
+    inner: Option<TransactionContext>,
+}
+
+impl Transaction {
+    fn inner(&self) -> &TransactionContext {
+        self.inner.as_ref().expect("transaction already released")
+    }
+
+    fn inner_mut(&mut self) -> &mut TransactionContext {
+        self.inner.as_mut().expect("transaction already released")
+    }
+
+    pub fn commit(&mut self) -> Result<(), DbError> {
+        block_on(self.inner_mut().commit())
+    }
+
+    pub fn rollback(&mut self) -> Result<(), DbError> {
+        block_on(self.inner_mut().rollback())
+    }
+
+    pub fn execute<T: Serialize>(&self, sql: &str, args: &T) -> Result<u64, DbError> {
+        block_on(self.inner().execute(sql, args))
+    }
+
+    pub fn query<T: Serialize>(
+        &self,
+        sql: &str,
+        args: &T,
+    ) -> Result<Vec<std::collections::HashMap<String, crate::udbc::value::Value>>, DbError> {
+        block_on(self.inner().query(sql, args))
+    }
+
+    pub fn last_insert_id(&self) -> Result<u64, DbError> {
+        block_on(self.inner().last_insert_id())
+    }
+
+    pub fn history(&self) -> Vec<StatementRecord> {
+        self.inner().history()
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        // 隐式回滚在运行时上派生任务，须在运行时上下文中释放
+        let _guard = RUNTIME.enter();
+        self.inner.take();
+    }
+}
+
+/// [`AsyncMapper`] 的同步版本
+pub struct Mapper {
+    inner: AsyncMapper,
+}
+
+impl From<AsyncMapper> for Mapper {
+    fn from(inner: AsyncMapper) -> Self {
+        Self { inner }
+    }
+}
+
+impl Mapper {
+    pub fn new(pool: Arc<dyn Driver>) -> Self {
+        Self {
+            inner: AsyncMapper::new(pool),
+        }
+    }
+
+    /// 返回带有单次调用查询选项的映射器
+    pub fn with_options(&self, options: QueryOptions) -> Mapper {
+        Mapper {
+            inner: self.inner.with_options(options),
+        }
+    }
+
+    pub fn get<R, T>(&self, sql_id: &str, args: &T) -> Result<R, DbError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        block_on(self.inner.get(sql_id, args))
+    }
+
+    pub fn find<R, T>(&self, sql_id: &str, args: &T) -> Result<Option<R>, DbError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        block_on(self.inner.find(sql_id, args))
+    }
+
+    pub fn list<R, T>(&self, sql_id: &str, args: &T) -> Result<Vec<R>, DbError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        block_on(self.inner.list(sql_id, args))
+    }
+
+    pub fn create<R, T>(&self, sql_id: &str, args: &T) -> Result<R, DbError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        block_on(self.inner.create(sql_id, args))
+    }
+
+    pub fn batch_create<R, T>(&self, sql_id: &str, args: &[T]) -> Result<Vec<R>, DbError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        block_on(self.inner.batch_create(sql_id, args))
+    }
+
+    pub fn create_returning<R, T>(&self, sql_id: &str, args: &T) -> Result<R, DbError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        block_on(self.inner.create_returning(sql_id, args))
+    }
+
+    pub fn create_graph<R, T>(&self, sql_id: &str, args: &T) -> Result<R, DbError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        block_on(self.inner.create_graph(sql_id, args))
+    }
+
+    pub fn update<T: Serialize>(&self, sql_id: &str, args: &T) -> Result<u64, DbError> {
+        block_on(self.inner.update(sql_id, args))
+    }
+
+    pub fn batch_update<T: Serialize>(
+        &self,
+        sql_id: &str,
+        args: &[T],
+    ) -> Result<Vec<u64>, DbError> {
+        block_on(self.inner.batch_update(sql_id, args))
+    }
+
+    pub fn batch_update_in_tx<T: Serialize>(
+        &self,
+        sql_id: &str,
+        args: &[T],
+    ) -> Result<Vec<u64>, DbError> {
+        block_on(self.inner.batch_update_in_tx(sql_id, args))
+    }
+
+    pub fn delete<T: Serialize>(&self, sql_id: &str, args: &T) -> Result<u64, DbError> {
+        block_on(self.inner.delete(sql_id, args))
+    }
+}
@@ -0,0 +1,462 @@
+//! 语句观察窗口：采样记录线上负载，并在预发布库上回放
+//!
+//! 开启后在采样窗口内按比例记录经 `Session`/`Mapper` 执行的语句（SQL ID、参数摘要、耗时、行数），
+//! 以 JSON Lines 写入文件；[`replay`] 读取该文件，在另一连接池上按原顺序重新执行，
+//! 用于压测与数据库升级验证，结果按语句汇总为耗时对比。
+//!
+//! ```ignore
+//! // 线上：采样 10% 的语句，持续 5 分钟，记录参数值以便回放
+//! let options = CaptureOptions { sample_rate: 0.1, window: Duration::from_secs(300), params: true };
+//! capture::capture("workload.jsonl", options).await?;
+//! // 预发布：以 8 个并发、两倍速度回放
+//! let options = ReplayOptions { concurrency: 8, speed: Some(2.0), ..Default::default() };
+//! let report = capture::replay(staging.as_ref(), "workload.jsonl", &options).await?;
+//! println!("{}", report);
+//! ```
+//!
+//! 记录中默认只保留参数摘要，这样的记录回放时跳过；开启 [`CaptureOptions::params`]
+//! 后记录参数值以便回放，此时文件可能包含敏感数据。
+
+use crate::error::DbError;
+use crate::executor::digest::{self, fnv1a};
+use crate::executor::instrument::Outcome;
+use crate::udbc::driver::Driver;
+use crate::udbc::value::Value;
+use futures_util::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// 采样选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureOptions {
+    /// 记录的语句比例，`0.0` 到 `1.0`，按执行顺序均匀采样
+    pub sample_rate: f64,
+    /// 采样窗口，开始后超过该时长的语句不再记录
+    pub window: Duration,
+    /// 是否记录参数值，默认关闭；关闭时只记录参数摘要，记录无法回放
+    pub params: bool,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        Self {
+            sample_rate: 1.0,
+            window: Duration::from_secs(60),
+            params: false,
+        }
+    }
+}
+
+/// 记录下的一条语句，对应文件中的一行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapturedStatement {
+    /// 相对采样开始的时间（毫秒），回放时用于还原节奏
+    pub offset_ms: u64,
+    /// 操作类型，如 `query`、`execute`
+    pub op: String,
+    /// 执行语句的连接池名
+    pub pool: String,
+    /// 语句 ID；直接执行 SQL 文本时为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sql_id: Option<String>,
+    /// 渲染后的 SQL
+    pub sql: String,
+    /// 参数的 16 位十六进制摘要，参数相同的执行摘要相同
+    pub params_digest: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Vec<(String, Value)>>,
+    pub latency_us: u64,
+    /// 查询返回或更新影响的行数；执行失败时为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rows: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CapturedStatement {
+    /// 是否为更新语句
+    pub fn is_write(&self) -> bool {
+        self.op.ends_with("execute")
+    }
+
+    /// 汇总统计所用的键：语句 ID，没有时为 SQL 指纹
+    fn key(&self) -> String {
+        self.sql_id
+            .clone()
+            .unwrap_or_else(|| digest::fingerprint(&self.sql))
+    }
+}
+
+/// 采样结束时的统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CaptureSummary {
+    /// 窗口内执行的语句数
+    pub seen: u64,
+    /// 写入文件的语句数
+    pub recorded: u64,
+}
+
+/// 正在进行的采样
+struct Recorder {
+    options: CaptureOptions,
+    started: Instant,
+    seen: AtomicU64,
+    recorded: AtomicU64,
+    writer: Mutex<BufWriter<File>>,
+    /// 第一次写入失败的错误，结束时返回
+    failure: Mutex<Option<String>>,
+}
+
+/// 按执行顺序均匀采样：第 `seq` 条语句（从 0 开始）在 `(seq + 1) * rate` 跨过整数时记录
+fn sampled(rate: f64, seq: u64) -> bool {
+    let rate = rate.clamp(0.0, 1.0);
+    ((seq + 1) as f64 * rate).floor() > (seq as f64 * rate).floor()
+}
+
+impl Recorder {
+    fn write(&self, entry: &CapturedStatement) {
+        let result = serde_json::to_string(entry)
+            .map_err(|e| e.to_string())
+            .and_then(|line| {
+                writeln!(self.writer.lock().unwrap(), "{}", line).map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(()) => {
+                self.recorded.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => {
+                self.failure.lock().unwrap().get_or_insert(e);
+            }
+        }
+    }
+}
+
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+static RECORDER: LazyLock<RwLock<Option<Arc<Recorder>>>> = LazyLock::new(|| RwLock::new(None));
+
+/// 开始采样，记录写入 `path`（覆盖原有文件）；已在采样时返回错误
+pub fn start(path: impl AsRef<Path>, options: CaptureOptions) -> Result<(), DbError> {
+    let path = path.as_ref();
+    let mut slot = RECORDER.write().unwrap();
+    if slot.is_some() {
+        return Err(DbError::General("statement capture already running".into()));
+    }
+    let file = File::create(path)
+        .map_err(|e| DbError::General(format!("create {}: {}", path.display(), e)))?;
+    *slot = Some(Arc::new(Recorder {
+        options,
+        started: Instant::now(),
+        seen: AtomicU64::new(0),
+        recorded: AtomicU64::new(0),
+        writer: Mutex::new(BufWriter::new(file)),
+        failure: Mutex::new(None),
+    }));
+    CAPTURING.store(true, Ordering::Release);
+    Ok(())
+}
+
+/// 结束采样并写出缓冲的记录；未在采样时返回 `None`
+pub fn stop() -> Result<Option<CaptureSummary>, DbError> {
+    let Some(recorder) = RECORDER.write().unwrap().take() else {
+        return Ok(None);
+    };
+    CAPTURING.store(false, Ordering::Release);
+    let flushed = recorder.writer.lock().unwrap().flush();
+    if let Some(e) = recorder.failure.lock().unwrap().take() {
+        return Err(DbError::General(format!("write statement capture: {}", e)));
+    }
+    flushed.map_err(|e| DbError::General(format!("write statement capture: {}", e)))?;
+    Ok(Some(CaptureSummary {
+        seen: recorder.seen.load(Ordering::Relaxed),
+        recorded: recorder.recorded.load(Ordering::Relaxed),
+    }))
+}
+
+/// 是否正在采样
+pub fn is_capturing() -> bool {
+    CAPTURING.load(Ordering::Acquire)
+}
+
+/// 采样一个完整窗口：开始、等待 `options.window` 后结束
+pub async fn capture(
+    path: impl AsRef<Path>,
+    options: CaptureOptions,
+) -> Result<CaptureSummary, DbError> {
+    let window = options.window;
+    start(path, options)?;
+    tokio::time::sleep(window).await;
+    Ok(stop()?.unwrap_or_default())
+}
+
+/// 语句执行结束时由埋点调用
+pub(crate) fn observe(op: &str, pool: &str, sql_id: Option<&str>, outcome: &Outcome<'_>) {
+    // 批量导入没有可回放的 SQL
+    if op == "bulk_load" {
+        return;
+    }
+    let Some(recorder) = RECORDER.read().unwrap().clone() else {
+        return;
+    };
+    let offset = recorder.started.elapsed();
+    if offset > recorder.options.window {
+        return;
+    }
+    let seq = recorder.seen.fetch_add(1, Ordering::Relaxed);
+    if !sampled(recorder.options.sample_rate, seq) {
+        return;
+    }
+    recorder.write(&CapturedStatement {
+        offset_ms: offset.as_millis() as u64,
+        op: op.to_string(),
+        pool: pool.to_string(),
+        sql_id: sql_id.map(str::to_string),
+        sql: outcome.sql.to_string(),
+        params_digest: params_digest(outcome.params),
+        params: recorder.options.params.then(|| outcome.params.to_vec()),
+        latency_us: outcome.elapsed.as_micros() as u64,
+        rows: outcome.rows,
+        error: outcome.error.map(|e| e.to_string()),
+    });
+}
+
+/// 参数摘要：参数序列化后的 FNV-1a 哈希，跨进程稳定
+pub fn params_digest(params: &[(String, Value)]) -> String {
+    let text = serde_json::to_string(params).unwrap_or_default();
+    format!("{:016x}", fnv1a(&text))
+}
+
+/// 读取采样文件
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<CapturedStatement>, DbError> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .map_err(|e| DbError::General(format!("read {}: {}", path.display(), e)))?;
+    let mut statements = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| DbError::General(format!("read {}: {}", path.display(), e)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let statement = serde_json::from_str(&line)
+            .map_err(|e| DbError::General(format!("parse {}:{}: {}", path.display(), i + 1, e)))?;
+        statements.push(statement);
+    }
+    Ok(statements)
+}
+
+/// 回放选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayOptions {
+    /// 同时执行的语句数
+    pub concurrency: usize,
+    /// 按记录的时间间隔回放的速度倍数，如 `2.0` 为两倍速；`None` 表示不等待、尽快执行
+    pub speed: Option<f64>,
+    /// 跳过更新语句，只回放查询
+    pub skip_writes: bool,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 1,
+            speed: None,
+            skip_writes: false,
+        }
+    }
+}
+
+/// 单条语句（按语句 ID 或 SQL 指纹汇总）在采样与回放中的耗时对比
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyComparison {
+    /// 回放成功的次数
+    pub count: u64,
+    /// 这些执行在采样中的总耗时
+    pub captured: Duration,
+    /// 回放的总耗时
+    pub replayed: Duration,
+}
+
+impl LatencyComparison {
+    /// 回放相对采样的耗时变化比例，如 `0.25` 表示慢了 25%
+    pub fn change(&self) -> f64 {
+        if self.captured.is_zero() {
+            return 0.0;
+        }
+        self.replayed.as_secs_f64() / self.captured.as_secs_f64() - 1.0
+    }
+}
+
+/// 回放失败的语句
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayFailure {
+    pub key: String,
+    pub error: String,
+}
+
+/// [`replay`] 的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// 成功执行的语句数
+    pub executed: u64,
+    /// 未执行的语句数：没有记录参数、采样时即已失败或按选项跳过的更新语句
+    pub skipped: u64,
+    /// 返回或影响的行数与采样时不同的语句数
+    pub row_mismatches: u64,
+    pub failures: Vec<ReplayFailure>,
+    /// 按语句 ID（没有时为 SQL 指纹）汇总的耗时对比
+    pub statements: BTreeMap<String, LatencyComparison>,
+}
+
+impl ReplayReport {
+    /// 是否全部回放成功
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "executed {}, skipped {}, failed {}, row mismatches {}",
+            self.executed,
+            self.skipped,
+            self.failures.len(),
+            self.row_mismatches
+        )?;
+        for (key, stats) in &self.statements {
+            let mean = |total: Duration| total.as_secs_f64() * 1000.0 / stats.count.max(1) as f64;
+            writeln!(
+                f,
+                "{}: {} runs, captured {:.2}ms, replayed {:.2}ms ({:+.0}%)",
+                key,
+                stats.count,
+                mean(stats.captured),
+                mean(stats.replayed),
+                stats.change() * 100.0
+            )?;
+        }
+        for failure in &self.failures {
+            writeln!(f, "{}: {}", failure.key, failure.error)?;
+        }
+        Ok(())
+    }
+}
+
+/// 一条语句的回放结果
+enum Replayed {
+    Skipped,
+    Done {
+        key: String,
+        captured: Duration,
+        result: Result<(Duration, bool), DbError>,
+    },
+}
+
+/// 在 `driver` 上回放 `path` 中记录的语句
+///
+/// 语句直接在驱动连接上执行，不经过 `Session`，因而不会被正在进行的采样再次记录。
+/// 并发大于 1 时语句按记录顺序开始执行，但完成顺序不定。
+pub async fn replay(
+    driver: &dyn Driver,
+    path: impl AsRef<Path>,
+    options: &ReplayOptions,
+) -> Result<ReplayReport, DbError> {
+    let statements = load(path)?;
+    let started = tokio::time::Instant::now();
+    let results: Vec<Replayed> = stream::iter(statements)
+        .map(|statement| async move {
+            let Some(params) = statement.params.as_deref() else {
+                return Replayed::Skipped;
+            };
+            if statement.error.is_some() || (options.skip_writes && statement.is_write()) {
+                return Replayed::Skipped;
+            }
+            if let Some(speed) = options.speed.filter(|s| *s > 0.0) {
+                let offset = Duration::from_millis(statement.offset_ms).div_f64(speed);
+                tokio::time::sleep_until(started + offset).await;
+            }
+            let start = Instant::now();
+            let result = async {
+                let conn = driver.connection().await?;
+                let rows = match statement.is_write() {
+                    true => conn.execute(&statement.sql, params).await?,
+                    false => conn.query(&statement.sql, params).await?.len() as u64,
+                };
+                Ok((start.elapsed(), statement.rows == Some(rows)))
+            }
+            .await;
+            Replayed::Done {
+                key: statement.key(),
+                captured: Duration::from_micros(statement.latency_us),
+                result,
+            }
+        })
+        .buffered(options.concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut report = ReplayReport::default();
+    for replayed in results {
+        match replayed {
+            Replayed::Skipped => report.skipped += 1,
+            Replayed::Done {
+                key,
+                result: Err(e),
+                ..
+            } => report.failures.push(ReplayFailure {
+                key,
+                error: e.to_string(),
+            }),
+            Replayed::Done {
+                key,
+                captured,
+                result: Ok((elapsed, rows_match)),
+            } => {
+                report.executed += 1;
+                if !rows_match {
+                    report.row_mismatches += 1;
+                }
+                let stats = report.statements.entry(key).or_default();
+                stats.count += 1;
+                stats.captured += captured;
+                stats.replayed += elapsed;
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampling_is_even() {
+        let count = |rate| (0..100).filter(|&seq| sampled(rate, seq)).count();
+        assert_eq!(count(1.0), 100);
+        assert_eq!(count(0.25), 25);
+        assert_eq!(count(0.0), 0);
+        assert!(
+            (0..4)
+                .map(|seq| sampled(0.25, seq))
+                .eq([false, false, false, true])
+        );
+    }
+
+    #[test]
+    fn test_params_digest() {
+        let a = vec![("id".to_string(), Value::I64(1))];
+        let b = vec![("id".to_string(), Value::I64(2))];
+        assert_eq!(params_digest(&a), params_digest(&a.clone()));
+        assert_ne!(params_digest(&a), params_digest(&b));
+        assert_eq!(params_digest(&a).len(), 16);
+    }
+}
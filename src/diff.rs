@@ -0,0 +1,158 @@
+//! 行级差异计算
+//!
+//! [`changed_fields`] 比较同一实体的新旧两个版本，得到实际发生变化的字段；
+//! 配合模板中的 `<set from="changeset"/>`，UPDATE 语句只写入这些列，
+//! 减少锁竞争与 binlog 体积。
+//!
+//! ```xml
+//! <update id="updateUser">
+//!     UPDATE user <set from="changes"/> WHERE id = #{id}
+//! </update>
+//! ```
+
+use crate::udbc::serializer::to_value;
+use crate::udbc::value::Value;
+use serde::Serialize;
+use serde::ser::SerializeMap;
+
+/// 发生变化的字段及其新值，按字段名排序
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChangeSet {
+    fields: Vec<(String, Value)>,
+}
+
+impl ChangeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 手动加入一个字段；字段已存在时覆盖其值
+    pub fn insert(&mut self, field: impl Into<String>, value: impl Into<Value>) {
+        let field = field.into();
+        let value = value.into();
+        match self
+            .fields
+            .binary_search_by(|(f, _)| f.as_str().cmp(&field))
+        {
+            Ok(i) => self.fields[i].1 = value,
+            Err(i) => self.fields.insert(i, (field, value)),
+        }
+    }
+
+    /// 移除一个字段（例如不允许更新的主键），返回其值
+    pub fn remove(&mut self, field: &str) -> Option<Value> {
+        let i = self
+            .fields
+            .binary_search_by(|(f, _)| f.as_str().cmp(field))
+            .ok()?;
+        Some(self.fields.remove(i).1)
+    }
+
+    pub fn get(&self, field: &str) -> Option<&Value> {
+        self.fields
+            .binary_search_by(|(f, _)| f.as_str().cmp(field))
+            .ok()
+            .map(|i| &self.fields[i].1)
+    }
+
+    pub fn contains(&self, field: &str) -> bool {
+        self.get(field).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// 按字段名顺序遍历
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.fields.iter().map(|(f, v)| (f.as_str(), v))
+    }
+
+    /// 发生变化的字段名
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|(f, _)| f.as_str())
+    }
+}
+
+/// 以字段名到新值的映射序列化，可直接作为语句参数的一个字段
+impl Serialize for ChangeSet {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for (field, value) in &self.fields {
+            map.serialize_entry(field, value)?;
+        }
+        map.end()
+    }
+}
+
+/// 比较同一类型的新旧两个值，返回新值中与旧值不同的顶层字段
+///
+/// 只有在 `new` 中出现的字段会被比较；嵌套结构按整体比较。
+/// 参数序列化后不是结构体或映射时返回空的 [`ChangeSet`]。
+pub fn changed_fields<T: Serialize>(old: &T, new: &T) -> ChangeSet {
+    let (Value::Map(old), Value::Map(new)) = (to_value(old), to_value(new)) else {
+        return ChangeSet::new();
+    };
+    let mut fields: Vec<(String, Value)> = new
+        .into_iter()
+        .filter(|(field, value)| old.get(field) != Some(value))
+        .collect();
+    fields.sort_by(|a, b| a.0.cmp(&b.0));
+    ChangeSet { fields }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct User {
+        id: i64,
+        name: String,
+        age: Option<i32>,
+        email: String,
+    }
+
+    fn user(name: &str, age: Option<i32>) -> User {
+        User {
+            id: 1,
+            name: name.to_string(),
+            age,
+            email: "a@example.com".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_changed_fields() {
+        let changes = changed_fields(&user("alice", Some(20)), &user("bob", None));
+        assert_eq!(changes.fields().collect::<Vec<_>>(), ["age", "name"]);
+        assert_eq!(changes.get("age"), Some(&Value::Null));
+        assert_eq!(changes.get("name"), Some(&Value::Str("bob".into())));
+
+        let same = changed_fields(&user("alice", Some(20)), &user("alice", Some(20)));
+        assert!(same.is_empty());
+    }
+
+    #[test]
+    fn test_insert_remove_keep_order() {
+        let mut changes = ChangeSet::new();
+        changes.insert("b", 2);
+        changes.insert("a", 1);
+        changes.insert("b", 3);
+        assert_eq!(
+            changes.iter().collect::<Vec<_>>(),
+            [("a", &Value::I32(1)), ("b", &Value::I32(3))]
+        );
+        assert_eq!(changes.remove("a"), Some(Value::I32(1)));
+        assert!(!changes.contains("a"));
+        assert_eq!(to_value(&changes), {
+            let mut m = std::collections::HashMap::new();
+            m.insert("b".to_string(), Value::I32(3));
+            Value::Map(m)
+        });
+    }
+}
@@ -1,21 +1,79 @@
-use std::sync::{Arc, LazyLock};
+use std::sync::{Arc, LazyLock, Weak};
+use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
+use futures_util::future::try_join_all;
 
 use crate::error::DbError;
 use crate::executor::mapper::Mapper;
 use crate::executor::session::Session;
+use crate::mapper_loader::{self, template_key};
+use crate::tpl::engine;
 use crate::udbc::driver::Driver;
+use crate::udbc::failover::{FailoverDriver, FailoverUrl};
+use crate::udbc::listener::ListenedDriver;
 
 // 全局单例（Rust 1.80+ 推荐）
 pub static UORM: LazyLock<DriverManager> = LazyLock::new(DriverManager::new);
 
+/// 由连接池名与数据库 URL 创建驱动
+pub type DriverFactory = fn(name: &str, url: &str) -> Result<Arc<dyn Driver>, DbError>;
+
+/// URL scheme（小写）-> 驱动工厂
+static FACTORIES: LazyLock<DashMap<String, DriverFactory>> = LazyLock::new(|| {
+    let factories: DashMap<String, DriverFactory> = DashMap::new();
+    #[cfg(feature = "mysql")]
+    factories.insert("mysql".to_string(), mysql_factory);
+    factories
+});
+
+#[cfg(feature = "mysql")]
+fn mysql_factory(name: &str, url: &str) -> Result<Arc<dyn Driver>, DbError> {
+    let driver = crate::udbc_mysql::pool::MysqlDriver::new(url)
+        .name(name.to_string())
+        .build()?;
+    Ok(Arc::new(driver))
+}
+
+/// 注册 URL scheme 对应的驱动工厂，已存在时覆盖
+///
+/// 第三方驱动通常通过 [`crate::register_driver_factory!`] 在程序启动时自动注册。
+pub fn register_driver_factory(scheme: &str, factory: DriverFactory) {
+    FACTORIES.insert(scheme.to_ascii_lowercase(), factory);
+}
+
+/// 在程序启动时（main 之前）注册驱动工厂
+///
+/// ```ignore
+/// fn snowflake(name: &str, url: &str) -> Result<Arc<dyn Driver>, DbError> { ... }
+///
+/// uorm::register_driver_factory!("snowflake", snowflake);
+/// // 之后即可：UORM.register_url("default", "snowflake://account/db")
+/// ```
+#[macro_export]
+macro_rules! register_driver_factory {
+    ($scheme:expr, $factory:expr) => {
+        const _: () = {
+            #[$crate::ctor::ctor]
+            fn __uorm_register_driver_factory() {
+                $crate::driver_manager::register_driver_factory($scheme, $factory);
+            }
+        };
+    };
+}
+
 /// 数据库连接池管理器
 /// Manages database connection pools
 pub struct DriverManager {
     pools: DashMap<String, Arc<dyn Driver>>,
 }
 
+impl Default for DriverManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DriverManager {
     pub fn new() -> Self {
         Self {
@@ -24,16 +82,71 @@ impl DriverManager {
     }
 
     /// 注册数据库连接池
+    ///
+    /// 驱动配置了保活间隔（[`Driver::keepalive_interval`]）时，同时启动该连接池的后台维护任务，
+    /// 需在 tokio 运行时中调用。
     pub fn register(&self, driver: impl Driver + 'static) -> Result<(), DbError> {
-        self.pools
-            .insert(driver.name().to_string(), Arc::new(driver));
+        self.insert(driver.name().to_string(), Arc::new(driver));
+        Ok(())
+    }
+
+    /// 按 URL 的 scheme 选择驱动工厂创建连接池，并以 `name` 注册
+    ///
+    /// URL 列出多个主机（`mysql://a,b,c/db?failover=primary-first`）时为每个主机创建连接池，
+    /// 并以 [`FailoverDriver`] 组合，见 [`crate::udbc::failover`]。
+    pub fn register_url(&self, name: &str, url: &str) -> Result<(), DbError> {
+        let scheme = url
+            .split_once("://")
+            .map(|(scheme, _)| scheme.to_ascii_lowercase())
+            .ok_or_else(|| DbError::InvalidDatabaseUrl(url.to_string()))?;
+        let factory = *FACTORIES
+            .get(&scheme)
+            .ok_or(DbError::UnsupportedDatabaseType(scheme))?;
+        let driver = match FailoverUrl::parse(url)? {
+            Some(failover) => {
+                let hosts = failover
+                    .hosts
+                    .into_iter()
+                    .map(|(host, url)| Ok((host, factory(name, &url)?)))
+                    .collect::<Result<Vec<_>, DbError>>()?;
+                Arc::new(FailoverDriver::new(name, hosts, failover.options)?)
+            }
+            None => factory(name, url)?,
+        };
+        self.insert(name.to_string(), driver);
         Ok(())
     }
 
+    /// 连接池统一包装为 [`ListenedDriver`]，以便触发 [`EventListener`](crate::events::EventListener) 事件
+    fn insert(&self, name: String, driver: Arc<dyn Driver>) {
+        let driver: Arc<dyn Driver> = Arc::new(ListenedDriver::new(driver));
+        if let Some(interval) = driver.keepalive_interval() {
+            spawn_keepalive(Arc::downgrade(&driver), interval);
+        }
+        self.pools.insert(name, driver);
+    }
+
+    /// 已注册的连接池及其注册名，按名称排序
+    pub fn drivers(&self) -> Vec<(String, Arc<dyn Driver>)> {
+        let mut drivers: Vec<_> = self
+            .pools
+            .iter()
+            .map(|v| (v.key().clone(), v.value().clone()))
+            .collect();
+        drivers.sort_by(|a, b| a.0.cmp(&b.0));
+        drivers
+    }
+
+    /// 获取已注册的连接池
+    pub fn driver(&self, db_name: &str) -> Option<Arc<dyn Driver>> {
+        self.pools.get(db_name).map(|v| v.value().clone())
+    }
+
     /// 从指定模式加载 XML mapper 文件
     ///
     /// # 参数
     /// * `pattern` - 文件路径匹配模式，例如 "src/resources/**/*.xml"
+    #[cfg(feature = "xml")]
     pub fn assets(&self, pattern: &str) -> Result<(), DbError> {
         crate::mapper_loader::load(pattern).map_err(|e| {
             DbError::General(format!("Failed to load mapper assets from pattern: {}", e))
@@ -53,4 +166,112 @@ impl DriverManager {
             .get(db_name)
             .map(|v| Mapper::new(v.value().clone()))
     }
+
+    /// 按语句绑定的连接池（语句的 `route` 或 `<mapper datasource>`）获取 Mapper，
+    /// 未绑定时使用默认连接池
+    pub fn mapper_for(&self, sql_id: &str) -> Option<Mapper> {
+        match crate::mapper_loader::statement_datasource(sql_id) {
+            Some(db_name) => self.mapper(&db_name),
+            None => self.mapper(crate::udbc::DEFAULT_DB_NAME),
+        }
+    }
+
+    /// 启动预热：预先建立连接并解析、渲染常用语句，避免部署后的第一批请求承担冷启动延迟
+    ///
+    /// 同时打开 `min_conns` 个连接后归还连接池；超出驱动空闲上限的连接会被连接池关闭。
+    /// 语句按连接池的数据库类型选择并解析进模板缓存，再以必填参数均为 `NULL` 的参数渲染一次，
+    /// 严格模式下同样经过必填参数检查；该次渲染的错误不视为预热失败。
+    /// 语句 ID 不存在时返回错误。
+    pub async fn warm_up(
+        &self,
+        db_name: &str,
+        options: WarmUpOptions,
+    ) -> Result<WarmUpReport, DbError> {
+        let started = Instant::now();
+        let driver = self.driver(db_name).ok_or_else(|| {
+            DbError::General(format!("database pool '{}' is not registered", db_name))
+        })?;
+
+        let conns = try_join_all((0..options.min_conns).map(|_| driver.connection())).await?;
+        let connections = conns.len();
+        drop(conns);
+
+        for sql_id in &options.sql_ids {
+            // 查找与解析在同一视图内完成
+            let snapshot = mapper_loader::snapshot();
+            let mapper = snapshot
+                .find_mapper(sql_id, driver.r#type())
+                .ok_or_else(|| DbError::Query(format!("SQL ID not found: {}", sql_id)))?;
+            let content = mapper
+                .content
+                .as_deref()
+                .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
+            let key = template_key(sql_id, mapper.database_type.as_deref());
+            engine::warm_up(&key, content, driver.as_ref());
+        }
+
+        Ok(WarmUpReport {
+            connections,
+            statements: options.sql_ids.len(),
+            elapsed: started.elapsed(),
+        })
+    }
+}
+
+/// [`DriverManager::warm_up`] 的选项
+#[derive(Debug, Clone, Default)]
+pub struct WarmUpOptions {
+    /// 预先建立的连接数
+    pub min_conns: usize,
+    /// 预先解析与渲染的语句 ID
+    pub sql_ids: Vec<String>,
+}
+
+/// 预热结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmUpReport {
+    /// 建立的连接数
+    pub connections: usize,
+    /// 预热的语句数
+    pub statements: usize,
+    pub elapsed: Duration,
+}
+
+/// 按间隔调用 [`Driver::maintain`]，连接池被替换且不再被 Session 持有后退出
+fn spawn_keepalive(driver: Weak<dyn Driver>, interval: Duration) {
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        tracing::warn!("no tokio runtime, pool keepalive disabled");
+        return;
+    };
+    handle.spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // 第一次 tick 立即完成，跳过
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let Some(driver) = driver.upgrade() else {
+                break;
+            };
+            match driver.maintain().await {
+                Ok(report) if report.dropped > 0 || report.trimmed > 0 => tracing::info!(
+                    db.name = driver.name(),
+                    alive = report.alive,
+                    dropped = report.dropped,
+                    trimmed = report.trimmed,
+                    "pool keepalive"
+                ),
+                Ok(report) => tracing::debug!(
+                    db.name = driver.name(),
+                    alive = report.alive,
+                    "pool keepalive"
+                ),
+                Err(e) => tracing::warn!(
+                    db.name = driver.name(),
+                    error = %e,
+                    "pool keepalive failed"
+                ),
+            }
+        }
+    });
 }
@@ -1,6 +1,7 @@
+use crate::udbc::deadlock::{DeadlockSummary, LockError};
 use thiserror::Error;
 
-/// Represents errors that can occur in the RDBC module.
+/// 数据库访问（`udbc` 驱动、模板渲染与映射）过程中的错误
 #[derive(Error, Debug)]
 pub enum DbError {
     #[error("General error: {0}")]
@@ -19,19 +20,247 @@ pub enum DbError {
     UnsupportedDatabaseType(String),
     #[error("Invalid database URL: {0}")]
     InvalidDatabaseUrl(String),
+    #[error("Template error: {0}")]
+    Template(String),
     #[error("Database error: {0}")]
     Database(String),
+    /// 数据库驱动返回的错误，原始错误保留为 [`source()`](std::error::Error::source)
+    #[error(
+        "Database error: {message}{}",
+        .deadlock.as_ref().map(|d| format!(" ({})", d)).unwrap_or_default()
+    )]
+    Backend {
+        message: String,
+        /// 数据库错误码，如 MySQL 的 `1062`（唯一键冲突）
+        code: Option<u16>,
+        /// SQLSTATE，如 `23000`
+        sql_state: Option<String>,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+        /// 开启锁诊断时附加的最近一次死锁摘要
+        deadlock: Option<Box<DeadlockSummary>>,
+    },
+    /// 查询超过 `QueryOptions::timeout`
+    #[error("Timeout: {0}")]
+    Timeout(String),
+    /// 连接池熔断器已打开，请求被直接拒绝
+    #[error("Circuit open for pool '{0}'")]
+    CircuitOpen(String),
+    /// 事务超过最长持续时间已被强制回滚
+    #[error("Transaction aborted: {0}")]
+    TransactionAborted(String),
+    /// 语句估算的负载超过连接池的 `max_packet_size`，未发送给服务端
+    #[error(
+        "Packet too large: statement payload of about {size} bytes exceeds the limit of {limit} bytes"
+    )]
+    PacketTooLarge { size: usize, limit: usize },
+    /// 只读会话拒绝了写语句，语句未发送给服务端
+    #[error("Read-only session rejected statement: {0}")]
+    ReadOnly(String),
+    /// 行数据映射到结果类型失败
+    #[error(
+        "Mapping error{}: column '{column}' expected {expected}, found {found}",
+        .sql_id.as_deref().map(|id| format!(" in '{}'", id)).unwrap_or_default()
+    )]
+    Mapping {
+        /// 语句 ID；直接执行 SQL 文本时为 `None`
+        sql_id: Option<String>,
+        column: String,
+        expected: String,
+        found: String,
+    },
+}
+
+impl DbError {
+    /// 数据库返回的错误码；非数据库服务端错误时为 `None`
+    pub fn code(&self) -> Option<u16> {
+        match self {
+            DbError::Backend { code, .. } => *code,
+            _ => None,
+        }
+    }
+
+    /// 数据库返回的 SQLSTATE；非数据库服务端错误时为 `None`
+    pub fn sql_state(&self) -> Option<&str> {
+        match self {
+            DbError::Backend { sql_state, .. } => sql_state.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// 错误是否由死锁或锁等待超时引起
+    pub fn lock_error(&self) -> Option<LockError> {
+        match (self.code(), self.sql_state()) {
+            (Some(1213), _) | (_, Some("40001")) => Some(LockError::Deadlock),
+            (Some(1205), _) => Some(LockError::LockWaitTimeout),
+            _ => None,
+        }
+    }
+
+    /// 错误是否由唯一键冲突引起（MySQL `1062`、SQLSTATE `23505`）
+    pub fn is_unique_violation(&self) -> bool {
+        self.code() == Some(1062) || self.sql_state() == Some("23505")
+    }
+
+    /// 错误是否由连接中断引起（MySQL `2006`、`2013`、`4031`，SQLSTATE `08` 类），
+    /// 此时语句可能未执行或结果未返回，连接已不可用
+    pub fn is_connection_lost(&self) -> bool {
+        matches!(self, DbError::Connection(_))
+            || matches!(self.code(), Some(2006 | 2013 | 4031))
+            || self.sql_state().is_some_and(|s| s.starts_with("08"))
+    }
+
+    /// 锁诊断附加的最近一次死锁摘要
+    pub fn deadlock(&self) -> Option<&DeadlockSummary> {
+        match self {
+            DbError::Backend { deadlock, .. } => deadlock.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// 为数据库错误附加死锁摘要，其他错误原样返回
+    #[cfg_attr(not(feature = "mysql"), allow(dead_code))]
+    pub(crate) fn with_deadlock(mut self, summary: DeadlockSummary) -> Self {
+        if let DbError::Backend { deadlock, .. } = &mut self {
+            *deadlock = Some(Box::new(summary));
+        }
+        self
+    }
+
+    /// 为映射错误补充语句 ID，其他错误原样返回
+    #[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+    pub(crate) fn with_sql_id(self, id: &str) -> Self {
+        match self {
+            DbError::Mapping {
+                sql_id: None,
+                column,
+                expected,
+                found,
+            } => DbError::Mapping {
+                sql_id: Some(id.to_string()),
+                column,
+                expected,
+                found,
+            },
+            other => other,
+        }
+    }
 }
 
 impl serde::de::Error for DbError {
     fn custom<T: std::fmt::Display>(msg: T) -> Self {
         DbError::General(msg.to_string())
     }
+
+    fn invalid_type(unexp: serde::de::Unexpected, exp: &dyn serde::de::Expected) -> Self {
+        DbError::Mapping {
+            sql_id: None,
+            column: String::new(),
+            expected: exp.to_string(),
+            found: unexp.to_string(),
+        }
+    }
+
+    fn invalid_value(unexp: serde::de::Unexpected, exp: &dyn serde::de::Expected) -> Self {
+        Self::invalid_type(unexp, exp)
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        DbError::Mapping {
+            sql_id: None,
+            column: field.to_string(),
+            expected: "a column in the result set".to_string(),
+            found: "no such column".to_string(),
+        }
+    }
 }
 
 #[cfg(feature = "mysql")]
 impl From<mysql_async::Error> for DbError {
     fn from(e: mysql_async::Error) -> Self {
-        DbError::Database(e.to_string())
+        let (code, sql_state) = match &e {
+            mysql_async::Error::Server(server) => (Some(server.code), Some(server.state.clone())),
+            // 连接在读写过程中断开，按 MySQL 客户端的约定标为通信链路故障
+            mysql_async::Error::Io(_)
+            | mysql_async::Error::Driver(mysql_async::DriverError::ConnectionClosed) => {
+                (None, Some("08S01".to_string()))
+            }
+            _ => (None, None),
+        };
+        DbError::Backend {
+            message: e.to_string(),
+            code,
+            sql_state,
+            source: Box::new(e),
+            deadlock: None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mysql"))]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_mysql_error_keeps_source() {
+        let server = mysql_async::ServerError {
+            code: 1062,
+            message: "Duplicate entry '1' for key 'PRIMARY'".into(),
+            state: "23000".into(),
+        };
+        let err = DbError::from(mysql_async::Error::Server(server));
+        assert_eq!(err.code(), Some(1062));
+        assert_eq!(err.sql_state(), Some("23000"));
+        assert!(err.to_string().starts_with("Database error: "), "{}", err);
+        let source = err.source().unwrap();
+        assert!(matches!(
+            source.downcast_ref::<mysql_async::Error>(),
+            Some(mysql_async::Error::Server(e)) if e.code == 1062
+        ));
+
+        assert_eq!(err.lock_error(), None);
+
+        let server = mysql_async::ServerError {
+            code: 1213,
+            message: "Deadlock found when trying to get lock".into(),
+            state: "40001".into(),
+        };
+        let err = DbError::from(mysql_async::Error::Server(server));
+        assert_eq!(err.lock_error(), Some(LockError::Deadlock));
+        let summary = DeadlockSummary {
+            rolled_back: Some(2),
+            ..Default::default()
+        };
+        let err = err.with_deadlock(summary);
+        assert_eq!(err.deadlock().unwrap().rolled_back, Some(2));
+        assert!(
+            err.to_string()
+                .ends_with("(latest deadlock; rolled back (2))"),
+            "{}",
+            err
+        );
+
+        let err = DbError::Query("x".into());
+        assert_eq!(err.code(), None);
+        assert_eq!(err.sql_state(), None);
+        assert!(!err.is_connection_lost());
+    }
+
+    #[test]
+    fn test_connection_lost() {
+        let err = DbError::from(mysql_async::Error::Driver(
+            mysql_async::DriverError::ConnectionClosed,
+        ));
+        assert_eq!(err.sql_state(), Some("08S01"));
+        assert!(err.is_connection_lost());
+
+        let server = mysql_async::ServerError {
+            code: 2013,
+            message: "Lost connection to MySQL server during query".into(),
+            state: "HY000".into(),
+        };
+        assert!(DbError::from(mysql_async::Error::Server(server)).is_connection_lost());
+        assert!(DbError::Connection("reset by peer".into()).is_connection_lost());
     }
 }
@@ -0,0 +1,347 @@
+use crate::error::DbError;
+use crate::mapper_loader::{SqlMapper, StatementKind};
+use crate::tpl::render_context::Context;
+use crate::udbc::MaybeSendSync;
+use crate::udbc::serializer::to_value;
+use crate::udbc::value::Value;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// 变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOp {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// 实体变更事件
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityEvent {
+    /// 语句上 `entity` 属性声明的实体名
+    pub entity: String,
+    pub op: ChangeOp,
+    /// 触发事件的语句 ID
+    pub sql_id: String,
+    /// `entityKeys` 声明的主键及其取值；自增插入时包含生成的主键
+    pub keys: Vec<(String, Value)>,
+    /// 受影响行数
+    pub affected: u64,
+}
+
+struct Subscriber {
+    /// 只接收指定实体的事件；`None` 表示接收全部
+    entity: Option<String>,
+    tx: mpsc::UnboundedSender<EntityEvent>,
+}
+
+/// 进程内的实体变更事件总线
+///
+/// 带有 `entity` 属性的 insert/update/delete 语句执行成功后发布事件，
+/// 订阅者通过各自的 channel 接收，可用于缓存失效、搜索索引同步等。
+/// 事件在语句成功后立即发布，不等待外层事务提交。
+pub struct EntityEventBus {
+    subscribers: RwLock<Vec<Subscriber>>,
+}
+
+impl Default for EntityEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EntityEventBus {
+    pub fn new() -> Self {
+        Self {
+            subscribers: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 订阅所有实体的变更事件
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<EntityEvent> {
+        self.add(None)
+    }
+
+    /// 订阅指定实体的变更事件
+    pub fn subscribe_entity(&self, entity: &str) -> mpsc::UnboundedReceiver<EntityEvent> {
+        self.add(Some(entity.to_string()))
+    }
+
+    fn add(&self, entity: Option<String>) -> mpsc::UnboundedReceiver<EntityEvent> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.subscribers
+            .write()
+            .unwrap()
+            .push(Subscriber { entity, tx });
+        rx
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        !self.subscribers.read().unwrap().is_empty()
+    }
+
+    /// 发布事件；接收端已关闭的订阅者会被移除
+    pub fn publish(&self, event: EntityEvent) {
+        let mut closed = false;
+        {
+            let subscribers = self.subscribers.read().unwrap();
+            for sub in subscribers.iter() {
+                if sub.entity.as_ref().is_some_and(|e| *e != event.entity) {
+                    continue;
+                }
+                closed |= sub.tx.send(event.clone()).is_err();
+            }
+        }
+        if closed {
+            self.subscribers
+                .write()
+                .unwrap()
+                .retain(|sub| !sub.tx.is_closed());
+        }
+    }
+}
+
+/// 全局事件总线
+pub static EVENT_BUS: LazyLock<EntityEventBus> = LazyLock::new(EntityEventBus::new);
+
+/// 语句执行成功后按需发布事件（未声明 entity 或无订阅者时不做任何事）
+pub(crate) fn emit<T: Serialize>(
+    sql_id: &str,
+    mapper: &SqlMapper,
+    args: &T,
+    generated_key: Option<i64>,
+    affected: u64,
+) {
+    let Some(entity) = &mapper.entity else {
+        return;
+    };
+    let op = match mapper.kind {
+        StatementKind::Insert => ChangeOp::Insert,
+        StatementKind::Update => ChangeOp::Update,
+        StatementKind::Delete => ChangeOp::Delete,
+        StatementKind::Sql | StatementKind::Select | StatementKind::Cte => return,
+    };
+    if !EVENT_BUS.has_subscribers() {
+        return;
+    }
+
+    let root = to_value(args);
+    let ctx = Context::new(&root);
+    let mut keys: Vec<(String, Value)> = mapper
+        .entity_keys
+        .iter()
+        .map(|k| {
+            // 参数本身是标量时（如按 ID 删除）直接作为唯一主键的值
+            let v = match &root {
+                Value::Map(_) => ctx.lookup(k).clone(),
+                scalar if mapper.entity_keys.len() == 1 => scalar.clone(),
+                _ => Value::Null,
+            };
+            (k.clone(), v)
+        })
+        .collect();
+    if let Some(id) = generated_key {
+        let column = mapper
+            .key_column
+            .clone()
+            .unwrap_or_else(|| "id".to_string());
+        keys.retain(|(k, _)| *k != column);
+        keys.push((column, Value::I64(id)));
+    }
+
+    EVENT_BUS.publish(EntityEvent {
+        entity: entity.clone(),
+        op,
+        sql_id: sql_id.to_string(),
+        keys,
+        affected,
+    });
+}
+
+/// 连接池切换主机的事件，见 [`crate::udbc::failover`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailoverEvent {
+    /// 连接池名
+    pub pool: String,
+    /// 切换前的主机
+    pub from: String,
+    /// 切换后的主机
+    pub to: String,
+    /// 原主机不可用的原因；按 `primary-first` 切回首选主机时为 `None`
+    pub reason: Option<String>,
+}
+
+static FAILOVER_SUBSCRIBERS: LazyLock<RwLock<Vec<mpsc::UnboundedSender<FailoverEvent>>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+/// 订阅所有连接池的主机切换事件
+pub fn subscribe_failover() -> mpsc::UnboundedReceiver<FailoverEvent> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    FAILOVER_SUBSCRIBERS.write().unwrap().push(tx);
+    rx
+}
+
+/// 发布主机切换事件；接收端已关闭的订阅者会被移除
+pub(crate) fn publish_failover(event: FailoverEvent) {
+    FAILOVER_SUBSCRIBERS
+        .write()
+        .unwrap()
+        .retain(|tx| tx.send(event.clone()).is_ok());
+    for listener in listeners(&event.pool) {
+        listener.failover(&event);
+    }
+}
+
+/// 连接池生命周期事件的监听器，按连接池名注册（[`add_listener`]），用于告警与自定义指标
+///
+/// 回调在触发事件的任务中同步执行，应当尽快返回；耗时的处理请转发到 channel。
+/// 经 [`DriverManager`](crate::driver_manager::DriverManager) 注册的连接池自动触发事件，
+/// 直接构造的驱动可用 [`ListenedDriver`](crate::udbc::listener::ListenedDriver) 包装。
+///
+/// ```ignore
+/// struct Alerts;
+///
+/// impl EventListener for Alerts {
+///     fn acquire_timeout(&self, pool: &str, waited: Duration) {
+///         alert!("pool {} exhausted after {:?}", pool, waited);
+///     }
+/// }
+///
+/// uorm::events::add_listener("default", Arc::new(Alerts));
+/// ```
+pub trait EventListener: MaybeSendSync {
+    /// 从连接池取出连接
+    fn connection_opened(&self, _pool: &str) {}
+
+    /// 取出的连接被释放（归还连接池或关闭）
+    fn connection_closed(&self, _pool: &str) {}
+
+    /// 在超时内取不到连接，`waited` 为实际等待时长
+    fn acquire_timeout(&self, _pool: &str, _waited: Duration) {}
+
+    /// 语句执行失败
+    fn query_error(&self, _pool: &str, _sql: &str, _error: &DbError) {}
+
+    /// 连接池切换了主机，见 [`crate::udbc::failover`]
+    fn failover(&self, _event: &FailoverEvent) {}
+}
+
+/// 连接池名 -> 监听器
+type ListenerMap = HashMap<String, Vec<Arc<dyn EventListener>>>;
+
+static LISTENERS: LazyLock<RwLock<ListenerMap>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// 为名为 `pool` 的连接池注册监听器；可在连接池注册之前调用
+///
+/// 注册之前已取出的连接不触发该监听器的连接事件。
+pub fn add_listener(pool: &str, listener: Arc<dyn EventListener>) {
+    LISTENERS
+        .write()
+        .unwrap()
+        .entry(pool.to_string())
+        .or_default()
+        .push(listener);
+}
+
+/// 移除名为 `pool` 的连接池的全部监听器
+pub fn remove_listeners(pool: &str) {
+    LISTENERS.write().unwrap().remove(pool);
+}
+
+/// 连接池当前的监听器；在锁外回调，监听器中可以再注册或移除
+pub(crate) fn listeners(pool: &str) -> Vec<Arc<dyn EventListener>> {
+    LISTENERS
+        .read()
+        .unwrap()
+        .get(pool)
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn mapper(kind: StatementKind, keys: &[&str]) -> SqlMapper {
+        SqlMapper {
+            kind,
+            entity: Some("user".to_string()),
+            entity_keys: keys.iter().map(|k| k.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bus_filters_and_drops_closed() {
+        let bus = EntityEventBus::new();
+        let mut all = bus.subscribe();
+        let mut orders = bus.subscribe_entity("order");
+        let dropped = bus.subscribe();
+        drop(dropped);
+
+        let event = EntityEvent {
+            entity: "user".into(),
+            op: ChangeOp::Update,
+            sql_id: "user.update".into(),
+            keys: vec![],
+            affected: 1,
+        };
+        bus.publish(event.clone());
+        assert_eq!(all.try_recv().unwrap(), event);
+        assert!(orders.try_recv().is_err());
+        assert_eq!(bus.subscribers.read().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_emit_extracts_keys() {
+        let mut rx = EVENT_BUS.subscribe_entity("user");
+
+        let args = HashMap::from([("id", 7), ("age", 3)]);
+        emit(
+            "user.update",
+            &mapper(StatementKind::Update, &["id"]),
+            &args,
+            None,
+            1,
+        );
+        let e = rx.try_recv().unwrap();
+        assert_eq!(e.op, ChangeOp::Update);
+        assert_eq!(e.keys, vec![("id".to_string(), Value::I32(7))]);
+
+        emit(
+            "user.delete",
+            &mapper(StatementKind::Delete, &["id"]),
+            &9i64,
+            None,
+            1,
+        );
+        assert_eq!(
+            rx.try_recv().unwrap().keys,
+            vec![("id".to_string(), Value::I64(9))]
+        );
+
+        emit(
+            "user.insert",
+            &mapper(StatementKind::Insert, &[]),
+            &args,
+            Some(42),
+            1,
+        );
+        let e = rx.try_recv().unwrap();
+        assert_eq!(e.op, ChangeOp::Insert);
+        assert_eq!(e.keys, vec![("id".to_string(), Value::I64(42))]);
+
+        emit(
+            "user.get",
+            &mapper(StatementKind::Select, &["id"]),
+            &args,
+            None,
+            0,
+        );
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,383 @@
+//! 语句指纹与聚合统计
+//!
+//! 指纹由归一化后的 SQL 计算：字面量替换为 `?`、IN 列表折叠、空白与大小写统一，
+//! 因而同一语句在不同参数下得到相同指纹，可用于日志关联与按语句聚合耗时。
+
+use dashmap::DashMap;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// 统计表默认最多保留的指纹数
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+/// 单个指纹的聚合统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementStats {
+    /// 16 位十六进制指纹
+    pub fingerprint: String,
+    /// 归一化后的 SQL 样本
+    pub sample: String,
+    pub count: u64,
+    pub errors: u64,
+    pub total: Duration,
+    pub max: Duration,
+    /// 最近一次执行该语句的 SQL ID；直接执行 SQL 文本时为 `None`
+    pub sql_id: Option<String>,
+}
+
+impl StatementStats {
+    /// 平均耗时
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+        }
+    }
+}
+
+/// 有界的指纹统计表
+///
+/// 超出容量时淘汰执行次数最少的一批指纹，防止大量一次性 SQL（如拼接了字面量的语句）
+/// 导致内存无限增长。
+struct Registry {
+    stats: DashMap<String, StatementStats>,
+    capacity: AtomicUsize,
+    evictions: AtomicU64,
+}
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(|| Registry::new(DEFAULT_CAPACITY));
+
+impl Registry {
+    fn new(capacity: usize) -> Self {
+        Self {
+            stats: DashMap::new(),
+            capacity: AtomicUsize::new(capacity.max(1)),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, sql_id: Option<&str>, sample: String, fp: &str, elapsed: Duration, ok: bool) {
+        {
+            let mut entry = self
+                .stats
+                .entry(fp.to_string())
+                .or_insert_with(|| StatementStats {
+                    fingerprint: fp.to_string(),
+                    sample,
+                    count: 0,
+                    errors: 0,
+                    total: Duration::ZERO,
+                    max: Duration::ZERO,
+                    sql_id: None,
+                });
+            if let Some(id) = sql_id {
+                entry.sql_id = Some(logical_id(id).to_string());
+            }
+            entry.count += 1;
+            if !ok {
+                entry.errors += 1;
+            }
+            entry.total += elapsed;
+            entry.max = entry.max.max(elapsed);
+        }
+        self.evict_if_needed(Some(fp));
+    }
+
+    /// 超出容量时淘汰执行次数最少的指纹，一次淘汰约 1/8 以摊薄扫描成本；`keep` 不参与淘汰
+    fn evict_if_needed(&self, keep: Option<&str>) {
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        let len = self.stats.len();
+        if len <= capacity {
+            return;
+        }
+        let target = capacity - capacity / 8;
+        let mut counts: Vec<(u64, String)> = self
+            .stats
+            .iter()
+            .filter(|e| Some(e.key().as_str()) != keep)
+            .map(|e| (e.count, e.key().clone()))
+            .collect();
+        counts.sort_unstable_by_key(|(count, _)| *count);
+        for (_, key) in counts.into_iter().take(len.saturating_sub(target)) {
+            if self.stats.remove(&key).is_some() {
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity.max(1), Ordering::Relaxed);
+        self.evict_if_needed(None);
+    }
+
+    fn snapshot(&self) -> Vec<StatementStats> {
+        let mut stats: Vec<StatementStats> = self.stats.iter().map(|e| e.value().clone()).collect();
+        stats.sort_by_key(|s| std::cmp::Reverse(s.total));
+        stats
+    }
+
+    fn reset(&self) {
+        self.stats.clear();
+        self.evictions.store(0, Ordering::Relaxed);
+    }
+}
+
+/// 归一化 SQL：去除字面量与块注释、折叠 IN 列表与空白，关键字统一为小写
+pub fn normalize(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut pending_space = false;
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        if c == '/' && chars.peek() == Some(&'*') {
+            // 块注释（如链路注释）不参与指纹
+            chars.next();
+            let mut prev = ' ';
+            for n in chars.by_ref() {
+                if prev == '*' && n == '/' {
+                    break;
+                }
+                prev = n;
+            }
+            pending_space = true;
+            continue;
+        }
+        if pending_space && !out.is_empty() {
+            out.push(' ');
+        }
+        pending_space = false;
+
+        match c {
+            '\'' | '"' => {
+                // 跳过字符串字面量
+                while let Some(n) = chars.next() {
+                    if n == '\\' {
+                        chars.next();
+                    } else if n == c {
+                        if chars.peek() == Some(&c) {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                out.push('?');
+            }
+            '0'..='9' if !out.ends_with(|p: char| p.is_alphanumeric() || p == '_') => {
+                while chars
+                    .peek()
+                    .is_some_and(|n| n.is_ascii_alphanumeric() || *n == '.')
+                {
+                    chars.next();
+                }
+                out.push('?');
+            }
+            '$' if chars.peek().is_some_and(|n| n.is_ascii_digit()) => {
+                while chars.peek().is_some_and(|n| n.is_ascii_digit()) {
+                    chars.next();
+                }
+                out.push('?');
+            }
+            _ => out.extend(c.to_lowercase()),
+        }
+    }
+    collapse_lists(&out)
+}
+
+/// 将 `(?, ?, ?)` 折叠为 `(?+)`，使不同长度的 IN 列表得到相同指纹
+fn collapse_lists(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut rest = sql;
+    while let Some(start) = rest.find("(?") {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start + 1..];
+        let end = tail
+            .find(|c: char| !matches!(c, '?' | ',' | ' '))
+            .unwrap_or(tail.len());
+        if tail[end..].starts_with(')') && tail[..end].contains(',') {
+            out.push_str("(?+)");
+            rest = &tail[end + 1..];
+        } else {
+            out.push('(');
+            rest = tail;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// FNV-1a 64 位哈希，保证指纹跨进程、跨版本稳定
+pub(crate) fn fnv1a(s: &str) -> u64 {
+    fnv1a_bytes(s.as_bytes())
+}
+
+pub(crate) fn fnv1a_bytes(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// 计算 SQL 指纹
+pub fn fingerprint(sql: &str) -> String {
+    format!("{:016x}", fnv1a(&normalize(sql)))
+}
+
+/// 语句缓存键中的逻辑 SQL ID（去掉 `@databaseType` 后缀）
+pub(crate) fn logical_id(stmt_id: &str) -> &str {
+    stmt_id.split('@').next().unwrap_or(stmt_id)
+}
+
+/// 记录一次语句执行，返回其指纹
+pub fn record(sql: &str, elapsed: Duration, ok: bool) -> String {
+    record_named(None, sql, elapsed, ok)
+}
+
+/// 记录一次语句执行并关联其 SQL ID，返回其指纹
+pub fn record_named(sql_id: Option<&str>, sql: &str, elapsed: Duration, ok: bool) -> String {
+    let sample = normalize(sql);
+    let fp = format!("{:016x}", fnv1a(&sample));
+    REGISTRY.record(sql_id, sample, &fp, elapsed, ok);
+    fp
+}
+
+/// 获取所有指纹的统计快照，按累计耗时降序排列
+pub fn snapshot() -> Vec<StatementStats> {
+    REGISTRY.snapshot()
+}
+
+/// 设置统计表最多保留的指纹数（默认 [`DEFAULT_CAPACITY`]），超出时淘汰执行次数最少的指纹
+pub fn set_capacity(max_entries: usize) {
+    REGISTRY.set_capacity(max_entries);
+}
+
+/// 因超出容量被淘汰的指纹数
+pub fn evictions() -> u64 {
+    REGISTRY.evictions.load(Ordering::Relaxed)
+}
+
+/// 清空统计数据与淘汰计数
+pub fn reset() {
+    REGISTRY.reset();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_literals_and_lists() {
+        assert_eq!(
+            normalize(
+                "SELECT * FROM  users\n WHERE id IN (1, 2, 3) AND name = 'o''neil' AND t2.x > 10.5"
+            ),
+            "select * from users where id in (?+) and name = ? and t2.x > ?"
+        );
+        assert_eq!(
+            normalize("select * from t where id in (?, ?) and a = ?"),
+            "select * from t where id in (?+) and a = ?"
+        );
+        assert_eq!(
+            normalize("select count(?) from t"),
+            "select count(?) from t"
+        );
+        assert_eq!(
+            normalize("update t set a = $1 where b = $2"),
+            "update t set a = ? where b = ?"
+        );
+        assert_eq!(
+            normalize("/* app=svc trace=abc */ SELECT a /* x */ FROM t"),
+            "select a from t"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_stable_across_params() {
+        let a = fingerprint("select * from t where id in (?, ?, ?)");
+        let b = fingerprint("SELECT * FROM t WHERE id IN (?)");
+        let c = fingerprint("select * from t where id = ?");
+        assert_ne!(a, c);
+        assert_eq!(a.len(), 16);
+        // 单元素列表不折叠，与多元素列表区分
+        assert_ne!(a, b);
+        assert_eq!(a, fingerprint("select * from t where id in (1,2)"));
+    }
+
+    #[test]
+    fn test_record_aggregates() {
+        let sql = "select digest_test from t where id = 1";
+        let fp = record(sql, Duration::from_millis(10), true);
+        record(
+            "select digest_test from t where id = 2",
+            Duration::from_millis(30),
+            false,
+        );
+        let stats = snapshot()
+            .into_iter()
+            .find(|s| s.fingerprint == fp)
+            .unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.max, Duration::from_millis(30));
+        assert_eq!(stats.mean(), Duration::from_millis(20));
+        assert_eq!(stats.sql_id, None);
+
+        // 执行次数超过 u32 范围时不截断
+        let many = StatementStats {
+            count: 1 << 33,
+            total: Duration::from_secs(1 << 33),
+            ..stats.clone()
+        };
+        assert_eq!(many.mean(), Duration::from_secs(1));
+
+        let fp = record_named(
+            Some("user.digestById@mysql"),
+            "select digest_named from t where id = 1",
+            Duration::from_millis(1),
+            true,
+        );
+        let stats = snapshot()
+            .into_iter()
+            .find(|s| s.fingerprint == fp)
+            .unwrap();
+        assert_eq!(stats.sql_id.as_deref(), Some("user.digestById"));
+    }
+
+    #[test]
+    fn test_registry_evicts_least_executed() {
+        let registry = Registry::new(8);
+        let record = |sql: &str| {
+            let sample = normalize(sql);
+            let fp = format!("{:016x}", fnv1a(&sample));
+            registry.record(None, sample, &fp, Duration::from_millis(1), true);
+            fp
+        };
+        let hot = record("select hot from t");
+        record("select hot from t");
+        for i in 0..7 {
+            record(&format!("select c{} from t", i));
+        }
+        // 超出容量时淘汰到约 7/8，执行次数多的与刚记录的指纹保留
+        let last = record("select c7 from t");
+        assert_eq!(registry.stats.len(), 7);
+        assert_eq!(registry.evictions.load(Ordering::Relaxed), 2);
+        assert!(registry.stats.contains_key(&hot));
+        assert!(registry.stats.contains_key(&last));
+
+        registry.set_capacity(2);
+        assert!(registry.stats.len() <= 2);
+        assert!(registry.stats.contains_key(&hot));
+
+        registry.reset();
+        assert!(registry.stats.is_empty());
+        assert_eq!(registry.evictions.load(Ordering::Relaxed), 0);
+    }
+}
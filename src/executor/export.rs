@@ -0,0 +1,242 @@
+use crate::error::DbError;
+use crate::udbc::connection::RowSink;
+use crate::udbc::value::Value;
+use async_trait::async_trait;
+use std::fmt::Write as _;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// RFC 4180 CSV，首行为列名，NULL 输出为空字段
+    Csv,
+    /// 每行一个 JSON 对象，键顺序与列顺序一致
+    JsonLines,
+}
+
+/// 缓冲区超过该大小时写出
+const FLUSH_THRESHOLD: usize = 16 * 1024;
+
+/// 将查询结果按指定格式写入 `AsyncWrite`
+pub(crate) struct WriterSink<W> {
+    writer: W,
+    format: Format,
+    columns: Vec<String>,
+    buf: String,
+}
+
+impl<W: AsyncWrite + Unpin + Send> WriterSink<W> {
+    pub(crate) fn new(writer: W, format: Format) -> Self {
+        Self {
+            writer,
+            format,
+            columns: Vec::new(),
+            buf: String::with_capacity(FLUSH_THRESHOLD),
+        }
+    }
+
+    /// 写出剩余缓冲并刷新底层 writer
+    pub(crate) async fn finish(mut self) -> Result<(), DbError> {
+        self.write_buf().await?;
+        self.writer.flush().await.map_err(io_err)
+    }
+
+    async fn write_buf(&mut self) -> Result<(), DbError> {
+        if !self.buf.is_empty() {
+            self.writer
+                .write_all(self.buf.as_bytes())
+                .await
+                .map_err(io_err)?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+}
+
+fn io_err(e: std::io::Error) -> DbError {
+    DbError::General(format!("export write failed: {}", e))
+}
+
+#[async_trait]
+impl<W: AsyncWrite + Unpin + Send> RowSink for WriterSink<W> {
+    async fn columns(&mut self, columns: &[String]) -> Result<(), DbError> {
+        self.columns = columns.to_vec();
+        if self.format == Format::Csv {
+            for (i, c) in columns.iter().enumerate() {
+                if i > 0 {
+                    self.buf.push(',');
+                }
+                csv_field(&mut self.buf, c);
+            }
+            self.buf.push_str("\r\n");
+        }
+        Ok(())
+    }
+
+    async fn row(&mut self, values: Vec<Value>) -> Result<(), DbError> {
+        match self.format {
+            Format::Csv => {
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        self.buf.push(',');
+                    }
+                    if !matches!(v, Value::Null) {
+                        csv_field(&mut self.buf, &text(v));
+                    }
+                }
+                self.buf.push_str("\r\n");
+            }
+            Format::JsonLines => {
+                self.buf.push('{');
+                for (i, (c, v)) in self.columns.iter().zip(&values).enumerate() {
+                    if i > 0 {
+                        self.buf.push(',');
+                    }
+                    json_string(&mut self.buf, c);
+                    self.buf.push(':');
+                    json_value(&mut self.buf, v);
+                }
+                self.buf.push_str("}\n");
+            }
+        }
+        if self.buf.len() >= FLUSH_THRESHOLD {
+            self.write_buf().await?;
+        }
+        Ok(())
+    }
+}
+
+/// 标量值的文本形式
+fn text(v: &Value) -> String {
+    match v {
+        Value::Null => String::new(),
+        Value::Bool(b) => b.to_string(),
+        Value::I16(n) => n.to_string(),
+        Value::I32(n) => n.to_string(),
+        Value::I64(n) => n.to_string(),
+        Value::U8(n) => n.to_string(),
+        Value::F64(n) => n.to_string(),
+        Value::Str(s) => s.clone(),
+        Value::Bytes(b) => String::from_utf8_lossy(b).into_owned(),
+        #[cfg(feature = "chrono")]
+        Value::Date(d) => d.to_string(),
+        #[cfg(feature = "chrono")]
+        Value::Time(t) => t.to_string(),
+        #[cfg(feature = "chrono")]
+        Value::DateTime(dt) => dt.to_string(),
+        #[cfg(feature = "chrono")]
+        Value::DateTimeUtc(dt) => dt.to_rfc3339(),
+        #[cfg(feature = "decimal")]
+        Value::Decimal(d) => d.to_string(),
+        Value::List(_) | Value::Map(_) => {
+            let mut out = String::new();
+            json_value(&mut out, v);
+            out
+        }
+    }
+}
+
+fn csv_field(out: &mut String, s: &str) {
+    if s.contains([',', '"', '\n', '\r']) {
+        out.push('"');
+        out.push_str(&s.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(s);
+    }
+}
+
+fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn json_value(out: &mut String, v: &Value) {
+    match v {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::I16(_) | Value::I32(_) | Value::I64(_) | Value::U8(_) => out.push_str(&text(v)),
+        Value::F64(n) if n.is_finite() => out.push_str(&n.to_string()),
+        Value::F64(_) => out.push_str("null"),
+        Value::List(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                json_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Map(m) => {
+            let mut keys: Vec<&String> = m.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, k) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                json_string(out, k);
+                out.push(':');
+                json_value(out, &m[k]);
+            }
+            out.push('}');
+        }
+        // 日期与十进制数按字符串输出，避免精度与格式损失
+        other => json_string(out, &text(other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn export(format: Format) -> String {
+        let mut out = Vec::new();
+        let mut sink = WriterSink::new(&mut out, format);
+        sink.columns(&["id".into(), "name".into(), "note".into()])
+            .await
+            .unwrap();
+        sink.row(vec![Value::I64(1), Value::from("a,b"), Value::Null])
+            .await
+            .unwrap();
+        sink.row(vec![
+            Value::I64(2),
+            Value::from("say \"hi\"\n"),
+            Value::F64(1.5),
+        ])
+        .await
+        .unwrap();
+        sink.finish().await.unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_csv() {
+        assert_eq!(
+            export(Format::Csv).await,
+            "id,name,note\r\n1,\"a,b\",\r\n2,\"say \"\"hi\"\"\n\",1.5\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_lines() {
+        assert_eq!(
+            export(Format::JsonLines).await,
+            "{\"id\":1,\"name\":\"a,b\",\"note\":null}\n{\"id\":2,\"name\":\"say \\\"hi\\\"\\n\",\"note\":1.5}\n"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! 写语句的幂等执行
+//!
+//! 声明了 `idempotentKey="request_id"` 的 `<insert>`/`<update>`/`<delete>` 执行时，
+//! 先在 [`IDEMPOTENCY_TABLE`] 中登记 `(语句 ID, 键值)`，再执行语句并记下结果
+//! （生成的主键或影响行数），三者在同一事务中完成；已处于事务中时在保存点中完成。
+//! 同一键再次执行时登记因唯一键冲突失败，语句不再执行，直接返回首次记下的结果，
+//! 至少一次投递的消息消费者重试时不会重复写入。
+//!
+//! 语句失败时登记随事务回滚，之后可以用同一键重试。登记表在每个连接池首次使用时
+//! 以 `CREATE TABLE IF NOT EXISTS` 创建，已有记录需由应用按 `created_at` 自行清理。
+
+use crate::error::DbError;
+use crate::executor::session::{Nested, Session};
+use crate::mapper_loader::SqlMapper;
+use crate::tpl::render_context::Context;
+use crate::udbc::driver::Driver;
+use crate::udbc::serializer::to_value;
+use crate::udbc::value::Value;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// 幂等键登记表
+pub const IDEMPOTENCY_TABLE: &str = "uorm_idempotency";
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS uorm_idempotency (\
+     sql_id VARCHAR(191) NOT NULL, \
+     idem_key VARCHAR(191) NOT NULL, \
+     result BIGINT, \
+     created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, \
+     PRIMARY KEY (sql_id, idem_key))";
+const REGISTER: &str = "INSERT INTO uorm_idempotency (sql_id, idem_key) VALUES (#{sql_id}, #{key})";
+const RECORD: &str =
+    "UPDATE uorm_idempotency SET result = #{result} WHERE sql_id = #{sql_id} AND idem_key = #{key}";
+const LOOKUP: &str = "SELECT COALESCE(result, 0) AS result FROM uorm_idempotency WHERE sql_id = #{sql_id} AND idem_key = #{key}";
+
+/// 已创建登记表的连接池
+static PREPARED: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// 写语句的执行结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Applied {
+    /// 语句已执行
+    Executed {
+        affected: u64,
+        generated_key: Option<i64>,
+    },
+    /// 幂等键已登记，语句未执行；值为首次执行记下的结果
+    Replayed(i64),
+}
+
+impl Applied {
+    /// 返回给调用方的结果：有生成的主键时为主键，否则为影响行数
+    pub(crate) fn result(&self) -> i64 {
+        match *self {
+            Applied::Executed {
+                affected,
+                generated_key,
+            } => generated_key.unwrap_or(affected as i64),
+            Applied::Replayed(result) => result,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct KeyArgs<'a> {
+    sql_id: &'a str,
+    key: &'a str,
+    result: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct Recorded {
+    result: i64,
+}
+
+/// 登记冲突与其他错误都需要回滚登记，冲突在事务结束后转为 [`Applied::Replayed`]
+enum Attempt {
+    Duplicate,
+    Failed(DbError),
+}
+
+impl From<DbError> for Attempt {
+    fn from(e: DbError) -> Self {
+        Attempt::Failed(e)
+    }
+}
+
+/// 执行写语句 `write`，返回影响行数与生成的主键
+///
+/// 语句声明了 `idempotentKey` 时按幂等方式执行，否则直接执行。
+pub(crate) async fn apply<T, F>(
+    pool: &Arc<dyn Driver>,
+    sql_id: &str,
+    mapper: &SqlMapper,
+    args: &T,
+    write: F,
+) -> Result<Applied, DbError>
+where
+    T: Serialize,
+    F: Future<Output = Result<(u64, Option<i64>), DbError>>,
+{
+    let Some(path) = &mapper.idempotent_key else {
+        let (affected, generated_key) = write.await?;
+        return Ok(Applied::Executed {
+            affected,
+            generated_key,
+        });
+    };
+    let key = key_value(sql_id, path, args)?;
+    prepare(pool).await?;
+
+    let session = Session::new(pool.clone());
+    let key_args = |result| KeyArgs {
+        sql_id,
+        key: &key,
+        result,
+    };
+    let attempt = session
+        .transactional(Nested::Savepoint, async {
+            if let Err(e) = session.execute(REGISTER, &key_args(None)).await {
+                return Err(if e.is_unique_violation() {
+                    Attempt::Duplicate
+                } else {
+                    Attempt::Failed(e)
+                });
+            }
+            let (affected, generated_key) = write.await?;
+            let applied = Applied::Executed {
+                affected,
+                generated_key,
+            };
+            session
+                .execute(RECORD, &key_args(Some(applied.result())))
+                .await?;
+            Ok(applied)
+        })
+        .await;
+    match attempt {
+        Ok(applied) => Ok(applied),
+        Err(Attempt::Failed(e)) => Err(e),
+        Err(Attempt::Duplicate) => {
+            let recorded: Vec<Recorded> = session.query(LOOKUP, &key_args(None)).await?;
+            let result = recorded.first().map_or(0, |r| r.result);
+            Ok(Applied::Replayed(result))
+        }
+    }
+}
+
+/// 取幂等键的值，只接受非空字符串与整数
+fn key_value<T: Serialize>(sql_id: &str, path: &str, args: &T) -> Result<String, DbError> {
+    let root = to_value(args);
+    match Context::new(&root).lookup(path) {
+        Value::Str(s) if !s.is_empty() => Ok(s.clone()),
+        Value::U8(n) => Ok(n.to_string()),
+        Value::I16(n) => Ok(n.to_string()),
+        Value::I32(n) => Ok(n.to_string()),
+        Value::I64(n) => Ok(n.to_string()),
+        other => Err(DbError::Query(format!(
+            "{}: idempotency key '{}' must be a non-empty string or integer, got {:?}",
+            sql_id, path, other
+        ))),
+    }
+}
+
+/// 在连接池上创建登记表，每个连接池只执行一次
+///
+/// 使用单独的连接执行，避免 DDL 隐式提交外层事务。建表失败（如缺少 `CREATE` 权限，
+/// 登记表已由 DBA 预先创建）时只记录警告，表确实不存在时由随后的登记报错。
+async fn prepare(pool: &Arc<dyn Driver>) -> Result<(), DbError> {
+    if PREPARED.lock().unwrap().contains(pool.name()) {
+        return Ok(());
+    }
+    if let Err(e) = pool.connection().await?.execute(CREATE_TABLE, &[]).await {
+        warn!(
+            "idempotency: failed to create {} on '{}': {}",
+            IDEMPOTENCY_TABLE,
+            pool.name(),
+            e
+        );
+    }
+    PREPARED.lock().unwrap().insert(pool.name().to_string());
+    Ok(())
+}
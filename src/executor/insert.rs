@@ -0,0 +1,187 @@
+//! 由结构体字段生成 `INSERT` 语句，供 [`Session::insert_into`](crate::executor::session::Session::insert_into) 使用
+
+use crate::error::DbError;
+use crate::udbc::driver::Driver;
+use crate::udbc::serializer::to_value;
+use crate::udbc::value::Value;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// `insert_into` 的列选择与重命名
+///
+/// 默认插入序列化结果中的全部字段，列名与字段名相同。
+#[derive(Debug, Clone, Default)]
+pub struct ColumnMap {
+    only: Option<Vec<String>>,
+    skip: Vec<String>,
+    rename: HashMap<String, String>,
+    skip_null: bool,
+}
+
+impl ColumnMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 只插入这些字段
+    pub fn only(mut self, fields: &[&str]) -> Self {
+        self.only = Some(fields.iter().map(|f| f.to_string()).collect());
+        self
+    }
+
+    /// 不插入该字段（如自增主键）
+    pub fn skip(mut self, field: &str) -> Self {
+        self.skip.push(field.to_string());
+        self
+    }
+
+    /// 字段写入名称不同的列
+    pub fn rename(mut self, field: &str, column: &str) -> Self {
+        self.rename.insert(field.to_string(), column.to_string());
+        self
+    }
+
+    /// 不插入值为 NULL 的字段，使其取列的默认值
+    pub fn skip_null(mut self) -> Self {
+        self.skip_null = true;
+        self
+    }
+
+    fn includes(&self, field: &str, value: &Value) -> bool {
+        self.only
+            .as_ref()
+            .is_none_or(|only| only.iter().any(|f| f == field))
+            && !self.skip.iter().any(|f| f == field)
+            && !(self.skip_null && matches!(value, Value::Null))
+    }
+}
+
+/// 生成 `INSERT INTO table (c1, c2) VALUES (?, ?)` 及其参数
+///
+/// 序列化后的字段顺序不固定，列按名称排序，保证同一结构体得到相同的 SQL。
+pub(crate) fn build<T: Serialize>(
+    driver: &dyn Driver,
+    table: &str,
+    entity: &T,
+    columns: &ColumnMap,
+) -> Result<(String, Vec<(String, Value)>), DbError> {
+    if !is_identifier(table, true) {
+        return Err(DbError::Query(format!("invalid table name '{}'", table)));
+    }
+    let Value::Map(fields) = to_value(entity) else {
+        return Err(DbError::Value(
+            "insert_into expects a struct or map".to_string(),
+        ));
+    };
+    let mut fields: Vec<(String, Value)> = fields
+        .into_iter()
+        .filter(|(field, value)| columns.includes(field, value))
+        .collect();
+    fields.sort_by(|a, b| a.0.cmp(&b.0));
+    if fields.is_empty() {
+        return Err(DbError::Query(format!(
+            "no columns to insert into {}",
+            table
+        )));
+    }
+
+    let mut names = Vec::with_capacity(fields.len());
+    let mut placeholders = Vec::with_capacity(fields.len());
+    for (i, (field, value)) in fields.iter().enumerate() {
+        if matches!(value, Value::Map(_) | Value::List(_)) {
+            return Err(DbError::Value(format!(
+                "field '{}' is not a scalar value",
+                field
+            )));
+        }
+        let column = columns.rename.get(field).unwrap_or(field);
+        if !is_identifier(column, false) {
+            return Err(DbError::Query(format!("invalid column name '{}'", column)));
+        }
+        names.push(column.as_str());
+        placeholders.push(driver.placeholder(i + 1, column));
+    }
+    let sql = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table,
+        names.join(", "),
+        placeholders.join(", ")
+    );
+    Ok((sql, fields))
+}
+
+/// 表名允许带库名（`db.table`）
+fn is_identifier(name: &str, qualified: bool) -> bool {
+    !name.is_empty()
+        && name.split('.').all(|part| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+        && (qualified || !name.contains('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::udbc::connection::Connection;
+    use async_trait::async_trait;
+    use std::sync::Arc;
+
+    struct Dollar;
+
+    #[async_trait]
+    impl Driver for Dollar {
+        fn name(&self) -> &str {
+            "dollar"
+        }
+
+        fn r#type(&self) -> &str {
+            "postgres"
+        }
+
+        fn placeholder(&self, seq: usize, _name: &str) -> String {
+            format!("${}", seq)
+        }
+
+        async fn connection(&self) -> Result<Arc<dyn Connection>, DbError> {
+            Err(DbError::NotImplemented)
+        }
+
+        async fn close(&self) -> Result<(), DbError> {
+            Ok(())
+        }
+    }
+
+    #[derive(Serialize)]
+    struct User {
+        id: Option<i64>,
+        name: String,
+        email: Option<String>,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn test_build_insert() {
+        let user = User {
+            id: None,
+            name: "a".into(),
+            email: None,
+            tags: vec![],
+        };
+        let columns = ColumnMap::new().skip("tags").rename("name", "user_name");
+        let (sql, params) = build(&Dollar, "app.users", &user, &columns).unwrap();
+        assert_eq!(
+            sql,
+            "INSERT INTO app.users (email, id, user_name) VALUES ($1, $2, $3)"
+        );
+        assert_eq!(params[2], ("name".to_string(), Value::Str("a".into())));
+
+        let columns = ColumnMap::new().only(&["id", "name", "email"]).skip_null();
+        let (sql, _) = build(&Dollar, "users", &user, &columns).unwrap();
+        assert_eq!(sql, "INSERT INTO users (name) VALUES ($1)");
+
+        // 列表字段未排除时报错，而不是生成无法执行的语句
+        assert!(build(&Dollar, "users", &user, &ColumnMap::new()).is_err());
+        assert!(build(&Dollar, "users; drop", &user, &columns).is_err());
+        assert!(build(&Dollar, "users", &1, &columns).is_err());
+    }
+}
@@ -0,0 +1,404 @@
+//! 语句执行的 tracing 埋点
+//!
+//! 每条语句在 `uorm.statement` span 中执行，创建时带 `op`、`db.name`、`sql_id` 字段，
+//! 结束后补全 `fingerprint`、`rows`、`elapsed_ms`，并在 span 内记录一条 DEBUG 事件
+//! （含 SQL、参数与错误）。span 继承调用方当前的 span，可与请求链路关联。
+//!
+//! 启用 `log-output` 特性时，另按原先的格式输出一行 `log` 日志。
+//!
+//! 调试时可通过 [`set_inline_params`] 打开参数内联：事件中额外带有 `inlined_sql` 字段，
+//! 即把参数值以字面量代入占位符后的 SQL，以 `/* debug only, not executed */` 开头，
+//! 仅用于阅读，不会被执行。
+//!
+//! 事件中的参数经过裁剪：超过 [`set_max_param_log_bytes`] 的字符串截断并标注原长度，
+//! 二进制参数只记录长度与 FNV-1a 摘要，避免 BLOB 较多的负载把整段内容写入日志。
+
+use crate::error::DbError;
+use crate::executor::digest::{fnv1a_bytes, logical_id};
+use crate::udbc::value::Value;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::Span;
+use tracing::field::Empty;
+
+static INLINE_PARAMS: AtomicBool = AtomicBool::new(false);
+
+/// 日志中字符串参数默认保留的字节数
+const DEFAULT_MAX_PARAM_BYTES: usize = 256;
+
+static MAX_PARAM_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_PARAM_BYTES);
+
+/// 设置日志中单个字符串参数保留的最大字节数，超出部分截断并标注原长度；`0` 表示不截断
+///
+/// 默认 256 字节。二进制参数不受影响，始终只记录长度与摘要。
+pub fn set_max_param_log_bytes(max: usize) {
+    MAX_PARAM_BYTES.store(max, Ordering::Relaxed);
+}
+
+/// 日志中单个字符串参数保留的最大字节数
+pub fn max_param_log_bytes() -> usize {
+    MAX_PARAM_BYTES.load(Ordering::Relaxed)
+}
+
+/// 打开或关闭日志中的参数内联，可在运行时随时切换
+///
+/// 内联后的 SQL 含有参数值本身，不应在生产环境长期开启。
+pub fn set_inline_params(enabled: bool) {
+    INLINE_PARAMS.store(enabled, Ordering::Relaxed);
+}
+
+/// 是否已打开参数内联
+pub fn inline_params_enabled() -> bool {
+    INLINE_PARAMS.load(Ordering::Relaxed)
+}
+
+/// 将参数值以转义后的字面量代入 `?` 或 `$1` 形式的占位符，结果仅供阅读
+///
+/// 字符串字面量、引用标识符与注释中的 `?`、`$` 不视为占位符；
+/// 占位符多于参数时保持原样。
+pub fn inline_params(sql: &str, params: &[(String, Value)]) -> String {
+    let mut out = String::from("/* debug only, not executed */ ");
+    let mut chars = sql.chars().peekable();
+    let mut next = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                out.push(c);
+                while let Some(n) = chars.next() {
+                    out.push(n);
+                    if n == '\\' && c != '`' {
+                        if let Some(escaped) = chars.next() {
+                            out.push(escaped);
+                        }
+                    } else if n == c {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                out.push(c);
+                let mut prev = ' ';
+                for n in chars.by_ref() {
+                    out.push(n);
+                    if prev == '*' && n == '/' {
+                        break;
+                    }
+                    prev = n;
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                out.push(c);
+                for n in chars.by_ref() {
+                    out.push(n);
+                    if n == '\n' {
+                        break;
+                    }
+                }
+            }
+            '?' => match params.get(next) {
+                Some((_, value)) => {
+                    push_literal(&mut out, value);
+                    next += 1;
+                }
+                None => out.push(c),
+            },
+            '$' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                let mut digits = String::new();
+                while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(d);
+                }
+                match digits
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| params.get(n.checked_sub(1)?))
+                {
+                    Some((_, value)) => push_literal(&mut out, value),
+                    None => {
+                        out.push(c);
+                        out.push_str(&digits);
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// 以 SQL 字面量写出参数值；字符串中的引号与反斜杠均转义
+fn push_literal(out: &mut String, value: &Value) {
+    let quoted = |out: &mut String, s: &str| {
+        out.push('\'');
+        for c in s.chars() {
+            match c {
+                '\'' => out.push_str("''"),
+                '\\' => out.push_str("\\\\"),
+                c => out.push(c),
+            }
+        }
+        out.push('\'');
+    };
+    match value {
+        Value::Null => out.push_str("NULL"),
+        Value::Bool(b) => out.push_str(if *b { "TRUE" } else { "FALSE" }),
+        Value::I16(n) => write!(out, "{}", n).unwrap(),
+        Value::I32(n) => write!(out, "{}", n).unwrap(),
+        Value::I64(n) => write!(out, "{}", n).unwrap(),
+        Value::U8(n) => write!(out, "{}", n).unwrap(),
+        Value::F64(n) => write!(out, "{}", n).unwrap(),
+        #[cfg(feature = "decimal")]
+        Value::Decimal(d) => write!(out, "{}", d).unwrap(),
+        Value::Str(s) => quoted(out, s),
+        Value::Bytes(b) => {
+            out.push_str("X'");
+            for byte in b {
+                write!(out, "{:02X}", byte).unwrap();
+            }
+            out.push('\'');
+        }
+        #[cfg(feature = "chrono")]
+        Value::Date(d) => quoted(out, &d.to_string()),
+        #[cfg(feature = "chrono")]
+        Value::Time(t) => quoted(out, &t.to_string()),
+        #[cfg(feature = "chrono")]
+        Value::DateTime(dt) => quoted(out, &dt.to_string()),
+        #[cfg(feature = "chrono")]
+        Value::DateTimeUtc(dt) => quoted(out, &dt.naive_utc().to_string()),
+        Value::List(_) | Value::Map(_) => quoted(out, &format!("{:?}", value)),
+    }
+}
+
+/// 写入日志的参数列表，格式与 `Vec<(String, Value)>` 的 `Debug` 输出一致，
+/// 但长字符串被截断、二进制只保留长度与摘要
+pub(crate) struct LoggedParams<'a> {
+    params: &'a [(String, Value)],
+    max_bytes: usize,
+}
+
+impl<'a> LoggedParams<'a> {
+    pub(crate) fn new(params: &'a [(String, Value)]) -> Self {
+        Self {
+            params,
+            max_bytes: max_param_log_bytes(),
+        }
+    }
+}
+
+impl std::fmt::Debug for LoggedParams<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(
+                self.params
+                    .iter()
+                    .map(|(name, value)| (name, LoggedValue(value, self.max_bytes))),
+            )
+            .finish()
+    }
+}
+
+struct LoggedValue<'a>(&'a Value, usize);
+
+impl std::fmt::Debug for LoggedValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let max = self.1;
+        match self.0 {
+            Value::Str(s) if max > 0 && s.len() > max => {
+                let end = (0..=max)
+                    .rev()
+                    .find(|&i| s.is_char_boundary(i))
+                    .unwrap_or(0);
+                write!(f, "Str({:?}... {} bytes)", &s[..end], s.len())
+            }
+            Value::Bytes(b) => write!(f, "Bytes({} bytes, fnv1a={:016x})", b.len(), fnv1a_bytes(b)),
+            Value::List(items) => f
+                .debug_tuple("List")
+                .field(&LoggedList(items, max))
+                .finish(),
+            Value::Map(map) => f.debug_tuple("Map").field(&LoggedMap(map, max)).finish(),
+            other => other.fmt(f),
+        }
+    }
+}
+
+struct LoggedList<'a>(&'a [Value], usize);
+
+impl std::fmt::Debug for LoggedList<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|v| LoggedValue(v, self.1)))
+            .finish()
+    }
+}
+
+struct LoggedMap<'a>(&'a std::collections::HashMap<String, Value>, usize);
+
+impl std::fmt::Debug for LoggedMap<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(k, v)| (k, LoggedValue(v, self.1))))
+            .finish()
+    }
+}
+
+/// 一条语句的 span
+pub(crate) struct StatementSpan<'a> {
+    op: &'static str,
+    db: &'a str,
+    sql_id: Option<&'a str>,
+    span: Span,
+}
+
+impl<'a> StatementSpan<'a> {
+    /// `op` 为操作类型，如 `query`、`execute`
+    pub(crate) fn new(op: &'static str, db: &'a str, sql_id: Option<&'a str>) -> Self {
+        let sql_id = sql_id.map(logical_id);
+        let span = tracing::debug_span!(
+            "uorm.statement",
+            op,
+            db.name = db,
+            sql_id = sql_id.unwrap_or("-"),
+            fingerprint = Empty,
+            rows = Empty,
+            elapsed_ms = Empty,
+        );
+        Self {
+            op,
+            db,
+            sql_id,
+            span,
+        }
+    }
+
+    /// 执行语句时进入的 span
+    pub(crate) fn span(&self) -> Span {
+        self.span.clone()
+    }
+
+    /// 补全 span 字段并记录事件；正在采样时交给 [`crate::capture`] 记录
+    pub(crate) fn finish(&self, outcome: Outcome<'_>) {
+        if crate::capture::is_capturing() {
+            crate::capture::observe(self.op, self.db, self.sql_id, &outcome);
+        }
+        let elapsed_ms = outcome.elapsed.as_millis() as u64;
+        if let Some(fingerprint) = outcome.fingerprint {
+            self.span.record("fingerprint", fingerprint);
+        }
+        if let Some(rows) = outcome.rows {
+            self.span.record("rows", rows);
+        }
+        self.span.record("elapsed_ms", elapsed_ms);
+        let inlined = inline_params_enabled().then(|| inline_params(outcome.sql, outcome.params));
+        let params = LoggedParams::new(outcome.params);
+        self.span.in_scope(|| match outcome.error {
+            Some(error) => tracing::debug!(
+                sql = outcome.sql,
+                params = ?params,
+                inlined_sql = inlined.as_deref(),
+                error = %error,
+                "statement failed"
+            ),
+            None => tracing::debug!(
+                sql = outcome.sql,
+                params = ?params,
+                inlined_sql = inlined.as_deref(),
+                "statement finished"
+            ),
+        });
+
+        #[cfg(feature = "log-output")]
+        log::debug!(
+            "{} statement: sql_id={}, fingerprint={}, sql={}, params={:?}, elapsed_ms={}, rows={:?}, error={:?}",
+            self.op,
+            self.sql_id.unwrap_or("-"),
+            outcome.fingerprint.unwrap_or("-"),
+            outcome.sql,
+            params,
+            elapsed_ms,
+            outcome.rows,
+            outcome.error.map(|e| e.to_string())
+        );
+        #[cfg(feature = "log-output")]
+        if let Some(inlined) = &inlined {
+            log::debug!("{} statement inlined: {}", self.op, inlined);
+        }
+    }
+}
+
+/// 语句执行结束时的结果
+pub(crate) struct Outcome<'a> {
+    pub fingerprint: Option<&'a str>,
+    pub sql: &'a str,
+    pub params: &'a [(String, Value)],
+    pub elapsed: Duration,
+    /// 查询返回或更新影响的行数
+    pub rows: Option<u64>,
+    pub error: Option<&'a DbError>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_params() {
+        let params = vec![
+            ("name".to_string(), Value::Str("O'Brien \\ co".to_string())),
+            ("age".to_string(), Value::I32(30)),
+            ("deleted".to_string(), Value::Null),
+        ];
+        let sql = "SELECT '?' AS q, `a?` FROM t /* ? */ WHERE name = ? AND age > ? AND deleted_at <=> ? AND x = ?";
+        assert_eq!(
+            inline_params(sql, &params),
+            "/* debug only, not executed */ SELECT '?' AS q, `a?` FROM t /* ? */ \
+             WHERE name = 'O''Brien \\\\ co' AND age > 30 AND deleted_at <=> NULL AND x = ?"
+        );
+
+        let params = vec![
+            ("a".to_string(), Value::Bytes(vec![0xde, 0xad])),
+            ("b".to_string(), Value::Bool(true)),
+        ];
+        assert_eq!(
+            inline_params("UPDATE t SET a = $1 WHERE b = $2 AND c = $3", &params),
+            "/* debug only, not executed */ UPDATE t SET a = X'DEAD' WHERE b = TRUE AND c = $3"
+        );
+    }
+
+    #[test]
+    fn test_logged_params() {
+        let params = vec![
+            ("id".to_string(), Value::I32(1)),
+            ("note".to_string(), Value::Str("héllo world".to_string())),
+            ("blob".to_string(), Value::Bytes(vec![0; 1024])),
+            (
+                "tags".to_string(),
+                Value::List(vec![Value::Str("short".to_string())]),
+            ),
+        ];
+        let logged = |max_bytes| {
+            format!(
+                "{:?}",
+                LoggedParams {
+                    params: &params,
+                    max_bytes
+                }
+            )
+        };
+        let blob = format!("Bytes(1024 bytes, fnv1a={:016x})", fnv1a_bytes(&[0; 1024]));
+        // 截断位置落在多字节字符中间时向前退到字符边界
+        assert_eq!(
+            logged(2),
+            format!(
+                r#"[("id", I32(1)), ("note", Str("h"... 12 bytes)), ("blob", {}), ("tags", List([Str("sh"... 5 bytes)]))]"#,
+                blob
+            )
+        );
+        assert_eq!(
+            logged(0),
+            format!(
+                r#"[("id", I32(1)), ("note", Str("héllo world")), ("blob", {}), ("tags", List([Str("short")]))]"#,
+                blob
+            )
+        );
+    }
+}
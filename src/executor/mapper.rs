@@ -1,27 +1,113 @@
+use crate::driver_manager::UORM;
 use crate::error::DbError;
-use crate::executor::session::Session;
-use crate::mapper_loader::find_mapper;
-use crate::udbc::deserializer::ValueDeserializer;
+use crate::events;
+use crate::executor::digest;
+use crate::executor::idempotency::{self, Applied};
+use crate::executor::options::{QueryOptions, query_options, with_timeout};
+use crate::executor::page::{self, Page};
+use crate::executor::session::{self, Session};
+use crate::executor::shard;
+use crate::mapper_loader::{self, SqlMapper, StatementKind, chained_key, find_mapper};
+use crate::query_cache;
+use crate::tpl::engine;
+use crate::tpl::render_context::Context;
+use crate::transaction::TransactionContext;
+use crate::udbc::connection::Connection;
+use crate::udbc::deserializer::{RowDeserializer, ValueDeserializer};
 use crate::udbc::driver::Driver;
+use crate::udbc::serializer::to_value;
 use crate::udbc::value::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// 语句链中已执行的一条语句，事务提交后据此发布事件、失效缓存
+struct Executed<'a> {
+    sql_id: String,
+    mapper: &'a SqlMapper,
+    args: Value,
+    generated_key: Option<i64>,
+    affected: u64,
+}
+
+type ChainFuture<'a> = Pin<Box<dyn Future<Output = Result<Value, DbError>> + Send + 'a>>;
 
 /// 映射器客户端，封装了连接池与模板调用
 pub struct Mapper {
     pool: Arc<dyn Driver>,
+    options: QueryOptions,
 }
 
 impl Mapper {
     pub fn new(pool: Arc<dyn Driver>) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            options: QueryOptions::default(),
+        }
+    }
+
+    /// 返回带有单次调用查询选项的映射器，覆盖全局、连接池与语句上的设置
+    pub fn with_options(&self, options: QueryOptions) -> Mapper {
+        Mapper {
+            pool: self.pool.clone(),
+            options,
+        }
+    }
+
+    /// 逐层合并得到语句最终生效的查询选项
+    fn effective_options(&self, mapper: &SqlMapper) -> QueryOptions {
+        query_options()
+            .merge(&self.pool.query_options())
+            .merge(&mapper.options)
+            .merge(&self.options)
+    }
+
+    /// 选择执行语句的连接池：声明了 `shardedBy` 时由分片解析器决定，
+    /// 否则按 `route`（未声明时为命名空间的 `datasource`）
+    fn routed_pool<T: serde::Serialize>(
+        &self,
+        sql_id: &str,
+        mapper: &SqlMapper,
+        options: &QueryOptions,
+        args: &T,
+    ) -> Result<Arc<dyn Driver>, DbError> {
+        let route = shard::route(sql_id, mapper, args)?.or_else(|| options.route.clone());
+        match route.as_deref() {
+            Some(route) if route != self.pool.name() => UORM
+                .driver(route)
+                .ok_or_else(|| DbError::Query(format!("Route not found: {}", route))),
+            _ => Ok(self.pool.clone()),
+        }
     }
 
-    fn session(&self) -> Session {
-        Session::new(self.pool.clone())
+    fn routed_session<T: serde::Serialize>(
+        &self,
+        sql_id: &str,
+        mapper: &SqlMapper,
+        generation: u64,
+        options: &QueryOptions,
+        args: &T,
+    ) -> Result<Session, DbError> {
+        let pool = self.routed_pool(sql_id, mapper, options, args)?;
+        Ok(Session::new(pool)
+            .with_param_naming(options.param_naming)
+            .with_generation(generation))
     }
 
-    fn get_sql_mapper(&self, sql_id: &str) -> Result<std::sync::Arc<crate::mapper_loader::SqlMapper>, DbError> {
-        find_mapper(sql_id, self.pool.r#type()).ok_or_else(|| DbError::Query(format!("SQL ID not found: {}", sql_id)))
+    /// 模板缓存键：同一 SQL ID 的不同 databaseType 变体需要区分
+    fn cache_key(sql_id: &str, mapper: &crate::mapper_loader::SqlMapper) -> String {
+        crate::mapper_loader::template_key(sql_id, mapper.database_type.as_deref())
+    }
+
+    /// 查找语句，同时返回查找时的重载代数，渲染时据此判断语句是否已被重载
+    fn get_sql_mapper(&self, sql_id: &str) -> Result<(Arc<SqlMapper>, u64), DbError> {
+        let snapshot = mapper_loader::snapshot();
+        let mapper = snapshot
+            .find_mapper(sql_id, self.pool.r#type())
+            .ok_or_else(|| DbError::Query(format!("SQL ID not found: {}", sql_id)))?;
+        Ok((mapper, snapshot.generation()))
     }
 
     pub async fn get<R, T>(&self, sql_id: &str, args: &T) -> Result<R, DbError>
@@ -29,57 +115,221 @@ impl Mapper {
         T: serde::Serialize,
         R: serde::de::DeserializeOwned,
     {
-        let mapper = self.get_sql_mapper(sql_id)?;
-        let sql = mapper
-            .as_ref()
-            .content
-            .as_ref()
-            .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
-        let mut rows: Vec<R> = self.session().query(sql, args).await?;
+        let (mapper, generation) = self.get_sql_mapper(sql_id)?;
+        crate::type_registry::check_result::<R>(sql_id, &mapper)?;
+        let mut rows: Vec<R> = self.select(sql_id, &mapper, generation, args).await?;
         if rows.len() > 1 {
             return Err(DbError::Query("Expected 1 row, got multiple".into()));
         }
         rows.pop().ok_or(DbError::Query("No row found".into()))
     }
 
+    /// 查询至多一行，未找到时返回 `None`
+    pub async fn find<R, T>(&self, sql_id: &str, args: &T) -> Result<Option<R>, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let mut rows: Vec<R> = self.list(sql_id, args).await?;
+        if rows.len() > 1 {
+            return Err(DbError::Query(
+                "Expected at most 1 row, got multiple".into(),
+            ));
+        }
+        Ok(rows.pop())
+    }
+
     pub async fn list<R, T>(&self, sql_id: &str, args: &T) -> Result<Vec<R>, DbError>
     where
         T: serde::Serialize,
         R: serde::de::DeserializeOwned,
     {
-        let mapper = self.get_sql_mapper(sql_id)?;
+        let (mapper, generation) = self.get_sql_mapper(sql_id)?;
+        crate::type_registry::check_result::<R>(sql_id, &mapper)?;
+        self.select(sql_id, &mapper, generation, args).await
+    }
+
+    /// 执行查询语句；声明了 cacheKey 且设置了缓存后端时先读缓存，未命中再查询并写回
+    async fn select<R, T>(
+        &self,
+        sql_id: &str,
+        mapper: &crate::mapper_loader::SqlMapper,
+        generation: u64,
+        args: &T,
+    ) -> Result<Vec<R>, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
         let sql = mapper
-            .as_ref()
             .content
             .as_deref()
             .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
-        self.session().query(sql, args).await
+        let stmt_key = Self::cache_key(sql_id, mapper);
+        let options = self.effective_options(mapper);
+        let session = self.routed_session(sql_id, mapper, generation, &options, args)?;
+        let cache_key = match options.cache {
+            Some(false) => None,
+            // 缓存键不区分 schema
+            _ if session::in_schema_scope(session.database_name()) => None,
+            // 事务中可能读到未提交的数据
+            _ if session::in_transaction(session.database_name()) => None,
+            _ => query_cache::read_key(mapper, args),
+        };
+        let cached = match &cache_key {
+            Some(key) => query_cache::get(key).await,
+            None => None,
+        };
+        let mut rows = match cached {
+            Some(rows) => rows,
+            None => {
+                let rows = with_timeout(
+                    options.timeout,
+                    session.fetch_rows_limited(Some(&stmt_key), sql, args, options.auto_limit),
+                )
+                .await?;
+                if let Some(key) = &cache_key {
+                    query_cache::put(key, &rows, mapper.cache_ttl.map(Duration::from_secs)).await;
+                }
+                rows
+            }
+        };
+        if let Some(max_rows) = options.max_rows {
+            rows.truncate(max_rows);
+        }
+        // 缓存中保存的是处理前的行
+        session.post_process(&mapper.row_processors, Some(&stmt_key), &mut rows)?;
+        Session::map_rows_named(Some(&stmt_key), rows)
     }
 
-    pub async fn create<R, T>(&self, sql_id: &str, args: &T) -> Result<R, DbError>
+    /// 分页查询：在语句后追加 `LIMIT size OFFSET (page - 1) * size`，并查询总行数
+    ///
+    /// `page` 从 1 开始。总行数优先使用同一命名空间中的伴随语句 `<select id="{id}.count">`
+    /// （以相同参数渲染），否则由渲染出的 SQL 推导，见 [`page::count_sql`]。
+    /// 本页未取满时总行数可直接算出，不再执行计数查询。查询结果缓存不用于分页查询。
+    pub async fn page<R, T>(
+        &self,
+        sql_id: &str,
+        args: &T,
+        page: u64,
+        size: u64,
+    ) -> Result<Page<R>, DbError>
     where
         T: serde::Serialize,
         R: serde::de::DeserializeOwned,
     {
-        let mapper = self.get_sql_mapper(sql_id)?;
+        if size == 0 {
+            return Err(DbError::Query("page size must be greater than 0".into()));
+        }
+        let page = page.max(1);
+        let (mapper, generation) = self.get_sql_mapper(sql_id)?;
+        crate::type_registry::check_result::<R>(sql_id, &mapper)?;
         let sql = mapper
-            .as_ref()
             .content
             .as_deref()
             .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
-        let session = self.session();
+        let stmt_key = Self::cache_key(sql_id, &mapper);
+        let options = self.effective_options(&mapper);
+        let session = self.routed_session(sql_id, &mapper, generation, &options, args)?;
+        let (rendered_sql, params) = session.render(Some(&stmt_key), sql, args)?;
+        let offset = (page - 1).saturating_mul(size);
 
-        let affected = session.execute(sql, args).await?;
+        let page_sql = format!(
+            "{} LIMIT {} OFFSET {}",
+            page::trim_statement(&rendered_sql),
+            size,
+            offset
+        );
+        let mut rows = with_timeout(
+            options.timeout,
+            session.fetch_rendered(Some(&stmt_key), page_sql, params.clone()),
+        )
+        .await?;
+        session.post_process(&mapper.row_processors, Some(&stmt_key), &mut rows)?;
+        let items: Vec<R> = Session::map_rows_named(Some(&stmt_key), rows)?;
 
-        if mapper.use_generated_keys {
-            let id = session.last_insert_id().await?;
-            let v = Value::I64(id as i64);
-            R::deserialize(ValueDeserializer { value: &v })
+        let fetched = items.len() as u64;
+        let total = if fetched < size && (fetched > 0 || offset == 0) {
+            offset + fetched
         } else {
-            // Try to return affected rows as R
-            let v = Value::I64(affected as i64);
-            R::deserialize(ValueDeserializer { value: &v })
+            let count_id = format!("{}.count", sql_id);
+            let (count_sql, count_params, count_key) =
+                match find_mapper(&count_id, self.pool.r#type()) {
+                    Some(count) => {
+                        let content = count.content.as_deref().ok_or_else(|| {
+                            DbError::Query(format!("SQL content empty for {}", count_id))
+                        })?;
+                        let key = Self::cache_key(&count_id, &count);
+                        let (count_sql, count_params) =
+                            session.render(Some(&key), content, args)?;
+                        (count_sql, count_params, Some(key))
+                    }
+                    None => {
+                        let pool = session.pool();
+                        let placeholders: Vec<String> = params
+                            .iter()
+                            .enumerate()
+                            .map(|(i, (name, _))| pool.placeholder(i + 1, name))
+                            .collect();
+                        let is_placeholder =
+                            |text: &str| placeholders.iter().any(|p| text.contains(p.as_str()));
+                        (page::count_sql(&rendered_sql, is_placeholder), params, None)
+                    }
+                };
+            let rows = with_timeout(
+                options.timeout,
+                session.fetch_rendered(count_key.as_deref(), count_sql, count_params),
+            )
+            .await?;
+            Session::count_value(&rows)?
+        };
+        Ok(Page {
+            items,
+            total,
+            page,
+            size,
+        })
+    }
+
+    pub async fn create<R, T>(&self, sql_id: &str, args: &T) -> Result<R, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let (mapper, generation) = self.get_sql_mapper(sql_id)?;
+        let sql = mapper
+            .as_ref()
+            .content
+            .as_deref()
+            .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
+        let options = self.effective_options(&mapper);
+        let pool = self.routed_pool(sql_id, &mapper, &options, args)?;
+        let session = Session::new(pool.clone())
+            .with_param_naming(options.param_naming)
+            .with_generation(generation);
+
+        let applied = idempotency::apply(&pool, sql_id, &mapper, args, async {
+            let affected = session
+                .execute_named(&Self::cache_key(sql_id, &mapper), sql, args)
+                .await?;
+            let id = match mapper.use_generated_keys {
+                true => Some(session.last_insert_id().await? as i64),
+                false => None,
+            };
+            Ok((affected, id))
+        })
+        .await?;
+        if let Applied::Executed {
+            affected,
+            generated_key,
+        } = applied
+        {
+            events::emit(sql_id, &mapper, args, generated_key, affected);
+            query_cache::evict(pool.name(), &mapper, args).await;
         }
+        // 返回生成的主键，未使用自增主键时返回影响行数
+        let v = Value::I64(applied.result());
+        R::deserialize(ValueDeserializer { value: &v })
     }
 
     pub async fn batch_create<R, T>(&self, sql_id: &str, args: &[T]) -> Result<Vec<R>, DbError>
@@ -87,22 +337,29 @@ impl Mapper {
         T: serde::Serialize,
         R: serde::de::DeserializeOwned,
     {
-        let mapper = self.get_sql_mapper(sql_id)?;
+        let (mapper, generation) = self.get_sql_mapper(sql_id)?;
         let sql = mapper
             .as_ref()
             .content
             .as_ref()
             .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
-        let session = self.session();
+        let options = self.effective_options(&mapper);
 
         let mut results = Vec::with_capacity(args.len());
 
+        let key = Self::cache_key(sql_id, &mapper);
         for arg in args {
-            let affected = session.execute(sql, arg).await?;
+            // 分片语句的每个元素可能落在不同的连接池
+            let session = self.routed_session(sql_id, &mapper, generation, &options, arg)?;
+            let affected = session.execute_named(&key, sql, arg).await?;
             let val = if mapper.use_generated_keys {
                 let id = session.last_insert_id().await?;
+                events::emit(sql_id, &mapper, arg, Some(id as i64), affected);
+                query_cache::evict(session.database_name(), &mapper, arg).await;
                 Value::I64(id as i64)
             } else {
+                events::emit(sql_id, &mapper, arg, None, affected);
+                query_cache::evict(session.database_name(), &mapper, arg).await;
                 Value::I64(affected as i64)
             };
 
@@ -112,28 +369,654 @@ impl Mapper {
         Ok(results)
     }
 
+    /// 插入一行并返回插入后的完整行
+    ///
+    /// 驱动支持 RETURNING 时在语句末尾追加 `RETURNING *`（语句已包含 RETURNING 时原样执行）；
+    /// 否则在同一连接上取 `last_insert_id`，再回读该行：声明了 `returningSelect` 时执行该查询
+    /// （参数为插入参数另加生成的主键，键名为 `keyColumn`，默认 `id`），
+    /// 否则按 `INSERT INTO` 的表名与主键列查询。当前任务已处于该连接池的事务中时在事务连接上执行。
+    pub async fn create_returning<R, T>(&self, sql_id: &str, args: &T) -> Result<R, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let (mapper, generation) = self.get_sql_mapper(sql_id)?;
+        let sql = mapper
+            .content
+            .as_deref()
+            .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
+        let options = self.effective_options(&mapper);
+        let pool = self.routed_pool(sql_id, &mapper, &options, args)?;
+        let naming = options
+            .param_naming
+            .unwrap_or_else(|| engine::default_naming(pool.as_ref()));
+        let (rendered_sql, params) = engine::render_mapper_statement(
+            &Self::cache_key(sql_id, &mapper),
+            sql,
+            generation,
+            args,
+            pool.as_ref(),
+            naming,
+        )?;
+        let conn = StatementConn::acquire(pool.as_ref()).await?;
+
+        let (row, generated_key, affected) = if pool.supports_returning() {
+            let returning_sql = with_returning(&rendered_sql);
+            let mut rows = observed_query(&conn, sql_id, &returning_sql, &params).await?;
+            let row = rows
+                .pop()
+                .ok_or_else(|| DbError::Query(format!("{} returned no row", sql_id)))?;
+            (row, None, 1)
+        } else {
+            let start = std::time::Instant::now();
+            let result = conn.execute(&rendered_sql, &params).await;
+            digest::record_named(Some(sql_id), &rendered_sql, start.elapsed(), result.is_ok());
+            let affected = result?;
+            let id = conn.last_insert_id().await? as i64;
+            let row = self
+                .read_back(
+                    pool.as_ref(),
+                    &conn,
+                    sql_id,
+                    &mapper,
+                    &rendered_sql,
+                    args,
+                    id,
+                )
+                .await?;
+            (row, Some(id), affected)
+        };
+
+        events::emit(sql_id, &mapper, args, generated_key, affected);
+        query_cache::evict(pool.name(), &mapper, args).await;
+        R::deserialize(RowDeserializer::new(&row)).map_err(|e| e.with_sql_id(sql_id))
+    }
+
+    /// 在插入所用的连接上回读生成主键对应的行
+    #[allow(clippy::too_many_arguments)]
+    async fn read_back<T>(
+        &self,
+        pool: &dyn Driver,
+        conn: &StatementConn,
+        sql_id: &str,
+        mapper: &SqlMapper,
+        insert_sql: &str,
+        args: &T,
+        id: i64,
+    ) -> Result<HashMap<String, Value>, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let key = mapper.key_column.as_deref().unwrap_or("id");
+        let (select_sql, params) = match &mapper.returning_select {
+            Some(select_id) => {
+                // 未写命名空间时取插入语句所在的命名空间
+                let select_id = match (select_id.contains('.'), sql_id.rsplit_once('.')) {
+                    (false, Some((ns, _))) => format!("{}.{}", ns, select_id),
+                    _ => select_id.clone(),
+                };
+                let (select, generation) = self.get_sql_mapper(&select_id)?;
+                let content = select.content.as_deref().ok_or_else(|| {
+                    DbError::Query(format!("SQL content empty for {}", select_id))
+                })?;
+                let mut select_args = match to_value(args) {
+                    Value::Map(m) => m,
+                    _ => HashMap::new(),
+                };
+                select_args.insert(key.to_string(), Value::I64(id));
+                engine::render_mapper_statement(
+                    &Self::cache_key(&select_id, &select),
+                    content,
+                    generation,
+                    &Value::Map(select_args),
+                    pool,
+                    engine::default_naming(pool),
+                )?
+            }
+            None => {
+                let table = insert_table(insert_sql).ok_or_else(|| {
+                    DbError::Query(format!(
+                        "cannot find the table of {}; declare returningSelect",
+                        sql_id
+                    ))
+                })?;
+                if !is_identifier(key) {
+                    return Err(DbError::Query(format!(
+                        "invalid keyColumn '{}' in {}",
+                        key, sql_id
+                    )));
+                }
+                let sql = format!(
+                    "SELECT * FROM {} WHERE {} = {}",
+                    table,
+                    key,
+                    pool.placeholder(1, key)
+                );
+                (sql, vec![(key.to_string(), Value::I64(id))])
+            }
+        };
+        let mut rows = observed_query(conn, sql_id, &select_sql, &params).await?;
+        if rows.len() > 1 {
+            return Err(DbError::Query(format!(
+                "{} read back multiple rows for key {}",
+                sql_id, id
+            )));
+        }
+        rows.pop()
+            .ok_or_else(|| DbError::Query(format!("{} read back no row for key {}", sql_id, id)))
+    }
+
+    /// 在一个事务内执行 `<insert>` 及其嵌套的子 `<insert>`
+    ///
+    /// 子语句的参数为父语句的参数，另加 `parent`（父语句参数及其生成的主键，
+    /// 键名为 `keyColumn`，默认 `id`）；声明了 `collection` 的子语句对集合中每个元素
+    /// 各执行一次，元素以 `item` 声明的名称访问。任一语句失败时整体回滚。
+    /// 当前任务已处于该连接池的事务中时在保存点中执行，失败时只回滚到保存点，由外层事务决定提交。
+    /// 返回值与 [`Mapper::create`] 相同：父语句的生成主键或影响行数。
+    pub async fn create_graph<R, T>(&self, sql_id: &str, args: &T) -> Result<R, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let (mapper, generation) = self.get_sql_mapper(sql_id)?;
+        if mapper.kind != StatementKind::Insert {
+            return Err(DbError::Query(format!(
+                "{} is not an <insert> statement",
+                sql_id
+            )));
+        }
+        let pool = self.routed_pool(sql_id, &mapper, &self.effective_options(&mapper), args)?;
+        let pool_name = pool.name().to_string();
+        let stmt_key = Self::cache_key(sql_id, &mapper);
+        let mut executed = Vec::new();
+        let value = if let Some(ctx) = session::ambient_tx(&pool_name) {
+            // 已处于事务中：在保存点中执行，随外层事务提交
+            let mut tx = ctx.lock().await;
+            let savepoint = tx.savepoint().await?;
+            match Self::insert_chain(
+                &tx,
+                sql_id.to_string(),
+                stmt_key,
+                &mapper,
+                generation,
+                to_value(args),
+                &mut executed,
+            )
+            .await
+            {
+                Ok(value) => {
+                    tx.release_savepoint(&savepoint).await?;
+                    value
+                }
+                Err(e) => {
+                    tx.rollback_to_savepoint(&savepoint).await?;
+                    return Err(e);
+                }
+            }
+        } else {
+            let mut tx = TransactionContext::begin(pool).await?;
+            match Self::insert_chain(
+                &tx,
+                sql_id.to_string(),
+                stmt_key,
+                &mapper,
+                generation,
+                to_value(args),
+                &mut executed,
+            )
+            .await
+            {
+                Ok(value) => {
+                    tx.commit().await?;
+                    value
+                }
+                Err(e) => {
+                    let _ = tx.rollback().await;
+                    return Err(e);
+                }
+            }
+        };
+        for stmt in &executed {
+            events::emit(
+                &stmt.sql_id,
+                stmt.mapper,
+                &stmt.args,
+                stmt.generated_key,
+                stmt.affected,
+            );
+            query_cache::evict(&pool_name, stmt.mapper, &stmt.args).await;
+        }
+        R::deserialize(ValueDeserializer { value: &value })
+    }
+
+    /// 执行一条语句及其子语句，返回生成主键或影响行数
+    fn insert_chain<'a, 'm: 'a>(
+        tx: &'a TransactionContext,
+        sql_id: String,
+        stmt_key: String,
+        mapper: &'m SqlMapper,
+        generation: u64,
+        args: Value,
+        executed: &'a mut Vec<Executed<'m>>,
+    ) -> ChainFuture<'a> {
+        Box::pin(async move {
+            let sql = mapper
+                .content
+                .as_deref()
+                .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
+            let affected = tx.execute_mapper(&stmt_key, sql, generation, &args).await?;
+            let generated_key = if mapper.use_generated_keys {
+                Some(tx.last_insert_id().await? as i64)
+            } else {
+                None
+            };
+
+            if !mapper.chained.is_empty() {
+                let mut parent = match &args {
+                    Value::Map(m) => m.clone(),
+                    _ => Default::default(),
+                };
+                if let Some(id) = generated_key {
+                    let key = mapper
+                        .key_column
+                        .clone()
+                        .unwrap_or_else(|| "id".to_string());
+                    parent.insert(key, Value::I64(id));
+                }
+                let mut base = parent.clone();
+                base.insert("parent".to_string(), Value::Map(parent));
+
+                for child in &mapper.chained {
+                    let child_id = format!("{}/{}", sql_id, child.id);
+                    let child_key = chained_key(&stmt_key, &child.id);
+                    let items = match &child.collection {
+                        Some(path) => match Context::new(&args).lookup(path) {
+                            Value::List(items) => items.clone(),
+                            Value::Null => Vec::new(),
+                            _ => {
+                                return Err(DbError::Query(format!(
+                                    "collection '{}' of {} is not a list",
+                                    path, child_id
+                                )));
+                            }
+                        },
+                        None => vec![Value::Null],
+                    };
+                    for item in items {
+                        let mut child_args = base.clone();
+                        if child.collection.is_some() {
+                            child_args.insert(child.item.clone(), item);
+                        }
+                        Self::insert_chain(
+                            tx,
+                            child_id.clone(),
+                            child_key.clone(),
+                            &child.mapper,
+                            generation,
+                            Value::Map(child_args),
+                            executed,
+                        )
+                        .await?;
+                    }
+                }
+            }
+
+            executed.push(Executed {
+                sql_id,
+                mapper,
+                args,
+                generated_key,
+                affected,
+            });
+            Ok(Value::I64(generated_key.unwrap_or(affected as i64)))
+        })
+    }
+
+    /// 执行更新/删除语句，声明了 `idempotentKey` 时按幂等方式执行
+    async fn write<T>(
+        &self,
+        sql_id: &str,
+        mapper: &SqlMapper,
+        generation: u64,
+        sql: &str,
+        args: &T,
+    ) -> Result<u64, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let options = self.effective_options(mapper);
+        let pool = self.routed_pool(sql_id, mapper, &options, args)?;
+        let session = Session::new(pool.clone())
+            .with_param_naming(options.param_naming)
+            .with_generation(generation);
+        let applied = idempotency::apply(&pool, sql_id, mapper, args, async {
+            Ok((
+                session
+                    .execute_named(&Self::cache_key(sql_id, mapper), sql, args)
+                    .await?,
+                None,
+            ))
+        })
+        .await?;
+        if let Applied::Executed { affected, .. } = applied {
+            events::emit(sql_id, mapper, args, None, affected);
+            query_cache::evict(pool.name(), mapper, args).await;
+        }
+        Ok(applied.result() as u64)
+    }
+
     pub async fn update<T>(&self, sql_id: &str, args: &T) -> Result<u64, DbError>
     where
         T: serde::Serialize,
     {
-        let mapper = self.get_sql_mapper(sql_id)?;
+        let (mapper, generation) = self.get_sql_mapper(sql_id)?;
         let sql = mapper
             .as_ref()
             .content
             .as_deref()
             .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
-        self.session().execute(sql, args).await
+        self.write(sql_id, &mapper, generation, sql, args).await
+    }
+
+    /// 对每个元素分别渲染并执行同一条更新语句，返回各元素的影响行数
+    ///
+    /// 所有语句在同一个连接上依次执行，不再每行获取一次连接（已处于事务中时使用事务连接）。
+    /// 某一行失败时立即返回错误，此前已执行的行不会回滚；需要整体回滚时使用
+    /// [`Mapper::batch_update_in_tx`]。分片语句要求所有元素落在同一连接池上。
+    pub async fn batch_update<T>(&self, sql_id: &str, args: &[T]) -> Result<Vec<u64>, DbError>
+    where
+        T: serde::Serialize,
+    {
+        self.batch_update_inner(sql_id, args, false).await
+    }
+
+    /// 同 [`Mapper::batch_update`]，但所有行在一个事务内执行，任一行失败时整体回滚
+    pub async fn batch_update_in_tx<T>(&self, sql_id: &str, args: &[T]) -> Result<Vec<u64>, DbError>
+    where
+        T: serde::Serialize,
+    {
+        self.batch_update_inner(sql_id, args, true).await
+    }
+
+    async fn batch_update_inner<T>(
+        &self,
+        sql_id: &str,
+        args: &[T],
+        atomic: bool,
+    ) -> Result<Vec<u64>, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let (mapper, generation) = self.get_sql_mapper(sql_id)?;
+        let sql = mapper
+            .content
+            .as_deref()
+            .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
+        let Some(first) = args.first() else {
+            return Ok(Vec::new());
+        };
+        let options = self.effective_options(&mapper);
+        let pool = self.routed_pool(sql_id, &mapper, &options, first)?;
+        if mapper.sharded_by.is_some() {
+            for arg in &args[1..] {
+                let other = self.routed_pool(sql_id, &mapper, &options, arg)?;
+                if other.name() != pool.name() {
+                    return Err(DbError::Query(format!(
+                        "{}: batch items span multiple shards ({} and {})",
+                        sql_id,
+                        pool.name(),
+                        other.name()
+                    )));
+                }
+            }
+        }
+
+        let key = Self::cache_key(sql_id, &mapper);
+        let pool_name = pool.name().to_string();
+        let mut affected = Vec::with_capacity(args.len());
+        let mut failed = None;
+        if session::in_transaction(pool.name()) {
+            let session = Session::new(pool)
+                .with_param_naming(options.param_naming)
+                .with_generation(generation);
+            for arg in args {
+                match session.execute_named(&key, sql, arg).await {
+                    Ok(n) => affected.push(n),
+                    Err(e) => {
+                        failed = Some(e);
+                        break;
+                    }
+                }
+            }
+        } else if atomic {
+            let mut tx = TransactionContext::begin(pool).await?;
+            for arg in args {
+                match tx.execute_mapper(&key, sql, generation, arg).await {
+                    Ok(n) => affected.push(n),
+                    Err(e) => {
+                        let _ = tx.rollback().await;
+                        return Err(e);
+                    }
+                }
+            }
+            tx.commit().await?;
+        } else {
+            let conn = session::acquire(pool.as_ref()).await?;
+            let naming = options
+                .param_naming
+                .unwrap_or_else(|| engine::default_naming(pool.as_ref()));
+            for arg in args {
+                let result = match engine::render_mapper_statement(
+                    &key,
+                    sql,
+                    generation,
+                    arg,
+                    pool.as_ref(),
+                    naming,
+                ) {
+                    Ok((rendered_sql, params)) => {
+                        let start = std::time::Instant::now();
+                        let result = conn.execute(&rendered_sql, &params).await;
+                        digest::record_named(
+                            Some(sql_id),
+                            &rendered_sql,
+                            start.elapsed(),
+                            result.is_ok(),
+                        );
+                        result
+                    }
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(n) => affected.push(n),
+                    Err(e) => {
+                        failed = Some(e);
+                        break;
+                    }
+                }
+            }
+        }
+
+        // 已生效的行照常发布事件、失效缓存
+        for (arg, n) in args.iter().zip(&affected) {
+            events::emit(sql_id, &mapper, arg, None, *n);
+            query_cache::evict(&pool_name, &mapper, arg).await;
+        }
+        match failed {
+            Some(e) => Err(e),
+            None => Ok(affected),
+        }
     }
 
     pub async fn delete<T>(&self, sql_id: &str, args: &T) -> Result<u64, DbError>
     where
         T: serde::Serialize,
     {
-        let mapper = self.get_sql_mapper(sql_id)?;
+        let (mapper, generation) = self.get_sql_mapper(sql_id)?;
         let sql = mapper
             .content
             .as_ref()
             .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
-        self.session().execute(sql, args).await
+        self.write(sql_id, &mapper, generation, sql, args).await
+    }
+}
+
+/// 执行语句的连接：当前任务处于该连接池的事务中时为事务连接，否则为单独取出的连接
+enum StatementConn {
+    Tx(Arc<tokio::sync::Mutex<TransactionContext>>),
+    Conn(Arc<dyn Connection>),
+}
+
+impl StatementConn {
+    async fn acquire(pool: &dyn Driver) -> Result<Self, DbError> {
+        Ok(match session::ambient_tx(pool.name()) {
+            Some(ctx) => Self::Tx(ctx),
+            None => Self::Conn(session::acquire(pool).await?),
+        })
+    }
+
+    async fn query(
+        &self,
+        sql: &str,
+        params: &[(String, Value)],
+    ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+        match self {
+            Self::Tx(ctx) => ctx.lock().await.query_rendered(sql, params).await,
+            Self::Conn(conn) => conn.query(sql, params).await,
+        }
+    }
+
+    async fn execute(&self, sql: &str, params: &[(String, Value)]) -> Result<u64, DbError> {
+        match self {
+            Self::Tx(ctx) => ctx.lock().await.execute_rendered(sql, params).await,
+            Self::Conn(conn) => conn.execute(sql, params).await,
+        }
+    }
+
+    async fn last_insert_id(&self) -> Result<u64, DbError> {
+        match self {
+            Self::Tx(ctx) => ctx.lock().await.last_insert_id().await,
+            Self::Conn(conn) => conn.last_insert_id().await,
+        }
+    }
+}
+
+/// 执行查询并记录语句统计
+async fn observed_query(
+    conn: &StatementConn,
+    sql_id: &str,
+    sql: &str,
+    params: &[(String, Value)],
+) -> Result<Vec<HashMap<String, Value>>, DbError> {
+    let start = std::time::Instant::now();
+    let result = conn.query(sql, params).await;
+    digest::record_named(Some(sql_id), sql, start.elapsed(), result.is_ok());
+    result
+}
+
+/// 语句末尾追加 `RETURNING *`，已包含 RETURNING 子句时原样返回
+fn with_returning(sql: &str) -> String {
+    let sql = sql.trim_end().trim_end_matches(';').trim_end();
+    if sql
+        .to_ascii_lowercase()
+        .split_whitespace()
+        .any(|w| w == "returning")
+    {
+        sql.to_string()
+    } else {
+        format!("{} RETURNING *", sql)
+    }
+}
+
+/// 取 `INSERT INTO <table>` 中的表名（可带库名与引号）
+fn insert_table(mut sql: &str) -> Option<&str> {
+    // 跳过开头的注释（如链路注释）
+    while let Some(rest) = sql.trim_start().strip_prefix("/*") {
+        sql = rest.split_once("*/")?.1;
+    }
+    let mut words = sql.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("insert") {
+        return None;
+    }
+    let mut word = words.next()?;
+    if word.eq_ignore_ascii_case("ignore") {
+        word = words.next()?;
+    }
+    if word.eq_ignore_ascii_case("into") {
+        word = words.next()?;
+    }
+    let table = word.split('(').next()?;
+    let valid = !table.is_empty()
+        && table
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '`' | '"'));
+    valid.then_some(table)
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// 将具名参数组装为模板根对象（供 `#[sql]` 宏生成的代码使用）
+///
+/// 只有一个参数且其序列化结果为对象时，直接以该对象作为根，
+/// 使 `#{name}` 可以访问结构体字段；否则以参数名作为键。
+#[doc(hidden)]
+pub fn named_args(args: Vec<(&str, Value)>) -> Value {
+    if args.len() == 1 && matches!(args[0].1, Value::Map(_)) {
+        return args
+            .into_iter()
+            .next()
+            .map(|(_, v)| v)
+            .unwrap_or(Value::Null);
+    }
+    Value::Map(args.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+}
+
+/// 组装带 `self` 接收者的方法的模板根对象（供 `#[sql]` 宏生成的代码使用）
+///
+/// `self` 序列化为对象时以其字段作为根，其余参数按名称并入（同名时参数优先）；
+/// 否则以 `self` 作为键。
+#[doc(hidden)]
+pub fn receiver_args(receiver: Value, args: Vec<(&str, Value)>) -> Value {
+    let mut root = match receiver {
+        Value::Map(m) => m,
+        other => HashMap::from([("self".to_string(), other)]),
+    };
+    root.extend(args.into_iter().map(|(k, v)| (k.to_string(), v)));
+    Value::Map(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_returning() {
+        assert_eq!(
+            with_returning("INSERT INTO t (a) VALUES (?);\n"),
+            "INSERT INTO t (a) VALUES (?) RETURNING *"
+        );
+        assert_eq!(
+            with_returning("INSERT INTO t (a) VALUES (?) RETURNING id"),
+            "INSERT INTO t (a) VALUES (?) RETURNING id"
+        );
+    }
+
+    #[test]
+    fn test_insert_table() {
+        assert_eq!(
+            insert_table("INSERT INTO users (name) VALUES (?)"),
+            Some("users")
+        );
+        assert_eq!(
+            insert_table("insert ignore into app.`users`(name) values (?)"),
+            Some("app.`users`")
+        );
+        assert_eq!(insert_table("REPLACE INTO users VALUES (?)"), None);
+        assert_eq!(
+            insert_table("/* trace=abc */ INSERT INTO users VALUES (?)"),
+            Some("users")
+        );
     }
 }
@@ -1,2 +1,29 @@
+pub mod digest;
+#[cfg(feature = "runtime")]
+pub mod export;
+#[cfg(feature = "runtime")]
+pub mod idempotency;
+#[cfg(feature = "runtime")]
+pub mod insert;
+#[cfg(feature = "runtime")]
+pub mod instrument;
+#[cfg(feature = "runtime")]
 pub mod mapper;
+#[cfg(feature = "runtime")]
+pub mod multi;
+pub mod options;
+#[cfg(feature = "runtime")]
+pub mod page;
+#[cfg(feature = "runtime")]
+pub mod pinned;
+pub mod postprocess;
+#[cfg(feature = "runtime")]
+pub mod prepared;
+#[cfg(feature = "runtime")]
+pub mod readonly;
+#[cfg(feature = "runtime")]
 pub mod session;
+#[cfg(feature = "runtime")]
+pub mod shard;
+#[cfg(feature = "runtime")]
+pub mod trace;
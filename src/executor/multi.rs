@@ -0,0 +1,42 @@
+use crate::error::DbError;
+use crate::udbc::deserializer::RowDeserializer;
+use crate::udbc::value::Value;
+use std::collections::{HashMap, VecDeque};
+
+/// [`crate::executor::session::Session::query_multi`] 返回的多个结果集
+///
+/// 结果集按语句顺序排列，通过 [`ResultSets::next_set`] 依次取出并映射为各自的类型。
+#[derive(Debug, Default)]
+pub struct ResultSets {
+    sets: VecDeque<(String, Vec<HashMap<String, Value>>)>,
+}
+
+impl ResultSets {
+    pub(crate) fn push(&mut self, sql_id: &str, rows: Vec<HashMap<String, Value>>) {
+        self.sets.push_back((sql_id.to_string(), rows));
+    }
+
+    /// 剩余的结果集个数
+    pub fn len(&self) -> usize {
+        self.sets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    /// 取出下一个结果集并映射为目标类型；映射错误中带有对应的 SQL ID
+    pub fn next_set<R>(&mut self) -> Result<Vec<R>, DbError>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        let (sql_id, rows) = self
+            .sets
+            .pop_front()
+            .ok_or_else(|| DbError::Query("No more result sets".into()))?;
+        rows.iter()
+            .map(|r| R::deserialize(RowDeserializer::new(r)))
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.with_sql_id(&sql_id))
+    }
+}
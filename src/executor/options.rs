@@ -0,0 +1,113 @@
+//! 查询选项的分层默认值
+//!
+//! 生效顺序（后者覆盖前者中已设置的字段）：
+//! 全局（[`set_query_options`]）→ 连接池（[`crate::udbc::driver::Driver::query_options`]）
+//! → 语句 XML 属性 → 单次调用（[`crate::executor::mapper::Mapper::with_options`]）。
+//!
+//! ```xml
+//! <select id="report" timeout="5" maxRows="1000" autoLimit="0" route="replica" useCache="false">
+//!     SELECT * FROM orders
+//! </select>
+//! ```
+
+#[cfg(feature = "runtime")]
+use crate::error::DbError;
+use crate::tpl::ParamNaming;
+#[cfg(feature = "runtime")]
+use std::future::Future;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+/// 查询选项；`None` 表示沿用上一层的设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct QueryOptions {
+    /// 查询超时，超时返回 [`DbError::Timeout`]
+    pub timeout: Option<Duration>,
+    /// 最多返回的行数，超出部分被丢弃
+    pub max_rows: Option<usize>,
+    /// 为没有 `LIMIT` 的查询追加 `LIMIT n`，防止误取整表；`0` 表示关闭
+    ///
+    /// 由渲染出的 SQL 判断，见 [`crate::executor::page::limit_sql`]。
+    /// 与 `max_rows` 不同，上限在服务端生效，达到上限时记录警告日志。
+    pub auto_limit: Option<usize>,
+    /// 流式读取时每批的行数提示，驱动不支持时忽略
+    pub fetch_size: Option<usize>,
+    /// 执行查询的连接池名（如只读副本），须已在 [`crate::driver_manager::UORM`] 中注册
+    pub route: Option<String>,
+    /// 是否使用查询结果缓存（`cacheKey`）
+    pub cache: Option<bool>,
+    /// 模板参数名与参数键的匹配方式，如 `#{userId}` 匹配 `user_id`
+    pub param_naming: Option<ParamNaming>,
+}
+
+impl QueryOptions {
+    /// 以 `other` 中已设置的字段覆盖当前值
+    pub fn merge(&self, other: &QueryOptions) -> QueryOptions {
+        QueryOptions {
+            timeout: other.timeout.or(self.timeout),
+            max_rows: other.max_rows.or(self.max_rows),
+            auto_limit: other.auto_limit.or(self.auto_limit),
+            fetch_size: other.fetch_size.or(self.fetch_size),
+            route: other.route.clone().or_else(|| self.route.clone()),
+            cache: other.cache.or(self.cache),
+            param_naming: other.param_naming.or(self.param_naming),
+        }
+    }
+}
+
+static QUERY_OPTIONS: LazyLock<RwLock<QueryOptions>> =
+    LazyLock::new(|| RwLock::new(QueryOptions::default()));
+
+/// 设置全局默认查询选项
+pub fn set_query_options(options: QueryOptions) {
+    *QUERY_OPTIONS.write().unwrap() = options;
+}
+
+/// 获取当前全局默认查询选项
+pub fn query_options() -> QueryOptions {
+    QUERY_OPTIONS.read().unwrap().clone()
+}
+
+/// 按超时设置等待查询完成
+#[cfg(feature = "runtime")]
+pub(crate) async fn with_timeout<T, F>(timeout: Option<Duration>, fut: F) -> Result<T, DbError>
+where
+    F: Future<Output = Result<T, DbError>>,
+{
+    match timeout {
+        Some(limit) => tokio::time::timeout(limit, fut)
+            .await
+            .map_err(|_| DbError::Timeout(format!("query exceeded {}ms", limit.as_millis())))?,
+        None => fut.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_overrides_set_fields() {
+        let pool = QueryOptions {
+            timeout: Some(Duration::from_secs(30)),
+            max_rows: Some(100),
+            route: Some("replica".into()),
+            param_naming: Some(ParamNaming::Relaxed),
+            ..Default::default()
+        };
+        let call = QueryOptions {
+            max_rows: Some(10),
+            auto_limit: Some(0),
+            cache: Some(false),
+            ..Default::default()
+        };
+        let merged = pool.merge(&call);
+        assert_eq!(merged.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(merged.max_rows, Some(10));
+        assert_eq!(merged.auto_limit, Some(0));
+        assert_eq!(merged.route.as_deref(), Some("replica"));
+        assert_eq!(merged.cache, Some(false));
+        assert_eq!(merged.fetch_size, None);
+        assert_eq!(merged.param_naming, Some(ParamNaming::Relaxed));
+    }
+}
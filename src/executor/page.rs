@@ -0,0 +1,425 @@
+//! 分页查询结果与计数语句推导
+//!
+//! [`Mapper::page`](crate::executor::mapper::Mapper::page) 在语句后追加 `LIMIT/OFFSET` 取一页数据，
+//! 总行数优先由伴随的 `<select id="{id}.count">` 语句给出，否则由 [`count_sql`] 从渲染出的 SQL 推导：
+//! 去掉最外层的 `ORDER BY`，能直接改写时将选择列表替换为 `COUNT(*)`，
+//! 否则包装为 `SELECT COUNT(*) FROM (...)`。
+//!
+//! [`limit_sql`] 为没有 `LIMIT` 的查询追加上限，见
+//! [`QueryOptions::auto_limit`](crate::executor::options::QueryOptions::auto_limit)。
+
+use serde::Serialize;
+
+/// 一页查询结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Page<R> {
+    /// 本页的行
+    pub items: Vec<R>,
+    /// 总行数
+    pub total: u64,
+    /// 页码，从 1 开始
+    pub page: u64,
+    /// 每页行数
+    pub size: u64,
+}
+
+impl<R> Page<R> {
+    /// 总页数
+    pub fn pages(&self) -> u64 {
+        self.total.div_ceil(self.size)
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.page < self.pages()
+    }
+}
+
+/// 选择列表中出现时不能直接改写为 `COUNT(*)` 的聚合函数
+const AGGREGATES: &[&str] = &[
+    "count",
+    "sum",
+    "avg",
+    "min",
+    "max",
+    "group_concat",
+    "json_arrayagg",
+    "json_objectagg",
+    "bit_and",
+    "bit_or",
+    "bit_xor",
+    "std",
+    "stddev",
+    "stddev_pop",
+    "stddev_samp",
+    "variance",
+    "var_pop",
+    "var_samp",
+    "string_agg",
+    "array_agg",
+];
+
+/// 最外层出现时不能直接改写选择列表的关键字
+const NOT_REWRITABLE: &[&str] = &[
+    "distinct",
+    "group",
+    "having",
+    "union",
+    "intersect",
+    "except",
+    "limit",
+    "offset",
+    "fetch",
+    "for",
+    "into",
+    "lock",
+    "window",
+];
+
+/// `ORDER BY` 之后出现时说明排序影响结果行，不能去掉
+const ORDER_DEPENDENT: &[&str] = &["limit", "offset", "fetch", "for", "lock"];
+
+/// 最外层出现时说明查询已有行数上限或不是单纯的查询，不追加 `LIMIT`
+const NOT_LIMITABLE: &[&str] = &["limit", "fetch", "into", "insert", "update", "delete"];
+
+/// 最外层的一个单词
+struct Word {
+    start: usize,
+    end: usize,
+    lower: String,
+}
+
+/// 由渲染出的 SQL 推导计数语句
+///
+/// `is_placeholder` 判断一段文本中是否含有参数占位符：被去掉的选择列表或 `ORDER BY`
+/// 中含有占位符时参数无法对应，改为保留原文包装为子查询。
+pub fn count_sql(sql: &str, is_placeholder: impl Fn(&str) -> bool) -> String {
+    let masked = mask(sql);
+    let end = statement_end(&masked);
+    let (sql, masked) = (&sql[..end], &masked[..end]);
+    let words = top_level_words(masked);
+    let find = |from: usize, word: &str| {
+        words[from..]
+            .iter()
+            .position(|w| w.lower == word)
+            .map(|i| i + from)
+    };
+
+    // 最外层的 ORDER BY 及其之后都可去掉，除非其后还有依赖排序的子句
+    let mut body_end = sql.len();
+    if let Some(order) = find(0, "order")
+        && words.get(order + 1).is_some_and(|w| w.lower == "by")
+        && !words[order..]
+            .iter()
+            .any(|w| ORDER_DEPENDENT.contains(&w.lower.as_str()))
+        && !is_placeholder(&masked[words[order].start..])
+    {
+        body_end = words[order].start;
+    }
+    let body = sql[..body_end].trim_end();
+
+    let rewritable = words.first().is_some_and(|w| w.lower == "select")
+        && !words
+            .iter()
+            .take_while(|w| w.start < body_end)
+            .any(|w| NOT_REWRITABLE.contains(&w.lower.as_str()));
+    if rewritable && let Some(from) = find(1, "from") {
+        let select_end = words[0].end;
+        let list = &masked[select_end..words[from].start];
+        if !is_placeholder(list) && !has_aggregate(list) {
+            return format!(
+                "{} COUNT(*) {}",
+                &sql[..select_end],
+                &body[words[from].start..]
+            );
+        }
+    }
+    format!("SELECT COUNT(*) FROM ({}) uorm_count", body)
+}
+
+/// 语句是最外层没有 `LIMIT` 的查询时追加 `LIMIT limit`，否则返回 `None`
+///
+/// 只处理以 `SELECT`/`WITH` 开头的语句；已有 `LIMIT`/`FETCH`、`SELECT ... INTO`
+/// 或数据修改语句保持不变。最外层有 `FOR UPDATE`、`LOCK IN SHARE MODE` 等加锁子句时插在其前。
+pub fn limit_sql(sql: &str, limit: usize) -> Option<String> {
+    let masked = mask(sql);
+    let end = statement_end(&masked);
+    let (sql, masked) = (&sql[..end], &masked[..end]);
+    let words = top_level_words(masked);
+    if !words
+        .first()
+        .is_some_and(|w| w.lower == "select" || w.lower == "with")
+    {
+        return None;
+    }
+    let locking = words.windows(2).position(|pair| {
+        matches!(
+            (pair[0].lower.as_str(), pair[1].lower.as_str()),
+            ("for", "update" | "share" | "no" | "key") | ("lock", "in")
+        )
+    });
+    let body = &words[..locking.unwrap_or(words.len())];
+    if body
+        .iter()
+        .any(|w| NOT_LIMITABLE.contains(&w.lower.as_str()))
+    {
+        return None;
+    }
+    Some(match locking {
+        Some(i) => {
+            let at = words[i].start;
+            format!("{} LIMIT {} {}", sql[..at].trim_end(), limit, &sql[at..])
+        }
+        None => format!("{} LIMIT {}", sql, limit),
+    })
+}
+
+/// 去掉末尾的分号、空白与注释，便于在语句后追加子句
+pub fn trim_statement(sql: &str) -> &str {
+    &sql[..statement_end(&mask(sql))]
+}
+
+fn statement_end(masked: &str) -> usize {
+    masked
+        .trim_end_matches(|c: char| c == ';' || c.is_whitespace())
+        .len()
+}
+
+/// 选择列表中是否调用了聚合函数；标量子查询中的聚合不计
+fn has_aggregate(list: &str) -> bool {
+    let lower = without_subqueries(&list.to_ascii_lowercase());
+    let bytes = lower.as_bytes();
+    AGGREGATES.iter().any(|name| {
+        lower.match_indices(name).any(|(i, _)| {
+            let before = i.checked_sub(1).map(|p| bytes[p]);
+            let after = lower[i + name.len()..].trim_start();
+            !before.is_some_and(is_word_byte) && after.starts_with('(')
+        })
+    })
+}
+
+/// 去掉 `(select ...)` 子查询，`sql` 为已转为小写的掩码文本
+fn without_subqueries(sql: &str) -> String {
+    let bytes = sql.as_bytes();
+    let mut out = String::with_capacity(sql.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = sql[i + 1..].trim_start();
+        if bytes[i] == b'('
+            && rest.starts_with("select")
+            && !rest[6..].starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        {
+            let mut depth = 0;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'(' => depth += 1,
+                    b')' => depth -= 1,
+                    _ => {}
+                }
+                i += 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            out.push_str("()");
+            continue;
+        }
+        let c = sql[i..].chars().next().unwrap();
+        out.push(c);
+        i += c.len_utf8();
+    }
+    out
+}
+
+pub(crate) fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// 将字符串字面量与带引号的标识符替换为等长的引号、注释替换为等长的空白，便于按位置查找关键字
+pub(crate) fn mask(sql: &str) -> String {
+    let bytes = sql.as_bytes();
+    let mut out = bytes.to_vec();
+    let mut i = 0;
+    while i < bytes.len() {
+        let (end, fill) = match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                let mut j = i + 1;
+                while j < bytes.len() {
+                    if bytes[j] == b'\\' && quote != b'`' {
+                        j += 2;
+                    } else if bytes[j] == quote {
+                        // 双写的引号是转义
+                        if bytes.get(j + 1) == Some(&quote) {
+                            j += 2;
+                        } else {
+                            break;
+                        }
+                    } else {
+                        j += 1;
+                    }
+                }
+                ((j + 1).min(bytes.len()), quote)
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => (line_end(bytes, i), b' '),
+            b'#' => (line_end(bytes, i), b' '),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = sql[i + 2..].find("*/").map_or(bytes.len(), |p| i + p + 4);
+                (end, b' ')
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        out[i..end].fill(fill);
+        i = end;
+    }
+    // 只把完整的字符替换为 ASCII 字符，结果仍是合法的 UTF-8
+    String::from_utf8(out).expect("masked SQL is valid UTF-8")
+}
+
+/// 从 `start` 开始的行注释的结束位置（不含换行）
+fn line_end(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |p| start + p)
+}
+
+/// 最外层的单词，按出现顺序
+fn top_level_words(masked: &str) -> Vec<Word> {
+    let bytes = masked.as_bytes();
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' => depth += 1,
+            b')' => depth = depth.saturating_sub(1),
+            b if is_word_byte(b) && (i == 0 || !is_word_byte(bytes[i - 1])) => {
+                let end = bytes[i..]
+                    .iter()
+                    .position(|&b| !is_word_byte(b))
+                    .map_or(bytes.len(), |p| i + p);
+                if depth == 0 {
+                    words.push(Word {
+                        start: i,
+                        end,
+                        lower: masked[i..end].to_ascii_lowercase(),
+                    });
+                }
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(sql: &str) -> String {
+        count_sql(sql, |s| s.contains('?'))
+    }
+
+    #[test]
+    fn test_count_sql_rewrites_select_list() {
+        assert_eq!(
+            count(
+                "SELECT u.id, u.name, o.total FROM users u JOIN orders o ON o.user_id = u.id \
+                 WHERE u.status = ? ORDER BY o.created_at DESC, u.id"
+            ),
+            "SELECT COUNT(*) FROM users u JOIN orders o ON o.user_id = u.id WHERE u.status = ?"
+        );
+        // 字面量、子查询与注释中的关键字不影响改写
+        assert_eq!(
+            count(
+                "/* app */ select id, (select max(x) from t2 where t2.id = t.id) m from t \
+                 where name = 'order by' -- from\n;"
+            ),
+            "/* app */ select COUNT(*) from t where name = 'order by'"
+        );
+        assert_eq!(trim_statement("select 1 -- note\n ; "), "select 1");
+    }
+
+    #[test]
+    fn test_count_sql_wraps_when_not_rewritable() {
+        assert_eq!(
+            count("SELECT DISTINCT city FROM users ORDER BY city"),
+            "SELECT COUNT(*) FROM (SELECT DISTINCT city FROM users) uorm_count"
+        );
+        assert_eq!(
+            count("SELECT status, COUNT(*) FROM users GROUP BY status"),
+            "SELECT COUNT(*) FROM (SELECT status, COUNT(*) FROM users GROUP BY status) uorm_count"
+        );
+        assert_eq!(
+            count("SELECT IFNULL(SUM(amount), 0) FROM orders"),
+            "SELECT COUNT(*) FROM (SELECT IFNULL(SUM(amount), 0) FROM orders) uorm_count"
+        );
+        // 选择列表或排序中有参数时保留原文
+        assert_eq!(
+            count("SELECT id, name = ? AS me FROM users ORDER BY FIELD(id, ?)"),
+            "SELECT COUNT(*) FROM (SELECT id, name = ? AS me FROM users ORDER BY FIELD(id, ?)) uorm_count"
+        );
+        // 排序影响 LIMIT 取到的行
+        assert_eq!(
+            count("SELECT id FROM users ORDER BY id LIMIT 10"),
+            "SELECT COUNT(*) FROM (SELECT id FROM users ORDER BY id LIMIT 10) uorm_count"
+        );
+    }
+
+    #[test]
+    fn test_limit_sql() {
+        assert_eq!(
+            limit_sql("SELECT * FROM users WHERE name = 'limit' -- all\n;", 100).as_deref(),
+            Some("SELECT * FROM users WHERE name = 'limit' LIMIT 100")
+        );
+        assert_eq!(
+            limit_sql(
+                "WITH t AS (SELECT id FROM a LIMIT 5) SELECT * FROM t UNION SELECT id FROM b",
+                10
+            )
+            .as_deref(),
+            Some(
+                "WITH t AS (SELECT id FROM a LIMIT 5) SELECT * FROM t UNION SELECT id FROM b LIMIT 10"
+            )
+        );
+        assert_eq!(
+            limit_sql(
+                "select id from jobs where state = ? for update skip locked",
+                1
+            )
+            .as_deref(),
+            Some("select id from jobs where state = ? LIMIT 1 for update skip locked")
+        );
+        assert_eq!(
+            limit_sql("SELECT id FROM t LOCK IN SHARE MODE", 2).as_deref(),
+            Some("SELECT id FROM t LIMIT 2 LOCK IN SHARE MODE")
+        );
+        for sql in [
+            "SELECT id FROM users ORDER BY id LIMIT 10",
+            "SELECT id FROM users FETCH FIRST 10 ROWS ONLY",
+            "SELECT id INTO @id FROM users",
+            "WITH t AS (SELECT 1) UPDATE users SET a = 1",
+            "UPDATE users SET a = 1",
+            "SHOW TABLES",
+        ] {
+            assert_eq!(limit_sql(sql, 10), None, "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_page_counts() {
+        let page = Page {
+            items: vec![1, 2],
+            total: 12,
+            page: 2,
+            size: 5,
+        };
+        assert_eq!(page.pages(), 3);
+        assert!(page.has_next());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::error::DbError;
+use crate::executor::digest;
+use crate::executor::instrument::{Outcome, StatementSpan};
+use crate::executor::postprocess::{self, RowPostProcessor};
+use crate::tpl::engine;
+use crate::udbc::connection::{Connection, RawConnection};
+use crate::udbc::deserializer::RowDeserializer;
+use crate::udbc::driver::Driver;
+use crate::udbc::value::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::Instrument;
+
+/// 固定在单个连接上的会话，由 [`crate::executor::session::Session::pinned`] 创建
+///
+/// 所有语句都在同一连接上执行（不开启事务），适用于临时表、用户变量、
+/// `GET_LOCK` 等会话级特性。会话释放后连接归还连接池；
+/// 归还前应自行清理会话状态（如 `RELEASE_LOCK`、`DROP TEMPORARY TABLE`）。
+pub struct PinnedSession {
+    conn: Arc<dyn Connection>,
+    pool: Arc<dyn Driver>,
+    /// 连接池与会话上的行后处理器
+    row_processors: Vec<Arc<dyn RowPostProcessor>>,
+}
+
+impl PinnedSession {
+    pub(crate) fn new(
+        conn: Arc<dyn Connection>,
+        pool: Arc<dyn Driver>,
+        row_processors: Vec<Arc<dyn RowPostProcessor>>,
+    ) -> Self {
+        Self {
+            conn,
+            pool,
+            row_processors,
+        }
+    }
+
+    pub async fn execute<T>(&self, sql: &str, args: &T) -> Result<u64, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let (rendered_sql, params) = engine::render_template(sql, sql, args, self.pool.as_ref())?;
+        let stmt = StatementSpan::new("pinned_execute", self.pool.name(), None);
+        let start = Instant::now();
+        let result = self
+            .conn
+            .execute(&rendered_sql, &params)
+            .instrument(stmt.span())
+            .await;
+        let elapsed = start.elapsed();
+        let fingerprint = digest::record(&rendered_sql, elapsed, result.is_ok());
+        stmt.finish(Outcome {
+            fingerprint: Some(&fingerprint),
+            sql: &rendered_sql,
+            params: &params,
+            elapsed,
+            rows: result.as_ref().ok().copied(),
+            error: result.as_ref().err(),
+        });
+        result
+    }
+
+    pub async fn query<R, T>(&self, sql: &str, args: &T) -> Result<Vec<R>, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let mut rows = self.fetch_rows(sql, args).await?;
+        postprocess::apply(&self.row_processors, &[], None, &mut rows)?;
+        rows.iter()
+            .map(|r| R::deserialize(RowDeserializer::new(r)))
+            .collect()
+    }
+
+    /// 查询至多一行，未找到时返回 `None`
+    pub async fn find<R, T>(&self, sql: &str, args: &T) -> Result<Option<R>, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let mut rows: Vec<R> = self.query(sql, args).await?;
+        if rows.len() > 1 {
+            return Err(DbError::Query(
+                "Expected at most 1 row, got multiple".into(),
+            ));
+        }
+        Ok(rows.pop())
+    }
+
+    async fn fetch_rows<T>(
+        &self,
+        sql: &str,
+        args: &T,
+    ) -> Result<Vec<HashMap<String, Value>>, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let (rendered_sql, params) = engine::render_template(sql, sql, args, self.pool.as_ref())?;
+        let stmt = StatementSpan::new("pinned_query", self.pool.name(), None);
+        let start = Instant::now();
+        let result = self
+            .conn
+            .query(&rendered_sql, &params)
+            .instrument(stmt.span())
+            .await;
+        let elapsed = start.elapsed();
+        let fingerprint = digest::record(&rendered_sql, elapsed, result.is_ok());
+        stmt.finish(Outcome {
+            fingerprint: Some(&fingerprint),
+            sql: &rendered_sql,
+            params: &params,
+            elapsed,
+            rows: result.as_ref().ok().map(|r| r.len() as u64),
+            error: result.as_ref().err(),
+        });
+        result
+    }
+
+    /// 本连接上最近一次插入生成的主键
+    pub async fn last_insert_id(&self) -> Result<u64, DbError> {
+        self.conn.last_insert_id().await
+    }
+
+    /// 所固定连接的句柄
+    pub fn raw_connection(&self) -> RawConnection {
+        RawConnection::new(self.conn.clone())
+    }
+}
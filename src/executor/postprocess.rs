@@ -0,0 +1,150 @@
+//! 行后处理器：在反序列化之前改写查询返回的行
+//!
+//! 用于解密加密列、将逗号分隔的旧字段拆分为列表等，无需改动反序列化器。
+//! 处理器可以挂在三个层级，按以下顺序依次执行：
+//!
+//! 1. 连接池：[`Driver::row_processors`](crate::udbc::driver::Driver::row_processors)
+//! 2. 会话：[`Session::with_row_processor`](crate::executor::session::Session::with_row_processor)
+//! 3. 语句：`<select rowProcessors="decrypt,tags">` 引用以 [`register_row_processor`] 注册的名称
+//!
+//! ```ignore
+//! postprocess::register_row_processor("tags", Arc::new(SplitColumns::new(",", ["tags"])));
+//! ```
+
+use crate::error::DbError;
+use crate::udbc::value::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// 行后处理器
+pub trait RowPostProcessor: Send + Sync {
+    /// 改写一行；`sql_id` 为语句 ID，直接执行 SQL 文本时为 `None`。返回错误时查询以该错误结束
+    fn process(
+        &self,
+        sql_id: Option<&str>,
+        row: &mut HashMap<String, Value>,
+    ) -> Result<(), DbError>;
+}
+
+/// 名称 -> 处理器，供语句的 `rowProcessors` 属性引用
+static PROCESSORS: LazyLock<RwLock<HashMap<String, Arc<dyn RowPostProcessor>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// 以名称注册行后处理器，同名时覆盖
+pub fn register_row_processor(name: &str, processor: Arc<dyn RowPostProcessor>) {
+    PROCESSORS
+        .write()
+        .unwrap()
+        .insert(name.to_string(), processor);
+}
+
+/// 移除以名称注册的行后处理器，之后引用它的语句执行时报错
+pub fn unregister_row_processor(name: &str) {
+    PROCESSORS.write().unwrap().remove(name);
+}
+
+/// 清空已注册的行后处理器（主要用于测试环境重置状态）
+pub fn clear_row_processors() {
+    PROCESSORS.write().unwrap().clear();
+}
+
+/// 将字符串列按分隔符拆分为列表，如 `"a,b,c"` -> `["a", "b", "c"]`
+///
+/// 各项去掉首尾空白，空字符串得到空列表；`NULL` 与非字符串列保持不变。
+pub struct SplitColumns {
+    separator: String,
+    columns: Vec<String>,
+}
+
+impl SplitColumns {
+    pub fn new<I, S>(separator: &str, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            separator: separator.to_string(),
+            columns: columns.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl RowPostProcessor for SplitColumns {
+    fn process(
+        &self,
+        _sql_id: Option<&str>,
+        row: &mut HashMap<String, Value>,
+    ) -> Result<(), DbError> {
+        for column in &self.columns {
+            if let Some(value) = row.get_mut(column)
+                && let Value::Str(s) = value
+            {
+                let items = s
+                    .split(self.separator.as_str())
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| Value::Str(item.to_string()))
+                    .collect();
+                *value = Value::List(items);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 依次以 `processors` 与名为 `names` 的已注册处理器改写各行
+#[cfg(feature = "runtime")]
+pub(crate) fn apply(
+    processors: &[Arc<dyn RowPostProcessor>],
+    names: &[String],
+    sql_id: Option<&str>,
+    rows: &mut [HashMap<String, Value>],
+) -> Result<(), DbError> {
+    if names.is_empty() && processors.is_empty() {
+        return Ok(());
+    }
+    let registered = {
+        let registry = PROCESSORS.read().unwrap();
+        names
+            .iter()
+            .map(|name| {
+                registry.get(name).cloned().ok_or_else(|| {
+                    DbError::Query(format!("row processor '{}' is not registered", name))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    for row in rows.iter_mut() {
+        for processor in processors.iter().chain(&registered) {
+            processor.process(sql_id, row)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "runtime"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_columns() {
+        let split = SplitColumns::new(",", ["tags", "roles"]);
+        let mut rows = vec![HashMap::from([
+            ("tags".to_string(), Value::Str("a, b,,c".into())),
+            ("roles".to_string(), Value::Null),
+            ("name".to_string(), Value::Str("x,y".into())),
+        ])];
+        apply(&[Arc::new(split)], &[], None, &mut rows).unwrap();
+        let tags = ["a", "b", "c"].map(|s| Value::Str(s.into()));
+        assert_eq!(rows[0]["tags"], Value::List(tags.to_vec()));
+        assert_eq!(rows[0]["roles"], Value::Null);
+        assert_eq!(rows[0]["name"], Value::Str("x,y".into()));
+
+        let err = apply(&[], &["missing".to_string()], None, &mut rows).unwrap_err();
+        assert!(
+            err.to_string().contains("'missing' is not registered"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::error::DbError;
+use crate::executor::options::{QueryOptions, query_options, with_timeout};
+use crate::executor::session::{Rendered, Session};
+use crate::mapper_loader::{SqlMapper, find_mapper, template_key};
+use crate::tpl::barrier::RELOAD;
+use crate::tpl::engine::{self, PreparedTemplate};
+use crate::tpl::render_options;
+use crate::udbc::serializer::to_value;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// 预先解析的 Mapper 语句，由 [`Session::prepare`] 创建
+///
+/// 缓存语句定义、模板 AST，以及只含文本与 `#{}` 参数的语句渲染出的 SQL，
+/// 在循环中重复执行同一语句时省去按 ID 查找语句与模板缓存的开销。
+/// Mapper 重载后，下次执行时重新解析。
+///
+/// 语句上的超时、最大行数与参数名匹配方式照常生效；路由、分片、查询结果缓存与变更事件
+/// 不经过此处，需要时使用 [`Mapper`](crate::executor::mapper::Mapper)。
+pub struct PreparedMapperStatement {
+    session: Session,
+    sql_id: String,
+    resolved: RwLock<Arc<Resolved>>,
+}
+
+/// 某一版本 Mapper 中解析出的语句
+struct Resolved {
+    mapper: Arc<SqlMapper>,
+    /// 模板缓存键，见 [`template_key`]
+    stmt_key: String,
+    options: QueryOptions,
+    template: PreparedTemplate,
+    /// 解析时的重载代数
+    generation: u64,
+}
+
+impl Resolved {
+    fn new(session: &Session, sql_id: &str) -> Result<Self, DbError> {
+        let snapshot = RELOAD.read();
+        let pool = session.pool();
+        let mapper = find_mapper(sql_id, pool.r#type())
+            .ok_or_else(|| DbError::Query(format!("SQL ID not found: {}", sql_id)))?;
+        let content = mapper
+            .content
+            .as_deref()
+            .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
+        let stmt_key = template_key(sql_id, mapper.database_type.as_deref());
+        let template = engine::prepare_statement(&stmt_key, content, pool.as_ref());
+        let options = query_options()
+            .merge(&pool.query_options())
+            .merge(&mapper.options);
+        Ok(Self {
+            stmt_key,
+            options,
+            template,
+            generation: snapshot.generation(),
+            mapper,
+        })
+    }
+}
+
+impl PreparedMapperStatement {
+    pub(crate) fn new(session: Session, sql_id: &str) -> Result<Self, DbError> {
+        let resolved = Resolved::new(&session, sql_id)?;
+        Ok(Self {
+            session,
+            sql_id: sql_id.to_string(),
+            resolved: RwLock::new(Arc::new(resolved)),
+        })
+    }
+
+    pub fn sql_id(&self) -> &str {
+        &self.sql_id
+    }
+
+    /// 执行查询语句
+    pub async fn query<R, T>(&self, args: &T) -> Result<Vec<R>, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let (resolved, mut statements) = self.render(args)?;
+        crate::type_registry::check_result::<R>(&self.sql_id, &resolved.mapper)?;
+        let stmt_key = Some(resolved.stmt_key.as_str());
+        let limit = Session::auto_limit(&mut statements, resolved.options.auto_limit);
+        let mut rows = with_timeout(resolved.options.timeout, async {
+            let mut rows = Vec::new();
+            for (sql, params) in statements {
+                rows.extend(self.session.fetch_rendered(stmt_key, sql, params).await?);
+            }
+            Ok(rows)
+        })
+        .await?;
+        Session::cap_rows(stmt_key, &mut rows, limit);
+        if let Some(max_rows) = resolved.options.max_rows {
+            rows.truncate(max_rows);
+        }
+        self.session
+            .post_process(&resolved.mapper.row_processors, stmt_key, &mut rows)?;
+        Session::map_rows_named(stmt_key, rows)
+    }
+
+    /// 执行更新语句，返回影响行数
+    pub async fn execute<T>(&self, args: &T) -> Result<u64, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let (resolved, statements) = self.render(args)?;
+        let stmt_key = Some(resolved.stmt_key.as_str());
+        with_timeout(
+            resolved.options.timeout,
+            self.session.execute_statements(stmt_key, statements),
+        )
+        .await
+    }
+
+    /// 代数为 `generation` 的版本中的语句
+    fn current(&self, generation: u64) -> Result<Arc<Resolved>, DbError> {
+        let resolved = self
+            .resolved
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        if resolved.generation == generation {
+            return Ok(resolved);
+        }
+        let resolved = Arc::new(Resolved::new(&self.session, &self.sql_id)?);
+        *self
+            .resolved
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = resolved.clone();
+        Ok(resolved)
+    }
+
+    /// 渲染语句；列表参数超过
+    /// [`RenderOptions::max_list_params`](crate::tpl::RenderOptions::max_list_params)
+    /// 时切分为多条
+    fn render<T>(&self, args: &T) -> Result<(Arc<Resolved>, Vec<Rendered>), DbError>
+    where
+        T: serde::Serialize,
+    {
+        // 解析与渲染在同一版本内完成
+        let snapshot = RELOAD.read();
+        let resolved = self.current(snapshot.generation())?;
+        let pool = self.session.pool().as_ref();
+        let naming = self
+            .session
+            .param_naming_override()
+            .or(resolved.options.param_naming)
+            .unwrap_or_default();
+        let value = to_value(args);
+        let chunks = match render_options().max_list_params {
+            Some(limit) => {
+                engine::split_template(resolved.template.template(), &value, limit, naming)?
+            }
+            None => None,
+        };
+        let statements = match chunks {
+            Some(chunks) => chunks
+                .iter()
+                .map(|chunk| {
+                    engine::render_prepared(
+                        &resolved.template,
+                        &resolved.stmt_key,
+                        chunk,
+                        pool,
+                        naming,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![engine::render_prepared(
+                &resolved.template,
+                &resolved.stmt_key,
+                &value,
+                pool,
+                naming,
+            )?],
+        };
+        Ok((resolved, statements))
+    }
+}
@@ -0,0 +1,236 @@
+//! 只读会话：在客户端拒绝写语句，供报表等只读接口使用
+//!
+//! [`ReadOnlySession`] 由 [`Session::read_only`] 创建，只提供查询方法，
+//! 渲染出的每条语句在发送前都会检查：开头必须是 `SELECT`、`WITH`、`SHOW`、`EXPLAIN` 等只读关键字，
+//! 且不能包含 `INSERT`、`UPDATE`、`DELETE`、`INTO`（数据修改 CTE、`SELECT ... INTO`、
+//! `SELECT ... FOR UPDATE` 等），否则返回 [`DbError::ReadOnly`]。
+//! 由它开启的事务以 [`Connection::begin_read_only`](crate::udbc::connection::Connection::begin_read_only)
+//! 声明为只读，由数据库再做一层保证。
+
+use crate::error::DbError;
+use crate::executor::export::Format;
+use crate::executor::multi::ResultSets;
+use crate::executor::page::{is_word_byte, mask};
+use crate::executor::session::{Nested, RowBuffer, Session};
+use crate::udbc::connection::ColumnMeta;
+use crate::udbc::value::Value;
+use tokio::io::AsyncWrite;
+
+/// 只读语句允许的开头关键字
+const READ_STATEMENTS: [&str; 8] = [
+    "select", "with", "show", "explain", "describe", "desc", "values", "table",
+];
+
+/// 在语句任意位置出现即视为写操作的关键字
+const WRITE_KEYWORDS: [&str; 4] = ["insert", "update", "delete", "into"];
+
+/// 错误信息中保留的语句长度
+const SUMMARY_LEN: usize = 80;
+
+/// 检查 `sql` 是否只包含只读语句；以 `;` 分隔的多条语句逐条检查
+pub(crate) fn check(sql: &str) -> Result<(), DbError> {
+    let masked = mask(sql);
+    for statement in masked.split(';') {
+        let mut words = words(statement);
+        let Some(first) = words.next() else {
+            continue;
+        };
+        let offending = if READ_STATEMENTS.contains(&first.as_str()) {
+            words.find(|w| WRITE_KEYWORDS.contains(&w.as_str()))
+        } else {
+            Some(first)
+        };
+        if let Some(keyword) = offending {
+            return Err(DbError::ReadOnly(format!(
+                "{} in `{}`",
+                keyword.to_ascii_uppercase(),
+                summary(sql)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 已屏蔽字面量与注释的语句中的单词（小写），按出现顺序
+fn words(masked: &str) -> impl Iterator<Item = String> + '_ {
+    masked
+        .split(|c: char| !c.is_ascii() || !is_word_byte(c as u8))
+        .filter(|w| !w.is_empty())
+        .map(str::to_ascii_lowercase)
+}
+
+/// 截断后的单行语句，用于错误信息
+fn summary(sql: &str) -> String {
+    let line = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(SUMMARY_LEN) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None => line,
+    }
+}
+
+/// 只读会话，由 [`Session::read_only`] 创建
+///
+/// 只提供查询方法；即使模板被误配为写语句，也会在发送前被拒绝。
+/// 与 `Session` 一样可以自由克隆。
+#[derive(Clone)]
+pub struct ReadOnlySession {
+    session: Session,
+}
+
+impl ReadOnlySession {
+    pub(crate) fn new(session: Session) -> Self {
+        Self { session }
+    }
+
+    pub fn database_name(&self) -> &str {
+        self.session.database_name()
+    }
+
+    pub async fn query<R, T>(&self, sql: &str, args: &T) -> Result<Vec<R>, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        self.session.query(sql, args).await
+    }
+
+    /// 见 [`Session::query_named`]
+    pub async fn query_named<R, T>(
+        &self,
+        stmt_id: &str,
+        sql: &str,
+        args: &T,
+    ) -> Result<Vec<R>, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        self.session.query_named(stmt_id, sql, args).await
+    }
+
+    /// 见 [`Session::query_raw`]
+    pub async fn query_raw<R>(&self, sql: &str, params: &[Value]) -> Result<Vec<R>, DbError>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        self.session.query_raw(sql, params).await
+    }
+
+    /// 见 [`Session::query_with_meta`]
+    pub async fn query_with_meta<R, T>(
+        &self,
+        sql: &str,
+        args: &T,
+    ) -> Result<(Vec<ColumnMeta>, Vec<R>), DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        self.session.query_with_meta(sql, args).await
+    }
+
+    /// 见 [`Session::exists`]
+    pub async fn exists<T>(&self, sql: &str, args: &T) -> Result<bool, DbError>
+    where
+        T: serde::Serialize,
+    {
+        self.session.exists(sql, args).await
+    }
+
+    /// 见 [`Session::count`]
+    pub async fn count<T>(&self, sql: &str, args: &T) -> Result<u64, DbError>
+    where
+        T: serde::Serialize,
+    {
+        self.session.count(sql, args).await
+    }
+
+    /// 见 [`Session::query_borrowed`]
+    pub async fn query_borrowed<'a, R, T>(
+        &self,
+        buf: &'a mut RowBuffer,
+        sql: &str,
+        args: &T,
+    ) -> Result<Vec<R>, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::Deserialize<'a>,
+    {
+        self.session.query_borrowed(buf, sql, args).await
+    }
+
+    /// 见 [`Session::query_export`]
+    pub async fn query_export<T, W>(
+        &self,
+        sql: &str,
+        args: &T,
+        format: Format,
+        writer: W,
+    ) -> Result<u64, DbError>
+    where
+        T: serde::Serialize,
+        W: AsyncWrite + Unpin + Send,
+    {
+        self.session.query_export(sql, args, format, writer).await
+    }
+
+    /// 见 [`Session::query_multi`]
+    pub async fn query_multi<T>(&self, statements: &[(&str, T)]) -> Result<ResultSets, DbError>
+    where
+        T: serde::Serialize,
+    {
+        self.session.query_multi(statements).await
+    }
+
+    /// 在只读事务中执行 `fut`，各查询看到同一致性快照
+    ///
+    /// 当前任务已处于该连接池的事务中时直接加入外层事务，语句仍在客户端检查。
+    pub async fn transactional<F, T, E>(&self, fut: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: From<DbError>,
+    {
+        self.session.transactional(Nested::Join, fut).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_read_only() {
+        for sql in [
+            "SELECT * FROM users WHERE name = 'update me'",
+            "  -- delete old rows\n select 1",
+            "WITH t AS (SELECT id FROM users) SELECT * FROM t",
+            "(SELECT 1) UNION (SELECT 2);",
+            "SHOW TABLES",
+            "EXPLAIN SELECT `delete` FROM t",
+            "",
+        ] {
+            assert!(check(sql).is_ok(), "{}", sql);
+        }
+
+        for (sql, keyword) in [
+            ("UPDATE users SET name = ?", "UPDATE"),
+            ("  /* report */ DELETE FROM users", "DELETE"),
+            ("drop table users", "DROP"),
+            ("SELECT id FROM users FOR UPDATE", "UPDATE"),
+            ("SELECT * INTO OUTFILE '/tmp/x' FROM users", "INTO"),
+            (
+                "WITH d AS (DELETE FROM t RETURNING id) SELECT * FROM d",
+                "DELETE",
+            ),
+            ("SELECT 1; TRUNCATE users", "TRUNCATE"),
+        ] {
+            let err = check(sql).unwrap_err();
+            assert!(
+                matches!(&err, DbError::ReadOnly(m) if m.starts_with(keyword)),
+                "{}: {}",
+                sql,
+                err
+            );
+        }
+    }
+}
@@ -1,81 +1,954 @@
 use crate::error::DbError;
+use crate::executor::digest;
+use crate::executor::export::{Format, WriterSink};
+use crate::executor::insert::{self, ColumnMap};
+use crate::executor::instrument::{Outcome, StatementSpan};
+use crate::executor::multi::ResultSets;
+use crate::executor::page;
+use crate::executor::pinned::PinnedSession;
+use crate::executor::postprocess::{self, RowPostProcessor};
+use crate::executor::prepared::PreparedMapperStatement;
+use crate::executor::readonly::{self, ReadOnlySession};
+use crate::mapper_loader::{self, template_key};
+use crate::query_cache;
 use crate::tpl::engine;
+use crate::tpl::{ParamNaming, render_options};
 use crate::transaction::TransactionContext;
-use crate::udbc::deserializer::RowDeserializer;
-use crate::udbc::driver::Driver;
+use crate::udbc::bulk::{Progress, RowStream};
+use crate::udbc::connection::{ColumnMeta, Connection, RawConnection};
+use crate::udbc::deserializer::{RowDeserializer, ValueDeserializer};
+use crate::udbc::driver::{Driver, PacketLimit, payload_size};
 use crate::udbc::value::Value;
+use futures_util::{Stream, StreamExt};
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
+use tokio::io::AsyncWrite;
 use tokio::task_local;
-use log::debug;
+use tracing::Instrument;
+
+pub(crate) use crate::tpl::engine::Rendered;
+
+/// 绑定到当前任务的事务
+#[derive(Clone)]
+struct AmbientTx {
+    /// 事务所在连接池的名称，其他连接池上的语句不加入该事务
+    pool: String,
+    ctx: Arc<tokio::sync::Mutex<TransactionContext>>,
+    /// 事务中写语句待失效的查询缓存键，提交后失效
+    evictions: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+/// 绑定到当前任务的 schema 作用域，见 [`Session::using_schema`]
+#[derive(Clone)]
+struct AmbientSchema {
+    /// 作用域所在连接池的名称
+    pool: String,
+    schema: String,
+    /// 已切换 schema 的连接
+    conn: Arc<dyn Connection>,
+}
+
+/// [`Session::using_schema`] 切换 schema 后未能恢复时丢弃连接
+///
+/// 恢复成功后解除；`fut` 被取消时随 future 一起释放，同样丢弃连接。
+struct SchemaGuard {
+    conn: Arc<dyn Connection>,
+    armed: bool,
+}
+
+impl Drop for SchemaGuard {
+    fn drop(&mut self) {
+        if self.armed {
+            self.conn.discard();
+        }
+    }
+}
 
 task_local! {
     /// 当前任务的事务上下文
-     static TX_CONTEXT: Arc<tokio::sync::Mutex<TransactionContext>>;
+     static TX_CONTEXT: AmbientTx;
+    /// 当前任务的 schema 作用域
+    static SCHEMA_CONTEXT: AmbientSchema;
+}
+
+/// 当前任务在连接池 `pool` 上的事务
+pub(crate) fn ambient_tx(pool: &str) -> Option<Arc<tokio::sync::Mutex<TransactionContext>>> {
+    TX_CONTEXT
+        .try_with(|tx| (tx.pool == pool).then(|| tx.ctx.clone()))
+        .ok()
+        .flatten()
+}
+
+/// 当前任务是否绑定了连接池 `pool` 上的事务
+pub(crate) fn in_transaction(pool: &str) -> bool {
+    ambient_tx(pool).is_some()
+}
+
+/// 当前任务处于连接池 `pool` 的事务中时登记待失效的缓存键，返回是否已登记
+pub(crate) fn defer_evictions(pool: &str, keys: &[String]) -> bool {
+    TX_CONTEXT
+        .try_with(|tx| {
+            if tx.pool != pool {
+                return false;
+            }
+            tx.evictions.lock().unwrap().extend_from_slice(keys);
+            true
+        })
+        .unwrap_or(false)
+}
+
+/// 当前任务在连接池 `pool` 上的 schema 作用域
+fn ambient_schema(pool: &str) -> Option<AmbientSchema> {
+    SCHEMA_CONTEXT
+        .try_with(|scope| (scope.pool == pool).then(|| scope.clone()))
+        .ok()
+        .flatten()
+}
+
+/// 当前任务是否处于连接池 `pool` 上的 schema 作用域中
+pub(crate) fn in_schema_scope(pool: &str) -> bool {
+    ambient_schema(pool).is_some()
+}
+
+/// 从连接池取出连接；处于 [`Session::using_schema`] 作用域中时返回已切换 schema 的连接
+pub(crate) async fn acquire(pool: &dyn Driver) -> Result<Arc<dyn Connection>, DbError> {
+    match ambient_schema(pool.name()) {
+        Some(scope) => Ok(scope.conn),
+        None => pool.connection().await,
+    }
+}
+
+/// 按位置命名参数（`1`、`2`、……），供原样执行的语句绑定
+fn positional(params: &[Value]) -> Vec<(String, Value)> {
+    params
+        .iter()
+        .enumerate()
+        .map(|(i, v)| ((i + 1).to_string(), v.clone()))
+        .collect()
+}
+
+/// 已处于事务中时，嵌套的事务性调用（[`Session::transactional`]）的执行方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Nested {
+    /// 在保存点中执行：失败时只回滚到保存点，错误交由外层处理，外层事务不受影响
+    #[default]
+    Savepoint,
+    /// 直接加入外层事务，由外层决定提交或回滚
+    Join,
+}
+
+/// 查询结果行缓冲区，供 [`Session::query_borrowed`] 借用
+#[derive(Debug, Default)]
+pub struct RowBuffer {
+    rows: Vec<HashMap<String, Value>>,
+}
+
+impl RowBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
 }
 
 /// 数据库客户端，封装了连接池操作
+///
+/// `Session` 只是连接池的句柄，克隆只增加 `Arc` 引用计数，可在请求间自由复制或放入
+/// 框架的共享状态，无需每次从 [`UORM`](crate::driver_manager::UORM) 重新获取。
+/// 句柄不持有连接：连接在每条语句执行时取出、执行完归还，
+/// 事务与 [`Session::pinned`] 持有的连接随其自身释放。
+/// 连接池被关闭（[`Driver::close`]）后，已有句柄上的操作将返回错误。
+#[derive(Clone)]
 pub struct Session {
     pool: Arc<dyn Driver>,
+    param_naming: Option<ParamNaming>,
+    row_processors: Vec<Arc<dyn RowPostProcessor>>,
+    /// 是否在发送前拒绝写语句，见 [`Session::read_only`]
+    read_only: bool,
+    /// 执行 Mapper 语句时查找语句的重载代数，见 [`Session::with_generation`]
+    generation: Option<u64>,
 }
 
 impl Session {
     pub fn new(pool: Arc<dyn Driver>) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            param_naming: None,
+            row_processors: Vec::new(),
+            read_only: false,
+            generation: None,
+        }
+    }
+
+    /// 设置模板参数名的匹配方式，覆盖连接池与全局的
+    /// [`QueryOptions::param_naming`](crate::executor::options::QueryOptions::param_naming)；
+    /// `None` 表示沿用它们的设置
+    pub fn with_param_naming(mut self, naming: Option<ParamNaming>) -> Self {
+        self.param_naming = naming;
+        self
+    }
+
+    /// 按语句 ID 渲染的都是在重载代数 `generation` 时查找的 Mapper 语句，
+    /// 渲染时已发生重载则按当前版本重新查找
+    pub(crate) fn with_generation(mut self, generation: u64) -> Self {
+        self.generation = Some(generation);
+        self
+    }
+
+    /// 添加行后处理器，该会话上的查询结果在反序列化前依次经过，
+    /// 执行于连接池的处理器之后、语句的处理器之前，见 [`crate::executor::postprocess`]
+    pub fn with_row_processor(mut self, processor: Arc<dyn RowPostProcessor>) -> Self {
+        self.row_processors.push(processor);
+        self
+    }
+
+    /// 返回只读会话：只提供查询方法，写语句在发送前被拒绝，开启的事务声明为只读
+    ///
+    /// 用于报表等只读接口，模板被误配为写语句时也不会修改数据，见 [`ReadOnlySession`]。
+    pub fn read_only(&self) -> ReadOnlySession {
+        ReadOnlySession::new(Self {
+            read_only: true,
+            ..self.clone()
+        })
+    }
+
+    /// 所用连接池的名称，即注册到 [`UORM`](crate::driver_manager::UORM) 时的名称
+    pub fn database_name(&self) -> &str {
+        self.pool.name()
+    }
+
+    pub(crate) fn pool(&self) -> &Arc<dyn Driver> {
+        &self.pool
+    }
+
+    /// 预先解析 Mapper 语句 `sql_id`，返回可在循环中重复执行的语句，见 [`PreparedMapperStatement`]
+    pub fn prepare(&self, sql_id: &str) -> Result<PreparedMapperStatement, DbError> {
+        PreparedMapperStatement::new(self.clone(), sql_id)
+    }
+
+    /// 当前任务在该连接池上通过 [`Session::using_schema`] 切换到的 schema
+    pub fn current_schema(&self) -> Option<String> {
+        ambient_schema(self.pool.name()).map(|scope| scope.schema)
+    }
+
+    /// 在 `schema` 下执行 `fut`：取出一个连接切换其默认 schema（如 MySQL 的 `USE`），
+    /// 期间当前任务内对同一连接池的 `Session`/`Mapper` 调用都在该连接上执行，结束后恢复原来的 schema
+    ///
+    /// 当前任务已处于该连接池的事务中时在事务所用的连接上切换；作用域内开启的事务也使用该连接，
+    /// schema 切换与事务始终在同一连接上。作用域内不读取查询结果缓存（缓存键不区分 schema）。
+    ///
+    /// 无法保证恢复时（连接原本未选择 schema、恢复语句失败或 `fut` 被中途取消）丢弃该连接，
+    /// 不让它带着租户的 schema 回到连接池，见 [`Connection::discard`]；恢复失败不影响返回 `fut` 的结果。
+    /// 驱动不支持切换时返回 [`DbError::NotImplemented`]，见 [`Driver::switch_schema_sql`]。
+    pub async fn using_schema<F>(&self, schema: &str, fut: F) -> Result<F::Output, DbError>
+    where
+        F: Future,
+    {
+        let (Some(switch), Some(current)) = (
+            self.pool.switch_schema_sql(schema),
+            self.pool.current_schema_sql(),
+        ) else {
+            return Err(DbError::NotImplemented);
+        };
+        let conn = match ambient_tx(self.pool.name()) {
+            Some(ctx) => ctx.lock().await.raw_connection().connection().clone(),
+            None => acquire(self.pool.as_ref()).await?,
+        };
+        let rows = conn.query(current, &[]).await?;
+        let previous = match rows
+            .into_iter()
+            .next()
+            .and_then(|row| row.into_values().next())
+        {
+            Some(Value::Str(s)) => Some(s),
+            Some(Value::Bytes(b)) => String::from_utf8(b).ok(),
+            _ => None,
+        };
+        let mut guard = SchemaGuard {
+            conn: conn.clone(),
+            armed: true,
+        };
+        conn.execute(&switch, &[]).await?;
+        let scope = AmbientSchema {
+            pool: self.pool.name().to_string(),
+            schema: schema.to_string(),
+            conn: conn.clone(),
+        };
+        let output = SCHEMA_CONTEXT.scope(scope, fut).await;
+        match previous.and_then(|previous| self.pool.switch_schema_sql(&previous)) {
+            Some(restore) => match conn.execute(&restore, &[]).await {
+                Ok(_) => guard.armed = false,
+                Err(e) => log::warn!(
+                    "连接池 '{}' 的连接无法从 schema '{}' 恢复，已丢弃: {}",
+                    self.pool.name(),
+                    schema,
+                    e
+                ),
+            },
+            None => log::warn!(
+                "连接池 '{}' 的连接原本未选择 schema，无法从 '{}' 恢复，已丢弃",
+                self.pool.name(),
+                schema
+            ),
+        }
+        Ok(output)
     }
 
     pub async fn begin(&self) -> Result<TransactionContext, DbError> {
+        if self.read_only {
+            return TransactionContext::begin_read_only(self.pool.clone()).await;
+        }
         TransactionContext::begin(self.pool.clone()).await
     }
 
+    /// 在事务中执行 `fut`：期间当前任务内对同一连接池的 `Session`/`Mapper` 调用都使用该事务
+    ///
+    /// `commit(&output)` 为 true 时提交，否则回滚；提交失败时返回错误。
+    /// 当前任务已处于该连接池的事务中时直接加入，由外层事务决定提交或回滚。
+    pub(crate) async fn run_in_transaction<F, O>(
+        &self,
+        fut: F,
+        commit: impl FnOnce(&O) -> bool,
+    ) -> Result<O, DbError>
+    where
+        F: Future<Output = O>,
+    {
+        if in_transaction(self.pool.name()) {
+            return Ok(fut.await);
+        }
+        let ctx = Arc::new(tokio::sync::Mutex::new(self.begin().await?));
+        let evictions = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let ambient = AmbientTx {
+            pool: self.pool.name().to_string(),
+            ctx: ctx.clone(),
+            evictions: evictions.clone(),
+        };
+        let output = TX_CONTEXT.scope(ambient, fut).await;
+        let mut tx = ctx.lock().await;
+        if commit(&output) {
+            tx.commit().await?;
+            let keys = std::mem::take(&mut *evictions.lock().unwrap());
+            query_cache::evict_keys(keys).await;
+        } else {
+            tx.rollback().await?;
+        }
+        Ok(output)
+    }
+
+    /// 在事务中执行 `fut`，返回 `Ok` 时提交、`Err` 时回滚，供 `#[transactional]` 使用
+    ///
+    /// 当前任务已处于该连接池的事务中时，按 `nested` 在保存点中执行或直接加入外层事务。
+    /// 期间对同一连接池的 `Session`/`Mapper` 调用都使用该事务。
+    pub async fn transactional<F, T, E>(&self, nested: Nested, fut: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: From<DbError>,
+    {
+        let Some(ctx) = ambient_tx(self.pool.name()) else {
+            return self.run_in_transaction(fut, Result::is_ok).await?;
+        };
+        if nested == Nested::Join {
+            return fut.await;
+        }
+        let savepoint = ctx.lock().await.savepoint().await?;
+        let result = fut.await;
+        let mut tx = ctx.lock().await;
+        match &result {
+            Ok(_) => tx.release_savepoint(&savepoint).await?,
+            Err(_) => tx.rollback_to_savepoint(&savepoint).await?,
+        }
+        result
+    }
+
     pub async fn execute<T>(&self, sql: &str, args: &T) -> Result<u64, DbError>
     where
         T: serde::Serialize,
     {
-        if let Ok(ctx) = TX_CONTEXT.try_with(|tx| tx.clone()) {
-            let start = Instant::now();
-            let result = ctx.lock().await.execute(sql, args).await;
-            let elapsed_ms = start.elapsed().as_millis();
-            let affected = result.as_ref().ok().copied();
-            let err = result.as_ref().err().map(|e| e.to_string());
-            debug!("execute: sql={}, elapsed_ms={}, affected={:?}, error={:?}", sql, elapsed_ms, affected, err);
-            result
-        } else {
-            let (rendered_sql, params) =
-                engine::render_template(sql, sql, args, self.pool.as_ref());
-            let conn = self.pool.connection().await?;
-            let start = Instant::now();
-            let result = conn.execute(&rendered_sql, &params).await;
-            let elapsed_ms = start.elapsed().as_millis();
-            let affected = result.as_ref().ok().copied();
-            let err = result.as_ref().err().map(|e| e.to_string());
-            debug!("Preparing query: sql={}, params={:?}, elapsed_ms={}, affected={:?}, error={:?}", rendered_sql, params, elapsed_ms, affected, err);
-            result
+        self.execute_inner(None, sql, args).await
+    }
+
+    /// 以语句 ID 作为模板缓存键执行更新，避免每次对超长 SQL 文本做哈希
+    ///
+    /// 同一 `stmt_id` 对应的 SQL 内容变化时重新解析，交替使用不同内容会使缓存反复失效。
+    pub async fn execute_named<T>(&self, stmt_id: &str, sql: &str, args: &T) -> Result<u64, DbError>
+    where
+        T: serde::Serialize,
+    {
+        self.execute_inner(Some(stmt_id), sql, args).await
+    }
+
+    /// 原样执行 SQL，不经模板引擎处理，参数按位置绑定
+    ///
+    /// 适用于包含 `#{`、`<` 等非模板语法文本的语句（如 JSON 路径字面量），
+    /// 占位符使用驱动自身的语法（MySQL 为 `?`）。
+    pub async fn execute_raw(&self, sql: &str, params: &[Value]) -> Result<u64, DbError> {
+        self.execute_rendered(None, sql.to_string(), positional(params))
+            .await
+    }
+
+    /// 将结构体的字段插入表中，生成 `INSERT INTO table (f1, f2, ...) VALUES (?, ?, ...)`
+    ///
+    /// 列名取自序列化后的字段名，返回影响行数。
+    pub async fn insert_into<T>(&self, table: &str, entity: &T) -> Result<u64, DbError>
+    where
+        T: serde::Serialize,
+    {
+        self.insert_into_with(table, entity, &ColumnMap::default())
+            .await
+    }
+
+    /// 同 [`Session::insert_into`]，按 `columns` 选择与重命名列
+    pub async fn insert_into_with<T>(
+        &self,
+        table: &str,
+        entity: &T,
+        columns: &ColumnMap,
+    ) -> Result<u64, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let (sql, params) = insert::build(self.pool.as_ref(), table, entity, columns)?;
+        self.execute_rendered(None, sql, params).await
+    }
+
+    async fn execute_inner<T>(
+        &self,
+        stmt_id: Option<&str>,
+        sql: &str,
+        args: &T,
+    ) -> Result<u64, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let statements = self.render_split(stmt_id, sql, args)?;
+        self.execute_statements(stmt_id, statements).await
+    }
+
+    /// 依次执行渲染出的更新语句，返回影响行数之和
+    ///
+    /// 列表参数切分出多条语句且不在事务中时，在一个事务中执行，避免只有部分语句生效。
+    pub(crate) async fn execute_statements(
+        &self,
+        stmt_id: Option<&str>,
+        statements: Vec<Rendered>,
+    ) -> Result<u64, DbError> {
+        let split = statements.len() > 1;
+        let run = async {
+            let mut affected = 0;
+            for (rendered_sql, params) in statements {
+                affected += self.execute_rendered(stmt_id, rendered_sql, params).await?;
+            }
+            Ok(affected)
+        };
+        if split {
+            return self.run_in_transaction(run, Result::is_ok).await?;
+        }
+        run.await
+    }
+
+    /// 列表参数超过 [`RenderOptions::max_list_params`](crate::tpl::RenderOptions::max_list_params)
+    /// 时切分出的各条语句；无需切分时返回 `None`
+    fn split<T>(
+        &self,
+        stmt_id: Option<&str>,
+        sql: &str,
+        args: &T,
+    ) -> Result<Option<Vec<Rendered>>, DbError>
+    where
+        T: serde::Serialize,
+    {
+        match render_options().max_list_params {
+            Some(limit) => engine::split_oversized_lists(
+                stmt_id,
+                sql,
+                self.generation,
+                args,
+                limit,
+                self.pool.as_ref(),
+                self.param_naming(),
+            ),
+            None => Ok(None),
+        }
+    }
+
+    /// 渲染语句；列表参数超过 `max_list_params`，或估算的负载超过连接池的
+    /// [`PacketLimit`] 且允许切分时，按列表切分为多条语句
+    fn render_split<T>(
+        &self,
+        stmt_id: Option<&str>,
+        sql: &str,
+        args: &T,
+    ) -> Result<Vec<Rendered>, DbError>
+    where
+        T: serde::Serialize,
+    {
+        if let Some(statements) = self.split(stmt_id, sql, args)? {
+            return Ok(statements);
+        }
+        let rendered = self.render(stmt_id, sql, args)?;
+        match self.pool.packet_limit() {
+            Some(limit) if limit.split_lists && limit.exceeded_by(&rendered.0, &rendered.1) => {
+                self.split_to_fit(stmt_id, sql, args, limit, rendered)
+            }
+            _ => Ok(vec![rendered]),
+        }
+    }
+
+    /// 按列表参数切分，使每条语句的负载不超过 `limit`
+    ///
+    /// 先按负载比例估算每组元素数，仍有超限的组时减半重试；无法切分（没有列表参数，
+    /// 或每组一个元素仍超限）时原样返回，由连接在发送前报错。
+    fn split_to_fit<T>(
+        &self,
+        stmt_id: Option<&str>,
+        sql: &str,
+        args: &T,
+        limit: PacketLimit,
+        rendered: Rendered,
+    ) -> Result<Vec<Rendered>, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let size = payload_size(&rendered.0, &rendered.1);
+        let mut per_chunk = (rendered.1.len() * limit.max_bytes / size).max(1);
+        loop {
+            let Some(statements) = engine::split_oversized_lists(
+                stmt_id,
+                sql,
+                self.generation,
+                args,
+                per_chunk,
+                self.pool.as_ref(),
+                self.param_naming(),
+            )?
+            else {
+                return Ok(vec![rendered]);
+            };
+            if per_chunk == 1
+                || statements
+                    .iter()
+                    .all(|(sql, params)| !limit.exceeded_by(sql, params))
+            {
+                return Ok(statements);
+            }
+            per_chunk /= 2;
+        }
+    }
+
+    pub(crate) async fn execute_rendered(
+        &self,
+        stmt_id: Option<&str>,
+        rendered_sql: String,
+        params: Vec<(String, Value)>,
+    ) -> Result<u64, DbError> {
+        let stmt = StatementSpan::new("execute", self.pool.name(), stmt_id);
+        let start = Instant::now();
+        let result = async {
+            if let Some(ctx) = ambient_tx(self.pool.name()) {
+                ctx.lock()
+                    .await
+                    .execute_rendered(&rendered_sql, &params)
+                    .await
+            } else {
+                let conn = acquire(self.pool.as_ref()).await?;
+                conn.execute(&rendered_sql, &params).await
+            }
         }
+        .instrument(stmt.span())
+        .await;
+        let elapsed = start.elapsed();
+        let fingerprint = digest::record_named(stmt_id, &rendered_sql, elapsed, result.is_ok());
+        stmt.finish(Outcome {
+            fingerprint: Some(&fingerprint),
+            sql: &rendered_sql,
+            params: &params,
+            elapsed,
+            rows: result.as_ref().ok().copied(),
+            error: result.as_ref().err(),
+        });
+        result
     }
 
+    /// 执行查询
+    ///
+    /// 事务外的只读查询因连接中断失败时（如数据库故障切换后连接池中残留的失效连接），
+    /// 自动换用新连接重试一次，见 [`DbError::is_connection_lost`]。
     pub async fn query<R, T>(&self, sql: &str, args: &T) -> Result<Vec<R>, DbError>
     where
         T: serde::Serialize,
         R: serde::de::DeserializeOwned,
     {
-        if let Ok(ctx) = TX_CONTEXT.try_with(|tx| tx.clone()) {
-            let start = Instant::now();
-            let rows = ctx.lock().await.query(sql, args).await?;
-            let elapsed_ms = start.elapsed().as_millis();
-            debug!("query: sql={}, elapsed_ms={}, rows={}", sql, elapsed_ms, rows.len());
-            Self::map_rows(rows)
-        } else {
-            let (rendered_sql, params) =
-                engine::render_template(sql, sql, args, self.pool.as_ref());
-            let conn = self.pool.connection().await?;
-            let start = Instant::now();
-            let rows = conn.query(&rendered_sql, &params).await?;
-            let elapsed_ms = start.elapsed().as_millis();
-            debug!("Preparing query: sql={}, params={:?}, elapsed_ms={}, rows={}", rendered_sql, params, elapsed_ms, rows.len());
-            Self::map_rows(rows)
+        self.query_inner(None, sql, args).await
+    }
+
+    /// 以语句 ID 作为模板缓存键执行查询，避免每次对超长 SQL 文本做哈希
+    ///
+    /// 同一 `stmt_id` 对应的 SQL 内容变化时重新解析，交替使用不同内容会使缓存反复失效。
+    pub async fn query_named<R, T>(
+        &self,
+        stmt_id: &str,
+        sql: &str,
+        args: &T,
+    ) -> Result<Vec<R>, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        self.query_inner(Some(stmt_id), sql, args).await
+    }
+
+    /// 原样执行查询，不经模板引擎处理，参数按位置绑定，见 [`Session::execute_raw`]
+    pub async fn query_raw<R>(&self, sql: &str, params: &[Value]) -> Result<Vec<R>, DbError>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        let mut rows = self
+            .fetch_rendered(None, sql.to_string(), positional(params))
+            .await?;
+        self.post_process(&[], None, &mut rows)?;
+        Self::map_rows(rows)
+    }
+
+    /// 查询并返回结果列的元数据（列名、数据库类型名、可空、长度），列按结果集中的顺序排列
+    ///
+    /// 供通用的表格/报表界面或代码生成推断类型使用；驱动不支持时返回 [`DbError::NotImplemented`]。
+    pub async fn query_with_meta<R, T>(
+        &self,
+        sql: &str,
+        args: &T,
+    ) -> Result<(Vec<ColumnMeta>, Vec<R>), DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let (rendered_sql, params) = self.render(None, sql, args)?;
+        self.check_read_only(&rendered_sql)?;
+        let stmt = StatementSpan::new("query", self.pool.name(), None);
+        let start = Instant::now();
+        let result = async {
+            if let Some(ctx) = ambient_tx(self.pool.name()) {
+                ctx.lock()
+                    .await
+                    .query_meta_rendered(&rendered_sql, &params)
+                    .await
+            } else {
+                let conn = acquire(self.pool.as_ref()).await?;
+                conn.query_with_meta(&rendered_sql, &params).await
+            }
         }
+        .instrument(stmt.span())
+        .await;
+        let elapsed = start.elapsed();
+        let fingerprint = digest::record_named(None, &rendered_sql, elapsed, result.is_ok());
+        stmt.finish(Outcome {
+            fingerprint: Some(&fingerprint),
+            sql: &rendered_sql,
+            params: &params,
+            elapsed,
+            rows: result.as_ref().ok().map(|(_, rows)| rows.len() as u64),
+            error: result.as_ref().err(),
+        });
+        let (columns, mut rows) = result?;
+        self.post_process(&[], None, &mut rows)?;
+        Ok((columns, Self::map_rows(rows)?))
+    }
+
+    /// 查询是否返回了行，如 `SELECT 1 FROM users WHERE email = #{email} LIMIT 1`
+    ///
+    /// 只判断行是否存在、不读取列值；语句应自行限制返回行数。
+    pub async fn exists<T>(&self, sql: &str, args: &T) -> Result<bool, DbError>
+    where
+        T: serde::Serialize,
+    {
+        Ok(!self.fetch_rows(None, sql, args).await?.is_empty())
+    }
+
+    /// 执行返回单个计数的查询，如 `SELECT COUNT(*) FROM users WHERE status = #{status}`
+    ///
+    /// 结果必须恰好一行一列，列值可以是整数、DECIMAL 或数字字符串。
+    pub async fn count<T>(&self, sql: &str, args: &T) -> Result<u64, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let rows = self.fetch_rows(None, sql, args).await?;
+        Self::count_value(&rows)
+    }
+
+    /// 从计数查询的结果中取出计数：必须恰好一行一列
+    pub(crate) fn count_value(rows: &[HashMap<String, Value>]) -> Result<u64, DbError> {
+        let value = match rows {
+            [row] if row.len() == 1 => row.values().next().unwrap(),
+            [row] => {
+                return Err(DbError::Query(format!(
+                    "count expects one column, got {}",
+                    row.len()
+                )));
+            }
+            _ => {
+                return Err(DbError::Query(format!(
+                    "count expects one row, got {}",
+                    rows.len()
+                )));
+            }
+        };
+        let count = match value {
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => d.normalize().to_string().parse().ok(),
+            Value::Str(s) => s.trim().parse().ok(),
+            other => u64::deserialize(ValueDeserializer { value: other }).ok(),
+        };
+        count.ok_or_else(|| DbError::Query(format!("count returned a non-count value {:?}", value)))
+    }
+
+    /// 查询并以借用方式反序列化结果
+    ///
+    /// 行数据保存在调用方提供的 `buf` 中（原有内容会被替换），
+    /// 结果中的 `&str`/`&[u8]` 字段直接引用缓冲区，避免逐字段分配 String。
+    pub async fn query_borrowed<'a, R, T>(
+        &self,
+        buf: &'a mut RowBuffer,
+        sql: &str,
+        args: &T,
+    ) -> Result<Vec<R>, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::Deserialize<'a>,
+    {
+        buf.rows = self.fetch_rows(None, sql, args).await?;
+        self.post_process(&[], None, &mut buf.rows)?;
+        let rows: &'a [HashMap<String, Value>] = &buf.rows;
+        rows.iter()
+            .map(|r| R::deserialize(RowDeserializer::new(r)))
+            .collect()
+    }
+
+    async fn query_inner<R, T>(
+        &self,
+        stmt_id: Option<&str>,
+        sql: &str,
+        args: &T,
+    ) -> Result<Vec<R>, DbError>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let mut rows = self.fetch_rows(stmt_id, sql, args).await?;
+        self.post_process(&[], stmt_id, &mut rows)?;
+        Self::map_rows_named(stmt_id, rows)
+    }
+
+    /// 连接池与会话上的行后处理器，按执行顺序排列
+    fn row_processors(&self) -> Vec<Arc<dyn RowPostProcessor>> {
+        self.pool
+            .row_processors()
+            .iter()
+            .chain(&self.row_processors)
+            .cloned()
+            .collect()
+    }
+
+    /// 以连接池、会话上的行后处理器与语句声明的处理器 `names` 依次改写各行
+    pub(crate) fn post_process(
+        &self,
+        names: &[String],
+        stmt_id: Option<&str>,
+        rows: &mut [HashMap<String, Value>],
+    ) -> Result<(), DbError> {
+        postprocess::apply(
+            &self.row_processors(),
+            names,
+            stmt_id.map(digest::logical_id),
+            rows,
+        )
+    }
+
+    /// 将行数据映射为目标类型，映射错误中补充语句 ID
+    pub(crate) fn map_rows_named<R>(
+        stmt_id: Option<&str>,
+        rows: Vec<HashMap<String, Value>>,
+    ) -> Result<Vec<R>, DbError>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        Self::map_rows(rows).map_err(|e| match stmt_id {
+            // 缓存键可能带有 `@databaseType` 后缀
+            Some(id) => e.with_sql_id(digest::logical_id(id)),
+            None => e,
+        })
+    }
+
+    /// 渲染并执行查询，返回原始行数据
+    ///
+    /// 列表参数超限被切分时，各条语句的结果按顺序拼接。
+    pub(crate) async fn fetch_rows<T>(
+        &self,
+        stmt_id: Option<&str>,
+        sql: &str,
+        args: &T,
+    ) -> Result<Vec<HashMap<String, Value>>, DbError>
+    where
+        T: serde::Serialize,
+    {
+        self.fetch_rows_limited(stmt_id, sql, args, None).await
+    }
+
+    /// 同 [`Session::fetch_rows`]，并按 `auto_limit` 为没有 `LIMIT` 的查询追加上限，
+    /// 见 [`QueryOptions::auto_limit`](crate::executor::options::QueryOptions::auto_limit)
+    pub(crate) async fn fetch_rows_limited<T>(
+        &self,
+        stmt_id: Option<&str>,
+        sql: &str,
+        args: &T,
+        auto_limit: Option<usize>,
+    ) -> Result<Vec<HashMap<String, Value>>, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let mut statements = self.render_split(stmt_id, sql, args)?;
+        let limit = Self::auto_limit(&mut statements, auto_limit);
+        let mut rows = Vec::new();
+        for (rendered_sql, params) in statements {
+            rows.extend(self.fetch_rendered(stmt_id, rendered_sql, params).await?);
+        }
+        Self::cap_rows(stmt_id, &mut rows, limit);
+        Ok(rows)
+    }
+
+    /// 为没有 `LIMIT` 的查询追加 `LIMIT limit`，返回实际追加时的上限；`0` 表示关闭
+    pub(crate) fn auto_limit(statements: &mut [Rendered], limit: Option<usize>) -> Option<usize> {
+        let limit = limit.filter(|&limit| limit > 0)?;
+        let mut applied = false;
+        for (sql, _) in statements.iter_mut() {
+            if let Some(limited) = page::limit_sql(sql, limit) {
+                *sql = limited;
+                applied = true;
+            }
+        }
+        applied.then_some(limit)
+    }
+
+    /// 追加了 `LIMIT` 的查询达到上限时记录警告；列表参数切分出的多条语句合计不超过上限
+    pub(crate) fn cap_rows(
+        stmt_id: Option<&str>,
+        rows: &mut Vec<HashMap<String, Value>>,
+        limit: Option<usize>,
+    ) {
+        if let Some(limit) = limit
+            && rows.len() >= limit
+        {
+            rows.truncate(limit);
+            log::warn!(
+                "语句 '{}' 没有 LIMIT，结果已按 autoLimit 限制为 {} 行",
+                stmt_id.map(digest::logical_id).unwrap_or("<sql>"),
+                limit
+            );
+        }
+    }
+
+    pub(crate) async fn fetch_rendered(
+        &self,
+        stmt_id: Option<&str>,
+        rendered_sql: String,
+        params: Vec<(String, Value)>,
+    ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+        self.check_read_only(&rendered_sql)?;
+        let stmt = StatementSpan::new("query", self.pool.name(), stmt_id);
+        let start = Instant::now();
+        let result = async {
+            if let Some(ctx) = ambient_tx(self.pool.name()) {
+                ctx.lock()
+                    .await
+                    .query_rendered(&rendered_sql, &params)
+                    .await
+            } else {
+                let conn = acquire(self.pool.as_ref()).await?;
+                match conn.query(&rendered_sql, &params).await {
+                    Err(e) if self.can_replay(&rendered_sql, &e) => {
+                        drop(conn);
+                        log::warn!(
+                            "连接池 '{}' 的连接在查询中断开，换用新连接重试一次: {}",
+                            self.pool.name(),
+                            e
+                        );
+                        self.pool
+                            .connection()
+                            .await?
+                            .query(&rendered_sql, &params)
+                            .await
+                    }
+                    result => result,
+                }
+            }
+        }
+        .instrument(stmt.span())
+        .await;
+        let elapsed = start.elapsed();
+        let fingerprint = digest::record_named(stmt_id, &rendered_sql, elapsed, result.is_ok());
+        stmt.finish(Outcome {
+            fingerprint: Some(&fingerprint),
+            sql: &rendered_sql,
+            params: &params,
+            elapsed,
+            rows: result.as_ref().ok().map(|r| r.len() as u64),
+            error: result.as_ref().err(),
+        });
+        result
+    }
+
+    /// 事务外的只读查询因连接中断失败时可在新连接上重放一次
+    ///
+    /// schema 作用域固定在一条连接上，换连接会丢失已切换的 schema，不重放。
+    fn can_replay(&self, rendered_sql: &str, error: &DbError) -> bool {
+        error.is_connection_lost()
+            && !in_schema_scope(self.pool.name())
+            && readonly::check(rendered_sql).is_ok()
+    }
+
+    /// 只读会话中拒绝写语句
+    fn check_read_only(&self, rendered_sql: &str) -> Result<(), DbError> {
+        if self.read_only {
+            readonly::check(rendered_sql)?;
+        }
+        Ok(())
+    }
+
+    /// 渲染模板：有语句 ID 时按 ID 命中缓存，否则以 SQL 文本本身为键
+    pub(crate) fn render<T>(
+        &self,
+        stmt_id: Option<&str>,
+        sql: &str,
+        args: &T,
+    ) -> Result<Rendered, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let naming = self.param_naming();
+        let pool = self.pool.as_ref();
+        match (stmt_id, self.generation) {
+            (Some(id), Some(generation)) => {
+                engine::render_mapper_statement(id, sql, generation, args, pool, naming)
+            }
+            (Some(id), None) => engine::render_statement_with(id, sql, args, pool, naming),
+            (None, _) => engine::render_template_with(sql, sql, args, pool, naming),
+        }
+    }
+
+    fn param_naming(&self) -> ParamNaming {
+        self.param_naming
+            .unwrap_or_else(|| engine::default_naming(self.pool.as_ref()))
+    }
+
+    /// 通过 [`Session::with_param_naming`] 设置的参数名匹配方式
+    pub(crate) fn param_naming_override(&self) -> Option<ParamNaming> {
+        self.param_naming
     }
 
     /// 将行数据映射为目标类型
@@ -84,18 +957,182 @@ impl Session {
         R: serde::de::DeserializeOwned,
     {
         rows.into_iter()
-            .map(|r| {
-                R::deserialize(RowDeserializer::new(&r))
-                    .map_err(|e| DbError::General(e.to_string()))
-            })
+            .map(|r| R::deserialize(RowDeserializer::new(&r)))
             .collect()
     }
 
+    /// 批量导入行数据，返回导入的行数
+    ///
+    /// MySQL 上使用 `LOAD DATA LOCAL INFILE`（需要服务端开启 `local_infile`），
+    /// 数据以流的方式发送，不会整体缓存在内存中。
+    pub async fn bulk_load<S>(&self, table: &str, columns: &[&str], rows: S) -> Result<u64, DbError>
+    where
+        S: Stream<Item = Vec<Value>> + Send + 'static,
+    {
+        self.bulk_load_inner(table, columns, rows.boxed(), None)
+            .await
+    }
+
+    /// 批量导入行数据，每发送一个数据块以累计行数调用 `progress`
+    pub async fn bulk_load_with_progress<S, F>(
+        &self,
+        table: &str,
+        columns: &[&str],
+        rows: S,
+        progress: F,
+    ) -> Result<u64, DbError>
+    where
+        S: Stream<Item = Vec<Value>> + Send + 'static,
+        F: Fn(u64) + Send + Sync + 'static,
+    {
+        self.bulk_load_inner(table, columns, rows.boxed(), Some(Arc::new(progress)))
+            .await
+    }
+
+    async fn bulk_load_inner(
+        &self,
+        table: &str,
+        columns: &[&str],
+        rows: RowStream,
+        progress: Option<Progress>,
+    ) -> Result<u64, DbError> {
+        let columns: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+        let raw = self.raw_connection().await?;
+        let stmt = StatementSpan::new("bulk_load", self.pool.name(), None);
+        let start = Instant::now();
+        let result = raw
+            .connection()
+            .bulk_load(table, &columns, rows, progress)
+            .instrument(stmt.span())
+            .await;
+        stmt.finish(Outcome {
+            fingerprint: None,
+            sql: &format!("bulk load into {} ({})", table, columns.join(", ")),
+            params: &[],
+            elapsed: start.elapsed(),
+            rows: result.as_ref().ok().copied(),
+            error: result.as_ref().err(),
+        });
+        result
+    }
+
+    /// 执行查询并将结果按 `format` 写入 `writer`，返回导出的行数
+    ///
+    /// 驱动支持时逐行读取并写出，结果集不会整体缓存在内存中。
+    pub async fn query_export<T, W>(
+        &self,
+        sql: &str,
+        args: &T,
+        format: Format,
+        writer: W,
+    ) -> Result<u64, DbError>
+    where
+        T: serde::Serialize,
+        W: AsyncWrite + Unpin + Send,
+    {
+        let (rendered_sql, params) = self.render(None, sql, args)?;
+        self.check_read_only(&rendered_sql)?;
+        let raw = self.raw_connection().await?;
+        let mut sink = WriterSink::new(writer, format);
+        let stmt = StatementSpan::new("export", self.pool.name(), None);
+        let start = Instant::now();
+        let result = async {
+            let rows = raw
+                .connection()
+                .query_to(&rendered_sql, &params, &mut sink)
+                .await?;
+            sink.finish().await.map(|_| rows)
+        }
+        .instrument(stmt.span())
+        .await;
+        let elapsed = start.elapsed();
+        let fingerprint = digest::record(&rendered_sql, elapsed, result.is_ok());
+        stmt.finish(Outcome {
+            fingerprint: Some(&fingerprint),
+            sql: &rendered_sql,
+            params: &params,
+            elapsed,
+            rows: result.as_ref().ok().copied(),
+            error: result.as_ref().err(),
+        });
+        result
+    }
+
+    /// 在同一连接上依次执行多个 mapper 查询语句，一并返回各自的结果集
+    ///
+    /// 只占用一次连接池的连接，省去逐条取连接的开销；语句按顺序执行，
+    /// 任一语句失败即返回错误。结果通过 [`ResultSets::next_set`] 按顺序取出。
+    pub async fn query_multi<T>(&self, statements: &[(&str, T)]) -> Result<ResultSets, DbError>
+    where
+        T: serde::Serialize,
+    {
+        let mut rendered = Vec::with_capacity(statements.len());
+        for (sql_id, args) in statements {
+            // 查找与渲染在同一视图内完成
+            let snapshot = mapper_loader::snapshot();
+            let mapper = snapshot
+                .find_mapper(sql_id, self.pool.r#type())
+                .ok_or_else(|| DbError::Query(format!("SQL ID not found: {}", sql_id)))?;
+            let sql = mapper
+                .content
+                .as_deref()
+                .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
+            let stmt_key = template_key(sql_id, mapper.database_type.as_deref());
+            let (rendered_sql, params) = self.render(Some(&stmt_key), sql, args)?;
+            self.check_read_only(&rendered_sql)?;
+            rendered.push((*sql_id, rendered_sql, params));
+        }
+
+        let conn = acquire(self.pool.as_ref()).await?;
+        let mut sets = ResultSets::default();
+        for (sql_id, rendered_sql, params) in rendered {
+            let stmt = StatementSpan::new("query_multi", self.pool.name(), Some(sql_id));
+            let start = Instant::now();
+            let result = conn
+                .query(&rendered_sql, &params)
+                .instrument(stmt.span())
+                .await;
+            let elapsed = start.elapsed();
+            let fingerprint =
+                digest::record_named(Some(sql_id), &rendered_sql, elapsed, result.is_ok());
+            stmt.finish(Outcome {
+                fingerprint: Some(&fingerprint),
+                sql: &rendered_sql,
+                params: &params,
+                elapsed,
+                rows: result.as_ref().ok().map(|r| r.len() as u64),
+                error: result.as_ref().err(),
+            });
+            sets.push(sql_id, result?);
+        }
+        Ok(sets)
+    }
+
+    /// 从连接池取出一个连接，之后的语句都在该连接上执行（不开启事务）
+    pub async fn pinned(&self) -> Result<PinnedSession, DbError> {
+        Ok(PinnedSession::new(
+            acquire(self.pool.as_ref()).await?,
+            self.pool.clone(),
+            self.row_processors(),
+        ))
+    }
+
+    /// 获取底层驱动连接的句柄
+    ///
+    /// 处于事务中时返回事务所用的连接，否则从连接池取出一个连接，句柄释放后归还。
+    pub async fn raw_connection(&self) -> Result<RawConnection, DbError> {
+        if let Some(ctx) = ambient_tx(self.pool.name()) {
+            Ok(ctx.lock().await.raw_connection())
+        } else {
+            Ok(RawConnection::new(acquire(self.pool.as_ref()).await?))
+        }
+    }
+
     pub async fn last_insert_id(&self) -> Result<u64, DbError> {
-        if let Ok(ctx) = TX_CONTEXT.try_with(|tx| tx.clone()) {
+        if let Some(ctx) = ambient_tx(self.pool.name()) {
             ctx.lock().await.last_insert_id().await
         } else {
-            let conn = self.pool.connection().await?;
+            let conn = acquire(self.pool.as_ref()).await?;
             conn.last_insert_id().await
         }
     }
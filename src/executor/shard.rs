@@ -0,0 +1,125 @@
+//! 按参数取值选择连接池的水平分片
+//!
+//! 声明了 `shardedBy="user_id"` 的语句执行时，取参数中 `user_id` 的值交给
+//! [`ShardResolver`]，由其返回应使用的连接池名（如 `user_0` ~ `user_15`），
+//! 连接池需事先通过 [`crate::driver_manager::UORM`] 注册。
+//!
+//! ```ignore
+//! shard::set_shard_resolver(Arc::new(ModuloShards::new("user_", 16)));
+//! ```
+
+use crate::error::DbError;
+use crate::executor::digest::fnv1a;
+use crate::mapper_loader::SqlMapper;
+use crate::tpl::render_context::Context;
+use crate::udbc::serializer::to_value;
+use crate::udbc::value::Value;
+use serde::Serialize;
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// 分片解析器：由分片键的取值决定语句在哪个连接池上执行
+pub trait ShardResolver: Send + Sync {
+    /// 返回连接池名；无法确定分片时返回 `None`，语句以错误结束
+    fn resolve(&self, sql_id: &str, value: &Value) -> Option<String>;
+}
+
+static RESOLVER: LazyLock<RwLock<Option<Arc<dyn ShardResolver>>>> =
+    LazyLock::new(|| RwLock::new(None));
+
+/// 设置全局分片解析器
+pub fn set_shard_resolver(resolver: Arc<dyn ShardResolver>) {
+    *RESOLVER.write().unwrap() = Some(resolver);
+}
+
+/// 移除全局分片解析器，之后执行声明了 `shardedBy` 的语句将报错
+pub fn clear_shard_resolver() {
+    *RESOLVER.write().unwrap() = None;
+}
+
+/// 按取值取模选择 `{prefix}{n}`，`n` 取 `0..shards`
+///
+/// 整数取其对 `shards` 的非负余数；字符串若能解析为整数按整数处理，
+/// 否则取其 FNV-1a 哈希的余数，保证跨进程稳定。其余类型无法分片。
+pub struct ModuloShards {
+    prefix: String,
+    shards: u64,
+}
+
+impl ModuloShards {
+    pub fn new(prefix: impl Into<String>, shards: u64) -> Self {
+        assert!(shards > 0, "shards must be positive");
+        Self {
+            prefix: prefix.into(),
+            shards,
+        }
+    }
+
+    fn index(&self, value: &Value) -> Option<u64> {
+        let signed = |n: i64| n.rem_euclid(self.shards as i64) as u64;
+        match value {
+            Value::I16(n) => Some(signed(*n as i64)),
+            Value::I32(n) => Some(signed(*n as i64)),
+            Value::I64(n) => Some(signed(*n)),
+            Value::U8(n) => Some(*n as u64 % self.shards),
+            Value::Str(s) => Some(match s.trim().parse::<i64>() {
+                Ok(n) => signed(n),
+                Err(_) => fnv1a(s) % self.shards,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl ShardResolver for ModuloShards {
+    fn resolve(&self, _sql_id: &str, value: &Value) -> Option<String> {
+        self.index(value).map(|n| format!("{}{}", self.prefix, n))
+    }
+}
+
+/// 语句声明了 `shardedBy` 时，返回参数取值对应的连接池名
+pub(crate) fn route<T: Serialize>(
+    sql_id: &str,
+    mapper: &SqlMapper,
+    args: &T,
+) -> Result<Option<String>, DbError> {
+    let Some(key) = mapper.sharded_by.as_deref() else {
+        return Ok(None);
+    };
+    let resolver = RESOLVER.read().unwrap().clone().ok_or_else(|| {
+        DbError::Query(format!(
+            "{} is sharded by '{}' but no ShardResolver is set",
+            sql_id, key
+        ))
+    })?;
+    let args = to_value(args);
+    let value = Context::new(&args).lookup(key);
+    if matches!(value, Value::Null) {
+        return Err(DbError::Query(format!(
+            "{}: shard key '{}' is not bound",
+            sql_id, key
+        )));
+    }
+    resolver
+        .resolve(sql_id, value)
+        .map(Some)
+        .ok_or_else(|| DbError::Query(format!("{}: no shard for {} = {:?}", sql_id, key, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modulo_shards() {
+        let shards = ModuloShards::new("user_", 16);
+        let resolve = |v: Value| shards.resolve("user.get", &v);
+        assert_eq!(resolve(Value::I64(35)).as_deref(), Some("user_3"));
+        assert_eq!(resolve(Value::I32(-1)).as_deref(), Some("user_15"));
+        assert_eq!(resolve(Value::Str("35".into())).as_deref(), Some("user_3"));
+        assert_eq!(
+            resolve(Value::Str("alice".into())),
+            resolve(Value::Str("alice".into()))
+        );
+        assert_eq!(resolve(Value::Bool(true)), None);
+    }
+}
@@ -0,0 +1,34 @@
+//! 任务级链路 ID
+//!
+//! 在 [`with_trace_id`] 包裹的异步调用内渲染的语句，开启
+//! [`RenderOptions::sql_comment`](crate::tpl::RenderOptions::sql_comment) 后会在 SQL 前附加
+//! `trace=<id>`，便于从数据库慢日志关联到应用的调用链路。
+
+use std::future::Future;
+
+tokio::task_local! {
+    static TRACE_ID: String;
+}
+
+/// 在设置了链路 ID 的任务上下文中执行 `f`
+pub async fn with_trace_id<F: Future>(trace_id: impl Into<String>, f: F) -> F::Output {
+    TRACE_ID.scope(trace_id.into(), f).await
+}
+
+/// 当前任务的链路 ID
+pub fn current_trace_id() -> Option<String> {
+    TRACE_ID.try_with(Clone::clone).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_trace_id_scope() {
+        assert_eq!(current_trace_id(), None);
+        let inner = with_trace_id("abc123", async { current_trace_id() }).await;
+        assert_eq!(inner.as_deref(), Some("abc123"));
+        assert_eq!(current_trace_id(), None);
+    }
+}
@@ -0,0 +1,142 @@
+//! 健康检查：汇总连接池、Mapper 与模板缓存状态，供服务的 `/health` 接口直接输出
+//!
+//! [`report`] 对 [`UORM`] 中注册的每个连接池取一个连接执行 `SELECT 1`，记录耗时与排队情况，
+//! 并附上已加载 Mapper 的指纹与模板缓存统计。结果可直接序列化为 JSON：
+//!
+//! ```ignore
+//! async fn health() -> (StatusCode, Json<HealthReport>) {
+//!     let report = uorm::health::report().await;
+//!     let code = if report.is_up() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+//!     (code, Json(report))
+//! }
+//! ```
+
+use crate::driver_manager::{DriverManager, UORM};
+use crate::mapper_loader::{self, MapperFingerprint};
+use crate::tpl::{CacheStats, cache_stats};
+use crate::udbc::driver::{Driver, QueueMetrics};
+use futures_util::future::join_all;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 探活语句
+const PING_SQL: &str = "SELECT 1";
+
+/// 健康状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Up,
+    /// 部分连接池不可用
+    Degraded,
+    Down,
+}
+
+/// [`report_with`] 的选项
+#[derive(Debug, Clone)]
+pub struct HealthOptions {
+    /// 单个连接池取连接与探活的总超时
+    pub ping_timeout: Duration,
+}
+
+impl Default for HealthOptions {
+    fn default() -> Self {
+        Self {
+            ping_timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+/// 健康检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// 所有连接池可用时为 `up`，全部不可用时为 `down`，否则为 `degraded`；
+    /// 没有注册连接池时为 `up`
+    pub status: Status,
+    /// 按名称排序的各连接池状态
+    pub pools: Vec<PoolHealth>,
+    pub mappers: MapperFingerprint,
+    pub template_cache: CacheStats,
+}
+
+impl HealthReport {
+    /// 所有连接池均可用
+    pub fn is_up(&self) -> bool {
+        self.status == Status::Up
+    }
+}
+
+/// 单个连接池的状态
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolHealth {
+    /// 注册名
+    pub name: String,
+    /// 数据库类型，如 `mysql`
+    pub r#type: String,
+    pub status: Status,
+    /// 取连接并执行探活语句的耗时（毫秒）；超时时为 `None`
+    pub latency_ms: Option<f64>,
+    /// 探活失败或超时的原因
+    pub error: Option<String>,
+    /// 语句排队情况，未限制并发时为 `None`
+    pub queue: Option<QueueMetrics>,
+}
+
+/// 按默认选项检查 [`UORM`] 中注册的连接池
+pub async fn report() -> HealthReport {
+    report_with(&UORM, &HealthOptions::default()).await
+}
+
+/// 检查 `manager` 中注册的连接池；各连接池并发探活
+pub async fn report_with(manager: &DriverManager, options: &HealthOptions) -> HealthReport {
+    let pools = join_all(
+        manager
+            .drivers()
+            .into_iter()
+            .map(|(name, driver)| check_pool(name, driver, options.ping_timeout)),
+    )
+    .await;
+    let up = pools.iter().filter(|p| p.status == Status::Up).count();
+    let status = if up == pools.len() {
+        Status::Up
+    } else if up == 0 {
+        Status::Down
+    } else {
+        Status::Degraded
+    };
+    HealthReport {
+        status,
+        pools,
+        mappers: mapper_loader::fingerprint(),
+        template_cache: cache_stats(),
+    }
+}
+
+async fn check_pool(name: String, driver: Arc<dyn Driver>, timeout: Duration) -> PoolHealth {
+    let started = Instant::now();
+    let ping = async {
+        let conn = driver.connection().await?;
+        conn.query(PING_SQL, &[]).await
+    };
+    let (latency_ms, error) = match tokio::time::timeout(timeout, ping).await {
+        Ok(Ok(_)) => (Some(started.elapsed().as_secs_f64() * 1000.0), None),
+        Ok(Err(e)) => (
+            Some(started.elapsed().as_secs_f64() * 1000.0),
+            Some(e.to_string()),
+        ),
+        Err(_) => (None, Some(format!("ping timed out after {:?}", timeout))),
+    };
+    PoolHealth {
+        name,
+        r#type: driver.r#type().to_string(),
+        status: if error.is_none() {
+            Status::Up
+        } else {
+            Status::Down
+        },
+        latency_ms,
+        error,
+        queue: driver.queue_metrics(),
+    }
+}
@@ -1,13 +1,41 @@
+#[cfg(feature = "chrono")]
+pub mod bench_fixtures;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "runtime")]
+pub mod capture;
+pub mod diff;
+#[cfg(feature = "runtime")]
 pub mod driver_manager;
 pub mod error;
+#[cfg(feature = "runtime")]
+pub mod events;
 pub mod executor;
+#[cfg(feature = "runtime")]
+pub mod health;
 pub mod mapper_loader;
-pub(crate) mod tpl;
+#[cfg(all(feature = "runtime", feature = "xml"))]
+pub mod mapper_source;
+#[cfg(feature = "runtime")]
+pub mod planguard;
+pub mod prelude;
+#[cfg(feature = "runtime")]
+pub mod query_cache;
+pub mod tpl;
+#[cfg(feature = "runtime")]
 pub mod transaction;
+pub mod type_registry;
 pub mod udbc;
 #[cfg(feature = "mysql")]
 pub mod udbc_mysql;
+#[cfg(any(feature = "axum", feature = "actix"))]
+pub mod web;
 
+#[doc(hidden)]
+pub use async_trait;
+#[cfg(feature = "runtime")]
 #[doc(hidden)]
 pub use ctor;
-pub use uorm_macros::mapper_assets;
+#[doc(hidden)]
+pub use serde;
+pub use uorm_macros::{IntEnum, dao, mapper_assets, sql, sql_map, transactional};
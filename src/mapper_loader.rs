@@ -1,16 +1,60 @@
-use anyhow::{Context, Result};
+use crate::executor::digest::fnv1a;
+use crate::executor::options::QueryOptions;
+use crate::tpl::barrier::{RELOAD, ReadGuard, WriteGuard};
+#[cfg(feature = "xml")]
+use crate::tpl::parser::{qualify_ref, qualify_with_refs};
+#[cfg(feature = "xml")]
+use anyhow::Context;
+use anyhow::Result;
 use dashmap::DashMap;
+#[cfg(feature = "xml")]
 use glob::glob;
+#[cfg(feature = "xml")]
+use log::info;
+#[cfg(feature = "xml")]
 use quick_xml::de;
 use serde::Deserialize;
+#[cfg(feature = "xml")]
 use std::fs;
+#[cfg(feature = "xml")]
 use std::path::Path;
-use std::sync::{Arc, OnceLock};
+use std::sync::{Arc, LazyLock, OnceLock};
+use std::time::Duration;
 
 
+/// 语句类型，对应 XML 标签名
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub enum StatementKind {
+    /// `<sql>` 片段
+    #[default]
+    Sql,
+    Select,
+    Insert,
+    Update,
+    Delete,
+    /// `<cte>` 片段，由 `<with refs>` 引用
+    Cte,
+}
+
+impl StatementKind {
+    /// 对应的 XML 标签名
+    pub fn tag(self) -> &'static str {
+        match self {
+            StatementKind::Sql => "sql",
+            StatementKind::Select => "select",
+            StatementKind::Insert => "insert",
+            StatementKind::Update => "update",
+            StatementKind::Delete => "delete",
+            StatementKind::Cte => "cte",
+        }
+    }
+}
+
 /// SQL 映射对象，包含 SQL 内容及相关配置
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, serde::Serialize)]
 pub struct SqlMapper {
+    /// 语句类型
+    pub kind: StatementKind,
     /// 数据库类型
     pub database_type: Option<String>,
     /// SQL 文本内容
@@ -19,6 +63,54 @@ pub struct SqlMapper {
     pub use_generated_keys: bool,
     /// 主键列名
     pub key_column: Option<String>,
+    /// 参数类型名（`parameterType`）
+    pub parameter_type: Option<String>,
+    /// 结果类型名（`resultType`）
+    pub result_type: Option<String>,
+    /// 变更事件的实体名（`entity`）
+    pub entity: Option<String>,
+    /// 变更事件携带的主键参数（`entityKeys`，逗号分隔）
+    pub entity_keys: Vec<String>,
+    /// 查询结果缓存键模板（`cacheKey`）
+    pub cache_key: Option<String>,
+    /// 缓存有效期（`ttl`，秒）
+    pub cache_ttl: Option<u64>,
+    /// 执行成功后失效的缓存键模板（`evicts`，逗号分隔）
+    pub evicts: Vec<String>,
+    /// 嵌套在 `<insert>` 中、依赖其生成主键的子语句
+    pub chained: Vec<ChainedInsert>,
+    /// 不支持 RETURNING 时用于回读插入行的查询 ID（`returningSelect`）
+    pub returning_select: Option<String>,
+    /// 语句级查询选项（`timeout`、`maxRows`、`autoLimit`、`fetchSize`、`route`、`useCache`）
+    pub options: QueryOptions,
+    /// 分片键参数路径（`shardedBy`），执行时据其取值选择连接池
+    pub sharded_by: Option<String>,
+    /// 不经模板引擎、原样执行（`raw`），参数按位置绑定
+    pub raw: bool,
+    /// 幂等键参数路径（`idempotentKey`），同一键的写语句只生效一次
+    pub idempotent_key: Option<String>,
+    /// 查询结果反序列化前经过的行后处理器名（`rowProcessors`，逗号分隔），
+    /// 见 [`crate::executor::postprocess`]
+    pub row_processors: Vec<String>,
+    /// `<cte>` 依赖的其他片段（`refs`，逗号分隔），加载时补全命名空间
+    pub cte_refs: Vec<String>,
+    /// `<cte>` 是否为递归片段（`recursive`），引用时输出 `WITH RECURSIVE`
+    pub recursive: bool,
+}
+
+/// 语句链中的子 `<insert>`
+///
+/// 由 [`crate::executor::mapper::Mapper::create_graph`] 在父语句之后、同一事务内执行，
+/// 通过 `#{parent.<keyColumn>}` 引用父语句生成的主键。
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ChainedInsert {
+    /// 子语句 ID，仅在父语句内唯一
+    pub id: String,
+    /// 按集合逐项执行时的集合路径（`collection`）
+    pub collection: Option<String>,
+    /// 集合元素的变量名（`item`，默认 `item`）
+    pub item: String,
+    pub mapper: SqlMapper,
 }
 
 /// SQL 映射器存储仓库，使用 DashMap 实现并发安全的存储
@@ -28,24 +120,58 @@ pub type SqlMapperStore = DashMap<String, DashMap<String, Vec<Arc<SqlMapper>>>>;
 /// 全局单例的 SQL 映射器存储
 static SQL_MAPPERS: OnceLock<SqlMapperStore> = OnceLock::new();
 
+/// 已加载的 Mapper 来源文件信息
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SourceVersion {
+    /// 文件路径或远程标识
+    pub source: String,
+    pub namespace: String,
+    /// `<mapper version="...">` 声明的版本
+    pub version: Option<String>,
+    /// 文件内容哈希（16 位十六进制）
+    pub content_hash: String,
+}
+
+/// 已加载 Mapper 集合的指纹
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct MapperFingerprint {
+    /// 所有命名空间、ID 与内容的稳定哈希（16 位十六进制）
+    pub digest: String,
+    pub namespaces: usize,
+    pub statements: usize,
+    /// 按来源排序的各文件版本
+    pub sources: Vec<SourceVersion>,
+}
+
+/// 来源文件信息：source -> SourceVersion
+static SOURCES: LazyLock<DashMap<String, SourceVersion>> = LazyLock::new(DashMap::new);
+
 /// 资源提供者特征，用于抽象资源加载
 pub trait AssetProvider {
     fn list(&self) -> Vec<&[u8]>;
 }
 
 /// XML 映射文件根节点结构
+#[cfg(feature = "xml")]
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Mapper {
     /// 命名空间，用于隔离不同的 Mapper
     #[serde(rename = "@namespace")]
     namespace: String,
+    /// 版本号，仅用于指纹与运维核对
+    #[serde(rename = "@version")]
+    version: Option<String>,
+    /// 命名空间默认使用的连接池名，语句上的 `route` 优先
+    #[serde(rename = "@datasource")]
+    datasource: Option<String>,
     /// SQL 节点列表
     #[serde(rename = "$value")]
     nodes: Vec<SqlNode>,
 }
 
 /// SQL 节点枚举，支持多种 SQL 操作类型
+#[cfg(feature = "xml")]
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
 enum SqlNode {
@@ -54,25 +180,29 @@ enum SqlNode {
     Insert(SqlItem),
     Update(SqlItem),
     Delete(SqlItem),
+    Cte(SqlItem),
     #[serde(other)]
     Unknown,
 }
 
+#[cfg(feature = "xml")]
 impl SqlNode {
     /// 将节点转换为统一的 SqlItem
-    fn into_item(self) -> Option<SqlItem> {
+    fn into_item(self) -> Option<(StatementKind, SqlItem)> {
         match self {
-            SqlNode::Sql(item)
-            | SqlNode::Select(item)
-            | SqlNode::Insert(item)
-            | SqlNode::Update(item)
-            | SqlNode::Delete(item) => Some(item),
+            SqlNode::Sql(item) => Some((StatementKind::Sql, item)),
+            SqlNode::Select(item) => Some((StatementKind::Select, item)),
+            SqlNode::Insert(item) => Some((StatementKind::Insert, item)),
+            SqlNode::Update(item) => Some((StatementKind::Update, item)),
+            SqlNode::Delete(item) => Some((StatementKind::Delete, item)),
+            SqlNode::Cte(item) => Some((StatementKind::Cte, item)),
             SqlNode::Unknown => None,
         }
     }
 }
 
 /// SQL配置项结构，对应 XML 中的具体标签
+#[doc(hidden)]
 #[derive(Debug, Deserialize)]
 pub struct SqlItem {
     /// SQL 语句唯一标识
@@ -87,58 +217,358 @@ pub struct SqlItem {
     /// 主键列名配置
     #[serde(rename = "@keyColumn")]
     pub key_column: Option<String>,
+    /// 参数类型名
+    #[serde(rename = "@parameterType")]
+    pub parameter_type: Option<String>,
+    /// 结果类型名
+    #[serde(rename = "@resultType")]
+    pub result_type: Option<String>,
+    /// 变更事件实体名
+    #[serde(rename = "@entity")]
+    pub entity: Option<String>,
+    /// 变更事件主键参数
+    #[serde(rename = "@entityKeys")]
+    pub entity_keys: Option<String>,
+    /// 缓存键模板
+    #[serde(rename = "@cacheKey")]
+    pub cache_key: Option<String>,
+    /// 缓存有效期（秒）
+    #[serde(rename = "@ttl")]
+    pub ttl: Option<String>,
+    /// 失效的缓存键模板
+    #[serde(rename = "@evicts")]
+    pub evicts: Option<String>,
+    /// 子语句按集合逐项执行时的集合路径
+    #[serde(rename = "@collection")]
+    pub collection: Option<String>,
+    /// 集合元素的变量名
+    #[serde(rename = "@item")]
+    pub item: Option<String>,
+    /// 回读插入行的查询 ID
+    #[serde(rename = "@returningSelect")]
+    pub returning_select: Option<String>,
+    /// 查询超时（秒）
+    #[serde(rename = "@timeout")]
+    pub timeout: Option<String>,
+    /// 最多返回的行数
+    #[serde(rename = "@maxRows")]
+    pub max_rows: Option<String>,
+    /// 为没有 `LIMIT` 的查询追加的行数上限，`0` 表示关闭
+    #[serde(rename = "@autoLimit")]
+    pub auto_limit: Option<String>,
+    /// 流式读取时每批的行数
+    #[serde(rename = "@fetchSize")]
+    pub fetch_size: Option<String>,
+    /// 执行查询的连接池名
+    #[serde(rename = "@route")]
+    pub route: Option<String>,
+    /// 是否使用查询结果缓存
+    #[serde(rename = "@useCache")]
+    pub use_cache: Option<String>,
+    /// 模板参数名的匹配方式：`exact`、`ignore_case`、`relaxed`
+    #[serde(rename = "@paramNaming")]
+    pub param_naming: Option<String>,
+    /// 分片键参数路径
+    #[serde(rename = "@shardedBy", alias = "@sharded-by")]
+    pub sharded_by: Option<String>,
+    /// 是否原样执行
+    #[serde(rename = "@raw")]
+    pub raw: Option<String>,
+    /// 幂等键参数路径
+    #[serde(rename = "@idempotentKey", alias = "@idempotent-key")]
+    pub idempotent_key: Option<String>,
+    /// 行后处理器名
+    #[serde(rename = "@rowProcessors")]
+    pub row_processors: Option<String>,
+    /// `<cte>` 依赖的片段名
+    #[serde(rename = "@refs")]
+    pub refs: Option<String>,
+    /// `<cte>` 是否递归
+    #[serde(rename = "@recursive")]
+    pub recursive: Option<String>,
     /// SQL 文本内容
+    ///
+    /// 文本与 `<![CDATA[...]]>` 段按原顺序拼接，实体（`&lt;`、`&amp;` 等）已解码、注释已去除，
+    /// `<if>`/`<for>` 等动态标签原样保留（见 [`normalize_statements`]）；
+    /// 含 `<` 比较的 SQL 可写在 CDATA 中，也可使用 `&lt;`。
     #[serde(rename = "$text")]
     pub content: Option<String>,
+    /// 嵌套的子 `<insert>`
+    #[serde(rename = "insert", default)]
+    pub children: Vec<SqlItem>,
 }
 
 impl From<&SqlItem> for SqlMapper {
     fn from(item: &SqlItem) -> Self {
-        // 解析 use_generated_keys 属性，支持 true/1/yes
-        let use_generated_keys = item
-            .use_generated_keys
-            .as_deref()
-            .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "true" | "1" | "yes"))
-            .unwrap_or(false);
+        // 解析布尔属性，支持 true/1/yes
+        let flag = |value: Option<&str>| {
+            value
+                .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "true" | "1" | "yes"))
+                .unwrap_or(false)
+        };
+        let use_generated_keys = flag(item.use_generated_keys.as_deref());
+
+        let cache_ttl = item.ttl.as_deref().and_then(|s| match s.trim().parse() {
+            Ok(ttl) => Some(ttl),
+            Err(_) => {
+                log::warn!("语句 '{}' 的 ttl 无效: '{}'，缓存将不过期", item.id, s);
+                None
+            }
+        });
+
+        let options = QueryOptions {
+            timeout: parse_attr(&item.id, "timeout", item.timeout.as_deref())
+                .map(Duration::from_secs),
+            max_rows: parse_attr(&item.id, "maxRows", item.max_rows.as_deref()),
+            auto_limit: parse_attr(&item.id, "autoLimit", item.auto_limit.as_deref()),
+            fetch_size: parse_attr(&item.id, "fetchSize", item.fetch_size.as_deref()),
+            route: item.route.clone(),
+            cache: parse_attr(&item.id, "useCache", item.use_cache.as_deref()),
+            param_naming: parse_attr(&item.id, "paramNaming", item.param_naming.as_deref()),
+        };
 
         Self {
+            kind: StatementKind::Sql,
             database_type: item.database_type.clone(),
             content: item.content.clone(),
             use_generated_keys,
             key_column: item.key_column.clone(),
+            parameter_type: item.parameter_type.clone(),
+            result_type: item.result_type.clone(),
+            entity: item.entity.clone(),
+            entity_keys: split_list(item.entity_keys.as_deref()),
+            cache_key: item.cache_key.clone(),
+            cache_ttl,
+            evicts: split_list(item.evicts.as_deref()),
+            returning_select: item.returning_select.clone(),
+            options,
+            sharded_by: item.sharded_by.clone(),
+            raw: flag(item.raw.as_deref()),
+            idempotent_key: item.idempotent_key.clone(),
+            row_processors: split_list(item.row_processors.as_deref()),
+            cte_refs: split_list(item.refs.as_deref()),
+            recursive: flag(item.recursive.as_deref()),
+            chained: item
+                .children
+                .iter()
+                .map(|child| ChainedInsert {
+                    id: child.id.clone(),
+                    collection: child.collection.clone(),
+                    item: child.item.clone().unwrap_or_else(|| "item".to_string()),
+                    mapper: SqlMapper {
+                        kind: StatementKind::Insert,
+                        ..SqlMapper::from(child)
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 解析数值或布尔属性，无效时记录警告并忽略
+fn parse_attr<T: std::str::FromStr>(id: &str, name: &str, value: Option<&str>) -> Option<T> {
+    let value = value?;
+    match value.trim().parse() {
+        Ok(v) => Some(v),
+        Err(_) => {
+            log::warn!("语句 '{}' 的 {} 无效: '{}'，已忽略", id, name, value);
+            None
+        }
+    }
+}
+
+/// 解析逗号分隔的属性值
+fn split_list(value: Option<&str>) -> Vec<String> {
+    value
+        .map(|s| {
+            s.split(',')
+                .map(str::trim)
+                .filter(|k| !k.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 单个 mapper 文件的加载错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadError {
+    /// 文件路径或资源名
+    pub source: String,
+    /// 出错位置的行号（从 1 开始），无法定位时为 `None`
+    pub line: Option<usize>,
+    /// 出错位置的列号（从 1 开始，按字符计）
+    pub column: Option<usize>,
+    /// 出错的语句，如 `<select id="findById">`
+    pub element: Option<String>,
+    pub message: String,
+}
+
+#[cfg(feature = "xml")]
+impl LoadError {
+    fn new(source: &str, message: impl Into<String>) -> Self {
+        Self {
+            source: source.to_string(),
+            line: None,
+            column: None,
+            element: None,
+            message: message.into(),
+        }
+    }
+
+    /// 按原文中的字节偏移补充行列号
+    fn at(mut self, xml: &str, offset: usize) -> Self {
+        let before = &xml[..offset.min(xml.len())];
+        self.line = Some(before.matches('\n').count() + 1);
+        self.column = Some(before.rsplit('\n').next().unwrap_or(before).chars().count() + 1);
+        self
+    }
+
+    /// 补充出错的语句，并定位到其在原文中最后一次出现的位置
+    fn element(mut self, xml: &str, tag: &str, id: &str) -> Self {
+        self.element = Some(format!("<{} id=\"{}\">", tag, id));
+        let offset = [format!("id=\"{}\"", id), format!("id='{}'", id)]
+            .iter()
+            .filter_map(|pattern| xml.rfind(pattern.as_str()))
+            .max();
+        match offset {
+            Some(offset) => self.at(xml, xml[..offset].rfind('<').unwrap_or(offset)),
+            None => self,
+        }
+    }
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)?;
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, ":{}:{}", line, column)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(element) = &self.element {
+            write!(f, " (in {})", element)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// 一次加载的结果
+///
+/// 出错的文件整体不生效，其余文件照常加载，避免一个 mapper 有误导致无关命名空间无法启动。
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    /// 成功加载的来源
+    pub loaded: Vec<String>,
+    /// 各文件的加载错误
+    pub errors: Vec<LoadError>,
+}
+
+impl LoadReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// 有错误时汇总为一个错误返回
+    pub fn into_result(self) -> Result<Self> {
+        if self.errors.is_empty() {
+            return Ok(self);
+        }
+        let details: Vec<String> = self.errors.iter().map(|e| format!("  {}", e)).collect();
+        anyhow::bail!(
+            "{} 个 mapper 文件加载失败:\n{}",
+            self.errors.len(),
+            details.join("\n")
+        )
+    }
+
+    #[cfg(feature = "xml")]
+    fn record(&mut self, source: &str, result: std::result::Result<(), LoadError>) {
+        match result {
+            Ok(()) => self.loaded.push(source.to_string()),
+            Err(e) => {
+                log::error!("mapper load failed: {}", e);
+                self.errors.push(e);
+            }
         }
     }
 }
 
+#[cfg(feature = "xml")]
 /// 加载指定模式（glob pattern）匹配的所有 XML 映射文件
 ///
 /// # 参数
 /// * `pattern` - 文件路径匹配模式，例如 "src/resources/**/*.xml"
 ///
 /// # 返回
-/// * `Result<()>` - 加载成功返回 Ok(())，否则返回错误
+/// * `Result<()>` - 全部成功返回 Ok(())；任一文件出错时，其余文件仍会加载，错误汇总后返回
 pub fn load(pattern: &str) -> Result<()> {
+    load_with_report(pattern)?.into_result().map(drop)
+}
+
+#[cfg(feature = "xml")]
+/// 同 [`load`]，返回每个文件的加载结果而不是在出错时报错
+///
+/// 仅 glob 模式本身无效时返回错误。
+pub fn load_with_report(pattern: &str) -> Result<LoadReport> {
     let paths = glob(pattern).with_context(|| format!("读取 glob 模式失败: {}", pattern))?;
 
+    // 遍历、读取与解析都在写锁之外完成，只在存入全局存储时持有写锁
+    let mut files = Vec::new();
     for entry in paths {
         match entry {
             Ok(path) => {
                 if path.is_file() {
-                    process_mapper_file(&path)?;
+                    let source = path.display().to_string();
+                    let staged = stage_mapper_file(&path, &source);
+                    files.push((source, staged));
                 }
             }
-            Err(e) => anyhow::bail!("读取路径失败: {}", e),
+            Err(e) => {
+                let source = e.path().display().to_string();
+                let error = LoadError::new(&source, format!("读取路径失败: {}", e));
+                files.push((source, Err(error)));
+            }
         }
     }
-    Ok(())
+
+    let _guard = write_guard()?;
+    let mut report = LoadReport::default();
+    for (source, staged) in files {
+        let result = staged.and_then(|(staged, xml)| store_staged(staged, &source, &xml));
+        report.record(&source, result);
+    }
+    log_fingerprint();
+    Ok(report)
 }
 
+#[cfg(feature = "xml")]
 /// 加载内嵌的 mapper 资源（通常用于编译进二进制的资源）
 pub fn load_assets(assets: Vec<(&str, &str)>) -> Result<()> {
-    for (source, content) in assets {
-        process_mapper_data(content, source)?;
+    load_assets_with_report(assets).into_result().map(drop)
+}
+
+#[cfg(feature = "xml")]
+/// 同 [`load_assets`]，返回每个资源的加载结果
+pub fn load_assets_with_report(assets: Vec<(&str, &str)>) -> LoadReport {
+    // 解析在写锁之外完成，只在存入全局存储时持有写锁
+    let staged: Vec<_> = assets
+        .into_iter()
+        .map(|(source, content)| (source, content, stage_mapper_data(content, source)))
+        .collect();
+
+    let mut report = LoadReport::default();
+    let guard = write_guard();
+    for (source, content, staged) in staged {
+        let result = match &guard {
+            Ok(_) => staged.and_then(|staged| store_staged(staged, source, content)),
+            Err(e) => Err(LoadError::new(source, e.to_string())),
+        };
+        report.record(source, result);
     }
-    Ok(())
+    log_fingerprint();
+    report
 }
 
 /// 根据 SQL ID 查找对应的 Mapper 配置
@@ -147,65 +577,338 @@ pub fn load_assets(assets: Vec<(&str, &str)>) -> Result<()> {
 /// * `sql_id` - 完整的 SQL ID，格式为 "namespace.id"
 /// * `db_type` - 数据库类型，例如 "mysql", "postgres"
 pub fn find_mapper(sql_id: &str, db_type: &str) -> Option<Arc<SqlMapper>> {
-    // 分割 namespace 和 id
-    let (namespace, id) = sql_id.rsplit_once('.')?;
+    with_variants(sql_id, |mappers| {
+        // 优先匹配指定数据库类型，如果没有则使用默认（无数据库类型）的配置
+        let mut default_mapper = None;
+        for mapper in mappers {
+            if let Some(ref t) = mapper.database_type {
+                if t == db_type {
+                    return Some(mapper.clone());
+                }
+            } else {
+                default_mapper = Some(mapper.clone());
+            }
+        }
+        default_mapper
+    })
+    .flatten()
+}
+
+/// 语句绑定的连接池名：语句声明的 `route`，未声明时为 `<mapper datasource>`
+pub fn statement_datasource(sql_id: &str) -> Option<String> {
+    with_variants(sql_id, |mappers| {
+        mappers.iter().find_map(|m| m.options.route.clone())
+    })
+    .flatten()
+}
 
+/// 以 `sql_id` 的各 databaseType 变体调用 `f`
+///
+/// 从右往左依次在各个 `.` 处将 `sql_id` 分为命名空间与语句 ID，取第一个存在的语句；
+/// 语句 ID 本身可以包含 `.`，如 `user.list.count` 可以是命名空间 `user` 中的 `list.count`。
+fn with_variants<R>(sql_id: &str, f: impl FnOnce(&[Arc<SqlMapper>]) -> R) -> Option<R> {
     let store = SQL_MAPPERS.get()?;
-    let ns_map = store.get(namespace)?;
-    let mappers = ns_map.get(id)?;
-
-    // 优先匹配指定数据库类型，如果没有则使用默认（无数据库类型）的配置
-    let mut default_mapper = None;
-    for mapper in mappers.value() {
-        if let Some(ref t) = mapper.database_type {
-            if t == db_type {
-                return Some(mapper.clone());
-            }
-        } else {
-            default_mapper = Some(mapper.clone());
+    for (i, _) in sql_id.rmatch_indices('.') {
+        let Some(ns_map) = store.get(&sql_id[..i]) else {
+            continue;
+        };
+        if let Some(mappers) = ns_map.get(&sql_id[i + 1..]) {
+            return Some(f(mappers.value()));
         }
     }
+    None
+}
+
+#[cfg(feature = "xml")]
+/// 读取并解析单个 Mapper 文件，返回解析结果与文件内容
+fn stage_mapper_file(
+    path: &Path,
+    source: &str,
+) -> std::result::Result<(StagedMapper, String), LoadError> {
+    let xml_content = fs::read_to_string(path)
+        .map_err(|e| LoadError::new(source, format!("读取文件失败: {}", e)))?;
+    let staged = stage_mapper_data(&xml_content, source)?;
+    Ok((staged, xml_content))
+}
 
-    default_mapper
+/// 单个命名空间内的语句集合：ID -> 各 databaseType 变体
+#[cfg(feature = "xml")]
+type NamespaceStore = DashMap<String, Vec<Arc<SqlMapper>>>;
+
+#[cfg(feature = "xml")]
+/// 解析并校验完毕、尚未存入全局存储的 Mapper
+struct StagedMapper {
+    namespace: String,
+    version: SourceVersion,
+    statements: NamespaceStore,
 }
 
-/// 处理单个 Mapper 文件
-fn process_mapper_file(path: &Path) -> Result<()> {
-    let xml_content =
-        fs::read_to_string(path).with_context(|| format!("读取文件失败: {}", path.display()))?;
-    process_mapper_data(&xml_content, &path.display().to_string())
+#[cfg(feature = "xml")]
+/// 解析 Mapper XML 内容并在暂存结构中完成校验，不访问全局存储
+fn stage_mapper_data(
+    xml_content: &str,
+    source: &str,
+) -> std::result::Result<StagedMapper, LoadError> {
+    let mapper = parse_mapper(xml_content, source)?;
+    let namespace = mapper.namespace.clone();
+    let version = source_version(source, &mapper, xml_content);
+    let statements = NamespaceStore::new();
+    merge_nodes(&statements, mapper, source, xml_content)?;
+    Ok(StagedMapper {
+        namespace,
+        version,
+        statements,
+    })
 }
 
-/// 解析 Mapper XML 内容并存入全局存储
-fn process_mapper_data(xml_content: &str, source: &str) -> Result<()> {
-    let mapper: Mapper =
-        de::from_str(xml_content).with_context(|| format!("XML 解析失败: {}", source))?;
-    let namespace = mapper.namespace;
+#[cfg(feature = "xml")]
+/// 将暂存的语句存入全局存储，与已加载的语句重复时报错且不修改全局存储；调用方需持有写锁
+fn store_staged(
+    staged: StagedMapper,
+    source: &str,
+    xml_content: &str,
+) -> std::result::Result<(), LoadError> {
+    let StagedMapper {
+        namespace,
+        version,
+        statements: staged,
+    } = staged;
 
     // 获取或初始化全局存储
     let store = SQL_MAPPERS.get_or_init(DashMap::new);
 
     // 获取或初始化命名空间存储
-    let ns_map = store.entry(namespace.clone()).or_insert_with(DashMap::new);
+    let ns_map = store.entry(namespace.clone()).or_default();
+    for entry in staged.iter() {
+        if let Some(existing) = ns_map.get(entry.key()) {
+            for mapper in entry.value() {
+                if existing
+                    .iter()
+                    .any(|e| e.database_type == mapper.database_type)
+                {
+                    return Err(duplicate_error(
+                        source,
+                        xml_content,
+                        &namespace,
+                        entry.key(),
+                        mapper,
+                    ));
+                }
+            }
+        }
+    }
+    for (id, mappers) in staged {
+        ns_map.entry(id).or_default().extend(mappers);
+    }
+    SOURCES.insert(source.to_string(), version);
+    Ok(())
+}
+
+#[cfg(feature = "xml")]
+fn duplicate_error(
+    source: &str,
+    xml: &str,
+    namespace: &str,
+    id: &str,
+    mapper: &SqlMapper,
+) -> LoadError {
+    LoadError::new(
+        source,
+        format!(
+            "发现重复的 ID: '{}' (命名空间: '{}', databaseType: '{:?}')",
+            id, namespace, mapper.database_type
+        ),
+    )
+    .element(xml, mapper.kind.tag(), id)
+}
+
+#[cfg(feature = "xml")]
+fn parse_mapper(xml_content: &str, source: &str) -> std::result::Result<Mapper, LoadError> {
+    let (normalized, statements) = normalize_statements(xml_content).map_err(|(offset, e)| {
+        LoadError::new(source, format!("XML 解析失败: {}", e)).at(xml_content, offset)
+    })?;
+    de::from_str(&normalized).map_err(|e| {
+        let error = LoadError::new(source, format!("XML 解析失败: {}", e));
+        // 逐个语句重新反序列化，定位出错的元素
+        statements
+            .iter()
+            .find_map(|(offset, range)| {
+                let e = de::from_str::<SqlNode>(&normalized[range.clone()]).err()?;
+                Some(
+                    LoadError::new(source, format!("XML 解析失败: {}", e)).at(xml_content, *offset),
+                )
+            })
+            .unwrap_or(error)
+    })
+}
+
+#[cfg(feature = "xml")]
+type NormalizeResult =
+    std::result::Result<(String, Vec<(usize, std::ops::Range<usize>)>), (usize, anyhow::Error)>;
+
+#[cfg(feature = "xml")]
+const STATEMENT_TAGS: [&str; 6] = ["sql", "select", "insert", "update", "delete", "cte"];
+
+/// 语句元素内的原始内容交给模板引擎解析，而不是由 XML 反序列化展开
+#[cfg(feature = "xml")]
+enum Frame {
+    Statement(bool),
+    Dynamic,
+    Other,
+}
+
+#[cfg(feature = "xml")]
+/// 将每个语句元素的内容（文本、CDATA 与 `<if>`/`<for>` 等动态标签）原样收集为模板文本，
+/// 再以单个 CDATA 段写回，保证动态标签在反序列化时不会丢失
+///
+/// 文本中的实体已解码、CDATA 标记与注释已去除；嵌套的子 `<insert>` 仍作为元素保留。
+/// 同时返回各顶层语句在原文中的偏移及其在结果中的范围，用于定位错误；
+/// 出错时返回原文中的字节偏移。
+fn normalize_statements(xml: &str) -> NormalizeResult {
+    use quick_xml::Reader;
+    use quick_xml::escape::unescape;
+    use quick_xml::events::Event;
+
+    let mut reader = Reader::from_str(xml);
+    let mut out = String::with_capacity(xml.len() + 64);
+    let mut statements = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut content = String::new();
+    // 已写入 out 的原文位置
+    let mut copied = 0usize;
+    // 当前事件在原文中的起始位置，出错时用于定位
+    let mut start = 0usize;
+    // 当前顶层语句：原文偏移与在 out 中的起始位置
+    let mut current = (0usize, 0usize);
+
+    let flush = |out: &mut String, content: &mut String| {
+        if !content.trim().is_empty() {
+            out.push_str("<![CDATA[");
+            out.push_str(&content.replace("]]>", "]]]]><![CDATA[>"));
+            out.push_str("]]>");
+        }
+        content.clear();
+    };
+
+    let mut read = || -> Result<()> {
+        loop {
+            start = reader.buffer_position() as usize;
+            let event = reader.read_event()?;
+            let end = reader.buffer_position() as usize;
+            let raw = &xml[start..end];
+            let in_body = matches!(
+                stack.iter().rev().find(|f| !matches!(f, Frame::Dynamic)),
+                Some(Frame::Statement(_))
+            );
+            match event {
+                Event::Start(ref e) | Event::Empty(ref e) => {
+                    let empty = matches!(event, Event::Empty(_));
+                    let name = e.local_name();
+                    let name = std::str::from_utf8(name.as_ref())?;
+                    let parent_is_insert = matches!(stack.last(), Some(Frame::Statement(true)));
+                    let frame = if in_body && !(parent_is_insert && name == "insert") {
+                        content.push_str(&unescape(raw)?);
+                        Frame::Dynamic
+                    } else if STATEMENT_TAGS.contains(&name) && (in_body || stack.len() == 1) {
+                        // 顶层语句或 <insert> 的子 <insert>：先写出之前收集的父语句内容
+                        if in_body {
+                            flush(&mut out, &mut content);
+                            copied = start;
+                        } else {
+                            current = (start, out.len() + start - copied);
+                        }
+                        out.push_str(&xml[copied..end]);
+                        copied = end;
+                        if empty && !in_body {
+                            statements.push((current.0, current.1..out.len()));
+                        }
+                        Frame::Statement(name == "insert")
+                    } else {
+                        Frame::Other
+                    };
+                    if !empty {
+                        stack.push(frame);
+                    }
+                }
+                Event::End(_) => match stack.pop() {
+                    Some(Frame::Dynamic) => content.push_str(&unescape(raw)?),
+                    Some(Frame::Statement(_)) => {
+                        flush(&mut out, &mut content);
+                        out.push_str(raw);
+                        copied = end;
+                        if stack.len() == 1 {
+                            statements.push((current.0, current.1..out.len()));
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Text(_) | Event::GeneralRef(_) if in_body => {
+                    content.push_str(&unescape(raw)?)
+                }
+                Event::CData(e) if in_body => content.push_str(std::str::from_utf8(&e)?),
+                Event::Eof => return Ok(()),
+                _ => {}
+            }
+        }
+    };
+    read().map_err(|e| (start, e))?;
+    out.push_str(&xml[copied..]);
+    Ok((out, statements))
+}
 
+#[cfg(feature = "xml")]
+fn source_version(source: &str, mapper: &Mapper, xml_content: &str) -> SourceVersion {
+    SourceVersion {
+        source: source.to_string(),
+        namespace: mapper.namespace.clone(),
+        version: mapper.version.clone(),
+        content_hash: format!("{:016x}", fnv1a(xml_content)),
+    }
+}
+
+#[cfg(feature = "xml")]
+/// 将 Mapper 中的语句合并进命名空间存储，同一 ID 下 databaseType 重复时报错
+fn merge_nodes(
+    ns_map: &NamespaceStore,
+    mapper: Mapper,
+    source: &str,
+    xml: &str,
+) -> std::result::Result<(), LoadError> {
+    let namespace = mapper.namespace;
     for node in mapper.nodes {
-        if let Some(item) = node.into_item() {
-            let sql_mapper = SqlMapper::from(&item);
+        if let Some((kind, item)) = node.into_item() {
+            let mut sql_mapper = SqlMapper::from(&item);
+            sql_mapper.kind = kind;
+            if sql_mapper.options.route.is_none() {
+                sql_mapper.options.route = mapper.datasource.clone();
+            }
+            qualify_refs(&mut sql_mapper, &namespace);
+            if kind != StatementKind::Insert && !sql_mapper.chained.is_empty() {
+                return Err(LoadError::new(
+                    source,
+                    format!(
+                        "语句 '{}' 不是 <insert>，不能包含子 <insert> (命名空间: '{}')",
+                        item.id, namespace
+                    ),
+                )
+                .element(xml, kind.tag(), &item.id));
+            }
 
             // 获取该 ID 的映射列表
-            let mut mappers = ns_map.entry(item.id.clone()).or_insert_with(Vec::new);
+            let mut mappers = ns_map.entry(item.id.clone()).or_default();
 
             // 检查是否存在相同 database_type 的配置
-            for existing in mappers.iter() {
-                if existing.database_type == sql_mapper.database_type {
-                    anyhow::bail!(
-                        "文件 '{}' 中发现重复的 ID: '{}' (命名空间: '{}', databaseType: '{:?}')",
-                        source,
-                        item.id,
-                        namespace,
-                        sql_mapper.database_type
-                    );
-                }
+            if mappers
+                .iter()
+                .any(|existing| existing.database_type == sql_mapper.database_type)
+            {
+                return Err(duplicate_error(
+                    source,
+                    xml,
+                    &namespace,
+                    &item.id,
+                    &sql_mapper,
+                ));
             }
 
             mappers.push(Arc::new(sql_mapper));
@@ -214,9 +917,257 @@ fn process_mapper_data(xml_content: &str, source: &str) -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "xml")]
+/// 将 `<cte refs>` 与模板中 `<with refs>` 引用的片段名补全为 `namespace.id`，
+/// 渲染时按完整 ID 查找，片段可以引用其他命名空间中的片段
+fn qualify_refs(mapper: &mut SqlMapper, namespace: &str) {
+    for id in &mut mapper.cte_refs {
+        *id = qualify_ref(namespace, id);
+    }
+    let qualified = mapper
+        .content
+        .as_deref()
+        .and_then(|c| qualify_with_refs(c, namespace));
+    if qualified.is_some() {
+        mapper.content = qualified;
+    }
+    for chained in &mut mapper.chained {
+        qualify_refs(&mut chained.mapper, namespace);
+    }
+}
+
+/// 语句在模板缓存中的键：同一 SQL ID 的不同 databaseType 变体需要区分
+pub(crate) fn template_key(sql_id: &str, database_type: Option<&str>) -> String {
+    match database_type {
+        Some(t) => format!("{}@{}", sql_id, t),
+        None => sql_id.to_string(),
+    }
+}
+
+/// 模板缓存键 `key`（见 [`template_key`]）对应的已加载语句
+pub(crate) fn statement_for_key(key: &str) -> Option<Arc<SqlMapper>> {
+    let (sql_id, database_type) = key.split_once('@').unwrap_or((key, ""));
+    find_mapper(sql_id, database_type)
+        .filter(|mapper| mapper.database_type.as_deref().unwrap_or("") == database_type)
+}
+
+/// 模板缓存键 `key` 是否对应一条声明了 `raw="true"` 且内容为 `content` 的语句
+pub(crate) fn is_raw_statement(key: &str, content: &str) -> bool {
+    statement_for_key(key)
+        .is_some_and(|mapper| mapper.raw && mapper.content.as_deref() == Some(content))
+}
+
+/// 语句链中子语句的模板缓存键
+#[cfg(any(feature = "runtime", feature = "xml"))]
+pub(crate) fn chained_key(parent_key: &str, child_id: &str) -> String {
+    format!("{}/{}", parent_key, child_id)
+}
+
+#[cfg(feature = "xml")]
+/// 用一组 Mapper 文档整体替换其涉及的命名空间
+///
+/// 所有文档先解析到暂存结构中，任一文档解析失败则不修改全局存储；
+/// 全部成功后逐个命名空间原子替换，并清理被替换语句的模板缓存。
+/// 返回被替换的命名空间列表。
+pub fn replace_namespaces(docs: &[(String, String)]) -> Result<Vec<String>> {
+    let (staged, versions) = stage(docs)?;
+    swap(staged, versions)
+}
+
+#[cfg(feature = "xml")]
+/// 重新加载单个命名空间
+///
+/// 新的 XML 先解析到暂存结构，解析失败或命名空间不匹配时保持原有定义不变；
+/// 成功后原子替换该命名空间（其余命名空间不受影响），并清理相关模板缓存。
+/// 注意：该命名空间原先若由多个文件组成，将被 `xml` 的内容整体取代。
+pub fn reload(namespace: &str, xml: &str) -> Result<()> {
+    let source = format!("reload://{}", namespace);
+    let (staged, versions) = stage(&[(source, xml.to_string())])?;
+    if staged.len() != 1 || !staged.contains_key(namespace) {
+        anyhow::bail!("重新加载的 XML 命名空间与目标不一致: 期望 '{}'", namespace);
+    }
+    swap(staged, versions)?;
+    Ok(())
+}
+
+#[cfg(feature = "xml")]
+/// 将文档解析到暂存结构，不修改全局存储
+fn stage(
+    docs: &[(String, String)],
+) -> Result<(DashMap<String, NamespaceStore>, Vec<SourceVersion>)> {
+    let staged: DashMap<String, NamespaceStore> = DashMap::new();
+    let mut versions = Vec::with_capacity(docs.len());
+    for (source, xml_content) in docs {
+        let mapper = parse_mapper(xml_content, source)?;
+        versions.push(source_version(source, &mapper, xml_content));
+        let ns_map = staged.entry(mapper.namespace.clone()).or_default();
+        merge_nodes(&ns_map, mapper, source, xml_content)?;
+    }
+    Ok((staged, versions))
+}
+
+#[cfg(feature = "xml")]
+/// 用暂存结构替换全局存储中的命名空间
+///
+/// 替换与模板缓存清理在同一写锁内完成，持有 [`MapperSnapshot`] 的渲染看不到中间状态。
+fn swap(
+    staged: DashMap<String, NamespaceStore>,
+    versions: Vec<SourceVersion>,
+) -> Result<Vec<String>> {
+    let _guard = write_guard()?;
+    let store = SQL_MAPPERS.get_or_init(DashMap::new);
+    let mut namespaces = Vec::with_capacity(staged.len());
+    for (namespace, ns_map) in staged {
+        let replaced = store.insert(namespace.clone(), ns_map);
+        if let Some(old) = replaced {
+            evict_templates(&namespace, &old);
+        }
+        SOURCES.retain(|_, v| v.namespace != namespace);
+        namespaces.push(namespace);
+    }
+    for version in versions {
+        SOURCES.insert(version.source.clone(), version);
+    }
+    log_fingerprint();
+    Ok(namespaces)
+}
+
+#[cfg(feature = "xml")]
+/// 清理命名空间下所有语句的模板缓存
+fn evict_templates(namespace: &str, ns_map: &NamespaceStore) {
+    for entry in ns_map.iter() {
+        let sql_id = format!("{}.{}", namespace, entry.key());
+        for mapper in entry.value() {
+            let key = template_key(&sql_id, mapper.database_type.as_deref());
+            evict_chained(&key, mapper);
+            crate::tpl::engine::remove_template(&key);
+        }
+    }
+}
+
+#[cfg(feature = "xml")]
+fn evict_chained(parent_key: &str, mapper: &SqlMapper) {
+    for child in &mapper.chained {
+        let key = chained_key(parent_key, &child.id);
+        evict_chained(&key, &child.mapper);
+        crate::tpl::engine::remove_template(&key);
+    }
+}
+
+/// Mapper 存储与模板缓存的一致视图
+///
+/// 持有期间加载与重载（[`load`]、[`reload`]、[`replace_namespaces`] 等）会等待，
+/// 其间查找的语句与渲染时解析的 `<include>` 片段都来自同一版本。
+/// 视图不能跨线程传递，也不应跨越 `.await` 持有；持有视图的线程不能再加载或重载 Mapper。
+pub struct MapperSnapshot {
+    guard: ReadGuard<'static>,
+}
+
+impl MapperSnapshot {
+    /// 重载代数，每次加载或重载后加一
+    pub fn generation(&self) -> u64 {
+        self.guard.generation()
+    }
+
+    /// 在该视图中查找语句，见 [`find_mapper`]
+    pub fn find_mapper(&self, sql_id: &str, db_type: &str) -> Option<Arc<SqlMapper>> {
+        find_mapper(sql_id, db_type)
+    }
+}
+
+/// 获取 Mapper 存储与模板缓存的一致视图
+pub fn snapshot() -> MapperSnapshot {
+    MapperSnapshot {
+        guard: RELOAD.read(),
+    }
+}
+
+fn write_guard() -> Result<WriteGuard<'static>> {
+    RELOAD
+        .write()
+        .ok_or_else(|| anyhow::anyhow!("持有 MapperSnapshot 时不能加载或重载 Mapper"))
+}
+
+/// 计算已加载 Mapper 集合的指纹
+///
+/// 按命名空间、ID、databaseType 排序后对语句的完整定义（内容、全部属性与子语句）做稳定哈希，
+/// 与加载顺序无关，可用于核对集群内各实例的 SQL 定义是否一致。
+pub fn fingerprint() -> MapperFingerprint {
+    let mut entries: Vec<String> = Vec::new();
+    let mut namespaces = 0;
+    if let Some(store) = SQL_MAPPERS.get() {
+        for ns in store.iter() {
+            namespaces += 1;
+            for stmt in ns.value().iter() {
+                for mapper in stmt.value() {
+                    entries.push(format!(
+                        "{}\u{1}{}\u{1}{}",
+                        ns.key(),
+                        stmt.key(),
+                        serde_json::to_string(mapper.as_ref()).unwrap_or_default()
+                    ));
+                }
+            }
+        }
+    }
+    entries.sort_unstable();
+
+    let mut sources: Vec<SourceVersion> = SOURCES.iter().map(|e| e.value().clone()).collect();
+    sources.sort_by(|a, b| a.source.cmp(&b.source));
+
+    MapperFingerprint {
+        digest: format!("{:016x}", fnv1a(&entries.join("\u{0}"))),
+        namespaces,
+        statements: entries.len(),
+        sources,
+    }
+}
+
+#[cfg(feature = "xml")]
+fn log_fingerprint() {
+    let fp = fingerprint();
+    info!(
+        "mapper set loaded: fingerprint={}, namespaces={}, statements={}, sources={}",
+        fp.digest,
+        fp.namespaces,
+        fp.statements,
+        fp.sources.len()
+    );
+}
+
+/// 校验所有语句声明的 parameterType/resultType
+///
+/// 检查类型名是否已通过 [`crate::type_registry::register_type`] 注册（或为内置别名），
+/// 并确认语句引用的参数均为 parameterType 结构体的字段。
+/// 建议在启动时、完成 Mapper 加载与类型注册后调用，所有问题汇总在一个错误中返回。
+pub fn validate_types() -> Result<()> {
+    let mut issues = Vec::new();
+    if let Some(store) = SQL_MAPPERS.get() {
+        for ns in store.iter() {
+            for stmt in ns.value().iter() {
+                let sql_id = format!("{}.{}", ns.key(), stmt.key());
+                for mapper in stmt.value() {
+                    issues.extend(crate::type_registry::check_statement(&sql_id, mapper));
+                }
+            }
+        }
+    }
+    if issues.is_empty() {
+        return Ok(());
+    }
+    issues.sort_unstable();
+    issues.dedup();
+    anyhow::bail!("Mapper 类型校验失败:\n{}", issues.join("\n"))
+}
+
 /// 清理所有已加载的 mapper（主要用于测试环境重置状态）
-pub fn clear_mappers() {
+///
+/// 当前线程持有 [`MapperSnapshot`] 时返回错误，不做清理。
+pub fn clear_mappers() -> Result<()> {
+    let _guard = write_guard()?;
     if let Some(store) = SQL_MAPPERS.get() {
         store.clear();
     }
+    SOURCES.clear();
+    Ok(())
 }
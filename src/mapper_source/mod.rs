@@ -0,0 +1,130 @@
+//! Mapper 远程来源
+//!
+//! 通过 [`MapperSource`] 从配置中心等外部位置拉取共享的 SQL 定义，
+//! 并以命名空间为单位原子替换已加载的语句，实现无需重新部署的热更新。
+
+#[cfg(feature = "remote-mapper")]
+mod remote;
+
+#[cfg(feature = "remote-mapper")]
+pub use remote::{ConsulSource, EtcdSource, HttpSource};
+
+use crate::error::DbError;
+use async_trait::async_trait;
+use log::{info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Mapper 文档来源
+#[async_trait]
+pub trait MapperSource: Send + Sync {
+    /// 来源描述，用于日志与错误信息
+    fn name(&self) -> &str;
+
+    /// 拉取全部 Mapper 文档，返回 (文档标识, XML 内容) 列表
+    async fn fetch(&self) -> Result<Vec<(String, String)>, DbError>;
+}
+
+/// 内存中的静态来源，主要用于测试与本地覆盖
+pub struct StaticSource {
+    name: String,
+    docs: Vec<(String, String)>,
+}
+
+impl StaticSource {
+    pub fn new(name: impl Into<String>, docs: Vec<(String, String)>) -> Self {
+        Self {
+            name: name.into(),
+            docs,
+        }
+    }
+}
+
+#[async_trait]
+impl MapperSource for StaticSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(&self) -> Result<Vec<(String, String)>, DbError> {
+        Ok(self.docs.clone())
+    }
+}
+
+/// 从来源拉取文档并替换对应命名空间，返回被替换的命名空间
+///
+/// 拉取或解析失败时保持现有定义不变。
+pub async fn refresh(source: &dyn MapperSource) -> Result<Vec<String>, DbError> {
+    let docs = source.fetch().await?;
+    let namespaces = crate::mapper_loader::replace_namespaces(&docs).map_err(|e| {
+        DbError::General(format!(
+            "Failed to refresh mappers from {}: {:#}",
+            source.name(),
+            e
+        ))
+    })?;
+    info!(
+        "mapper source refreshed: source={}, namespaces={:?}",
+        source.name(),
+        namespaces
+    );
+    Ok(namespaces)
+}
+
+/// 启动后台任务，按固定间隔从来源刷新 Mapper
+///
+/// 单次刷新失败只记录警告，下一个周期继续重试。
+pub fn spawn_refresh(
+    source: Arc<dyn MapperSource>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = refresh(source.as_ref()).await {
+                warn!(
+                    "mapper source refresh failed: source={}, error={}",
+                    source.name(),
+                    e
+                );
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapper_loader::find_mapper;
+
+    fn doc(body: &str) -> (String, String) {
+        (
+            "mem://remote_ns.xml".to_string(),
+            format!(
+                r#"<mapper namespace="remote_ns"><select id="get">{}</select></mapper>"#,
+                body
+            ),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_refresh_swaps_namespace() {
+        let source = StaticSource::new("mem", vec![doc("SELECT 1")]);
+        assert_eq!(refresh(&source).await.unwrap(), vec!["remote_ns"]);
+        let m = find_mapper("remote_ns.get", "mysql").unwrap();
+        assert_eq!(m.content.as_deref(), Some("SELECT 1"));
+
+        // 再次刷新不会因 ID 重复而失败，而是整体替换
+        let source = StaticSource::new("mem", vec![doc("SELECT 2")]);
+        refresh(&source).await.unwrap();
+        let m = find_mapper("remote_ns.get", "mysql").unwrap();
+        assert_eq!(m.content.as_deref(), Some("SELECT 2"));
+
+        // 解析失败时保留原有定义
+        let broken = StaticSource::new("mem", vec![("bad".to_string(), "<mapper".to_string())]);
+        assert!(refresh(&broken).await.is_err());
+        let m = find_mapper("remote_ns.get", "mysql").unwrap();
+        assert_eq!(m.content.as_deref(), Some("SELECT 2"));
+    }
+}
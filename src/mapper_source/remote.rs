@@ -0,0 +1,219 @@
+use crate::error::DbError;
+use crate::mapper_source::MapperSource;
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+
+fn http_error(source: &str, e: impl std::fmt::Display) -> DbError {
+    DbError::Connection(format!("{}: {}", source, e))
+}
+
+/// 从一组 HTTP(S) 地址拉取 Mapper XML，每个地址返回一个文档
+pub struct HttpSource {
+    name: String,
+    urls: Vec<String>,
+    client: reqwest::Client,
+}
+
+impl HttpSource {
+    pub fn new(urls: Vec<String>) -> Self {
+        Self {
+            name: format!("http({})", urls.join(",")),
+            urls,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// 使用自定义客户端（例如配置超时、证书或认证头）
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+#[async_trait]
+impl MapperSource for HttpSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(&self) -> Result<Vec<(String, String)>, DbError> {
+        let mut docs = Vec::with_capacity(self.urls.len());
+        for url in &self.urls {
+            let body = self
+                .client
+                .get(url)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| http_error(url, e))?
+                .text()
+                .await
+                .map_err(|e| http_error(url, e))?;
+            docs.push((url.clone(), body));
+        }
+        Ok(docs)
+    }
+}
+
+/// 从 Consul KV 的某个前缀下拉取所有 Mapper XML
+pub struct ConsulSource {
+    name: String,
+    address: String,
+    prefix: String,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct ConsulEntry {
+    #[serde(rename = "Key")]
+    key: String,
+    #[serde(rename = "Value")]
+    value: Option<String>,
+}
+
+impl ConsulSource {
+    /// `address` 形如 `http://127.0.0.1:8500`，`prefix` 形如 `uorm/mappers/`
+    pub fn new(address: impl Into<String>, prefix: impl Into<String>) -> Self {
+        let address = address.into().trim_end_matches('/').to_string();
+        let prefix = prefix.into();
+        Self {
+            name: format!("consul({}/{})", address, prefix),
+            address,
+            prefix,
+            token: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// 设置 ACL Token
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+}
+
+#[async_trait]
+impl MapperSource for ConsulSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(&self) -> Result<Vec<(String, String)>, DbError> {
+        let url = format!("{}/v1/kv/{}?recurse=true", self.address, self.prefix);
+        let mut req = self.client.get(&url);
+        if let Some(token) = &self.token {
+            req = req.header("X-Consul-Token", token);
+        }
+        let resp = req.send().await.map_err(|e| http_error(&self.name, e))?;
+        // 前缀下没有任何键时 Consul 返回 404
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let entries: Vec<ConsulEntry> = resp
+            .error_for_status()
+            .map_err(|e| http_error(&self.name, e))?
+            .json()
+            .await
+            .map_err(|e| http_error(&self.name, e))?;
+
+        let mut docs = Vec::new();
+        for entry in entries {
+            let Some(value) = entry.value else { continue };
+            let bytes = STANDARD
+                .decode(value)
+                .map_err(|e| http_error(&entry.key, e))?;
+            let xml = String::from_utf8(bytes).map_err(|e| http_error(&entry.key, e))?;
+            docs.push((format!("consul://{}", entry.key), xml));
+        }
+        Ok(docs)
+    }
+}
+
+/// 通过 etcd v3 的 HTTP/JSON 网关拉取某个前缀下的所有 Mapper XML
+pub struct EtcdSource {
+    name: String,
+    endpoint: String,
+    prefix: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct EtcdRangeResponse {
+    #[serde(default)]
+    kvs: Vec<EtcdKv>,
+}
+
+#[derive(Deserialize)]
+struct EtcdKv {
+    key: String,
+    #[serde(default)]
+    value: String,
+}
+
+impl EtcdSource {
+    /// `endpoint` 形如 `http://127.0.0.1:2379`
+    pub fn new(endpoint: impl Into<String>, prefix: impl Into<String>) -> Self {
+        let endpoint = endpoint.into().trim_end_matches('/').to_string();
+        let prefix = prefix.into();
+        Self {
+            name: format!("etcd({}/{})", endpoint, prefix),
+            endpoint,
+            prefix,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// 计算前缀查询的 range_end：最后一个字节加一
+    fn range_end(prefix: &[u8]) -> Vec<u8> {
+        let mut end = prefix.to_vec();
+        while let Some(last) = end.pop() {
+            if last < 0xff {
+                end.push(last + 1);
+                return end;
+            }
+        }
+        vec![0]
+    }
+}
+
+#[async_trait]
+impl MapperSource for EtcdSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(&self) -> Result<Vec<(String, String)>, DbError> {
+        let body = serde_json::json!({
+            "key": STANDARD.encode(self.prefix.as_bytes()),
+            "range_end": STANDARD.encode(Self::range_end(self.prefix.as_bytes())),
+        });
+        let resp: EtcdRangeResponse = self
+            .client
+            .post(format!("{}/v3/kv/range", self.endpoint))
+            .json(&body)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| http_error(&self.name, e))?
+            .json()
+            .await
+            .map_err(|e| http_error(&self.name, e))?;
+
+        let mut docs = Vec::with_capacity(resp.kvs.len());
+        for kv in resp.kvs {
+            let key = STANDARD
+                .decode(&kv.key)
+                .map(|k| String::from_utf8_lossy(&k).into_owned())
+                .map_err(|e| http_error(&self.name, e))?;
+            let value = STANDARD
+                .decode(&kv.value)
+                .map_err(|e| http_error(&key, e))?;
+            let xml = String::from_utf8(value).map_err(|e| http_error(&key, e))?;
+            docs.push((format!("etcd://{}", key), xml));
+        }
+        Ok(docs)
+    }
+}
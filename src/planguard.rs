@@ -0,0 +1,236 @@
+//! 执行计划回归检查
+//!
+//! 对一组语句（附样例参数）执行 `EXPLAIN`，将每张表的访问方式按语句 ID 记录到基线文件；
+//! 之后在 CI 中对预发布库执行 [`check`]，找出因表结构或统计信息变化而退化为全表扫描的语句。
+//!
+//! ```ignore
+//! let cases = vec![
+//!     PlanCase::new("user.find_by_email", &json!({"email": "a@b.c"})),
+//!     PlanCase::new("order.list_by_user", &json!({"user_id": 1})),
+//! ];
+//! // 确认计划无误后生成基线并提交到仓库
+//! planguard::record(driver.as_ref(), &cases, "plans.json").await?;
+//! // CI 中检查
+//! let report = planguard::check(driver.as_ref(), &cases, "plans.json").await?;
+//! assert!(report.is_ok(), "{}", report);
+//! ```
+//!
+//! 解析的是 MySQL 风格的 `EXPLAIN` 输出（`table`、`type`、`key` 列），`type = ALL` 视为全表扫描。
+
+use crate::error::DbError;
+use crate::mapper_loader::{snapshot, template_key};
+use crate::tpl::engine;
+use crate::udbc::driver::Driver;
+use crate::udbc::serializer::to_value;
+use crate::udbc::value::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// 检查用例：语句 ID 与渲染 `EXPLAIN` 所用的样例参数
+#[derive(Debug, Clone)]
+pub struct PlanCase {
+    sql_id: String,
+    args: Value,
+}
+
+impl PlanCase {
+    pub fn new<T: Serialize>(sql_id: impl Into<String>, args: &T) -> Self {
+        Self {
+            sql_id: sql_id.into(),
+            args: to_value(args),
+        }
+    }
+}
+
+/// 执行计划中对单张表的访问
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub table: String,
+    /// 访问方式，如 `const`、`ref`、`range`、`ALL`
+    pub access: String,
+    /// 使用的索引
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+impl PlanStep {
+    pub fn is_full_scan(&self) -> bool {
+        self.access.eq_ignore_ascii_case("ALL")
+    }
+}
+
+impl fmt::Display for PlanStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.table, self.access)?;
+        if let Some(key) = &self.key {
+            write!(f, "({})", key)?;
+        }
+        Ok(())
+    }
+}
+
+/// 基线：语句 ID 到执行计划的映射，以 JSON 保存
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub plans: BTreeMap<String, Vec<PlanStep>>,
+}
+
+impl Baseline {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DbError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| DbError::General(format!("read {}: {}", path.display(), e)))?;
+        serde_json::from_str(&text)
+            .map_err(|e| DbError::General(format!("parse {}: {}", path.display(), e)))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DbError> {
+        let path = path.as_ref();
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| DbError::General(format!("serialize plan baseline: {}", e)))?;
+        std::fs::write(path, text + "\n")
+            .map_err(|e| DbError::General(format!("write {}: {}", path.display(), e)))
+    }
+}
+
+/// 退化为全表扫描的语句
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanRegression {
+    pub sql_id: String,
+    /// 新出现全表扫描的表
+    pub tables: Vec<String>,
+    pub baseline: Vec<PlanStep>,
+    pub current: Vec<PlanStep>,
+}
+
+/// [`check`] 的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlanReport {
+    pub regressions: Vec<PlanRegression>,
+    /// 基线中没有记录的语句，未做检查
+    pub unrecorded: Vec<String>,
+}
+
+impl PlanReport {
+    /// 没有退化的语句
+    pub fn is_ok(&self) -> bool {
+        self.regressions.is_empty()
+    }
+}
+
+impl fmt::Display for PlanReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |steps: &[PlanStep]| {
+            steps
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        for r in &self.regressions {
+            writeln!(
+                f,
+                "{}: full table scan on {} (baseline [{}], now [{}])",
+                r.sql_id,
+                r.tables.join(", "),
+                join(&r.baseline),
+                join(&r.current)
+            )?;
+        }
+        for sql_id in &self.unrecorded {
+            writeln!(f, "{}: not in baseline", sql_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// 对每个用例执行 `EXPLAIN`，返回各语句的执行计划
+pub async fn explain(driver: &dyn Driver, cases: &[PlanCase]) -> Result<Baseline, DbError> {
+    let conn = driver.connection().await?;
+    let mut plans = BTreeMap::new();
+    for case in cases {
+        let (sql, params) = {
+            // 查找与渲染在同一视图内完成
+            let snapshot = snapshot();
+            let mapper = snapshot
+                .find_mapper(&case.sql_id, driver.r#type())
+                .ok_or_else(|| DbError::Query(format!("SQL ID not found: {}", case.sql_id)))?;
+            let content = mapper
+                .content
+                .as_deref()
+                .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", case.sql_id)))?;
+            let key = template_key(&case.sql_id, mapper.database_type.as_deref());
+            engine::render_statement(&key, content, &case.args, driver)?
+        };
+        let rows = conn.query(&format!("EXPLAIN {}", sql), &params).await?;
+        plans.insert(case.sql_id.clone(), rows.iter().map(plan_step).collect());
+    }
+    Ok(Baseline { plans })
+}
+
+/// 生成基线并写入 `path`，覆盖原有文件
+pub async fn record(
+    driver: &dyn Driver,
+    cases: &[PlanCase],
+    path: impl AsRef<Path>,
+) -> Result<Baseline, DbError> {
+    let baseline = explain(driver, cases).await?;
+    baseline.save(path)?;
+    Ok(baseline)
+}
+
+/// 与 `path` 中的基线比较，找出新出现全表扫描的语句
+pub async fn check(
+    driver: &dyn Driver,
+    cases: &[PlanCase],
+    path: impl AsRef<Path>,
+) -> Result<PlanReport, DbError> {
+    let baseline = Baseline::load(path)?;
+    let current = explain(driver, cases).await?;
+    Ok(compare(&baseline, &current))
+}
+
+/// 比较两组执行计划；只看全表扫描，索引选择等其他变化不视为退化
+pub fn compare(baseline: &Baseline, current: &Baseline) -> PlanReport {
+    let mut report = PlanReport::default();
+    for (sql_id, steps) in &current.plans {
+        let Some(before) = baseline.plans.get(sql_id) else {
+            report.unrecorded.push(sql_id.clone());
+            continue;
+        };
+        let tables: Vec<String> = steps
+            .iter()
+            .filter(|s| s.is_full_scan())
+            .filter(|s| {
+                !before
+                    .iter()
+                    .any(|b| b.table == s.table && b.is_full_scan())
+            })
+            .map(|s| s.table.clone())
+            .collect();
+        if !tables.is_empty() {
+            report.regressions.push(PlanRegression {
+                sql_id: sql_id.clone(),
+                tables,
+                baseline: before.clone(),
+                current: steps.clone(),
+            });
+        }
+    }
+    report
+}
+
+fn plan_step(row: &HashMap<String, Value>) -> PlanStep {
+    let text = |column: &str| match row.get(column) {
+        Some(Value::Str(s)) => Some(s.clone()),
+        Some(Value::Bytes(b)) => Some(String::from_utf8_lossy(b).into_owned()),
+        _ => None,
+    };
+    PlanStep {
+        table: text("table").unwrap_or_default(),
+        access: text("type").unwrap_or_default(),
+        key: text("key"),
+    }
+}
@@ -0,0 +1,33 @@
+//! 常用类型的统一导出
+//!
+//! 下游代码通过 `use uorm::prelude::*;` 引入会话、映射器、连接池管理、错误与值类型
+//! 以及过程宏，不必依赖 `udbc::driver` 等内部模块路径。
+//!
+//! ```ignore
+//! use uorm::prelude::*;
+//!
+//! let mapper = UORM.mapper("default").expect("pool not registered");
+//! let users: Vec<User> = mapper.list("user.list", &()).await?;
+//! ```
+
+pub use crate::error::DbError;
+pub use crate::executor::options::QueryOptions;
+pub use crate::executor::postprocess::RowPostProcessor;
+pub use crate::udbc::connection::Connection;
+pub use crate::udbc::driver::Driver;
+pub use crate::udbc::value::Value;
+pub use crate::{IntEnum, dao, sql};
+
+#[cfg(feature = "runtime")]
+pub use crate::driver_manager::{DriverManager, UORM};
+#[cfg(feature = "runtime")]
+pub use crate::executor::{
+    mapper::Mapper, multi::ResultSets, page::Page, pinned::PinnedSession,
+    prepared::PreparedMapperStatement, readonly::ReadOnlySession, session::Session,
+};
+#[cfg(feature = "runtime")]
+pub use crate::transaction::TransactionContext;
+#[cfg(feature = "mysql")]
+pub use crate::udbc_mysql::pool::MysqlDriver;
+#[cfg(feature = "runtime")]
+pub use crate::{mapper_assets, transactional};
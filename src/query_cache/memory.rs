@@ -0,0 +1,73 @@
+use super::CacheBackend;
+use crate::error::DbError;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+
+/// 进程内缓存后端，过期条目在读取时清理
+#[derive(Default)]
+pub struct MemoryCacheBackend {
+    entries: DashMap<String, (Vec<u8>, Option<Instant>)>,
+}
+
+impl MemoryCacheBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCacheBackend {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, DbError> {
+        let expired = match self.entries.get(key) {
+            Some(entry) => match entry.1 {
+                Some(deadline) if deadline <= Instant::now() => true,
+                _ => return Ok(Some(entry.0.clone())),
+            },
+            None => return Ok(None),
+        };
+        if expired {
+            self.entries.remove(key);
+        }
+        Ok(None)
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<(), DbError> {
+        let deadline = ttl.map(|ttl| Instant::now() + ttl);
+        self.entries.insert(key.to_string(), (value, deadline));
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), DbError> {
+        self.entries.remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_backend_expiry() {
+        let cache = MemoryCacheBackend::new();
+        cache.set("a", b"1".to_vec(), None).await.unwrap();
+        cache
+            .set("b", b"2".to_vec(), Some(Duration::ZERO))
+            .await
+            .unwrap();
+        assert_eq!(cache.get("a").await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(cache.get("b").await.unwrap(), None);
+        assert_eq!(cache.len(), 1);
+        cache.delete("a").await.unwrap();
+        assert!(cache.is_empty());
+    }
+}
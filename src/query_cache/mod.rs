@@ -0,0 +1,218 @@
+//! 查询结果的读穿透缓存
+//!
+//! `<select cacheKey="user:#{id}" ttl="300">` 声明的语句先查询 [`CacheBackend`]，
+//! 未命中时访问数据库并写回缓存；`<update>`/`<delete>` 等语句可通过
+//! `evicts="user:#{id}"` 在执行成功后失效相应的键。
+//! 未通过 [`set_cache_backend`] 设置后端时，这些属性不起作用。
+
+mod memory;
+#[cfg(feature = "redis-cache")]
+mod redis;
+
+pub use memory::MemoryCacheBackend;
+#[cfg(feature = "redis-cache")]
+pub use redis::RedisCacheBackend;
+
+use crate::error::DbError;
+use crate::executor::session;
+use crate::mapper_loader::SqlMapper;
+use crate::tpl::render_context::Context;
+use crate::udbc::serializer::to_value;
+use crate::udbc::value::Value;
+use async_trait::async_trait;
+use log::warn;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Duration;
+
+/// 缓存后端
+///
+/// 值为已编码的行数据，后端只需按键存取字节。
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, DbError>;
+
+    /// 写入缓存；`ttl` 为 `None` 时不过期
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<(), DbError>;
+
+    async fn delete(&self, key: &str) -> Result<(), DbError>;
+}
+
+static BACKEND: LazyLock<RwLock<Option<Arc<dyn CacheBackend>>>> =
+    LazyLock::new(|| RwLock::new(None));
+
+/// 设置全局缓存后端
+pub fn set_cache_backend(backend: Arc<dyn CacheBackend>) {
+    *BACKEND.write().unwrap() = Some(backend);
+}
+
+/// 移除全局缓存后端，之后的查询直接访问数据库
+pub fn clear_cache_backend() {
+    *BACKEND.write().unwrap() = None;
+}
+
+fn backend() -> Option<Arc<dyn CacheBackend>> {
+    BACKEND.read().unwrap().clone()
+}
+
+type Rows = Vec<HashMap<String, Value>>;
+
+/// 语句声明了 cacheKey 且设置了后端时，返回渲染后的缓存键
+pub(crate) fn read_key<T: Serialize>(mapper: &SqlMapper, args: &T) -> Option<String> {
+    let template = mapper.cache_key.as_deref()?;
+    backend()?;
+    Some(render_key(template, &to_value(args)))
+}
+
+/// 读取缓存的行数据；后端出错或数据无法解码时视为未命中
+pub(crate) async fn get(key: &str) -> Option<Rows> {
+    let backend = backend()?;
+    match backend.get(key).await {
+        Ok(Some(bytes)) => match serde_json::from_slice(&bytes) {
+            Ok(rows) => Some(rows),
+            Err(e) => {
+                warn!("query cache: failed to decode '{}': {}", key, e);
+                None
+            }
+        },
+        Ok(None) => None,
+        Err(e) => {
+            warn!("query cache: get '{}' failed: {}", key, e);
+            None
+        }
+    }
+}
+
+/// 写回缓存，失败时仅记录日志
+pub(crate) async fn put(key: &str, rows: &Rows, ttl: Option<Duration>) {
+    let Some(backend) = backend() else {
+        return;
+    };
+    let bytes = match serde_json::to_vec(rows) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("query cache: failed to encode '{}': {}", key, e);
+            return;
+        }
+    };
+    if let Err(e) = backend.set(key, bytes, ttl).await {
+        warn!("query cache: set '{}' failed: {}", key, e);
+    }
+}
+
+/// 语句执行成功后失效 `evicts` 声明的键
+///
+/// 当前任务处于连接池 `pool` 的事务中时，键登记到事务上，提交后才失效、回滚时丢弃，
+/// 避免并发的读取在提交前把旧数据重新写回缓存。
+pub(crate) async fn evict<T: Serialize>(pool: &str, mapper: &SqlMapper, args: &T) {
+    if mapper.evicts.is_empty() || backend().is_none() {
+        return;
+    }
+    let root = to_value(args);
+    let keys: Vec<String> = mapper
+        .evicts
+        .iter()
+        .map(|template| render_key(template, &root))
+        .collect();
+    if !session::defer_evictions(pool, &keys) {
+        evict_keys(keys).await;
+    }
+}
+
+/// 立即失效给定的键
+pub(crate) async fn evict_keys(keys: Vec<String>) {
+    let Some(backend) = backend() else {
+        return;
+    };
+    for key in keys {
+        if let Err(e) = backend.delete(&key).await {
+            warn!("query cache: evict '{}' failed: {}", key, e);
+        }
+    }
+}
+
+/// 将键模板中的 `#{path}` 替换为参数值
+fn render_key(template: &str, root: &Value) -> String {
+    let ctx = Context::new(root);
+    let mut out = String::with_capacity(template.len() + 16);
+    let mut rest = template;
+    while let Some(start) = rest.find("#{") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let path = rest[start + 2..start + len].trim();
+        // 参数本身是标量时，任何占位符都取该值
+        let value = match root {
+            Value::Map(_) => ctx.lookup(path),
+            scalar => scalar,
+        };
+        push_key_part(&mut out, value);
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn push_key_part(out: &mut String, value: &Value) {
+    use std::fmt::Write;
+    let _ = match value {
+        Value::Null => write!(out, "null"),
+        Value::Bool(v) => write!(out, "{}", v),
+        Value::I16(v) => write!(out, "{}", v),
+        Value::I32(v) => write!(out, "{}", v),
+        Value::I64(v) => write!(out, "{}", v),
+        Value::U8(v) => write!(out, "{}", v),
+        Value::F64(v) => write!(out, "{}", v),
+        Value::Str(v) => write!(out, "{}", v),
+        Value::Bytes(v) => v.iter().try_for_each(|b| write!(out, "{:02x}", b)),
+        #[cfg(feature = "chrono")]
+        Value::Date(v) => write!(out, "{}", v),
+        #[cfg(feature = "chrono")]
+        Value::Time(v) => write!(out, "{}", v),
+        #[cfg(feature = "chrono")]
+        Value::DateTime(v) => write!(out, "{}", v),
+        #[cfg(feature = "chrono")]
+        Value::DateTimeUtc(v) => write!(out, "{}", v.to_rfc3339()),
+        #[cfg(feature = "decimal")]
+        Value::Decimal(v) => write!(out, "{}", v),
+        Value::List(items) => {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                push_key_part(out, item);
+            }
+            Ok(())
+        }
+        Value::Map(_) => write!(out, "{{map}}"),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_key() {
+        let args = to_value(&HashMap::from([("id", 7)]));
+        assert_eq!(render_key("user:#{id}", &args), "user:7");
+        assert_eq!(render_key("user:#{ id }:x", &args), "user:7:x");
+        assert_eq!(render_key("user:#{missing}", &args), "user:null");
+        assert_eq!(render_key("user:#{id}", &Value::from("a")), "user:a");
+        assert_eq!(render_key("user:#{id", &args), "user:#{id");
+    }
+
+    #[test]
+    fn test_rows_roundtrip() {
+        let rows: Rows = vec![HashMap::from([
+            ("id".to_string(), Value::I64(1)),
+            ("name".to_string(), Value::Str("a".into())),
+            ("at".to_string(), Value::Null),
+        ])];
+        let bytes = serde_json::to_vec(&rows).unwrap();
+        let back: Rows = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(back, rows);
+    }
+}
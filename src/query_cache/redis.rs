@@ -0,0 +1,162 @@
+use super::CacheBackend;
+use crate::error::DbError;
+use async_trait::async_trait;
+use deadpool_redis::redis::AsyncCommands;
+use deadpool_redis::{Config, Pool, PoolConfig, Runtime};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 默认连接池大小
+const DEFAULT_POOL_SIZE: usize = 16;
+/// 默认 TTL 抖动比例：在声明的 TTL 基础上随机增加至多 10%
+const DEFAULT_JITTER: f64 = 0.1;
+
+/// 基于 Redis 的缓存后端（`redis-cache` feature）
+///
+/// 通过连接池访问 Redis，所有键带有统一前缀；写入时对 TTL 加入随机抖动，
+/// 避免同一批缓存在同一时刻集中过期。
+pub struct RedisCacheBackend {
+    pool: Pool,
+    prefix: String,
+    jitter: f64,
+    seed: AtomicU64,
+}
+
+impl RedisCacheBackend {
+    /// 以默认连接池大小连接 `redis://` URL
+    pub fn new(url: &str) -> Result<Self, DbError> {
+        Self::with_pool_size(url, DEFAULT_POOL_SIZE)
+    }
+
+    pub fn with_pool_size(url: &str, max_size: usize) -> Result<Self, DbError> {
+        let mut cfg = Config::from_url(url);
+        cfg.pool = Some(PoolConfig::new(max_size));
+        let pool = cfg
+            .create_pool(Some(Runtime::Tokio1))
+            .map_err(|e| DbError::Connection(format!("redis pool: {}", e)))?;
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x9e37_79b9_7f4a_7c15);
+        Ok(Self {
+            pool,
+            prefix: "uorm:".to_string(),
+            jitter: DEFAULT_JITTER,
+            seed: AtomicU64::new(seed | 1),
+        })
+    }
+
+    /// 设置键前缀（默认 `uorm:`）
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// 设置 TTL 抖动比例，取值范围 `[0, 1]`，0 表示不抖动
+    pub fn jitter(mut self, ratio: f64) -> Self {
+        self.jitter = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 读取并以 JSON 反序列化任意值
+    pub async fn get_json<V: DeserializeOwned>(&self, key: &str) -> Result<Option<V>, DbError> {
+        match self.get(key).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| DbError::Value(format!("redis cache decode '{}': {}", key, e))),
+            None => Ok(None),
+        }
+    }
+
+    /// 以 JSON 序列化任意值后写入
+    pub async fn set_json<V: Serialize + ?Sized>(
+        &self,
+        key: &str,
+        value: &V,
+        ttl: Option<Duration>,
+    ) -> Result<(), DbError> {
+        let bytes = serde_json::to_vec(value)
+            .map_err(|e| DbError::Value(format!("redis cache encode '{}': {}", key, e)))?;
+        self.set(key, bytes, ttl).await
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    async fn conn(&self) -> Result<deadpool_redis::Connection, DbError> {
+        self.pool
+            .get()
+            .await
+            .map_err(|e| DbError::Connection(format!("redis: {}", e)))
+    }
+
+    /// 加入抖动后的过期秒数（至少 1 秒）
+    fn jittered_secs(&self, ttl: Duration) -> u64 {
+        let base = ttl.as_secs_f64();
+        let extra = base * self.jitter * self.next_unit();
+        ((base + extra).round() as u64).max(1)
+    }
+
+    /// xorshift64 生成 [0, 1) 的随机数，仅用于打散过期时间
+    fn next_unit(&self) -> f64 {
+        let mut x = self.seed.load(Ordering::Relaxed);
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.seed.store(x, Ordering::Relaxed);
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn redis_err(e: deadpool_redis::redis::RedisError) -> DbError {
+    DbError::Driver(Box::new(e))
+}
+
+#[async_trait]
+impl CacheBackend for RedisCacheBackend {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, DbError> {
+        let mut conn = self.conn().await?;
+        conn.get(self.key(key)).await.map_err(redis_err)
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<(), DbError> {
+        let mut conn = self.conn().await?;
+        let key = self.key(key);
+        match ttl {
+            Some(ttl) => conn
+                .set_ex::<_, _, ()>(key, value, self.jittered_secs(ttl))
+                .await
+                .map_err(redis_err),
+            None => conn.set::<_, _, ()>(key, value).await.map_err(redis_err),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), DbError> {
+        let mut conn = self.conn().await?;
+        conn.del::<_, ()>(self.key(key)).await.map_err(redis_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jitter_bounds() {
+        // 创建连接池不会立即建立连接
+        let backend = RedisCacheBackend::new("redis://127.0.0.1:6379")
+            .unwrap()
+            .jitter(0.5);
+        for _ in 0..100 {
+            let secs = backend.jittered_secs(Duration::from_secs(100));
+            assert!((100..=150).contains(&secs), "{}", secs);
+        }
+        let backend = backend.jitter(0.0).prefix("app:");
+        assert_eq!(backend.jittered_secs(Duration::from_secs(100)), 100);
+        assert_eq!(backend.jittered_secs(Duration::ZERO), 1);
+        assert_eq!(backend.key("user:1"), "app:user:1");
+    }
+}
@@ -0,0 +1,216 @@
+//! Mapper 重载屏障
+//!
+//! 加载与重载在写锁内修改 Mapper 存储并清理模板缓存，渲染在读锁内查找语句与
+//! `<include>` 片段，因此一次渲染看到的语句与片段总是来自同一版本。
+//! 读锁在同一线程上可以嵌套；持有读锁的线程不能再加载或重载 Mapper。
+//!
+//! 并发模型测试基于 loom：`RUSTFLAGS="--cfg loom" cargo test --lib --no-default-features barrier`
+
+#[cfg(loom)]
+use loom::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::cell::Cell;
+use std::sync::{LazyLock, PoisonError};
+#[cfg(not(loom))]
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+#[cfg(loom)]
+loom::thread_local! {
+    /// 当前线程持有屏障的层数
+    static HELD: Cell<usize> = Cell::new(0);
+}
+#[cfg(not(loom))]
+std::thread_local! {
+    /// 当前线程持有屏障的层数
+    static HELD: Cell<usize> = const { Cell::new(0) };
+}
+
+#[cfg(loom)]
+loom::thread_local! {
+    /// 当前线程持有屏障时看到的代数
+    static GENERATION: Cell<u64> = Cell::new(0);
+}
+#[cfg(not(loom))]
+std::thread_local! {
+    /// 当前线程持有屏障时看到的代数
+    static GENERATION: Cell<u64> = const { Cell::new(0) };
+}
+
+/// 全局重载屏障
+pub(crate) static RELOAD: LazyLock<ReloadBarrier> = LazyLock::new(ReloadBarrier::new);
+
+/// 保护 Mapper 存储与模板缓存的读写锁，锁内的值为重载代数
+pub(crate) struct ReloadBarrier {
+    generation: RwLock<u64>,
+}
+
+/// 读锁；当前线程已持有屏障时不再重复加锁
+pub(crate) struct ReadGuard<'a> {
+    _guard: Option<RwLockReadGuard<'a, u64>>,
+    generation: u64,
+}
+
+/// 写锁，释放时重载代数加一
+pub(crate) struct WriteGuard<'a> {
+    guard: RwLockWriteGuard<'a, u64>,
+}
+
+impl ReloadBarrier {
+    pub(crate) fn new() -> Self {
+        Self {
+            generation: RwLock::new(0),
+        }
+    }
+
+    pub(crate) fn read(&self) -> ReadGuard<'_> {
+        if HELD.with(Cell::get) > 0 {
+            // 外层已持有读锁或写锁，期间代数不会变化
+            HELD.with(|h| h.set(h.get() + 1));
+            return ReadGuard {
+                _guard: None,
+                generation: GENERATION.with(Cell::get),
+            };
+        }
+        let guard = self
+            .generation
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let generation = *guard;
+        HELD.with(|h| h.set(1));
+        GENERATION.with(|g| g.set(generation));
+        ReadGuard {
+            _guard: Some(guard),
+            generation,
+        }
+    }
+
+    /// 获取写锁；当前线程已持有屏障时返回 `None`，否则会与自身死锁
+    pub(crate) fn write(&self) -> Option<WriteGuard<'_>> {
+        if HELD.with(Cell::get) > 0 {
+            return None;
+        }
+        let guard = self
+            .generation
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        HELD.with(|h| h.set(1));
+        GENERATION.with(|g| g.set(*guard));
+        Some(WriteGuard { guard })
+    }
+}
+
+impl ReadGuard<'_> {
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+impl Drop for ReadGuard<'_> {
+    fn drop(&mut self) {
+        HELD.with(|h| h.set(h.get() - 1));
+    }
+}
+
+impl Drop for WriteGuard<'_> {
+    fn drop(&mut self) {
+        *self.guard += 1;
+        HELD.with(|h| h.set(0));
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_read_and_write() {
+        let barrier = ReloadBarrier::new();
+        {
+            let outer = barrier.read();
+            let inner = barrier.read();
+            assert_eq!(outer.generation(), inner.generation());
+            // 持有读锁时获取写锁会死锁，直接拒绝
+            assert!(barrier.write().is_none());
+        }
+        let write = barrier.write().unwrap();
+        // 写锁内的渲染可以读取
+        assert_eq!(barrier.read().generation(), 0);
+        drop(write);
+        assert_eq!(barrier.read().generation(), 1);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::*;
+    use loom::sync::Arc;
+    use loom::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 重载分两步替换语句与片段，并发的渲染不会看到只替换了一半的状态
+    #[test]
+    fn test_reload_is_atomic_for_readers() {
+        loom::model(|| {
+            let barrier = Arc::new(ReloadBarrier::new());
+            let statement = Arc::new(AtomicUsize::new(0));
+            let fragment = Arc::new(AtomicUsize::new(0));
+
+            let writer = {
+                let (barrier, statement, fragment) =
+                    (barrier.clone(), statement.clone(), fragment.clone());
+                loom::thread::spawn(move || {
+                    let _guard = barrier.write().unwrap();
+                    statement.store(1, Ordering::Relaxed);
+                    fragment.store(1, Ordering::Relaxed);
+                })
+            };
+
+            let guard = barrier.read();
+            let version = statement.load(Ordering::Relaxed);
+            // 嵌套读取不重复加锁
+            let nested = barrier.read();
+            assert_eq!(fragment.load(Ordering::Relaxed), version);
+            assert_eq!(nested.generation(), version as u64);
+            assert_eq!(guard.generation(), version as u64);
+            drop(nested);
+            drop(guard);
+
+            writer.join().unwrap();
+            assert_eq!(barrier.read().generation(), 1);
+        });
+    }
+
+    /// 查找语句与渲染分别持有读锁，其间可能发生重载：渲染时代数变化则重新读取语句，
+    /// 渲染用到的语句与片段仍来自同一版本
+    #[test]
+    fn test_render_after_lookup_checks_generation() {
+        loom::model(|| {
+            let barrier = Arc::new(ReloadBarrier::new());
+            let statement = Arc::new(AtomicUsize::new(0));
+            let fragment = Arc::new(AtomicUsize::new(0));
+
+            let writer = {
+                let (barrier, statement, fragment) =
+                    (barrier.clone(), statement.clone(), fragment.clone());
+                loom::thread::spawn(move || {
+                    let _guard = barrier.write().unwrap();
+                    statement.store(1, Ordering::Relaxed);
+                    fragment.store(1, Ordering::Relaxed);
+                })
+            };
+
+            let (found, generation) = {
+                let guard = barrier.read();
+                (statement.load(Ordering::Relaxed), guard.generation())
+            };
+            let guard = barrier.read();
+            let version = if guard.generation() == generation {
+                found
+            } else {
+                statement.load(Ordering::Relaxed)
+            };
+            assert_eq!(fragment.load(Ordering::Relaxed), version);
+            drop(guard);
+
+            writer.join().unwrap();
+        });
+    }
+}
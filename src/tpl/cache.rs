@@ -1,38 +1,284 @@
-use crate::tpl::AstNode;
-use crate::tpl::parser::parse_template;
+use crate::mapper_loader::is_raw_statement;
+use crate::tpl::parser::{Template, compile};
 use dashmap::DashMap;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, LazyLock};
 
-#[derive(Clone)]
+/// 模板缓存默认最大条目数
+pub const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
 pub struct CachedTemplate {
-    pub ast: Arc<Vec<AstNode>>,
+    pub ast: Arc<Template>,
     pub content_hash: u64,
+    /// 按语句 ID 缓存的条目保存的内容，命中时与调用方传入的内容逐字节比较
+    #[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+    content: Option<Box<str>>,
+    /// 最近一次访问的逻辑时钟，用于 LRU 淘汰
+    last_used: AtomicU64,
+}
+
+/// 模板缓存统计信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct CacheStats {
+    /// 当前缓存条目数
+    pub entries: usize,
+    /// 最大条目数
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// 因超出容量被淘汰的条目数
+    pub evictions: u64,
+}
+
+/// 有界模板 AST 缓存
+///
+/// 超出容量时按最近访问时间淘汰最旧的一批条目（近似 LRU），
+/// 防止通过 `Session::query` 传入的大量一次性 SQL 导致内存无限增长。
+pub(crate) struct TemplateCache {
+    entries: DashMap<String, CachedTemplate>,
+    capacity: AtomicUsize,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
 }
 
 /// 缓存模板 AST
-pub(crate) static TEMPLATE_CACHE: LazyLock<DashMap<String, CachedTemplate>> =
-    LazyLock::new(DashMap::new);
+pub(crate) static TEMPLATE_CACHE: LazyLock<TemplateCache> =
+    LazyLock::new(|| TemplateCache::new(DEFAULT_CACHE_CAPACITY));
 
-pub(crate) fn get_ast(template_name: &str, template_content: &str) -> Arc<Vec<AstNode>> {
+fn hash_content(content: &str) -> u64 {
     let mut hasher = DefaultHasher::new();
-    template_content.hash(&mut hasher);
-    let new_hash = hasher.finish();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl TemplateCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            entries: DashMap::new(),
+            capacity: AtomicUsize::new(capacity.max(1)),
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// 按名称查找缓存的 AST（用于 `<include>` 等按名引用的场景）
+    pub(crate) fn get(&self, name: &str) -> Option<Arc<Template>> {
+        let cached = self.entries.get(name)?;
+        cached.last_used.store(self.tick(), Ordering::Relaxed);
+        Some(cached.ast.clone())
+    }
+
+    /// 查找并校验缓存条目，校验失败视为未命中
+    fn lookup(
+        &self,
+        name: &str,
+        valid: impl FnOnce(&CachedTemplate) -> bool,
+    ) -> Option<Arc<Template>> {
+        let hit = self.entries.get(name).filter(|c| valid(c)).map(|c| {
+            c.last_used.store(self.tick(), Ordering::Relaxed);
+            c.ast.clone()
+        });
+        match hit {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        hit
+    }
+
+    fn parse_and_insert(
+        &self,
+        name: &str,
+        content: &str,
+        hash: u64,
+        keep_content: bool,
+    ) -> Arc<Template> {
+        let ast = Arc::new(if is_raw_statement(name, content) {
+            Template::raw(content)
+        } else {
+            compile(content)
+        });
+        self.entries.insert(
+            name.to_string(),
+            CachedTemplate {
+                ast: ast.clone(),
+                content_hash: hash,
+                content: keep_content.then(|| content.into()),
+                last_used: AtomicU64::new(self.tick()),
+            },
+        );
+        self.evict_if_needed();
+        ast
+    }
+
+    /// 超出容量时淘汰最久未访问的条目，一次淘汰约 1/8 以摊薄扫描成本
+    fn evict_if_needed(&self) {
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        let len = self.entries.len();
+        if len <= capacity {
+            return;
+        }
+        let target = capacity - capacity / 8;
+        let mut ages: Vec<(u64, String)> = self
+            .entries
+            .iter()
+            .map(|e| (e.last_used.load(Ordering::Relaxed), e.key().clone()))
+            .collect();
+        ages.sort_unstable_by_key(|(t, _)| *t);
+        for (_, key) in ages.into_iter().take(len.saturating_sub(target)) {
+            if self.entries.remove(&key).is_some() {
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub(crate) fn get_ast(&self, template_name: &str, template_content: &str) -> Arc<Template> {
+        // 名称即内容本身（内联 SQL）时，键相等已保证内容一致，无需再计算一次内容哈希
+        if std::ptr::eq(template_name, template_content) {
+            if let Some(ast) = self.lookup(template_name, |_| true) {
+                return ast;
+            }
+            return self.parse_and_insert(
+                template_name,
+                template_content,
+                hash_content(template_content),
+                false,
+            );
+        }
+
+        let new_hash = hash_content(template_content);
+        if let Some(ast) = self.lookup(template_name, |c| c.content_hash == new_hash) {
+            return ast;
+        }
+        self.parse_and_insert(template_name, template_content, new_hash, false)
+    }
 
-    if let Some(cached) = TEMPLATE_CACHE.get(template_name) {
-        if cached.content_hash == new_hash {
-            return cached.ast.clone();
+    #[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+    pub(crate) fn get_ast_by_id(&self, stmt_id: &str, template_content: &str) -> Arc<Template> {
+        let unchanged = |c: &CachedTemplate| c.content.as_deref() == Some(template_content);
+        if let Some(ast) = self.lookup(stmt_id, unchanged) {
+            return ast;
         }
+        self.parse_and_insert(
+            stmt_id,
+            template_content,
+            hash_content(template_content),
+            true,
+        )
     }
 
-    let ast = Arc::new(parse_template(template_content));
-    TEMPLATE_CACHE.insert(
-        template_name.to_string(),
-        CachedTemplate {
-            ast: ast.clone(),
-            content_hash: new_hash,
-        },
-    );
-    ast
+    #[cfg(feature = "xml")]
+    pub(crate) fn remove(&self, name: &str) {
+        self.entries.remove(name);
+    }
+
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity.max(1), Ordering::Relaxed);
+        self.evict_if_needed();
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            capacity: self.capacity.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub(crate) fn get_ast(template_name: &str, template_content: &str) -> Arc<Template> {
+    TEMPLATE_CACHE.get_ast(template_name, template_content)
+}
+
+/// 按调用方提供的语句 ID 获取 AST
+///
+/// 条目保存一份内容，命中时与传入的内容逐字节比较而不对全文做哈希，适合超长的热点语句；
+/// 同一 ID 对应的内容变化时重新解析。
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+pub(crate) fn get_ast_by_id(stmt_id: &str, template_content: &str) -> Arc<Template> {
+    TEMPLATE_CACHE.get_ast_by_id(stmt_id, template_content)
+}
+
+/// 设置模板缓存的最大条目数，超出部分按 LRU 淘汰
+pub fn set_cache_capacity(max_entries: usize) {
+    TEMPLATE_CACHE.set_capacity(max_entries);
+}
+
+/// 获取模板缓存统计信息
+pub fn cache_stats() -> CacheStats {
+    TEMPLATE_CACHE.stats()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction() {
+        let cache = TemplateCache::new(8);
+        for i in 0..8 {
+            let sql = format!("select {}", i);
+            cache.get_ast(&sql, &sql);
+        }
+        // 访问最早的条目，使其成为最近使用
+        cache.get_ast("select 0", "select 0");
+        cache.get_ast("select 8", "select 8");
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 7);
+        assert_eq!(stats.evictions, 2);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 9);
+        assert!(cache.get("select 0").is_some());
+        assert!(cache.get("select 1").is_none());
+        assert!(cache.get("select 2").is_none());
+    }
+
+    #[test]
+    fn test_shrink_capacity() {
+        let cache = TemplateCache::new(100);
+        for i in 0..50 {
+            let sql = format!("select {}", i);
+            cache.get_ast(&sql, &sql);
+        }
+        cache.set_capacity(16);
+        assert!(cache.stats().entries <= 16);
+        assert!(cache.get("select 49").is_some());
+    }
+
+    #[test]
+    fn test_get_by_id_detects_same_length_change() {
+        let cache = TemplateCache::new(8);
+        let a = cache.get_ast_by_id("user.get", "select a from t");
+        assert!(Arc::ptr_eq(
+            &a,
+            &cache.get_ast_by_id("user.get", "select a from t")
+        ));
+        // 长度相同、内容不同时重新解析
+        let b = cache.get_ast_by_id("user.get", "select b from t");
+        assert!(!Arc::ptr_eq(&a, &b));
+
+        // 超长内容只在中段变化时同样重新解析
+        let long = format!("select {} from t", "x, ".repeat(5000));
+        let middle = long.len() / 2;
+        let changed = format!("{}y{}", &long[..middle], &long[middle + 1..]);
+        assert_eq!(long.len(), changed.len());
+        let c = cache.get_ast_by_id("user.list", &long);
+        assert!(Arc::ptr_eq(&c, &cache.get_ast_by_id("user.list", &long)));
+        assert!(!Arc::ptr_eq(
+            &c,
+            &cache.get_ast_by_id("user.list", &changed)
+        ));
+    }
 }
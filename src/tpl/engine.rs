@@ -1,38 +1,591 @@
+use crate::error::DbError;
+use crate::executor::digest::logical_id;
+use crate::executor::options::query_options;
+use crate::mapper_loader::{SqlMapper, statement_for_key};
+use crate::tpl::ParamNaming;
+use crate::tpl::barrier::{RELOAD, ReadGuard};
+use crate::tpl::options::render_options;
+use crate::tpl::parser::Template;
 use crate::tpl::render::RenderBuffer;
 use crate::tpl::render_context::Context;
 use crate::tpl::{cache, render};
 use crate::udbc::driver::Driver;
 use crate::udbc::serializer::to_value;
 use crate::udbc::value::Value;
+use std::sync::Arc;
+
+/// 渲染出的 SQL 及其参数
+pub(crate) type Rendered = (String, Vec<(String, Value)>);
 
 /// 渲染模板，返回 SQL 和参数
+#[cfg_attr(not(any(feature = "runtime", feature = "chrono")), allow(dead_code))]
 pub fn render_template<T: serde::Serialize>(
     template_name: &str,
     template_content: &str,
     param: &T,
     driver: &dyn Driver,
-) -> (String, Vec<(String, Value)>) {
+) -> Result<(String, Vec<(String, Value)>), DbError> {
+    render_template_with(
+        template_name,
+        template_content,
+        param,
+        driver,
+        default_naming(driver),
+    )
+}
+
+/// 同 [`render_template`]，按 `naming` 匹配参数名
+#[cfg_attr(not(any(feature = "runtime", feature = "chrono")), allow(dead_code))]
+pub fn render_template_with<T: serde::Serialize>(
+    template_name: &str,
+    template_content: &str,
+    param: &T,
+    driver: &dyn Driver,
+    naming: ParamNaming,
+) -> Result<(String, Vec<(String, Value)>), DbError> {
+    let _snapshot = RELOAD.read();
     // 获取 AST（缓存）
-    let ast = cache::get_ast(template_name, template_content);
+    let template = cache::get_ast(template_name, template_content);
+    render_ast(
+        &template,
+        None,
+        template_content.len(),
+        param,
+        driver,
+        naming,
+    )
+}
+
+/// 按语句 ID 渲染模板，命中缓存时跳过对 SQL 内容的哈希
+pub fn render_statement<T: serde::Serialize>(
+    stmt_id: &str,
+    template_content: &str,
+    param: &T,
+    driver: &dyn Driver,
+) -> Result<(String, Vec<(String, Value)>), DbError> {
+    render_statement_with(
+        stmt_id,
+        template_content,
+        param,
+        driver,
+        default_naming(driver),
+    )
+}
+
+/// 同 [`render_statement`]，按 `naming` 匹配参数名
+pub fn render_statement_with<T: serde::Serialize>(
+    stmt_id: &str,
+    template_content: &str,
+    param: &T,
+    driver: &dyn Driver,
+    naming: ParamNaming,
+) -> Result<(String, Vec<(String, Value)>), DbError> {
+    let _snapshot = RELOAD.read();
+    let template = cache::get_ast_by_id(stmt_id, template_content);
+    render_ast(
+        &template,
+        Some(stmt_id),
+        template_content.len(),
+        param,
+        driver,
+        naming,
+    )
+}
+
+/// 渲染 Mapper 语句，`generation` 为调用方查找该语句时的重载代数
+///
+/// 此后发生过重载时按当前版本重新查找语句，避免旧语句与新的 `<include>` 片段混用；
+/// 未重载时直接渲染调用方传入的内容。
+pub(crate) fn render_mapper_statement<T: serde::Serialize>(
+    stmt_id: &str,
+    template_content: &str,
+    generation: u64,
+    param: &T,
+    driver: &dyn Driver,
+    naming: ParamNaming,
+) -> Result<(String, Vec<(String, Value)>), DbError> {
+    let snapshot = RELOAD.read();
+    let current = reloaded_statement(&snapshot, stmt_id, generation);
+    let template_content = current_content(&current, template_content);
+    render_statement_with(stmt_id, template_content, param, driver, naming)
+}
+
+/// 查找后发生过重载时，语句在当前版本中的定义
+fn reloaded_statement(
+    snapshot: &ReadGuard,
+    stmt_id: &str,
+    generation: u64,
+) -> Option<Arc<SqlMapper>> {
+    if snapshot.generation() == generation {
+        return None;
+    }
+    statement_for_key(stmt_id)
+}
+
+fn current_content<'a>(current: &'a Option<Arc<SqlMapper>>, template_content: &'a str) -> &'a str {
+    current
+        .as_ref()
+        .and_then(|mapper| mapper.content.as_deref())
+        .unwrap_or(template_content)
+}
+
+/// 连接池（[`Driver::query_options`]）或全局查询选项中设置的参数名匹配方式
+pub(crate) fn default_naming(driver: &dyn Driver) -> ParamNaming {
+    driver
+        .query_options()
+        .param_naming
+        .or_else(|| query_options().param_naming)
+        .unwrap_or_default()
+}
+
+/// 将语句解析进模板缓存，并以必填参数均为 `NULL` 的参数渲染一次；该次渲染的错误忽略
+#[cfg(feature = "runtime")]
+pub(crate) fn warm_up(stmt_id: &str, template_content: &str, driver: &dyn Driver) {
+    let _snapshot = RELOAD.read();
+    let template = cache::get_ast_by_id(stmt_id, template_content);
+    let args = Value::Map(
+        template
+            .required_params
+            .iter()
+            .map(|name| (name.clone(), Value::Null))
+            .collect(),
+    );
+    let _ = render_ast(
+        &template,
+        Some(stmt_id),
+        template_content.len(),
+        &args,
+        driver,
+        default_naming(driver),
+    );
+}
 
+fn render_ast<T: serde::Serialize>(
+    template: &Template,
+    stmt_id: Option<&str>,
+    capacity: usize,
+    param: &T,
+    driver: &dyn Driver,
+    naming: ParamNaming,
+) -> Result<(String, Vec<(String, Value)>), DbError> {
     // 序列化参数为 Value
     let value = to_value(param);
+    render_value(template, stmt_id, capacity, &value, driver, naming)
+}
 
+fn render_value(
+    template: &Template,
+    stmt_id: Option<&str>,
+    capacity: usize,
+    value: &Value,
+    driver: &dyn Driver,
+    naming: ParamNaming,
+) -> Result<(String, Vec<(String, Value)>), DbError> {
     // 创建渲染上下文
     let mut buf = RenderBuffer {
-        sql: String::with_capacity(template_content.len()),
+        sql: String::with_capacity(capacity),
         params: Vec::with_capacity(10),
         driver,
         param_count: 0,
+        options: render_options(),
+    };
+    if buf.options.strict {
+        check_required(template, value, naming)?;
+    }
+
+    let mut ctx = Context::new(value).with_naming(naming);
+    render::render(template, &mut ctx, &mut buf)?;
+    render::encode_nested_params(&mut buf)?;
+    // 原样执行的语句不做任何改写
+    if buf.options.normalize_whitespace && !template.is_raw() {
+        buf.normalize_whitespace();
+    }
+    prepend_comment(&mut buf, stmt_id);
+
+    Ok((buf.sql, buf.params))
+}
+
+/// 按 [`RenderOptions::sql_comment`](crate::tpl::RenderOptions::sql_comment) 在 SQL 前加注释
+fn prepend_comment(buf: &mut RenderBuffer, stmt_id: Option<&str>) {
+    if let Some(comment) = &buf.options.sql_comment
+        && let Some(comment) = comment.render(stmt_id.map(logical_id), trace_id().as_deref())
+    {
+        buf.sql.insert_str(0, &comment);
+    }
+}
+
+/// 预先解析的语句模板，见 [`PreparedMapperStatement`](crate::executor::prepared::PreparedMapperStatement)
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+pub(crate) struct PreparedTemplate {
+    template: Arc<Template>,
+    capacity: usize,
+    /// 只含文本与 `#{}` 参数的模板预先渲染出的 SQL 与各参数名，以及渲染时是否规整了空白
+    fixed: Option<(String, Vec<String>, bool)>,
+}
+
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+impl PreparedTemplate {
+    pub(crate) fn template(&self) -> &Template {
+        &self.template
+    }
+}
+
+/// 解析语句模板；模板只含文本与 `#{}` 参数时预先渲染出 SQL，执行时只需取参数值
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+pub(crate) fn prepare_statement(
+    stmt_id: &str,
+    template_content: &str,
+    driver: &dyn Driver,
+) -> PreparedTemplate {
+    use crate::tpl::AstNode;
+
+    let _snapshot = RELOAD.read();
+    let template = cache::get_ast_by_id(stmt_id, template_content);
+    let fixed = template
+        .iter()
+        .all(|node| matches!(node, AstNode::Text(_) | AstNode::Var(_)))
+        .then(|| {
+            let mut buf = RenderBuffer {
+                sql: String::with_capacity(template_content.len()),
+                params: Vec::new(),
+                driver,
+                param_count: 0,
+                options: render_options(),
+            };
+            for node in template.iter() {
+                match node {
+                    AstNode::Var(name) => render::push_param(&mut buf, name.clone(), Value::Null),
+                    AstNode::Text(text) => buf.sql.push_str(text),
+                    _ => unreachable!(),
+                }
+            }
+            let normalized = buf.options.normalize_whitespace;
+            if normalized {
+                buf.normalize_whitespace();
+            }
+            let names = buf.params.into_iter().map(|(name, _)| name).collect();
+            (buf.sql, names, normalized)
+        });
+    PreparedTemplate {
+        template,
+        capacity: template_content.len(),
+        fixed,
+    }
+}
+
+/// 渲染预先解析的语句
+///
+/// 预先渲染出的 SQL 在参数值为列表（需展开为多个占位符）或渲染选项已变化时不可用，
+/// 此时按模板完整渲染。
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+pub(crate) fn render_prepared(
+    prepared: &PreparedTemplate,
+    stmt_id: &str,
+    value: &Value,
+    driver: &dyn Driver,
+    naming: ParamNaming,
+) -> Result<(String, Vec<(String, Value)>), DbError> {
+    let _snapshot = RELOAD.read();
+    let render_full = || {
+        render_value(
+            &prepared.template,
+            Some(stmt_id),
+            prepared.capacity,
+            value,
+            driver,
+            naming,
+        )
+    };
+    let options = render_options();
+    let Some((sql, names, normalized)) = &prepared.fixed else {
+        return render_full();
     };
+    if *normalized != options.normalize_whitespace {
+        return render_full();
+    }
+    if options.strict {
+        check_required(&prepared.template, value, naming)?;
+    }
+
+    let ctx = Context::new(value).with_naming(naming);
+    let mut params = Vec::with_capacity(names.len());
+    for name in names {
+        match ctx.lookup(name) {
+            Value::List(_) => return render_full(),
+            v => params.push((name.clone(), v.clone())),
+        }
+    }
+    let mut buf = RenderBuffer {
+        sql: sql.clone(),
+        params,
+        driver,
+        param_count: names.len(),
+        options,
+    };
+    render::encode_nested_params(&mut buf)?;
+    prepend_comment(&mut buf, Some(stmt_id));
+    Ok((buf.sql, buf.params))
+}
+
+/// 模板引用的列表参数超过 `limit` 个元素时，按该列表切分参数，每组渲染为一条语句
+///
+/// 只切分模板（含 `<if>` 内部）直接引用的列表，`<for>` 循环体内的引用不计；
+/// 同时有多个列表超限，或语句的结果不能按组拼接（见 [`split_template`]）时返回错误。
+/// 无需切分时返回 `None`。`generation` 为 Mapper 语句的查找代数，见 [`render_mapper_statement`]。
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+pub(crate) fn split_oversized_lists<T: serde::Serialize>(
+    stmt_id: Option<&str>,
+    template_content: &str,
+    generation: Option<u64>,
+    param: &T,
+    limit: usize,
+    driver: &dyn Driver,
+    naming: ParamNaming,
+) -> Result<Option<Vec<Rendered>>, DbError> {
+    let snapshot = RELOAD.read();
+    let current = match (stmt_id, generation) {
+        (Some(id), Some(generation)) => reloaded_statement(&snapshot, id, generation),
+        _ => None,
+    };
+    let template_content = current_content(&current, template_content);
+    let template = match stmt_id {
+        Some(id) => cache::get_ast_by_id(id, template_content),
+        None => cache::get_ast(template_content, template_content),
+    };
+    let value = to_value(param);
+    let Some(chunks) = split_template(&template, &value, limit, naming)? else {
+        return Ok(None);
+    };
+    chunks
+        .iter()
+        .map(|chunk| {
+            render_value(
+                &template,
+                stmt_id,
+                template_content.len(),
+                chunk,
+                driver,
+                naming,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// 同 [`split_oversized_lists`]，模板已解析、参数已序列化，返回切分出的各组参数
+///
+/// 按组执行后拼接结果只对逐行筛选的语句成立：`NOT IN`（每组都会放过其余组排除的行）、
+/// 聚合函数与 `GROUP BY`/`HAVING`/`DISTINCT`（每组各自聚合）、`ORDER BY`/`LIMIT`/`OFFSET`
+/// （每组各自排序、截取）都会得到错误的结果，这些语句不切分，返回错误。
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+pub(crate) fn split_template(
+    template: &Template,
+    value: &Value,
+    limit: usize,
+    naming: ParamNaming,
+) -> Result<Option<Vec<Value>>, DbError> {
+    let mut paths = Vec::new();
+    collect_list_paths(template, &mut paths);
+    paths.sort_unstable();
+    paths.dedup();
+
+    let ctx = Context::new(value).with_naming(naming);
+    let oversized: Vec<&str> = paths
+        .iter()
+        .map(String::as_str)
+        .filter(|p| matches!(ctx.lookup(p), Value::List(items) if items.len() > limit))
+        .collect();
+    match oversized.as_slice() {
+        [] => Ok(None),
+        [path] => {
+            let Value::List(items) = ctx.lookup(path) else {
+                return Ok(None);
+            };
+            if let Some(clause) = unsplittable_clause(template) {
+                return Err(DbError::Template(format!(
+                    "cannot split statement: list {} exceeds {} items and the results of {} cannot be concatenated",
+                    path, limit, clause
+                )));
+            }
+            items
+                .chunks(limit.max(1))
+                .map(|chunk| replace_list(value, path, chunk.to_vec(), naming))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Some)
+        }
+        many => Err(DbError::Template(format!(
+            "cannot split statement: lists {} all exceed {} items",
+            many.join(", "),
+            limit
+        ))),
+    }
+}
 
-    let mut ctx = Context::new(&value);
-    render::render(&ast, &mut ctx, &mut buf);
+/// 模板文本中使切分后的结果不能按组拼接的子句，见 [`split_template`]
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+fn unsplittable_clause(template: &Template) -> Option<&'static str> {
+    let mut text = String::new();
+    collect_text(template, &mut text);
+    let words = sql_words(&text);
+    let followed_by = |i: usize, next: &str| words.get(i + 1).is_some_and(|w| w == next);
+    words
+        .iter()
+        .enumerate()
+        .find_map(|(i, word)| match word.as_str() {
+            "NOT" if followed_by(i, "IN") => Some("NOT IN"),
+            "COUNT" | "SUM" | "AVG" | "MIN" | "MAX" | "GROUP_CONCAT" | "STRING_AGG"
+                if followed_by(i, "(") =>
+            {
+                Some("aggregate functions")
+            }
+            "GROUP" if followed_by(i, "BY") => Some("GROUP BY"),
+            "HAVING" => Some("HAVING"),
+            "DISTINCT" => Some("DISTINCT"),
+            "ORDER" if followed_by(i, "BY") => Some("ORDER BY"),
+            "LIMIT" => Some("LIMIT"),
+            "OFFSET" => Some("OFFSET"),
+            _ => None,
+        })
+}
 
-    (buf.sql, buf.params)
+/// 拼接模板（含 `<if>` 内部）的文本，各段之间以空格分隔
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+fn collect_text(nodes: &[crate::tpl::AstNode], out: &mut String) {
+    use crate::tpl::AstNode;
+    for node in nodes {
+        match node {
+            AstNode::Text(text) | AstNode::Raw(text) => {
+                out.push_str(text);
+                out.push(' ');
+            }
+            AstNode::If { body, .. } | AstNode::For { body, .. } => collect_text(body, out),
+            _ => out.push(' '),
+        }
+    }
+}
+
+/// 将 SQL 文本拆分为大写的单词与 `(`，跳过字符串、带引号的标识符与注释
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+fn sql_words(sql: &str) -> Vec<String> {
+    let bytes = sql.as_bytes();
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |p| i + p);
+            }
+            b'#' => i = sql[i..].find('\n').map_or(bytes.len(), |p| i + p),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |p| i + p + 4);
+            }
+            b'(' => {
+                words.push("(".to_string());
+                i += 1;
+            }
+            b if b.is_ascii_alphanumeric() || b == b'_' => {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                words.push(sql[start..i].to_ascii_uppercase());
+            }
+            _ => i += 1,
+        }
+    }
+    words
+}
+
+/// 收集可能展开为列表的参数路径
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+fn collect_list_paths(nodes: &[crate::tpl::AstNode], out: &mut Vec<String>) {
+    use crate::tpl::AstNode;
+    for node in nodes {
+        match node {
+            AstNode::Var(name) | AstNode::Call { name, .. } | AstNode::Default { name, .. } => {
+                out.push(name.clone())
+            }
+            AstNode::For { collection, .. } => out.push(collection.clone()),
+            AstNode::If { body, .. } => collect_list_paths(body, out),
+            AstNode::Text(_)
+            | AstNode::Include { .. }
+            | AstNode::IncludeEnv { .. }
+            | AstNode::With { .. }
+            | AstNode::Ident(_)
+            | AstNode::Set { .. }
+            | AstNode::PoolVar { .. }
+            | AstNode::Raw(_) => {}
+        }
+    }
+}
+
+/// 复制参数，将 `path`（以 `.` 分隔的映射路径）处的列表替换为 `items`
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+fn replace_list(
+    root: &Value,
+    path: &str,
+    items: Vec<Value>,
+    naming: ParamNaming,
+) -> Result<Value, DbError> {
+    let mut root = root.clone();
+    let mut current = &mut root;
+    for part in path.split('.') {
+        current = match current {
+            Value::Map(m) => match naming.find_entry(m, part) {
+                Some((key, _)) => {
+                    let key = key.clone();
+                    m.get_mut(&key)
+                }
+                None => None,
+            },
+            _ => None,
+        }
+        .ok_or_else(|| DbError::Template(format!("cannot split list parameter '{}'", path)))?;
+    }
+    *current = Value::List(items);
+    Ok(root)
+}
+
+#[cfg(feature = "runtime")]
+fn trace_id() -> Option<String> {
+    crate::executor::trace::current_trace_id()
+}
+
+#[cfg(not(feature = "runtime"))]
+fn trace_id() -> Option<String> {
+    None
+}
+
+/// 参数为结构体或映射时，检查模板的必需参数是否齐全，一次列出全部缺失项
+fn check_required(template: &Template, value: &Value, naming: ParamNaming) -> Result<(), DbError> {
+    let Value::Map(map) = value else {
+        return Ok(());
+    };
+    let missing: Vec<&str> = template
+        .required_params
+        .iter()
+        .filter(|p| naming.find(map, p).is_none())
+        .map(String::as_str)
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(DbError::Template(format!(
+            "missing required parameters: {}",
+            missing.join(", ")
+        )))
+    }
 }
 
 /// 卸载模板缓存
+#[cfg(feature = "xml")]
 pub fn remove_template(template_name: &str) {
     cache::TEMPLATE_CACHE.remove(template_name);
 }
@@ -45,6 +598,7 @@ mod tests {
     use crate::udbc::driver::Driver;
     use crate::udbc::value::Value;
     use serde::Serialize;
+    use std::collections::HashMap;
     use std::sync::Arc;
 
     struct MockDriver;
@@ -61,6 +615,9 @@ mod tests {
         fn placeholder(&self, _seq: usize, _name: &str) -> String {
             "?".to_string()
         }
+        fn template_var(&self, name: &str) -> Option<&str> {
+            (name == "schema").then_some("app")
+        }
         async fn connection(&self) -> Result<Arc<dyn Connection>, DbError> {
             todo!()
         }
@@ -75,6 +632,68 @@ mod tests {
         age: u8,
     }
 
+    #[test]
+    fn test_render_pool_vars() {
+        let user = User {
+            name: "a".to_string(),
+            age: 1,
+        };
+        let tpl = "select * from ${schema}.${prefix ?: t_}user where name = #{name}";
+        let (sql, params) = render_template("test_pool_vars", tpl, &user, &MockDriver).unwrap();
+        assert_eq!(sql, "select * from app.t_user where name = ?");
+        assert_eq!(params.len(), 1);
+
+        // 连接池变量不从语句参数中读取
+        let err = render_template(
+            "test_pool_vars_missing",
+            "select ${name}",
+            &user,
+            &MockDriver,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("'${name}' is not configured for pool 'mock'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_render_ident() {
+        let tpl = "select * from ${ident(table)} order by ${ ident(col) } limit #{n}";
+        let args = HashMap::from([
+            ("table", Value::Str("app.us\"er".to_string())),
+            ("col", Value::Str("created at".to_string())),
+            ("n", Value::I32(10)),
+        ]);
+        let (sql, params) = render_template("test_ident", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(
+            sql,
+            r#"select * from "app"."us""er" order by "created at" limit ?"#
+        );
+        assert_eq!(params.len(), 1);
+
+        for bad in [
+            Value::Str(String::new()),
+            Value::Str("a..b".to_string()),
+            Value::Str("a\0b".to_string()),
+            Value::Str("x".repeat(129)),
+            Value::I32(1),
+        ] {
+            let args = HashMap::from([("table", bad)]);
+            assert!(
+                render_template(
+                    "test_ident_bad",
+                    "select * from ${ident(table)}",
+                    &args,
+                    &MockDriver
+                )
+                .is_err()
+            );
+        }
+    }
+
     #[test]
     fn test_render_simple_sql() {
         let tpl = "select * from user where name = #{name} and age = #{age}";
@@ -84,7 +703,7 @@ mod tests {
         };
         let driver = MockDriver;
 
-        let (sql, params) = render_template("test_simple", tpl, &user, &driver);
+        let (sql, params) = render_template("test_simple", tpl, &user, &driver).unwrap();
 
         assert_eq!(sql, "select * from user where name = ? and age = ?");
         assert_eq!(params.len(), 2);
@@ -123,7 +742,7 @@ mod tests {
             age: 20,
             name: Some("tom".to_string()),
         };
-        let (sql, params) = render_template("test_if_1", tpl, &args, &MockDriver);
+        let (sql, params) = render_template("test_if_1", tpl, &args, &MockDriver).unwrap();
         assert_eq!(
             sql,
             "select * from user where 1=1 and status = 1 and type = 'adult' and name = ?"
@@ -137,7 +756,7 @@ mod tests {
             age: 10,
             name: None,
         };
-        let (sql, params) = render_template("test_if_2", tpl, &args, &MockDriver);
+        let (sql, params) = render_template("test_if_2", tpl, &args, &MockDriver).unwrap();
         assert_eq!(sql, "select * from user where 1=1");
         assert_eq!(params.len(), 0);
     }
@@ -153,7 +772,7 @@ mod tests {
 
         let args = ForArgs { ids: vec![1, 2, 3] };
 
-        let (sql, params) = render_template("test_for", tpl, &args, &MockDriver);
+        let (sql, params) = render_template("test_for", tpl, &args, &MockDriver).unwrap();
         assert_eq!(sql, "select * from user where id in (?,?,?)");
         assert_eq!(params.len(), 3);
 
@@ -169,11 +788,112 @@ mod tests {
 
         // Empty list
         let args = ForArgs { ids: vec![] };
-        let (sql, params) = render_template("test_for_empty", tpl, &args, &MockDriver);
+        let (sql, params) = render_template("test_for_empty", tpl, &args, &MockDriver).unwrap();
         assert_eq!(sql, "select * from user where id in "); // Note: usually empty IN clause is invalid SQL, but engine renders what's asked
         assert_eq!(params.len(), 0);
     }
 
+    #[test]
+    fn test_for_if_empty() {
+        let tpl = "select * from user where id in <for item=\"id\" collection=\"ids\" open=\"(\" sep=\",\" close=\")\" ifEmpty=\"(NULL)\">#{id}</for>";
+
+        let args = ForArgs { ids: vec![] };
+        let (sql, params) = render_template("test_for_if_empty", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(sql, "select * from user where id in (NULL)");
+        assert!(params.is_empty());
+
+        let args = ForArgs { ids: vec![5] };
+        let (sql, _) = render_template("test_for_if_empty", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(sql, "select * from user where id in (?)");
+    }
+
+    #[test]
+    fn test_for_strict_empty_errors() {
+        use crate::tpl::options::RenderOptions;
+        use crate::tpl::parser::parse_template;
+        use crate::tpl::render::{RenderBuffer, render};
+        use crate::tpl::render_context::Context;
+        use crate::udbc::serializer::to_value;
+
+        let ast = parse_template("id in <for item=\"id\" collection=\"ids\">#{id}</for>");
+        let value = to_value(&ForArgs { ids: vec![] });
+        let mut buf = RenderBuffer {
+            sql: String::new(),
+            params: Vec::new(),
+            driver: &MockDriver,
+            param_count: 0,
+            options: RenderOptions {
+                strict: true,
+                ..Default::default()
+            },
+        };
+        let err = render(&ast, &mut Context::new(&value), &mut buf).unwrap_err();
+        assert!(matches!(err, DbError::Template(_)));
+        assert!(err.to_string().contains("ids"));
+    }
+
+    #[test]
+    fn test_list_param_expansion() {
+        let tpl = "select * from user where id in #{ids} and status = #{status}";
+
+        #[derive(Serialize)]
+        struct Args {
+            ids: Vec<i64>,
+            status: i32,
+        }
+
+        let args = Args {
+            ids: vec![7, 8, 9],
+            status: 1,
+        };
+        let (sql, params) = render_template("test_list_expand", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(
+            sql,
+            "select * from user where id in (?, ?, ?) and status = ?"
+        );
+        assert_eq!(params.len(), 4);
+        assert_eq!(params[0], ("ids[0]".to_string(), Value::I64(7)));
+        assert_eq!(params[2], ("ids[2]".to_string(), Value::I64(9)));
+        assert_eq!(params[3], ("status".to_string(), Value::I32(1)));
+
+        let args = Args {
+            ids: vec![],
+            status: 1,
+        };
+        let (sql, params) = render_template("test_list_expand", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(sql, "select * from user where id in (NULL) and status = ?");
+        assert_eq!(params.len(), 1);
+
+        // NOT IN 空列表恒成立，而不是 NULL
+        let tpl = "select * from user where id NOT IN #{ids} and status = #{status}";
+        let (sql, params) =
+            render_template("test_list_expand_not_in", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(
+            sql,
+            "select * from user where id NOT IN (SELECT NULL FROM (SELECT 1) empty_list WHERE 1 = 0) and status = ?"
+        );
+        assert_eq!(params.len(), 1);
+    }
+
+    #[test]
+    fn test_render_statement_by_id() {
+        use crate::tpl::engine::render_statement;
+
+        let tpl = "select * from user where name = #{name}";
+        let user = User {
+            name: "bob".to_string(),
+            age: 1,
+        };
+        let (sql, params) = render_statement("test_stmt_id", tpl, &user, &MockDriver).unwrap();
+        assert_eq!(sql, "select * from user where name = ?");
+        assert_eq!(params.len(), 1);
+
+        // 内容长度变化时重新解析
+        let tpl = "select * from user where age = #{age}";
+        let (sql, _) = render_statement("test_stmt_id", tpl, &user, &MockDriver).unwrap();
+        assert_eq!(sql, "select * from user where age = ?");
+    }
+
     #[derive(Serialize)]
     struct NestedUser {
         name: String,
@@ -204,7 +924,7 @@ mod tests {
             ],
         };
 
-        let (sql, params) = render_template("test_nested", tpl, &user, &MockDriver);
+        let (sql, params) = render_template("test_nested", tpl, &user, &MockDriver).unwrap();
         // Expected: insert into user_roles (user, role) values (?, ?), (?, ?)
         assert_eq!(
             sql,
@@ -225,4 +945,178 @@ mod tests {
             _ => panic!("Expected 2"),
         }
     }
+
+    #[test]
+    fn test_set_from_changeset() {
+        use crate::diff::{ChangeSet, changed_fields};
+
+        #[derive(Serialize)]
+        struct Args {
+            id: i64,
+            changes: ChangeSet,
+        }
+
+        let tpl = "update user <set from=\"changes\"/> where id = #{id}";
+        let old = User {
+            name: "bob".to_string(),
+            age: 20,
+        };
+        let new = User {
+            name: "bob".to_string(),
+            age: 21,
+        };
+        let args = Args {
+            id: 1,
+            changes: changed_fields(&old, &new),
+        };
+        let (sql, params) = render_template("test_set", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(sql, "update user SET age = ? where id = ?");
+        assert_eq!(params[0].0, "changes.age");
+        assert_eq!(params[1], ("id".to_string(), Value::I64(1)));
+
+        let args = Args {
+            id: 1,
+            changes: ChangeSet::new(),
+        };
+        let err = render_template("test_set", tpl, &args, &MockDriver).unwrap_err();
+        assert!(matches!(err, DbError::Template(_)));
+    }
+
+    #[test]
+    fn test_call_wraps_placeholder() {
+        let tpl = "insert into t values (#{createdAt, fn=from_unixtime}, #{ids, fn=hex})";
+        let args = serde_json::json!({"createdAt": 1700000000, "ids": [1, 2]});
+        let (sql, params) = render_template("test_call", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(
+            sql,
+            "insert into t values (FROM_UNIXTIME(?), (HEX(?), HEX(?)))"
+        );
+        assert_eq!(params.len(), 3);
+        assert_eq!(params[2].0, "ids[1]");
+
+        let err = render_template("test_call_bad", "#{a, fn=now()}", &args, &MockDriver);
+        assert!(matches!(err, Err(DbError::Template(_))));
+    }
+
+    #[test]
+    fn test_default_values() {
+        let tpl = "select * from u where name = #{name, default='guest'} limit #{limit ?: 50}";
+        let args: HashMap<&str, Option<&str>> = HashMap::from([("name", None)]);
+        let (sql, params) = render_template("test_default", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(sql, "select * from u where name = ? limit ?");
+        assert_eq!(params[0].1, Value::Str("guest".to_string()));
+        assert_eq!(params[1].1, Value::I64(50));
+
+        let args = serde_json::json!({"name": "alice", "limit": 10});
+        let (_, params) = render_template("test_default", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(params[0].1, Value::Str("alice".to_string()));
+        assert_eq!(params[1].1, Value::I64(10));
+    }
+
+    #[test]
+    fn test_if_compares_enum_variants() {
+        #[derive(Serialize)]
+        #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+        #[allow(dead_code)]
+        enum Status {
+            Active,
+            Suspended { reason: String },
+            Moved(i64, i64),
+        }
+        #[derive(Serialize)]
+        struct Args {
+            status: Status,
+        }
+
+        let tpl = r#"<if test="status == 'ACTIVE'">a</if><if test="status == 'SUSPENDED'">s #{status.SUSPENDED.reason}</if><if test="status != 'MOVED'">!m</if>"#;
+        let render = |status| {
+            render_template("test_enum_if", tpl, &Args { status }, &MockDriver)
+                .unwrap()
+                .0
+        };
+        assert_eq!(render(Status::Active), "a!m");
+        assert_eq!(
+            render(Status::Suspended {
+                reason: "spam".to_string()
+            }),
+            "s ?!m"
+        );
+        assert_eq!(render(Status::Moved(1, 2)), "");
+    }
+
+    #[test]
+    fn test_for_over_local_nested_path() {
+        let tpl = r#"<for item="u" collection="users" sep=";"><for item="t" collection="u.tags" sep=",">#{t}</for> #{u.tags[0]}</for>"#;
+        let args = serde_json::json!({"users": [{"tags": ["a", "b"]}, {"tags": ["c"]}]});
+        let (sql, params) = render_template("test_nested_for", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(sql, "?,? ?;? ?");
+        let values: Vec<_> = params.into_iter().map(|(_, v)| v).collect();
+        let s = |v: &str| Value::Str(v.to_string());
+        assert_eq!(values, vec![s("a"), s("b"), s("a"), s("c"), s("c")]);
+    }
+
+    #[test]
+    fn test_indexed_access_in_vars_and_tests() {
+        let tpl = r#"select #{ids[0]}, #{ids[-1]}<if test="items[0].qty > 0"> first</if><if test="items[-1].qty > 0"> last</if>"#;
+        let args = serde_json::json!({"ids": [7, 8, 9], "items": [{"qty": 2}, {"qty": 0}]});
+        let (sql, params) = render_template("test_indexed", tpl, &args, &MockDriver).unwrap();
+        assert_eq!(sql, "select ?, ? first");
+        assert_eq!(params[0], ("ids[0]".to_string(), Value::I64(7)));
+        assert_eq!(params[1].1, Value::I64(9));
+    }
+
+    #[test]
+    fn test_check_required_lists_missing() {
+        use crate::tpl::ParamNaming;
+        use crate::tpl::parser::compile;
+        use crate::udbc::serializer::to_value;
+
+        let template = compile("update u set name = #{name}, age = #{age} where id = #{id}");
+        let args = HashMap::from([("name", "a")]);
+        let err =
+            super::check_required(&template, &to_value(&args), ParamNaming::Exact).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Template error: missing required parameters: age, id"
+        );
+        let args = HashMap::from([("name", 1), ("age", 2), ("id", 3)]);
+        assert!(super::check_required(&template, &to_value(&args), ParamNaming::Exact).is_ok());
+        // 宽松匹配时按规范化后的名称检查
+        let args = HashMap::from([("NAME", 1), ("Age", 2), ("i_d", 3)]);
+        assert!(super::check_required(&template, &to_value(&args), ParamNaming::Relaxed).is_ok());
+    }
+
+    #[test]
+    fn test_prepare_statement_fixed_sql() {
+        use crate::tpl::ParamNaming;
+
+        let prepared = super::prepare_statement(
+            "test_prepared_fixed",
+            "select * from u where a = #{a} and b = #{b}",
+            &MockDriver,
+        );
+        let (sql, names, _) = prepared.fixed.as_ref().unwrap();
+        assert_eq!(sql, "select * from u where a = ? and b = ?");
+        assert_eq!(names, &["a", "b"]);
+        let args = serde_json::json!({"a": 1, "b": [2, 3]});
+        let value = crate::udbc::serializer::to_value(&args);
+        // 列表参数需要展开，改为完整渲染
+        let (sql, params) = super::render_prepared(
+            &prepared,
+            "test_prepared_fixed",
+            &value,
+            &MockDriver,
+            ParamNaming::Exact,
+        )
+        .unwrap();
+        assert_eq!(sql, "select * from u where a = ? and b = (?, ?)");
+        assert_eq!(params.len(), 3);
+
+        let prepared = super::prepare_statement(
+            "test_prepared_dynamic",
+            r#"select * from u<if test="a != null"> where a = #{a}</if>"#,
+            &MockDriver,
+        );
+        assert!(prepared.fixed.is_none());
+    }
 }
@@ -0,0 +1,133 @@
+use crate::error::DbError;
+use crate::mapper_loader::{find_mapper, snapshot, template_key};
+use crate::tpl::cache;
+use crate::tpl::engine::render_statement;
+use crate::udbc::connection::Connection;
+use crate::udbc::driver::{Driver, quote_ident_with};
+use crate::udbc::value::Value;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+
+/// 渲染结果：最终 SQL 及按顺序绑定的参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedSql {
+    pub sql: String,
+    pub params: Vec<(String, Value)>,
+}
+
+/// 快照友好的输出格式，便于直接与 golden 文件比较
+///
+/// ```text
+/// -- sql
+/// select * from user where id = ?
+/// -- params
+/// 1: id = I64(1)
+/// ```
+impl fmt::Display for RenderedSql {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "-- sql")?;
+        writeln!(f, "{}", self.sql.trim())?;
+        writeln!(f, "-- params")?;
+        for (i, (name, value)) in self.params.iter().enumerate() {
+            writeln!(f, "{}: {} = {:?}", i + 1, name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// 语句模板的静态信息，解析时计算并随 AST 缓存
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedSqlInfo {
+    /// 无条件引用的参数根名称；`<if>` 内部、带默认值或声明了 `ifEmpty` 的参数不在其中
+    ///
+    /// 严格模式（[`RenderOptions::strict`](crate::tpl::RenderOptions::strict)）下，
+    /// 参数为结构体或映射且缺少其中的字段时，渲染直接报错并列出全部缺失项。
+    pub required_params: Vec<String>,
+}
+
+/// 获取已加载语句的模板信息
+pub fn statement_info(sql_id: &str) -> Result<RenderedSqlInfo, DbError> {
+    let mapper = find_mapper(sql_id, "")
+        .ok_or_else(|| DbError::Query(format!("SQL ID not found: {}", sql_id)))?;
+    let content = mapper
+        .content
+        .as_deref()
+        .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
+    let template = cache::get_ast(sql_id, content);
+    Ok(RenderedSqlInfo {
+        required_params: template.required_params.clone(),
+    })
+}
+
+/// 仅用于离线渲染的驱动：使用 `?` 占位符，不提供连接
+#[derive(Default)]
+pub(crate) struct OfflineDriver {
+    database_type: String,
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Driver for OfflineDriver {
+    fn name(&self) -> &str {
+        "offline"
+    }
+
+    fn r#type(&self) -> &str {
+        &self.database_type
+    }
+
+    fn placeholder(&self, _param_seq: usize, _param_name: &str) -> String {
+        "?".to_string()
+    }
+
+    fn quote_ident(&self, ident: &str) -> String {
+        let quote = if self.database_type == "mysql" {
+            '`'
+        } else {
+            '"'
+        };
+        quote_ident_with(quote, ident)
+    }
+
+    async fn connection(&self) -> Result<Arc<dyn Connection>, DbError> {
+        Err(DbError::Connection(
+            "offline render driver has no connection".to_string(),
+        ))
+    }
+
+    async fn close(&self) -> Result<(), DbError> {
+        Ok(())
+    }
+}
+
+/// 在不连接数据库的情况下渲染已加载的 mapper 语句
+///
+/// 使用未声明 `databaseType` 的默认语句，便于在单元测试中断言 SQL 与参数。
+pub fn test_render<T: Serialize>(sql_id: &str, params: &T) -> Result<RenderedSql, DbError> {
+    test_render_for("", sql_id, params)
+}
+
+/// 按指定数据库类型选择语句并离线渲染
+pub fn test_render_for<T: Serialize>(
+    database_type: &str,
+    sql_id: &str,
+    params: &T,
+) -> Result<RenderedSql, DbError> {
+    // 查找与渲染在同一视图内完成
+    let snapshot = snapshot();
+    let mapper = snapshot
+        .find_mapper(sql_id, database_type)
+        .ok_or_else(|| DbError::Query(format!("SQL ID not found: {}", sql_id)))?;
+    let content = mapper
+        .content
+        .as_deref()
+        .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
+    let driver = OfflineDriver {
+        database_type: database_type.to_string(),
+    };
+    let key = template_key(sql_id, mapper.database_type.as_deref());
+    let (sql, params) = render_statement(&key, content, params, &driver)?;
+    Ok(RenderedSql { sql, params })
+}
@@ -1,16 +1,53 @@
+pub(crate) mod barrier;
 mod cache;
 pub(crate) mod engine;
-mod parser;
+pub(crate) mod harness;
+mod options;
+pub(crate) mod parser;
 mod render;
-mod render_context;
+pub(crate) mod render_context;
+mod snippet;
 
+pub use cache::{CacheStats, cache_stats, set_cache_capacity};
+pub use harness::{RenderedSql, RenderedSqlInfo, statement_info, test_render, test_render_for};
+pub use options::{
+    Coercion, NestedParams, ParamNaming, RenderOptions, SqlComment, render_options,
+    set_render_options,
+};
+pub use snippet::{EnvSnippets, SnippetProvider, clear_snippet_provider, set_snippet_provider};
+
+/// 模板语法树节点（内部使用）
+#[doc(hidden)]
 #[derive(Debug, Clone)]
 pub enum AstNode {
     Text(String),
     Var(String),
+    /// `#{name, fn=func}`：占位符由驱动包裹为 SQL 函数调用，如 `FROM_UNIXTIME(?)`
+    Call {
+        name: String,
+        func: String,
+    },
+    /// `#{name ?: 50}` / `#{name, default='guest'}`：参数缺失或为 NULL 时绑定默认值，
+    /// 可与 `fn=` 同时使用
+    Default {
+        name: String,
+        default: crate::udbc::value::Value,
+        func: Option<String>,
+    },
     Include {
         refid: String,
     },
+    /// `${ident(name)}`：参数值作为标识符，按驱动的方言加引号转义后拼入 SQL
+    Ident(String),
+    /// `<include env="NAME"/>`：渲染时取运行时提供的片段，见 [`SnippetProvider`]
+    IncludeEnv {
+        name: String,
+    },
+    /// `<with refs="a, b"/>`：按依赖顺序展开引用的 `<cte>` 片段，渲染为 `WITH a AS (...), b AS (...)`；
+    /// 名称已在加载时补全命名空间
+    With {
+        refs: Vec<String>,
+    },
     If {
         test: String,
         body: Vec<AstNode>,
@@ -21,6 +58,20 @@ pub enum AstNode {
         open: String,
         sep: String,
         close: String,
+        /// 集合为空时输出的文本（`ifEmpty` 属性）
+        if_empty: Option<String>,
         body: Vec<AstNode>,
     },
+    /// `<set from="changeset"/>`：按映射中的字段展开为 `SET a = ?, b = ?`
+    Set {
+        from: String,
+    },
+    /// `${schema}` / `${prefix ?: app_}`：由连接池提供的模板变量，原样拼入 SQL，
+    /// 不读取语句参数；连接池未配置且没有默认值时渲染报错
+    PoolVar {
+        name: String,
+        default: Option<String>,
+    },
+    /// 声明了 `raw="true"` 的语句：SQL 原样输出，参数按位置绑定到其中的占位符
+    Raw(String),
 }
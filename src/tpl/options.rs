@@ -0,0 +1,161 @@
+use crate::udbc::value::Value;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// 模板渲染选项
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// 严格模式：未声明 `ifEmpty` 的 `<for>` 遇到空集合时渲染报错，
+    /// 避免生成 `id in ` 这类残缺语句
+    pub strict: bool,
+    /// 折叠渲染结果中字符串字面量以外的连续空白，使日志与基于 SQL 文本的缓存键保持稳定
+    pub normalize_whitespace: bool,
+    /// `test` 表达式中类型不同的操作数如何比较
+    pub coercion: Coercion,
+    /// 设置后在渲染结果前附加 `/* app=.. sql_id=.. trace=.. */` 注释，
+    /// 便于从数据库慢日志关联到应用与调用链路
+    pub sql_comment: Option<SqlComment>,
+    /// 单个列表参数（自动展开的 `#{ids}` 或 `<for>` 集合）的最大元素数
+    ///
+    /// 通过 `Session`/`Mapper` 执行时，超过该值的列表按此大小切分为多条语句依次执行：
+    /// 查询结果按顺序拼接，更新返回影响行数之和；不在事务中时，切分出的更新在一个事务中执行。
+    /// 含 `NOT IN`、聚合函数、`GROUP BY`、`ORDER BY`/`LIMIT` 等结果不能按组拼接的语句不切分，
+    /// 返回错误。
+    pub max_list_params: Option<usize>,
+    /// 绑定参数为映射或列表（如直接传给 `#{profile}` 的嵌套结构体）时的处理方式
+    pub nested_params: NestedParams,
+}
+
+/// 映射/列表参数的绑定方式
+///
+/// 顶层列表参数（`#{ids}`）展开为 `(?, ?, ...)`，不受影响；
+/// 只有未被展开的嵌套值（结构体字段、列表中的列表等）才会按此处理。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NestedParams {
+    /// 序列化为 JSON 文本绑定，适合写入 JSON 列
+    #[default]
+    Json,
+    /// 渲染报错并指出参数名
+    Reject,
+}
+
+/// 渲染结果前附加的注释内容
+///
+/// `sql_id` 取自执行的语句，`trace` 取自 `executor::trace::with_trace_id` 设置的任务级链路 ID；
+/// 缺少的字段不输出。
+#[derive(Debug, Clone, Default)]
+pub struct SqlComment {
+    /// 应用名，输出为 `app=...`
+    pub app: Option<String>,
+}
+
+impl SqlComment {
+    /// 生成注释文本；没有任何字段时返回 `None`
+    pub(crate) fn render(&self, sql_id: Option<&str>, trace: Option<&str>) -> Option<String> {
+        let fields: Vec<String> = [
+            ("app", self.app.as_deref()),
+            ("sql_id", sql_id),
+            ("trace", trace),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|v| format!("{}={}", key, sanitize(v))))
+        .collect();
+        (!fields.is_empty()).then(|| format!("/* {} */ ", fields.join(" ")))
+    }
+}
+
+/// 去掉 `*` 与换行，取值中无法出现 `*/` 提前结束注释
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '*' | '\n' | '\r'))
+        .collect()
+}
+
+/// `test` 表达式比较时的类型转换策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Coercion {
+    /// 不做转换；无法比较的操作数（如 `"10" > 5`）记录警告并视为不成立
+    #[default]
+    None,
+    /// 数字与数字字符串比较时，将字符串解析为数字
+    NumericStrings,
+    /// 不做转换；无法比较的操作数渲染报错
+    Strict,
+}
+
+/// 模板参数名与参数键的匹配方式
+///
+/// 总是先按原名查找，找不到时才按此放宽；多个键都能匹配时取字典序最小的键。
+/// 通过 [`QueryOptions::param_naming`](crate::executor::options::QueryOptions::param_naming)
+/// 按全局、连接池、语句或单次调用设置。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub enum ParamNaming {
+    /// 只按原名匹配
+    #[default]
+    Exact,
+    /// 忽略大小写，`#{userId}` 可匹配 `userid`
+    IgnoreCase,
+    /// 忽略大小写、`_` 与 `-`，`#{userId}` 可匹配 `user_id`，`#{user_id}` 可匹配 `userId`
+    Relaxed,
+}
+
+impl ParamNaming {
+    /// 在映射中按该方式查找 `key`
+    pub(crate) fn find<'a>(self, map: &'a HashMap<String, Value>, key: &str) -> Option<&'a Value> {
+        self.find_entry(map, key).map(|(_, v)| v)
+    }
+
+    /// 同 [`ParamNaming::find`]，同时返回匹配到的键
+    pub(crate) fn find_entry<'a>(
+        self,
+        map: &'a HashMap<String, Value>,
+        key: &str,
+    ) -> Option<(&'a String, &'a Value)> {
+        if let Some(entry) = map.get_key_value(key) {
+            return Some(entry);
+        }
+        let matches: fn(&str, &str) -> bool = match self {
+            ParamNaming::Exact => return None,
+            ParamNaming::IgnoreCase => |a, b| a.eq_ignore_ascii_case(b),
+            ParamNaming::Relaxed => |a, b| {
+                let fold = |s: &str| {
+                    s.chars()
+                        .filter(|c| !matches!(c, '_' | '-'))
+                        .map(|c| c.to_ascii_lowercase())
+                        .collect::<String>()
+                };
+                fold(a) == fold(b)
+            },
+        };
+        map.iter()
+            .filter(|(k, _)| matches(k, key))
+            .min_by(|(a, _), (b, _)| a.cmp(b))
+    }
+}
+
+impl std::str::FromStr for ParamNaming {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "exact" => Ok(ParamNaming::Exact),
+            "ignore_case" | "ignorecase" => Ok(ParamNaming::IgnoreCase),
+            "relaxed" => Ok(ParamNaming::Relaxed),
+            other => Err(format!("unknown parameter naming: {}", other)),
+        }
+    }
+}
+
+static RENDER_OPTIONS: LazyLock<RwLock<RenderOptions>> =
+    LazyLock::new(|| RwLock::new(RenderOptions::default()));
+
+/// 设置全局渲染选项
+pub fn set_render_options(options: RenderOptions) {
+    *RENDER_OPTIONS.write().unwrap() = options;
+}
+
+/// 获取当前全局渲染选项
+pub fn render_options() -> RenderOptions {
+    RENDER_OPTIONS.read().unwrap().clone()
+}
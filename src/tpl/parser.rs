@@ -1,4 +1,13 @@
 use crate::tpl::AstNode;
+use crate::tpl::render::parse_literal;
+use crate::udbc::value::Value;
+
+/// 原样输出块的起止标记，块内文本不做任何模板解析
+const RAW_OPEN: &str = "<![RAW[";
+const RAW_CLOSE: &str = "]]>";
+
+/// 以 `\` 转义后按字面输出的序列：`\#{`、`\${`、`\<`
+const ESCAPES: [&str; 3] = ["\\#{", "\\${", "\\<"];
 
 /// 用于跟踪嵌套标签（如 <if> 和 <for>）的栈帧。
 enum TagFrame {
@@ -11,6 +20,7 @@ enum TagFrame {
         open: String,
         sep: String,
         close: String,
+        if_empty: Option<String>,
     },
 }
 
@@ -37,7 +47,12 @@ impl<'a> Parser<'a> {
     fn parse(mut self) -> Vec<AstNode> {
         while self.pos < self.template.len() {
             // 尝试优先解析结构化元素
-            if self.try_parse_tag() || self.try_parse_var() {
+            if self.try_parse_escape()
+                || self.try_parse_raw_block()
+                || self.try_parse_tag()
+                || self.try_parse_var()
+                || self.try_parse_pool_var()
+            {
                 continue;
             }
 
@@ -49,6 +64,29 @@ impl<'a> Parser<'a> {
         self.nodes_stack.pop().unwrap_or_default()
     }
 
+    /// 尝试解析转义序列 `\#{`、`\${`、`\<`：去掉反斜杠，其后的字符按文本输出
+    fn try_parse_escape(&mut self) -> bool {
+        let remaining = &self.template[self.pos..];
+        if ESCAPES.iter().any(|e| remaining.starts_with(e)) {
+            self.append_text(&remaining[1..2]);
+            self.pos += 2;
+            return true;
+        }
+        false
+    }
+
+    /// 尝试解析原样输出块 `<![RAW[...]]>`，未闭合时块延续到模板末尾
+    fn try_parse_raw_block(&mut self) -> bool {
+        let remaining = &self.template[self.pos..];
+        let Some(body) = remaining.strip_prefix(RAW_OPEN) else {
+            return false;
+        };
+        let end = body.find(RAW_CLOSE).unwrap_or(body.len());
+        self.append_text(&body[..end]);
+        self.pos += (RAW_OPEN.len() + end + RAW_CLOSE.len()).min(remaining.len());
+        true
+    }
+
     /// 尝试解析标签：<if>, </if>, <for>, </for>, <include>。
     /// 如果成功解析并消耗了一个标签，则返回 true。
     fn try_parse_tag(&mut self) -> bool {
@@ -66,6 +104,12 @@ impl<'a> Parser<'a> {
         if remaining.starts_with("<include") {
             return self.handle_include_tag(remaining);
         }
+        if remaining.starts_with("<set ") {
+            return self.handle_set_tag(remaining);
+        }
+        if remaining.starts_with("<with ") {
+            return self.handle_with_tag(remaining);
+        }
 
         false
     }
@@ -97,6 +141,7 @@ impl<'a> Parser<'a> {
                 let open = extract_attr(tag_content, "open").unwrap_or("");
                 let sep = extract_attr(tag_content, "sep").unwrap_or(",");
                 let close = extract_attr(tag_content, "close").unwrap_or("");
+                let if_empty = extract_attr(tag_content, "ifEmpty").map(str::to_string);
 
                 self.nodes_stack.push(Vec::new());
                 self.tag_stack.push(TagFrame::For {
@@ -105,6 +150,7 @@ impl<'a> Parser<'a> {
                     open: open.to_string(),
                     sep: sep.to_string(),
                     close: close.to_string(),
+                    if_empty,
                 });
                 self.pos += end_idx + 1;
                 return true;
@@ -113,15 +159,58 @@ impl<'a> Parser<'a> {
         false
     }
 
-    /// 处理 <include refid="..." />
+    /// 处理 <include refid="..." /> 与 <include env="..." />
     fn handle_include_tag(&mut self, remaining: &str) -> bool {
         if let Some(end_idx) = find_tag_end(remaining) {
             let tag_content = &remaining[8..end_idx]; // 跳过 "<include"
-            if let Some(refid) = extract_attr(tag_content, "refid") {
-                self.append_node(AstNode::Include {
+            let node = if let Some(refid) = extract_attr(tag_content, "refid") {
+                AstNode::Include {
                     refid: refid.to_string(),
+                }
+            } else if let Some(name) = extract_attr(tag_content, "env") {
+                AstNode::IncludeEnv {
+                    name: name.to_string(),
+                }
+            } else {
+                return false;
+            };
+            self.append_node(node);
+            self.pos += end_idx + 1;
+            return true;
+        }
+        false
+    }
+
+    /// 处理 <set from="..."/>，也接受紧随其后的 </set>
+    fn handle_set_tag(&mut self, remaining: &str) -> bool {
+        if let Some(end_idx) = find_tag_end(remaining) {
+            let tag_content = &remaining[5..end_idx]; // 跳过 "<set "
+            if let Some(from) = extract_attr(tag_content, "from") {
+                self.append_node(AstNode::Set {
+                    from: from.to_string(),
                 });
                 self.pos += end_idx + 1;
+                if remaining[end_idx + 1..].starts_with("</set>") {
+                    self.pos += 6;
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// 处理 <with refs="..."/>，也接受紧随其后的 </with>
+    fn handle_with_tag(&mut self, remaining: &str) -> bool {
+        if let Some(end_idx) = find_tag_end(remaining) {
+            let tag_content = &remaining[6..end_idx]; // 跳过 "<with "
+            if let Some(refs) = extract_attr(tag_content, "refs") {
+                self.append_node(AstNode::With {
+                    refs: split_refs(refs).map(str::to_string).collect(),
+                });
+                self.pos += end_idx + 1;
+                if remaining[end_idx + 1..].starts_with("</with>") {
+                    self.pos += 7;
+                }
                 return true;
             }
         }
@@ -131,63 +220,133 @@ impl<'a> Parser<'a> {
     /// 处理闭合标签 </if> 和 </for>
     fn handle_close_tag(&mut self, remaining: &str) -> bool {
         if remaining.starts_with("</if>") {
-            if let Some(TagFrame::If { .. }) = self.tag_stack.last() {
-                if let Some(TagFrame::If { test }) = self.tag_stack.pop() {
-                    let body = self.nodes_stack.pop().unwrap_or_default();
-                    self.append_node(AstNode::If { test, body });
-                    self.pos += 5;
-                    return true;
-                }
-            }
-        } else if remaining.starts_with("</for>") {
-            if let Some(TagFrame::For { .. }) = self.tag_stack.last() {
-                if let Some(TagFrame::For {
-                    item,
-                    collection,
-                    open,
-                    sep,
-                    close,
-                }) = self.tag_stack.pop()
-                {
-                    let body = self.nodes_stack.pop().unwrap_or_default();
-                    self.append_node(AstNode::For {
-                        item,
-                        collection,
-                        open,
-                        sep,
-                        close,
-                        body,
-                    });
-                    self.pos += 6;
-                    return true;
-                }
+            if let Some(TagFrame::If { .. }) = self.tag_stack.last()
+                && let Some(TagFrame::If { test }) = self.tag_stack.pop()
+            {
+                let body = self.nodes_stack.pop().unwrap_or_default();
+                self.append_node(AstNode::If { test, body });
+                self.pos += 5;
+                return true;
             }
+        } else if remaining.starts_with("</for>")
+            && let Some(TagFrame::For { .. }) = self.tag_stack.last()
+            && let Some(TagFrame::For {
+                item,
+                collection,
+                open,
+                sep,
+                close,
+                if_empty,
+            }) = self.tag_stack.pop()
+        {
+            let body = self.nodes_stack.pop().unwrap_or_default();
+            self.append_node(AstNode::For {
+                item,
+                collection,
+                open,
+                sep,
+                close,
+                if_empty,
+                body,
+            });
+            self.pos += 6;
+            return true;
         }
         false
     }
 
-    /// 尝试解析变量表达式 #{var}
+    /// 尝试解析变量表达式 #{var}、#{var, fn=func}、#{var ?: default} 或 #{var, default=...}
     fn try_parse_var(&mut self) -> bool {
         let remaining = &self.template[self.pos..];
-        if remaining.starts_with("#{") {
-            if let Some(end) = remaining.find('}') {
-                let var_name = remaining[2..end].trim();
-                if !var_name.is_empty() {
-                    self.append_node(AstNode::Var(var_name.to_string()));
-                    self.pos += end + 1;
-                    return true;
+        if remaining.starts_with("#{")
+            && let Some(end) = remaining.find('}')
+        {
+            let mut parts = remaining[2..end].split(',');
+            let head = parts.next().unwrap_or_default();
+            let (var_name, mut default) = match head.split_once("?:") {
+                Some((name, default)) => (name.trim(), Some(default)),
+                None => (head.trim(), None),
+            };
+            let mut func = None;
+            for m in parts {
+                if let Some((key, value)) = m.split_once('=') {
+                    match key.trim() {
+                        "fn" => func = Some(value.trim().to_string()),
+                        "default" => default = Some(value),
+                        _ => {}
+                    }
                 }
             }
+            if !var_name.is_empty() {
+                let node = match (default, func) {
+                    (Some(default), func) => AstNode::Default {
+                        name: var_name.to_string(),
+                        // 无法识别的字面量按字符串处理，如 `#{sort ?: name}`
+                        default: parse_literal(default)
+                            .unwrap_or_else(|| Value::Str(default.trim().to_string())),
+                        func,
+                    },
+                    (None, Some(func)) => AstNode::Call {
+                        name: var_name.to_string(),
+                        func,
+                    },
+                    (None, None) => AstNode::Var(var_name.to_string()),
+                };
+                self.append_node(node);
+                self.pos += end + 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// 尝试解析连接池变量 ${name} 或 ${name ?: default}，名称只能是标识符；
+    /// 以及引用参数作为标识符的 ${ident(name)}
+    fn try_parse_pool_var(&mut self) -> bool {
+        let remaining = &self.template[self.pos..];
+        if remaining.starts_with("${")
+            && let Some(end) = remaining.find('}')
+        {
+            let inner = &remaining[2..end];
+            if let Some(path) = inner
+                .trim()
+                .strip_prefix("ident(")
+                .and_then(|s| s.strip_suffix(')'))
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+            {
+                self.append_node(AstNode::Ident(path.to_string()));
+                self.pos += end + 1;
+                return true;
+            }
+            let (name, default) = match inner.split_once("?:") {
+                Some((name, default)) => (name.trim(), Some(default.trim().to_string())),
+                None => (inner.trim(), None),
+            };
+            if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                self.append_node(AstNode::PoolVar {
+                    name: name.to_string(),
+                    default,
+                });
+                self.pos += end + 1;
+                return true;
+            }
         }
         false
     }
 
-    /// 消耗文本直到遇到下一个特殊字符（'<' 或 '#{'）
+    /// 消耗文本直到遇到下一个特殊字符（'<'、'#{'、'${' 或转义序列）
     fn parse_text(&mut self) {
         let remaining = &self.template[self.pos..];
         let next_tag = remaining.find('<').unwrap_or(remaining.len());
         let next_var = remaining.find("#{").unwrap_or(remaining.len());
-        let next_stop = std::cmp::min(next_tag, next_var);
+        let next_pool_var = remaining.find("${").unwrap_or(remaining.len());
+        let next_escape = ESCAPES
+            .iter()
+            .filter_map(|e| remaining.find(e))
+            .min()
+            .unwrap_or(remaining.len());
+        let next_stop = next_tag.min(next_var).min(next_pool_var).min(next_escape);
 
         if next_stop > 0 {
             self.append_text(&remaining[..next_stop]);
@@ -230,12 +389,14 @@ impl<'a> Parser<'a> {
                     open,
                     sep,
                     close,
+                    if_empty,
                 } => AstNode::For {
                     item,
                     collection,
                     open,
                     sep,
                     close,
+                    if_empty,
                     body,
                 },
             };
@@ -249,6 +410,135 @@ pub fn parse_template(template: &str) -> Vec<AstNode> {
     Parser::new(template).parse()
 }
 
+/// 编译后的模板：AST 及解析时预先计算的必需参数
+pub(crate) struct Template {
+    pub ast: Vec<AstNode>,
+    /// 无条件引用的参数根名称，见 [`required_params`]
+    pub required_params: Vec<String>,
+}
+
+impl std::ops::Deref for Template {
+    type Target = Vec<AstNode>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.ast
+    }
+}
+
+impl Template {
+    /// 不经模板解析、原样执行的语句
+    pub(crate) fn raw(sql: &str) -> Self {
+        Template {
+            ast: vec![AstNode::Raw(sql.to_string())],
+            required_params: Vec::new(),
+        }
+    }
+
+    pub(crate) fn is_raw(&self) -> bool {
+        matches!(self.ast.as_slice(), [AstNode::Raw(_)])
+    }
+}
+
+/// 解析模板并计算必需参数
+pub(crate) fn compile(template: &str) -> Template {
+    let ast = parse_template(template);
+    let required_params = required_params(&ast);
+    Template {
+        ast,
+        required_params,
+    }
+}
+
+/// 收集模板无条件引用的参数根名称（去重、保持出现顺序）
+///
+/// `<if>` 内部、带默认值的 `#{a ?: 1}`、声明了 `ifEmpty` 的 `<for>` 集合
+/// 以及 `<include>` 引用的片段不计入。
+pub(crate) fn required_params(nodes: &[AstNode]) -> Vec<String> {
+    fn push(path: &str, scope: &[String], out: &mut Vec<String>) {
+        let root = path.split(['.', '[']).next().unwrap_or(path).trim();
+        if !root.is_empty() && !scope.iter().any(|s| s == root) && !out.iter().any(|s| s == root) {
+            out.push(root.to_string());
+        }
+    }
+    fn walk(nodes: &[AstNode], scope: &mut Vec<String>, out: &mut Vec<String>) {
+        for node in nodes {
+            match node {
+                AstNode::Var(name) | AstNode::Call { name, .. } => push(name, scope, out),
+                AstNode::Set { from } | AstNode::Ident(from) => push(from, scope, out),
+                AstNode::For {
+                    item,
+                    collection,
+                    if_empty,
+                    body,
+                    ..
+                } => {
+                    if if_empty.is_none() {
+                        push(collection, scope, out);
+                    }
+                    scope.push(item.clone());
+                    walk(body, scope, out);
+                    scope.pop();
+                }
+                AstNode::Text(_)
+                | AstNode::Default { .. }
+                | AstNode::If { .. }
+                | AstNode::Include { .. }
+                | AstNode::IncludeEnv { .. }
+                | AstNode::With { .. }
+                | AstNode::PoolVar { .. }
+                | AstNode::Raw(_) => {}
+            }
+        }
+    }
+    let mut out = Vec::new();
+    walk(nodes, &mut Vec::new(), &mut out);
+    out
+}
+
+/// 逗号分隔的片段名
+fn split_refs(refs: &str) -> impl Iterator<Item = &str> {
+    refs.split(',').map(str::trim).filter(|r| !r.is_empty())
+}
+
+/// 不含 `.` 的片段名补全为 `namespace.name`
+#[cfg_attr(not(feature = "xml"), allow(dead_code))]
+pub(crate) fn qualify_ref(namespace: &str, name: &str) -> String {
+    if name.contains('.') {
+        name.to_string()
+    } else {
+        format!("{}.{}", namespace, name)
+    }
+}
+
+/// 将模板中 `<with refs>` 引用的片段名补全命名空间；没有需要补全的名称时返回 `None`
+#[cfg_attr(not(feature = "xml"), allow(dead_code))]
+pub(crate) fn qualify_with_refs(content: &str, namespace: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut copied = 0;
+    for (start, _) in content.match_indices("<with ") {
+        let Some(end) = find_tag_end(&content[start..]).map(|i| start + i) else {
+            continue;
+        };
+        let Some(refs) = extract_attr(&content[start + 6..end], "refs") else {
+            continue;
+        };
+        if split_refs(refs).all(|r| r.contains('.')) {
+            continue;
+        }
+        let refs: Vec<String> = split_refs(refs)
+            .map(|r| qualify_ref(namespace, r))
+            .collect();
+        out.push_str(&content[copied..start]);
+        out.push_str(&format!("<with refs=\"{}\"/>", refs.join(", ")));
+        copied = end + 1;
+    }
+    if copied == 0 {
+        return None;
+    }
+    out.push_str(&content[copied..]);
+    Some(out)
+}
+
 /// 查找标签闭合 '>' 的索引，忽略引号内的内容。
 fn find_tag_end(s: &str) -> Option<usize> {
     let mut in_quote = false;
@@ -279,14 +569,11 @@ fn extract_attr<'a>(tag_content: &'a str, key: &str) -> Option<&'a str> {
         let trimmed = remaining.trim_start();
 
         // 期望 '=' 后跟带引号的字符串
-        if trimmed.starts_with('=') {
-            let after_eq = trimmed[1..].trim_start();
-            if after_eq.starts_with('"') {
-                if let Some(end) = after_eq[1..].find('"') {
-                    // +1 跳过起始引号
-                    return Some(&after_eq[1..1 + end]);
-                }
-            }
+        if let Some(after_eq) = trimmed.strip_prefix('=')
+            && let Some(quoted) = after_eq.trim_start().strip_prefix('"')
+            && let Some(end) = quoted.find('"')
+        {
+            return Some(&quoted[..end]);
         }
     }
     None
@@ -337,6 +624,83 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_pool_var() {
+        let nodes = parse_template("${schema}.${ prefix ?: app_ }users ${not valid} $1");
+        match &nodes[0] {
+            AstNode::PoolVar { name, default } => {
+                assert_eq!(name, "schema");
+                assert_eq!(default, &None);
+            }
+            _ => panic!(),
+        }
+        match &nodes[2] {
+            AstNode::PoolVar { name, default } => {
+                assert_eq!(name, "prefix");
+                assert_eq!(default.as_deref(), Some("app_"));
+            }
+            _ => panic!(),
+        }
+        match &nodes[3] {
+            AstNode::Text(t) => assert_eq!(t, "users ${not valid} $1"),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_var_with_fn() {
+        let nodes = parse_template("#{ts, fn=from_unixtime} #{name, jdbcType=VARCHAR}");
+        match &nodes[0] {
+            AstNode::Call { name, func } => {
+                assert_eq!(name, "ts");
+                assert_eq!(func, "from_unixtime");
+            }
+            _ => panic!(),
+        }
+        match &nodes[2] {
+            AstNode::Var(v) => assert_eq!(v, "name"),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_var_with_default() {
+        let nodes =
+            parse_template("#{limit ?: 50} #{name, default='guest'} #{ts ?: 0, fn=from_unixtime}");
+        match &nodes[0] {
+            AstNode::Default {
+                name,
+                default,
+                func,
+            } => {
+                assert_eq!(name, "limit");
+                assert_eq!(default, &Value::I64(50));
+                assert!(func.is_none());
+            }
+            _ => panic!(),
+        }
+        match &nodes[2] {
+            AstNode::Default { name, default, .. } => {
+                assert_eq!(name, "name");
+                assert_eq!(default, &Value::Str("guest".to_string()));
+            }
+            _ => panic!(),
+        }
+        match &nodes[4] {
+            AstNode::Default { func, .. } => assert_eq!(func.as_deref(), Some("from_unixtime")),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_required_params() {
+        let tpl = r#"select * from u where id = #{user.id} and ts = #{ts, fn=from_unixtime}
+            <if test="name != null">and name = #{name}</if> limit #{limit ?: 10}
+            and tag in <for item="t" collection="tags">#{t}</for>
+            and x in <for item="x" collection="xs" ifEmpty="(NULL)">#{x}</for> and id > #{user.min}"#;
+        assert_eq!(compile(tpl).required_params, vec!["user", "ts", "tags"]);
+    }
+
     #[test]
     fn test_parse_if() {
         let tpl = r#"<if test="a > 1">content</if>"#;
@@ -375,6 +739,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_set() {
+        for tpl in [
+            r#"UPDATE t <set from="changes"/> WHERE id = #{id}"#,
+            r#"UPDATE t <set from="changes"></set> WHERE id = #{id}"#,
+        ] {
+            let nodes = parse_template(tpl);
+            assert_eq!(nodes.len(), 4, "{}", tpl);
+            match &nodes[1] {
+                AstNode::Set { from } => assert_eq!(from, "changes"),
+                _ => panic!("Expected Set"),
+            }
+            match &nodes[2] {
+                AstNode::Text(t) => assert_eq!(t, " WHERE id = "),
+                _ => panic!("Expected Text"),
+            }
+        }
+    }
+
     #[test]
     fn test_auto_close() {
         let tpl = r#"<if test="x">content"#;
@@ -409,4 +792,68 @@ mod tests {
             _ => panic!("Expected If"),
         }
     }
+
+    #[test]
+    fn test_escapes() {
+        let tpl =
+            r#"SELECT '\#{a}', '\${b}', '\<if test="x">' FROM t WHERE id = #{id} AND p = '\x'"#;
+        let nodes = parse_template(tpl);
+        assert_eq!(nodes.len(), 3);
+        match &nodes[0] {
+            AstNode::Text(t) => assert_eq!(
+                t,
+                r#"SELECT '#{a}', '${b}', '<if test="x">' FROM t WHERE id = "#
+            ),
+            _ => panic!("Expected Text"),
+        }
+        assert!(matches!(&nodes[1], AstNode::Var(v) if v == "id"));
+        // 其他位置的反斜杠保持原样
+        match &nodes[2] {
+            AstNode::Text(t) => assert_eq!(t, r" AND p = '\x'"),
+            _ => panic!("Expected Text"),
+        }
+    }
+
+    #[test]
+    fn test_raw_block() {
+        let tpl = r#"SELECT <![RAW[doc->'$.a', '#{x}', '<if test="y">']]> FROM t WHERE id = #{id}"#;
+        let nodes = parse_template(tpl);
+        assert_eq!(nodes.len(), 2);
+        match &nodes[0] {
+            AstNode::Text(t) => assert_eq!(
+                t,
+                r#"SELECT doc->'$.a', '#{x}', '<if test="y">' FROM t WHERE id = "#
+            ),
+            _ => panic!("Expected Text"),
+        }
+
+        // 未闭合的块延续到模板末尾
+        let nodes = parse_template("a <![RAW[#{b}");
+        assert_eq!(nodes.len(), 1);
+        assert!(matches!(&nodes[0], AstNode::Text(t) if t == "a #{b}"));
+    }
+
+    #[test]
+    fn test_parse_include_env() {
+        let nodes =
+            parse_template(r#"WHERE 1 = 1 <include env="EXTRA_PREDICATE"/> <include other="x"/>"#);
+        assert_eq!(nodes.len(), 3);
+        assert!(matches!(&nodes[1], AstNode::IncludeEnv { name } if name == "EXTRA_PREDICATE"));
+        // 既没有 refid 也没有 env 的 include 按文本处理
+        assert!(matches!(&nodes[2], AstNode::Text(t) if t == r#" <include other="x"/>"#));
+    }
+
+    #[test]
+    fn test_parse_with() {
+        let nodes = parse_template(r#"<with refs="a, b"></with> SELECT 1"#);
+        assert_eq!(nodes.len(), 2);
+        assert!(matches!(&nodes[0], AstNode::With { refs } if refs == &["a", "b"]));
+
+        let content = r#"<with refs="a, other.b"/> SELECT <with refs="x.c"/>"#;
+        assert_eq!(
+            qualify_with_refs(content, "ns").unwrap(),
+            r#"<with refs="ns.a, other.b"/> SELECT <with refs="x.c"/>"#
+        );
+        assert_eq!(qualify_with_refs(r#"<with refs="x.c"/>"#, "ns"), None);
+    }
 }
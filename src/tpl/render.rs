@@ -1,14 +1,104 @@
+use crate::error::DbError;
+use crate::mapper_loader::{SqlMapper, StatementKind, find_mapper, template_key};
 use crate::tpl::AstNode;
 use crate::tpl::cache::TEMPLATE_CACHE;
+use crate::tpl::options::{Coercion, NestedParams, RenderOptions};
+use crate::tpl::parser::Template;
 use crate::tpl::render_context::Context;
+use crate::tpl::snippet;
 use crate::udbc::driver::Driver;
+use crate::udbc::json::value_to_json;
 use crate::udbc::value::Value;
+use std::sync::Arc;
 
 pub struct RenderBuffer<'a> {
     pub sql: String,
     pub params: Vec<(String, Value)>,
     pub driver: &'a dyn Driver,
     pub param_count: usize,
+    pub options: RenderOptions,
+}
+
+impl RenderBuffer<'_> {
+    /// 将字符串字面量以外的连续空白（空格、换行、制表符）折叠为单个空格，并去除首尾空白
+    pub fn normalize_whitespace(&mut self) {
+        self.sql = normalize_whitespace(&self.sql);
+    }
+}
+
+/// 折叠 SQL 中引号（'、"、`）与注释以外的连续空白
+///
+/// `--`、`#` 行注释与 `/* */` 块注释原样保留；行注释之后的空白折叠为一个换行，
+/// 避免其后的 SQL 被并入注释。
+pub fn normalize_whitespace(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut quote: Option<char> = None;
+    let mut pending_space = false;
+    let mut after_line_comment = false;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            out.push(c);
+            if c == '\\' {
+                if let Some(next) = chars.next() {
+                    out.push(next);
+                }
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        if c.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        if after_line_comment {
+            out.push('\n');
+        } else if pending_space && !out.is_empty() {
+            out.push(' ');
+        }
+        pending_space = false;
+        after_line_comment = false;
+        match c {
+            '\'' | '"' | '`' => quote = Some(c),
+            '-' if chars.peek() == Some(&'-') => {
+                out.push(c);
+                while let Some(next) = chars.next_if(|&next| next != '\n') {
+                    out.push(next);
+                }
+                after_line_comment = true;
+                continue;
+            }
+            '#' => {
+                out.push(c);
+                while let Some(next) = chars.next_if(|&next| next != '\n') {
+                    out.push(next);
+                }
+                after_line_comment = true;
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                out.push(c);
+                out.extend(chars.next());
+                let mut prev = ' ';
+                for next in chars.by_ref() {
+                    out.push(next);
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+    }
+    // 末尾的行注释去掉其后的空白即可
+    out.truncate(out.trim_end_matches([' ', '\r']).len());
+    out
 }
 
 fn to_f64(v: &Value) -> Option<f64> {
@@ -22,10 +112,75 @@ fn to_f64(v: &Value) -> Option<f64> {
     }
 }
 
-fn eval_atom(expr: &str, ctx: &Context) -> bool {
+/// 解析模板中的字面量：`null`、`true`/`false`、带引号的字符串与数字；其他返回 `None`
+pub(crate) fn parse_literal(s: &str) -> Option<Value> {
+    let s = s.trim();
+    match s {
+        "null" => return Some(Value::Null),
+        "true" => return Some(Value::Bool(true)),
+        "false" => return Some(Value::Bool(false)),
+        _ => {}
+    }
+    if s.len() >= 2
+        && ((s.starts_with('\'') && s.ends_with('\'')) || (s.starts_with('"') && s.ends_with('"')))
+    {
+        return Some(Value::Str(s[1..s.len() - 1].to_string()));
+    }
+    if let Ok(n) = s.parse::<i64>() {
+        return Some(Value::I64(n));
+    }
+    s.parse::<f64>().ok().map(Value::F64)
+}
+
+/// 数值；`NumericStrings` 策略下数字字符串也视为数值
+fn numeric(v: &Value, coercion: Coercion) -> Option<f64> {
+    match v {
+        Value::Str(s) if coercion == Coercion::NumericStrings => s.trim().parse().ok(),
+        v => to_f64(v),
+    }
+}
+
+/// 比较两个操作数，无法比较时返回 `None`
+///
+/// 数字按数值比较；与字符串比较时，元组/结构体枚举变体按变体名比较；
+/// 任一侧为 NULL 时只有 `==`/`!=` 有意义。
+fn compare(left: &Value, op: &str, right: &Value, coercion: Coercion) -> Option<bool> {
+    if matches!(left, Value::Null) || matches!(right, Value::Null) {
+        return Some(match op {
+            "==" => left == right,
+            "!=" => left != right,
+            _ => false,
+        });
+    }
+    if let (Some(l), Some(r)) = (numeric(left, coercion), numeric(right, coercion)) {
+        return Some(match op {
+            "==" => (l - r).abs() < f64::EPSILON,
+            "!=" => (l - r).abs() > f64::EPSILON,
+            ">" => l > r,
+            ">=" => l >= r,
+            "<" => l < r,
+            "<=" => l <= r,
+            _ => false,
+        });
+    }
+    let equal = match (left, right) {
+        (Value::Map(m), Value::Str(s)) | (Value::Str(s), Value::Map(m)) if m.len() == 1 => {
+            m.contains_key(s)
+        }
+        _ if std::mem::discriminant(left) == std::mem::discriminant(right) => left == right,
+        _ => return None,
+    };
+    match op {
+        "==" => Some(equal),
+        "!=" => Some(!equal),
+        _ => None,
+    }
+}
+
+fn eval_atom(expr: &str, ctx: &Context, coercion: Coercion) -> Result<bool, DbError> {
     let expr = expr.trim();
     if expr.is_empty() {
-        return false;
+        return Ok(false);
     }
 
     // Split by operator (check longest operators first)
@@ -43,99 +198,401 @@ fn eval_atom(expr: &str, ctx: &Context) -> bool {
         (k.trim(), "<", v.trim())
     } else {
         let val = ctx.lookup(expr);
-        return !matches!(val, Value::Null | Value::Bool(false));
+        return Ok(!matches!(val, Value::Null | Value::Bool(false)));
     };
 
     let left = ctx.lookup(key);
 
     let right_owned;
-    let right = if val_str == "null" {
-        &Value::Null
-    } else if val_str == "true" {
-        &Value::Bool(true)
-    } else if val_str == "false" {
-        &Value::Bool(false)
-    } else if (val_str.starts_with('\'') && val_str.ends_with('\''))
-        || (val_str.starts_with('"') && val_str.ends_with('"'))
-    {
-        right_owned = Value::Str(val_str[1..val_str.len() - 1].to_string());
-        &right_owned
-    } else if let Ok(n) = val_str.parse::<i64>() {
-        right_owned = Value::I64(n);
-        &right_owned
-    } else if let Ok(n) = val_str.parse::<f64>() {
-        right_owned = Value::F64(n);
-        &right_owned
-    } else {
-        ctx.lookup(val_str)
+    let right = match parse_literal(val_str) {
+        Some(v) => {
+            right_owned = v;
+            &right_owned
+        }
+        None => ctx.lookup(val_str),
     };
 
-    match op {
-        "==" => {
-            if let (Some(l), Some(r)) = (to_f64(left), to_f64(right)) {
-                (l - r).abs() < f64::EPSILON
-            } else {
-                left == right
-            }
-        }
-        "!=" => {
-            if let (Some(l), Some(r)) = (to_f64(left), to_f64(right)) {
-                (l - r).abs() > f64::EPSILON
-            } else {
-                left != right
-            }
+    match compare(left, op, right, coercion) {
+        Some(result) => Ok(result),
+        None if coercion == Coercion::Strict => Err(DbError::Template(format!(
+            "cannot compare {:?} {} {:?} in test '{}'",
+            left, op, right, expr
+        ))),
+        None => {
+            log::warn!(
+                "test '{}': cannot compare {:?} {} {:?}, evaluating as false",
+                expr,
+                left,
+                op,
+                right
+            );
+            Ok(false)
         }
-        ">" => to_f64(left)
-            .zip(to_f64(right))
-            .map_or(false, |(l, r)| l > r),
-        ">=" => to_f64(left)
-            .zip(to_f64(right))
-            .map_or(false, |(l, r)| l >= r),
-        "<" => to_f64(left)
-            .zip(to_f64(right))
-            .map_or(false, |(l, r)| l < r),
-        "<=" => to_f64(left)
-            .zip(to_f64(right))
-            .map_or(false, |(l, r)| l <= r),
-        _ => false,
     }
 }
 
-pub fn eval_expr(expr: &str, ctx: &Context) -> bool {
+pub fn eval_expr(expr: &str, ctx: &Context, coercion: Coercion) -> Result<bool, DbError> {
     for or_part in expr.split(" or ") {
         let mut and_satisfied = true;
         for atom in or_part.split(" and ") {
-            if !eval_atom(atom, ctx) {
+            if !eval_atom(atom, ctx, coercion)? {
                 and_satisfied = false;
                 break;
             }
         }
         if and_satisfied {
-            return true;
+            return Ok(true);
         }
     }
-    false
+    Ok(false)
 }
 
-pub(crate) fn render(nodes: &[AstNode], ctx: &mut Context, buf: &mut RenderBuffer) {
+/// 空列表展开后的占位文本：`x IN (NULL)` 恒不成立，不会匹配任何行
+const EMPTY_LIST: &str = "(NULL)";
+
+/// `NOT IN` 后的空列表：`x NOT IN (NULL)` 的结果为 NULL，同样不匹配任何行，
+/// 改为不返回行的子查询，使 `x NOT IN (...)` 恒成立
+const EMPTY_NOT_IN_LIST: &str = "(SELECT NULL FROM (SELECT 1) empty_list WHERE 1 = 0)";
+
+/// 在已渲染的 SQL 之后展开空列表
+fn push_empty_list(buf: &mut RenderBuffer) {
+    let mut words = buf.sql.split_whitespace().rev();
+    let not_in = matches!(
+        (words.next(), words.next()),
+        (Some(last), Some(prev)) if last.eq_ignore_ascii_case("IN") && prev.eq_ignore_ascii_case("NOT")
+    );
+    buf.sql.push_str(if not_in {
+        EMPTY_NOT_IN_LIST
+    } else {
+        EMPTY_LIST
+    });
+}
+
+/// 追加一个绑定参数及其占位符
+pub(crate) fn push_param(buf: &mut RenderBuffer, name: String, value: Value) {
+    buf.param_count += 1;
+    buf.sql
+        .push_str(&buf.driver.placeholder(buf.param_count, &name));
+    buf.params.push((name, value));
+}
+
+/// 按 [`NestedParams`] 处理映射/列表参数：编码为 JSON 文本或报错
+pub(crate) fn encode_nested_params(buf: &mut RenderBuffer) -> Result<(), DbError> {
+    for (name, value) in buf.params.iter_mut() {
+        let kind = match value {
+            Value::Map(_) => "map",
+            Value::List(_) => "list",
+            _ => continue,
+        };
+        match buf.options.nested_params {
+            NestedParams::Json => *value = Value::Str(value_to_json(value).to_string()),
+            NestedParams::Reject => {
+                return Err(DbError::Value(format!(
+                    "parameter '{}' is a {} and cannot be bound; wrap it in Json<T> or bind its fields",
+                    name, kind
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 原样输出 SQL，参数按位置绑定：列表（含元组）逐个元素绑定，`NULL` 不绑定，
+/// 其他标量作为唯一的参数
+fn push_raw(buf: &mut RenderBuffer, sql: &str, args: &Value) -> Result<(), DbError> {
+    let values = match args {
+        Value::Null => &[][..],
+        Value::List(items) => items.as_slice(),
+        Value::Map(_) => {
+            return Err(DbError::Template(
+                "raw statement takes positional parameters, got named ones".to_string(),
+            ));
+        }
+        v => std::slice::from_ref(v),
+    };
+    buf.sql.push_str(sql);
+    for value in values {
+        buf.param_count += 1;
+        buf.params
+            .push((buf.param_count.to_string(), value.clone()));
+    }
+    Ok(())
+}
+
+/// 标识符各段的最大长度
+const MAX_IDENT_LEN: usize = 128;
+
+/// 追加加引号的标识符；`schema.table` 形式按 `.` 分段分别加引号
+fn push_ident(buf: &mut RenderBuffer, name: &str, value: &Value) -> Result<(), DbError> {
+    let Value::Str(ident) = value else {
+        return Err(DbError::Template(format!(
+            "identifier '{}' must be a string, got {:?}",
+            name, value
+        )));
+    };
+    for (i, part) in ident.split('.').enumerate() {
+        if part.is_empty() || part.len() > MAX_IDENT_LEN || part.chars().any(char::is_control) {
+            return Err(DbError::Template(format!(
+                "invalid identifier '{}' for '{}'",
+                ident, name
+            )));
+        }
+        if i > 0 {
+            buf.sql.push('.');
+        }
+        buf.sql.push_str(&buf.driver.quote_ident(part));
+    }
+    Ok(())
+}
+
+/// 追加一个包裹在 SQL 函数中的绑定参数；列表参数逐个元素包裹
+fn push_call(buf: &mut RenderBuffer, name: &str, func: &str, value: &Value) -> Result<(), DbError> {
+    let items = match value {
+        Value::List(items) if items.is_empty() => {
+            push_empty_list(buf);
+            return Ok(());
+        }
+        Value::List(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("{}[{}]", name, i), v.clone()))
+            .collect(),
+        v => vec![(name.to_string(), v.clone())],
+    };
+    let is_list = matches!(value, Value::List(_));
+    if is_list {
+        buf.sql.push('(');
+    }
+    for (i, (param, v)) in items.into_iter().enumerate() {
+        if i > 0 {
+            buf.sql.push_str(", ");
+        }
+        buf.param_count += 1;
+        let placeholder = buf.driver.placeholder(buf.param_count, &param);
+        let wrapped = buf.driver.wrap_param(func, &placeholder).ok_or_else(|| {
+            DbError::Template(format!("unsupported function '{}' on #{{{}}}", func, name))
+        })?;
+        buf.sql.push_str(&wrapped);
+        buf.params.push((param, v));
+    }
+    if is_list {
+        buf.sql.push(')');
+    }
+    Ok(())
+}
+
+/// 将列表参数展开为 `(?, ?, ?)`，每个元素单独绑定
+fn push_list(buf: &mut RenderBuffer, name: &str, items: &[Value]) {
+    if items.is_empty() {
+        push_empty_list(buf);
+        return;
+    }
+    buf.sql.push('(');
+    for (i, v) in items.iter().enumerate() {
+        if i > 0 {
+            buf.sql.push_str(", ");
+        }
+        push_param(buf, format!("{}[{}]", name, i), v.clone());
+    }
+    buf.sql.push(')');
+}
+
+/// 将映射展开为 `SET a = ?, b = ?`，按字段名排序以保证 SQL 稳定
+fn push_set(buf: &mut RenderBuffer, from: &str, value: &Value) -> Result<(), DbError> {
+    let fields = match value {
+        Value::Map(m) if !m.is_empty() => m,
+        _ => {
+            return Err(DbError::Template(format!(
+                "'{}' in <set> must be a non-empty changeset",
+                from
+            )));
+        }
+    };
+    let mut names: Vec<&String> = fields.keys().collect();
+    names.sort();
+    buf.sql.push_str("SET ");
+    for (i, name) in names.into_iter().enumerate() {
+        if !is_column_name(name) {
+            return Err(DbError::Template(format!(
+                "invalid column name '{}' in <set from=\"{}\">",
+                name, from
+            )));
+        }
+        if i > 0 {
+            buf.sql.push_str(", ");
+        }
+        buf.sql.push_str(name);
+        buf.sql.push_str(" = ");
+        push_param(buf, format!("{}.{}", from, name), fields[name].clone());
+    }
+    Ok(())
+}
+
+/// 列名直接拼入 SQL，只允许字母、数字与下划线
+fn is_column_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `<include refid>` 引用的片段：已加载的 `<sql>` 语句按当前版本解析，否则取按名缓存的模板
+///
+/// 片段内容变化时按内容哈希重新解析，与引用它的语句在同一重载屏障内读取，不会混用新旧版本。
+fn include_ast(refid: &str, database_type: &str) -> Option<Arc<Template>> {
+    let Some(mapper) = find_mapper(refid, database_type) else {
+        return TEMPLATE_CACHE.get(refid);
+    };
+    let content = mapper.content.as_deref()?;
+    let key = template_key(refid, mapper.database_type.as_deref());
+    Some(TEMPLATE_CACHE.get_ast(&key, content))
+}
+
+/// `<with refs>`：展开引用的 `<cte>` 片段及其依赖，渲染为 `WITH a AS (...), b AS (...)`
+///
+/// 依赖排在引用它的片段之前，同一片段只出现一次；任一片段声明了 `recursive` 时输出 `WITH RECURSIVE`。
+/// 片段按当前重载版本解析，内容按模板渲染，参数按出现顺序绑定。
+fn push_with(refs: &[String], ctx: &mut Context, buf: &mut RenderBuffer) -> Result<(), DbError> {
+    let database_type = buf.driver.r#type();
+    let mut ordered = Vec::new();
+    for id in refs {
+        resolve_cte(id, database_type, &mut Vec::new(), &mut ordered)?;
+    }
+    if ordered.is_empty() {
+        return Ok(());
+    }
+    buf.sql.push_str("WITH ");
+    if ordered.iter().any(|(_, mapper)| mapper.recursive) {
+        buf.sql.push_str("RECURSIVE ");
+    }
+    for (i, (id, mapper)) in ordered.iter().enumerate() {
+        if i > 0 {
+            buf.sql.push_str(", ");
+        }
+        buf.sql.push_str(cte_name(id));
+        buf.sql.push_str(" AS (");
+        let content = mapper.content.as_deref().unwrap_or_default();
+        let key = template_key(id, mapper.database_type.as_deref());
+        render(&TEMPLATE_CACHE.get_ast(&key, content), ctx, buf)?;
+        buf.sql.push(')');
+    }
+    Ok(())
+}
+
+/// 片段在 WITH 中的名称：ID 去掉命名空间
+fn cte_name(id: &str) -> &str {
+    id.rsplit('.').next().unwrap_or(id)
+}
+
+/// 深度优先地将 `id` 及其依赖按依赖顺序加入 `ordered`；`visiting` 为当前路径，用于发现循环引用
+fn resolve_cte(
+    id: &str,
+    database_type: &str,
+    visiting: &mut Vec<String>,
+    ordered: &mut Vec<(String, Arc<SqlMapper>)>,
+) -> Result<(), DbError> {
+    if ordered.iter().any(|(done, _)| done == id) {
+        return Ok(());
+    }
+    if let Some(pos) = visiting.iter().position(|v| v == id) {
+        let path: Vec<&str> = visiting[pos..]
+            .iter()
+            .map(String::as_str)
+            .chain([id])
+            .collect();
+        return Err(DbError::Template(format!(
+            "cyclic <cte> reference: {}",
+            path.join(" -> ")
+        )));
+    }
+    let mapper = find_mapper(id, database_type)
+        .filter(|mapper| mapper.kind == StatementKind::Cte)
+        .ok_or_else(|| DbError::Template(format!("<cte> '{}' not found", id)))?;
+    visiting.push(id.to_string());
+    for dep in &mapper.cte_refs {
+        resolve_cte(dep, database_type, visiting, ordered)?;
+    }
+    visiting.pop();
+    // 不同命名空间中的同名片段不能出现在同一个 WITH 中
+    if let Some((other, _)) = ordered
+        .iter()
+        .find(|(done, _)| cte_name(done) == cte_name(id))
+    {
+        return Err(DbError::Template(format!(
+            "<cte> '{}' and '{}' have the same name",
+            other, id
+        )));
+    }
+    ordered.push((id.to_string(), mapper));
+    Ok(())
+}
+
+pub(crate) fn render(
+    nodes: &[AstNode],
+    ctx: &mut Context,
+    buf: &mut RenderBuffer,
+) -> Result<(), DbError> {
     for node in nodes {
         match node {
             AstNode::Text(t) => buf.sql.push_str(t),
-            AstNode::Var(name) => {
-                let v = ctx.lookup(name);
-                buf.params.push((name.clone(), v.clone()));
-                buf.param_count += 1;
-                buf.sql
-                    .push_str(&buf.driver.placeholder(buf.param_count, name));
+            AstNode::Var(name) => match ctx.lookup(name) {
+                Value::List(items) => push_list(buf, name, items),
+                v => push_param(buf, name.clone(), v.clone()),
+            },
+            AstNode::Call { name, func } => push_call(buf, name, func, ctx.lookup(name))?,
+            AstNode::Default {
+                name,
+                default,
+                func,
+            } => {
+                let value = match ctx.lookup(name) {
+                    Value::Null => default,
+                    v => v,
+                };
+                match (func, value) {
+                    (Some(func), v) => push_call(buf, name, func, v)?,
+                    (None, Value::List(items)) => push_list(buf, name, items),
+                    (None, v) => push_param(buf, name.clone(), v.clone()),
+                }
             }
             AstNode::Include { refid } => {
-                if let Some(cached) = TEMPLATE_CACHE.get(refid) {
-                    render(&cached.ast, ctx, buf);
+                if let Some(ast) = include_ast(refid, buf.driver.r#type()) {
+                    render(&ast, ctx, buf)?;
                 }
             }
+            AstNode::IncludeEnv { name } => match snippet::snippet(name) {
+                // 以名称为键缓存，内容变化时按内容哈希重新解析
+                Some(content) => render(
+                    &TEMPLATE_CACHE.get_ast(&format!("env:{}", name), &content),
+                    ctx,
+                    buf,
+                )?,
+                None if buf.options.strict => {
+                    return Err(DbError::Template(format!(
+                        "snippet '{}' in <include env> is not provided",
+                        name
+                    )));
+                }
+                None => {}
+            },
+            AstNode::With { refs } => push_with(refs, ctx, buf)?,
+            AstNode::Set { from } => push_set(buf, from, ctx.lookup(from))?,
+            AstNode::Ident(name) => push_ident(buf, name, ctx.lookup(name))?,
+            AstNode::PoolVar { name, default } => {
+                let value = buf
+                    .driver
+                    .template_var(name)
+                    .or(default.as_deref())
+                    .ok_or_else(|| {
+                        DbError::Template(format!(
+                            "template variable '${{{}}}' is not configured for pool '{}'",
+                            name,
+                            buf.driver.name()
+                        ))
+                    })?;
+                buf.sql.push_str(value);
+            }
+            AstNode::Raw(sql) => push_raw(buf, sql, ctx.root())?,
             AstNode::If { test, body } => {
-                if eval_expr(test, ctx) {
-                    render(body, ctx, buf);
+                if eval_expr(test, ctx, buf.options.coercion)? {
+                    render(body, ctx, buf)?;
                 }
             }
             AstNode::For {
@@ -144,13 +601,22 @@ pub(crate) fn render(nodes: &[AstNode], ctx: &mut Context, buf: &mut RenderBuffe
                 open,
                 sep,
                 close,
+                if_empty,
                 body,
             } => {
                 let arr = match ctx.lookup(collection) {
-                    Value::List(v) => v,
-                    _ => continue,
+                    Value::List(v) => v.as_slice(),
+                    _ => &[],
                 };
                 if arr.is_empty() {
+                    if let Some(text) = if_empty {
+                        buf.sql.push_str(text);
+                    } else if buf.options.strict {
+                        return Err(DbError::Template(format!(
+                            "collection '{}' in <for> is empty and no ifEmpty is declared",
+                            collection
+                        )));
+                    }
                     continue;
                 }
 
@@ -161,13 +627,15 @@ pub(crate) fn render(nodes: &[AstNode], ctx: &mut Context, buf: &mut RenderBuffe
                     }
 
                     ctx.push(item, v);
-                    render(body, ctx, buf);
+                    let r = render(body, ctx, buf);
                     ctx.pop();
+                    r?;
                 }
                 buf.sql.push_str(close);
             }
         }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -180,7 +648,7 @@ mod tests {
         let root = Value::Map(HashMap::new());
         let ctx = Context::new(&root);
 
-        assert_eq!(eval_atom("var", &ctx), false);
+        assert!(!eval_atom("var", &ctx, Coercion::None).unwrap());
 
         let mut map = HashMap::new();
         map.insert("a".to_string(), Value::I64(10));
@@ -189,18 +657,55 @@ mod tests {
         let root = Value::Map(map);
         let ctx = Context::new(&root);
 
-        assert!(eval_atom("a == 10", &ctx));
-        assert!(eval_atom("a != 5", &ctx));
-        assert!(eval_atom("b == 'hello'", &ctx));
-        assert!(eval_atom("b != 'world'", &ctx));
-        assert!(eval_atom("c", &ctx));
-        assert!(eval_atom("c == true", &ctx));
+        assert!(eval_atom("a == 10", &ctx, Coercion::None).unwrap());
+        assert!(eval_atom("a != 5", &ctx, Coercion::None).unwrap());
+        assert!(eval_atom("b == 'hello'", &ctx, Coercion::None).unwrap());
+        assert!(eval_atom("b != 'world'", &ctx, Coercion::None).unwrap());
+        assert!(eval_atom("c", &ctx, Coercion::None).unwrap());
+        assert!(eval_atom("c == true", &ctx, Coercion::None).unwrap());
 
         // New comparisons
-        assert!(eval_atom("a > 5", &ctx));
-        assert!(eval_atom("a >= 10", &ctx));
-        assert!(eval_atom("a < 20", &ctx));
-        assert!(eval_atom("a <= 10", &ctx));
+        assert!(eval_atom("a > 5", &ctx, Coercion::None).unwrap());
+        assert!(eval_atom("a >= 10", &ctx, Coercion::None).unwrap());
+        assert!(eval_atom("a < 20", &ctx, Coercion::None).unwrap());
+        assert!(eval_atom("a <= 10", &ctx, Coercion::None).unwrap());
+    }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        let sql = "\n  select *\n    from user\n  where 1=1\n   and name = '  a   b '  \n and x = \"c  d\"\n";
+        assert_eq!(
+            normalize_whitespace(sql),
+            "select * from user where 1=1 and name = '  a   b ' and x = \"c  d\""
+        );
+        assert_eq!(
+            normalize_whitespace("a = 'it''s   ok'   b"),
+            "a = 'it''s   ok' b"
+        );
+        assert_eq!(normalize_whitespace("a = 'x\\'  y'  b"), "a = 'x\\'  y' b");
+    }
+
+    #[test]
+    fn test_normalize_whitespace_keeps_comments() {
+        // 行注释结束处的换行保留，其后的条件不会被并入注释
+        let sql = "select *\n  from user -- only   active\n  where status = 1\n";
+        assert_eq!(
+            normalize_whitespace(sql),
+            "select * from user -- only   active\nwhere status = 1"
+        );
+        assert_eq!(
+            normalize_whitespace("select 1 #  note\n\n  , 2"),
+            "select 1 #  note\n, 2"
+        );
+        // 块注释原样保留，其中的引号不影响之后的折叠
+        let sql = "select /*  it's\n  a hint */   id\n  from user";
+        assert_eq!(
+            normalize_whitespace(sql),
+            "select /*  it's\n  a hint */ id from user"
+        );
+        assert_eq!(normalize_whitespace("a /**/  b"), "a /**/ b");
+        assert_eq!(normalize_whitespace("a /*/  x  */  b"), "a /*/  x  */ b");
+        assert_eq!(normalize_whitespace("a -- end  "), "a -- end");
     }
 
     #[test]
@@ -211,8 +716,30 @@ mod tests {
         let root = Value::Map(map);
         let ctx = Context::new(&root);
 
-        assert!(eval_expr("x == 1 and y == 2", &ctx));
-        assert!(eval_expr("x == 1 or y == 3", &ctx));
-        assert!(!eval_expr("x == 2 or y == 3", &ctx));
+        assert!(eval_expr("x == 1 and y == 2", &ctx, Coercion::None).unwrap());
+        assert!(eval_expr("x == 1 or y == 3", &ctx, Coercion::None).unwrap());
+        assert!(!eval_expr("x == 2 or y == 3", &ctx, Coercion::None).unwrap());
+    }
+
+    #[test]
+    fn test_coercion_policies() {
+        let mut map = HashMap::new();
+        map.insert("n".to_string(), Value::Str("10".to_string()));
+        map.insert("flag".to_string(), Value::Bool(true));
+        let root = Value::Map(map);
+        let ctx = Context::new(&root);
+
+        assert!(!eval_atom("n > 5", &ctx, Coercion::None).unwrap());
+        assert!(eval_atom("n > 5", &ctx, Coercion::NumericStrings).unwrap());
+        assert!(eval_atom("n == 10", &ctx, Coercion::NumericStrings).unwrap());
+        assert!(!eval_atom("n == 'x'", &ctx, Coercion::NumericStrings).unwrap());
+        assert!(matches!(
+            eval_atom("n > 5", &ctx, Coercion::Strict),
+            Err(DbError::Template(_))
+        ));
+        assert!(eval_atom("flag == 1", &ctx, Coercion::Strict).is_err());
+        // NULL 参与比较不视为类型错误
+        assert!(!eval_atom("missing > 5", &ctx, Coercion::Strict).unwrap());
+        assert!(eval_atom("missing == null", &ctx, Coercion::Strict).unwrap());
     }
 }
@@ -1,8 +1,10 @@
+use crate::tpl::ParamNaming;
 use crate::udbc::value::Value;
 
 pub struct Context<'a> {
     root: &'a Value,
     locals: Vec<(String, &'a Value)>,
+    naming: ParamNaming,
 }
 
 impl<'a> Context<'a> {
@@ -10,9 +12,21 @@ impl<'a> Context<'a> {
         Self {
             root,
             locals: Vec::new(),
+            naming: ParamNaming::Exact,
         }
     }
 
+    /// 设置参数名的匹配方式，只影响参数中的键，`<for>` 等定义的局部变量总是按原名匹配
+    pub fn with_naming(mut self, naming: ParamNaming) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// 渲染参数本身
+    pub fn root(&self) -> &'a Value {
+        self.root
+    }
+
     pub fn push(&mut self, key: &str, value: &'a Value) {
         self.locals.push((key.to_string(), value));
     }
@@ -27,15 +41,16 @@ impl<'a> Context<'a> {
             return v;
         }
 
-        // 2. 尝试嵌套查找（例如 "user.name"）
-        if let Some((head, rest)) = key.split_once('.') {
-            // 先找到第一级对象
-            if let Some(head_value) = self.get_from_scope(head) {
-                // 然后递归查找剩余路径
-                if let Some(target) = Self::resolve_path(head_value, rest) {
-                    return target;
-                }
-            }
+        // 2. 尝试嵌套查找（例如 "user.name"、"tags[0]"、"users[1].name"）
+        let split = key.find(['.', '[']).unwrap_or(key.len());
+        let (head, rest) = key.split_at(split);
+        if !rest.is_empty()
+            // 先找到第一级对象（局部变量优先）
+            && let Some(head_value) = self.get_from_scope(head)
+            // 然后递归查找剩余路径
+            && let Some(target) = self.resolve_path(head_value, rest)
+        {
+            return target;
         }
 
         &Value::Null
@@ -49,20 +64,40 @@ impl<'a> Context<'a> {
 
         // 2. 查找根对象
         if let Value::Map(m) = self.root {
-            return m.get(key);
+            return self.naming.find(m, key);
         }
 
         None
     }
 
-    /// 辅助函数：在 Value 中根据点号分隔的路径查找值
-    fn resolve_path(mut current: &'a Value, path: &str) -> Option<&'a Value> {
-        for part in path.split('.') {
-            match current {
-                Value::Map(m) => {
-                    current = m.get(part)?;
-                }
-                _ => return None,
+    /// 辅助函数：在 Value 中按路径查找值，路径由 `.name` 与 `[index]` 组成；
+    /// 负数下标从末尾计数，`[-1]` 为最后一个元素
+    fn resolve_path(&self, mut current: &'a Value, path: &str) -> Option<&'a Value> {
+        let mut rest = path;
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('[') {
+                let (index, tail) = after.split_once(']')?;
+                current = match current {
+                    Value::List(items) => {
+                        let index: i64 = index.trim().parse().ok()?;
+                        let index = if index < 0 {
+                            items.len().checked_sub(index.unsigned_abs() as usize)?
+                        } else {
+                            index as usize
+                        };
+                        items.get(index)?
+                    }
+                    _ => return None,
+                };
+                rest = tail;
+            } else {
+                let part = rest.strip_prefix('.').unwrap_or(rest);
+                let end = part.find(['.', '[']).unwrap_or(part.len());
+                current = match current {
+                    Value::Map(m) => self.naming.find(m, &part[..end])?,
+                    _ => return None,
+                };
+                rest = &part[end..];
             }
         }
         Some(current)
@@ -126,4 +161,55 @@ mod tests {
         // "a.b" should be found in locals as exact match
         assert_eq!(ctx.lookup("a.b"), &Value::I64(3));
     }
+
+    #[test]
+    fn test_lookup_indexed_and_local_paths() {
+        let user = Value::Map(HashMap::from([(
+            "tags".to_string(),
+            Value::List(vec![
+                Value::Str("a".to_string()),
+                Value::Str("b".to_string()),
+            ]),
+        )]));
+        let root = Value::Map(HashMap::from([(
+            "users".to_string(),
+            Value::List(vec![user.clone()]),
+        )]));
+        let mut ctx = Context::new(&root);
+
+        assert_eq!(ctx.lookup("users[0].tags[1]"), &Value::Str("b".to_string()));
+        assert_eq!(ctx.lookup("users[1].tags"), &Value::Null);
+        assert_eq!(ctx.lookup("users[x]"), &Value::Null);
+        assert_eq!(
+            ctx.lookup("users[-1].tags[-1]"),
+            &Value::Str("b".to_string())
+        );
+        assert_eq!(ctx.lookup("users[0].tags[-3]"), &Value::Null);
+
+        ctx.push("u", &user);
+        assert_eq!(ctx.lookup("u.tags[0]"), &Value::Str("a".to_string()));
+        assert!(matches!(ctx.lookup("u.tags"), Value::List(v) if v.len() == 2));
+    }
+
+    #[test]
+    fn test_lookup_with_naming() {
+        let profile = Value::Map(HashMap::from([("home_city".to_string(), Value::I64(2))]));
+        let root = Value::Map(HashMap::from([
+            ("user_id".to_string(), Value::I64(1)),
+            ("userProfile".to_string(), profile),
+            ("Name".to_string(), Value::I64(3)),
+        ]));
+
+        let ctx = Context::new(&root);
+        assert_eq!(ctx.lookup("userId"), &Value::Null);
+
+        let ctx = Context::new(&root).with_naming(ParamNaming::IgnoreCase);
+        assert_eq!(ctx.lookup("name"), &Value::I64(3));
+        assert_eq!(ctx.lookup("userId"), &Value::Null);
+
+        let ctx = Context::new(&root).with_naming(ParamNaming::Relaxed);
+        assert_eq!(ctx.lookup("userId"), &Value::I64(1));
+        assert_eq!(ctx.lookup("user_profile.homeCity"), &Value::I64(2));
+        assert_eq!(ctx.lookup("user"), &Value::Null);
+    }
 }
@@ -0,0 +1,58 @@
+//! 运行时注入的 SQL 片段
+//!
+//! 模板中的 `<include env="EXTRA_PREDICATE"/>` 在渲染时向 [`SnippetProvider`] 取片段文本，
+//! 片段本身按模板解析，可以引用参数（如 `AND tenant_id = #{tenant}`）。
+//! 运维可据此注入租户或合规条件而无需重新构建应用；片段原样拼入 SQL，只应来自可信配置。
+//!
+//! 未设置提供者时从进程环境变量读取。片段缺失时渲染为空，
+//! 严格模式（[`RenderOptions::strict`](crate::tpl::RenderOptions::strict)）下渲染报错。
+//!
+//! ```ignore
+//! tpl::set_snippet_provider(Arc::new(EnvSnippets::with_prefix("UORM_SNIPPET_")));
+//! ```
+
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// 片段提供者：按名称返回片段文本，不存在时返回 `None`
+pub trait SnippetProvider: Send + Sync {
+    fn snippet(&self, name: &str) -> Option<String>;
+}
+
+/// 从环境变量 `{prefix}{name}` 读取片段
+#[derive(Debug, Clone, Default)]
+pub struct EnvSnippets {
+    prefix: String,
+}
+
+impl EnvSnippets {
+    pub fn with_prefix(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+}
+
+impl SnippetProvider for EnvSnippets {
+    fn snippet(&self, name: &str) -> Option<String> {
+        std::env::var(format!("{}{}", self.prefix, name)).ok()
+    }
+}
+
+static PROVIDER: LazyLock<RwLock<Arc<dyn SnippetProvider>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(EnvSnippets::default())));
+
+/// 设置全局片段提供者
+pub fn set_snippet_provider(provider: Arc<dyn SnippetProvider>) {
+    *PROVIDER.write().unwrap() = provider;
+}
+
+/// 恢复为从环境变量读取片段
+pub fn clear_snippet_provider() {
+    set_snippet_provider(Arc::new(EnvSnippets::default()));
+}
+
+/// 取名为 `name` 的片段
+pub(crate) fn snippet(name: &str) -> Option<String> {
+    let provider = PROVIDER.read().unwrap().clone();
+    provider.snippet(name)
+}
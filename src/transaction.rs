@@ -1,68 +1,623 @@
 use crate::error::DbError;
+use crate::executor::digest;
+use crate::executor::instrument::{Outcome, StatementSpan};
+use crate::executor::session::{self, Session};
+use crate::mapper_loader::{self, template_key};
 use crate::tpl::engine;
-use crate::udbc::connection::Connection;
-use crate::udbc::driver::Driver;
+use crate::type_registry::check_result;
+use crate::udbc::connection::{ColumnMeta, Connection, RawConnection};
+use crate::udbc::driver::{Driver, TransactionLimits, is_function_name};
 use crate::udbc::value::Value;
+use futures_util::{Stream, TryStreamExt, stream};
 use serde::Serialize;
-use std::collections::HashMap;
-use std::sync::Arc;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tracing::{Instrument, Span};
+
+/// 事务历史中保留的最近语句条数
+const MAX_HISTORY: usize = 64;
+
+/// 事务中执行过的一条语句
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementRecord {
+    pub sql: String,
+    /// 绑定参数的摘要（16 位十六进制），日志中不出现参数值本身
+    pub params_digest: String,
+    pub elapsed: Duration,
+    pub ok: bool,
+}
+
+impl fmt::Display for StatementRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} [params={}, elapsed_ms={}, {}]",
+            self.sql,
+            self.params_digest,
+            self.elapsed.as_millis(),
+            if self.ok { "ok" } else { "failed" }
+        )
+    }
+}
 
 pub struct TransactionContext {
     conn: Arc<dyn Connection>,
     committed: bool,
+    /// 已创建的保存点数，用于生成保存点名称
+    savepoints: u32,
     driver: Arc<dyn Driver>,
+    watch: Arc<TxWatch>,
+}
+
+/// 事务的运行状态，由事务本身与超时监视任务共享
+struct TxWatch {
+    started: Instant,
+    /// `uorm.transaction` span，事务内的语句与生命周期事件都在其中记录
+    span: Span,
+    /// 最近执行的语句
+    history: Mutex<VecDeque<StatementRecord>>,
+    /// 事务已提交或回滚
+    finished: AtomicBool,
+    /// 事务因超时被强制回滚
+    poisoned: AtomicBool,
+    /// 语句、提交/回滚与强制回滚互斥执行，强制回滚不会插在检查与执行之间
+    gate: tokio::sync::Mutex<()>,
+    /// 事务结束（提交、回滚或释放）时通知监视任务退出
+    done: tokio::sync::Notify,
+}
+
+impl TxWatch {
+    fn record(&self, sql: &str, params: &[(String, Value)], elapsed: Duration, ok: bool) {
+        let record = StatementRecord {
+            sql: sql.to_string(),
+            params_digest: format!("{:016x}", digest::fnv1a(&format!("{:?}", params))),
+            elapsed,
+            ok,
+        };
+        let mut history = self.history.lock().unwrap();
+        if history.len() == MAX_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(record);
+    }
+
+    /// 用于日志的多行历史文本
+    fn dump(&self) -> String {
+        self.history
+            .lock()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .map(|(i, record)| format!("\n  {}. {}", i + 1, record))
+            .collect()
+    }
+
+    fn is_active(&self) -> bool {
+        !self.finished.load(Ordering::SeqCst)
+    }
+
+    /// 标记事务结束并让监视任务立即退出
+    fn finish(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+        self.done.notify_one();
+    }
+
+    /// 记录事务生命周期事件，带 `elapsed_ms` 与 `statements` 字段；
+    /// 启用 `log-output` 特性时另输出一行 `log` 日志
+    fn report(&self, warn: bool, message: &str) {
+        let elapsed_ms = self.started.elapsed().as_millis() as u64;
+        let statements = self.dump();
+        self.span.in_scope(|| {
+            if warn {
+                tracing::warn!(elapsed_ms, statements = %statements, "{}", message);
+            } else {
+                tracing::debug!(elapsed_ms, statements = %statements, "{}", message);
+            }
+        });
+        #[cfg(feature = "log-output")]
+        {
+            let level = if warn {
+                log::Level::Warn
+            } else {
+                log::Level::Debug
+            };
+            log::log!(
+                level,
+                "{} after {}ms, statements:{}",
+                message,
+                elapsed_ms,
+                statements
+            );
+        }
+    }
 }
 
 impl TransactionContext {
     pub async fn begin(pool: Arc<dyn Driver>) -> Result<Self, DbError> {
-        let conn = pool.connection().await?;
-        conn.begin().await?;
+        Self::open(pool, false).await
+    }
+
+    /// 开启只读事务，见 [`Connection::begin_read_only`](crate::udbc::connection::Connection::begin_read_only)
+    pub async fn begin_read_only(pool: Arc<dyn Driver>) -> Result<Self, DbError> {
+        Self::open(pool, true).await
+    }
+
+    async fn open(pool: Arc<dyn Driver>, read_only: bool) -> Result<Self, DbError> {
+        let span = tracing::debug_span!("uorm.transaction", db.name = pool.name());
+        // 处于 schema 作用域中时在已切换 schema 的连接上开启事务
+        let conn = session::acquire(pool.as_ref())
+            .instrument(span.clone())
+            .await?;
+        if read_only {
+            conn.begin_read_only().instrument(span.clone()).await?;
+        } else {
+            conn.begin().instrument(span.clone()).await?;
+        }
+        let watch = Arc::new(TxWatch {
+            started: Instant::now(),
+            span,
+            history: Mutex::new(VecDeque::new()),
+            finished: AtomicBool::new(false),
+            poisoned: AtomicBool::new(false),
+            gate: tokio::sync::Mutex::new(()),
+            done: tokio::sync::Notify::new(),
+        });
+        let limits = pool.transaction_limits();
+        if !limits.is_unlimited() {
+            // 只持有弱引用，事务结束后连接照常归还连接池
+            tokio::spawn(supervise(watch.clone(), Arc::downgrade(&conn), limits));
+        }
         Ok(Self {
             conn,
             committed: false,
+            savepoints: 0,
             driver: pool,
+            watch,
         })
     }
 
     pub async fn commit(&mut self) -> Result<(), DbError> {
-        self.conn.commit().await?;
+        let watch = self.watch.clone();
+        let _gate = watch.gate.lock().await;
+        self.check()?;
+        self.conn
+            .commit()
+            .instrument(self.watch.span.clone())
+            .await?;
         self.committed = true;
+        self.watch.finish();
+        self.watch.report(false, "transaction committed");
         Ok(())
     }
 
     pub async fn rollback(&mut self) -> Result<(), DbError> {
-        let r = self.conn.rollback().await;
-        if r.is_ok() {
+        let watch = self.watch.clone();
+        let _gate = watch.gate.lock().await;
+        if self.watch.poisoned.load(Ordering::SeqCst) {
+            // 已被强制回滚
             self.committed = true;
+            return Ok(());
+        }
+        let r = self
+            .conn
+            .rollback()
+            .instrument(self.watch.span.clone())
+            .await;
+        match &r {
+            Ok(()) => {
+                self.committed = true;
+                self.watch.finish();
+                self.watch.report(false, "transaction rolled back");
+            }
+            Err(e) => self
+                .watch
+                .report(true, &format!("transaction rollback failed: {}", e)),
         }
         r
     }
 
+    /// 事务中最近执行的语句（至多保留 64 条），按执行顺序排列
+    pub fn history(&self) -> Vec<StatementRecord> {
+        self.watch.history.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// 事务是否因超过最长持续时间而被强制回滚
+    pub fn is_poisoned(&self) -> bool {
+        self.watch.poisoned.load(Ordering::SeqCst)
+    }
+
+    /// 事务已持续的时间
+    pub fn elapsed(&self) -> Duration {
+        self.watch.started.elapsed()
+    }
+
+    /// 被强制回滚后拒绝继续使用，避免后续语句在自动提交模式下执行
+    fn check(&self) -> Result<(), DbError> {
+        if self.is_poisoned() {
+            return Err(DbError::TransactionAborted(format!(
+                "rolled back after exceeding max duration ({}ms elapsed)",
+                self.elapsed().as_millis()
+            )));
+        }
+        Ok(())
+    }
+
+    /// 取得执行权并检查事务仍然有效，持有返回的守卫期间强制回滚不会发生
+    async fn enter(&self) -> Result<tokio::sync::MutexGuard<'_, ()>, DbError> {
+        let gate = self.watch.gate.lock().await;
+        self.check()?;
+        Ok(gate)
+    }
+
+    /// 事务所用底层连接的句柄
+    pub fn raw_connection(&self) -> RawConnection {
+        RawConnection::new(self.conn.clone())
+    }
+
     pub async fn query<T: Serialize>(
         &self,
         sql: &str,
         args: &T,
     ) -> Result<Vec<HashMap<String, Value>>, DbError> {
-        let (rendered_sql, params) = engine::render_template(sql, sql, args, self.driver.as_ref());
-        self.conn.query(&rendered_sql, &params).await
+        let (rendered_sql, params) = engine::render_template(sql, sql, args, self.driver.as_ref())?;
+        self.observed_query(None, &rendered_sql, &params).await
+    }
+
+    /// 以语句 ID 作为模板缓存键执行查询
+    pub async fn query_named<T: Serialize>(
+        &self,
+        stmt_id: &str,
+        sql: &str,
+        args: &T,
+    ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+        let (rendered_sql, params) =
+            engine::render_statement(stmt_id, sql, args, self.driver.as_ref())?;
+        self.observed_query(Some(stmt_id), &rendered_sql, &params)
+            .await
     }
 
     pub async fn execute<T: Serialize>(&self, sql: &str, args: &T) -> Result<u64, DbError> {
-        let (rendered_sql, params) = engine::render_template(sql, sql, args, self.driver.as_ref());
-        self.conn.execute(&rendered_sql, &params).await
+        let (rendered_sql, params) = engine::render_template(sql, sql, args, self.driver.as_ref())?;
+        self.observed_execute(None, &rendered_sql, &params).await
+    }
+
+    /// 以语句 ID 作为模板缓存键执行更新
+    pub async fn execute_named<T: Serialize>(
+        &self,
+        stmt_id: &str,
+        sql: &str,
+        args: &T,
+    ) -> Result<u64, DbError> {
+        let (rendered_sql, params) =
+            engine::render_statement(stmt_id, sql, args, self.driver.as_ref())?;
+        self.observed_execute(Some(stmt_id), &rendered_sql, &params)
+            .await
+    }
+
+    /// 执行在重载代数 `generation` 时查找的 Mapper 语句，见 [`engine::render_mapper_statement`]
+    pub(crate) async fn execute_mapper<T: Serialize>(
+        &self,
+        stmt_id: &str,
+        sql: &str,
+        generation: u64,
+        args: &T,
+    ) -> Result<u64, DbError> {
+        let driver = self.driver.as_ref();
+        let (rendered_sql, params) = engine::render_mapper_statement(
+            stmt_id,
+            sql,
+            generation,
+            args,
+            driver,
+            engine::default_naming(driver),
+        )?;
+        self.observed_execute(Some(stmt_id), &rendered_sql, &params)
+            .await
+    }
+
+    /// 在事务连接上执行已渲染的查询（统计由调用方负责）
+    pub(crate) async fn query_rendered(
+        &self,
+        sql: &str,
+        params: &[(String, Value)],
+    ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+        let _gate = self.enter().await?;
+        let start = Instant::now();
+        let result = self.conn.query(sql, params).await;
+        self.watch
+            .record(sql, params, start.elapsed(), result.is_ok());
+        result
+    }
+
+    /// 在事务连接上执行已渲染的查询并返回列元数据（统计由调用方负责）
+    pub(crate) async fn query_meta_rendered(
+        &self,
+        sql: &str,
+        params: &[(String, Value)],
+    ) -> Result<(Vec<ColumnMeta>, Vec<HashMap<String, Value>>), DbError> {
+        let _gate = self.enter().await?;
+        let start = Instant::now();
+        let result = self.conn.query_with_meta(sql, params).await;
+        self.watch
+            .record(sql, params, start.elapsed(), result.is_ok());
+        result
+    }
+
+    /// 在事务连接上执行已渲染的更新（统计由调用方负责）
+    pub(crate) async fn execute_rendered(
+        &self,
+        sql: &str,
+        params: &[(String, Value)],
+    ) -> Result<u64, DbError> {
+        let _gate = self.enter().await?;
+        let start = Instant::now();
+        let result = self.conn.execute(sql, params).await;
+        self.watch
+            .record(sql, params, start.elapsed(), result.is_ok());
+        result
+    }
+
+    async fn observed_query(
+        &self,
+        stmt_id: Option<&str>,
+        sql: &str,
+        params: &[(String, Value)],
+    ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+        let _gate = self.enter().await?;
+        let stmt = self
+            .watch
+            .span
+            .in_scope(|| StatementSpan::new("query", self.driver.name(), stmt_id));
+        let start = Instant::now();
+        let result = self.conn.query(sql, params).instrument(stmt.span()).await;
+        let elapsed = start.elapsed();
+        self.watch.record(sql, params, elapsed, result.is_ok());
+        let fingerprint = digest::record_named(stmt_id, sql, elapsed, result.is_ok());
+        stmt.finish(Outcome {
+            fingerprint: Some(&fingerprint),
+            sql,
+            params,
+            elapsed,
+            rows: result.as_ref().ok().map(|r| r.len() as u64),
+            error: result.as_ref().err(),
+        });
+        result
+    }
+
+    async fn observed_execute(
+        &self,
+        stmt_id: Option<&str>,
+        sql: &str,
+        params: &[(String, Value)],
+    ) -> Result<u64, DbError> {
+        let _gate = self.enter().await?;
+        let stmt = self
+            .watch
+            .span
+            .in_scope(|| StatementSpan::new("execute", self.driver.name(), stmt_id));
+        let start = Instant::now();
+        let result = self.conn.execute(sql, params).instrument(stmt.span()).await;
+        let elapsed = start.elapsed();
+        self.watch.record(sql, params, elapsed, result.is_ok());
+        let fingerprint = digest::record_named(stmt_id, sql, elapsed, result.is_ok());
+        stmt.finish(Outcome {
+            fingerprint: Some(&fingerprint),
+            sql,
+            params,
+            elapsed,
+            rows: result.as_ref().ok().copied(),
+            error: result.as_ref().err(),
+        });
+        result
     }
 
     pub async fn last_insert_id(&self) -> Result<u64, DbError> {
+        let _gate = self.enter().await?;
         self.conn.last_insert_id().await
     }
+
+    /// 创建保存点，返回其名称
+    pub async fn savepoint(&mut self) -> Result<String, DbError> {
+        self.savepoints += 1;
+        let name = format!("uorm_sp_{}", self.savepoints);
+        self.observed_execute(None, &format!("SAVEPOINT {}", name), &[])
+            .await?;
+        Ok(name)
+    }
+
+    /// 释放保存点，保留其后执行的修改
+    pub async fn release_savepoint(&mut self, name: &str) -> Result<(), DbError> {
+        check_savepoint_name(name)?;
+        self.observed_execute(None, &format!("RELEASE SAVEPOINT {}", name), &[])
+            .await
+            .map(drop)
+    }
+
+    /// 撤销保存点之后执行的修改，事务本身继续有效
+    pub async fn rollback_to_savepoint(&mut self, name: &str) -> Result<(), DbError> {
+        check_savepoint_name(name)?;
+        self.observed_execute(None, &format!("ROLLBACK TO SAVEPOINT {}", name), &[])
+            .await
+            .map(drop)
+    }
+
+    /// 在事务连接上按批读取 mapper 查询语句的结果，每批至多 `chunk_size` 行
+    ///
+    /// 在渲染后的 SQL 末尾追加 `LIMIT n OFFSET m` 分页，因此语句本身不能带 `LIMIT`，
+    /// 且应按唯一键排序（如 `ORDER BY id`），否则批与批之间可能重复或遗漏行。
+    /// 适合需要在同一事务中边读边写的数据回填。
+    pub fn cursor<R, T>(
+        &self,
+        sql_id: &str,
+        args: &T,
+        chunk_size: u64,
+    ) -> Result<TxCursor<'_, R>, DbError>
+    where
+        R: DeserializeOwned,
+        T: Serialize,
+    {
+        if chunk_size == 0 {
+            return Err(DbError::Query(
+                "cursor chunk_size must be positive".to_string(),
+            ));
+        }
+        // 查找与渲染在同一视图内完成
+        let snapshot = mapper_loader::snapshot();
+        let mapper = snapshot
+            .find_mapper(sql_id, self.driver.r#type())
+            .ok_or_else(|| DbError::Query(format!("SQL ID not found: {}", sql_id)))?;
+        check_result::<R>(sql_id, &mapper)?;
+        let content = mapper
+            .content
+            .as_deref()
+            .ok_or_else(|| DbError::Query(format!("SQL content empty for {}", sql_id)))?;
+        let stmt_id = template_key(sql_id, mapper.database_type.as_deref());
+        let (sql, params) =
+            engine::render_statement(&stmt_id, content, args, self.driver.as_ref())?;
+        Ok(TxCursor {
+            tx: self,
+            stmt_id,
+            sql: sql.trim_end().trim_end_matches(';').to_string(),
+            params,
+            chunk_size,
+            offset: 0,
+            done: false,
+            _row: PhantomData,
+        })
+    }
+}
+
+/// 保存点名称直接拼入 SQL
+fn check_savepoint_name(name: &str) -> Result<(), DbError> {
+    if is_function_name(name) {
+        Ok(())
+    } else {
+        Err(DbError::Query(format!("invalid savepoint name: {}", name)))
+    }
+}
+
+/// 事务内的分批查询游标，见 [`TransactionContext::cursor`]
+pub struct TxCursor<'a, R> {
+    tx: &'a TransactionContext,
+    stmt_id: String,
+    sql: String,
+    params: Vec<(String, Value)>,
+    chunk_size: u64,
+    offset: u64,
+    done: bool,
+    _row: PhantomData<fn() -> R>,
+}
+
+impl<'a, R: DeserializeOwned> TxCursor<'a, R> {
+    /// 读取下一批行；读完后返回 `None`
+    pub async fn next_chunk(&mut self) -> Result<Option<Vec<R>>, DbError> {
+        if self.done {
+            return Ok(None);
+        }
+        let sql = format!(
+            "{} LIMIT {} OFFSET {}",
+            self.sql, self.chunk_size, self.offset
+        );
+        let rows = self
+            .tx
+            .observed_query(Some(&self.stmt_id), &sql, &self.params)
+            .await?;
+        let fetched = rows.len() as u64;
+        self.offset += fetched;
+        // 不足一批说明已经读完，省去一次返回空结果的查询
+        self.done = fetched < self.chunk_size;
+        if fetched == 0 {
+            return Ok(None);
+        }
+        Session::map_rows_named(Some(&self.stmt_id), rows).map(Some)
+    }
+
+    /// 逐行产出的异步流，按需读取下一批
+    pub fn into_stream(self) -> impl Stream<Item = Result<R, DbError>> + 'a
+    where
+        R: 'a,
+    {
+        stream::try_unfold(self, |mut cursor| async move {
+            let chunk = cursor.next_chunk().await?;
+            Ok::<_, DbError>(
+                chunk.map(|rows| (stream::iter(rows.into_iter().map(Ok::<R, DbError>)), cursor)),
+            )
+        })
+        .try_flatten()
+    }
+}
+
+/// 监视事务时长：超过 `warn_after` 时记录警告，超过 `max_duration` 时强制回滚；
+/// 事务提交、回滚或释放后立即退出
+async fn supervise(watch: Arc<TxWatch>, conn: Weak<dyn Connection>, limits: TransactionLimits) {
+    if let Some(warn_after) = limits.warn_after
+        && limits.max_duration.is_none_or(|max| warn_after < max)
+    {
+        if tokio::time::timeout(warn_after, watch.done.notified())
+            .await
+            .is_ok()
+            || !watch.is_active()
+        {
+            return;
+        }
+        watch.report(
+            true,
+            &format!(
+                "transaction still open (warn_after={}ms)",
+                warn_after.as_millis()
+            ),
+        );
+    }
+    let Some(max) = limits.max_duration else {
+        return;
+    };
+    let remaining = max.saturating_sub(watch.started.elapsed());
+    if tokio::time::timeout(remaining, watch.done.notified())
+        .await
+        .is_ok()
+    {
+        return;
+    }
+    // 等待进行中的语句结束，之后的语句都会看到事务已被回滚
+    let _gate = watch.gate.lock().await;
+    let Some(conn) = conn.upgrade() else {
+        return;
+    };
+    if !watch.is_active() {
+        return;
+    }
+    watch.poisoned.store(true, Ordering::SeqCst);
+    watch.finished.store(true, Ordering::SeqCst);
+    watch.report(
+        true,
+        &format!(
+            "transaction exceeded max duration of {}ms, rolling back",
+            max.as_millis()
+        ),
+    );
+    if let Err(e) = conn.rollback().instrument(watch.span.clone()).await {
+        watch.report(true, &format!("forced rollback failed: {}", e));
+    }
 }
 
 impl Drop for TransactionContext {
     fn drop(&mut self) {
-        if !self.committed {
+        if !self.committed && self.watch.is_active() {
+            self.watch
+                .report(true, "transaction dropped without commit, rolling back");
             let conn = self.conn.clone();
+            let watch = self.watch.clone();
+            watch.finish();
             tokio::spawn(async move {
-                let _ = conn.rollback().await;
+                let _gate = watch.gate.lock().await;
+                if let Err(e) = conn.rollback().instrument(watch.span.clone()).await {
+                    watch.report(true, &format!("implicit rollback failed: {}", e));
+                }
             });
         }
     }
@@ -0,0 +1,260 @@
+use crate::error::DbError;
+use crate::mapper_loader::SqlMapper;
+use crate::tpl::AstNode;
+use dashmap::DashMap;
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+use std::any::type_name;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+/// 已注册的 Rust 类型信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeInfo {
+    /// `std::any::type_name` 给出的完整类型名
+    pub rust_name: &'static str,
+    /// 结构体字段名；非结构体（或使用 flatten 等无法探测）时为 `None`
+    pub fields: Option<Vec<&'static str>>,
+}
+
+/// parameterType/resultType 名称 -> 类型信息
+static TYPES: LazyLock<DashMap<String, TypeInfo>> = LazyLock::new(DashMap::new);
+
+/// 内置的 MyBatis 风格别名（与 `dao!` 宏保持一致）
+const BUILTIN_ALIASES: &[(&str, &str)] = &[
+    ("int", "i32"),
+    ("integer", "i32"),
+    ("long", "i64"),
+    ("short", "i16"),
+    ("byte", "i8"),
+    ("double", "f64"),
+    ("float", "f32"),
+    ("boolean", "bool"),
+    ("string", "alloc::string::String"),
+    ("map", "std::collections::hash::map::HashMap"),
+];
+
+/// 以 XML 中使用的名称注册 Rust 类型
+///
+/// 同一名称重复注册时以最后一次为准。结构体字段名通过 serde 探测，
+/// 用于校验 parameterType 对应语句中引用的参数是否存在。
+pub fn register_type<T: DeserializeOwned + 'static>(name: &str) {
+    let info = TypeInfo {
+        rust_name: type_name::<T>(),
+        fields: probe_fields::<T>(),
+    };
+    TYPES.insert(name.to_string(), info);
+}
+
+/// 查询已注册（或内置别名）的类型信息
+pub fn lookup(name: &str) -> Option<TypeInfo> {
+    if let Some(info) = TYPES.get(name) {
+        return Some(info.clone());
+    }
+    BUILTIN_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, rust_name)| TypeInfo {
+            rust_name,
+            fields: None,
+        })
+}
+
+/// 清空类型注册表（主要用于测试环境重置状态）
+pub fn clear_types() {
+    TYPES.clear();
+}
+
+/// 校验单条语句声明的类型，返回发现的问题描述
+pub(crate) fn check_statement(sql_id: &str, mapper: &SqlMapper) -> Vec<String> {
+    let mut issues = Vec::new();
+    if let Some(name) = &mapper.result_type
+        && lookup(name).is_none()
+    {
+        issues.push(format!("{}: resultType '{}' 未注册", sql_id, name));
+    }
+    let Some(name) = &mapper.parameter_type else {
+        return issues;
+    };
+    let Some(info) = lookup(name) else {
+        issues.push(format!("{}: parameterType '{}' 未注册", sql_id, name));
+        return issues;
+    };
+    if let (Some(fields), Some(content)) = (&info.fields, &mapper.content) {
+        let ast = crate::tpl::parser::parse_template(content);
+        let mut roots = Vec::new();
+        collect_roots(&ast, &mut Vec::new(), &mut roots);
+        let mut reported = HashSet::new();
+        for root in roots {
+            if !fields.contains(&root.as_str()) && reported.insert(root.clone()) {
+                issues.push(format!(
+                    "{}: 参数 '{}' 不是 parameterType '{}' 的字段",
+                    sql_id, root, name
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// 运行时校验调用方的结果类型与 resultType 声明一致
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+pub(crate) fn check_result<R>(sql_id: &str, mapper: &SqlMapper) -> Result<(), DbError> {
+    if let Some(name) = &mapper.result_type
+        && let Some(info) = lookup(name)
+        && !same_type(type_name::<R>(), info.rust_name)
+    {
+        return Err(DbError::Query(format!(
+            "{}: resultType '{}' ({}) does not match requested type {}",
+            sql_id,
+            name,
+            info.rust_name,
+            type_name::<R>()
+        )));
+    }
+    Ok(())
+}
+
+/// 类型名一致；内置别名只记录了泛型类型的路径（如 `HashMap`），忽略泛型参数
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+fn same_type(actual: &str, expected: &str) -> bool {
+    actual
+        .strip_prefix(expected)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('<'))
+}
+
+/// 收集模板中引用的参数根名称（排除 `<for>` 的迭代变量）
+fn collect_roots(nodes: &[AstNode], scope: &mut Vec<String>, out: &mut Vec<String>) {
+    fn push(path: &str, scope: &[String], out: &mut Vec<String>) {
+        let root = path.split(['.', '[']).next().unwrap_or(path).trim();
+        if !root.is_empty() && !scope.iter().any(|s| s == root) {
+            out.push(root.to_string());
+        }
+    }
+    for node in nodes {
+        match node {
+            AstNode::Var(name) | AstNode::Call { name, .. } | AstNode::Default { name, .. } => {
+                push(name, scope, out)
+            }
+            AstNode::If { body, .. } => collect_roots(body, scope, out),
+            AstNode::For {
+                item,
+                collection,
+                body,
+                ..
+            } => {
+                push(collection, scope, out);
+                scope.push(item.clone());
+                collect_roots(body, scope, out);
+                scope.pop();
+            }
+            AstNode::Set { from } | AstNode::Ident(from) => push(from, scope, out),
+            AstNode::Text(_)
+            | AstNode::Include { .. }
+            | AstNode::IncludeEnv { .. }
+            | AstNode::With { .. }
+            | AstNode::PoolVar { .. }
+            | AstNode::Raw(_) => {}
+        }
+    }
+}
+
+/// 通过 serde 的 `deserialize_struct` 回调获取结构体字段名
+fn probe_fields<T: DeserializeOwned>() -> Option<Vec<&'static str>> {
+    let mut fields = None;
+    let _ = T::deserialize(FieldProbe {
+        fields: &mut fields,
+    });
+    fields
+}
+
+struct FieldProbe<'a> {
+    fields: &'a mut Option<Vec<&'static str>>,
+}
+
+impl<'de> Deserializer<'de> for FieldProbe<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("probe"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.fields = Some(fields.to_vec());
+        Err(de::Error::custom("probe"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Account {
+        id: i64,
+        #[serde(rename = "userName")]
+        user_name: String,
+    }
+
+    fn stmt(parameter_type: Option<&str>, result_type: Option<&str>, content: &str) -> SqlMapper {
+        SqlMapper {
+            content: Some(content.to_string()),
+            parameter_type: parameter_type.map(str::to_string),
+            result_type: result_type.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_probe_fields() {
+        register_type::<Account>("TrAccount");
+        let info = lookup("TrAccount").unwrap();
+        assert_eq!(info.fields, Some(vec!["id", "userName"]));
+        assert!(info.rust_name.ends_with("Account"));
+        assert_eq!(lookup("long").unwrap().rust_name, "i64");
+        assert!(lookup("TrMissing").is_none());
+    }
+
+    #[test]
+    fn test_check_statement() {
+        register_type::<Account>("TrAccount2");
+        let ok = stmt(
+            Some("TrAccount2"),
+            Some("TrAccount2"),
+            "UPDATE t SET name = #{userName} WHERE id = #{id}",
+        );
+        assert!(check_statement("ns.ok", &ok).is_empty());
+
+        let bad = stmt(
+            Some("TrAccount2"),
+            Some("TrNope"),
+            "UPDATE t SET name = #{name} WHERE id = #{id} OR id = #{name}",
+        );
+        let issues = check_statement("ns.bad", &bad);
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+        assert!(issues[0].contains("TrNope"));
+        assert!(issues[1].contains("'name'"));
+    }
+
+    #[test]
+    fn test_check_result() {
+        register_type::<Account>("TrAccount3");
+        let m = stmt(None, Some("TrAccount3"), "SELECT 1");
+        assert!(check_result::<Account>("ns.a", &m).is_ok());
+        assert!(check_result::<i64>("ns.a", &m).is_err());
+        assert!(check_result::<i64>("ns.a", &stmt(None, Some("long"), "")).is_ok());
+        assert!(check_result::<String>("ns.a", &stmt(None, None, "")).is_ok());
+    }
+}
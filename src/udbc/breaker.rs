@@ -0,0 +1,319 @@
+//! 连接池熔断器
+//!
+//! 连续失败达到阈值后打开熔断，冷却期内直接返回 [`DbError::CircuitOpen`]，
+//! 不再占用连接或等待超时；冷却结束后放行一个探测请求，成功则恢复，失败则重新计时。
+//!
+//! ```ignore
+//! let driver = MysqlDriver::new(url).build()?;
+//! UORM.register(CircuitBreakerDriver::new(driver, CircuitBreakerOptions::default()))?;
+//! ```
+
+use crate::error::DbError;
+use crate::executor::options::QueryOptions;
+use crate::executor::postprocess::RowPostProcessor;
+use crate::udbc::bulk::{Progress, RowStream};
+use crate::udbc::connection::{ColumnMeta, Connection, RowSink};
+use crate::udbc::driver::{Driver, Maintenance, PacketLimit, QueueMetrics, TransactionLimits};
+use crate::udbc::value::Value;
+use async_trait::async_trait;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 熔断器配置
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerOptions {
+    /// 连续失败多少次后打开熔断
+    pub failure_threshold: u32,
+    /// 打开后多久放行探测请求
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerOptions {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// 正常放行
+    Closed,
+    /// 快速失败
+    Open,
+    /// 冷却结束，探测请求执行中
+    HalfOpen,
+}
+
+struct BreakerState {
+    state: CircuitState,
+    failures: u32,
+    opened_at: Option<Instant>,
+}
+
+struct CircuitBreaker {
+    name: String,
+    options: CircuitBreakerOptions,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    fn acquire(&self) -> Result<(), DbError> {
+        let mut s = self.state.lock().unwrap();
+        match s.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open
+                if s.opened_at
+                    .is_some_and(|t| t.elapsed() >= self.options.cooldown) =>
+            {
+                s.state = CircuitState::HalfOpen;
+                Ok(())
+            }
+            _ => Err(DbError::CircuitOpen(self.name.clone())),
+        }
+    }
+
+    fn record<T>(&self, result: &Result<T, DbError>) {
+        let mut s = self.state.lock().unwrap();
+        match result {
+            Ok(_) => {
+                if s.state != CircuitState::Closed {
+                    log::info!("circuit closed for pool '{}'", self.name);
+                }
+                s.state = CircuitState::Closed;
+                s.failures = 0;
+                s.opened_at = None;
+            }
+            Err(e) if counts_as_failure(e) => {
+                s.failures = s.failures.saturating_add(1);
+                if s.state == CircuitState::HalfOpen || s.failures >= self.options.failure_threshold
+                {
+                    if s.state != CircuitState::Open {
+                        log::warn!(
+                            "circuit opened for pool '{}' after {} consecutive failures: {}",
+                            self.name,
+                            s.failures,
+                            e
+                        );
+                    }
+                    s.state = CircuitState::Open;
+                    s.opened_at = Some(Instant::now());
+                }
+            }
+            // 模板、映射等客户端错误与数据库是否可用无关
+            Err(_) => {}
+        }
+    }
+}
+
+/// 只有连接、驱动与超时类错误计入失败次数
+fn counts_as_failure(e: &DbError) -> bool {
+    matches!(
+        e,
+        DbError::Connection(_)
+            | DbError::Database(_)
+            | DbError::Backend { .. }
+            | DbError::Driver(_)
+            | DbError::Timeout(_)
+    )
+}
+
+/// 为任意驱动加上熔断器，获取连接与执行语句的结果都会计入
+pub struct CircuitBreakerDriver<D: Driver> {
+    inner: D,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl<D: Driver> CircuitBreakerDriver<D> {
+    pub fn new(driver: D, options: CircuitBreakerOptions) -> Self {
+        let breaker = Arc::new(CircuitBreaker {
+            name: driver.name().to_string(),
+            options,
+            state: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                failures: 0,
+                opened_at: None,
+            }),
+        });
+        Self {
+            inner: driver,
+            breaker,
+        }
+    }
+
+    /// 当前熔断器状态
+    pub fn state(&self) -> CircuitState {
+        self.breaker.state.lock().unwrap().state
+    }
+}
+
+#[async_trait]
+impl<D: Driver + 'static> Driver for CircuitBreakerDriver<D> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn r#type(&self) -> &str {
+        self.inner.r#type()
+    }
+
+    fn placeholder(&self, param_seq: usize, param_name: &str) -> String {
+        self.inner.placeholder(param_seq, param_name)
+    }
+
+    async fn connection(&self) -> Result<Arc<dyn Connection>, DbError> {
+        self.breaker.acquire()?;
+        let result = self.inner.connection().await;
+        if result.is_err() {
+            self.breaker.record(&result);
+        }
+        Ok(Arc::new(GuardedConnection {
+            inner: result?,
+            breaker: self.breaker.clone(),
+        }))
+    }
+
+    async fn close(&self) -> Result<(), DbError> {
+        self.inner.close().await
+    }
+
+    fn supports_returning(&self) -> bool {
+        self.inner.supports_returning()
+    }
+
+    fn wrap_param(&self, func: &str, placeholder: &str) -> Option<String> {
+        self.inner.wrap_param(func, placeholder)
+    }
+
+    fn quote_ident(&self, ident: &str) -> String {
+        self.inner.quote_ident(ident)
+    }
+
+    fn template_var(&self, name: &str) -> Option<&str> {
+        self.inner.template_var(name)
+    }
+
+    fn switch_schema_sql(&self, schema: &str) -> Option<String> {
+        self.inner.switch_schema_sql(schema)
+    }
+
+    fn current_schema_sql(&self) -> Option<&str> {
+        self.inner.current_schema_sql()
+    }
+
+    fn query_options(&self) -> QueryOptions {
+        self.inner.query_options()
+    }
+
+    fn transaction_limits(&self) -> TransactionLimits {
+        self.inner.transaction_limits()
+    }
+
+    fn packet_limit(&self) -> Option<PacketLimit> {
+        self.inner.packet_limit()
+    }
+
+    fn row_processors(&self) -> &[Arc<dyn RowPostProcessor>] {
+        self.inner.row_processors()
+    }
+
+    fn queue_metrics(&self) -> Option<QueueMetrics> {
+        self.inner.queue_metrics()
+    }
+
+    fn keepalive_interval(&self) -> Option<Duration> {
+        self.inner.keepalive_interval()
+    }
+
+    async fn maintain(&self) -> Result<Maintenance, DbError> {
+        self.inner.maintain().await
+    }
+}
+
+/// 记录语句执行结果的连接
+struct GuardedConnection {
+    inner: Arc<dyn Connection>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl GuardedConnection {
+    fn observe<T>(&self, result: Result<T, DbError>) -> Result<T, DbError> {
+        self.breaker.record(&result);
+        result
+    }
+}
+
+#[async_trait]
+impl Connection for GuardedConnection {
+    async fn query(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+    ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+        self.observe(self.inner.query(sql, args).await)
+    }
+
+    async fn execute(&self, sql: &str, args: &[(String, Value)]) -> Result<u64, DbError> {
+        self.observe(self.inner.execute(sql, args).await)
+    }
+
+    async fn last_insert_id(&self) -> Result<u64, DbError> {
+        self.inner.last_insert_id().await
+    }
+
+    async fn begin(&self) -> Result<(), DbError> {
+        self.observe(self.inner.begin().await)
+    }
+
+    async fn begin_read_only(&self) -> Result<(), DbError> {
+        self.observe(self.inner.begin_read_only().await)
+    }
+
+    async fn commit(&self) -> Result<(), DbError> {
+        self.observe(self.inner.commit().await)
+    }
+
+    async fn rollback(&self) -> Result<(), DbError> {
+        self.inner.rollback().await
+    }
+
+    async fn query_to(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+        sink: &mut dyn RowSink,
+    ) -> Result<u64, DbError> {
+        self.observe(self.inner.query_to(sql, args, sink).await)
+    }
+
+    async fn query_with_meta(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+    ) -> Result<(Vec<ColumnMeta>, Vec<HashMap<String, Value>>), DbError> {
+        self.observe(self.inner.query_with_meta(sql, args).await)
+    }
+
+    async fn bulk_load(
+        &self,
+        table: &str,
+        columns: &[String],
+        rows: RowStream,
+        progress: Option<Progress>,
+    ) -> Result<u64, DbError> {
+        self.observe(self.inner.bulk_load(table, columns, rows, progress).await)
+    }
+
+    fn discard(&self) {
+        self.inner.discard()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        self.inner.as_any()
+    }
+}
@@ -1,10 +1,15 @@
 use crate::error::DbError;
+use crate::udbc::bulk::{Progress, RowStream};
 use crate::udbc::value::Value;
+use crate::udbc::{MaybeSend, MaybeSendSync};
 use async_trait::async_trait;
+use std::any::Any;
 use std::collections::HashMap;
+use std::sync::Arc;
 
-#[async_trait]
-pub trait Connection: Send + Sync {
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Connection: MaybeSendSync {
     async fn query(
         &self,
         sql: &str,
@@ -19,4 +24,199 @@ pub trait Connection: Send + Sync {
     async fn begin(&self) -> Result<(), DbError>;
     async fn commit(&self) -> Result<(), DbError>;
     async fn rollback(&self) -> Result<(), DbError>;
+
+    /// 开启只读事务，事务中的写语句由数据库拒绝
+    ///
+    /// 默认在 `begin` 之后执行标准 SQL 的 `SET TRANSACTION READ ONLY`；
+    /// 要求在事务开始前声明的数据库（如 MySQL）应覆盖此方法。
+    async fn begin_read_only(&self) -> Result<(), DbError> {
+        self.begin().await?;
+        self.execute("SET TRANSACTION READ ONLY", &[]).await?;
+        Ok(())
+    }
+
+    /// 逐行查询，依次将列名与每行数据交给 `sink`，返回行数
+    ///
+    /// 默认实现先缓冲全部结果，列按名称排序；驱动可覆盖为真正的流式读取。
+    async fn query_to(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+        sink: &mut dyn RowSink,
+    ) -> Result<u64, DbError> {
+        let rows = self.query(sql, args).await?;
+        let mut columns: Vec<String> = rows
+            .first()
+            .map(|r| r.keys().cloned().collect())
+            .unwrap_or_default();
+        columns.sort();
+        sink.columns(&columns).await?;
+        let count = rows.len() as u64;
+        for mut row in rows {
+            let values = columns
+                .iter()
+                .map(|c| row.remove(c).unwrap_or(Value::Null))
+                .collect();
+            sink.row(values).await?;
+        }
+        Ok(count)
+    }
+
+    /// 查询并返回结果列的元数据，列按结果集中的顺序排列
+    ///
+    /// 默认不支持。
+    async fn query_with_meta(
+        &self,
+        _sql: &str,
+        _args: &[(String, Value)],
+    ) -> Result<(Vec<ColumnMeta>, Vec<HashMap<String, Value>>), DbError> {
+        Err(DbError::NotImplemented)
+    }
+
+    /// 批量导入行数据（MySQL 使用 `LOAD DATA LOCAL INFILE`），返回导入的行数
+    ///
+    /// 默认不支持。
+    async fn bulk_load(
+        &self,
+        _table: &str,
+        _columns: &[String],
+        _rows: RowStream,
+        _progress: Option<Progress>,
+    ) -> Result<u64, DbError> {
+        Err(DbError::NotImplemented)
+    }
+
+    /// 丢弃连接：释放时直接关闭，不再归还连接池
+    ///
+    /// 用于会话状态（如 [`Session::using_schema`](crate::executor::session::Session::using_schema)
+    /// 切换的 schema）无法可靠恢复的连接。默认什么也不做。
+    fn discard(&self) {}
+
+    /// 暴露具体的驱动连接类型，供 [`RawConnection::downcast_ref`] 使用
+    ///
+    /// 默认不支持向下转型。
+    fn as_any(&self) -> Option<&dyn Any> {
+        None
+    }
+}
+
+/// 结果列的元数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMeta {
+    /// 列名（有别名时为别名）
+    pub name: String,
+    /// 数据库类型名，如 `VARCHAR`、`BIGINT UNSIGNED`
+    pub type_name: String,
+    /// 是否可以为 NULL
+    pub nullable: bool,
+    /// 列的最大长度，由驱动报告；字符列通常为字节数
+    pub length: u64,
+}
+
+/// 逐行接收查询结果
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait RowSink: MaybeSend {
+    /// 在第一行之前调用一次
+    async fn columns(&mut self, columns: &[String]) -> Result<(), DbError>;
+
+    /// 每行调用一次，值与列顺序一致
+    async fn row(&mut self, values: Vec<Value>) -> Result<(), DbError>;
+}
+
+/// 底层驱动连接的句柄
+///
+/// 持有期间连接不会归还连接池；在事务中获取时与事务共用同一连接。
+/// 用于执行 `LOAD DATA LOCAL INFILE` 等驱动特有的操作。
+#[derive(Clone)]
+pub struct RawConnection {
+    conn: Arc<dyn Connection>,
+}
+
+impl RawConnection {
+    pub fn new(conn: Arc<dyn Connection>) -> Self {
+        Self { conn }
+    }
+
+    /// 转换为具体的驱动连接类型，例如 `MysqlConnection`
+    pub fn downcast_ref<C: Connection + 'static>(&self) -> Option<&C> {
+        self.conn.as_any()?.downcast_ref::<C>()
+    }
+
+    /// 通用的连接接口
+    pub fn connection(&self) -> &Arc<dyn Connection> {
+        &self.conn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Plain;
+    struct Typed(u32);
+
+    #[async_trait]
+    impl Connection for Plain {
+        async fn query(
+            &self,
+            _sql: &str,
+            _args: &[(String, Value)],
+        ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+            Ok(vec![])
+        }
+        async fn execute(&self, _sql: &str, _args: &[(String, Value)]) -> Result<u64, DbError> {
+            Ok(0)
+        }
+        async fn last_insert_id(&self) -> Result<u64, DbError> {
+            Ok(0)
+        }
+        async fn begin(&self) -> Result<(), DbError> {
+            Ok(())
+        }
+        async fn commit(&self) -> Result<(), DbError> {
+            Ok(())
+        }
+        async fn rollback(&self) -> Result<(), DbError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Connection for Typed {
+        async fn query(
+            &self,
+            _sql: &str,
+            _args: &[(String, Value)],
+        ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+            Ok(vec![])
+        }
+        async fn execute(&self, _sql: &str, _args: &[(String, Value)]) -> Result<u64, DbError> {
+            Ok(0)
+        }
+        async fn last_insert_id(&self) -> Result<u64, DbError> {
+            Ok(0)
+        }
+        async fn begin(&self) -> Result<(), DbError> {
+            Ok(())
+        }
+        async fn commit(&self) -> Result<(), DbError> {
+            Ok(())
+        }
+        async fn rollback(&self) -> Result<(), DbError> {
+            Ok(())
+        }
+        fn as_any(&self) -> Option<&dyn Any> {
+            Some(self)
+        }
+    }
+
+    #[test]
+    fn test_raw_connection_downcast() {
+        let raw = RawConnection::new(Arc::new(Typed(7)));
+        assert_eq!(raw.downcast_ref::<Typed>().map(|t| t.0), Some(7));
+        assert!(raw.downcast_ref::<Plain>().is_none());
+        let plain = RawConnection::new(Arc::new(Plain));
+        assert!(plain.downcast_ref::<Plain>().is_none());
+    }
 }
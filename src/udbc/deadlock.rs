@@ -0,0 +1,262 @@
+//! 死锁与锁等待超时的诊断信息
+//!
+//! 驱动开启诊断后（如 [`MysqlDriver::lock_diagnostics`](crate::udbc_mysql::pool::MysqlDriver::lock_diagnostics)），
+//! 语句因死锁或锁等待超时失败时会在同一连接上执行 `SHOW ENGINE INNODB STATUS`，
+//! 将其中最近一次死锁的摘要附加到错误上，通过 [`DbError::deadlock`](crate::error::DbError::deadlock) 读取。
+
+use std::fmt;
+
+/// 锁相关错误的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockError {
+    /// 死锁，事务已被回滚（MySQL `1213`）
+    Deadlock,
+    /// 等待行锁超时，仅当前语句被回滚（MySQL `1205`）
+    LockWaitTimeout,
+}
+
+/// `LATEST DETECTED DEADLOCK` 一节的摘要
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeadlockSummary {
+    /// 检测到死锁的时间，按服务端输出原样保留
+    pub detected_at: Option<String>,
+    /// 参与死锁的事务，按输出中的编号排列
+    pub transactions: Vec<DeadlockTransaction>,
+    /// 被回滚的事务编号
+    pub rolled_back: Option<u32>,
+}
+
+/// 参与死锁的单个事务
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeadlockTransaction {
+    /// 输出中的编号，如 `(1)` 中的 `1`
+    pub seq: u32,
+    /// 事务正在执行的语句
+    pub query: Option<String>,
+    /// 已持有的锁，如 ``index PRIMARY of table `app`.`users` lock_mode X locks rec but not gap``
+    pub holds: Vec<String>,
+    /// 正在等待的锁
+    pub waits_for: Vec<String>,
+}
+
+/// 当前在解析事务的哪一部分
+enum Part {
+    Header,
+    Holds,
+    Waits,
+}
+
+/// 从 `SHOW ENGINE INNODB STATUS` 的输出中解析最近一次死锁；服务端未记录死锁时返回 `None`
+pub fn parse_innodb_status(status: &str) -> Option<DeadlockSummary> {
+    let start = status.find("LATEST DETECTED DEADLOCK")?;
+    let mut summary = DeadlockSummary::default();
+    let mut part = Part::Header;
+    // 事务头中 `MySQL thread id` 行之后直到下一个 `***` 为语句内容
+    let mut in_query = false;
+
+    for line in status[start..].lines().skip(1) {
+        let line = line.trim_end();
+        if is_rule(line) {
+            // 节标题下方的分隔线之后才是内容，内容结束于下一个节标题
+            if summary.detected_at.is_some() || !summary.transactions.is_empty() {
+                break;
+            }
+            continue;
+        }
+        if let Some(marker) = line.strip_prefix("*** ") {
+            in_query = false;
+            if let Some(seq) = marker.strip_prefix("WE ROLL BACK TRANSACTION (") {
+                summary.rolled_back = seq.trim_end_matches(')').parse().ok();
+                continue;
+            }
+            let Some((seq, rest)) = parse_seq(marker) else {
+                continue;
+            };
+            if rest.starts_with("TRANSACTION") {
+                summary.transactions.push(DeadlockTransaction {
+                    seq,
+                    ..Default::default()
+                });
+                part = Part::Header;
+            } else if rest.starts_with("HOLDS") {
+                part = Part::Holds;
+            } else if rest.starts_with("WAITING") {
+                part = Part::Waits;
+            }
+            continue;
+        }
+        let Some(trx) = summary.transactions.last_mut() else {
+            if summary.detected_at.is_none() && !line.trim().is_empty() {
+                summary.detected_at = Some(detected_at(line));
+            }
+            continue;
+        };
+        match part {
+            Part::Header if line.starts_with("MySQL thread id") => in_query = true,
+            Part::Header if in_query => {
+                let query = trx.query.get_or_insert_with(String::new);
+                if !query.is_empty() {
+                    query.push(' ');
+                }
+                query.push_str(line.trim());
+            }
+            Part::Holds | Part::Waits => {
+                if let Some(lock) = lock_description(line) {
+                    match part {
+                        Part::Holds => trx.holds.push(lock),
+                        _ => trx.waits_for.push(lock),
+                    }
+                }
+            }
+            Part::Header => {}
+        }
+    }
+    Some(summary)
+}
+
+fn is_rule(line: &str) -> bool {
+    line.len() >= 4 && line.chars().all(|c| c == '-')
+}
+
+/// 解析 `(1) TRANSACTION:` 中的编号与其余部分
+fn parse_seq(marker: &str) -> Option<(u32, &str)> {
+    let rest = marker.strip_prefix('(')?;
+    let (seq, rest) = rest.split_once(')')?;
+    Some((seq.parse().ok()?, rest.trim_start()))
+}
+
+/// 时间行形如 `2024-05-01 10:00:00 0x7f8a...`，去掉末尾的线程标识
+fn detected_at(line: &str) -> String {
+    let line = line.trim();
+    match line.rsplit_once(' ') {
+        Some((time, thread)) if thread.starts_with("0x") => time.to_string(),
+        _ => line.to_string(),
+    }
+}
+
+/// 从 `RECORD LOCKS ...`/`TABLE LOCK ...` 行中提取锁住的索引、表与锁模式，
+/// 去掉页号、事务号等每次都不同的部分
+fn lock_description(line: &str) -> Option<String> {
+    let line = line.trim();
+    let rest = if line.starts_with("RECORD LOCKS") {
+        &line[line.find("index ")?..]
+    } else if line.starts_with("TABLE LOCK") {
+        &line[line.find("table ")?..]
+    } else {
+        return None;
+    };
+    let mut words = Vec::new();
+    let mut iter = rest.split_whitespace();
+    while let Some(word) = iter.next() {
+        if word == "trx" {
+            // `trx id 1234`
+            iter.next();
+            iter.next();
+            continue;
+        }
+        words.push(word);
+    }
+    Some(words.join(" "))
+}
+
+impl fmt::Display for DeadlockSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "latest deadlock")?;
+        if let Some(at) = &self.detected_at {
+            write!(f, " at {}", at)?;
+        }
+        for trx in &self.transactions {
+            write!(f, "; ({})", trx.seq)?;
+            if let Some(query) = &trx.query {
+                write!(f, " {}", query)?;
+            }
+            if !trx.holds.is_empty() {
+                write!(f, " holds [{}]", trx.holds.join(", "))?;
+            }
+            if !trx.waits_for.is_empty() {
+                write!(f, " waits for [{}]", trx.waits_for.join(", "))?;
+            }
+        }
+        if let Some(seq) = self.rolled_back {
+            write!(f, "; rolled back ({})", seq)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = "
+=====================================
+2024-05-01 10:00:05 0x7f8a2c0 INNODB MONITOR OUTPUT
+=====================================
+------------------------
+LATEST DETECTED DEADLOCK
+------------------------
+2024-05-01 10:00:01 0x7f8a1b0
+*** (1) TRANSACTION:
+TRANSACTION 5001, ACTIVE 3 sec starting index read
+mysql tables in use 1, locked 1
+LOCK WAIT 3 lock struct(s), heap size 1128, 2 row lock(s)
+MySQL thread id 11, OS thread handle 1401, query id 90 localhost app updating
+UPDATE accounts SET balance = balance - 10
+WHERE id = 2
+*** (1) HOLDS THE LOCK(S):
+RECORD LOCKS space id 7 page no 4 n bits 72 index PRIMARY of table `bank`.`accounts` trx id 5001 lock_mode X locks rec but not gap
+Record lock, heap no 2 PHYSICAL RECORD: n_fields 4; compact format; info bits 0
+*** (1) WAITING FOR THIS LOCK TO BE GRANTED:
+RECORD LOCKS space id 7 page no 4 n bits 72 index PRIMARY of table `bank`.`accounts` trx id 5001 lock_mode X locks rec but not gap waiting
+*** (2) TRANSACTION:
+TRANSACTION 5002, ACTIVE 2 sec starting index read
+MySQL thread id 12, OS thread handle 1402, query id 91 localhost app updating
+UPDATE accounts SET balance = balance + 10 WHERE id = 1
+*** (2) HOLDS THE LOCK(S):
+TABLE LOCK table `bank`.`accounts` trx id 5002 lock mode IX
+*** (2) WAITING FOR THIS LOCK TO BE GRANTED:
+RECORD LOCKS space id 7 page no 4 n bits 72 index PRIMARY of table `bank`.`accounts` trx id 5002 lock_mode X locks rec but not gap waiting
+*** WE ROLL BACK TRANSACTION (2)
+------------
+TRANSACTIONS
+------------
+Trx id counter 5010
+";
+
+    #[test]
+    fn test_parse_latest_deadlock() {
+        let summary = parse_innodb_status(STATUS).unwrap();
+        assert_eq!(summary.detected_at.as_deref(), Some("2024-05-01 10:00:01"));
+        assert_eq!(summary.rolled_back, Some(2));
+        assert_eq!(summary.transactions.len(), 2);
+
+        let first = &summary.transactions[0];
+        assert_eq!(
+            first.query.as_deref(),
+            Some("UPDATE accounts SET balance = balance - 10 WHERE id = 2")
+        );
+        assert_eq!(
+            first.holds,
+            ["index PRIMARY of table `bank`.`accounts` lock_mode X locks rec but not gap"]
+        );
+        assert_eq!(
+            first.waits_for,
+            ["index PRIMARY of table `bank`.`accounts` lock_mode X locks rec but not gap waiting"]
+        );
+        assert_eq!(
+            summary.transactions[1].holds,
+            ["table `bank`.`accounts` lock mode IX"]
+        );
+
+        let text = summary.to_string();
+        assert!(
+            text.starts_with("latest deadlock at 2024-05-01 10:00:01; (1) UPDATE"),
+            "{}",
+            text
+        );
+        assert!(text.ends_with("; rolled back (2)"), "{}", text);
+
+        // 服务端启动后未发生过死锁时没有该节
+        assert_eq!(parse_innodb_status("TRANSACTIONS\n------------\n"), None);
+    }
+}
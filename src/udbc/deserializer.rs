@@ -1,8 +1,12 @@
 use crate::error::DbError;
 use crate::udbc::value::Value;
+use serde::de::value::{BorrowedStrDeserializer, MapDeserializer, SeqDeserializer};
 use serde::de::{self, Deserializer, IntoDeserializer, MapAccess, Visitor};
 use std::collections::HashMap;
 
+/// 行反序列化器
+///
+/// 反序列化的生命周期与行数据绑定，字符串/字节列可以直接借用为 `&str`/`&[u8]`。
 pub struct RowDeserializer<'a> {
     row: &'a HashMap<String, Value>,
 }
@@ -13,7 +17,7 @@ impl<'a> RowDeserializer<'a> {
     }
 }
 
-impl<'de, 'a> Deserializer<'de> for RowDeserializer<'a> {
+impl<'de> Deserializer<'de> for RowDeserializer<'de> {
     type Error = DbError;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -44,7 +48,7 @@ impl<'a> RowMapAccess<'a> {
     }
 }
 
-impl<'de, 'a> MapAccess<'de> for RowMapAccess<'a> {
+impl<'de> MapAccess<'de> for RowMapAccess<'de> {
     type Error = DbError;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
@@ -53,7 +57,8 @@ impl<'de, 'a> MapAccess<'de> for RowMapAccess<'a> {
     {
         if let Some((k, v)) = self.iter.next() {
             self.current = Some((k, v));
-            seed.deserialize(k.as_str().into_deserializer()).map(Some)
+            seed.deserialize(BorrowedStrDeserializer::new(k.as_str()))
+                .map(Some)
         } else {
             Ok(None)
         }
@@ -63,8 +68,28 @@ impl<'de, 'a> MapAccess<'de> for RowMapAccess<'a> {
     where
         V: de::DeserializeSeed<'de>,
     {
-        let (_k, v) = self.current.take().unwrap();
+        let (k, v) = self.current.take().unwrap();
         seed.deserialize(ValueDeserializer { value: v })
+            .map_err(|e| match e {
+                DbError::Mapping {
+                    sql_id,
+                    expected,
+                    found,
+                    ..
+                } => DbError::Mapping {
+                    sql_id,
+                    column: k.clone(),
+                    expected,
+                    found,
+                },
+                DbError::General(msg) => DbError::Mapping {
+                    sql_id: None,
+                    column: k.clone(),
+                    expected: std::any::type_name::<V::Value>().to_string(),
+                    found: msg,
+                },
+                other => other,
+            })
     }
 }
 
@@ -72,7 +97,7 @@ pub struct ValueDeserializer<'a> {
     pub value: &'a Value,
 }
 
-impl<'de, 'a> Deserializer<'de> for ValueDeserializer<'a> {
+impl<'de> Deserializer<'de> for ValueDeserializer<'de> {
     type Error = DbError;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -87,14 +112,30 @@ impl<'de, 'a> Deserializer<'de> for ValueDeserializer<'a> {
             Value::I64(v) => visitor.visit_i64(*v),
             Value::U8(v) => visitor.visit_u8(*v),
             Value::F64(v) => visitor.visit_f64(*v),
-            Value::Str(v) => visitor.visit_str(v),
-            Value::Bytes(v) => visitor.visit_bytes(v),
+            Value::Str(v) => visitor.visit_borrowed_str(v),
+            Value::Bytes(v) => visitor.visit_borrowed_bytes(v),
+            #[cfg(feature = "chrono")]
             Value::Date(d) => visitor.visit_string(d.to_string()),
+            #[cfg(feature = "chrono")]
             Value::Time(t) => visitor.visit_string(t.to_string()),
+            #[cfg(feature = "chrono")]
             Value::DateTime(dt) => visitor.visit_string(dt.to_string()),
+            #[cfg(feature = "chrono")]
             Value::DateTimeUtc(dt) => visitor.visit_string(dt.to_rfc3339()),
+            #[cfg(feature = "decimal")]
             Value::Decimal(d) => visitor.visit_string(d.to_string()),
-            Value::List(_) | Value::Map(_) => visitor.visit_unit(),
+            // 列值通常不是列表或映射，只有经行后处理器转换后才会出现
+            Value::List(items) => visitor.visit_seq(SeqDeserializer::new(
+                items.iter().map(|value| ValueDeserializer { value }),
+            )),
+            Value::Map(map) => {
+                visitor.visit_map(MapDeserializer::new(map.iter().map(|(k, value)| {
+                    (
+                        BorrowedStrDeserializer::new(k.as_str()),
+                        ValueDeserializer { value },
+                    )
+                })))
+            }
         }
     }
 
@@ -111,3 +152,79 @@ impl<'de, 'a> Deserializer<'de> for ValueDeserializer<'a> {
         unit_struct newtype_struct bytes byte_buf option
     }
 }
+
+impl<'de> IntoDeserializer<'de, DbError> for ValueDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Row {
+        id: i64,
+        name: String,
+    }
+
+    #[test]
+    fn test_mapping_error_names_column() {
+        let mut row = HashMap::new();
+        row.insert("id".to_string(), Value::Str("abc".to_string()));
+        row.insert("name".to_string(), Value::Str("n".to_string()));
+        let err = Row::deserialize(RowDeserializer::new(&row)).unwrap_err();
+        match &err {
+            DbError::Mapping {
+                sql_id,
+                column,
+                expected,
+                found,
+            } => {
+                assert_eq!(sql_id, &None);
+                assert_eq!(column, "id");
+                assert_eq!(expected, "i64");
+                assert_eq!(found, "string \"abc\"");
+            }
+            other => panic!("expected mapping error, got {:?}", other),
+        }
+        let err = err.with_sql_id("user.get");
+        assert_eq!(
+            err.to_string(),
+            "Mapping error in 'user.get': column 'id' expected i64, found string \"abc\""
+        );
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct BorrowedRow<'a> {
+        name: &'a str,
+        data: &'a [u8],
+    }
+
+    #[test]
+    fn test_borrowed_columns() {
+        let mut row = HashMap::new();
+        row.insert("name".to_string(), Value::Str("alice".to_string()));
+        row.insert("data".to_string(), Value::Bytes(vec![1, 2, 3]));
+        let r = BorrowedRow::deserialize(RowDeserializer::new(&row)).unwrap();
+        assert_eq!(r.name, "alice");
+        assert_eq!(r.data, &[1, 2, 3]);
+        match &row["name"] {
+            Value::Str(s) => assert_eq!(s.as_ptr(), r.name.as_ptr()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_missing_column() {
+        let mut row = HashMap::new();
+        row.insert("id".to_string(), Value::I64(1));
+        let err = Row::deserialize(RowDeserializer::new(&row)).unwrap_err();
+        assert!(matches!(err, DbError::Mapping { ref column, .. } if column == "name"));
+    }
+}
@@ -1,10 +1,17 @@
 use crate::error::DbError;
+use crate::executor::options::QueryOptions;
+use crate::executor::postprocess::RowPostProcessor;
+use crate::udbc::MaybeSendSync;
 use crate::udbc::connection::Connection;
+use crate::udbc::json::value_to_json;
+use crate::udbc::value::Value;
 use async_trait::async_trait;
 use std::sync::Arc;
+use std::time::Duration;
 
-#[async_trait]
-pub trait Driver: Send + Sync {
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Driver: MaybeSendSync {
     fn name(&self) -> &str;
 
     fn r#type(&self) -> &str;
@@ -13,4 +20,196 @@ pub trait Driver: Send + Sync {
 
     async fn connection(&self) -> Result<Arc<dyn Connection>, DbError>;
     async fn close(&self) -> Result<(), DbError>;
+
+    /// 是否支持 `INSERT ... RETURNING`
+    fn supports_returning(&self) -> bool {
+        false
+    }
+
+    /// 将占位符包裹在模板中 `#{name, fn=func}` 指定的 SQL 函数中
+    ///
+    /// 默认生成 `FUNC(?)`；返回 `None` 表示不支持该函数，渲染时报错。
+    fn wrap_param(&self, func: &str, placeholder: &str) -> Option<String> {
+        is_function_name(func).then(|| format!("{}({})", func.to_uppercase(), placeholder))
+    }
+
+    /// 为标识符（表名、列名）加上引号并转义其中的引号，用于模板中的 `${ident(name)}`
+    ///
+    /// 默认使用 SQL 标准的双引号。
+    fn quote_ident(&self, ident: &str) -> String {
+        quote_ident_with('"', ident)
+    }
+
+    /// 模板中 `${name}` 的取值，如 `schema`、`prefix`；未配置时返回 `None`
+    ///
+    /// 取值原样拼入 SQL，只应来自连接池配置。
+    fn template_var(&self, _name: &str) -> Option<&str> {
+        None
+    }
+
+    /// 将连接的默认 schema 切换为 `schema` 的语句，如 MySQL 的 ``USE `tenant_42` ``、
+    /// PostgreSQL 的 `SET search_path TO "tenant_42"`；返回 `None` 表示不支持，
+    /// 见 [`Session::using_schema`](crate::executor::session::Session::using_schema)
+    fn switch_schema_sql(&self, _schema: &str) -> Option<String> {
+        None
+    }
+
+    /// 查询连接当前默认 schema 的语句，结果为一行一列，`NULL` 表示未选择
+    fn current_schema_sql(&self) -> Option<&str> {
+        None
+    }
+
+    /// 该连接池的默认查询选项，覆盖全局默认值
+    fn query_options(&self) -> QueryOptions {
+        QueryOptions::default()
+    }
+
+    /// 该连接池上事务的时长限制，默认不限制
+    fn transaction_limits(&self) -> TransactionLimits {
+        TransactionLimits::default()
+    }
+
+    /// 该连接池上语句负载的大小上限，默认不检查
+    fn packet_limit(&self) -> Option<PacketLimit> {
+        None
+    }
+
+    /// 该连接池上查询结果在反序列化前经过的行后处理器，先于会话与语句上的处理器执行
+    fn row_processors(&self) -> &[Arc<dyn RowPostProcessor>] {
+        &[]
+    }
+
+    /// 语句排队情况，未限制并发时为 `None`
+    fn queue_metrics(&self) -> Option<QueueMetrics> {
+        None
+    }
+
+    /// 空闲连接的保活间隔；返回 `Some` 时，注册到 [`DriverManager`](crate::driver_manager::DriverManager)
+    /// 后由后台任务按该间隔调用 [`Driver::maintain`]
+    fn keepalive_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// 检查连接池中的空闲连接：探活并丢弃已断开的连接，关闭超出空闲上限的连接
+    async fn maintain(&self) -> Result<Maintenance, DbError> {
+        Ok(Maintenance::default())
+    }
+}
+
+/// 一次空闲连接维护的结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Maintenance {
+    /// 探活成功的连接数
+    pub alive: u64,
+    /// 探活失败而丢弃的连接数
+    pub dropped: u64,
+    /// 超出空闲上限而关闭的连接数
+    pub trimmed: u64,
+}
+
+/// 排队情况统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct QueueMetrics {
+    /// 并发上限
+    pub max_concurrent: u64,
+    /// 正在执行的语句数
+    pub in_flight: u64,
+    /// 正在排队的语句数
+    pub queued: u64,
+    /// 历史最大排队数
+    pub peak_queued: u64,
+    /// 排队超时次数
+    pub timeouts: u64,
+}
+
+/// 函数名直接拼入 SQL，只允许字母、数字与下划线，且不以数字开头
+pub fn is_function_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// 以 `quote` 包裹标识符，其中出现的 `quote` 双写转义
+pub fn quote_ident_with(quote: char, ident: &str) -> String {
+    let mut out = String::with_capacity(ident.len() + 2);
+    out.push(quote);
+    for c in ident.chars() {
+        if c == quote {
+            out.push(quote);
+        }
+        out.push(c);
+    }
+    out.push(quote);
+    out
+}
+
+/// 事务时长限制，避免长事务占用连接导致连接池枯竭
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransactionLimits {
+    /// 事务持续超过该时长时记录一条警告，附带已执行的语句
+    pub warn_after: Option<Duration>,
+    /// 事务持续超过该时长时强制回滚，之后该事务上的操作均返回错误
+    pub max_duration: Option<Duration>,
+}
+
+impl TransactionLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.warn_after.is_none() && self.max_duration.is_none()
+    }
+}
+
+/// 语句负载大小上限，在发送前拦截超过服务端包大小限制（如 MySQL 的 `max_allowed_packet`）的语句
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketLimit {
+    /// 估算的语句负载（见 [`payload_size`]）上限，字节
+    pub max_bytes: usize,
+    /// 超限时按语句引用的列表参数切分为多条语句执行，而不是直接报错
+    pub split_lists: bool,
+}
+
+impl PacketLimit {
+    pub fn exceeded_by(&self, sql: &str, params: &[(String, Value)]) -> bool {
+        payload_size(sql, params) > self.max_bytes
+    }
+
+    /// 估算的负载超过上限时返回 [`DbError::PacketTooLarge`]
+    pub fn check(&self, sql: &str, params: &[(String, Value)]) -> Result<(), DbError> {
+        let size = payload_size(sql, params);
+        if size > self.max_bytes {
+            return Err(DbError::PacketTooLarge {
+                size,
+                limit: self.max_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// 估算语句发送给服务端的字节数：SQL 文本加上各参数按二进制协议编码后的长度
+pub fn payload_size(sql: &str, params: &[(String, Value)]) -> usize {
+    sql.len() + params.iter().map(|(_, v)| encoded_len(v)).sum::<usize>()
+}
+
+fn encoded_len(value: &Value) -> usize {
+    // 变长值带有至多 9 字节的长度前缀
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) | Value::U8(_) => 1,
+        Value::I16(_) => 2,
+        Value::I32(_) => 4,
+        Value::I64(_) | Value::F64(_) => 8,
+        Value::Str(s) => s.len() + 9,
+        Value::Bytes(b) => b.len() + 9,
+        #[cfg(feature = "chrono")]
+        Value::Date(_) => 5,
+        #[cfg(feature = "chrono")]
+        Value::Time(_) => 13,
+        #[cfg(feature = "chrono")]
+        Value::DateTime(_) | Value::DateTimeUtc(_) => 12,
+        #[cfg(feature = "decimal")]
+        Value::Decimal(d) => d.to_string().len() + 9,
+        // 映射与列表按 JSON 文本绑定
+        Value::List(_) | Value::Map(_) => value_to_json(value).to_string().len() + 9,
+    }
 }
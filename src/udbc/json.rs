@@ -0,0 +1,198 @@
+use crate::udbc::value::Value;
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+use serde::ser::{self, Serializer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// JSON 文档列包装类型
+///
+/// 作为参数时将内部值序列化为 JSON 文本交给驱动绑定；
+/// 作为结果字段时将列内容（文本或字节）解析回 `T`。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    /// 取出内部值
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Json<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for Json<T> {
+    fn from(v: T) -> Self {
+        Json(v)
+    }
+}
+
+impl<T: Serialize> Serialize for Json<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let text = serde_json::to_string(&self.0)
+            .map_err(|e| ser::Error::custom(format!("JSON encode failed: {}", e)))?;
+        serializer.serialize_str(&text)
+    }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Json<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonVisitor(PhantomData))
+    }
+}
+
+struct JsonVisitor<T>(PhantomData<T>);
+
+impl<T: DeserializeOwned> JsonVisitor<T> {
+    fn parse<E: de::Error>(bytes: &[u8]) -> Result<Json<T>, E> {
+        serde_json::from_slice(bytes)
+            .map(Json)
+            .map_err(|e| E::custom(format!("invalid JSON document: {}", e)))
+    }
+}
+
+impl<'de, T: DeserializeOwned> Visitor<'de> for JsonVisitor<T> {
+    type Value = Json<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON document as text or bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Self::parse(v.as_bytes())
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Self::parse(v)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Self::parse(b"null")
+    }
+}
+
+/// 将参数值转换为 JSON 文档
+///
+/// 映射与列表递归转换；字节按数组输出，时间按 ISO 8601 文本输出，
+/// 十进制数输出为字符串以免丢失精度，非有限浮点数输出为 `null`。
+pub fn value_to_json(value: &Value) -> serde_json::Value {
+    use serde_json::Value as J;
+    match value {
+        Value::Null => J::Null,
+        Value::Bool(b) => J::Bool(*b),
+        Value::I16(n) => J::from(*n),
+        Value::I32(n) => J::from(*n),
+        Value::I64(n) => J::from(*n),
+        Value::U8(n) => J::from(*n),
+        Value::F64(f) => serde_json::Number::from_f64(*f).map_or(J::Null, J::Number),
+        Value::Str(s) => J::String(s.clone()),
+        Value::Bytes(b) => J::from(b.clone()),
+        #[cfg(feature = "chrono")]
+        Value::Date(d) => J::String(d.to_string()),
+        #[cfg(feature = "chrono")]
+        Value::Time(t) => J::String(t.to_string()),
+        #[cfg(feature = "chrono")]
+        Value::DateTime(dt) => J::String(dt.format("%Y-%m-%dT%H:%M:%S%.f").to_string()),
+        #[cfg(feature = "chrono")]
+        Value::DateTimeUtc(dt) => J::String(dt.to_rfc3339()),
+        #[cfg(feature = "decimal")]
+        Value::Decimal(d) => J::String(d.to_string()),
+        Value::List(items) => J::Array(items.iter().map(value_to_json).collect()),
+        Value::Map(map) => J::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), value_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::udbc::deserializer::RowDeserializer;
+    use crate::udbc::serializer::to_value;
+    use std::collections::HashMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Profile {
+        city: String,
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct UserRow {
+        #[allow(dead_code)]
+        id: i64,
+        profile: Json<Profile>,
+    }
+
+    #[test]
+    fn test_json_param_serializes_to_text() {
+        let v = to_value(&Json(Profile {
+            city: "sh".into(),
+            tags: vec!["a".into()],
+        }));
+        assert_eq!(v, Value::Str(r#"{"city":"sh","tags":["a"]}"#.to_string()));
+    }
+
+    #[test]
+    fn test_json_column_roundtrip() {
+        let mut row = HashMap::new();
+        row.insert("id".to_string(), Value::I64(1));
+        row.insert(
+            "profile".to_string(),
+            Value::Bytes(br#"{"city":"bj","tags":["x","y"]}"#.to_vec()),
+        );
+        let user = UserRow::deserialize(RowDeserializer::new(&row)).unwrap();
+        assert_eq!(user.profile.city, "bj");
+        assert_eq!(user.profile.tags, vec!["x", "y"]);
+    }
+
+    #[test]
+    fn test_json_error_names_column() {
+        let mut row = HashMap::new();
+        row.insert("id".to_string(), Value::I64(1));
+        row.insert("profile".to_string(), Value::Str("{broken".to_string()));
+        let err = UserRow::deserialize(RowDeserializer::new(&row)).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("profile"), "{}", msg);
+        assert!(msg.contains("invalid JSON document"), "{}", msg);
+    }
+
+    #[test]
+    fn test_value_to_json() {
+        let value = to_value(&HashMap::from([(
+            "profile",
+            Profile {
+                city: "sh".into(),
+                tags: vec!["a".into(), "b".into()],
+            },
+        )]));
+        assert_eq!(
+            value_to_json(&value).to_string(),
+            r#"{"profile":{"city":"sh","tags":["a","b"]}}"#
+        );
+        assert_eq!(
+            value_to_json(&Value::F64(f64::NAN)),
+            serde_json::Value::Null
+        );
+        #[cfg(feature = "decimal")]
+        assert_eq!(
+            value_to_json(&Value::Decimal("1.10".parse().unwrap())),
+            serde_json::json!("1.10")
+        );
+    }
+}
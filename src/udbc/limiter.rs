@@ -0,0 +1,197 @@
+//! 连接池的语句并发限制
+//!
+//! 与最大连接数无关，限制同一连接池上同时执行的语句数；超出时排队等待，
+//! 等待超过 `queue_timeout` 返回 [`DbError::Timeout`]。适用于多个服务共享同一数据库的场景。
+
+use crate::error::DbError;
+use crate::udbc::ConnectionOptions;
+use crate::udbc::bulk::{Progress, RowStream};
+use crate::udbc::connection::{ColumnMeta, Connection, RowSink};
+use crate::udbc::driver::QueueMetrics;
+use crate::udbc::value::Value;
+use async_trait::async_trait;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// 基于信号量的语句并发限制
+pub struct QueryLimiter {
+    semaphore: Arc<Semaphore>,
+    max_concurrent: u64,
+    queue_timeout: Option<Duration>,
+    queued: AtomicU64,
+    peak_queued: AtomicU64,
+    timeouts: AtomicU64,
+}
+
+impl QueryLimiter {
+    /// `queue_timeout` 为 `None` 时一直等待
+    pub fn new(max_concurrent: u64, queue_timeout: Option<Duration>) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent as usize)),
+            max_concurrent,
+            queue_timeout,
+            queued: AtomicU64::new(0),
+            peak_queued: AtomicU64::new(0),
+            timeouts: AtomicU64::new(0),
+        }
+    }
+
+    /// 由 `max_concurrent_queries` / `queue_timeout` 创建，未设置并发上限时返回 `None`
+    pub fn from_options(options: &ConnectionOptions) -> Option<Arc<Self>> {
+        (options.max_concurrent_queries > 0).then(|| {
+            let timeout =
+                (options.queue_timeout > 0).then(|| Duration::from_secs(options.queue_timeout));
+            Arc::new(Self::new(options.max_concurrent_queries, timeout))
+        })
+    }
+
+    /// 获取执行许可，释放许可即结束该语句的占用
+    pub async fn acquire(&self) -> Result<OwnedSemaphorePermit, DbError> {
+        if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+        let queued = self.queued.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak_queued.fetch_max(queued, Ordering::SeqCst);
+        let wait = self.semaphore.clone().acquire_owned();
+        let result = match self.queue_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, wait).await.map_err(|_| {
+                self.timeouts.fetch_add(1, Ordering::SeqCst);
+                DbError::Timeout(format!("statement queued for more than {:?}", timeout))
+            }),
+            None => Ok(wait.await),
+        };
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+        result?.map_err(|e| DbError::General(e.to_string()))
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        QueueMetrics {
+            max_concurrent: self.max_concurrent,
+            in_flight: self.max_concurrent - self.semaphore.available_permits() as u64,
+            queued: self.queued.load(Ordering::SeqCst),
+            peak_queued: self.peak_queued.load(Ordering::SeqCst),
+            timeouts: self.timeouts.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// 每条语句执行前先获取许可的连接
+pub struct LimitedConnection {
+    inner: Arc<dyn Connection>,
+    limiter: Arc<QueryLimiter>,
+}
+
+impl LimitedConnection {
+    pub fn new(inner: Arc<dyn Connection>, limiter: Arc<QueryLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl Connection for LimitedConnection {
+    async fn query(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+    ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+        let _permit = self.limiter.acquire().await?;
+        self.inner.query(sql, args).await
+    }
+
+    async fn execute(&self, sql: &str, args: &[(String, Value)]) -> Result<u64, DbError> {
+        let _permit = self.limiter.acquire().await?;
+        self.inner.execute(sql, args).await
+    }
+
+    async fn last_insert_id(&self) -> Result<u64, DbError> {
+        self.inner.last_insert_id().await
+    }
+
+    async fn begin(&self) -> Result<(), DbError> {
+        self.inner.begin().await
+    }
+
+    async fn begin_read_only(&self) -> Result<(), DbError> {
+        self.inner.begin_read_only().await
+    }
+
+    async fn commit(&self) -> Result<(), DbError> {
+        self.inner.commit().await
+    }
+
+    async fn rollback(&self) -> Result<(), DbError> {
+        self.inner.rollback().await
+    }
+
+    async fn query_to(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+        sink: &mut dyn RowSink,
+    ) -> Result<u64, DbError> {
+        let _permit = self.limiter.acquire().await?;
+        self.inner.query_to(sql, args, sink).await
+    }
+
+    async fn query_with_meta(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+    ) -> Result<(Vec<ColumnMeta>, Vec<HashMap<String, Value>>), DbError> {
+        let _permit = self.limiter.acquire().await?;
+        self.inner.query_with_meta(sql, args).await
+    }
+
+    async fn bulk_load(
+        &self,
+        table: &str,
+        columns: &[String],
+        rows: RowStream,
+        progress: Option<Progress>,
+    ) -> Result<u64, DbError> {
+        let _permit = self.limiter.acquire().await?;
+        self.inner.bulk_load(table, columns, rows, progress).await
+    }
+
+    fn discard(&self) {
+        self.inner.discard()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        self.inner.as_any()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_queue_timeout_and_metrics() {
+        let limiter = Arc::new(QueryLimiter::new(1, Some(Duration::from_millis(20))));
+        let permit = limiter.acquire().await.unwrap();
+        assert_eq!(limiter.metrics().in_flight, 1);
+
+        let err = limiter.acquire().await.unwrap_err();
+        assert!(matches!(err, DbError::Timeout(_)));
+
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire().await.map(drop) })
+        };
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert_eq!(limiter.metrics().queued, 1);
+        drop(permit);
+        waiter.await.unwrap().unwrap();
+
+        let metrics = limiter.metrics();
+        assert_eq!(metrics.queued, 0);
+        assert_eq!(metrics.in_flight, 0);
+        assert_eq!(metrics.peak_queued, 1);
+        assert_eq!(metrics.timeouts, 1);
+    }
+}
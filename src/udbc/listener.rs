@@ -0,0 +1,233 @@
+//! 触发连接池生命周期事件的驱动包装
+//!
+//! 按驱动名查找 [`EventListener`]，取连接、取连接超时与语句失败时回调；
+//! 连接池没有监听器时直接返回原连接，不增加开销。
+//! [`DriverManager`](crate::driver_manager::DriverManager) 注册的连接池已自动包装。
+//!
+//! ```ignore
+//! let session = Session::new(Arc::new(ListenedDriver::new(Arc::new(driver))));
+//! ```
+
+use crate::error::DbError;
+use crate::events::{EventListener, listeners};
+use crate::executor::options::QueryOptions;
+use crate::executor::postprocess::RowPostProcessor;
+use crate::udbc::bulk::{Progress, RowStream};
+use crate::udbc::connection::{ColumnMeta, Connection, RowSink};
+use crate::udbc::driver::{Driver, Maintenance, PacketLimit, QueueMetrics, TransactionLimits};
+use crate::udbc::value::Value;
+use async_trait::async_trait;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 为任意驱动触发 [`EventListener`] 事件
+pub struct ListenedDriver {
+    inner: Arc<dyn Driver>,
+}
+
+impl ListenedDriver {
+    pub fn new(driver: Arc<dyn Driver>) -> Self {
+        Self { inner: driver }
+    }
+}
+
+#[async_trait]
+impl Driver for ListenedDriver {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn r#type(&self) -> &str {
+        self.inner.r#type()
+    }
+
+    fn placeholder(&self, param_seq: usize, param_name: &str) -> String {
+        self.inner.placeholder(param_seq, param_name)
+    }
+
+    async fn connection(&self) -> Result<Arc<dyn Connection>, DbError> {
+        let started = Instant::now();
+        let result = self.inner.connection().await;
+        let listeners = listeners(self.name());
+        if listeners.is_empty() {
+            return result;
+        }
+        match result {
+            Ok(conn) => {
+                for listener in &listeners {
+                    listener.connection_opened(self.name());
+                }
+                Ok(Arc::new(ListenedConnection {
+                    inner: conn,
+                    pool: self.name().to_string(),
+                    listeners,
+                }))
+            }
+            Err(e) => {
+                if matches!(e, DbError::Timeout(_)) {
+                    let waited = started.elapsed();
+                    for listener in &listeners {
+                        listener.acquire_timeout(self.name(), waited);
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn close(&self) -> Result<(), DbError> {
+        self.inner.close().await
+    }
+
+    fn supports_returning(&self) -> bool {
+        self.inner.supports_returning()
+    }
+
+    fn wrap_param(&self, func: &str, placeholder: &str) -> Option<String> {
+        self.inner.wrap_param(func, placeholder)
+    }
+
+    fn quote_ident(&self, ident: &str) -> String {
+        self.inner.quote_ident(ident)
+    }
+
+    fn template_var(&self, name: &str) -> Option<&str> {
+        self.inner.template_var(name)
+    }
+
+    fn switch_schema_sql(&self, schema: &str) -> Option<String> {
+        self.inner.switch_schema_sql(schema)
+    }
+
+    fn current_schema_sql(&self) -> Option<&str> {
+        self.inner.current_schema_sql()
+    }
+
+    fn query_options(&self) -> QueryOptions {
+        self.inner.query_options()
+    }
+
+    fn transaction_limits(&self) -> TransactionLimits {
+        self.inner.transaction_limits()
+    }
+
+    fn packet_limit(&self) -> Option<PacketLimit> {
+        self.inner.packet_limit()
+    }
+
+    fn row_processors(&self) -> &[Arc<dyn RowPostProcessor>] {
+        self.inner.row_processors()
+    }
+
+    fn queue_metrics(&self) -> Option<QueueMetrics> {
+        self.inner.queue_metrics()
+    }
+
+    fn keepalive_interval(&self) -> Option<Duration> {
+        self.inner.keepalive_interval()
+    }
+
+    async fn maintain(&self) -> Result<Maintenance, DbError> {
+        self.inner.maintain().await
+    }
+}
+
+/// 语句失败时回调、释放时触发 `connection_closed` 的连接
+///
+/// 监听器在取连接时确定，保证每个 `connection_opened` 都有对应的 `connection_closed`。
+struct ListenedConnection {
+    inner: Arc<dyn Connection>,
+    pool: String,
+    listeners: Vec<Arc<dyn EventListener>>,
+}
+
+impl ListenedConnection {
+    fn observe<T>(&self, sql: &str, result: Result<T, DbError>) -> Result<T, DbError> {
+        if let Err(e) = &result {
+            for listener in &self.listeners {
+                listener.query_error(&self.pool, sql, e);
+            }
+        }
+        result
+    }
+}
+
+impl Drop for ListenedConnection {
+    fn drop(&mut self) {
+        for listener in &self.listeners {
+            listener.connection_closed(&self.pool);
+        }
+    }
+}
+
+#[async_trait]
+impl Connection for ListenedConnection {
+    async fn query(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+    ) -> Result<Vec<HashMap<String, Value>>, DbError> {
+        self.observe(sql, self.inner.query(sql, args).await)
+    }
+
+    async fn execute(&self, sql: &str, args: &[(String, Value)]) -> Result<u64, DbError> {
+        self.observe(sql, self.inner.execute(sql, args).await)
+    }
+
+    async fn last_insert_id(&self) -> Result<u64, DbError> {
+        self.inner.last_insert_id().await
+    }
+
+    async fn begin(&self) -> Result<(), DbError> {
+        self.inner.begin().await
+    }
+
+    async fn begin_read_only(&self) -> Result<(), DbError> {
+        self.inner.begin_read_only().await
+    }
+
+    async fn commit(&self) -> Result<(), DbError> {
+        self.inner.commit().await
+    }
+
+    async fn rollback(&self) -> Result<(), DbError> {
+        self.inner.rollback().await
+    }
+
+    async fn query_to(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+        sink: &mut dyn RowSink,
+    ) -> Result<u64, DbError> {
+        self.observe(sql, self.inner.query_to(sql, args, sink).await)
+    }
+
+    async fn query_with_meta(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+    ) -> Result<(Vec<ColumnMeta>, Vec<HashMap<String, Value>>), DbError> {
+        self.observe(sql, self.inner.query_with_meta(sql, args).await)
+    }
+
+    async fn bulk_load(
+        &self,
+        table: &str,
+        columns: &[String],
+        rows: RowStream,
+        progress: Option<Progress>,
+    ) -> Result<u64, DbError> {
+        self.inner.bulk_load(table, columns, rows, progress).await
+    }
+
+    fn discard(&self) {
+        self.inner.discard()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        self.inner.as_any()
+    }
+}
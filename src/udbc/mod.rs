@@ -1,15 +1,84 @@
 pub mod value;
 
+#[cfg(feature = "runtime")]
+pub mod breaker;
+pub mod bulk;
 pub mod connection;
+pub mod deadlock;
 pub mod deserializer;
 pub mod driver;
+#[cfg(feature = "runtime")]
+pub mod failover;
+pub mod json;
+#[cfg(feature = "runtime")]
+pub mod limiter;
+#[cfg(feature = "runtime")]
+pub mod listener;
 pub mod serializer;
+#[cfg(feature = "time")]
+pub mod time;
+pub mod url;
 
-pub const DEFAULT_DB_NAME: &'static str = "default";
+/// 驱动相关 trait 的线程安全约束
+///
+/// wasm32 上为单线程执行，驱动（如 Workers D1 绑定）通常不是 `Send`，
+/// 此时不要求 `Send + Sync`，trait 方法也以 `async_trait(?Send)` 展开；
+/// 其他平台与原先一样要求可跨线程共享。
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSendSync {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSendSync for T {}
 
+/// 同 [`MaybeSendSync`]，只约束 `Send`
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + ?Sized> MaybeSend for T {}
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSend for T {}
+
+pub const DEFAULT_DB_NAME: &str = "default";
+
+#[derive(Default)]
 pub struct ConnectionOptions {
-    pub max_open_conns: u64, // 设置池最大连接数
-    pub max_idle_conns: u64, // 设置池最大空闲数
-    pub max_lifetime: u64,   // 设置连接最大生命周期
-    pub timeout: u64,        // 设置连接池获取连接的超时时间
+    pub max_open_conns: u64,           // 设置池最大连接数
+    pub max_idle_conns: u64,           // 设置池最大空闲数
+    pub max_lifetime: u64,             // 设置连接最大生命周期
+    pub timeout: u64,                  // 设置连接池获取连接的超时时间
+    pub warn_after: u64,               // 事务持续超过该秒数时记录警告，0 表示不检查
+    pub max_transaction_duration: u64, // 事务最长持续秒数，超时强制回滚，0 表示不限制
+    pub max_concurrent_queries: u64,   // 同时执行的语句数上限，与连接数无关，0 表示不限制
+    pub queue_timeout: u64,            // 语句排队等待的超时秒数，0 表示一直等待
+    pub keepalive_interval: u64,       // 空闲连接探活与裁剪的间隔秒数，0 表示不检查
+    pub max_packet_size: u64,          // 估算的语句负载字节数上限，超出时不发送并报错，0 表示不检查
+    pub split_oversized: bool,         // 负载超限时按列表参数切分为多条语句执行
+    /// 连接属性 `program_name`，便于 DBA 按服务区分连接
+    pub program_name: Option<String>,
+    /// 其他连接属性，如 `team`、`version`
+    pub connect_attrs: Vec<(String, String)>,
+}
+
+impl ConnectionOptions {
+    /// 由事务相关选项得到事务时长限制
+    pub fn transaction_limits(&self) -> driver::TransactionLimits {
+        let secs = |s: u64| (s > 0).then(|| std::time::Duration::from_secs(s));
+        driver::TransactionLimits {
+            warn_after: secs(self.warn_after),
+            max_duration: secs(self.max_transaction_duration),
+        }
+    }
+
+    /// 由 `max_packet_size` 与 `split_oversized` 得到语句负载上限，未设置时为 `None`
+    pub fn packet_limit(&self) -> Option<driver::PacketLimit> {
+        (self.max_packet_size > 0).then_some(driver::PacketLimit {
+            max_bytes: self.max_packet_size as usize,
+            split_lists: self.split_oversized,
+        })
+    }
 }
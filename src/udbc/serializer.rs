@@ -4,6 +4,23 @@ use serde::ser::*;
 
 use std::collections::HashMap;
 
+/// [`Value`] 自身序列化时的类型名，见其上的 `#[serde(rename)]`
+///
+/// 取一个用户类型不会使用的名字，只有 `Value` 本身按原变体还原。
+const VALUE_NAME: &str = "uorm::Value";
+
+/// 参数本身是 [`Value`]（如 `time` 字段转换后的取值）时按原变体还原，
+/// 避免 DECIMAL、日期等经序列化后变为字符串
+///
+/// `List`/`Map` 的元素同样是 `Value`，按常规路径逐个还原即可。
+fn restore_value<T: ?Sized + Serialize>(variant: &str, value: &T) -> Option<Value> {
+    if matches!(variant, "List" | "Map") {
+        return None;
+    }
+    let inner = serde_json::to_value(value).ok()?;
+    serde_json::from_value(serde_json::json!({ variant: inner })).ok()
+}
+
 #[derive(Debug)]
 pub enum Error {
     Custom(String),
@@ -33,10 +50,10 @@ impl Serializer for ValueSerializer {
     type SerializeSeq = ListSerializer;
     type SerializeTuple = ListSerializer;
     type SerializeTupleStruct = ListSerializer;
-    type SerializeTupleVariant = ListSerializer;
+    type SerializeTupleVariant = VariantSerializer<ListSerializer>;
     type SerializeMap = MapSerializer;
     type SerializeStruct = MapSerializer;
-    type SerializeStructVariant = MapSerializer;
+    type SerializeStructVariant = VariantSerializer<MapSerializer>;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
         Ok(Value::Bool(v))
@@ -94,10 +111,13 @@ impl Serializer for ValueSerializer {
     }
     fn serialize_unit_variant(
         self,
-        _: &'static str,
+        name: &'static str,
         _: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
+        if name == VALUE_NAME && variant == "Null" {
+            return Ok(Value::Null);
+        }
         Ok(Value::Str(variant.to_string()))
     }
     fn serialize_newtype_struct<T: ?Sized + Serialize>(
@@ -109,11 +129,16 @@ impl Serializer for ValueSerializer {
     }
     fn serialize_newtype_variant<T: ?Sized + Serialize>(
         self,
-        _: &'static str,
+        name: &'static str,
         _: u32,
-        _: &'static str,
+        variant: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error> {
+        if name == VALUE_NAME
+            && let Some(value) = restore_value(variant, value)
+        {
+            return Ok(value);
+        }
         value.serialize(self)
     }
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
@@ -135,10 +160,15 @@ impl Serializer for ValueSerializer {
         self,
         _: &'static str,
         _: u32,
-        _: &'static str,
-        _: usize,
+        variant: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        self.serialize_seq(None)
+        Ok(VariantSerializer {
+            variant,
+            inner: ListSerializer {
+                vec: Vec::with_capacity(len),
+            },
+        })
     }
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
         Ok(MapSerializer {
@@ -160,12 +190,15 @@ impl Serializer for ValueSerializer {
         self,
         _: &'static str,
         _: u32,
-        _: &'static str,
+        variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        Ok(MapSerializer {
-            map: HashMap::with_capacity(len),
-            key: None,
+        Ok(VariantSerializer {
+            variant,
+            inner: MapSerializer {
+                map: HashMap::with_capacity(len),
+                key: None,
+            },
         })
     }
 }
@@ -195,7 +228,6 @@ macro_rules! impl_serialize_seq {
 impl_serialize_seq!(SerializeSeq, serialize_element);
 impl_serialize_seq!(SerializeTuple, serialize_element);
 impl_serialize_seq!(SerializeTupleStruct, serialize_field);
-impl_serialize_seq!(SerializeTupleVariant, serialize_field);
 
 pub struct MapSerializer {
     pub map: HashMap<String, Value>,
@@ -255,8 +287,82 @@ macro_rules! impl_serialize_struct {
 }
 
 impl_serialize_struct!(SerializeStruct);
-impl_serialize_struct!(SerializeStructVariant);
+
+/// 元组/结构体枚举变体：与 serde_json 一致，序列化为以变体名（含 `rename`）为唯一键的映射，
+/// 如 `{"Suspended": {"reason": ...}}`，模板中可用 `status == 'Suspended'` 判断变体
+pub struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<S> VariantSerializer<S> {
+    fn wrap(variant: &'static str, value: Value) -> Value {
+        Value::Map(HashMap::from([(variant.to_string(), value)]))
+    }
+}
+
+impl SerializeTupleVariant for VariantSerializer<ListSerializer> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Self::wrap(self.variant, SerializeSeq::end(self.inner)?))
+    }
+}
+
+impl SerializeStructVariant for VariantSerializer<MapSerializer> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Self::wrap(self.variant, SerializeStruct::end(self.inner)?))
+    }
+}
 
 pub fn to_value<T: Serialize>(t: &T) -> Value {
     t.serialize(ValueSerializer).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_round_trips_through_to_value() {
+        assert_eq!(to_value(&Value::Null), Value::Null);
+        assert_eq!(to_value(&Value::I16(7)), Value::I16(7));
+        #[cfg(feature = "chrono")]
+        {
+            let date = chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+            assert_eq!(to_value(&Value::Date(date)), Value::Date(date));
+            assert_eq!(to_value(&Some(Value::Date(date))), Value::Date(date));
+        }
+        assert_eq!(
+            to_value(&Value::List(vec![Value::Null, Value::I64(1)])),
+            Value::List(vec![Value::Null, Value::I64(1)])
+        );
+    }
+
+    #[test]
+    fn test_user_enums_named_value_serialize_as_variant_names() {
+        #[derive(Serialize)]
+        enum Value {
+            Null,
+            Str(i64),
+        }
+        assert_eq!(to_value(&Value::Null), super::Value::Str("Null".into()));
+        assert_eq!(to_value(&Value::Str(1)), super::Value::I64(1));
+    }
+}
@@ -0,0 +1,348 @@
+//! `time` crate 日期时间类型与 [`Value`] 的互相转换
+//!
+//! [`Value`] 的日期时间变体仍以 chrono 类型保存，本模块在两者之间转换：
+//!
+//! | `time`                  | `Value`                |
+//! |-------------------------|------------------------|
+//! | `time::Date`            | `Value::Date`          |
+//! | `time::Time`            | `Value::Time`          |
+//! | `time::PrimitiveDateTime` | `Value::DateTime`    |
+//! | `time::OffsetDateTime`  | `Value::DateTimeUtc`   |
+//!
+//! 参数与结果结构体中的字段通过 `#[serde(with = "...")]` 使用对应的适配模块：
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Order {
+//!     #[serde(with = "uorm::udbc::time::date")]
+//!     day: time::Date,
+//!     #[serde(with = "uorm::udbc::time::offset_datetime::option")]
+//!     paid_at: Option<time::OffsetDateTime>,
+//! }
+//! ```
+//!
+//! 序列化为对应的 `Value` 变体，绑定参数时与 chrono 类型一样由驱动按日期时间编码
+//! （MySQL 下 `OffsetDateTime` 同样遵循连接池的时区策略）；该形式只面向 uorm 的参数序列化，
+//! 其他格式下输出带变体名的对象。反序列化接受数据库返回的日期时间列，也接受其字符串形式；
+//! 读到不带时区的 `DATETIME` 转为 `OffsetDateTime` 时按 UTC 解释。
+
+use crate::error::DbError;
+use crate::udbc::value::Value;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// `time` 类型与 `Value` 变体之间的转换
+trait Bridge: Sized {
+    fn to_value(&self) -> Value;
+    fn from_value(value: &Value) -> Result<Self, String>;
+}
+
+impl Bridge for time::Date {
+    fn to_value(&self) -> Value {
+        Value::Date(to_naive_date(*self))
+    }
+
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Date(d) => from_naive_date(*d),
+            Value::Str(s) => s
+                .parse::<NaiveDate>()
+                .map_err(|e| e.to_string())
+                .and_then(from_naive_date),
+            other => Err(mismatch("time::Date", other)),
+        }
+    }
+}
+
+impl Bridge for time::Time {
+    fn to_value(&self) -> Value {
+        Value::Time(to_naive_time(*self))
+    }
+
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Time(t) => from_naive_time(*t),
+            Value::Str(s) => s
+                .parse::<NaiveTime>()
+                .map_err(|e| e.to_string())
+                .and_then(from_naive_time),
+            other => Err(mismatch("time::Time", other)),
+        }
+    }
+}
+
+impl Bridge for time::PrimitiveDateTime {
+    fn to_value(&self) -> Value {
+        Value::DateTime(to_naive_date(self.date()).and_time(to_naive_time(self.time())))
+    }
+
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::DateTime(dt) => from_naive_datetime(*dt),
+            Value::Str(s) => parse_naive_datetime(s).and_then(from_naive_datetime),
+            other => Err(mismatch("time::PrimitiveDateTime", other)),
+        }
+    }
+}
+
+impl Bridge for time::OffsetDateTime {
+    fn to_value(&self) -> Value {
+        let utc = DateTime::<Utc>::from_timestamp(self.unix_timestamp(), self.nanosecond())
+            .expect("time::OffsetDateTime is within chrono's range");
+        Value::DateTimeUtc(utc)
+    }
+
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::DateTimeUtc(dt) => from_utc(*dt),
+            Value::DateTime(dt) => from_utc(dt.and_utc()),
+            Value::Str(s) => match DateTime::parse_from_rfc3339(s) {
+                Ok(dt) => from_utc(dt.to_utc()),
+                Err(_) => parse_naive_datetime(s).and_then(|dt| from_utc(dt.and_utc())),
+            },
+            other => Err(mismatch("time::OffsetDateTime", other)),
+        }
+    }
+}
+
+fn to_naive_date(d: time::Date) -> NaiveDate {
+    NaiveDate::from_yo_opt(d.year(), d.ordinal() as u32)
+        .expect("time::Date is within chrono's range")
+}
+
+fn to_naive_time(t: time::Time) -> NaiveTime {
+    let (h, m, s, ns) = t.as_hms_nano();
+    NaiveTime::from_hms_nano_opt(h as u32, m as u32, s as u32, ns)
+        .expect("time::Time is a valid time of day")
+}
+
+fn from_naive_date(d: NaiveDate) -> Result<time::Date, String> {
+    time::Date::from_ordinal_date(
+        chrono::Datelike::year(&d),
+        chrono::Datelike::ordinal(&d) as u16,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// chrono 以超过 10^9 的纳秒表示闰秒，`time` 不支持，此时报错
+fn from_naive_time(t: NaiveTime) -> Result<time::Time, String> {
+    time::Time::from_hms_nano(
+        t.hour() as u8,
+        t.minute() as u8,
+        t.second() as u8,
+        t.nanosecond(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn from_naive_datetime(dt: NaiveDateTime) -> Result<time::PrimitiveDateTime, String> {
+    Ok(time::PrimitiveDateTime::new(
+        from_naive_date(dt.date())?,
+        from_naive_time(dt.time())?,
+    ))
+}
+
+fn from_utc(dt: DateTime<Utc>) -> Result<time::OffsetDateTime, String> {
+    time::OffsetDateTime::from_unix_timestamp(dt.timestamp())
+        .and_then(|t| t.replace_nanosecond(dt.timestamp_subsec_nanos()))
+        .map_err(|e| e.to_string())
+}
+
+/// 数据库返回的 `DATETIME` 以空格分隔日期与时间，也接受 ISO 8601 的 `T`
+fn parse_naive_datetime(s: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| s.parse::<NaiveDateTime>())
+        .map_err(|e| e.to_string())
+}
+
+fn mismatch(expected: &str, found: &Value) -> String {
+    format!("cannot convert {:?} to {}", found, expected)
+}
+
+macro_rules! impl_time_value {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for Value {
+            fn from(v: $ty) -> Self {
+                v.to_value()
+            }
+        }
+
+        impl TryFrom<&Value> for $ty {
+            type Error = DbError;
+
+            fn try_from(value: &Value) -> Result<Self, Self::Error> {
+                <$ty as Bridge>::from_value(value).map_err(DbError::Value)
+            }
+        }
+    )*};
+}
+
+impl_time_value!(
+    time::Date,
+    time::Time,
+    time::PrimitiveDateTime,
+    time::OffsetDateTime
+);
+
+/// 以 [`Value`] 为中间形式读取字段
+fn deserialize_bridge<'de, T: Bridge, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    T::from_value(&Value::Str(s)).map_err(D::Error::custom)
+}
+
+/// 读取可能为 `NULL` 的字符串
+///
+/// 行反序列化器对非空值不走 `visit_some`，不能直接使用 `Option<String>`。
+struct NullableStr;
+
+impl<'de> serde::de::Visitor<'de> for NullableStr {
+    type Value = Option<String>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a date/time string or null")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        String::deserialize(deserializer).map(Some)
+    }
+}
+
+macro_rules! serde_adapter {
+    ($(#[$doc:meta])* $name:ident, $ty:ty) => {
+        $(#[$doc])*
+        pub mod $name {
+            use super::*;
+
+            pub fn serialize<S: Serializer>(v: &$ty, serializer: S) -> Result<S::Ok, S::Error> {
+                v.to_value().serialize(serializer)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<$ty, D::Error> {
+                deserialize_bridge(deserializer)
+            }
+
+            /// 用于 `Option` 字段，`NULL` 对应 `None`
+            pub mod option {
+                use super::*;
+
+                pub fn serialize<S: Serializer>(
+                    v: &Option<$ty>,
+                    serializer: S,
+                ) -> Result<S::Ok, S::Error> {
+                    v.map(|v| v.to_value()).unwrap_or(Value::Null).serialize(serializer)
+                }
+
+                pub fn deserialize<'de, D: Deserializer<'de>>(
+                    deserializer: D,
+                ) -> Result<Option<$ty>, D::Error> {
+                    deserializer
+                        .deserialize_any(NullableStr)?
+                        .map(|s| <$ty as Bridge>::from_value(&Value::Str(s)))
+                        .transpose()
+                        .map_err(D::Error::custom)
+                }
+            }
+        }
+    };
+}
+
+serde_adapter!(
+    /// `time::Date` 字段，对应 `DATE` 列
+    date,
+    time::Date
+);
+serde_adapter!(
+    /// `time::Time` 字段，对应 `TIME` 列
+    time_of_day,
+    time::Time
+);
+serde_adapter!(
+    /// `time::PrimitiveDateTime` 字段，对应 `DATETIME` 列
+    datetime,
+    time::PrimitiveDateTime
+);
+serde_adapter!(
+    /// `time::OffsetDateTime` 字段，对应 `TIMESTAMP` 列，或按 UTC 解释的 `DATETIME` 列
+    offset_datetime,
+    time::OffsetDateTime
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime, time};
+
+    #[test]
+    fn test_round_trip() {
+        let d = date!(2024 - 02 - 29);
+        assert_eq!(
+            Value::from(d),
+            Value::Date(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap())
+        );
+        assert_eq!(time::Date::try_from(&Value::from(d)).unwrap(), d);
+
+        let t = time!(23:59:58.123456789);
+        assert_eq!(time::Time::try_from(&Value::from(t)).unwrap(), t);
+
+        let dt = datetime!(2024-01-02 03:04:05.5);
+        assert_eq!(
+            Value::from(dt),
+            Value::DateTime(
+                NaiveDate::from_ymd_opt(2024, 1, 2)
+                    .unwrap()
+                    .and_hms_milli_opt(3, 4, 5, 500)
+                    .unwrap()
+            )
+        );
+        assert_eq!(
+            time::PrimitiveDateTime::try_from(&Value::from(dt)).unwrap(),
+            dt
+        );
+
+        // 带偏移的时间统一转为 UTC
+        let odt = datetime!(2024-01-02 11:04:05 +08:00);
+        let Value::DateTimeUtc(utc) = Value::from(odt) else {
+            panic!("expected DateTimeUtc");
+        };
+        assert_eq!(utc.to_rfc3339(), "2024-01-02T03:04:05+00:00");
+        assert_eq!(
+            time::OffsetDateTime::try_from(&Value::DateTimeUtc(utc)).unwrap(),
+            odt
+        );
+    }
+
+    #[test]
+    fn test_from_strings_and_mismatch() {
+        assert_eq!(
+            time::PrimitiveDateTime::try_from(&Value::Str("2024-01-02 03:04:05".into())).unwrap(),
+            datetime!(2024-01-02 03:04:05)
+        );
+        assert_eq!(
+            time::OffsetDateTime::try_from(&Value::Str("2024-01-02T11:04:05+08:00".into()))
+                .unwrap(),
+            datetime!(2024-01-02 03:04:05 UTC)
+        );
+        // 不带时区的 DATETIME 按 UTC 解释
+        assert_eq!(
+            time::OffsetDateTime::try_from(&Value::Str("2024-01-02 03:04:05".into())).unwrap(),
+            datetime!(2024-01-02 03:04:05 UTC)
+        );
+        let err = time::Date::try_from(&Value::I64(1)).unwrap_err();
+        assert!(matches!(err, DbError::Value(_)), "{}", err);
+    }
+}
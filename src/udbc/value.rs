@@ -1,11 +1,14 @@
 use crate::error::DbError;
 use crate::udbc;
+#[cfg(feature = "chrono")]
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+#[cfg(feature = "decimal")]
 use rust_decimal::Decimal;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "uorm::Value")]
 pub enum Value {
     Null,
     Bool(bool),
@@ -16,10 +19,15 @@ pub enum Value {
     F64(f64),
     Str(String),
     Bytes(Vec<u8>),
+    #[cfg(feature = "chrono")]
     Date(NaiveDate),
+    #[cfg(feature = "chrono")]
     Time(NaiveTime),
+    #[cfg(feature = "chrono")]
     DateTime(NaiveDateTime),
+    #[cfg(feature = "chrono")]
     DateTimeUtc(DateTime<Utc>),
+    #[cfg(feature = "decimal")]
     Decimal(Decimal),
     List(Vec<Value>),
     Map(HashMap<String, Value>),
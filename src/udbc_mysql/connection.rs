@@ -1,41 +1,143 @@
 use async_trait::async_trait;
 use mysql_async::prelude::Queryable;
-use mysql_async::{Conn, Row as MyRow};
+use mysql_async::{Column, Conn, Row as MyRow, Value as MyValue};
+use std::any::Any;
 use std::collections::HashMap;
-use tokio::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
 
 use crate::error::DbError;
-use crate::udbc::connection::Connection;
+use crate::udbc::bulk::{self, Progress, RowStream};
+use crate::udbc::connection::{ColumnMeta, Connection, RowSink};
+use crate::udbc::deadlock::parse_innodb_status;
+use crate::udbc::driver::PacketLimit;
 use crate::udbc::value::Value;
-use crate::udbc_mysql::value_codec::{from_mysql_value, to_mysql_value};
+use crate::udbc_mysql::value_codec::{
+    CharsetMode, TimezonePolicy, column_meta, from_mysql_column, to_mysql_value,
+};
 
 pub struct MysqlConnection {
-    conn: Mutex<Conn>,
+    /// 仅在释放时取出，见 [`Connection::discard`]
+    conn: Mutex<Option<Conn>>,
+    discarded: AtomicBool,
+    charset_mode: CharsetMode,
+    timezone: Option<TimezonePolicy>,
+    lock_diagnostics: bool,
+    packet_limit: Option<PacketLimit>,
 }
 
 impl MysqlConnection {
     pub fn new(conn: Conn) -> Self {
         Self {
-            conn: Mutex::new(conn),
+            conn: Mutex::new(Some(conn)),
+            discarded: AtomicBool::new(false),
+            charset_mode: CharsetMode::default(),
+            timezone: None,
+            lock_diagnostics: false,
+            packet_limit: None,
         }
     }
 
-    fn map_row(row: MyRow) -> HashMap<String, Value> {
+    /// 设置文本列的解码模式
+    pub fn with_charset_mode(mut self, mode: CharsetMode) -> Self {
+        self.charset_mode = mode;
+        self
+    }
+
+    /// 设置 DATETIME/TIMESTAMP 的时区处理策略
+    pub fn with_timezone_policy(mut self, policy: Option<TimezonePolicy>) -> Self {
+        self.timezone = policy;
+        self
+    }
+
+    /// 语句因死锁或锁等待超时失败时附加最近一次死锁的摘要
+    pub fn with_lock_diagnostics(mut self, enabled: bool) -> Self {
+        self.lock_diagnostics = enabled;
+        self
+    }
+
+    /// 发送前检查语句负载，超过上限时返回 [`DbError::PacketTooLarge`]
+    pub fn with_packet_limit(mut self, limit: Option<PacketLimit>) -> Self {
+        self.packet_limit = limit;
+        self
+    }
+
+    /// 锁定并返回底层的 `mysql_async::Conn`，用于驱动特有的操作
+    pub async fn conn(&self) -> MappedMutexGuard<'_, Conn> {
+        MutexGuard::map(self.conn.lock().await, |conn| {
+            conn.as_mut().expect("connection is only taken on drop")
+        })
+    }
+
+    /// 锁错误时读取 `SHOW ENGINE INNODB STATUS` 并附加到错误上
+    ///
+    /// 死锁与锁等待超时后连接仍可使用；读取失败（如缺少 `PROCESS` 权限）时返回原错误。
+    async fn diagnose(&self, conn: &mut Conn, err: mysql_async::Error) -> DbError {
+        let err = DbError::from(err);
+        if !self.lock_diagnostics || err.lock_error().is_none() {
+            return err;
+        }
+        let status: Result<Option<(String, String, String)>, _> =
+            conn.query_first("SHOW ENGINE INNODB STATUS").await;
+        match status {
+            Ok(Some((_, _, status))) => match parse_innodb_status(&status) {
+                Some(summary) => err.with_deadlock(summary),
+                None => err,
+            },
+            Ok(None) => err,
+            Err(e) => {
+                log::warn!("failed to read InnoDB status: {}", e);
+                err
+            }
+        }
+    }
+
+    /// 语句负载超过上限时返回错误，语句不会发送给服务端
+    fn check_packet(&self, sql: &str, args: &[(String, Value)]) -> Result<(), DbError> {
+        match &self.packet_limit {
+            Some(limit) => limit.check(sql, args),
+            None => Ok(()),
+        }
+    }
+
+    fn params(&self, args: &[(String, Value)]) -> mysql_async::Params {
+        let encode = |v: &Value| match self.timezone {
+            Some(policy) => policy.encode(v),
+            None => to_mysql_value(v),
+        };
+        mysql_async::Params::Positional(args.iter().map(|(_, v)| encode(v)).collect())
+    }
+
+    fn decode(&self, v: &MyValue, col: &Column) -> Result<Value, DbError> {
+        let value = from_mysql_column(v, col, self.charset_mode)?;
+        Ok(match self.timezone {
+            Some(policy) => policy.decode(value),
+            None => value,
+        })
+    }
+
+    fn map_row(&self, row: MyRow) -> Result<HashMap<String, Value>, DbError> {
         let mut out = HashMap::new();
-        let cols = row.columns_ref();
-        let len = row.len();
-        for i in 0..len {
+        for (i, col) in row.columns_ref().iter().enumerate() {
             let v = row.as_ref(i).expect("value");
-            let name = cols
-                .get(i)
-                .map(|c| c.name_str().to_string())
-                .unwrap_or_else(|| i.to_string());
-            out.insert(name, from_mysql_value(v));
+            out.insert(col.name_str().to_string(), self.decode(v, col)?);
         }
-        out
+        Ok(out)
     }
 }
 
+/// 以反引号引用标识符，支持 `db.table` 形式；拒绝包含反引号的名称
+fn quote_ident(name: &str) -> Result<String, DbError> {
+    if name.is_empty() || name.contains('`') {
+        return Err(DbError::Query(format!("invalid identifier: {}", name)));
+    }
+    Ok(name
+        .split('.')
+        .map(|part| format!("`{}`", part))
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
 #[async_trait]
 impl Connection for MysqlConnection {
     async fn query(
@@ -43,38 +145,159 @@ impl Connection for MysqlConnection {
         sql: &str,
         args: &[(String, Value)],
     ) -> Result<Vec<HashMap<String, Value>>, DbError> {
-        let mut conn = self.conn.lock().await;
-        let params =
-            mysql_async::Params::Positional(args.iter().map(|(_, v)| to_mysql_value(v)).collect());
-        let rows: Vec<MyRow> = conn.exec(sql, params).await?;
-        Ok(rows.into_iter().map(Self::map_row).collect())
+        self.check_packet(sql, args)?;
+        let mut conn = self.conn().await;
+        let params = self.params(args);
+        let rows: Vec<MyRow> = match conn.exec(sql, params).await {
+            Ok(rows) => rows,
+            Err(e) => return Err(self.diagnose(&mut conn, e).await),
+        };
+        rows.into_iter().map(|row| self.map_row(row)).collect()
     }
 
     async fn execute(&self, sql: &str, args: &[(String, Value)]) -> Result<u64, DbError> {
-        let mut conn = self.conn.lock().await;
-        let params =
-            mysql_async::Params::Positional(args.iter().map(|(_, v)| to_mysql_value(v)).collect());
-        conn.exec_drop(sql, params).await?;
+        self.check_packet(sql, args)?;
+        let mut conn = self.conn().await;
+        let params = self.params(args);
+        if let Err(e) = conn.exec_drop(sql, params).await {
+            return Err(self.diagnose(&mut conn, e).await);
+        }
         Ok(conn.affected_rows())
     }
 
     async fn last_insert_id(&self) -> Result<u64, DbError> {
-        let conn = self.conn.lock().await;
+        let conn = self.conn().await;
         Ok(conn.last_insert_id().unwrap_or(0))
     }
 
     async fn begin(&self) -> Result<(), DbError> {
-        self.conn.lock().await.query_drop("BEGIN").await?;
+        self.conn().await.query_drop("BEGIN").await?;
+        Ok(())
+    }
+
+    async fn begin_read_only(&self) -> Result<(), DbError> {
+        self.conn()
+            .await
+            .query_drop("START TRANSACTION READ ONLY")
+            .await?;
         Ok(())
     }
 
     async fn commit(&self) -> Result<(), DbError> {
-        self.conn.lock().await.query_drop("COMMIT").await?;
+        self.conn().await.query_drop("COMMIT").await?;
         Ok(())
     }
 
     async fn rollback(&self) -> Result<(), DbError> {
-        self.conn.lock().await.query_drop("ROLLBACK").await?;
+        self.conn().await.query_drop("ROLLBACK").await?;
         Ok(())
     }
+
+    async fn query_to(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+        sink: &mut dyn RowSink,
+    ) -> Result<u64, DbError> {
+        self.check_packet(sql, args)?;
+        let mut conn = self.conn().await;
+        let params = self.params(args);
+        let mut result = conn.exec_iter(sql, params).await?;
+        let columns: Vec<String> = result
+            .columns_ref()
+            .iter()
+            .map(|c| c.name_str().to_string())
+            .collect();
+        sink.columns(&columns).await?;
+        let mut count = 0;
+        while let Some(row) = result.next().await? {
+            let cols = row.columns_ref();
+            let values = (0..row.len())
+                .map(|i| self.decode(row.as_ref(i).expect("value"), &cols[i]))
+                .collect::<Result<_, _>>()?;
+            sink.row(values).await?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    async fn query_with_meta(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+    ) -> Result<(Vec<ColumnMeta>, Vec<HashMap<String, Value>>), DbError> {
+        self.check_packet(sql, args)?;
+        let mut conn = self.conn().await;
+        let params = self.params(args);
+        let mut result = conn.exec_iter(sql, params).await?;
+        let columns = result.columns_ref().iter().map(column_meta).collect();
+        let mut rows = Vec::new();
+        while let Some(row) = result.next().await? {
+            rows.push(self.map_row(row)?);
+        }
+        Ok((columns, rows))
+    }
+
+    async fn bulk_load(
+        &self,
+        table: &str,
+        columns: &[String],
+        rows: RowStream,
+        progress: Option<Progress>,
+    ) -> Result<u64, DbError> {
+        let columns = columns
+            .iter()
+            .map(|c| quote_ident(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        let sql = format!(
+            "LOAD DATA LOCAL INFILE 'uorm-bulk' INTO TABLE {} CHARACTER SET utf8mb4 \
+             FIELDS TERMINATED BY '\\t' ESCAPED BY '\\\\' LINES TERMINATED BY '\\n' ({})",
+            quote_ident(table)?,
+            columns.join(", ")
+        );
+
+        // 处理器要求 Sync，借助 Mutex 持有数据流，在服务端请求文件时取出
+        let data = std::sync::Mutex::new(Some(bulk::encode_rows(rows, progress)));
+        let mut conn = self.conn().await;
+        conn.set_infile_handler(async move {
+            data.lock()
+                .unwrap()
+                .take()
+                .ok_or_else(|| mysql_async::LocalInfileError::NoHandler.into())
+        });
+        conn.query_drop(sql).await?;
+        Ok(conn.affected_rows())
+    }
+
+    fn discard(&self) {
+        self.discarded.store(true, Ordering::SeqCst);
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+}
+
+impl Drop for MysqlConnection {
+    /// 被丢弃的连接直接断开，不归还连接池
+    fn drop(&mut self) {
+        if !self.discarded.load(Ordering::SeqCst) {
+            return;
+        }
+        let Some(conn) = self.conn.get_mut().take() else {
+            return;
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    if let Err(e) = conn.disconnect().await {
+                        log::warn!("failed to disconnect discarded connection: {}", e);
+                    }
+                });
+            }
+            Err(_) => log::warn!(
+                "no runtime to disconnect discarded connection, returning it to the pool"
+            ),
+        }
+    }
 }
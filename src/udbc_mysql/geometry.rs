@@ -0,0 +1,197 @@
+//! 空间类型解码（`geo` feature）
+//!
+//! MySQL 空间列解码为 WKB 格式的 `Value::Bytes`，
+//! 可通过 [`from_wkb`] 进一步转换为 `geo_types::Geometry`。
+
+use crate::error::DbError;
+use geo_types::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon,
+    Point, Polygon,
+};
+
+/// 嵌套几何集合的最大深度，防止恶意数据导致栈溢出
+const MAX_DEPTH: usize = 32;
+
+/// 将 WKB（Well-Known Binary）解码为 `geo_types::Geometry`
+pub fn from_wkb(bytes: &[u8]) -> Result<Geometry<f64>, DbError> {
+    let mut reader = WkbReader { bytes, pos: 0 };
+    let geometry = reader.geometry(0)?;
+    if reader.pos != bytes.len() {
+        return Err(wkb_err("trailing bytes"));
+    }
+    Ok(geometry)
+}
+
+fn wkb_err(msg: &str) -> DbError {
+    DbError::Value(format!("invalid WKB: {}", msg))
+}
+
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl WkbReader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DbError> {
+        let end = self.pos + N;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or_else(|| wkb_err("unexpected end of data"))?;
+        self.pos = end;
+        Ok(slice.try_into().unwrap())
+    }
+
+    fn u32(&mut self, little: bool) -> Result<u32, DbError> {
+        let b = self.take::<4>()?;
+        Ok(if little {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    fn f64(&mut self, little: bool) -> Result<f64, DbError> {
+        let b = self.take::<8>()?;
+        Ok(if little {
+            f64::from_le_bytes(b)
+        } else {
+            f64::from_be_bytes(b)
+        })
+    }
+
+    /// 读取元素个数，并按剩余字节数做上限检查，避免超大分配
+    fn count(&mut self, little: bool, min_size: usize) -> Result<usize, DbError> {
+        let n = self.u32(little)? as usize;
+        if n.saturating_mul(min_size) > self.bytes.len() - self.pos {
+            return Err(wkb_err("element count exceeds data length"));
+        }
+        Ok(n)
+    }
+
+    fn coord(&mut self, little: bool) -> Result<Coord<f64>, DbError> {
+        Ok(Coord {
+            x: self.f64(little)?,
+            y: self.f64(little)?,
+        })
+    }
+
+    fn line_string(&mut self, little: bool) -> Result<LineString<f64>, DbError> {
+        let n = self.count(little, 16)?;
+        (0..n)
+            .map(|_| self.coord(little))
+            .collect::<Result<Vec<_>, _>>()
+            .map(LineString::new)
+    }
+
+    fn polygon(&mut self, little: bool) -> Result<Polygon<f64>, DbError> {
+        let n = self.count(little, 4)?;
+        let mut rings = (0..n)
+            .map(|_| self.line_string(little))
+            .collect::<Result<Vec<_>, _>>()?;
+        if rings.is_empty() {
+            return Ok(Polygon::new(LineString::new(vec![]), vec![]));
+        }
+        let exterior = rings.remove(0);
+        Ok(Polygon::new(exterior, rings))
+    }
+
+    /// 读取一个带字节序与类型头的几何对象
+    fn geometry(&mut self, depth: usize) -> Result<Geometry<f64>, DbError> {
+        if depth > MAX_DEPTH {
+            return Err(wkb_err("geometry nested too deeply"));
+        }
+        let little = match self.take::<1>()?[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(wkb_err("bad byte order")),
+        };
+        let geometry = match self.u32(little)? {
+            1 => Geometry::Point(Point::from(self.coord(little)?)),
+            2 => Geometry::LineString(self.line_string(little)?),
+            3 => Geometry::Polygon(self.polygon(little)?),
+            4 => {
+                let n = self.count(little, 21)?;
+                let points = (0..n)
+                    .map(|_| match self.geometry(depth + 1)? {
+                        Geometry::Point(p) => Ok(p),
+                        _ => Err(wkb_err("MultiPoint member is not a Point")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Geometry::MultiPoint(MultiPoint::new(points))
+            }
+            5 => {
+                let n = self.count(little, 9)?;
+                let lines = (0..n)
+                    .map(|_| match self.geometry(depth + 1)? {
+                        Geometry::LineString(l) => Ok(l),
+                        _ => Err(wkb_err("MultiLineString member is not a LineString")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Geometry::MultiLineString(MultiLineString::new(lines))
+            }
+            6 => {
+                let n = self.count(little, 9)?;
+                let polygons = (0..n)
+                    .map(|_| match self.geometry(depth + 1)? {
+                        Geometry::Polygon(p) => Ok(p),
+                        _ => Err(wkb_err("MultiPolygon member is not a Polygon")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Geometry::MultiPolygon(MultiPolygon::new(polygons))
+            }
+            7 => {
+                let n = self.count(little, 5)?;
+                let members = (0..n)
+                    .map(|_| self.geometry(depth + 1))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Geometry::GeometryCollection(GeometryCollection::new_from(members))
+            }
+            other => return Err(wkb_err(&format!("unsupported geometry type {}", other))),
+        };
+        Ok(geometry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point_wkb(x: f64, y: f64) -> Vec<u8> {
+        let mut out = vec![1];
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&x.to_le_bytes());
+        out.extend_from_slice(&y.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_point_and_multipoint() {
+        assert_eq!(
+            from_wkb(&point_wkb(1.0, 2.0)).unwrap(),
+            Geometry::Point(Point::new(1.0, 2.0))
+        );
+
+        let mut multi = vec![1];
+        multi.extend_from_slice(&4u32.to_le_bytes());
+        multi.extend_from_slice(&2u32.to_le_bytes());
+        multi.extend(point_wkb(1.0, 2.0));
+        multi.extend(point_wkb(3.0, 4.0));
+        assert_eq!(
+            from_wkb(&multi).unwrap(),
+            Geometry::MultiPoint(MultiPoint::new(vec![
+                Point::new(1.0, 2.0),
+                Point::new(3.0, 4.0)
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_invalid_wkb() {
+        assert!(from_wkb(&[1, 1, 0]).is_err());
+        let mut huge = vec![1];
+        huge.extend_from_slice(&2u32.to_le_bytes());
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(from_wkb(&huge).is_err());
+    }
+}
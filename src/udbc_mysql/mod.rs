@@ -1,3 +1,5 @@
 pub mod connection;
+#[cfg(feature = "geo")]
+pub mod geometry;
 pub mod pool;
 pub mod value_codec;
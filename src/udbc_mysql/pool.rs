@@ -1,12 +1,23 @@
 use crate::error::DbError;
+use crate::executor::options::QueryOptions;
+use crate::executor::postprocess::RowPostProcessor;
 use crate::udbc::connection::Connection;
-use crate::udbc::driver::Driver;
+use crate::udbc::driver::{
+    Driver, Maintenance, PacketLimit, QueueMetrics, TransactionLimits, is_function_name,
+    quote_ident_with,
+};
+use crate::udbc::limiter::{LimitedConnection, QueryLimiter};
+use crate::udbc::url::UrlOptions;
 use crate::udbc::{ConnectionOptions, DEFAULT_DB_NAME};
 use crate::udbc_mysql::connection::MysqlConnection;
+use crate::udbc_mysql::value_codec::{CharsetMode, TimezonePolicy};
 use async_trait::async_trait;
 use mysql_async::Pool as MySqlPoolInternal;
+use mysql_async::prelude::Queryable;
 use mysql_async::{Opts, OptsBuilder, PoolConstraints, PoolOpts};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 const MYSQL_TYPE: &str = "mysql";
@@ -16,7 +27,16 @@ pub struct MysqlDriver {
     name: String,
     r#type: String,
     options: Option<ConnectionOptions>,
+    charset_mode: Option<CharsetMode>,
+    timezone: Option<TimezonePolicy>,
+    param_functions: HashMap<String, String>,
+    template_vars: HashMap<String, String>,
+    query_options: QueryOptions,
+    row_processors: Vec<Arc<dyn RowPostProcessor>>,
+    limiter: Option<Arc<QueryLimiter>>,
+    lock_diagnostics: bool,
     pool: Option<MySqlPoolInternal>,
+    closed: AtomicBool,
 }
 
 impl MysqlDriver {
@@ -26,7 +46,16 @@ impl MysqlDriver {
             r#type: MYSQL_TYPE.to_string(),
             url: url.into(),
             options: None,
+            charset_mode: None,
+            timezone: None,
+            param_functions: HashMap::new(),
+            template_vars: HashMap::new(),
+            query_options: QueryOptions::default(),
+            row_processors: Vec::new(),
+            limiter: None,
+            lock_diagnostics: false,
             pool: None,
+            closed: AtomicBool::new(false),
         }
     }
 
@@ -35,22 +64,207 @@ impl MysqlDriver {
         self
     }
 
-    pub fn options(mut self, options: ConnectionOptions) {
+    pub fn options(mut self, options: ConnectionOptions) -> Self {
         self.options = Some(options);
+        self
+    }
+
+    /// 设置文本列的解码模式（默认 [`CharsetMode::Strict`]）
+    pub fn charset_mode(mut self, mode: CharsetMode) -> Self {
+        self.charset_mode = Some(mode);
+        self
+    }
+
+    /// 设置 DATETIME/TIMESTAMP 的时区处理策略，并在每个连接上设置对应的会话时区
+    ///
+    /// 未设置时保持原样读写，会话时区沿用服务端配置。
+    pub fn timezone_policy(mut self, policy: TimezonePolicy) -> Self {
+        self.timezone = Some(policy);
+        self
+    }
+
+    /// 定义模板中 `#{name, fn=func}` 的展开方式，`{}` 处替换为占位符
+    ///
+    /// 例如 `param_function("millis", "FROM_UNIXTIME({} / 1000)")`；
+    /// 未定义的函数名展开为 `FUNC(?)`。
+    pub fn param_function(mut self, func: impl Into<String>, sql: impl Into<String>) -> Self {
+        self.param_functions
+            .insert(func.into().to_lowercase(), sql.into());
+        self
+    }
+
+    /// 模板中 `${schema}` 的取值，如 `SELECT * FROM ${schema}.users`
+    pub fn schema(self, schema: impl Into<String>) -> Self {
+        self.template_var("schema", schema)
+    }
+
+    /// 模板中 `${prefix}` 的取值，如 `SELECT * FROM ${prefix}users`
+    pub fn table_prefix(self, prefix: impl Into<String>) -> Self {
+        self.template_var("prefix", prefix)
+    }
+
+    /// 定义模板中 `${name}` 的取值；取值原样拼入 SQL，只允许字母、数字、下划线与 `$`
+    pub fn template_var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.template_vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// 设置该连接池的默认查询选项
+    pub fn query_options(mut self, options: QueryOptions) -> Self {
+        self.query_options = options;
+        self
+    }
+
+    /// 添加行后处理器，该连接池上的查询结果在反序列化前依次经过，见 [`Driver::row_processors`]
+    pub fn row_processor(mut self, processor: Arc<dyn RowPostProcessor>) -> Self {
+        self.row_processors.push(processor);
+        self
     }
 
+    /// 语句因死锁（`1213`）或锁等待超时（`1205`）失败时，执行 `SHOW ENGINE INNODB STATUS`
+    /// 并将最近一次死锁的摘要附加到错误上，见 [`DbError::deadlock`]
+    ///
+    /// 需要 `PROCESS` 权限；默认关闭。
+    pub fn lock_diagnostics(mut self, enabled: bool) -> Self {
+        self.lock_diagnostics = enabled;
+        self
+    }
+
+    /// 创建连接池
+    ///
+    /// URL 中除 mysql_async 自身支持的参数外，还可使用：
+    /// - 通用连接池参数（见 [`UrlOptions::parse`]），通过 [`MysqlDriver::options`] 设置时以后者为准
+    /// - `ssl-mode`：`disabled`、`required`、`verify_ca`、`verify_identity`
+    /// - `charset`：连接字符集，如 `utf8mb4`
+    /// - `charset_mode`：`strict`、`lossy`
+    /// - `time_zone`：`utc`、`local`、`convert_to_utc`
+    /// - `lock_diagnostics`：`true` 时同 [`MysqlDriver::lock_diagnostics`]
+    /// - `schema`、`table_prefix`：同 [`MysqlDriver::schema`]、[`MysqlDriver::table_prefix`]
+    /// - `program_name`、`connect_attrs`：连接属性，见 [`ConnectionOptions::program_name`]
+    ///
+    /// mysql_async 的握手包尚不携带连接属性，这里改为在每个连接上设置同名的会话变量
+    /// （如 `@program_name`），可在 `performance_schema.user_variables_by_thread` 中按线程查到。
+    ///
+    /// 通过构建方法设置的解码模式与时区策略优先于 URL 参数。
     pub fn build(mut self) -> Result<Self, DbError> {
-        let opts = Opts::from_url(&self.url).map_err(|e| DbError::Database(e.to_string()))?;
+        let mut url = UrlOptions::parse(&self.url)?;
+        if self.options.is_none() {
+            self.options = url.options.take();
+        }
+        let mut setup = Vec::new();
+        if let Some(mode) = url.take("ssl_mode") {
+            let (require, verify_ca, verify_identity) = match mode.to_ascii_lowercase().as_str() {
+                "disabled" => (false, false, false),
+                "required" => (true, false, false),
+                "verify_ca" => (true, true, false),
+                "verify_identity" => (true, true, true),
+                _ => {
+                    return Err(DbError::InvalidDatabaseUrl(format!(
+                        "unsupported ssl-mode: {}",
+                        mode
+                    )));
+                }
+            };
+            if require {
+                url.push("require_ssl", "true");
+                url.push("verify_ca", &verify_ca.to_string());
+                url.push("verify_identity", &verify_identity.to_string());
+            }
+        }
+        if let Some(charset) = url.take("charset") {
+            if !is_function_name(&charset) {
+                return Err(DbError::InvalidDatabaseUrl(format!(
+                    "invalid charset: {}",
+                    charset
+                )));
+            }
+            setup.push(format!("SET NAMES {}", charset));
+        }
+        if let Some(mode) = url.take("charset_mode") {
+            let mode = match mode.to_ascii_lowercase().as_str() {
+                "strict" => CharsetMode::Strict,
+                "lossy" => CharsetMode::Lossy,
+                _ => {
+                    return Err(DbError::InvalidDatabaseUrl(format!(
+                        "unsupported charset_mode: {}",
+                        mode
+                    )));
+                }
+            };
+            self.charset_mode.get_or_insert(mode);
+        }
+        if let Some(tz) = url.take("time_zone") {
+            let policy = match tz.to_ascii_lowercase().as_str() {
+                "utc" => TimezonePolicy::AssumeUtc,
+                "local" => TimezonePolicy::AssumeLocal,
+                "convert_to_utc" => TimezonePolicy::ConvertToUtc,
+                _ => {
+                    return Err(DbError::InvalidDatabaseUrl(format!(
+                        "unsupported time_zone: {}",
+                        tz
+                    )));
+                }
+            };
+            self.timezone.get_or_insert(policy);
+        }
+        if let Some(enabled) = url.take("lock_diagnostics") {
+            self.lock_diagnostics |= match enabled.to_ascii_lowercase().as_str() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => {
+                    return Err(DbError::InvalidDatabaseUrl(format!(
+                        "invalid lock_diagnostics: {}",
+                        enabled
+                    )));
+                }
+            };
+        }
+
+        for (param, var) in [("schema", "schema"), ("table_prefix", "prefix")] {
+            if let Some(value) = url.take(param) {
+                self.template_vars.entry(var.to_string()).or_insert(value);
+            }
+        }
+        if let Some((name, value)) = self.template_vars.iter().find(|(_, v)| {
+            !v.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        }) {
+            return Err(DbError::InvalidDatabaseUrl(format!(
+                "invalid value for template variable '{}': {}",
+                name, value
+            )));
+        }
+
+        let opts = Opts::from_url(&url.url()).map_err(|e| DbError::Database(e.to_string()))?;
+        setup.extend(opts.setup().iter().cloned());
         let mut builder = OptsBuilder::from_opts(opts);
 
+        if let Some(policy) = self.timezone {
+            // setup 语句在连接重置后也会重新执行，会话时区不会因归还连接池而丢失
+            setup.push(format!("SET time_zone = '{}'", policy.session_time_zone()));
+        }
+        if let Some(options) = &self.options
+            && let Some(sql) = connect_attrs_setup(options)?
+        {
+            setup.push(sql);
+        }
+        if !setup.is_empty() {
+            builder = builder.setup(setup);
+        }
+
         if let Some(options) = &self.options {
-            let constraints = PoolConstraints::new(
-                options.max_idle_conns as usize,
-                options.max_open_conns as usize,
-            )
-            .ok_or_else(|| DbError::Database("Invalid pool constraints: min > max".to_string()))?;
+            let mut pool_opts = PoolOpts::default();
 
-            let mut pool_opts = PoolOpts::default().with_constraints(constraints);
+            if options.max_open_conns > 0 {
+                let constraints = PoolConstraints::new(
+                    options.max_idle_conns as usize,
+                    options.max_open_conns as usize,
+                )
+                .ok_or_else(|| {
+                    DbError::Database("Invalid pool constraints: min > max".to_string())
+                })?;
+                pool_opts = pool_opts.with_constraints(constraints);
+            }
 
             if options.max_lifetime > 0 {
                 pool_opts = pool_opts
@@ -58,6 +272,7 @@ impl MysqlDriver {
             }
 
             builder = builder.pool_opts(pool_opts);
+            self.limiter = QueryLimiter::from_options(options);
         }
 
         let pool = MySqlPoolInternal::new(builder);
@@ -80,25 +295,192 @@ impl Driver for MysqlDriver {
         "?".to_string()
     }
 
+    fn wrap_param(&self, func: &str, placeholder: &str) -> Option<String> {
+        match self.param_functions.get(&func.to_lowercase()) {
+            Some(sql) => Some(sql.replace("{}", placeholder)),
+            None => {
+                is_function_name(func).then(|| format!("{}({})", func.to_uppercase(), placeholder))
+            }
+        }
+    }
+
     async fn connection(&self) -> Result<Arc<dyn Connection>, DbError> {
         let pool = self
             .pool
             .as_ref()
             .ok_or_else(|| DbError::Database("Pool not initialized".to_string()))?;
-        let conn = pool
-            .get_conn()
-            .await
-            .map_err(|e| DbError::Database(e.to_string()))?;
-        Ok(Arc::new(MysqlConnection::new(conn)))
+        let timeout = self.options.as_ref().map_or(0, |o| o.timeout);
+        let conn = if timeout > 0 {
+            tokio::time::timeout(Duration::from_secs(timeout), pool.get_conn())
+                .await
+                .map_err(|_| {
+                    DbError::Timeout(format!("no connection available within {}s", timeout))
+                })?
+        } else {
+            pool.get_conn().await
+        }
+        .map_err(DbError::from)?;
+        let conn: Arc<dyn Connection> = Arc::new(
+            MysqlConnection::new(conn)
+                .with_charset_mode(self.charset_mode.unwrap_or_default())
+                .with_timezone_policy(self.timezone)
+                .with_lock_diagnostics(self.lock_diagnostics)
+                .with_packet_limit(self.packet_limit()),
+        );
+        Ok(match &self.limiter {
+            Some(limiter) => Arc::new(LimitedConnection::new(conn, limiter.clone())),
+            None => conn,
+        })
+    }
+
+    fn quote_ident(&self, ident: &str) -> String {
+        quote_ident_with('`', ident)
+    }
+
+    fn template_var(&self, name: &str) -> Option<&str> {
+        self.template_vars.get(name).map(String::as_str)
+    }
+
+    fn switch_schema_sql(&self, schema: &str) -> Option<String> {
+        Some(format!("USE {}", quote_ident_with('`', schema)))
+    }
+
+    fn current_schema_sql(&self) -> Option<&str> {
+        Some("SELECT DATABASE()")
+    }
+
+    fn query_options(&self) -> QueryOptions {
+        self.query_options.clone()
+    }
+
+    fn transaction_limits(&self) -> TransactionLimits {
+        self.options
+            .as_ref()
+            .map(ConnectionOptions::transaction_limits)
+            .unwrap_or_default()
+    }
+
+    fn packet_limit(&self) -> Option<PacketLimit> {
+        self.options
+            .as_ref()
+            .and_then(ConnectionOptions::packet_limit)
+    }
+
+    fn row_processors(&self) -> &[Arc<dyn RowPostProcessor>] {
+        &self.row_processors
+    }
+
+    fn queue_metrics(&self) -> Option<QueueMetrics> {
+        self.limiter.as_ref().map(|l| l.metrics())
+    }
+
+    fn keepalive_interval(&self) -> Option<Duration> {
+        self.options
+            .as_ref()
+            .filter(|o| o.keepalive_interval > 0)
+            .map(|o| Duration::from_secs(o.keepalive_interval))
+    }
+
+    /// 取出当前全部空闲连接：超出 `max_idle_conns` 的直接关闭，其余执行 `COM_PING`，
+    /// 失败的关闭，成功的归还连接池
+    ///
+    /// 服务端按 `wait_timeout` 断开的连接由此提前发现，不会留到下一次查询时才报错。
+    async fn maintain(&self) -> Result<Maintenance, DbError> {
+        let mut report = Maintenance::default();
+        let Some(pool) = self.pool.as_ref() else {
+            return Ok(report);
+        };
+        if self.closed.load(Ordering::Acquire) {
+            return Ok(report);
+        }
+        let idle = pool.metrics().connections_in_pool.load(Ordering::Relaxed);
+        let max_idle = match self.options.as_ref().map_or(0, |o| o.max_idle_conns) {
+            0 => idle,
+            n => n as usize,
+        };
+        // 同时持有全部取出的连接，保证每个空闲连接只被检查一次
+        let mut conns = Vec::with_capacity(idle);
+        for _ in 0..idle {
+            conns.push(pool.get_conn().await.map_err(DbError::from)?);
+        }
+        for (i, mut conn) in conns.into_iter().enumerate() {
+            if i >= max_idle {
+                let _ = conn.disconnect().await;
+                report.trimmed += 1;
+            } else if conn.ping().await.is_ok() {
+                report.alive += 1;
+            } else {
+                let _ = conn.disconnect().await;
+                report.dropped += 1;
+            }
+        }
+        Ok(report)
     }
 
     async fn close(&self) -> Result<(), DbError> {
+        self.closed.store(true, Ordering::Release);
         if let Some(pool) = &self.pool {
-            pool.clone()
-                .disconnect()
-                .await
-                .map_err(|e| DbError::Database(e.to_string()))?;
+            pool.clone().disconnect().await.map_err(DbError::from)?;
         }
         Ok(())
     }
 }
+
+/// 由连接属性生成 `SET @program_name = '..', @team = '..'`；没有属性时返回 `None`
+///
+/// 属性名拼入 SQL，只允许字母、数字与下划线；取值不允许反斜杠与控制字符，
+/// 单引号双写转义，不受 `NO_BACKSLASH_ESCAPES` 影响。
+fn connect_attrs_setup(options: &ConnectionOptions) -> Result<Option<String>, DbError> {
+    let attrs = options
+        .program_name
+        .iter()
+        .map(|name| ("program_name", name.as_str()))
+        .chain(
+            options
+                .connect_attrs
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str())),
+        );
+    let mut assignments = Vec::new();
+    for (key, value) in attrs {
+        if !is_function_name(key) || value.chars().any(|c| c == '\\' || c.is_control()) {
+            return Err(DbError::InvalidDatabaseUrl(format!(
+                "invalid connection attribute {}: '{}'",
+                key, value
+            )));
+        }
+        assignments.push(format!("@{} = '{}'", key, value.replace('\'', "''")));
+    }
+    Ok((!assignments.is_empty()).then(|| format!("SET {}", assignments.join(", "))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connect_attrs_setup() {
+        assert_eq!(
+            connect_attrs_setup(&ConnectionOptions::default()).unwrap(),
+            None
+        );
+
+        let options = ConnectionOptions {
+            program_name: Some("orders".to_string()),
+            connect_attrs: vec![("team".to_string(), "pay'ments".to_string())],
+            ..Default::default()
+        };
+        assert_eq!(
+            connect_attrs_setup(&options).unwrap().as_deref(),
+            Some("SET @program_name = 'orders', @team = 'pay''ments'")
+        );
+
+        for (key, value) in [("bad key", "x"), ("team", "a\\b"), ("team", "a\nb")] {
+            let options = ConnectionOptions {
+                connect_attrs: vec![(key.to_string(), value.to_string())],
+                ..Default::default()
+            };
+            assert!(connect_attrs_setup(&options).is_err());
+        }
+    }
+}
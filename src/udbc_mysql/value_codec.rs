@@ -1,6 +1,240 @@
+use crate::error::DbError;
+use crate::udbc::connection::ColumnMeta;
+use crate::udbc::json::value_to_json;
 use crate::udbc::value::Value;
-use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
-use mysql_async::Value as MyValue;
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike};
+use mysql_async::consts::{ColumnFlags, ColumnType};
+use mysql_async::{Column, Value as MyValue};
+
+/// 文本列的解码模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CharsetMode {
+    /// 内容不符合列字符集时返回错误
+    #[default]
+    Strict,
+    /// 以 U+FFFD 替换无法解码的字节
+    Lossy,
+}
+
+/// DATETIME/TIMESTAMP 的时区处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimezonePolicy {
+    /// 数据库中的时间均为 UTC：会话时区设为 `+00:00`，`DateTimeUtc` 按 UTC 写入
+    AssumeUtc,
+    /// 数据库中的时间为应用所在时区的本地时间：会话时区设为本地偏移，
+    /// `DateTimeUtc` 转换为本地时间写入，读出的 `DateTime` 保持本地时间
+    AssumeLocal,
+    /// 同 `AssumeLocal`，但读出的 `DateTime` 由本地时间转换为 UTC
+    ConvertToUtc,
+}
+
+impl TimezonePolicy {
+    /// 连接初始化时设置的会话时区
+    ///
+    /// 本地偏移取自建立连接池时，夏令时切换后需重建连接池。
+    pub fn session_time_zone(self) -> String {
+        match self {
+            TimezonePolicy::AssumeUtc => "+00:00".to_string(),
+            TimezonePolicy::AssumeLocal | TimezonePolicy::ConvertToUtc => {
+                Local::now().offset().to_string()
+            }
+        }
+    }
+
+    /// 调整从数据库读出的值；只影响 `Value::DateTime`
+    pub fn decode(self, v: Value) -> Value {
+        match (self, v) {
+            (TimezonePolicy::ConvertToUtc, Value::DateTime(dt)) => Value::DateTime(
+                Local
+                    .from_local_datetime(&dt)
+                    .earliest()
+                    .map(|local| local.naive_utc())
+                    .unwrap_or(dt),
+            ),
+            (_, v) => v,
+        }
+    }
+
+    /// 编码绑定参数；`DateTimeUtc` 按策略转换为数据库一侧的时间
+    pub fn encode(self, v: &Value) -> MyValue {
+        match v {
+            Value::DateTimeUtc(utc) => {
+                let naive = match self {
+                    TimezonePolicy::AssumeUtc => utc.naive_utc(),
+                    TimezonePolicy::AssumeLocal | TimezonePolicy::ConvertToUtc => {
+                        utc.with_timezone(&Local).naive_local()
+                    }
+                };
+                to_mysql_value(&Value::DateTime(naive))
+            }
+            _ => to_mysql_value(v),
+        }
+    }
+}
+
+/// 列字符集（由服务端返回的排序规则 ID 推断）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charset {
+    Binary,
+    Utf8,
+    Latin1,
+    /// 暂不支持解码的字符集，保留原始字节
+    Other,
+}
+
+fn charset_of(collation: u16) -> Charset {
+    match collation {
+        63 => Charset::Binary,
+        // ascii 是 UTF-8 的子集
+        11 | 65 => Charset::Utf8,
+        // utf8mb3
+        33 | 76 | 83 | 192..=215 | 223 => Charset::Utf8,
+        // utf8mb4
+        45 | 46 | 224..=247 | 255..=323 => Charset::Utf8,
+        5 | 8 | 15 | 31 | 47 | 48 | 49 | 94 => Charset::Latin1,
+        _ => Charset::Other,
+    }
+}
+
+/// MySQL 的 latin1 实为 cp1252：0x80..=0x9F 区间与 ISO-8859-1 不同
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+fn decode_latin1(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9f => CP1252_HIGH[(b - 0x80) as usize],
+            _ => b as char,
+        })
+        .collect()
+}
+
+/// 按列字符集将字节解码为字符串；二进制及不支持的字符集返回 `None`
+fn decode_text(bytes: &[u8], col: &Column, mode: CharsetMode) -> Result<Option<String>, DbError> {
+    match charset_of(col.character_set()) {
+        Charset::Utf8 => match std::str::from_utf8(bytes) {
+            Ok(s) => Ok(Some(s.to_string())),
+            Err(e) if mode == CharsetMode::Strict => Err(DbError::Value(format!(
+                "column '{}' is not valid UTF-8: {}",
+                col.name_str(),
+                e
+            ))),
+            Err(_) => Ok(Some(String::from_utf8_lossy(bytes).into_owned())),
+        },
+        Charset::Latin1 => Ok(Some(decode_latin1(bytes))),
+        Charset::Binary | Charset::Other => Ok(None),
+    }
+}
+
+/// 按列元数据解码，处理以字节返回的特殊类型与文本字符集
+///
+/// - `BIT(1)` 解码为 `Bool`，`BIT(n)` 按大端序解码为 `I64`
+/// - `ENUM` 解码为 `Str`
+/// - `SET` 解码为由 `Str` 组成的 `List`
+/// - 空间类型去掉前 4 字节的 SRID，解码为 WKB 格式的 `Bytes`
+/// - UTF-8 与 latin1 文本列按列字符集解码为 `Str`，二进制列保留为 `Bytes`
+pub fn from_mysql_column(v: &MyValue, col: &Column, mode: CharsetMode) -> Result<Value, DbError> {
+    let MyValue::Bytes(b) = v else {
+        return Ok(from_mysql_value(v));
+    };
+    let value = match col.column_type() {
+        ColumnType::MYSQL_TYPE_BIT => {
+            let bits = b.iter().fold(0u64, |acc, byte| (acc << 8) | *byte as u64);
+            if col.column_length() == 1 {
+                Value::Bool(bits != 0)
+            } else {
+                Value::I64(bits as i64)
+            }
+        }
+        ColumnType::MYSQL_TYPE_GEOMETRY => Value::Bytes(b.get(4..).unwrap_or_default().to_vec()),
+        _ => {
+            let text = decode_text(b, col, mode)?;
+            let flags = col.flags();
+            if flags.contains(ColumnFlags::SET_FLAG) {
+                let text = text.unwrap_or_else(|| String::from_utf8_lossy(b).into_owned());
+                Value::List(
+                    text.split(',')
+                        .filter(|s| !s.is_empty())
+                        .map(|s| Value::Str(s.to_string()))
+                        .collect(),
+                )
+            } else if flags.contains(ColumnFlags::ENUM_FLAG) {
+                Value::Str(text.unwrap_or_else(|| String::from_utf8_lossy(b).into_owned()))
+            } else {
+                text.map(Value::Str)
+                    .unwrap_or_else(|| Value::Bytes(b.clone()))
+            }
+        }
+    };
+    Ok(value)
+}
+
+/// 由列定义得到通用的列元数据
+///
+/// `TEXT`/`BLOB` 与 `VARCHAR`/`VARBINARY` 等按列字符集区分；整数与浮点类型带 `UNSIGNED` 标志时追加后缀。
+pub fn column_meta(col: &Column) -> ColumnMeta {
+    let flags = col.flags();
+    let binary = charset_of(col.character_set()) == Charset::Binary;
+    let (name, numeric) = match col.column_type() {
+        ColumnType::MYSQL_TYPE_DECIMAL | ColumnType::MYSQL_TYPE_NEWDECIMAL => ("DECIMAL", true),
+        ColumnType::MYSQL_TYPE_TINY => ("TINYINT", true),
+        ColumnType::MYSQL_TYPE_SHORT => ("SMALLINT", true),
+        ColumnType::MYSQL_TYPE_INT24 => ("MEDIUMINT", true),
+        ColumnType::MYSQL_TYPE_LONG => ("INT", true),
+        ColumnType::MYSQL_TYPE_LONGLONG => ("BIGINT", true),
+        ColumnType::MYSQL_TYPE_FLOAT => ("FLOAT", true),
+        ColumnType::MYSQL_TYPE_DOUBLE => ("DOUBLE", true),
+        ColumnType::MYSQL_TYPE_NULL => ("NULL", false),
+        ColumnType::MYSQL_TYPE_TIMESTAMP | ColumnType::MYSQL_TYPE_TIMESTAMP2 => {
+            ("TIMESTAMP", false)
+        }
+        ColumnType::MYSQL_TYPE_DATE | ColumnType::MYSQL_TYPE_NEWDATE => ("DATE", false),
+        ColumnType::MYSQL_TYPE_TIME | ColumnType::MYSQL_TYPE_TIME2 => ("TIME", false),
+        ColumnType::MYSQL_TYPE_DATETIME | ColumnType::MYSQL_TYPE_DATETIME2 => ("DATETIME", false),
+        ColumnType::MYSQL_TYPE_YEAR => ("YEAR", false),
+        ColumnType::MYSQL_TYPE_BIT => ("BIT", false),
+        ColumnType::MYSQL_TYPE_JSON => ("JSON", false),
+        ColumnType::MYSQL_TYPE_GEOMETRY => ("GEOMETRY", false),
+        ColumnType::MYSQL_TYPE_VECTOR => ("VECTOR", false),
+        ColumnType::MYSQL_TYPE_ENUM => ("ENUM", false),
+        ColumnType::MYSQL_TYPE_SET => ("SET", false),
+        _ if flags.contains(ColumnFlags::ENUM_FLAG) => ("ENUM", false),
+        _ if flags.contains(ColumnFlags::SET_FLAG) => ("SET", false),
+        ColumnType::MYSQL_TYPE_VARCHAR | ColumnType::MYSQL_TYPE_VAR_STRING if binary => {
+            ("VARBINARY", false)
+        }
+        ColumnType::MYSQL_TYPE_VARCHAR | ColumnType::MYSQL_TYPE_VAR_STRING => ("VARCHAR", false),
+        ColumnType::MYSQL_TYPE_STRING if binary => ("BINARY", false),
+        ColumnType::MYSQL_TYPE_STRING => ("CHAR", false),
+        ColumnType::MYSQL_TYPE_TINY_BLOB
+        | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+        | ColumnType::MYSQL_TYPE_LONG_BLOB
+        | ColumnType::MYSQL_TYPE_BLOB
+            if binary =>
+        {
+            ("BLOB", false)
+        }
+        ColumnType::MYSQL_TYPE_TINY_BLOB
+        | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+        | ColumnType::MYSQL_TYPE_LONG_BLOB
+        | ColumnType::MYSQL_TYPE_BLOB => ("TEXT", false),
+        ColumnType::MYSQL_TYPE_TYPED_ARRAY | ColumnType::MYSQL_TYPE_UNKNOWN => ("UNKNOWN", false),
+    };
+    let type_name = if numeric && flags.contains(ColumnFlags::UNSIGNED_FLAG) {
+        format!("{} UNSIGNED", name)
+    } else {
+        name.to_string()
+    };
+    ColumnMeta {
+        name: col.name_str().to_string(),
+        type_name,
+        nullable: !flags.contains(ColumnFlags::NOT_NULL_FLAG),
+        length: col.column_length() as u64,
+    }
+}
 
 pub fn from_mysql_value(v: &MyValue) -> Value {
     match v {
@@ -16,15 +250,14 @@ pub fn from_mysql_value(v: &MyValue) -> Value {
             } else {
                 let dt = NaiveDate::from_ymd_opt(*y as i32, *m as u32, *d as u32)
                     .unwrap()
-                    .and_hms_micro_opt(*h as u32, *min as u32, *s as u32, *micro as u32)
+                    .and_hms_micro_opt(*h as u32, *min as u32, *s as u32, *micro)
                     .unwrap();
                 Value::DateTime(dt)
             }
         }
         MyValue::Time(is_neg, days, h, min, s, micro) => {
             let total_h = *days * 24 + (*h as u32);
-            let t = NaiveTime::from_hms_micro_opt(total_h, *min as u32, *s as u32, *micro as u32)
-                .unwrap();
+            let t = NaiveTime::from_hms_micro_opt(total_h, *min as u32, *s as u32, *micro).unwrap();
             if *is_neg {
                 Value::Str(format!("-{}", t))
             } else {
@@ -84,6 +317,165 @@ pub fn to_mysql_value(v: &Value) -> MyValue {
             )
         }
         Value::Decimal(d) => MyValue::Bytes(d.to_string().into_bytes()),
-        Value::List(_) | Value::Map(_) => MyValue::Bytes(Vec::new()),
+        // 渲染时已按 `NestedParams` 处理；直接交给连接的映射/列表同样按 JSON 文本绑定
+        Value::List(_) | Value::Map(_) => MyValue::Bytes(value_to_json(v).to_string().into_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(column_type: ColumnType, flags: ColumnFlags, length: u32) -> Column {
+        Column::new(column_type)
+            .with_flags(flags)
+            .with_column_length(length)
+            .with_character_set(255)
+    }
+
+    fn decode(v: &MyValue, col: &Column) -> Value {
+        from_mysql_column(v, col, CharsetMode::Strict).unwrap()
+    }
+
+    #[test]
+    fn test_decode_special_columns() {
+        let bytes = |b: &[u8]| MyValue::Bytes(b.to_vec());
+        let bit1 = column(ColumnType::MYSQL_TYPE_BIT, ColumnFlags::empty(), 1);
+        assert_eq!(decode(&bytes(&[1]), &bit1), Value::Bool(true));
+        let bit16 = column(ColumnType::MYSQL_TYPE_BIT, ColumnFlags::empty(), 16);
+        assert_eq!(decode(&bytes(&[1, 2]), &bit16), Value::I64(258));
+
+        let set = column(ColumnType::MYSQL_TYPE_STRING, ColumnFlags::SET_FLAG, 0);
+        assert_eq!(
+            decode(&bytes(b"a,b"), &set),
+            Value::List(vec![Value::Str("a".into()), Value::Str("b".into())])
+        );
+        assert_eq!(decode(&bytes(b""), &set), Value::List(vec![]));
+
+        let enumeration = column(ColumnType::MYSQL_TYPE_STRING, ColumnFlags::ENUM_FLAG, 0);
+        assert_eq!(
+            decode(&bytes(b"red"), &enumeration),
+            Value::Str("red".into())
+        );
+
+        let geometry = column(ColumnType::MYSQL_TYPE_GEOMETRY, ColumnFlags::empty(), 0);
+        assert_eq!(
+            decode(&bytes(&[0, 0, 0, 0, 1, 2]), &geometry),
+            Value::Bytes(vec![1, 2])
+        );
+    }
+
+    #[test]
+    fn test_encode_nested_as_json() {
+        let list = Value::List(vec![Value::I32(1), Value::Str("a".into())]);
+        assert_eq!(
+            to_mysql_value(&list),
+            MyValue::Bytes(br#"[1,"a"]"#.to_vec())
+        );
+        let map = Value::Map([("k".to_string(), Value::Null)].into());
+        assert_eq!(
+            to_mysql_value(&map),
+            MyValue::Bytes(br#"{"k":null}"#.to_vec())
+        );
+    }
+
+    #[test]
+    fn test_column_meta() {
+        let meta = column_meta(
+            &column(
+                ColumnType::MYSQL_TYPE_LONGLONG,
+                ColumnFlags::NOT_NULL_FLAG | ColumnFlags::UNSIGNED_FLAG,
+                20,
+            )
+            .with_name(b"id"),
+        );
+        assert_eq!(
+            meta,
+            ColumnMeta {
+                name: "id".to_string(),
+                type_name: "BIGINT UNSIGNED".to_string(),
+                nullable: false,
+                length: 20,
+            }
+        );
+
+        let type_name = |t: ColumnType, flags: ColumnFlags, charset: u16| {
+            column_meta(&column(t, flags, 0).with_character_set(charset)).type_name
+        };
+        let none = ColumnFlags::empty();
+        assert_eq!(
+            type_name(ColumnType::MYSQL_TYPE_VAR_STRING, none, 255),
+            "VARCHAR"
+        );
+        assert_eq!(
+            type_name(ColumnType::MYSQL_TYPE_VAR_STRING, none, 63),
+            "VARBINARY"
+        );
+        assert_eq!(type_name(ColumnType::MYSQL_TYPE_BLOB, none, 255), "TEXT");
+        assert_eq!(type_name(ColumnType::MYSQL_TYPE_BLOB, none, 63), "BLOB");
+        assert_eq!(
+            type_name(ColumnType::MYSQL_TYPE_STRING, ColumnFlags::ENUM_FLAG, 255),
+            "ENUM"
+        );
+        // 无符号标志只作用于数值类型
+        assert_eq!(
+            type_name(
+                ColumnType::MYSQL_TYPE_DATETIME,
+                ColumnFlags::UNSIGNED_FLAG,
+                63
+            ),
+            "DATETIME"
+        );
+        assert!(column_meta(&column(ColumnType::MYSQL_TYPE_LONG, none, 11)).nullable);
+    }
+
+    #[test]
+    fn test_decode_charsets() {
+        let text = |charset: u16| {
+            Column::new(ColumnType::MYSQL_TYPE_VAR_STRING)
+                .with_character_set(charset)
+                .with_name(b"name")
+        };
+        let latin1 = MyValue::Bytes(vec![b'c', 0xe9, 0x80]);
+        assert_eq!(decode(&latin1, &text(8)), Value::Str("cé€".into()));
+        assert_eq!(
+            decode(&latin1, &text(63)),
+            Value::Bytes(vec![b'c', 0xe9, 0x80])
+        );
+        assert_eq!(
+            decode(&MyValue::Bytes("中文".into()), &text(45)),
+            Value::Str("中文".into())
+        );
+
+        let err = from_mysql_column(&latin1, &text(45), CharsetMode::Strict).unwrap_err();
+        assert!(err.to_string().contains("name"));
+        assert_eq!(
+            from_mysql_column(&latin1, &text(45), CharsetMode::Lossy).unwrap(),
+            Value::Str("c\u{fffd}".into())
+        );
+    }
+
+    #[test]
+    fn test_timezone_policy() {
+        let utc = chrono::DateTime::parse_from_rfc3339("2024-01-02T03:04:05Z")
+            .unwrap()
+            .to_utc();
+        let naive = utc.naive_utc();
+        assert_eq!(
+            TimezonePolicy::AssumeUtc.encode(&Value::DateTimeUtc(utc)),
+            to_mysql_value(&Value::DateTime(naive))
+        );
+        assert_eq!(
+            TimezonePolicy::AssumeLocal.encode(&Value::DateTimeUtc(utc)),
+            to_mysql_value(&Value::DateTime(utc.with_timezone(&Local).naive_local()))
+        );
+        assert_eq!(TimezonePolicy::AssumeUtc.session_time_zone(), "+00:00");
+
+        let local = Value::DateTime(utc.with_timezone(&Local).naive_local());
+        assert_eq!(
+            TimezonePolicy::ConvertToUtc.decode(local.clone()),
+            Value::DateTime(naive)
+        );
+        assert_eq!(TimezonePolicy::AssumeLocal.decode(local.clone()), local);
     }
 }
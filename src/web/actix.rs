@@ -0,0 +1,66 @@
+//! actix-web 集成
+//!
+//! ```ignore
+//! use uorm::web::actix::{DbSession, transaction};
+//!
+//! async fn create_user(DbSession(session): DbSession, user: Json<User>) -> HttpResponse {
+//!     match session.insert_into("users", &*user).await {
+//!         Ok(_) => HttpResponse::Created().finish(),
+//!         Err(_) => HttpResponse::InternalServerError().finish(),
+//!     }
+//! }
+//!
+//! App::new()
+//!     // 使用非默认连接池时注册 Session
+//!     .app_data(UORM.session("app").unwrap())
+//!     .wrap(actix_web::middleware::from_fn(transaction))
+//!     .route("/users", web::post().to(create_user))
+//! ```
+
+use super::request_session;
+use crate::error::DbError;
+use crate::executor::session::Session;
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{Error, FromRequest, HttpRequest};
+use std::future::{Ready, ready};
+
+/// 请求级 `Session` 提取器；在 [`transaction`] 中间件内使用时，语句在请求事务中执行
+pub struct DbSession(pub Session);
+
+impl FromRequest for DbSession {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            request_session(req.app_data::<Session>())
+                .map(DbSession)
+                .map_err(internal_error),
+        )
+    }
+}
+
+/// 事务中间件，配合 `actix_web::middleware::from_fn` 使用
+///
+/// 响应为 2xx 时提交，处理出错或其他状态码时回滚；开启或提交事务失败时返回 500。
+pub async fn transaction<B: MessageBody>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, Error> {
+    let session = request_session(req.app_data::<Session>()).map_err(internal_error)?;
+    session
+        .run_in_transaction(
+            next.call(req),
+            |result| matches!(result, Ok(response) if response.status().is_success()),
+        )
+        .await
+        .map_err(internal_error)?
+}
+
+/// 错误详情只写入日志，不返回给客户端
+fn internal_error(e: DbError) -> Error {
+    tracing::error!(error = %e, "request transaction failed");
+    actix_web::error::ErrorInternalServerError("database error")
+}
@@ -0,0 +1,60 @@
+//! axum 集成
+//!
+//! ```ignore
+//! use uorm::web::axum::{DbSession, transaction};
+//!
+//! async fn create_user(DbSession(session): DbSession, Json(user): Json<User>) -> StatusCode {
+//!     match session.insert_into("users", &user).await {
+//!         Ok(_) => StatusCode::CREATED,
+//!         Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+//!     }
+//! }
+//!
+//! let app = Router::new()
+//!     .route("/users", post(create_user))
+//!     .layer(axum::middleware::from_fn(transaction))
+//!     // 使用非默认连接池时注册 Session，须位于事务中间件外层
+//!     .layer(Extension(UORM.session("app").unwrap()));
+//! ```
+
+use super::request_session;
+use crate::error::DbError;
+use crate::executor::session::Session;
+use axum::extract::{FromRequestParts, Request};
+use axum::http::StatusCode;
+use axum::http::request::Parts;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+/// 请求级 `Session` 提取器；在 [`transaction`] 中间件内使用时，语句在请求事务中执行
+pub struct DbSession(pub Session);
+
+impl<S: Send + Sync> FromRequestParts<S> for DbSession {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        request_session(parts.extensions.get::<Session>())
+            .map(DbSession)
+            .map_err(internal_error)
+    }
+}
+
+/// 事务中间件，配合 `axum::middleware::from_fn` 使用
+///
+/// 响应为 2xx 时提交，否则回滚；开启或提交事务失败时返回 500。
+pub async fn transaction(request: Request, next: Next) -> Response {
+    let session = match request_session(request.extensions().get::<Session>()) {
+        Ok(session) => session,
+        Err(e) => return internal_error(e),
+    };
+    session
+        .run_in_transaction(next.run(request), |response| response.status().is_success())
+        .await
+        .unwrap_or_else(internal_error)
+}
+
+/// 错误详情只写入日志，不返回给客户端
+fn internal_error(e: DbError) -> Response {
+    tracing::error!(error = %e, "request transaction failed");
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
@@ -0,0 +1,31 @@
+//! Web 框架集成（`axum` / `actix` feature）
+//!
+//! 为每个请求提供 [`Session`]，并可用中间件把整个请求包在一个事务中：
+//! 处理函数返回 2xx 时提交，其余状态码或出错时回滚。事务期间处理函数内对同一连接池的
+//! `Session`/`Mapper` 调用（包括通过 `UORM` 获取的）都自动使用该事务，无需显式传递。
+//!
+//! 请求所用的 `Session` 取自应用注册的实例（axum 的 `Extension`、actix 的 `app_data`），
+//! 未注册时使用 `UORM` 中的默认连接池。
+
+#[cfg(feature = "actix")]
+pub mod actix;
+#[cfg(feature = "axum")]
+pub mod axum;
+
+use crate::driver_manager::UORM;
+use crate::error::DbError;
+use crate::executor::session::Session;
+use crate::udbc::DEFAULT_DB_NAME;
+
+/// 应用注册的 `Session`，未注册时取默认连接池
+fn request_session(registered: Option<&Session>) -> Result<Session, DbError> {
+    match registered {
+        Some(session) => Ok(session.clone()),
+        None => UORM.session(DEFAULT_DB_NAME).ok_or_else(|| {
+            DbError::General(format!(
+                "database pool '{}' is not registered",
+                DEFAULT_DB_NAME
+            ))
+        }),
+    }
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{Log, MockDriver, id_rows, take_sql};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Once};
+use uorm::executor::mapper::Mapper;
+use uorm::executor::options::QueryOptions;
+use uorm::executor::session::Session;
+use uorm::mapper_loader;
+
+const XML: &str = r#"<mapper namespace="limit">
+    <select id="all">
+        SELECT id FROM orders WHERE status = #{status}
+    </select>
+    <select id="export" autoLimit="0">
+        SELECT id FROM orders
+    </select>
+    <select id="latest">
+        SELECT id FROM orders ORDER BY id DESC LIMIT 1
+    </select>
+    <select id="claim" autoLimit="2">
+        SELECT id FROM jobs FOR UPDATE
+    </select>
+</mapper>"#;
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    id: i64,
+}
+
+fn setup() -> (Arc<MockDriver>, Log) {
+    static LOADED: Once = Once::new();
+    LOADED.call_once(|| {
+        mapper_loader::load_assets(vec![("limit.xml", XML)]).unwrap();
+    });
+    // 忽略 LIMIT，总是返回 5 行
+    let driver = Arc::new(
+        MockDriver::new("auto_limit")
+            .with_rows(id_rows(5))
+            .with_query_options(QueryOptions {
+                auto_limit: Some(100),
+                ..Default::default()
+            }),
+    );
+    let log = driver.log();
+    (driver, log)
+}
+
+#[tokio::test]
+async fn test_auto_limit_layers() {
+    let (driver, log) = setup();
+    let mapper = Mapper::new(driver.clone());
+    let args = HashMap::from([("status", "paid")]);
+
+    let rows: Vec<Row> = mapper.list("limit.all", &args).await.unwrap();
+    assert_eq!(rows.len(), 5);
+    assert_eq!(rows[4].id, 4);
+    let _: Vec<Row> = mapper.list("limit.export", &()).await.unwrap();
+    let _: Vec<Row> = mapper.list("limit.latest", &()).await.unwrap();
+    let _: Vec<Row> = mapper.list("limit.claim", &()).await.unwrap();
+    assert_eq!(
+        take_sql(&log),
+        [
+            "SELECT id FROM orders WHERE status = ? LIMIT 100",
+            "SELECT id FROM orders",
+            "SELECT id FROM orders ORDER BY id DESC LIMIT 1",
+            "SELECT id FROM jobs LIMIT 2 FOR UPDATE",
+        ]
+    );
+
+    // 单次调用覆盖；达到上限时结果被截断
+    let rows: Vec<Row> = mapper
+        .with_options(QueryOptions {
+            auto_limit: Some(3),
+            ..Default::default()
+        })
+        .list("limit.all", &args)
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(
+        take_sql(&log),
+        ["SELECT id FROM orders WHERE status = ? LIMIT 3"]
+    );
+}
+
+#[tokio::test]
+async fn test_auto_limit_prepared() {
+    let (driver, log) = setup();
+    let statement = Session::new(driver).prepare("limit.claim").unwrap();
+    let rows: Vec<Row> = statement.query(&()).await.unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(take_sql(&log), ["SELECT id FROM jobs LIMIT 2 FOR UPDATE"]);
+}
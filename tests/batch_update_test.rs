@@ -0,0 +1,113 @@
+mod common;
+
+use common::{Counters, Log, MockDriver, committed};
+use serde::Serialize;
+use std::sync::{Arc, Once};
+use uorm::error::DbError;
+use uorm::executor::mapper::Mapper;
+use uorm::mapper_loader;
+use uorm::udbc::value::Value;
+
+/// 执行过的语句中的 id
+fn executed(log: &Log) -> Vec<i64> {
+    log.lock()
+        .unwrap()
+        .iter()
+        .filter(|call| call.sql.contains("UPDATE"))
+        .map(|call| match call.arg("id") {
+            Value::I64(id) => *id,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+const XML: &str = r#"<mapper namespace="batch_user">
+    <update id="rename">
+        UPDATE users SET name = #{name} WHERE id = #{id}
+    </update>
+</mapper>"#;
+
+#[derive(Serialize)]
+struct User {
+    id: i64,
+    name: String,
+}
+
+fn users(ids: &[i64]) -> Vec<User> {
+    ids.iter()
+        .map(|&id| User {
+            id,
+            name: format!("u{}", id),
+        })
+        .collect()
+}
+
+fn setup() -> (Log, Arc<Counters>, Mapper) {
+    static LOADED: Once = Once::new();
+    LOADED.call_once(|| {
+        mapper_loader::load_assets(vec![("batch_user.xml", XML)]).unwrap();
+    });
+    // id 为负数时模拟执行失败；影响行数等于 id 对 3 取余
+    let driver = MockDriver::new("recording").with_execute(|call| match call.arg("id") {
+        Value::I64(id) if *id < 0 => Err(DbError::Query(format!("row {} rejected", id))),
+        Value::I64(id) => Ok(*id as u64 % 3),
+        other => panic!("unexpected id {:?}", other),
+    });
+    let (log, counters) = (driver.log(), driver.counters());
+    (log, counters, Mapper::new(Arc::new(driver)))
+}
+
+#[tokio::test]
+async fn test_batch_update_uses_one_connection() {
+    let (log, counters, mapper) = setup();
+    let affected = mapper
+        .batch_update("batch_user.rename", &users(&[1, 2, 3, 4]))
+        .await
+        .unwrap();
+    assert_eq!(affected, [1, 2, 0, 1]);
+    assert_eq!(counters.opened(), 1);
+    assert_eq!(committed(&log), None);
+
+    let empty: Vec<User> = Vec::new();
+    assert!(
+        mapper
+            .batch_update("batch_user.rename", &empty)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(counters.opened(), 1);
+}
+
+#[tokio::test]
+async fn test_batch_update_stops_at_failure() {
+    let (log, _, mapper) = setup();
+    let err = mapper
+        .batch_update("batch_user.rename", &users(&[1, -2, 3]))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("row -2 rejected"), "{}", err);
+    assert_eq!(executed(&log), [1, -2]);
+    assert_eq!(committed(&log), None);
+}
+
+#[tokio::test]
+async fn test_batch_update_in_tx() {
+    let (log, counters, mapper) = setup();
+    let affected = mapper
+        .batch_update_in_tx("batch_user.rename", &users(&[5, 6]))
+        .await
+        .unwrap();
+    assert_eq!(affected, [2, 0]);
+    assert_eq!(counters.opened(), 1);
+    assert_eq!(committed(&log), Some(true));
+
+    let (log, _, mapper) = setup();
+    assert!(
+        mapper
+            .batch_update_in_tx("batch_user.rename", &users(&[7, -8]))
+            .await
+            .is_err()
+    );
+    assert_eq!(committed(&log), Some(false));
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{MockDriver, row};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::blocking::{Mapper, Session};
+use uorm::mapper_loader;
+use uorm::udbc::value::Value;
+
+const XML: &str = r#"<mapper namespace="blocking_user">
+    <select id="get">
+        SELECT id, name FROM users WHERE id = #{id}
+    </select>
+</mapper>"#;
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: i64,
+    name: String,
+}
+
+#[test]
+fn test_blocking_mapper_and_session() {
+    mapper_loader::load_assets(vec![("blocking_user.xml", XML)]).unwrap();
+    let driver = Arc::new(MockDriver::new("mock").with_rows(vec![row([
+        ("id", Value::I64(1)),
+        ("name", Value::Str("alice".into())),
+    ])]));
+    let log = driver.log();
+
+    let mapper = Mapper::new(driver.clone());
+    let user: User = mapper
+        .get("blocking_user.get", &HashMap::from([("id", 1)]))
+        .unwrap();
+    assert_eq!((user.id, user.name.as_str()), (1, "alice"));
+
+    let session = Session::new(driver.clone());
+    assert_eq!(session.execute("DELETE FROM users", &()).unwrap(), 1);
+
+    // 未提交的事务在释放时回滚
+    let tx = session.begin().unwrap();
+    tx.execute("UPDATE users SET name = 'bob'", &()).unwrap();
+    drop(tx);
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    let log = log.lock().unwrap();
+    assert_eq!(log.last().map(|c| c.sql.as_str()), Some("ROLLBACK"));
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use common::{MockDriver, id_rows};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::capture::{self, CaptureOptions, ReplayOptions};
+use uorm::executor::session::Session;
+use uorm::udbc::value::Value;
+
+#[tokio::test]
+async fn test_capture_and_replay() {
+    let path = std::env::temp_dir().join(format!("uorm_capture_{}.jsonl", std::process::id()));
+    let session = Session::new(Arc::new(MockDriver::new("primary").with_rows(id_rows(2))));
+    let args = HashMap::from([("id", 7)]);
+
+    // 窗口开始前的语句不记录
+    session
+        .query::<HashMap<String, i64>, _>("SELECT 0", &())
+        .await
+        .unwrap();
+    let options = CaptureOptions {
+        params: true,
+        ..Default::default()
+    };
+    capture::start(&path, options.clone()).unwrap();
+    assert!(capture::is_capturing());
+    assert!(capture::start(&path, options).is_err());
+    session
+        .query_named::<HashMap<String, i64>, _>(
+            "user.get",
+            "SELECT * FROM users WHERE id = #{id}",
+            &args,
+        )
+        .await
+        .unwrap();
+    session
+        .execute("UPDATE users SET seen = 1 WHERE id = #{id}", &args)
+        .await
+        .unwrap();
+    let summary = capture::stop().unwrap().unwrap();
+    assert!(!capture::is_capturing());
+    assert_eq!((summary.seen, summary.recorded), (2, 2));
+
+    let statements = capture::load(&path).unwrap();
+    assert_eq!(statements.len(), 2);
+    let get = &statements[0];
+    assert_eq!(get.op, "query");
+    assert_eq!(get.pool, "primary");
+    assert_eq!(get.sql_id.as_deref(), Some("user.get"));
+    assert_eq!(get.rows, Some(2));
+    let params = vec![("id".to_string(), Value::I32(7))];
+    assert_eq!(get.params.as_deref(), Some(params.as_slice()));
+    assert_eq!(get.params_digest, capture::params_digest(&params));
+    assert!(statements[1].is_write());
+    assert_eq!(statements[1].sql_id, None);
+
+    // 回放到预发布库：查询返回的行数不同
+    let staging = MockDriver::new("staging").with_rows(id_rows(3));
+    let log = staging.log();
+    let report = capture::replay(&staging, &path, &ReplayOptions::default())
+        .await
+        .unwrap();
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!(
+        (report.executed, report.skipped, report.row_mismatches),
+        (2, 0, 1)
+    );
+    assert_eq!(report.statements["user.get"].count, 1);
+    assert_eq!(
+        common::take(&log)
+            .into_iter()
+            .map(|call| (call.sql, call.args))
+            .collect::<Vec<_>>(),
+        [
+            (
+                "SELECT * FROM users WHERE id = ?".to_string(),
+                params.clone()
+            ),
+            (
+                "UPDATE users SET seen = 1 WHERE id = ?".to_string(),
+                params.clone()
+            ),
+        ]
+    );
+
+    let options = ReplayOptions {
+        skip_writes: true,
+        speed: Some(100.0),
+        ..Default::default()
+    };
+    let report = capture::replay(&staging, &path, &options).await.unwrap();
+    assert_eq!((report.executed, report.skipped), (1, 1));
+    assert!(
+        report
+            .to_string()
+            .starts_with("executed 1, skipped 1, failed 0, row mismatches 1"),
+        "{}",
+        report
+    );
+
+    // 默认不记录参数，只保留摘要，回放时跳过
+    capture::start(
+        &path,
+        CaptureOptions {
+            sample_rate: 0.5,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    for _ in 0..4 {
+        session
+            .execute("DELETE FROM t WHERE id = #{id}", &args)
+            .await
+            .unwrap();
+    }
+    let summary = capture::stop().unwrap().unwrap();
+    assert_eq!((summary.seen, summary.recorded), (4, 2));
+    let statements = capture::load(&path).unwrap();
+    assert!(
+        statements
+            .iter()
+            .all(|s| s.params.is_none() && s.params_digest == capture::params_digest(&params))
+    );
+    let report = capture::replay(&staging, &path, &ReplayOptions::default())
+        .await
+        .unwrap();
+    assert_eq!((report.executed, report.skipped), (0, 2));
+
+    std::fs::remove_file(&path).unwrap();
+}
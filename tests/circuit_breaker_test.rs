@@ -0,0 +1,263 @@
+mod common;
+
+use async_trait::async_trait;
+use common::MockDriver;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use uorm::error::DbError;
+use uorm::executor::options::QueryOptions;
+use uorm::executor::postprocess::RowPostProcessor;
+use uorm::udbc::breaker::{CircuitBreakerDriver, CircuitBreakerOptions, CircuitState};
+use uorm::udbc::connection::Connection;
+use uorm::udbc::driver::{Driver, Maintenance, PacketLimit, QueueMetrics, TransactionLimits};
+use uorm::udbc::value::Value;
+
+fn driver(
+    down: bool,
+) -> (
+    CircuitBreakerDriver<MockDriver>,
+    Arc<AtomicBool>,
+    Arc<AtomicUsize>,
+) {
+    let down = Arc::new(AtomicBool::new(down));
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let mock = MockDriver::new("mock")
+        .with_query(|call| {
+            if call.sql.contains("broken") {
+                return Err(DbError::Database("server has gone away".to_string()));
+            }
+            Ok(Vec::new())
+        })
+        .with_connect({
+            let down = down.clone();
+            let attempts = attempts.clone();
+            move |_| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                let down = down.load(Ordering::SeqCst);
+                async move {
+                    if down {
+                        return Err(DbError::Connection("connection refused".to_string()));
+                    }
+                    Ok(())
+                }
+            }
+        });
+    let driver = CircuitBreakerDriver::new(
+        mock,
+        CircuitBreakerOptions {
+            failure_threshold: 3,
+            cooldown: Duration::from_millis(50),
+        },
+    );
+    (driver, down, attempts)
+}
+
+#[tokio::test]
+async fn test_opens_after_consecutive_failures_and_recovers() {
+    let (driver, down, attempts) = driver(true);
+    for _ in 0..3 {
+        assert!(matches!(
+            driver.connection().await,
+            Err(DbError::Connection(_))
+        ));
+    }
+    assert_eq!(driver.state(), CircuitState::Open);
+
+    // 熔断期间不再尝试连接
+    assert!(matches!(driver.connection().await, Err(DbError::CircuitOpen(name)) if name == "mock"));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+    // 冷却后探测失败，重新打开
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(matches!(
+        driver.connection().await,
+        Err(DbError::Connection(_))
+    ));
+    assert_eq!(driver.state(), CircuitState::Open);
+    assert!(matches!(
+        driver.connection().await,
+        Err(DbError::CircuitOpen(_))
+    ));
+
+    // 数据库恢复后探测成功即关闭
+    down.store(false, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    let conn = driver.connection().await.unwrap();
+    conn.query("SELECT 1", &[]).await.unwrap();
+    assert_eq!(driver.state(), CircuitState::Closed);
+}
+
+#[tokio::test]
+async fn test_query_failures_count_and_success_resets() {
+    let (driver, _, _) = driver(false);
+    let conn = driver.connection().await.unwrap();
+    for _ in 0..2 {
+        assert!(conn.query("SELECT broken", &[]).await.is_err());
+    }
+    conn.query("SELECT 1", &[]).await.unwrap();
+    for _ in 0..2 {
+        assert!(conn.query("SELECT broken", &[]).await.is_err());
+    }
+    assert_eq!(driver.state(), CircuitState::Closed);
+
+    assert!(conn.query("SELECT broken", &[]).await.is_err());
+    assert_eq!(driver.state(), CircuitState::Open);
+    assert!(matches!(
+        driver.connection().await,
+        Err(DbError::CircuitOpen(_))
+    ));
+}
+
+/// 每个方法都返回非默认值的驱动
+struct ConfiguredDriver {
+    inner: MockDriver,
+    processors: Vec<Arc<dyn RowPostProcessor>>,
+    closed: Arc<AtomicBool>,
+}
+
+struct Noop;
+
+impl RowPostProcessor for Noop {
+    fn process(
+        &self,
+        _sql_id: Option<&str>,
+        _row: &mut HashMap<String, Value>,
+    ) -> Result<(), DbError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Driver for ConfiguredDriver {
+    fn name(&self) -> &str {
+        "configured"
+    }
+
+    fn r#type(&self) -> &str {
+        "postgres"
+    }
+
+    fn placeholder(&self, seq: usize, _name: &str) -> String {
+        format!("${}", seq)
+    }
+
+    async fn connection(&self) -> Result<Arc<dyn Connection>, DbError> {
+        self.inner.connection().await
+    }
+
+    async fn close(&self) -> Result<(), DbError> {
+        self.closed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn supports_returning(&self) -> bool {
+        true
+    }
+
+    fn wrap_param(&self, func: &str, placeholder: &str) -> Option<String> {
+        Some(format!("{}::{}", placeholder, func))
+    }
+
+    fn quote_ident(&self, ident: &str) -> String {
+        format!("[{}]", ident)
+    }
+
+    fn template_var(&self, name: &str) -> Option<&str> {
+        (name == "schema").then_some("tenant")
+    }
+
+    fn switch_schema_sql(&self, schema: &str) -> Option<String> {
+        Some(format!("SET search_path TO {}", schema))
+    }
+
+    fn current_schema_sql(&self) -> Option<&str> {
+        Some("SELECT current_schema()")
+    }
+
+    fn query_options(&self) -> QueryOptions {
+        QueryOptions {
+            max_rows: Some(10),
+            ..Default::default()
+        }
+    }
+
+    fn transaction_limits(&self) -> TransactionLimits {
+        TransactionLimits {
+            warn_after: Some(Duration::from_secs(1)),
+            max_duration: Some(Duration::from_secs(2)),
+        }
+    }
+
+    fn packet_limit(&self) -> Option<PacketLimit> {
+        Some(PacketLimit {
+            max_bytes: 1024,
+            split_lists: true,
+        })
+    }
+
+    fn row_processors(&self) -> &[Arc<dyn RowPostProcessor>] {
+        &self.processors
+    }
+
+    fn queue_metrics(&self) -> Option<QueueMetrics> {
+        Some(QueueMetrics {
+            max_concurrent: 4,
+            ..Default::default()
+        })
+    }
+
+    fn keepalive_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+
+    async fn maintain(&self) -> Result<Maintenance, DbError> {
+        Ok(Maintenance {
+            alive: 3,
+            ..Default::default()
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_delegates_every_driver_method() {
+    let inner = ConfiguredDriver {
+        inner: MockDriver::new("configured"),
+        processors: vec![Arc::new(Noop)],
+        closed: Arc::default(),
+    };
+    let processors = inner.processors.clone();
+    let closed = inner.closed.clone();
+    let driver = CircuitBreakerDriver::new(inner, CircuitBreakerOptions::default());
+
+    assert_eq!(driver.name(), "configured");
+    assert_eq!(driver.r#type(), "postgres");
+    assert_eq!(driver.placeholder(2, "id"), "$2");
+    assert!(driver.supports_returning());
+    assert_eq!(
+        driver.wrap_param("jsonb", "$1").as_deref(),
+        Some("$1::jsonb")
+    );
+    assert_eq!(driver.quote_ident("users"), "[users]");
+    assert_eq!(driver.template_var("schema"), Some("tenant"));
+    assert_eq!(
+        driver.switch_schema_sql("t1").as_deref(),
+        Some("SET search_path TO t1")
+    );
+    assert_eq!(driver.current_schema_sql(), Some("SELECT current_schema()"));
+    assert_eq!(driver.query_options().max_rows, Some(10));
+    assert_eq!(
+        driver.transaction_limits().max_duration,
+        Some(Duration::from_secs(2))
+    );
+    assert_eq!(driver.packet_limit().map(|l| l.max_bytes), Some(1024));
+    assert_eq!(driver.row_processors().len(), 1);
+    assert!(Arc::ptr_eq(&driver.row_processors()[0], &processors[0]));
+    assert_eq!(driver.queue_metrics().map(|m| m.max_concurrent), Some(4));
+    assert_eq!(driver.keepalive_interval(), Some(Duration::from_secs(30)));
+    assert_eq!(driver.maintain().await.unwrap().alive, 3);
+    driver.connection().await.unwrap();
+    driver.close().await.unwrap();
+    assert!(closed.load(Ordering::SeqCst));
+}
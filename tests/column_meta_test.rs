@@ -0,0 +1,95 @@
+mod common;
+
+use common::{Log, MockDriver, row, take_sql};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::error::DbError;
+use uorm::executor::session::{Nested, Session};
+use uorm::udbc::connection::ColumnMeta;
+use uorm::udbc::value::Value;
+
+fn meta(name: &str, type_name: &str, nullable: bool, length: u64) -> ColumnMeta {
+    ColumnMeta {
+        name: name.to_string(),
+        type_name: type_name.to_string(),
+        nullable,
+        length,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: u64,
+    name: String,
+}
+
+const SQL: &str = "SELECT id, name FROM users WHERE status = #{status}";
+
+/// `with_meta` 为 false 时使用默认的 `query_with_meta`
+fn session(with_meta: bool) -> (Session, Log) {
+    let mut driver = MockDriver::new("meta").with_query(|call| {
+        assert_eq!(call.args, [("status".to_string(), Value::I32(1))]);
+        Ok(vec![row([
+            ("id", Value::I64(7)),
+            ("name", Value::Str("alice".to_string())),
+        ])])
+    });
+    if with_meta {
+        driver = driver.with_columns(vec![
+            meta("id", "BIGINT UNSIGNED", false, 20),
+            meta("name", "VARCHAR", true, 256),
+        ]);
+    }
+    let log = driver.log();
+    (Session::new(Arc::new(driver)), log)
+}
+
+#[tokio::test]
+async fn test_query_with_meta() {
+    let (session, log) = session(true);
+    let args = HashMap::from([("status", 1)]);
+
+    let (columns, users) = session
+        .query_with_meta::<User, _>(SQL, &args)
+        .await
+        .unwrap();
+    assert_eq!(
+        columns,
+        [
+            meta("id", "BIGINT UNSIGNED", false, 20),
+            meta("name", "VARCHAR", true, 256),
+        ]
+    );
+    assert_eq!((users[0].id, users[0].name.as_str()), (7, "alice"));
+
+    // 事务中使用事务连接
+    let (columns, rows) = session
+        .transactional(Nested::Join, async {
+            session.query_with_meta::<User, _>(SQL, &args).await
+        })
+        .await
+        .unwrap();
+    assert_eq!(columns.len(), 2);
+    assert_eq!(rows[0].name, "alice");
+
+    assert_eq!(
+        take_sql(&log),
+        [
+            "SELECT id, name FROM users WHERE status = ?",
+            "BEGIN",
+            "SELECT id, name FROM users WHERE status = ?",
+            "COMMIT",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_query_with_meta_unsupported() {
+    let (session, _) = session(false);
+    let err = session
+        .query_with_meta::<User, _>(SQL, &HashMap::from([("status", 1)]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DbError::NotImplemented), "{}", err);
+}
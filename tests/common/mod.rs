@@ -0,0 +1,462 @@
+//! 集成测试共用的模拟连接池
+//!
+//! [`MockDriver`] 取出的连接把每条语句记录为一条 [`Call`]：事务的开始、提交与回滚记为
+//! `BEGIN`、`COMMIT`、`ROLLBACK`，丢弃连接记为 `DISCARD`。查询默认返回 [`MockDriver::with_rows`]
+//! 设置的行，更新默认影响 1 行，需要按语句返回不同结果或报错时用 [`MockDriver::with_query`]、
+//! [`MockDriver::with_execute`] 替换。
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uorm::error::DbError;
+use uorm::executor::options::QueryOptions;
+use uorm::executor::postprocess::RowPostProcessor;
+use uorm::udbc::connection::{ColumnMeta, Connection};
+use uorm::udbc::driver::{Driver, PacketLimit, TransactionLimits, quote_ident_with};
+use uorm::udbc::value::Value;
+
+pub type Row = HashMap<String, Value>;
+
+/// 连接上执行的一条语句
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    /// 连接编号，按取出顺序从 0 开始
+    pub conn: usize,
+    pub sql: String,
+    pub args: Vec<(String, Value)>,
+}
+
+impl Call {
+    /// 按绑定顺序排列的参数值
+    pub fn values(&self) -> Vec<Value> {
+        self.args.iter().map(|(_, v)| v.clone()).collect()
+    }
+
+    /// 名为 `name` 的参数值
+    pub fn arg(&self, name: &str) -> &Value {
+        match self.args.iter().find(|(k, _)| k == name) {
+            Some((_, v)) => v,
+            None => panic!("no argument '{}' in {:?}", name, self),
+        }
+    }
+}
+
+pub type Log = Arc<Mutex<Vec<Call>>>;
+
+/// 取出并清空记录
+pub fn take(log: &Log) -> Vec<Call> {
+    std::mem::take(&mut *log.lock().unwrap())
+}
+
+/// 取出并清空记录，只保留去掉首尾空白的 SQL
+pub fn take_sql(log: &Log) -> Vec<String> {
+    take(log)
+        .into_iter()
+        .map(|c| c.sql.trim().to_string())
+        .collect()
+}
+
+/// 最近一次事务的结果：提交为 `Some(true)`，回滚为 `Some(false)`
+pub fn committed(log: &Log) -> Option<bool> {
+    log.lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .find_map(|call| match call.sql.as_str() {
+            "COMMIT" => Some(true),
+            "ROLLBACK" => Some(false),
+            _ => None,
+        })
+}
+
+/// 由列名与取值构造一行
+pub fn row<const N: usize>(columns: [(&str, Value); N]) -> Row {
+    columns
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect()
+}
+
+/// `id` 列为 `0..n` 的 `n` 行
+pub fn id_rows(n: usize) -> Vec<Row> {
+    (0..n)
+        .map(|i| row([("id", Value::I64(i as i64))]))
+        .collect()
+}
+
+/// 连接的取出与释放计数
+#[derive(Default)]
+pub struct Counters {
+    opened: AtomicUsize,
+    released: AtomicUsize,
+    /// 仍被持有的连接数；单独计数，避免并发取出与释放时两个计数之差暂时为负
+    held: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl Counters {
+    /// 已取出的连接数
+    pub fn opened(&self) -> usize {
+        self.opened.load(Ordering::SeqCst)
+    }
+
+    /// 已释放的连接数
+    pub fn released(&self) -> usize {
+        self.released.load(Ordering::SeqCst)
+    }
+
+    /// 仍被持有的连接数
+    pub fn open(&self) -> usize {
+        self.held.load(Ordering::SeqCst)
+    }
+
+    /// 同时持有的连接数峰值
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+}
+
+type QueryFn = dyn Fn(&Call) -> Result<Vec<Row>, DbError> + Send + Sync;
+type ExecuteFn = dyn Fn(&Call) -> Result<u64, DbError> + Send + Sync;
+type CallbackFn = dyn Fn(&Call) + Send + Sync;
+type ConnectFuture = Pin<Box<dyn Future<Output = Result<(), DbError>> + Send>>;
+type ConnectFn = dyn Fn(usize) -> ConnectFuture + Send + Sync;
+type InsertIdFn = dyn Fn(usize) -> u64 + Send + Sync;
+
+/// 记录语句的模拟连接池
+pub struct MockDriver {
+    name: String,
+    r#type: String,
+    dollar_placeholders: bool,
+    log: Log,
+    query: Arc<QueryFn>,
+    execute: Arc<ExecuteFn>,
+    callback: Option<Arc<CallbackFn>>,
+    connect: Option<Arc<ConnectFn>>,
+    last_insert_id: Arc<InsertIdFn>,
+    query_delay: Duration,
+    next_conn: AtomicUsize,
+    counters: Arc<Counters>,
+    options: QueryOptions,
+    returning: bool,
+    packet_limit: Option<PacketLimit>,
+    row_processors: Vec<Arc<dyn RowPostProcessor>>,
+    transaction_limits: TransactionLimits,
+    schema_switching: bool,
+    columns: Option<Vec<ColumnMeta>>,
+}
+
+impl MockDriver {
+    /// 名为 `name` 的连接池：查询不返回行，更新影响 1 行，`last_insert_id` 为 0
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            r#type: "mock".to_string(),
+            dollar_placeholders: false,
+            log: Log::default(),
+            query: Arc::new(|_| Ok(Vec::new())),
+            execute: Arc::new(|_| Ok(1)),
+            callback: None,
+            connect: None,
+            last_insert_id: Arc::new(|_| 0),
+            query_delay: Duration::ZERO,
+            next_conn: AtomicUsize::new(0),
+            counters: Arc::default(),
+            options: QueryOptions::default(),
+            returning: false,
+            packet_limit: None,
+            row_processors: Vec::new(),
+            transaction_limits: TransactionLimits::default(),
+            schema_switching: false,
+            columns: None,
+        }
+    }
+
+    /// 查询一律返回 `rows`
+    pub fn with_rows(self, rows: Vec<Row>) -> Self {
+        self.with_query(move |_| Ok(rows.clone()))
+    }
+
+    /// 按语句返回查询结果
+    pub fn with_query(
+        mut self,
+        query: impl Fn(&Call) -> Result<Vec<Row>, DbError> + Send + Sync + 'static,
+    ) -> Self {
+        self.query = Arc::new(query);
+        self
+    }
+
+    /// 按语句返回更新的影响行数
+    pub fn with_execute(
+        mut self,
+        execute: impl Fn(&Call) -> Result<u64, DbError> + Send + Sync + 'static,
+    ) -> Self {
+        self.execute = Arc::new(execute);
+        self
+    }
+
+    /// 每条记录写入日志前调用 `callback`
+    pub fn with_callback(mut self, callback: impl Fn(&Call) + Send + Sync + 'static) -> Self {
+        self.callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// 取出第 `n` 个连接前等待 `connect(n)`，返回错误时取出失败
+    pub fn with_connect<F>(mut self, connect: impl Fn(usize) -> F + Send + Sync + 'static) -> Self
+    where
+        F: Future<Output = Result<(), DbError>> + Send + 'static,
+    {
+        self.connect = Some(Arc::new(move |n| Box::pin(connect(n))));
+        self
+    }
+
+    /// 第 `n` 个连接上的 `last_insert_id` 返回调用时的 `id(n)`
+    pub fn with_last_insert_id(
+        mut self,
+        id: impl Fn(usize) -> u64 + Send + Sync + 'static,
+    ) -> Self {
+        self.last_insert_id = Arc::new(id);
+        self
+    }
+
+    /// 数据库类型，默认 `mock`
+    pub fn with_type(mut self, r#type: &str) -> Self {
+        self.r#type = r#type.to_string();
+        self
+    }
+
+    /// 使用 `$1`、`$2` 形式的占位符，默认为 `?`
+    pub fn with_dollar_placeholders(mut self) -> Self {
+        self.dollar_placeholders = true;
+        self
+    }
+
+    /// 每次查询前等待 `delay`
+    pub fn with_query_delay(mut self, delay: Duration) -> Self {
+        self.query_delay = delay;
+        self
+    }
+
+    pub fn with_query_options(mut self, options: QueryOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// 支持 `INSERT ... RETURNING`
+    pub fn with_returning(mut self) -> Self {
+        self.returning = true;
+        self
+    }
+
+    /// 设置负载上限；与 MySQL 驱动一样，连接在发送前检查，超限的语句不记录
+    pub fn with_packet_limit(mut self, limit: PacketLimit) -> Self {
+        self.packet_limit = Some(limit);
+        self
+    }
+
+    pub fn with_row_processor(mut self, processor: Arc<dyn RowPostProcessor>) -> Self {
+        self.row_processors.push(processor);
+        self
+    }
+
+    pub fn with_transaction_limits(mut self, limits: TransactionLimits) -> Self {
+        self.transaction_limits = limits;
+        self
+    }
+
+    /// 以 MySQL 的 ``USE `schema` `` 与 `SELECT DATABASE()` 切换、查询 schema
+    pub fn with_schema_switching(mut self) -> Self {
+        self.schema_switching = true;
+        self
+    }
+
+    /// 支持 `query_with_meta`，结果列为 `columns`；默认不支持
+    pub fn with_columns(mut self, columns: Vec<ColumnMeta>) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    /// 执行记录
+    pub fn log(&self) -> Log {
+        self.log.clone()
+    }
+
+    /// 连接计数，注册到 `DriverManager` 前取出以便之后检查
+    pub fn counters(&self) -> Arc<Counters> {
+        self.counters.clone()
+    }
+}
+
+struct MockConn {
+    id: usize,
+    log: Log,
+    query: Arc<QueryFn>,
+    execute: Arc<ExecuteFn>,
+    callback: Option<Arc<CallbackFn>>,
+    last_insert_id: Arc<InsertIdFn>,
+    query_delay: Duration,
+    packet_limit: Option<PacketLimit>,
+    columns: Option<Vec<ColumnMeta>>,
+    counters: Arc<Counters>,
+}
+
+impl MockConn {
+    fn record(&self, sql: &str, args: &[(String, Value)]) -> Call {
+        let call = Call {
+            conn: self.id,
+            sql: sql.to_string(),
+            args: args.to_vec(),
+        };
+        if let Some(callback) = &self.callback {
+            callback(&call);
+        }
+        self.log.lock().unwrap().push(call.clone());
+        call
+    }
+
+    fn check(&self, sql: &str, args: &[(String, Value)]) -> Result<(), DbError> {
+        match &self.packet_limit {
+            Some(limit) => limit.check(sql, args),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for MockConn {
+    fn drop(&mut self) {
+        self.counters.held.fetch_sub(1, Ordering::SeqCst);
+        self.counters.released.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl Connection for MockConn {
+    async fn query(&self, sql: &str, args: &[(String, Value)]) -> Result<Vec<Row>, DbError> {
+        self.check(sql, args)?;
+        if !self.query_delay.is_zero() {
+            tokio::time::sleep(self.query_delay).await;
+        }
+        let call = self.record(sql, args);
+        (self.query)(&call)
+    }
+
+    async fn query_with_meta(
+        &self,
+        sql: &str,
+        args: &[(String, Value)],
+    ) -> Result<(Vec<ColumnMeta>, Vec<Row>), DbError> {
+        let Some(columns) = &self.columns else {
+            return Err(DbError::NotImplemented);
+        };
+        Ok((columns.clone(), self.query(sql, args).await?))
+    }
+
+    async fn execute(&self, sql: &str, args: &[(String, Value)]) -> Result<u64, DbError> {
+        self.check(sql, args)?;
+        let call = self.record(sql, args);
+        (self.execute)(&call)
+    }
+
+    async fn last_insert_id(&self) -> Result<u64, DbError> {
+        Ok((self.last_insert_id)(self.id))
+    }
+
+    async fn begin(&self) -> Result<(), DbError> {
+        self.record("BEGIN", &[]);
+        Ok(())
+    }
+
+    async fn commit(&self) -> Result<(), DbError> {
+        self.record("COMMIT", &[]);
+        Ok(())
+    }
+
+    async fn rollback(&self) -> Result<(), DbError> {
+        self.record("ROLLBACK", &[]);
+        Ok(())
+    }
+
+    fn discard(&self) {
+        self.record("DISCARD", &[]);
+    }
+}
+
+#[async_trait]
+impl Driver for MockDriver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn r#type(&self) -> &str {
+        &self.r#type
+    }
+
+    fn placeholder(&self, seq: usize, _name: &str) -> String {
+        if self.dollar_placeholders {
+            format!("${}", seq)
+        } else {
+            "?".to_string()
+        }
+    }
+
+    async fn connection(&self) -> Result<Arc<dyn Connection>, DbError> {
+        let id = self.next_conn.fetch_add(1, Ordering::SeqCst);
+        if let Some(connect) = &self.connect {
+            connect(id).await?;
+        }
+        let counters = &self.counters;
+        counters.opened.fetch_add(1, Ordering::SeqCst);
+        let open = counters.held.fetch_add(1, Ordering::SeqCst) + 1;
+        counters.peak.fetch_max(open, Ordering::SeqCst);
+        Ok(Arc::new(MockConn {
+            id,
+            log: self.log.clone(),
+            query: self.query.clone(),
+            execute: self.execute.clone(),
+            callback: self.callback.clone(),
+            last_insert_id: self.last_insert_id.clone(),
+            query_delay: self.query_delay,
+            packet_limit: self.packet_limit,
+            columns: self.columns.clone(),
+            counters: self.counters.clone(),
+        }))
+    }
+
+    async fn close(&self) -> Result<(), DbError> {
+        Ok(())
+    }
+
+    fn supports_returning(&self) -> bool {
+        self.returning
+    }
+
+    fn switch_schema_sql(&self, schema: &str) -> Option<String> {
+        self.schema_switching
+            .then(|| format!("USE {}", quote_ident_with('`', schema)))
+    }
+
+    fn current_schema_sql(&self) -> Option<&str> {
+        self.schema_switching.then_some("SELECT DATABASE()")
+    }
+
+    fn query_options(&self) -> QueryOptions {
+        self.options.clone()
+    }
+
+    fn transaction_limits(&self) -> TransactionLimits {
+        self.transaction_limits
+    }
+
+    fn packet_limit(&self) -> Option<PacketLimit> {
+        self.packet_limit
+    }
+
+    fn row_processors(&self) -> &[Arc<dyn RowPostProcessor>] {
+        &self.row_processors
+    }
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use common::{Call, Log, MockDriver, committed};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Once};
+use uorm::error::DbError;
+use uorm::executor::mapper::Mapper;
+use uorm::executor::session::{Nested, Session};
+use uorm::mapper_loader;
+use uorm::udbc::value::Value;
+
+/// 涉及 `broken` 表的语句失败，生成的主键从 100 开始递增
+fn driver() -> (Arc<MockDriver>, Log) {
+    let next_id = AtomicU64::new(100);
+    let driver = MockDriver::new("recording")
+        .with_execute(|call| {
+            if call.sql.contains("broken") {
+                return Err(DbError::Query("table broken does not exist".into()));
+            }
+            Ok(1)
+        })
+        .with_last_insert_id(move |_| next_id.fetch_add(1, Ordering::SeqCst));
+    let log = driver.log();
+    (Arc::new(driver), log)
+}
+
+/// 执行过的语句，不含事务的开始与结束
+fn statements(log: &Log) -> Vec<Call> {
+    log.lock()
+        .unwrap()
+        .iter()
+        .filter(|call| !matches!(call.sql.as_str(), "BEGIN" | "COMMIT" | "ROLLBACK"))
+        .cloned()
+        .collect()
+}
+
+const XML: &str = r#"<mapper namespace="order_graph">
+    <insert id="createOrder" useGeneratedKeys="true">
+        INSERT INTO orders (customer) VALUES (#{customer})
+        <insert id="lines" collection="lines" item="line">
+            INSERT INTO order_line (order_id, sku) VALUES (#{parent.id}, #{line.sku})
+        </insert>
+        <insert id="audit">
+            INSERT INTO order_audit (order_id, customer) VALUES (#{parent.id}, #{customer})
+        </insert>
+    </insert>
+    <insert id="createBroken" useGeneratedKeys="true">
+        INSERT INTO orders (customer) VALUES (#{customer})
+        <insert id="fail">
+            INSERT INTO broken (order_id) VALUES (#{parent.id})
+        </insert>
+    </insert>
+</mapper>"#;
+
+fn setup() {
+    static LOADED: Once = Once::new();
+    LOADED.call_once(|| mapper_loader::load_assets(vec![("order_graph.xml", XML)]).unwrap());
+}
+
+#[derive(Serialize)]
+struct Line {
+    sku: String,
+}
+
+#[derive(Serialize)]
+struct Order {
+    customer: String,
+    lines: Vec<Line>,
+}
+
+fn order() -> Order {
+    Order {
+        customer: "alice".into(),
+        lines: vec![Line { sku: "A".into() }, Line { sku: "B".into() }],
+    }
+}
+
+#[tokio::test]
+async fn test_create_graph() {
+    setup();
+
+    let (driver, log) = driver();
+    let mapper = Mapper::new(driver);
+    let id: i64 = mapper
+        .create_graph("order_graph.createOrder", &order())
+        .await
+        .unwrap();
+    assert_eq!(id, 100);
+    assert_eq!(committed(&log), Some(true));
+
+    let statements = statements(&log);
+    assert_eq!(statements.len(), 4);
+    assert!(statements[1].sql.contains("order_line"));
+    assert_eq!(statements[1].values(), [Value::I64(100), Value::from("A")]);
+    assert_eq!(statements[2].values(), [Value::I64(100), Value::from("B")]);
+    assert_eq!(
+        statements[3].values(),
+        [Value::I64(100), Value::from("alice")]
+    );
+}
+
+#[tokio::test]
+async fn test_create_graph_rolls_back() {
+    setup();
+
+    let (driver, log) = driver();
+    let mapper = Mapper::new(driver);
+    let err = mapper
+        .create_graph::<i64, _>("order_graph.createBroken", &order())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("broken"));
+    assert_eq!(committed(&log), Some(false));
+}
+
+#[tokio::test]
+async fn test_create_graph_uses_savepoint_in_transaction() {
+    setup();
+
+    let (driver, log) = driver();
+    let mapper = Mapper::new(driver.clone());
+    Session::new(driver)
+        .transactional(Nested::Savepoint, async {
+            let err = mapper
+                .create_graph::<i64, _>("order_graph.createBroken", &order())
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("broken"));
+            Ok::<_, DbError>(())
+        })
+        .await
+        .unwrap();
+    // 只回滚到保存点，外层事务照常提交
+    assert_eq!(committed(&log), Some(true));
+    let statements = statements(&log);
+    let sql: Vec<&str> = statements.iter().map(|call| call.sql.trim()).collect();
+    assert_eq!(sql.first(), Some(&"SAVEPOINT uorm_sp_1"));
+    assert_eq!(sql.last(), Some(&"ROLLBACK TO SAVEPOINT uorm_sp_1"));
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::{Log, MockDriver, row};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Once};
+use uorm::error::DbError;
+use uorm::executor::mapper::Mapper;
+use uorm::executor::session::{Nested, Session};
+use uorm::mapper_loader;
+use uorm::udbc::value::Value;
+
+const XML: &str = r#"<mapper namespace="returning_user">
+    <insert id="create" useGeneratedKeys="true">
+        INSERT INTO users (name) VALUES (#{name})
+    </insert>
+    <insert id="createWithSelect" useGeneratedKeys="true" keyColumn="user_id" returningSelect="getById">
+        INSERT INTO users (name) VALUES (#{name})
+    </insert>
+    <select id="getById">
+        SELECT user_id AS id, name FROM users WHERE user_id = #{user_id}
+    </select>
+</mapper>"#;
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: i64,
+    name: String,
+}
+
+fn driver(returning: bool) -> (Arc<MockDriver>, Log) {
+    static LOADED: Once = Once::new();
+    LOADED.call_once(|| mapper_loader::load_assets(vec![("returning_user.xml", XML)]).unwrap());
+    let mut driver = MockDriver::new("mock")
+        .with_rows(vec![row([
+            ("id", Value::I64(42)),
+            ("name", Value::Str("alice".into())),
+        ])])
+        .with_last_insert_id(|_| 42);
+    if returning {
+        driver = driver.with_returning();
+    }
+    let log = driver.log();
+    (Arc::new(driver), log)
+}
+
+fn mapper(returning: bool) -> (Mapper, Log) {
+    let (driver, log) = driver(returning);
+    (Mapper::new(driver), log)
+}
+
+#[tokio::test]
+async fn test_returning_clause() {
+    let (mapper, log) = mapper(true);
+    let args = HashMap::from([("name", "alice")]);
+    let user: User = mapper
+        .create_returning("returning_user.create", &args)
+        .await
+        .unwrap();
+    assert_eq!((user.id, user.name.as_str()), (42, "alice"));
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 1);
+    assert!(log[0].sql.ends_with("VALUES (?) RETURNING *"));
+}
+
+#[tokio::test]
+async fn test_fallback_reads_back_by_table() {
+    let (mapper, log) = mapper(false);
+    let args = HashMap::from([("name", "alice")]);
+    let user: User = mapper
+        .create_returning("returning_user.create", &args)
+        .await
+        .unwrap();
+    assert_eq!(user.id, 42);
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[1].sql, "SELECT * FROM users WHERE id = ?");
+    assert_eq!(log[1].values(), [Value::I64(42)]);
+}
+
+#[tokio::test]
+async fn test_fallback_uses_returning_select() {
+    let (mapper, log) = mapper(false);
+    let args = HashMap::from([("name", "alice")]);
+    let _: User = mapper
+        .create_returning("returning_user.createWithSelect", &args)
+        .await
+        .unwrap();
+    let log = log.lock().unwrap();
+    assert!(log[1].sql.contains("WHERE user_id = ?"));
+    assert_eq!(log[1].values(), [Value::I64(42)]);
+}
+
+#[tokio::test]
+async fn test_joins_ambient_transaction() {
+    let (driver, log) = driver(false);
+    let mapper = Mapper::new(driver.clone());
+    let session = Session::new(driver);
+    let args = HashMap::from([("name", "alice")]);
+    let result: Result<(), DbError> = session
+        .transactional(Nested::Savepoint, async {
+            let _: User = mapper
+                .create_returning("returning_user.create", &args)
+                .await?;
+            Err(DbError::Query("abort".into()))
+        })
+        .await;
+    assert!(result.is_err());
+    // 插入与回读都在外层事务中执行，随事务一起回滚
+    let statements: Vec<String> = log
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|call| call.sql.clone())
+        .collect();
+    assert_eq!(statements.len(), 4);
+    assert_eq!(statements[0], "BEGIN");
+    assert!(statements[1].trim().starts_with("INSERT INTO users"));
+    assert_eq!(statements[2], "SELECT * FROM users WHERE id = ?");
+    assert_eq!(statements[3], "ROLLBACK");
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use uorm::mapper_loader;
+use uorm::tpl::{RenderOptions, set_render_options, test_render};
+use uorm::udbc::value::Value;
+
+const XML: &str = r#"<mapper namespace="cte">
+    <cte id="active_users">SELECT id FROM users WHERE status = #{status}</cte>
+    <cte id="recent_orders" refs="active_users">SELECT o.* FROM orders o JOIN active_users u ON u.id = o.user_id WHERE o.created_at > #{since}</cte>
+    <cte id="tree" recursive="true">SELECT id, parent_id FROM nodes WHERE id = #{root} UNION ALL SELECT n.id, n.parent_id FROM nodes n JOIN tree t ON n.parent_id = t.id</cte>
+    <cte id="loop_a" refs="loop_b">SELECT 1</cte>
+    <cte id="loop_b" refs="loop_a">SELECT 2</cte>
+    <select id="report"><with refs="recent_orders, active_users"/> SELECT COUNT(*) FROM recent_orders</select>
+    <select id="subtree"><with refs="tree, shared.regions"/> SELECT * FROM tree JOIN regions r ON r.id = tree.id</select>
+    <select id="cyclic"><with refs="loop_a"/> SELECT 1</select>
+    <select id="missing"><with refs="nope"/> SELECT 1</select>
+</mapper>"#;
+
+const SHARED: &str = r#"<mapper namespace="shared">
+    <cte id="regions">SELECT id FROM regions</cte>
+</mapper>"#;
+
+#[test]
+fn test_cte_fragments() {
+    mapper_loader::load_assets(vec![("mem://cte.xml", XML), ("mem://shared.xml", SHARED)]).unwrap();
+    set_render_options(RenderOptions {
+        normalize_whitespace: true,
+        ..Default::default()
+    });
+    let args = HashMap::from([
+        ("status", Value::I32(1)),
+        ("since", Value::Str("2024-01-01".into())),
+        ("root", Value::I64(7)),
+    ]);
+
+    // 依赖排在前面，重复引用只展开一次，参数按出现顺序绑定
+    let rendered = test_render("cte.report", &args).unwrap();
+    assert_eq!(
+        rendered.sql,
+        "WITH active_users AS (SELECT id FROM users WHERE status = ?), \
+         recent_orders AS (SELECT o.* FROM orders o JOIN active_users u ON u.id = o.user_id \
+         WHERE o.created_at > ?) SELECT COUNT(*) FROM recent_orders"
+    );
+    let names: Vec<&str> = rendered.params.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, ["status", "since"]);
+
+    // 递归片段与其他命名空间中的片段
+    let rendered = test_render("cte.subtree", &args).unwrap();
+    assert!(
+        rendered
+            .sql
+            .starts_with("WITH RECURSIVE tree AS (SELECT id"),
+        "{}",
+        rendered.sql
+    );
+    assert!(
+        rendered
+            .sql
+            .contains("), regions AS (SELECT id FROM regions) SELECT *"),
+        "{}",
+        rendered.sql
+    );
+
+    let err = test_render("cte.cyclic", &args).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("cte.loop_a -> cte.loop_b -> cte.loop_a"),
+        "{}",
+        err
+    );
+    let err = test_render("cte.missing", &args).unwrap_err();
+    assert!(
+        err.to_string().contains("<cte> 'cte.nope' not found"),
+        "{}",
+        err
+    );
+}
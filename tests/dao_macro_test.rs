@@ -0,0 +1,93 @@
+mod common;
+
+use common::{MockDriver, row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::error::DbError;
+use uorm::executor::mapper::Mapper;
+use uorm::mapper_loader;
+use uorm::udbc::value::Value;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct User {
+    id: i64,
+    name: String,
+}
+
+uorm::dao!("tests/resources/mapper/dao_user.xml");
+uorm::dao!("tests/resources/mapper/dao_user.xml", name = "AccountDao");
+
+/// 仅用于检查生成的方法签名
+#[allow(dead_code)]
+async fn signatures(dao: &impl DaoUserDao) -> Result<(), DbError> {
+    let _: Vec<User> = dao.find_by_id(&1).await?;
+    let _: Vec<User> = dao.list_by_name(&HashMap::from([("name", "a")])).await?;
+    let _: Vec<User> = dao.list_active(&true).await?;
+    let _: i64 = dao
+        .insert_user(&User {
+            id: 0,
+            name: "a".into(),
+        })
+        .await?;
+    let _: u64 = dao
+        .rename(&User {
+            id: 1,
+            name: "b".into(),
+        })
+        .await?;
+    let _: u64 = dao.delete_by_id(&1).await?;
+    Ok(())
+}
+
+#[test]
+fn test_dao_requires_registered_db() {
+    let err = DaoUserDaoImpl::from_db("dao_macro_test").err().unwrap();
+    assert!(err.to_string().contains("dao_macro_test"), "{}", err);
+    assert!(AccountDaoImpl::from_db("dao_macro_test").is_err());
+}
+
+#[tokio::test]
+async fn test_scalar_parameter_is_bound_by_name() {
+    mapper_loader::load_assets(vec![(
+        "dao_user.xml",
+        include_str!("resources/mapper/dao_user.xml"),
+    )])
+    .unwrap();
+    let driver = MockDriver::new("dao_mock").with_rows(vec![row([
+        ("id", Value::I64(7)),
+        ("name", Value::Str("alice".into())),
+    ])]);
+    let log = driver.log();
+    let dao = DaoUserDaoImpl::new(Mapper::new(Arc::new(driver)));
+
+    let users: Vec<User> = dao.find_by_id(&7).await.unwrap();
+    assert_eq!(users[0].name, "alice");
+    assert_eq!(dao.delete_by_id(&7).await.unwrap(), 1);
+    // 仅出现在 <if test> 中的参数名同样用于包装标量参数
+    dao.list_active(&true).await.unwrap();
+    dao.list_active(&false).await.unwrap();
+
+    let log: Vec<_> = common::take(&log)
+        .into_iter()
+        .map(|call| (call.sql.trim().to_string(), call.values()))
+        .collect();
+    assert_eq!(
+        log,
+        [
+            (
+                "SELECT id, name FROM users WHERE id = ?".to_string(),
+                vec![Value::I64(7)]
+            ),
+            (
+                "DELETE FROM users WHERE id = ?".to_string(),
+                vec![Value::I64(7)]
+            ),
+            (
+                "SELECT id, name FROM users WHERE active = 1".to_string(),
+                vec![]
+            ),
+            ("SELECT id, name FROM users".to_string(), vec![]),
+        ]
+    );
+}
@@ -0,0 +1,62 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use uorm::driver_manager::UORM;
+use uorm::error::DbError;
+use uorm::udbc::connection::Connection;
+use uorm::udbc::driver::Driver;
+
+/// 记录创建时传入的 URL
+struct UrlDriver {
+    name: String,
+    url: String,
+}
+
+#[async_trait]
+impl Driver for UrlDriver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn r#type(&self) -> &str {
+        &self.url
+    }
+
+    fn placeholder(&self, _seq: usize, _name: &str) -> String {
+        "?".to_string()
+    }
+
+    async fn connection(&self) -> Result<Arc<dyn Connection>, DbError> {
+        Err(DbError::NotImplemented)
+    }
+
+    async fn close(&self) -> Result<(), DbError> {
+        Ok(())
+    }
+}
+
+fn url_factory(name: &str, url: &str) -> Result<Arc<dyn Driver>, DbError> {
+    Ok(Arc::new(UrlDriver {
+        name: name.to_string(),
+        url: url.to_string(),
+    }))
+}
+
+uorm::register_driver_factory!("snowflake", url_factory);
+
+#[test]
+fn test_register_url_resolves_custom_scheme() {
+    UORM.register_url("warehouse", "Snowflake://account/db")
+        .unwrap();
+    let driver = UORM.driver("warehouse").unwrap();
+    assert_eq!(driver.name(), "warehouse");
+    assert_eq!(driver.r#type(), "Snowflake://account/db");
+
+    assert!(matches!(
+        UORM.register_url("other", "oracle://db"),
+        Err(DbError::UnsupportedDatabaseType(_))
+    ));
+    assert!(matches!(
+        UORM.register_url("other", "not a url"),
+        Err(DbError::InvalidDatabaseUrl(_))
+    ));
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use common::{MockDriver, row};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uorm::driver_manager::DriverManager;
+use uorm::error::DbError;
+use uorm::events::{EventListener, FailoverEvent, add_listener, remove_listeners};
+use uorm::udbc::driver::Driver;
+use uorm::udbc::failover::{FailoverDriver, FailoverOptions};
+use uorm::udbc::value::Value;
+
+#[derive(Clone, Copy)]
+enum Behavior {
+    Healthy,
+    Exhausted,
+    Refused,
+}
+
+/// 查询 `missing` 表时报错
+fn mock(name: &str, behavior: Behavior) -> MockDriver {
+    MockDriver::new(name)
+        .with_query(|call| {
+            if call.sql.contains("missing") {
+                return Err(DbError::Database(
+                    "table 'missing' doesn't exist".to_string(),
+                ));
+            }
+            Ok(vec![row([("n", Value::I64(1))])])
+        })
+        .with_connect(move |_| async move {
+            match behavior {
+                Behavior::Healthy => Ok(()),
+                Behavior::Exhausted => {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    Err(DbError::Timeout("no connection available".to_string()))
+                }
+                Behavior::Refused => Err(DbError::Connection("connection refused".to_string())),
+            }
+        })
+}
+
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<String>>,
+}
+
+impl Recorder {
+    fn push(&self, event: String) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}
+
+impl EventListener for Recorder {
+    fn connection_opened(&self, pool: &str) {
+        self.push(format!("opened {}", pool));
+    }
+
+    fn connection_closed(&self, pool: &str) {
+        self.push(format!("closed {}", pool));
+    }
+
+    fn acquire_timeout(&self, pool: &str, waited: Duration) {
+        assert!(waited >= Duration::from_millis(20));
+        self.push(format!("timeout {}", pool));
+    }
+
+    fn query_error(&self, pool: &str, sql: &str, error: &DbError) {
+        self.push(format!("error {} {}: {}", pool, sql, error));
+    }
+
+    fn failover(&self, event: &FailoverEvent) {
+        self.push(format!(
+            "failover {} {} -> {}",
+            event.pool, event.from, event.to
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_connection_and_query_events() {
+    let manager = DriverManager::new();
+    manager
+        .register(mock("listened", Behavior::Healthy))
+        .unwrap();
+    let session = manager.session("listened").unwrap();
+
+    // 注册监听器之前不触发
+    session.count("SELECT COUNT(*) FROM t", &()).await.unwrap();
+    let recorder = Arc::new(Recorder::default());
+    add_listener("listened", recorder.clone());
+    add_listener("other", Arc::new(Recorder::default()));
+    assert!(recorder.take().is_empty());
+
+    session.count("SELECT COUNT(*) FROM t", &()).await.unwrap();
+    assert_eq!(recorder.take(), ["opened listened", "closed listened"]);
+
+    session
+        .count("SELECT COUNT(*) FROM missing", &())
+        .await
+        .unwrap_err();
+    let events = recorder.take();
+    assert_eq!(events.len(), 3);
+    assert!(events[1].starts_with("error listened SELECT COUNT(*) FROM missing: "));
+
+    remove_listeners("listened");
+    session.count("SELECT COUNT(*) FROM t", &()).await.unwrap();
+    assert!(recorder.take().is_empty());
+}
+
+#[tokio::test]
+async fn test_acquire_timeout_event() {
+    let recorder = Arc::new(Recorder::default());
+    add_listener("exhausted", recorder.clone());
+    let manager = DriverManager::new();
+    manager
+        .register(mock("exhausted", Behavior::Exhausted))
+        .unwrap();
+    let err = manager
+        .session("exhausted")
+        .unwrap()
+        .count("SELECT COUNT(*) FROM t", &())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DbError::Timeout(_)));
+    assert_eq!(recorder.take(), ["timeout exhausted"]);
+}
+
+#[tokio::test]
+async fn test_failover_event() {
+    let recorder = Arc::new(Recorder::default());
+    add_listener("replicated", recorder.clone());
+    let host = |host: &str, behavior| {
+        let driver: Arc<dyn Driver> = Arc::new(mock("replicated", behavior));
+        (host.to_string(), driver)
+    };
+    let driver = FailoverDriver::new(
+        "replicated",
+        vec![
+            host("db-a", Behavior::Refused),
+            host("db-b", Behavior::Healthy),
+        ],
+        FailoverOptions::default(),
+    )
+    .unwrap();
+    let manager = DriverManager::new();
+    manager.register(driver).unwrap();
+    manager
+        .session("replicated")
+        .unwrap()
+        .count("SELECT COUNT(*) FROM t", &())
+        .await
+        .unwrap();
+    assert_eq!(
+        recorder.take(),
+        [
+            "failover replicated db-a -> db-b",
+            "opened replicated",
+            "closed replicated"
+        ]
+    );
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use common::{MockDriver, row};
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use uorm::driver_manager::{DriverManager, register_driver_factory};
+use uorm::error::DbError;
+use uorm::events::subscribe_failover;
+use uorm::udbc::driver::Driver;
+use uorm::udbc::value::Value;
+
+/// 当前不可达的主机
+static DOWN: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+fn set_down(host: &str, down: bool) {
+    let mut hosts = DOWN.lock().unwrap();
+    if down {
+        hosts.insert(host.to_string());
+    } else {
+        hosts.remove(host);
+    }
+}
+
+/// `failmock://host/db`，故障切换参数应已被去掉
+fn factory(name: &str, url: &str) -> Result<Arc<dyn Driver>, DbError> {
+    assert!(
+        !url.contains("failover") && !url.contains("failback"),
+        "{}",
+        url
+    );
+    let host = url
+        .trim_start_matches("failmock://")
+        .split('/')
+        .next()
+        .unwrap();
+    // 主机不可达时连接失败，查询返回所在主机
+    let down = host.to_string();
+    let driver = MockDriver::new(name)
+        .with_rows(vec![row([("host", Value::Str(host.to_string()))])])
+        .with_connect(move |_| {
+            let refused = DOWN.lock().unwrap().contains(&down);
+            let error = format!("{}: connection refused", down);
+            async move {
+                if refused {
+                    return Err(DbError::Connection(error));
+                }
+                Ok(())
+            }
+        });
+    Ok(Arc::new(driver))
+}
+
+fn setup(name: &str, url: &str) -> Arc<dyn Driver> {
+    register_driver_factory("failmock", factory);
+    let manager = DriverManager::new();
+    manager.register_url(name, url).unwrap();
+    manager.driver(name).unwrap()
+}
+
+async fn host_of(driver: &Arc<dyn Driver>) -> String {
+    let rows = driver
+        .connection()
+        .await
+        .unwrap()
+        .query("", &[])
+        .await
+        .unwrap();
+    match &rows[0]["host"] {
+        Value::Str(host) => host.clone(),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_sequential_failover() {
+    let mut events = subscribe_failover();
+    let driver = setup("orders", "failmock://seq-a,seq-b,seq-c/db");
+    assert_eq!(host_of(&driver).await, "seq-a");
+
+    set_down("seq-a", true);
+    set_down("seq-b", true);
+    assert_eq!(host_of(&driver).await, "seq-c");
+    let event = loop {
+        let event = events.recv().await.unwrap();
+        if event.pool == "orders" {
+            break event;
+        }
+    };
+    assert_eq!((event.from.as_str(), event.to.as_str()), ("seq-a", "seq-c"));
+    assert!(event.reason.unwrap().contains("seq-b: connection refused"));
+
+    // 原主机恢复后仍停留在切换后的主机
+    set_down("seq-a", false);
+    assert_eq!(host_of(&driver).await, "seq-c");
+
+    set_down("seq-c", true);
+    assert_eq!(host_of(&driver).await, "seq-a");
+
+    set_down("seq-a", true);
+    let err = driver.connection().await.err().unwrap();
+    assert!(err.is_connection_lost(), "{}", err);
+}
+
+#[tokio::test]
+async fn test_primary_first_failback() {
+    let driver = setup(
+        "billing",
+        "failmock://pf-a,pf-b/db?failover=primary-first&failback_after=50ms",
+    );
+    set_down("pf-a", true);
+    assert_eq!(host_of(&driver).await, "pf-b");
+
+    // 间隔未到不尝试切回
+    set_down("pf-a", false);
+    assert_eq!(host_of(&driver).await, "pf-b");
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(host_of(&driver).await, "pf-a");
+}
+
+#[test]
+fn test_invalid_failover_params() {
+    register_driver_factory("failmock", factory);
+    let manager = DriverManager::new();
+    assert!(
+        manager
+            .register_url("bad", "failmock://a,b/db?failover=random")
+            .is_err()
+    );
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{MockDriver, row};
+use std::time::Duration;
+use uorm::driver_manager::DriverManager;
+use uorm::error::DbError;
+use uorm::health::{self, HealthOptions, Status};
+use uorm::udbc::value::Value;
+
+#[derive(Clone, Copy)]
+enum Behavior {
+    Healthy,
+    Refused,
+    Hanging,
+}
+
+fn register(manager: &DriverManager, name: &'static str, behavior: Behavior) {
+    let driver = MockDriver::new(name)
+        .with_query(|call| {
+            assert_eq!(call.sql, "SELECT 1");
+            Ok(vec![row([("1", Value::I64(1))])])
+        })
+        .with_connect(move |_| async move {
+            match behavior {
+                Behavior::Healthy => Ok(()),
+                Behavior::Refused => Err(DbError::Connection("connection refused".to_string())),
+                Behavior::Hanging => std::future::pending().await,
+            }
+        });
+    manager.register(driver).unwrap();
+}
+
+#[tokio::test]
+async fn test_health_report() {
+    let options = HealthOptions {
+        ping_timeout: Duration::from_millis(50),
+    };
+    let manager = DriverManager::new();
+    let report = health::report_with(&manager, &options).await;
+    assert!(report.is_up());
+    assert!(report.pools.is_empty());
+
+    register(&manager, "primary", Behavior::Healthy);
+    let report = health::report_with(&manager, &options).await;
+    assert!(report.is_up());
+    assert!(report.pools[0].latency_ms.is_some());
+
+    register(&manager, "replica", Behavior::Refused);
+    register(&manager, "archive", Behavior::Hanging);
+    let report = health::report_with(&manager, &options).await;
+    assert_eq!(report.status, Status::Degraded);
+    let names: Vec<_> = report.pools.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["archive", "primary", "replica"]);
+    assert_eq!(report.pools[0].status, Status::Down);
+    assert_eq!(report.pools[0].latency_ms, None);
+    assert!(
+        report.pools[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("timed out")
+    );
+    assert_eq!(report.pools[1].status, Status::Up);
+    assert!(
+        report.pools[2]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("connection refused")
+    );
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["status"], "degraded");
+    assert_eq!(json["pools"][1]["type"], "mock");
+    assert_eq!(json["pools"][1]["status"], "up");
+    assert!(json["mappers"]["digest"].is_string());
+    assert!(json["template_cache"]["capacity"].as_u64().unwrap() > 0);
+}
@@ -0,0 +1,287 @@
+mod common;
+
+use common::{Call, Log, MockDriver, row};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Once};
+use uorm::error::DbError;
+use uorm::executor::mapper::Mapper;
+use uorm::executor::session::{Nested, Session};
+use uorm::mapper_loader;
+use uorm::udbc::driver::Driver;
+use uorm::udbc::value::Value;
+
+/// 模拟的表数据：幂等登记与订单，事务与保存点回滚时恢复快照
+#[derive(Clone, Default)]
+struct Tables {
+    keys: HashMap<(String, String), Option<i64>>,
+    orders: Vec<i64>,
+    status: i64,
+}
+
+#[derive(Default)]
+struct Db {
+    tables: Tables,
+    snapshots: Vec<Tables>,
+}
+
+type Shared = Arc<Mutex<Db>>;
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::Str(s) => s.clone(),
+        other => panic!("expected string, got {:?}", other),
+    }
+}
+
+fn duplicate() -> DbError {
+    DbError::Backend {
+        message: "Duplicate entry for key 'PRIMARY'".to_string(),
+        code: Some(1062),
+        sql_state: Some("23000".to_string()),
+        source: "duplicate".into(),
+        deadlock: None,
+    }
+}
+
+/// 语句的前三个词
+fn verb(sql: &str) -> String {
+    sql.split_whitespace().take(3).collect::<Vec<_>>().join(" ")
+}
+
+fn key(call: &Call) -> (String, String) {
+    (text(call.arg("sql_id")), text(call.arg("key")))
+}
+
+fn transaction(db: &Shared, call: &Call) {
+    let mut db = db.lock().unwrap();
+    match call.sql.as_str() {
+        "BEGIN" => {
+            let snapshot = db.tables.clone();
+            db.snapshots.push(snapshot);
+        }
+        "COMMIT" => {
+            db.snapshots.pop();
+        }
+        "ROLLBACK" => {
+            db.tables = db.snapshots.pop().unwrap();
+        }
+        _ => {}
+    }
+}
+
+fn query(db: &Shared, call: &Call) -> Result<Vec<common::Row>, DbError> {
+    let db = db.lock().unwrap();
+    assert!(
+        call.sql
+            .starts_with("SELECT COALESCE(result, 0) AS result FROM uorm_idempotency")
+    );
+    Ok(db
+        .tables
+        .keys
+        .get(&key(call))
+        .map(|result| row([("result", Value::I64(result.unwrap_or(0)))]))
+        .into_iter()
+        .collect())
+}
+
+fn execute(db: &Shared, call: &Call) -> Result<u64, DbError> {
+    let mut db = db.lock().unwrap();
+    match verb(&call.sql).as_str() {
+        "CREATE TABLE IF" => Ok(0),
+        "INSERT INTO uorm_idempotency" => {
+            let key = key(call);
+            if db.tables.keys.contains_key(&key) {
+                return Err(duplicate());
+            }
+            db.tables.keys.insert(key, None);
+            Ok(1)
+        }
+        "UPDATE uorm_idempotency SET" => {
+            let Value::I64(result) = call.arg("result") else {
+                panic!("result must be an integer");
+            };
+            *db.tables.keys.get_mut(&key(call)).unwrap() = Some(*result);
+            Ok(1)
+        }
+        "INSERT INTO orders" => {
+            if *call.arg("amount") == Value::I32(-1) {
+                return Err(DbError::Database("amount must be positive".to_string()));
+            }
+            let id = db.tables.orders.len() as i64 + 1;
+            db.tables.orders.push(id);
+            Ok(1)
+        }
+        "UPDATE orders SET" => {
+            db.tables.status += 1;
+            Ok(db.tables.orders.len() as u64)
+        }
+        v if v.starts_with("SAVEPOINT ") => {
+            let snapshot = db.tables.clone();
+            db.snapshots.push(snapshot);
+            Ok(0)
+        }
+        v if v.starts_with("RELEASE SAVEPOINT ") => {
+            db.snapshots.pop();
+            Ok(0)
+        }
+        "ROLLBACK TO SAVEPOINT" => {
+            db.tables = db.snapshots.pop().unwrap();
+            Ok(0)
+        }
+        other => panic!("unexpected statement: {}", other),
+    }
+}
+
+/// 更新与事务语句的前三个词
+fn verbs(log: &Log) -> Vec<String> {
+    log.lock()
+        .unwrap()
+        .iter()
+        .filter(|c| !c.sql.starts_with("SELECT"))
+        .map(|c| verb(&c.sql))
+        .collect()
+}
+
+#[derive(Serialize)]
+struct NewOrder<'a> {
+    request_id: Option<&'a str>,
+    amount: i32,
+}
+
+fn order(request_id: &str, amount: i32) -> NewOrder<'_> {
+    NewOrder {
+        request_id: Some(request_id),
+        amount,
+    }
+}
+
+const XML: &str = r#"<mapper namespace="orders">
+    <insert id="create" useGeneratedKeys="true" idempotentKey="request_id">INSERT INTO orders (amount) VALUES (#{amount})</insert>
+    <update id="settle" idempotent-key="request_id">UPDATE orders SET status = 'settled'</update>
+</mapper>"#;
+
+/// 登记表按连接池名只创建一次，各测试使用不同的连接池名
+fn setup(name: &'static str) -> (Arc<dyn Driver>, Shared, Log) {
+    static LOAD: Once = Once::new();
+    LOAD.call_once(|| mapper_loader::load_assets(vec![("mem://orders.xml", XML)]).unwrap());
+    let db = Shared::default();
+    let (tx, q, ex, id) = (db.clone(), db.clone(), db.clone(), db.clone());
+    let driver = MockDriver::new(name)
+        .with_callback(move |call| transaction(&tx, call))
+        .with_query(move |call| query(&q, call))
+        .with_execute(move |call| execute(&ex, call))
+        .with_last_insert_id(move |_| id.lock().unwrap().tables.orders.len() as u64);
+    let log = driver.log();
+    (Arc::new(driver), db, log)
+}
+
+#[tokio::test]
+async fn test_idempotent_insert() {
+    let (driver, db, log) = setup("orders");
+    let mapper = Mapper::new(driver);
+
+    let id: i64 = mapper
+        .create("orders.create", &order("r1", 10))
+        .await
+        .unwrap();
+    assert_eq!(id, 1);
+    // 重复投递返回首次生成的主键，不再插入
+    let id: i64 = mapper
+        .create("orders.create", &order("r1", 10))
+        .await
+        .unwrap();
+    assert_eq!(id, 1);
+    assert_eq!(db.lock().unwrap().tables.orders, [1]);
+
+    // 语句失败时登记一并回滚，可用同一键重试
+    assert!(
+        mapper
+            .create::<i64, _>("orders.create", &order("r2", -1))
+            .await
+            .is_err()
+    );
+    let id: i64 = mapper
+        .create("orders.create", &order("r2", 20))
+        .await
+        .unwrap();
+    assert_eq!(id, 2);
+
+    let affected = mapper
+        .update("orders.settle", &order("s1", 0))
+        .await
+        .unwrap();
+    assert_eq!(affected, 2);
+    assert_eq!(
+        mapper
+            .update("orders.settle", &order("s1", 0))
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(db.lock().unwrap().tables.status, 1);
+
+    let missing = NewOrder {
+        request_id: None,
+        amount: 1,
+    };
+    let err = mapper.update("orders.settle", &missing).await.unwrap_err();
+    assert!(
+        err.to_string().contains("idempotency key 'request_id'"),
+        "{}",
+        err
+    );
+
+    let log = verbs(&log);
+    // 登记表只创建一次；重复投递只尝试登记并回滚
+    assert_eq!(log.iter().filter(|s| s.starts_with("CREATE")).count(), 1);
+    assert_eq!(
+        &log[1..9],
+        [
+            "BEGIN",
+            "INSERT INTO uorm_idempotency",
+            "INSERT INTO orders",
+            "UPDATE uorm_idempotency SET",
+            "COMMIT",
+            "BEGIN",
+            "INSERT INTO uorm_idempotency",
+            "ROLLBACK",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_idempotent_insert_in_transaction() {
+    let (driver, db, log) = setup("orders_tx");
+    let mapper = Mapper::new(driver.clone());
+    let session = Session::new(driver);
+
+    let ids = session
+        .transactional(Nested::Join, async {
+            let first: i64 = mapper.create("orders.create", &order("t1", 5)).await?;
+            let second: i64 = mapper.create("orders.create", &order("t1", 5)).await?;
+            Ok::<_, DbError>((first, second))
+        })
+        .await
+        .unwrap();
+    assert_eq!(ids, (1, 1));
+
+    assert_eq!(db.lock().unwrap().tables.orders, [1]);
+    // 外层事务中以保存点隔离登记冲突，外层事务照常提交；登记表在单独的连接上创建
+    assert_eq!(
+        verbs(&log),
+        [
+            "BEGIN",
+            "CREATE TABLE IF",
+            "SAVEPOINT uorm_sp_1",
+            "INSERT INTO uorm_idempotency",
+            "INSERT INTO orders",
+            "UPDATE uorm_idempotency SET",
+            "RELEASE SAVEPOINT uorm_sp_1",
+            "SAVEPOINT uorm_sp_2",
+            "INSERT INTO uorm_idempotency",
+            "ROLLBACK TO SAVEPOINT",
+            "COMMIT",
+        ]
+    );
+}
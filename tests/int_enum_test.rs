@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uorm::IntEnum;
+use uorm::udbc::deserializer::RowDeserializer;
+use uorm::udbc::serializer::to_value;
+use uorm::udbc::value::Value;
+
+#[derive(Debug, PartialEq, IntEnum)]
+#[repr(u8)]
+enum Status {
+    Active = 1,
+    Disabled = 2,
+    Deleted = 9,
+}
+
+#[derive(Debug, PartialEq, IntEnum)]
+#[repr(i32)]
+enum Level {
+    Low = -1,
+    Normal,
+    High,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Account {
+    id: i64,
+    status: Status,
+    level: Level,
+}
+
+fn row(cols: &[(&str, Value)]) -> HashMap<String, Value> {
+    cols.iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+}
+
+#[test]
+fn test_int_enum_binds_repr() {
+    let account = Account {
+        id: 7,
+        status: Status::Deleted,
+        level: Level::High,
+    };
+    let Value::Map(map) = to_value(&account) else {
+        panic!("expected map");
+    };
+    assert_eq!(map["status"], Value::U8(9));
+    assert_eq!(map["level"], Value::I32(1));
+    assert_eq!(to_value(&Level::Low), Value::I32(-1));
+}
+
+#[test]
+fn test_int_enum_reads_codes() {
+    let account = Account::deserialize(RowDeserializer::new(&row(&[
+        ("id", Value::I64(7)),
+        ("status", Value::I64(2)),
+        ("level", Value::Str("0".to_string())),
+    ])))
+    .unwrap();
+    assert_eq!(
+        account,
+        Account {
+            id: 7,
+            status: Status::Disabled,
+            level: Level::Normal,
+        }
+    );
+
+    let err = Account::deserialize(RowDeserializer::new(&row(&[
+        ("id", Value::I64(7)),
+        ("status", Value::U8(3)),
+        ("level", Value::I32(1)),
+    ])))
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("unknown Status code: 3"),
+        "{}",
+        err
+    );
+}
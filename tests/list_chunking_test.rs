@@ -0,0 +1,171 @@
+mod common;
+
+use common::{Log, MockDriver, row};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::error::DbError;
+use uorm::executor::session::Session;
+use uorm::tpl::{RenderOptions, set_render_options};
+use uorm::udbc::value::Value;
+
+/// 查询为每个绑定参数返回一行，更新返回绑定参数个数；更新 `broken` 表时绑定 3 的语句失败
+fn session() -> (Session, Log) {
+    let driver = MockDriver::new("mock")
+        .with_query(|call| {
+            Ok(call
+                .values()
+                .into_iter()
+                .map(|v| row([("id", v)]))
+                .collect())
+        })
+        .with_execute(|call| {
+            if call.sql.contains("broken") && call.values().contains(&Value::I64(3)) {
+                return Err(DbError::Query("broken".into()));
+            }
+            Ok(call.args.len() as u64)
+        });
+    let log = driver.log();
+    (Session::new(Arc::new(driver)), log)
+}
+
+#[derive(Serialize)]
+struct Filter {
+    ids: Vec<i64>,
+    status: Vec<i64>,
+}
+
+#[derive(Serialize)]
+struct Update {
+    ids: Vec<i64>,
+    deleted_at: Option<String>,
+    score: Decimal,
+}
+
+fn take_statements(log: &Log) -> Vec<usize> {
+    take_args(log).iter().map(Vec::len).collect()
+}
+
+/// 每条语句绑定的参数
+fn take_args(log: &Log) -> Vec<Vec<Value>> {
+    take(log).0
+}
+
+/// 取出每条语句绑定的参数，以及事务的开始、提交与回滚
+fn take(log: &Log) -> (Vec<Vec<Value>>, Vec<String>) {
+    let (transactions, statements): (Vec<_>, Vec<_>) = common::take(log)
+        .into_iter()
+        .partition(|call| matches!(call.sql.as_str(), "BEGIN" | "COMMIT" | "ROLLBACK"));
+    (
+        statements.iter().map(|call| call.values()).collect(),
+        transactions.into_iter().map(|call| call.sql).collect(),
+    )
+}
+
+#[tokio::test]
+async fn test_oversized_lists_are_chunked() {
+    set_render_options(RenderOptions {
+        max_list_params: Some(2),
+        ..Default::default()
+    });
+    let (session, log) = session();
+
+    // 自动展开的 IN 列表：5 个元素切分为 2 + 2 + 1，结果按顺序拼接
+    let ids: Vec<i64> = (1..=5).collect();
+    let rows: Vec<HashMap<String, i64>> = session
+        .query(
+            "SELECT id FROM users WHERE id IN #{ids}",
+            &HashMap::from([("ids", ids.clone())]),
+        )
+        .await
+        .unwrap();
+    let got: Vec<i64> = rows.iter().map(|r| r["id"]).collect();
+    assert_eq!(got, ids);
+    assert_eq!(take_statements(&log), [2, 2, 1]);
+
+    // `<for>` 集合同样切分，其余参数在每条语句中保持不变；影响行数累加
+    let sql = r#"DELETE FROM users WHERE status IN #{status} AND id IN <for item="id" collection="ids" open="(" sep="," close=")">#{id}</for>"#;
+    let filter = Filter {
+        ids: vec![1, 2, 3],
+        status: vec![7],
+    };
+    assert_eq!(session.execute(sql, &filter).await.unwrap(), 5);
+    assert_eq!(take_statements(&log), [3, 2]);
+
+    // 未超限时不切分
+    let filter = Filter {
+        ids: vec![1, 2],
+        status: vec![7],
+    };
+    assert_eq!(session.execute(sql, &filter).await.unwrap(), 3);
+    assert_eq!(take_statements(&log), [3]);
+
+    // 多个列表同时超限无法切分
+    let filter = Filter {
+        ids: vec![1, 2, 3],
+        status: vec![7, 8, 9],
+    };
+    let err = session.execute(sql, &filter).await.unwrap_err();
+    assert!(err.to_string().contains("cannot split"), "{}", err);
+    assert!(take_statements(&log).is_empty());
+
+    // 切分后其余参数与不切分时绑定相同的取值
+    let args = Update {
+        ids: vec![1, 2, 3],
+        deleted_at: None,
+        score: Decimal::new(125, 1),
+    };
+    let sql = "UPDATE users SET deleted_at = #{deleted_at}, score = #{score} WHERE id IN #{ids}";
+    assert_eq!(session.execute(sql, &args).await.unwrap(), 7);
+    let statements = take_args(&log);
+    assert_eq!(statements.len(), 2);
+    set_render_options(RenderOptions::default());
+    session.execute(sql, &args).await.unwrap();
+    let whole = take_args(&log).remove(0);
+    assert_eq!(statements[0], whole[..4]);
+    assert_eq!(
+        statements[1],
+        [whole[0].clone(), whole[1].clone(), Value::I64(3)]
+    );
+    assert_eq!(statements[1][0], Value::Null);
+
+    // 结果不能按组拼接的语句不切分
+    let args = HashMap::from([("ids", vec![1i64, 2, 3])]);
+    set_render_options(RenderOptions {
+        max_list_params: Some(2),
+        ..Default::default()
+    });
+    for sql in [
+        "DELETE FROM users WHERE id NOT IN #{ids}",
+        "SELECT COUNT(*) AS id FROM users WHERE id IN #{ids}",
+        "SELECT id FROM users WHERE id IN #{ids} ORDER BY id DESC LIMIT 2",
+        "SELECT status AS id FROM users WHERE id IN #{ids} GROUP BY status",
+    ] {
+        let err = session.execute(sql, &args).await.unwrap_err();
+        assert!(err.to_string().contains("cannot split"), "{}: {}", sql, err);
+    }
+    assert!(take_statements(&log).is_empty());
+
+    // 字符串与注释中的关键字不影响切分
+    let sql = "UPDATE users SET note = 'NOT IN stock' /* ORDER BY */ WHERE id IN #{ids}";
+    assert_eq!(session.execute(sql, &args).await.unwrap(), 3);
+    let (statements, transactions) = take(&log);
+    assert_eq!(statements.iter().map(Vec::len).collect::<Vec<_>>(), [2, 1]);
+    assert_eq!(transactions, ["BEGIN", "COMMIT"]);
+
+    // 切分出的更新在一个事务中执行，后面的组失败时前面的组一并回滚
+    let err = session
+        .execute("DELETE FROM broken WHERE id IN #{ids}", &args)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("broken"));
+    let (statements, transactions) = take(&log);
+    assert_eq!(
+        statements,
+        [vec![Value::I64(1), Value::I64(2)], vec![Value::I64(3)]]
+    );
+    assert_eq!(transactions, ["BEGIN", "ROLLBACK"]);
+
+    set_render_options(RenderOptions::default());
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::{MockDriver, row};
+use serde::{Deserialize, Serialize};
+use uorm::driver_manager::UORM;
+use uorm::error::DbError;
+use uorm::executor::mapper::named_args;
+use uorm::mapper_loader;
+use uorm::sql;
+use uorm::udbc::value::Value;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct User {
+    id: i64,
+    name: String,
+}
+
+impl User {
+    #[sql("user.findById")]
+    async fn find_by_id(id: i64) -> Result<Option<Self>, DbError>;
+
+    #[sql("user.getById", db = "sql_macro_test")]
+    pub async fn get_by_id(id: i64) -> Result<Self, DbError>;
+
+    #[sql("user.list")]
+    async fn list_by_name(name: &str, limit: u32) -> Result<Vec<Self>, DbError>;
+
+    #[sql("user.update")]
+    async fn update(&self) -> Result<u64, DbError>;
+
+    #[sql("user.touch", op = "update")]
+    async fn touch(user: &User) -> Result<(), DbError>;
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Account {
+    id: i64,
+    name: String,
+}
+
+impl Account {
+    #[sql("sql_account.getById", db = "sql_macro_mock")]
+    async fn get_by_id(id: i64) -> Result<Self, DbError>;
+
+    #[sql("sql_account.rename", db = "sql_macro_mock")]
+    async fn rename(&self, name: &str) -> Result<u64, DbError>;
+}
+
+const ACCOUNT_MAPPER: &str = r#"<mapper namespace="sql_account">
+    <select id="getById">SELECT id, name FROM accounts WHERE id = #{id}</select>
+    <update id="rename">UPDATE accounts SET name = #{name} WHERE id = #{id}</update>
+</mapper>"#;
+
+#[tokio::test]
+async fn test_sql_methods_bind_args_and_receiver() {
+    mapper_loader::load_assets(vec![("sql_account.xml", ACCOUNT_MAPPER)]).unwrap();
+    let driver = MockDriver::new("sql_macro_mock").with_rows(vec![row([
+        ("id", Value::I64(3)),
+        ("name", Value::from("a")),
+    ])]);
+    let log = driver.log();
+    UORM.register(driver).unwrap();
+
+    let account = Account::get_by_id(3).await.unwrap();
+    assert_eq!(
+        account,
+        Account {
+            id: 3,
+            name: "a".into()
+        }
+    );
+    let call = common::take(&log).remove(0);
+    assert_eq!(
+        call.sql.trim(),
+        "SELECT id, name FROM accounts WHERE id = ?"
+    );
+    assert_eq!(call.values(), [Value::I64(3)]);
+
+    // `self` 的字段作为根对象，同名参数覆盖字段
+    assert_eq!(account.rename("b").await.unwrap(), 1);
+    let call = common::take(&log).remove(0);
+    assert_eq!(call.sql.trim(), "UPDATE accounts SET name = ? WHERE id = ?");
+    assert_eq!(call.values(), [Value::from("b"), Value::I64(3)]);
+}
+
+#[tokio::test]
+async fn test_sql_methods_resolve_self() {
+    // 未注册数据库时返回明确的错误，而不是 panic
+    let err = User::find_by_id(1).await.unwrap_err();
+    assert!(err.to_string().contains("default"), "{}", err);
+    let err = User::get_by_id(1).await.unwrap_err();
+    assert!(err.to_string().contains("sql_macro_test"), "{}", err);
+    assert!(User::list_by_name("a", 10).await.is_err());
+
+    let user = User {
+        id: 1,
+        name: "a".into(),
+    };
+    assert!(user.update().await.is_err());
+    assert!(User::touch(&user).await.is_err());
+}
+
+#[test]
+fn test_named_args() {
+    let single = named_args(vec![(
+        "user",
+        uorm::udbc::serializer::to_value(&User {
+            id: 7,
+            name: "x".into(),
+        }),
+    )]);
+    match single {
+        Value::Map(m) => assert_eq!(m.get("id"), Some(&Value::I64(7))),
+        _ => panic!("expected map"),
+    }
+
+    let multi = named_args(vec![("id", Value::I64(1)), ("name", Value::from("a"))]);
+    match multi {
+        Value::Map(m) => {
+            assert_eq!(m.get("id"), Some(&Value::I64(1)));
+            assert_eq!(m.get("name"), Some(&Value::from("a")));
+        }
+        _ => panic!("expected map"),
+    }
+}
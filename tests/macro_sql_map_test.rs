@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use uorm::mapper_loader::{self, StatementKind};
+use uorm::sql_map;
+use uorm::tpl::test_render;
+
+sql_map! {
+    namespace sql_map_user;
+    select find_by_id = "SELECT * FROM users WHERE id = #{id}";
+    select search = r#"SELECT * FROM users WHERE 1 = 1<if test="age != null"> AND age > #{age}</if>"#;
+    insert create = "INSERT INTO users(name) VALUES (#{name})";
+    delete remove = "DELETE FROM users WHERE id = #{id}";
+}
+
+sql_map! {
+    namespace "app.audit";
+    update mark = "UPDATE audit SET note = ']]>' WHERE id = #{id}";
+}
+
+#[test]
+fn test_sql_map_registers_statements() {
+    let find = mapper_loader::find_mapper("sql_map_user.find_by_id", "mysql").unwrap();
+    assert_eq!(find.kind, StatementKind::Select);
+    assert_eq!(
+        find.content.as_deref().unwrap().trim(),
+        "SELECT * FROM users WHERE id = #{id}"
+    );
+    let create = mapper_loader::find_mapper("sql_map_user.create", "mysql").unwrap();
+    assert_eq!(create.kind, StatementKind::Insert);
+    let remove = mapper_loader::find_mapper("sql_map_user.remove", "mysql").unwrap();
+    assert_eq!(remove.kind, StatementKind::Delete);
+
+    // 内容中的 `<`、`>` 与动态标签原样保留为模板
+    let rendered = test_render("sql_map_user.search", &HashMap::from([("age", 18)])).unwrap();
+    assert_eq!(
+        rendered.sql.trim(),
+        "SELECT * FROM users WHERE 1 = 1 AND age > ?"
+    );
+
+    let mark = mapper_loader::find_mapper("app.audit.mark", "mysql").unwrap();
+    assert_eq!(mark.kind, StatementKind::Update);
+    assert!(mark.content.as_deref().unwrap().contains("note = ']]>'"));
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use uorm::mapper_loader;
+use uorm::tpl::test_render;
+
+const XML: &str = r#"<mapper namespace="cdata">
+    <select id="mixed">SELECT * FROM t WHERE a <![CDATA[<]]> b AND c &lt;= #{c} <!-- 注释 --> AND d &amp; 1 = 1</select>
+    <select id="wrapped"><![CDATA[
+        SELECT * FROM t
+        WHERE a < #{a}
+        <if test="b != null">AND b <> #{b}</if>
+    ]]></select>
+</mapper>"#;
+
+#[test]
+fn test_cdata_and_entities_in_content() {
+    mapper_loader::load_assets(vec![("mem://cdata.xml", XML)]).unwrap();
+
+    let mixed = mapper_loader::find_mapper("cdata.mixed", "").unwrap();
+    assert_eq!(
+        mixed.content.as_deref(),
+        Some("SELECT * FROM t WHERE a < b AND c <= #{c}  AND d & 1 = 1")
+    );
+    let rendered = test_render("cdata.mixed", &HashMap::from([("c", 1)])).unwrap();
+    assert_eq!(
+        rendered.sql,
+        "SELECT * FROM t WHERE a < b AND c <= ?  AND d & 1 = 1"
+    );
+
+    // CDATA 中的 `<` 比较与动态标签同时存在
+    let rendered = test_render("cdata.wrapped", &HashMap::from([("a", 1), ("b", 2)])).unwrap();
+    let sql: Vec<&str> = rendered.sql.split_whitespace().collect();
+    assert_eq!(sql.join(" "), "SELECT * FROM t WHERE a < ? AND b <> ?");
+    assert_eq!(rendered.params.len(), 2);
+}
+
+#[test]
+fn test_escaped_template_syntax() {
+    const ESCAPED: &str = r##"<mapper namespace="escaped">
+        <select id="json">SELECT JSON_EXTRACT(doc, '$.a') FROM t WHERE note = '\#{x}' AND tag = '\&lt;if test="a"&gt;' AND id = #{id}</select>
+        <select id="block">SELECT * FROM t WHERE doc = '&lt;![RAW[{"k": "#{v}"}]]&gt;' AND id = #{id}</select>
+    </mapper>"##;
+    mapper_loader::load_assets(vec![("mem://escaped.xml", ESCAPED)]).unwrap();
+
+    let rendered = test_render("escaped.json", &HashMap::from([("id", 1)])).unwrap();
+    assert_eq!(
+        rendered.sql,
+        r#"SELECT JSON_EXTRACT(doc, '$.a') FROM t WHERE note = '#{x}' AND tag = '<if test="a">' AND id = ?"#
+    );
+    assert_eq!(rendered.params.len(), 1);
+
+    let rendered = test_render("escaped.block", &HashMap::from([("id", 1)])).unwrap();
+    assert_eq!(
+        rendered.sql,
+        r##"SELECT * FROM t WHERE doc = '{"k": "#{v}"}' AND id = ?"##
+    );
+    assert_eq!(rendered.params.len(), 1);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{MockDriver, row};
+use serde::Deserialize;
+use std::sync::{Arc, Mutex, Once};
+use uorm::driver_manager::UORM;
+use uorm::error::DbError;
+use uorm::executor::mapper::Mapper;
+use uorm::mapper_loader;
+use uorm::sql;
+use uorm::udbc::value::Value;
+
+/// 记录每条语句实际执行所在的连接池
+static EXECUTED: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// 名为 `name` 的连接池，查询返回连接池名
+fn pool(name: &str) -> MockDriver {
+    let (recorded, returned) = (name.to_string(), name.to_string());
+    MockDriver::new(name)
+        .with_rows(vec![row([("pool", Value::Str(returned))])])
+        .with_callback(move |call| {
+            EXECUTED
+                .lock()
+                .unwrap()
+                .push((recorded.clone(), call.sql.trim().to_string()));
+        })
+}
+
+const XML: &str = r#"<mapper namespace="account" datasource="account_db">
+    <select id="byId">
+        SELECT pool FROM account WHERE id = #{id}
+    </select>
+    <update id="touch">
+        UPDATE account SET seen = 1
+    </update>
+    <select id="audit" route="audit_db">
+        SELECT pool FROM audit
+    </select>
+</mapper>"#;
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    pool: String,
+}
+
+struct AccountDao;
+
+impl AccountDao {
+    #[sql("account.byId")]
+    async fn by_id(id: i64) -> Result<Row, DbError>;
+}
+
+fn setup() {
+    static LOADED: Once = Once::new();
+    LOADED.call_once(|| {
+        mapper_loader::load_assets(vec![("account.xml", XML)]).unwrap();
+        for name in ["account_db", "audit_db"] {
+            UORM.register(pool(name)).unwrap();
+        }
+    });
+}
+
+fn executed_on(sql: &str) -> Vec<String> {
+    EXECUTED
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, s)| s.starts_with(sql))
+        .map(|(pool, _)| pool.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_namespace_datasource() {
+    setup();
+    assert_eq!(
+        mapper_loader::statement_datasource("account.byId").as_deref(),
+        Some("account_db")
+    );
+    assert_eq!(
+        mapper_loader::statement_datasource("account.audit").as_deref(),
+        Some("audit_db")
+    );
+
+    // 不传数据库名时按命名空间绑定的连接池执行
+    let row = AccountDao::by_id(1).await.unwrap();
+    assert_eq!(row.pool, "account_db");
+    let mapper = UORM.mapper_for("account.touch").unwrap();
+    assert_eq!(mapper.update("account.touch", &()).await.unwrap(), 1);
+
+    // 即使 Mapper 构造在其他连接池上，语句仍路由到绑定的连接池；语句级 route 优先
+    let other = Mapper::new(Arc::new(pool("other_db")));
+    other.update("account.touch", &()).await.unwrap();
+    let rows: Vec<Row> = other.list("account.audit", &()).await.unwrap();
+    assert_eq!(rows[0].pool, "audit_db");
+
+    assert_eq!(executed_on("UPDATE account"), ["account_db", "account_db"]);
+    assert_eq!(executed_on("SELECT pool FROM audit"), ["audit_db"]);
+}
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use uorm::mapper_loader;
+use uorm::tpl::test_render;
+
+const XML: &str = r#"<mapper namespace="dyn_tags">
+    <select id="search">
+        SELECT * FROM users WHERE 1 = 1
+        <if test="age &gt; 18">AND age &gt;= #{age}</if>
+        <if test="ids != null">AND id IN <for item="id" collection="ids" open="(" sep="," close=")">#{id}</for></if>
+        ORDER BY id
+    </select>
+    <insert id="create">
+        INSERT INTO orders (customer) VALUES (#{customer})<if test="note != null"> -- #{note}</if>
+        <insert id="lines" collection="lines" item="line">
+            INSERT INTO order_line (sku) VALUES (<if test="line != null">#{line}</if>)
+        </insert>
+    </insert>
+</mapper>"#;
+
+#[test]
+fn test_dynamic_tags_survive_loading() {
+    mapper_loader::load_assets(vec![("mem://dyn_tags.xml", XML)]).unwrap();
+
+    let search = mapper_loader::find_mapper("dyn_tags.search", "").unwrap();
+    let content = search.content.as_deref().unwrap();
+    assert!(content.contains(r#"<if test="age > 18">AND age >= #{age}</if>"#));
+    assert!(content.trim_end().ends_with("ORDER BY id"));
+
+    let args = HashMap::from([("age", vec![30]), ("ids", vec![1, 2])]);
+    let rendered = test_render("dyn_tags.search", &args).unwrap();
+    let sql: Vec<&str> = rendered.sql.split_whitespace().collect();
+    assert_eq!(
+        sql.join(" "),
+        "SELECT * FROM users WHERE 1 = 1 AND id IN (?,?) ORDER BY id"
+    );
+
+    let create = mapper_loader::find_mapper("dyn_tags.create", "").unwrap();
+    assert_eq!(
+        create.content.as_deref().map(str::trim),
+        Some(
+            r#"INSERT INTO orders (customer) VALUES (#{customer})<if test="note != null"> -- #{note}</if>"#
+        )
+    );
+    assert_eq!(create.chained.len(), 1);
+    assert!(
+        create.chained[0]
+            .mapper
+            .content
+            .as_deref()
+            .unwrap()
+            .contains(r#"(<if test="line != null">#{line}</if>)"#)
+    );
+}
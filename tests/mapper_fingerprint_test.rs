@@ -0,0 +1,46 @@
+use uorm::mapper_loader;
+
+#[test]
+fn test_fingerprint_is_order_independent() {
+    let a = (
+        "mem://a.xml",
+        r#"<mapper namespace="fp_a" version="3"><select id="x">SELECT 1</select></mapper>"#,
+    );
+    let b = (
+        "mem://b.xml",
+        r#"<mapper namespace="fp_b"><select id="y">SELECT 2</select></mapper>"#,
+    );
+
+    mapper_loader::load_assets(vec![a, b]).unwrap();
+    let first = mapper_loader::fingerprint();
+    assert_eq!(first.namespaces, 2);
+    assert_eq!(first.statements, 2);
+    assert_eq!(first.sources.len(), 2);
+    assert_eq!(first.sources[0].source, "mem://a.xml");
+    assert_eq!(first.sources[0].version.as_deref(), Some("3"));
+
+    mapper_loader::clear_mappers().unwrap();
+    mapper_loader::load_assets(vec![b, a]).unwrap();
+    let second = mapper_loader::fingerprint();
+    assert_eq!(first, second);
+
+    // 内容变化会改变指纹
+    let changed = vec![(
+        "mem://b.xml".to_string(),
+        r#"<mapper namespace="fp_b"><select id="y">SELECT 3</select></mapper>"#.to_string(),
+    )];
+    mapper_loader::replace_namespaces(&changed).unwrap();
+    let third = mapper_loader::fingerprint();
+    assert_ne!(second.digest, third.digest);
+    assert_eq!(third.sources.len(), 2);
+
+    // 只改属性（如超时、缓存键）同样改变指纹
+    let changed = vec![(
+        "mem://b.xml".to_string(),
+        r#"<mapper namespace="fp_b"><select id="y" timeout="5" cacheKey="y">SELECT 3</select></mapper>"#
+            .to_string(),
+    )];
+    mapper_loader::replace_namespaces(&changed).unwrap();
+    let fourth = mapper_loader::fingerprint();
+    assert_ne!(third.digest, fourth.digest);
+}
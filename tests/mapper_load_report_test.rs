@@ -0,0 +1,45 @@
+use uorm::mapper_loader;
+
+#[test]
+fn test_bad_mapper_does_not_block_others() {
+    let good = r#"<mapper namespace="report_good"><select id="get">SELECT 1</select></mapper>"#;
+    let malformed = "<mapper namespace=\"report_bad\">\n  <select id=\"a\">SELECT 1\n</mapper>";
+    let missing_id = "<mapper namespace=\"report_noid\">\n  <select id=\"ok\">SELECT 1</select>\n  <update>UPDATE t</update>\n</mapper>";
+    let duplicate = "<mapper namespace=\"report_dup\">\n  <select id=\"x\">SELECT 1</select>\n    <select id=\"x\">SELECT 2</select>\n</mapper>";
+
+    let report = mapper_loader::load_assets_with_report(vec![
+        ("bad.xml", malformed),
+        ("noid.xml", missing_id),
+        ("dup.xml", duplicate),
+        ("good.xml", good),
+    ]);
+    assert!(!report.is_ok());
+    assert_eq!(report.loaded, vec!["good.xml"]);
+    assert!(mapper_loader::find_mapper("report_good.get", "").is_some());
+
+    let [bad, noid, dup] = &report.errors[..] else {
+        panic!("{:?}", report.errors);
+    };
+    assert_eq!((bad.source.as_str(), bad.line), ("bad.xml", Some(3)));
+
+    assert_eq!((noid.line, noid.column), (Some(3), Some(3)));
+    assert!(noid.message.contains("id"), "{}", noid);
+
+    assert_eq!((dup.line, dup.column), (Some(3), Some(5)));
+    assert_eq!(dup.element.as_deref(), Some(r#"<select id="x">"#));
+    assert!(
+        dup.to_string()
+            .starts_with("dup.xml:3:5: 发现重复的 ID: 'x'")
+    );
+    // 出错的文件整体不生效
+    assert!(mapper_loader::find_mapper("report_dup.x", "").is_none());
+    assert!(mapper_loader::find_mapper("report_noid.ok", "").is_none());
+
+    let err = report.into_result().unwrap_err().to_string();
+    assert!(err.starts_with("3 个 mapper 文件加载失败"), "{}", err);
+
+    // 与已加载的语句重复同样报错
+    let again = mapper_loader::load_assets_with_report(vec![("good2.xml", good)]);
+    assert_eq!(again.errors[0].line, Some(1));
+    assert!(mapper_loader::load_assets(vec![("good3.xml", good)]).is_err());
+}
@@ -0,0 +1,43 @@
+use uorm::mapper_loader;
+
+#[test]
+fn test_reload_namespace() {
+    mapper_loader::load_assets(vec![
+        (
+            "mem://orders.xml",
+            r#"<mapper namespace="orders"><select id="get">SELECT 1</select><select id="old">SELECT 0</select></mapper>"#,
+        ),
+        (
+            "mem://items.xml",
+            r#"<mapper namespace="items"><select id="get">SELECT 'items'</select></mapper>"#,
+        ),
+    ])
+    .unwrap();
+
+    mapper_loader::reload(
+        "orders",
+        r#"<mapper namespace="orders"><select id="get">SELECT 2</select></mapper>"#,
+    )
+    .unwrap();
+    let m = mapper_loader::find_mapper("orders.get", "mysql").unwrap();
+    assert_eq!(m.content.as_deref(), Some("SELECT 2"));
+    // 被替换的命名空间中已删除的语句不再可见
+    assert!(mapper_loader::find_mapper("orders.old", "mysql").is_none());
+    // 其他命名空间不受影响
+    assert!(mapper_loader::find_mapper("items.get", "mysql").is_some());
+
+    // 解析失败时回滚（保持原有定义）
+    assert!(mapper_loader::reload("orders", "<mapper namespace=\"orders\"><select").is_err());
+    // 命名空间不匹配时拒绝
+    assert!(
+        mapper_loader::reload(
+            "orders",
+            r#"<mapper namespace="items"><select id="get">SELECT 3</select></mapper>"#,
+        )
+        .is_err()
+    );
+    let m = mapper_loader::find_mapper("orders.get", "mysql").unwrap();
+    assert_eq!(m.content.as_deref(), Some("SELECT 2"));
+    let m = mapper_loader::find_mapper("items.get", "mysql").unwrap();
+    assert_eq!(m.content.as_deref(), Some("SELECT 'items'"));
+}
@@ -0,0 +1,78 @@
+use serde::Serialize;
+use uorm::mapper_loader;
+use uorm::tpl::{NestedParams, RenderOptions, set_render_options, test_render};
+use uorm::udbc::value::Value;
+
+#[derive(Serialize)]
+struct Profile {
+    city: String,
+    tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct NewUser {
+    name: String,
+    profile: Profile,
+}
+
+const XML: &str = r#"<mapper namespace="nested">
+    <insert id="save">INSERT INTO users (name, profile) VALUES (#{name}, #{profile})</insert>
+    <select id="pairs">SELECT * FROM t WHERE tags IN #{tags}</select>
+</mapper>"#;
+
+fn user() -> NewUser {
+    NewUser {
+        name: "alice".into(),
+        profile: Profile {
+            city: "sh".into(),
+            tags: vec!["a".into()],
+        },
+    }
+}
+
+#[test]
+fn test_nested_params() {
+    mapper_loader::load_assets(vec![("mem://nested.xml", XML)]).unwrap();
+
+    // 默认按 JSON 文本绑定
+    let rendered = test_render("nested.save", &user()).unwrap();
+    assert_eq!(
+        rendered.params,
+        [
+            ("name".to_string(), Value::Str("alice".into())),
+            (
+                "profile".to_string(),
+                Value::Str(r#"{"city":"sh","tags":["a"]}"#.into())
+            ),
+        ]
+    );
+    // 顶层列表照常展开，其中的列表按 JSON 绑定
+    let args = serde_json::json!({"tags": [["a", "b"], "c"]});
+    let rendered = test_render("nested.pairs", &args).unwrap();
+    assert_eq!(rendered.sql, "SELECT * FROM t WHERE tags IN (?, ?)");
+    assert_eq!(
+        rendered.params,
+        [
+            ("tags[0]".to_string(), Value::Str(r#"["a","b"]"#.into())),
+            ("tags[1]".to_string(), Value::Str("c".into())),
+        ]
+    );
+
+    set_render_options(RenderOptions {
+        nested_params: NestedParams::Reject,
+        ..Default::default()
+    });
+    let err = test_render("nested.save", &user()).unwrap_err();
+    assert!(
+        err.to_string().contains("parameter 'profile' is a map"),
+        "{}",
+        err
+    );
+    let err = test_render("nested.pairs", &args).unwrap_err();
+    assert!(
+        err.to_string().contains("parameter 'tags[0]' is a list"),
+        "{}",
+        err
+    );
+    set_render_options(RenderOptions::default());
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::{Log, MockDriver};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::error::DbError;
+use uorm::executor::session::Session;
+use uorm::udbc::driver::{PacketLimit, payload_size};
+use uorm::udbc::value::Value;
+
+fn session(split_lists: bool) -> (Session, Log) {
+    let limit = PacketLimit {
+        max_bytes: 512,
+        split_lists,
+    };
+    let driver = MockDriver::new("packet")
+        .with_packet_limit(limit)
+        .with_query(|call| {
+            Ok(call
+                .values()
+                .into_iter()
+                .map(|v| common::row([("id", v)]))
+                .collect())
+        })
+        .with_execute(|call| Ok(call.args.len() as u64));
+    let log = driver.log();
+    (Session::new(Arc::new(driver)), log)
+}
+
+const DELETE: &str = "DELETE FROM t WHERE id IN #{ids}";
+
+#[tokio::test]
+async fn test_oversized_statement_is_rejected() {
+    let (session, log) = session(false);
+    let ids: Vec<i64> = (0..200).collect();
+    let args = HashMap::from([("ids", ids)]);
+    let err = session.execute(DELETE, &args).await.unwrap_err();
+    assert!(
+        matches!(err, DbError::PacketTooLarge { limit: 512, size } if size > 512),
+        "{}",
+        err
+    );
+    assert!(log.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_oversized_list_is_split() {
+    let (session, log) = session(true);
+    let ids: Vec<i64> = (0..200).collect();
+    let args = HashMap::from([("ids", ids.clone())]);
+    assert_eq!(session.execute(DELETE, &args).await.unwrap(), 200);
+
+    // 切分出的语句在同一事务中执行
+    let calls = common::take(&log);
+    assert_eq!(calls.first().unwrap().sql, "BEGIN");
+    assert_eq!(calls.last().unwrap().sql, "COMMIT");
+    let statements = &calls[1..calls.len() - 1];
+    assert!(statements.len() > 1);
+    let mut bound = Vec::new();
+    for call in statements {
+        assert!(payload_size(&call.sql, &call.args) <= 512);
+        bound.extend(call.values());
+    }
+    assert_eq!(bound, ids.into_iter().map(Value::I64).collect::<Vec<_>>());
+
+    // 查询结果按顺序拼接
+    let rows: Vec<HashMap<String, i64>> = session
+        .query("SELECT id FROM t WHERE id IN #{ids}", &args)
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 200);
+
+    // 没有列表参数可切分时照常报错
+    let args = HashMap::from([("body", "x".repeat(1024))]);
+    let err = session
+        .execute("INSERT INTO t (body) VALUES (#{body})", &args)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DbError::PacketTooLarge { .. }), "{}", err);
+}
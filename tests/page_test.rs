@@ -0,0 +1,104 @@
+mod common;
+
+use common::{Log, MockDriver, id_rows, row, take_sql};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uorm::executor::mapper::Mapper;
+use uorm::mapper_loader;
+use uorm::udbc::value::Value;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct User {
+    id: i64,
+}
+
+#[derive(Serialize)]
+struct Filter {
+    status: String,
+}
+
+const XML: &str = r#"<mapper namespace="page">
+    <select id="list">SELECT u.id, u.name FROM users u JOIN teams t ON t.id = u.team_id WHERE u.status = #{status} ORDER BY u.created_at DESC</select>
+    <select id="joined">SELECT DISTINCT u.id FROM users u JOIN roles r ON r.user_id = u.id WHERE u.status = #{status}</select>
+    <select id="joined.count">SELECT COUNT(*) FROM users u WHERE u.status = #{status} AND EXISTS (SELECT 1 FROM roles r WHERE r.user_id = u.id)</select>
+</mapper>"#;
+
+/// 计数查询返回 42，其他查询返回 `rows` 行
+fn mapper(rows: usize) -> (Mapper, Log) {
+    let driver = MockDriver::new("page").with_query(move |call| {
+        if call.sql.contains("COUNT(*)") {
+            return Ok(vec![row([("c", Value::I64(42))])]);
+        }
+        Ok(id_rows(rows))
+    });
+    let log = driver.log();
+    (Mapper::new(Arc::new(driver)), log)
+}
+
+fn filter() -> Filter {
+    Filter {
+        status: "active".into(),
+    }
+}
+
+#[tokio::test]
+async fn test_page() {
+    mapper_loader::load_assets(vec![("mem://page.xml", XML)]).unwrap();
+
+    // 首页未取满，总数即取到的行数
+    let (m, log) = mapper(3);
+    let page = m
+        .page::<User, _>("page.list", &filter(), 1, 10)
+        .await
+        .unwrap();
+    assert_eq!((page.total, page.pages(), page.items.len()), (3, 1, 3));
+    assert_eq!(log.lock().unwrap().len(), 1);
+
+    // 推导的计数语句去掉排序并改写选择列表
+    let (m, log) = mapper(2);
+    let page = m
+        .page::<User, _>("page.list", &filter(), 2, 2)
+        .await
+        .unwrap();
+    assert_eq!(page.items, [User { id: 0 }, User { id: 1 }]);
+    assert_eq!((page.total, page.page, page.size), (42, 2, 2));
+    assert!(page.has_next());
+    let log = take_sql(&log);
+    assert!(
+        log[0].ends_with("ORDER BY u.created_at DESC LIMIT 2 OFFSET 2"),
+        "{}",
+        log[0]
+    );
+    assert_eq!(
+        log[1],
+        "SELECT COUNT(*) FROM users u JOIN teams t ON t.id = u.team_id WHERE u.status = ?"
+    );
+
+    // 伴随的计数语句优先
+    let (m, log) = mapper(2);
+    let page = m
+        .page::<User, _>("page.joined", &filter(), 1, 2)
+        .await
+        .unwrap();
+    assert_eq!(page.total, 42);
+    assert!(
+        log.lock().unwrap()[1]
+            .sql
+            .contains("EXISTS (SELECT 1 FROM roles r")
+    );
+
+    // 超出末页时仍需计数
+    let (m, log) = mapper(0);
+    let page = m
+        .page::<User, _>("page.list", &filter(), 9, 10)
+        .await
+        .unwrap();
+    assert_eq!((page.total, page.items.len()), (42, 0));
+    assert_eq!(log.lock().unwrap().len(), 2);
+
+    let err = m
+        .page::<User, _>("page.list", &filter(), 1, 0)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("page size"), "{}", err);
+}
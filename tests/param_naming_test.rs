@@ -0,0 +1,83 @@
+mod common;
+
+use common::{Log, MockDriver};
+use serde::Serialize;
+use std::sync::Arc;
+use uorm::executor::mapper::Mapper;
+use uorm::executor::options::QueryOptions;
+use uorm::executor::session::Session;
+use uorm::mapper_loader;
+use uorm::tpl::ParamNaming;
+use uorm::udbc::driver::Driver;
+use uorm::udbc::value::Value;
+
+#[derive(Serialize)]
+struct Rename {
+    user_id: i64,
+    #[serde(rename = "displayName")]
+    display_name: String,
+}
+
+fn rename() -> Rename {
+    Rename {
+        user_id: 7,
+        display_name: "alice".into(),
+    }
+}
+
+const XML: &str = r#"<mapper namespace="naming">
+    <update id="rename">UPDATE users SET display_name = #{display_name} WHERE id = #{userId}</update>
+    <update id="exact" paramNaming="exact">UPDATE users SET display_name = #{display_name} WHERE id = #{userId}</update>
+</mapper>"#;
+
+fn driver(naming: Option<ParamNaming>) -> (Arc<dyn Driver>, Log) {
+    let driver = MockDriver::new("naming").with_query_options(QueryOptions {
+        param_naming: naming,
+        ..Default::default()
+    });
+    let log = driver.log();
+    (Arc::new(driver), log)
+}
+
+fn bound(log: &Log) -> Vec<Value> {
+    log.lock().unwrap().pop().unwrap().values()
+}
+
+#[tokio::test]
+async fn test_param_naming_layers() {
+    mapper_loader::load_assets(vec![("mem://naming.xml", XML)]).unwrap();
+    let resolved = [Value::Str("alice".into()), Value::I64(7)];
+    let unresolved = [Value::Null, Value::Null];
+
+    // 连接池设置为宽松匹配
+    let (pool, log) = driver(Some(ParamNaming::Relaxed));
+    let mapper = Mapper::new(pool.clone());
+    mapper.update("naming.rename", &rename()).await.unwrap();
+    assert_eq!(bound(&log), resolved);
+
+    // 语句属性覆盖连接池设置
+    mapper.update("naming.exact", &rename()).await.unwrap();
+    assert_eq!(bound(&log), unresolved);
+
+    // 单次调用覆盖语句属性
+    let relaxed = QueryOptions {
+        param_naming: Some(ParamNaming::Relaxed),
+        ..Default::default()
+    };
+    mapper
+        .with_options(relaxed)
+        .update("naming.exact", &rename())
+        .await
+        .unwrap();
+    assert_eq!(bound(&log), resolved);
+
+    // 默认按原名匹配；Session 可单独设置
+    let (pool, log) = driver(None);
+    let sql = "UPDATE users SET display_name = #{display_name} WHERE id = #{userId}";
+    let session = Session::new(pool.clone());
+    session.execute(sql, &rename()).await.unwrap();
+    assert_eq!(bound(&log), unresolved);
+    let session = Session::new(pool).with_param_naming(Some(ParamNaming::Relaxed));
+    session.execute(sql, &rename()).await.unwrap();
+    assert_eq!(bound(&log), resolved);
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::{MockDriver, row};
+use serde::Deserialize;
+use std::sync::Arc;
+use uorm::executor::session::Session;
+use uorm::udbc::value::Value;
+
+#[derive(Deserialize)]
+struct Row {
+    conn: i64,
+}
+
+#[tokio::test]
+async fn test_pinned_session_reuses_connection() {
+    // 查询返回执行该语句的连接编号
+    let driver = MockDriver::new("numbered")
+        .with_query(|call| Ok(vec![row([("conn", Value::I64(call.conn as i64))])]))
+        .with_last_insert_id(|conn| conn as u64);
+    let log = driver.log();
+    let session = Session::new(Arc::new(driver));
+
+    let pinned = session.pinned().await.unwrap();
+    pinned
+        .execute("CREATE TEMPORARY TABLE tmp (id INT)", &())
+        .await
+        .unwrap();
+    let row: Option<Row> = pinned.find("SELECT 1 AS conn", &()).await.unwrap();
+    let id = pinned.last_insert_id().await.unwrap();
+    assert_eq!(row.unwrap().conn as u64, id);
+
+    // 普通会话每条语句各取一个连接
+    session.execute("SELECT 1", &()).await.unwrap();
+    let conns: Vec<u64> = log.lock().unwrap().iter().map(|c| c.conn as u64).collect();
+    assert_eq!(conns, vec![id, id, id + 1]);
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{MockDriver, Row, row};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use uorm::mapper_loader;
+use uorm::planguard::{self, PlanCase};
+use uorm::udbc::value::Value;
+
+fn plan(table: &str, access: &str, key: Option<&str>) -> Row {
+    row([
+        ("table", Value::Str(table.to_string())),
+        ("type", Value::Str(access.to_string())),
+        (
+            "key",
+            key.map_or(Value::Null, |k| Value::Str(k.to_string())),
+        ),
+    ])
+}
+
+/// `index_dropped` 为 true 时 `users` 表的索引失效，计划退化为全表扫描
+fn explain_driver(index_dropped: Arc<AtomicBool>) -> MockDriver {
+    MockDriver::new("explain")
+        .with_type("mysql")
+        .with_query(move |call| {
+            let users = if index_dropped.load(Ordering::SeqCst) {
+                plan("users", "ALL", None)
+            } else {
+                plan("users", "ref", Some("idx_email"))
+            };
+            Ok(if call.sql.contains("JOIN") {
+                vec![users, plan("audit", "ALL", None)]
+            } else {
+                vec![users]
+            })
+        })
+}
+
+const XML: &str = r#"<mapper namespace="plan_user">
+    <select id="by_email">SELECT * FROM users WHERE email = #{email}</select>
+    <select id="with_audit">SELECT * FROM users u JOIN audit a ON a.user_id = u.id WHERE u.email = #{email}</select>
+</mapper>"#;
+
+#[tokio::test]
+async fn test_plan_regression_detected() {
+    mapper_loader::load_assets(vec![("plan_user.xml", XML)]).unwrap();
+    let path = std::env::temp_dir().join(format!("uorm_plans_{}.json", std::process::id()));
+    let args = HashMap::from([("email", "a@b.c")]);
+    let cases = vec![
+        PlanCase::new("plan_user.by_email", &args),
+        PlanCase::new("plan_user.with_audit", &args),
+    ];
+
+    let index_dropped = Arc::new(AtomicBool::new(false));
+    let driver = explain_driver(index_dropped.clone());
+    let explained = driver.log();
+    let baseline = planguard::record(&driver, &cases, &path).await.unwrap();
+    assert_eq!(baseline.plans.len(), 2);
+    assert_eq!(
+        explained.lock().unwrap()[0].sql,
+        "EXPLAIN SELECT * FROM users WHERE email = ?"
+    );
+
+    // 计划未变化；`audit` 在基线中已是全表扫描，不算退化
+    let report = planguard::check(&driver, &cases, &path).await.unwrap();
+    assert!(report.is_ok(), "{}", report);
+
+    index_dropped.store(true, Ordering::SeqCst);
+    let mut cases = cases;
+    cases.push(PlanCase::new("plan_user.by_email_new", &args));
+    mapper_loader::load_assets(vec![(
+        "plan_user_new.xml",
+        r#"<mapper namespace="plan_user"><select id="by_email_new">SELECT id FROM users WHERE email = #{email}</select></mapper>"#,
+    )])
+    .unwrap();
+    let report = planguard::check(&driver, &cases, &path).await.unwrap();
+    assert!(!report.is_ok());
+    let ids: Vec<&str> = report
+        .regressions
+        .iter()
+        .map(|r| r.sql_id.as_str())
+        .collect();
+    assert_eq!(ids, ["plan_user.by_email", "plan_user.with_audit"]);
+    assert_eq!(report.regressions[1].tables, ["users"]);
+    assert_eq!(report.unrecorded, ["plan_user.by_email_new"]);
+    let text = report.to_string();
+    assert!(
+        text.contains("plan_user.by_email: full table scan on users (baseline [users:ref(idx_email)], now [users:ALL])"),
+        "{}",
+        text
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use uorm::driver_manager::DriverManager;
+use uorm::error::DbError;
+use uorm::udbc::connection::Connection;
+use uorm::udbc::driver::{Driver, Maintenance};
+
+struct MockDriver {
+    interval: Option<Duration>,
+    runs: Arc<AtomicU64>,
+}
+
+#[async_trait]
+impl Driver for MockDriver {
+    fn name(&self) -> &str {
+        "keepalive"
+    }
+
+    fn r#type(&self) -> &str {
+        "mock"
+    }
+
+    fn placeholder(&self, _seq: usize, _name: &str) -> String {
+        "?".to_string()
+    }
+
+    async fn connection(&self) -> Result<Arc<dyn Connection>, DbError> {
+        Err(DbError::Database("no connections".to_string()))
+    }
+
+    async fn close(&self) -> Result<(), DbError> {
+        Ok(())
+    }
+
+    fn keepalive_interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    async fn maintain(&self) -> Result<Maintenance, DbError> {
+        self.runs.fetch_add(1, Ordering::SeqCst);
+        Ok(Maintenance {
+            alive: 2,
+            ..Default::default()
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_keepalive_runs_until_pool_replaced() {
+    let manager = DriverManager::new();
+    let runs = Arc::new(AtomicU64::new(0));
+    manager
+        .register(MockDriver {
+            interval: Some(Duration::from_millis(20)),
+            runs: runs.clone(),
+        })
+        .unwrap();
+
+    // 注册时不立即执行
+    assert_eq!(runs.load(Ordering::SeqCst), 0);
+    tokio::time::sleep(Duration::from_millis(110)).await;
+    assert!(runs.load(Ordering::SeqCst) >= 3);
+
+    // 替换后旧连接池被释放，维护任务退出
+    manager
+        .register(MockDriver {
+            interval: None,
+            runs: Arc::new(AtomicU64::new(0)),
+        })
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    let stopped = runs.load(Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), stopped);
+}
@@ -0,0 +1,10 @@
+use uorm::prelude::*;
+
+#[test]
+fn test_prelude_exports() {
+    assert!(UORM.session("prelude_missing").is_none());
+    let err: DbError = DbError::Query("missing".into());
+    assert!(err.to_string().contains("missing"));
+    assert_eq!(Value::from("a"), Value::Str("a".into()));
+    assert_eq!(QueryOptions::default().max_rows, None);
+}
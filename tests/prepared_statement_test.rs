@@ -0,0 +1,95 @@
+mod common;
+
+use common::{Log, MockDriver, row};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uorm::executor::session::Session;
+use uorm::mapper_loader;
+use uorm::udbc::value::Value;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Row {
+    id: i64,
+}
+
+#[derive(Serialize)]
+struct Args {
+    status: String,
+    ids: Vec<i64>,
+}
+
+const XML: &str = r#"<mapper namespace="prepared">
+    <select id="by_status" maxRows="2">SELECT id FROM users WHERE status = #{status}</select>
+    <select id="by_ids">SELECT id FROM users WHERE id IN #{ids}</select>
+    <update id="touch">UPDATE users SET touched = 1 WHERE status = #{status}</update>
+</mapper>"#;
+
+fn args() -> Args {
+    Args {
+        status: "active".into(),
+        ids: vec![1, 2],
+    }
+}
+
+fn last(log: &Log) -> (String, Vec<Value>) {
+    let call = log.lock().unwrap().pop().unwrap();
+    (call.sql.clone(), call.values())
+}
+
+#[tokio::test]
+async fn test_prepared_statement() {
+    mapper_loader::load_assets(vec![("mem://prepared.xml", XML)]).unwrap();
+    let driver = MockDriver::new("prepared")
+        .with_dollar_placeholders()
+        .with_rows((1..=3).map(|id| row([("id", Value::I64(id))])).collect());
+    let log = driver.log();
+    let session = Session::new(Arc::new(driver));
+
+    let stmt = session.prepare("prepared.by_status").unwrap();
+    assert_eq!(stmt.sql_id(), "prepared.by_status");
+    for _ in 0..3 {
+        let rows: Vec<Row> = stmt.query(&args()).await.unwrap();
+        // 语句上的 maxRows 生效
+        assert_eq!(rows, [Row { id: 1 }, Row { id: 2 }]);
+        assert_eq!(
+            last(&log),
+            (
+                "SELECT id FROM users WHERE status = $1".to_string(),
+                vec![Value::Str("active".into())]
+            )
+        );
+    }
+
+    // 列表参数展开为多个占位符
+    let stmt = session.prepare("prepared.by_ids").unwrap();
+    let _: Vec<Row> = stmt.query(&args()).await.unwrap();
+    assert_eq!(
+        last(&log),
+        (
+            "SELECT id FROM users WHERE id IN ($1, $2)".to_string(),
+            vec![Value::I64(1), Value::I64(2)]
+        )
+    );
+
+    let stmt = session.prepare("prepared.touch").unwrap();
+    assert_eq!(stmt.execute(&args()).await.unwrap(), 1);
+    assert_eq!(
+        last(&log).0,
+        "UPDATE users SET touched = 1 WHERE status = $1"
+    );
+
+    // 重载后按新版本执行
+    mapper_loader::reload(
+        "prepared",
+        &XML.replace("touched = 1 WHERE", "touched = 2 WHERE"),
+    )
+    .unwrap();
+    stmt.execute(&args()).await.unwrap();
+    assert_eq!(
+        last(&log).0,
+        "UPDATE users SET touched = 2 WHERE status = $1"
+    );
+
+    let err = session.prepare("prepared.missing").err().unwrap();
+    assert!(err.to_string().contains("SQL ID not found"), "{}", err);
+}
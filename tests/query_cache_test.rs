@@ -0,0 +1,89 @@
+mod common;
+
+use common::{MockDriver, row};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::error::DbError;
+use uorm::executor::mapper::Mapper;
+use uorm::executor::session::{Nested, Session};
+use uorm::mapper_loader;
+use uorm::query_cache::{MemoryCacheBackend, set_cache_backend};
+use uorm::udbc::value::Value;
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: i64,
+    name: String,
+}
+
+const XML: &str = r#"<mapper namespace="cached_user">
+    <select id="getUser" cacheKey="user:#{id}" ttl="300">
+        SELECT id, name FROM users WHERE id = #{id}
+    </select>
+    <update id="rename" evicts="user:#{id}">
+        UPDATE users SET name = #{name} WHERE id = #{id}
+    </update>
+</mapper>"#;
+
+#[tokio::test]
+async fn test_read_through_and_evict() {
+    mapper_loader::load_assets(vec![("cached_user.xml", XML)]).unwrap();
+    let backend = Arc::new(MemoryCacheBackend::new());
+    set_cache_backend(backend.clone());
+
+    let driver = Arc::new(MockDriver::new("counting").with_query(|call| {
+        Ok(vec![row([
+            ("id", call.values()[0].clone()),
+            ("name", Value::Str("alice".into())),
+        ])])
+    }));
+    let log = driver.log();
+    let queries = || {
+        log.lock()
+            .unwrap()
+            .iter()
+            .filter(|c| c.sql.contains("SELECT"))
+            .count()
+    };
+    let mapper = Mapper::new(driver.clone());
+    let args = HashMap::from([("id", 7i64)]);
+
+    let user: User = mapper.get("cached_user.getUser", &args).await.unwrap();
+    assert_eq!((user.id, user.name.as_str()), (7, "alice"));
+    let _: User = mapper.get("cached_user.getUser", &args).await.unwrap();
+    assert_eq!(queries(), 1);
+    assert_eq!(backend.len(), 1);
+
+    let update = HashMap::from([("id", Value::I64(7)), ("name", Value::from("bob"))]);
+    mapper.update("cached_user.rename", &update).await.unwrap();
+    assert!(backend.is_empty());
+
+    let _: Vec<User> = mapper.list("cached_user.getUser", &args).await.unwrap();
+    assert_eq!(queries(), 2);
+    assert_eq!(backend.len(), 1);
+
+    // 事务中不读写缓存，失效推迟到提交之后
+    let session = Session::new(driver);
+    let rolled_back: Result<(), DbError> = session
+        .transactional(Nested::Savepoint, async {
+            mapper.update("cached_user.rename", &update).await?;
+            assert_eq!(backend.len(), 1);
+            let _: User = mapper.get("cached_user.getUser", &args).await?;
+            Err(DbError::Query("abort".into()))
+        })
+        .await;
+    assert!(rolled_back.is_err());
+    assert_eq!(queries(), 3);
+    assert_eq!(backend.len(), 1);
+
+    session
+        .transactional(Nested::Savepoint, async {
+            mapper.update("cached_user.rename", &update).await?;
+            assert_eq!(backend.len(), 1);
+            Ok::<_, DbError>(())
+        })
+        .await
+        .unwrap();
+    assert!(backend.is_empty());
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{MockDriver, row};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::error::DbError;
+use uorm::executor::session::Session;
+use uorm::mapper_loader;
+use uorm::udbc::value::Value;
+
+const XML: &str = r#"<mapper namespace="dashboard">
+    <select id="user">
+        SELECT id, name FROM users WHERE id = #{id}
+    </select>
+    <select id="count">
+        SELECT COUNT(*) AS total FROM users
+    </select>
+</mapper>"#;
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: i64,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Count {
+    total: i64,
+}
+
+#[tokio::test]
+async fn test_query_multi_on_one_connection() {
+    mapper_loader::load_assets(vec![("dashboard.xml", XML)]).unwrap();
+    // 按 SQL 返回不同的行
+    let driver = MockDriver::new("mock").with_query(|call| {
+        if call.sql.contains("COUNT") {
+            return Ok(vec![row([("total", Value::I64(2))])]);
+        }
+        Ok(vec![row([
+            ("id", call.values()[0].clone()),
+            ("name", Value::Str("alice".into())),
+        ])])
+    });
+    let log = driver.log();
+    let session = Session::new(Arc::new(driver));
+
+    let args = HashMap::from([("id", 7)]);
+    let mut sets = session
+        .query_multi(&[("dashboard.user", &args), ("dashboard.count", &args)])
+        .await
+        .unwrap();
+    assert_eq!(sets.len(), 2);
+
+    let users: Vec<User> = sets.next_set().unwrap();
+    assert_eq!((users[0].id, users[0].name.as_str()), (7, "alice"));
+    let counts: Vec<Count> = sets.next_set().unwrap();
+    assert_eq!(counts[0].total, 2);
+    assert!(sets.next_set::<Count>().is_err());
+
+    let conns: Vec<usize> = log.lock().unwrap().iter().map(|c| c.conn).collect();
+    assert_eq!(conns, vec![0, 0]);
+
+    let missing = session.query_multi(&[("dashboard.missing", &args)]).await;
+    assert!(matches!(missing, Err(DbError::Query(_))));
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{MockDriver, row};
+use serde::Deserialize;
+use std::sync::{Arc, Once};
+use std::time::Duration;
+use uorm::driver_manager::UORM;
+use uorm::error::DbError;
+use uorm::executor::mapper::Mapper;
+use uorm::executor::options::QueryOptions;
+use uorm::mapper_loader;
+use uorm::udbc::value::Value;
+
+/// 返回 5 行，每行带有连接池名
+fn pool(name: &str) -> MockDriver {
+    let rows = (0..5)
+        .map(|i| {
+            row([
+                ("id", Value::I64(i)),
+                ("pool", Value::Str(name.to_string())),
+            ])
+        })
+        .collect();
+    MockDriver::new(name).with_rows(rows)
+}
+
+const XML: &str = r#"<mapper namespace="report">
+    <select id="all">
+        SELECT id, pool FROM orders
+    </select>
+    <select id="top" maxRows="2">
+        SELECT id, pool FROM orders
+    </select>
+    <select id="fromReplica" route="report_replica">
+        SELECT id, pool FROM orders
+    </select>
+</mapper>"#;
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    pool: String,
+}
+
+fn setup() {
+    static LOADED: Once = Once::new();
+    LOADED.call_once(|| {
+        mapper_loader::load_assets(vec![("report.xml", XML)]).unwrap();
+        UORM.register(pool("report_replica")).unwrap();
+    });
+}
+
+#[tokio::test]
+async fn test_layered_max_rows() {
+    setup();
+    let driver = pool("primary").with_query_options(QueryOptions {
+        max_rows: Some(3),
+        ..Default::default()
+    });
+    let mapper = Mapper::new(Arc::new(driver));
+
+    // 连接池 → 语句属性 → 单次调用，逐层覆盖
+    let rows: Vec<Row> = mapper.list("report.all", &()).await.unwrap();
+    assert_eq!(rows.len(), 3);
+    let rows: Vec<Row> = mapper.list("report.top", &()).await.unwrap();
+    assert_eq!(rows.len(), 2);
+    let call = QueryOptions {
+        max_rows: Some(1),
+        ..Default::default()
+    };
+    let rows: Vec<Row> = mapper
+        .with_options(call)
+        .list("report.top", &())
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[tokio::test]
+async fn test_timeout_and_route() {
+    setup();
+    // 模拟慢查询
+    let driver = pool("slow").with_query_delay(Duration::from_millis(200));
+    let mapper = Mapper::new(Arc::new(driver)).with_options(QueryOptions {
+        timeout: Some(Duration::from_millis(20)),
+        ..Default::default()
+    });
+    let err = mapper.list::<Row, _>("report.all", &()).await.unwrap_err();
+    assert!(matches!(err, DbError::Timeout(_)));
+
+    // 路由到已注册的副本连接池，不受本连接池的延迟影响
+    let rows: Vec<Row> = mapper.list("report.fromReplica", &()).await.unwrap();
+    assert_eq!(rows[0].pool, "report_replica");
+}
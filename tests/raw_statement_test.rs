@@ -0,0 +1,76 @@
+mod common;
+
+use common::{MockDriver, row};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::executor::session::Session;
+use uorm::mapper_loader;
+use uorm::tpl::test_render;
+use uorm::udbc::value::Value;
+
+#[derive(Debug, Deserialize)]
+struct Doc {
+    doc: String,
+}
+
+const XML: &str = r#"<mapper namespace="raw">
+    <select id="json" raw="true"><![CDATA[
+        SELECT JSON_EXTRACT(doc, '$.a') FROM t WHERE tag = '#{x}' AND <if test="a">1</if> AND id = ?
+    ]]></select>
+    <select id="templated">SELECT * FROM t WHERE id = #{id}</select>
+</mapper>"#;
+
+#[test]
+fn test_raw_mapper_statement_skips_template() {
+    mapper_loader::load_assets(vec![("mem://raw.xml", XML)]).unwrap();
+    assert!(mapper_loader::find_mapper("raw.json", "").unwrap().raw);
+    assert!(!mapper_loader::find_mapper("raw.templated", "").unwrap().raw);
+
+    let rendered = test_render("raw.json", &[7]).unwrap();
+    assert_eq!(
+        rendered.sql.trim(),
+        "SELECT JSON_EXTRACT(doc, '$.a') FROM t WHERE tag = '#{x}' AND <if test=\"a\">1</if> AND id = ?"
+    );
+    assert_eq!(rendered.params, [("1".to_string(), Value::I32(7))]);
+
+    // 单个标量按一个位置参数绑定，具名参数被拒绝
+    assert_eq!(test_render("raw.json", &7).unwrap().params.len(), 1);
+    assert!(test_render("raw.json", &HashMap::from([("id", 7)])).is_err());
+
+    let rendered = test_render("raw.templated", &HashMap::from([("id", 7)])).unwrap();
+    assert_eq!(rendered.sql, "SELECT * FROM t WHERE id = ?");
+}
+
+#[tokio::test]
+async fn test_execute_and_query_raw() {
+    let driver =
+        MockDriver::new("raw").with_rows(vec![row([("doc", Value::Str("{\"a\":1}".to_string()))])]);
+    let log = driver.log();
+    let session = Session::new(Arc::new(driver));
+
+    let sql = "UPDATE t SET doc = JSON_SET(doc, '$.a', ?) WHERE note = '#{not a param}' AND id = ?";
+    let affected = session
+        .execute_raw(sql, &[Value::I32(1), Value::I64(9)])
+        .await
+        .unwrap();
+    assert_eq!(affected, 1);
+
+    let docs: Vec<Doc> = session
+        .query_raw("SELECT doc FROM t WHERE doc->'$.a' < ?", &[Value::I32(2)])
+        .await
+        .unwrap();
+    assert_eq!(docs[0].doc, "{\"a\":1}");
+
+    let log = log.lock().unwrap();
+    assert_eq!(log[0].sql, sql);
+    assert_eq!(
+        log[0].args,
+        [
+            ("1".to_string(), Value::I32(1)),
+            ("2".to_string(), Value::I64(9)),
+        ]
+    );
+    assert_eq!(log[1].sql, "SELECT doc FROM t WHERE doc->'$.a' < ?");
+    assert_eq!(log[1].args, [("1".to_string(), Value::I32(2))]);
+}
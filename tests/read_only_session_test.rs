@@ -0,0 +1,81 @@
+mod common;
+
+use common::{Log, MockDriver, row, take_sql as take};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::error::DbError;
+use uorm::executor::session::Session;
+use uorm::udbc::value::Value;
+
+fn setup() -> (Session, Log) {
+    let driver = MockDriver::new("read_only").with_rows(vec![row([("n", Value::I64(3))])]);
+    let log = driver.log();
+    (Session::new(Arc::new(driver)), log)
+}
+
+#[tokio::test]
+async fn test_read_only_rejects_writes_before_sending() {
+    let (session, log) = setup();
+    let reports = session.read_only();
+
+    let count = reports
+        .count(
+            "SELECT COUNT(*) FROM orders WHERE status = #{status}",
+            &HashMap::from([("status", "paid")]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+
+    // 误配为写语句的模板
+    let err = reports
+        .query::<HashMap<String, Value>, _>(
+            "DELETE FROM orders WHERE id = #{id}",
+            &HashMap::from([("id", 1)]),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DbError::ReadOnly(_)), "{}", err);
+    assert!(
+        err.to_string().contains("DELETE in `DELETE FROM orders"),
+        "{}",
+        err
+    );
+
+    let err = reports
+        .query_raw::<HashMap<String, Value>>("SELECT id FROM orders FOR UPDATE", &[])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DbError::ReadOnly(_)), "{}", err);
+
+    assert_eq!(take(&log), ["SELECT COUNT(*) FROM orders WHERE status = ?"]);
+
+    // 原会话不受影响
+    session.execute("DELETE FROM orders", &()).await.unwrap();
+    assert_eq!(take(&log), ["DELETE FROM orders"]);
+}
+
+#[tokio::test]
+async fn test_read_only_transaction() {
+    let (session, log) = setup();
+    let reports = session.read_only();
+    let total = reports
+        .transactional(async {
+            let a = reports.count("SELECT COUNT(*) FROM a", &()).await?;
+            let b = reports.count("SELECT COUNT(*) FROM b", &()).await?;
+            Ok::<_, DbError>(a + b)
+        })
+        .await
+        .unwrap();
+    assert_eq!(total, 6);
+    assert_eq!(
+        take(&log),
+        [
+            "BEGIN",
+            "SET TRANSACTION READ ONLY",
+            "SELECT COUNT(*) FROM a",
+            "SELECT COUNT(*) FROM b",
+            "COMMIT",
+        ]
+    );
+}
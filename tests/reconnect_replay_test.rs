@@ -0,0 +1,76 @@
+mod common;
+
+use common::{Log, MockDriver, row};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::error::DbError;
+use uorm::executor::session::{Nested, Session};
+use uorm::udbc::value::Value;
+
+/// 前 `stale` 个连接查询时报连接中断，模拟故障切换后连接池中残留的失效连接
+fn setup(stale: usize) -> (Session, Log) {
+    let driver = MockDriver::new("reconnect").with_query(move |call| {
+        if call.conn < stale {
+            return Err(DbError::Connection("server has gone away".to_string()));
+        }
+        Ok(vec![row([("n", Value::I64(1))])])
+    });
+    let log = driver.log();
+    (Session::new(Arc::new(driver)), log)
+}
+
+/// 取出记录，格式为 `#连接编号 语句`
+fn take(log: &Log) -> Vec<String> {
+    common::take(log)
+        .into_iter()
+        .map(|call| format!("#{} {}", call.conn, call.sql))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_read_replayed_on_fresh_connection() {
+    let (session, log) = setup(1);
+    let count = session.count("SELECT COUNT(*) FROM t", &()).await.unwrap();
+    assert_eq!(count, 1);
+    assert_eq!(
+        take(&log),
+        ["#0 SELECT COUNT(*) FROM t", "#1 SELECT COUNT(*) FROM t"]
+    );
+}
+
+#[tokio::test]
+async fn test_replayed_only_once() {
+    let (session, log) = setup(2);
+    let err = session
+        .count("SELECT COUNT(*) FROM t", &())
+        .await
+        .unwrap_err();
+    assert!(err.is_connection_lost(), "{}", err);
+    assert_eq!(take(&log).len(), 2);
+}
+
+#[tokio::test]
+async fn test_locking_read_not_replayed() {
+    let (session, log) = setup(1);
+    let err = session
+        .query::<HashMap<String, i64>, _>("SELECT n FROM t FOR UPDATE", &())
+        .await
+        .unwrap_err();
+    assert!(err.is_connection_lost(), "{}", err);
+    assert_eq!(take(&log), ["#0 SELECT n FROM t FOR UPDATE"]);
+}
+
+#[tokio::test]
+async fn test_transactional_read_not_replayed() {
+    let (session, log) = setup(1);
+    let result = session
+        .transactional(Nested::Join, async {
+            session.count("SELECT COUNT(*) FROM t", &()).await
+        })
+        .await;
+    assert!(result.unwrap_err().is_connection_lost());
+    assert_eq!(
+        take(&log),
+        ["#0 BEGIN", "#0 SELECT COUNT(*) FROM t", "#0 ROLLBACK"]
+    );
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use common::MockDriver;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Once};
+use std::thread;
+use std::time::Duration;
+use uorm::executor::mapper::Mapper;
+use uorm::executor::session::Session;
+use uorm::mapper_loader;
+use uorm::tpl::test_render;
+use uorm::udbc::value::Value;
+
+type Row = HashMap<String, Value>;
+
+/// 语句与其引用的片段带有相同的版本号
+fn xml(namespace: &str, version: u32) -> String {
+    format!(
+        r#"<mapper namespace="{namespace}">
+    <sql id="cond">tag = 'v{version}'</sql>
+    <select id="find">SELECT 'v{version}' FROM t WHERE <include refid="{namespace}.cond"/></select>
+</mapper>"#
+    )
+}
+
+fn setup() {
+    static LOAD: Once = Once::new();
+    LOAD.call_once(|| {
+        mapper_loader::load_assets(vec![
+            ("mem://coherency.xml", &xml("coherency", 0)),
+            ("mem://blocking.xml", &xml("blocking", 0)),
+            ("mem://mapped.xml", &xml("mapped", 0)),
+            ("mem://shadowed.xml", &xml("shadowed", 0)),
+        ])
+        .unwrap()
+    });
+}
+
+/// 渲染结果中语句与片段的版本号
+fn versions(sql: &str) -> (String, String) {
+    let version = |prefix: &str| {
+        let start = sql.find(prefix).unwrap() + prefix.len();
+        sql[start..].split('\'').next().unwrap().to_string()
+    };
+    (version("SELECT '"), version("tag = '"))
+}
+
+#[test]
+fn test_include_renders_from_same_version() {
+    setup();
+    let rendered = test_render("coherency.find", &()).unwrap();
+    let (statement, fragment) = versions(&rendered.sql);
+    assert_eq!(statement, fragment);
+}
+
+#[test]
+fn test_reload_racing_with_renders() {
+    setup();
+    let done = Arc::new(AtomicBool::new(false));
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let done = done.clone();
+            thread::spawn(move || {
+                let mut renders = 0;
+                while !done.load(Ordering::Relaxed) || renders == 0 {
+                    let rendered = test_render("coherency.find", &()).unwrap();
+                    let (statement, fragment) = versions(&rendered.sql);
+                    assert_eq!(statement, fragment, "{}", rendered.sql);
+                    renders += 1;
+                }
+            })
+        })
+        .collect();
+
+    for version in 1..=200 {
+        mapper_loader::reload("coherency", &xml("coherency", version)).unwrap();
+    }
+    done.store(true, Ordering::Relaxed);
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    let rendered = test_render("coherency.find", &()).unwrap();
+    assert_eq!(versions(&rendered.sql), ("v200".into(), "v200".into()));
+}
+
+#[test]
+fn test_snapshot_blocks_reload() {
+    setup();
+    let snapshot = mapper_loader::snapshot();
+    let generation = snapshot.generation();
+    let before = snapshot.find_mapper("blocking.find", "").unwrap();
+
+    // 持有视图的线程不能重载，否则会与自身死锁
+    assert!(mapper_loader::reload("blocking", &xml("blocking", 1)).is_err());
+    // 视图内的渲染可以嵌套获取视图
+    assert!(test_render("blocking.find", &()).is_ok());
+
+    let (tx, rx) = mpsc::channel();
+    let reloader = thread::spawn(move || {
+        mapper_loader::reload("blocking", &xml("blocking", 2)).unwrap();
+        tx.send(()).unwrap();
+    });
+    assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    let current = snapshot.find_mapper("blocking.find", "").unwrap();
+    assert!(Arc::ptr_eq(&before, &current));
+
+    drop(snapshot);
+    rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    reloader.join().unwrap();
+    assert!(mapper_loader::snapshot().generation() > generation);
+    let rendered = test_render("blocking.find", &()).unwrap();
+    assert_eq!(versions(&rendered.sql), ("v2".into(), "v2".into()));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_reload_racing_with_mapper_renders() {
+    setup();
+    let driver = MockDriver::new("reload_mapper");
+    let log = driver.log();
+    let mapper = Arc::new(Mapper::new(Arc::new(driver)));
+    let done = Arc::new(AtomicBool::new(false));
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let (mapper, done) = (mapper.clone(), done.clone());
+            tokio::spawn(async move {
+                while !done.load(Ordering::Relaxed) {
+                    let _: Vec<Row> = mapper.list("mapped.find", &()).await.unwrap();
+                }
+            })
+        })
+        .collect();
+
+    tokio::task::spawn_blocking(|| {
+        for version in 1..=200 {
+            mapper_loader::reload("mapped", &xml("mapped", version)).unwrap();
+        }
+    })
+    .await
+    .unwrap();
+    done.store(true, Ordering::Relaxed);
+    for reader in readers {
+        reader.await.unwrap();
+    }
+    let _: Vec<Row> = mapper.list("mapped.find", &()).await.unwrap();
+
+    let sql = common::take_sql(&log);
+    for sql in &sql {
+        let (statement, fragment) = versions(sql);
+        assert_eq!(statement, fragment, "{}", sql);
+    }
+    assert_eq!(
+        versions(sql.last().unwrap()),
+        ("v200".into(), "v200".into())
+    );
+}
+
+#[tokio::test]
+async fn test_inline_statement_id_is_not_resolved_from_mappers() {
+    setup();
+    let driver = MockDriver::new("reload_inline");
+    let log = driver.log();
+    let session = Session::new(Arc::new(driver));
+
+    // 与已加载语句同名的内联语句按传入的 SQL 渲染
+    let _: Vec<Row> = session
+        .query_named("shadowed.find", "SELECT 1", &())
+        .await
+        .unwrap();
+    mapper_loader::reload("shadowed", &xml("shadowed", 1)).unwrap();
+    let _: Vec<Row> = session
+        .query_named("shadowed.find", "SELECT 1", &())
+        .await
+        .unwrap();
+    assert_eq!(common::take_sql(&log), ["SELECT 1", "SELECT 1"]);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::{MockDriver, row};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::error::DbError;
+use uorm::executor::mapper::Mapper;
+use uorm::executor::postprocess::{self, RowPostProcessor, SplitColumns};
+use uorm::executor::session::Session;
+use uorm::mapper_loader;
+use uorm::udbc::driver::Driver;
+use uorm::udbc::value::Value;
+
+/// “解密”即反转字符串，并记录语句 ID
+struct Reverse {
+    column: &'static str,
+}
+
+impl RowPostProcessor for Reverse {
+    fn process(
+        &self,
+        sql_id: Option<&str>,
+        row: &mut HashMap<String, Value>,
+    ) -> Result<(), DbError> {
+        if let Some(Value::Str(s)) = row.get_mut(self.column) {
+            *s = s.chars().rev().collect();
+        }
+        let sql_id = Value::Str(sql_id.unwrap_or_default().into());
+        row.insert("sql_id".to_string(), sql_id);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct User {
+    id: i64,
+    email: String,
+    tags: Vec<String>,
+    sql_id: String,
+}
+
+const XML: &str = r#"<mapper namespace="pp">
+    <select id="get" rowProcessors="tags">SELECT id, email, tags FROM users</select>
+    <select id="missing" rowProcessors="nope">SELECT id, email, tags FROM users</select>
+</mapper>"#;
+
+fn driver(processors: Vec<Arc<dyn RowPostProcessor>>) -> Arc<dyn Driver> {
+    let driver = MockDriver::new("postprocess").with_rows(vec![row([
+        ("id", Value::I64(1)),
+        ("email", Value::Str("moc.elpmaxe@ecila".into())),
+        ("tags", Value::Str("admin, ops".into())),
+    ])]);
+    Arc::new(
+        processors
+            .into_iter()
+            .fold(driver, MockDriver::with_row_processor),
+    )
+}
+
+#[tokio::test]
+async fn test_row_post_processors() {
+    mapper_loader::load_assets(vec![("mem://pp.xml", XML)]).unwrap();
+    postprocess::register_row_processor("tags", Arc::new(SplitColumns::new(",", ["tags"])));
+    let expected = |sql_id: &str| User {
+        id: 1,
+        email: "alice@example.com".into(),
+        tags: vec!["admin".into(), "ops".into()],
+        sql_id: sql_id.to_string(),
+    };
+
+    // 连接池解密，语句拆分列表
+    let mapper = Mapper::new(driver(vec![Arc::new(Reverse { column: "email" })]));
+    let users: Vec<User> = mapper.list("pp.get", &()).await.unwrap();
+    assert_eq!(users, [expected("pp.get")]);
+
+    // 会话上的处理器在连接池之后执行
+    let session = Session::new(driver(vec![]))
+        .with_row_processor(Arc::new(Reverse { column: "email" }))
+        .with_row_processor(Arc::new(SplitColumns::new(",", ["tags"])));
+    let users: Vec<User> = session
+        .query("SELECT id, email, tags FROM users", &())
+        .await
+        .unwrap();
+    assert_eq!(users, [expected("")]);
+    let stmt = Session::new(driver(vec![Arc::new(Reverse { column: "email" })]))
+        .prepare("pp.get")
+        .unwrap();
+    let users: Vec<User> = stmt.query(&()).await.unwrap();
+    assert_eq!(users, [expected("pp.get")]);
+
+    // 未注册的处理器名报错
+    let err = mapper.list::<User, _>("pp.missing", &()).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("row processor 'nope' is not registered"),
+        "{}",
+        err
+    );
+}
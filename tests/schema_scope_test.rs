@@ -0,0 +1,159 @@
+mod common;
+
+use common::{Call, Log, MockDriver, row, take};
+use std::sync::Arc;
+use uorm::error::DbError;
+use uorm::executor::session::{Nested, Session};
+use uorm::udbc::value::Value;
+
+/// 当前 schema 为 `app` 的连接池
+fn driver() -> MockDriver {
+    MockDriver::new("schema")
+        .with_schema_switching()
+        .with_rows(vec![row([("DATABASE()", Value::Str("app".into()))])])
+}
+
+fn setup() -> (Session, Log) {
+    setup_with(driver())
+}
+
+fn setup_with(driver: MockDriver) -> (Session, Log) {
+    let log = driver.log();
+    (Session::new(Arc::new(driver)), log)
+}
+
+fn statements(log: &[Call]) -> Vec<&str> {
+    log.iter().map(|call| call.sql.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_schema_scope_pins_connection() {
+    let (session, log) = setup();
+    let affected = session
+        .using_schema("tenant_42", async {
+            assert_eq!(session.current_schema().as_deref(), Some("tenant_42"));
+            session.execute("UPDATE t SET a = 1", &()).await.unwrap();
+            session.execute("UPDATE t SET a = 2", &()).await.unwrap()
+        })
+        .await
+        .unwrap();
+    assert_eq!(affected, 1);
+    assert_eq!(session.current_schema(), None);
+
+    let log = take(&log);
+    assert_eq!(
+        statements(&log),
+        [
+            "SELECT DATABASE()",
+            "USE `tenant_42`",
+            "UPDATE t SET a = 1",
+            "UPDATE t SET a = 2",
+            "USE `app`",
+        ]
+    );
+    // 全部在同一连接上执行
+    assert!(log.iter().all(|call| call.conn == log[0].conn));
+}
+
+#[tokio::test]
+async fn test_schema_scope_shares_transaction_connection() {
+    // 作用域内开启的事务使用已切换的连接
+    let (session, log) = setup();
+    session
+        .using_schema("tenant_1", async {
+            session
+                .transactional(Nested::Savepoint, async {
+                    session.execute("DELETE FROM t", &()).await
+                })
+                .await
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    let records = take(&log);
+    assert_eq!(
+        statements(&records),
+        [
+            "SELECT DATABASE()",
+            "USE `tenant_1`",
+            "BEGIN",
+            "DELETE FROM t",
+            "COMMIT",
+            "USE `app`",
+        ]
+    );
+    assert!(records.iter().all(|call| call.conn == records[0].conn));
+
+    // 事务中切换时在事务的连接上执行，结束后恢复，事务继续
+    session
+        .transactional(Nested::Savepoint, async {
+            session
+                .using_schema("tenant_2", session.execute("DELETE FROM t", &()))
+                .await??;
+            session.execute("DELETE FROM audit", &()).await
+        })
+        .await
+        .unwrap();
+    let records = take(&log);
+    assert_eq!(
+        statements(&records),
+        [
+            "BEGIN",
+            "SELECT DATABASE()",
+            "USE `tenant_2`",
+            "DELETE FROM t",
+            "USE `app`",
+            "DELETE FROM audit",
+            "COMMIT",
+        ]
+    );
+    assert!(records.iter().all(|call| call.conn == records[0].conn));
+}
+
+#[tokio::test]
+async fn test_schema_scope_discards_connection_on_cancel() {
+    let (session, log) = setup();
+    let scoped = session.using_schema("tenant_7", async {
+        session.execute("UPDATE t SET a = 1", &()).await.unwrap();
+        std::future::pending::<()>().await
+    });
+    // 作用域未结束即被取消（如客户端断开），连接不能带着租户 schema 回到连接池
+    let cancelled = tokio::time::timeout(std::time::Duration::from_millis(20), scoped).await;
+    assert!(cancelled.is_err());
+    assert_eq!(
+        statements(&take(&log)),
+        [
+            "SELECT DATABASE()",
+            "USE `tenant_7`",
+            "UPDATE t SET a = 1",
+            "DISCARD"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_schema_scope_keeps_output_when_restore_fails() {
+    // 恢复原 schema 的语句失败
+    let (session, log) = setup_with(driver().with_execute(|call| {
+        if call.sql == "USE `app`" {
+            return Err(DbError::Database("connection lost".into()));
+        }
+        Ok(1)
+    }));
+    let affected = session
+        .using_schema("tenant_8", session.execute("UPDATE t SET a = 1", &()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(affected, 1);
+    assert_eq!(
+        statements(&take(&log)),
+        [
+            "SELECT DATABASE()",
+            "USE `tenant_8`",
+            "UPDATE t SET a = 1",
+            "USE `app`",
+            "DISCARD",
+        ]
+    );
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::{MockDriver, row};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uorm::executor::insert::ColumnMap;
+use uorm::executor::session::Session;
+use uorm::udbc::value::Value;
+
+/// 按 SQL 中的表名返回预设结果
+fn driver() -> MockDriver {
+    MockDriver::new("mock").with_query(|call| {
+        let mut words = call.sql.split_whitespace();
+        let table = words
+            .find(|w| *w == "FROM")
+            .and(words.next())
+            .unwrap_or_default();
+        Ok(match table {
+            "users" if call.values()[0] == Value::Str("a@x.io".into()) => {
+                vec![row([("1", Value::I64(1))])]
+            }
+            "users" => Vec::new(),
+            "orders" => vec![row([("COUNT(*)", Value::I64(42))])],
+            "totals" => vec![row([("n", Value::Decimal(Decimal::new(700, 2)))])],
+            "wide" => vec![row([("a", Value::I64(1)), ("b", Value::I64(2))])],
+            _ => vec![row([("n", Value::I64(-1))])],
+        })
+    })
+}
+
+#[tokio::test]
+async fn test_exists_and_count() {
+    let session = Session::new(Arc::new(driver()));
+    let sql = "SELECT 1 FROM users WHERE email = #{email} LIMIT 1";
+    assert!(
+        session
+            .exists(sql, &HashMap::from([("email", "a@x.io")]))
+            .await
+            .unwrap()
+    );
+    assert!(
+        !session
+            .exists(sql, &HashMap::from([("email", "b@x.io")]))
+            .await
+            .unwrap()
+    );
+
+    assert_eq!(
+        session
+            .count("SELECT COUNT(*) FROM orders", &())
+            .await
+            .unwrap(),
+        42
+    );
+    assert_eq!(
+        session
+            .count("SELECT SUM(n) FROM totals", &())
+            .await
+            .unwrap(),
+        7
+    );
+    let err = session
+        .count("SELECT a, b FROM wide", &())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("one column"), "{}", err);
+    assert!(session.count("SELECT n FROM negative", &()).await.is_err());
+}
+
+#[derive(Serialize)]
+struct User {
+    id: Option<i64>,
+    name: String,
+    email: String,
+}
+
+#[tokio::test]
+async fn test_insert_into() {
+    let driver = driver();
+    let log = driver.log();
+    let session = Session::new(Arc::new(driver));
+    let user = User {
+        id: None,
+        name: "a".into(),
+        email: "a@x.io".into(),
+    };
+    assert_eq!(session.insert_into("users", &user).await.unwrap(), 1);
+    let columns = ColumnMap::new().skip("id").rename("email", "mail");
+    session
+        .insert_into_with("users", &user, &columns)
+        .await
+        .unwrap();
+
+    let inserted = log.lock().unwrap();
+    assert_eq!(
+        inserted[0].sql,
+        "INSERT INTO users (email, id, name) VALUES (?, ?, ?)"
+    );
+    assert_eq!(inserted[0].args[1], ("id".to_string(), Value::Null));
+    assert_eq!(
+        inserted[1].sql,
+        "INSERT INTO users (mail, name) VALUES (?, ?)"
+    );
+}
+
+#[tokio::test]
+async fn test_session_clone_shares_pool() {
+    let session = Session::new(Arc::new(driver()));
+    assert_eq!(session.database_name(), "mock");
+
+    // 克隆后的句柄可移入其他任务使用
+    let handle = session.clone();
+    let count = tokio::spawn(async move {
+        handle
+            .count("SELECT COUNT(*) FROM orders", &())
+            .await
+            .unwrap()
+    })
+    .await
+    .unwrap();
+    assert_eq!(count, 42);
+    assert_eq!(session.database_name(), "mock");
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use common::{MockDriver, row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Once};
+use uorm::driver_manager::UORM;
+use uorm::executor::mapper::Mapper;
+use uorm::executor::shard::{self, ModuloShards};
+use uorm::mapper_loader;
+use uorm::udbc::value::Value;
+
+/// 记录每条语句实际执行所在的连接池
+static EXECUTED: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// 名为 `name` 的连接池，查询返回连接池名
+fn pool(name: &str) -> MockDriver {
+    let (recorded, returned) = (name.to_string(), name.to_string());
+    MockDriver::new(name)
+        .with_rows(vec![row([("pool", Value::Str(returned))])])
+        .with_callback(move |call| {
+            EXECUTED
+                .lock()
+                .unwrap()
+                .push((recorded.clone(), call.sql.trim().to_string()));
+        })
+}
+
+const XML: &str = r#"<mapper namespace="orders">
+    <select id="byUser" sharded-by="user_id">
+        SELECT pool FROM orders WHERE user_id = #{user_id}
+    </select>
+    <insert id="create" shardedBy="order.user_id">
+        INSERT INTO orders(user_id) VALUES (#{order.user_id})
+    </insert>
+</mapper>"#;
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    pool: String,
+}
+
+#[derive(Serialize)]
+struct Order {
+    user_id: i64,
+}
+
+fn setup() {
+    static LOADED: Once = Once::new();
+    LOADED.call_once(|| {
+        mapper_loader::load_assets(vec![("orders.xml", XML)]).unwrap();
+        for name in ["orders_0", "orders_1"] {
+            UORM.register(pool(name)).unwrap();
+        }
+    });
+}
+
+fn executed_on(sql: &str) -> Vec<String> {
+    EXECUTED
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, s)| s.starts_with(sql))
+        .map(|(pool, _)| pool.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_sharded_statements() {
+    setup();
+    let mapper = Mapper::new(Arc::new(pool("primary")));
+
+    // 未设置解析器时报错，而不是落到默认连接池
+    let err = mapper
+        .list::<Row, _>("orders.byUser", &HashMap::from([("user_id", 3)]))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no ShardResolver"), "{}", err);
+
+    shard::set_shard_resolver(Arc::new(ModuloShards::new("orders_", 2)));
+    let rows: Vec<Row> = mapper
+        .list("orders.byUser", &HashMap::from([("user_id", 3)]))
+        .await
+        .unwrap();
+    assert_eq!(rows[0].pool, "orders_1");
+    let rows: Vec<Row> = mapper
+        .list("orders.byUser", &HashMap::from([("user_id", 4)]))
+        .await
+        .unwrap();
+    assert_eq!(rows[0].pool, "orders_0");
+
+    let err = mapper
+        .list::<Row, _>("orders.byUser", &HashMap::from([("other", 4)]))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not bound"), "{}", err);
+
+    // 批量插入时每个元素分别路由
+    let batch: Vec<HashMap<&str, Order>> = [5, 6, 7]
+        .into_iter()
+        .map(|user_id| HashMap::from([("order", Order { user_id })]))
+        .collect();
+    mapper
+        .batch_create::<u64, _>("orders.create", &batch)
+        .await
+        .unwrap();
+    assert_eq!(
+        executed_on("INSERT INTO orders"),
+        ["orders_1", "orders_0", "orders_1"]
+    );
+
+    shard::clear_shard_resolver();
+}
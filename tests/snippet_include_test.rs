@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use uorm::mapper_loader;
+use uorm::tpl::{
+    EnvSnippets, RenderOptions, SnippetProvider, clear_snippet_provider, set_render_options,
+    set_snippet_provider, test_render,
+};
+use uorm::udbc::value::Value;
+
+#[derive(Default)]
+struct Snippets(RwLock<HashMap<String, String>>);
+
+impl Snippets {
+    fn set(&self, name: &str, sql: &str) {
+        self.0
+            .write()
+            .unwrap()
+            .insert(name.to_string(), sql.to_string());
+    }
+}
+
+impl SnippetProvider for Snippets {
+    fn snippet(&self, name: &str) -> Option<String> {
+        self.0.read().unwrap().get(name).cloned()
+    }
+}
+
+const XML: &str = r#"<mapper namespace="snippet">
+    <select id="orders">SELECT * FROM orders WHERE status = #{status} <include env="TENANT_PREDICATE"/></select>
+</mapper>"#;
+
+#[test]
+fn test_include_env_snippets() {
+    mapper_loader::load_assets(vec![("mem://snippet.xml", XML)]).unwrap();
+    let args = HashMap::from([("status", Value::I32(1)), ("tenant", Value::I32(7))]);
+
+    set_render_options(RenderOptions {
+        normalize_whitespace: true,
+        ..Default::default()
+    });
+    let snippets = Arc::new(Snippets::default());
+    set_snippet_provider(snippets.clone());
+
+    // 片段缺失时渲染为空
+    let rendered = test_render("snippet.orders", &args).unwrap();
+    assert_eq!(rendered.sql, "SELECT * FROM orders WHERE status = ?");
+
+    // 片段按模板解析，可以引用参数；内容变化后立即生效
+    snippets.set("TENANT_PREDICATE", "AND tenant_id = #{tenant}");
+    let rendered = test_render("snippet.orders", &args).unwrap();
+    assert_eq!(
+        rendered.sql,
+        "SELECT * FROM orders WHERE status = ? AND tenant_id = ?"
+    );
+    assert_eq!(rendered.params[1], ("tenant".to_string(), Value::I32(7)));
+
+    snippets.set("TENANT_PREDICATE", "AND region = 'eu'");
+    let rendered = test_render("snippet.orders", &args).unwrap();
+    assert_eq!(
+        rendered.sql,
+        "SELECT * FROM orders WHERE status = ? AND region = 'eu'"
+    );
+
+    // 从环境变量读取
+    set_snippet_provider(Arc::new(EnvSnippets::with_prefix("UORM_TEST_SNIPPET_")));
+    unsafe { std::env::set_var("UORM_TEST_SNIPPET_TENANT_PREDICATE", "AND deleted = 0") };
+    let rendered = test_render("snippet.orders", &args).unwrap();
+    assert_eq!(
+        rendered.sql,
+        "SELECT * FROM orders WHERE status = ? AND deleted = 0"
+    );
+
+    // 严格模式下片段缺失报错
+    clear_snippet_provider();
+    set_render_options(RenderOptions {
+        strict: true,
+        normalize_whitespace: true,
+        ..Default::default()
+    });
+    let err = test_render("snippet.orders", &args).unwrap_err();
+    assert!(err.to_string().contains("TENANT_PREDICATE"), "{}", err);
+}
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use uorm::executor::digest;
+use uorm::executor::trace::with_trace_id;
+use uorm::mapper_loader;
+use uorm::tpl::{RenderOptions, SqlComment, set_render_options, test_render};
+
+const XML: &str = r#"<mapper namespace="traced">
+    <select id="findById">
+        SELECT * FROM users WHERE id = #{id}
+    </select>
+</mapper>"#;
+
+#[tokio::test]
+async fn test_sql_comment_with_trace_id() {
+    mapper_loader::load_assets(vec![("traced.xml", XML)]).unwrap();
+    let args = HashMap::from([("id", 1)]);
+    let plain = test_render("traced.findById", &args).unwrap().sql;
+    assert!(plain.trim_start().starts_with("SELECT"), "{}", plain);
+
+    set_render_options(RenderOptions {
+        sql_comment: Some(SqlComment {
+            app: Some("svc".into()),
+        }),
+        ..Default::default()
+    });
+    let sql = test_render("traced.findById", &args).unwrap().sql;
+    assert!(
+        sql.starts_with("/* app=svc sql_id=traced.findById */ "),
+        "{}",
+        sql
+    );
+
+    // 链路 ID 取自任务上下文，其中的 `*` 被去掉以免提前结束注释
+    let sql = with_trace_id("abc*/123", async {
+        test_render("traced.findById", &args).unwrap().sql
+    })
+    .await;
+    assert!(
+        sql.starts_with("/* app=svc sql_id=traced.findById trace=abc/123 */ "),
+        "{}",
+        sql
+    );
+
+    // 注释不影响语句指纹
+    assert_eq!(digest::fingerprint(&sql), digest::fingerprint(&plain));
+    set_render_options(RenderOptions::default());
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "time")]
+
+mod common;
+
+use chrono::{NaiveDate, NaiveTime};
+use common::{MockDriver, row};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use time::macros::{date, datetime, time};
+use uorm::executor::session::Session;
+use uorm::udbc::value::Value;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Shop {
+    #[serde(with = "uorm::udbc::time::date")]
+    day: time::Date,
+    #[serde(with = "uorm::udbc::time::time_of_day")]
+    opens: time::Time,
+    #[serde(with = "uorm::udbc::time::datetime")]
+    created_at: time::PrimitiveDateTime,
+    #[serde(with = "uorm::udbc::time::offset_datetime")]
+    paid_at: time::OffsetDateTime,
+    #[serde(with = "uorm::udbc::time::offset_datetime::option")]
+    shipped_at: Option<time::OffsetDateTime>,
+}
+
+#[tokio::test]
+async fn test_time_fields_round_trip() {
+    // 查询返回数据库一侧的日期时间列
+    let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    let at = day.and_hms_opt(8, 30, 0).unwrap();
+    let driver = MockDriver::new("time").with_rows(vec![row([
+        ("day", Value::Date(day)),
+        (
+            "opens",
+            Value::Time(NaiveTime::from_hms_opt(9, 0, 0).unwrap()),
+        ),
+        ("created_at", Value::DateTime(at)),
+        ("paid_at", Value::DateTimeUtc(at.and_utc())),
+        ("shipped_at", Value::Null),
+    ])]);
+    let log = driver.log();
+    let session = Session::new(Arc::new(driver));
+
+    let shop = Shop {
+        day: date!(2024 - 03 - 01),
+        opens: time!(09:00),
+        created_at: datetime!(2024-03-01 08:30),
+        paid_at: datetime!(2024-03-01 16:30 +08:00),
+        shipped_at: None,
+    };
+    session
+        .execute(
+            "INSERT INTO shops VALUES (#{day}, #{opens}, #{created_at}, #{paid_at}, #{shipped_at})",
+            &shop,
+        )
+        .await
+        .unwrap();
+    let params = log.lock().unwrap()[0].values();
+    assert_eq!(
+        params,
+        [
+            Value::from(shop.day),
+            Value::from(shop.opens),
+            Value::from(shop.created_at),
+            Value::from(shop.paid_at),
+            Value::Null,
+        ]
+    );
+    assert!(matches!(params[3], Value::DateTimeUtc(_)));
+
+    let rows: Vec<Shop> = session.query("SELECT * FROM shops", &()).await.unwrap();
+    assert_eq!(rows, [shop]);
+
+    // 直接作为位置参数
+    session
+        .execute_raw(
+            "DELETE FROM shops WHERE day = ?",
+            &[Value::from(date!(2024 - 03 - 01))],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        log.lock().unwrap()[2].values()[0],
+        Value::Date(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap())
+    );
+}
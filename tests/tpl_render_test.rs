@@ -0,0 +1,65 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use uorm::mapper_loader;
+use uorm::tpl::{statement_info, test_render, test_render_for};
+use uorm::udbc::value::Value;
+
+#[derive(Serialize)]
+struct SearchArgs {
+    status: i32,
+    name: Option<String>,
+    ids: Vec<i64>,
+}
+
+#[test]
+fn test_render_golden_snapshot() {
+    mapper_loader::load("tests/resources/mapper/user.xml").expect("Failed to load mapper");
+
+    let args = SearchArgs {
+        status: 1,
+        name: Some("alice".to_string()),
+        ids: vec![3, 4],
+    };
+    let rendered = test_render("user.search", &args).expect("render failed");
+    assert_eq!(rendered.params.len(), 4);
+    assert_eq!(rendered.params[1].1, Value::Str("alice".to_string()));
+
+    let snapshot = rendered.to_string();
+    let expected = "-- params\n\
+                    1: status = I32(1)\n\
+                    2: name = Str(\"alice\")\n\
+                    3: ids[0] = I64(3)\n\
+                    4: ids[1] = I64(4)\n";
+    assert!(snapshot.starts_with("-- sql\nSELECT id, name FROM users WHERE status = ?"));
+    assert!(snapshot.contains("AND id IN (?, ?)"));
+    assert!(snapshot.ends_with(expected), "{}", snapshot);
+
+    assert!(test_render("user.missing", &args).is_err());
+
+    let info = statement_info("user.search").unwrap();
+    assert_eq!(info.required_params, vec!["status", "name", "ids"]);
+    assert!(statement_info("user.missing").is_err());
+}
+
+#[test]
+fn test_render_ident_per_dialect() {
+    mapper_loader::load_assets(vec![(
+        "mem://ident.xml",
+        r#"<mapper namespace="ident">
+            <select id="list">SELECT * FROM ${ident(table)} WHERE id = #{id}</select>
+        </mapper>"#,
+    )])
+    .unwrap();
+    let args = HashMap::from([
+        ("table", Value::Str("logs_2024`x".to_string())),
+        ("id", Value::I32(1)),
+    ]);
+
+    let rendered = test_render_for("mysql", "ident.list", &args).unwrap();
+    assert_eq!(rendered.sql, "SELECT * FROM `logs_2024``x` WHERE id = ?");
+    let rendered = test_render("ident.list", &args).unwrap();
+    assert_eq!(rendered.sql, r#"SELECT * FROM "logs_2024`x" WHERE id = ?"#);
+
+    let info = statement_info("ident.list").unwrap();
+    assert_eq!(info.required_params, vec!["table", "id"]);
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use common::{MockDriver, row};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use uorm::error::DbError;
+use uorm::executor::session::Session;
+use uorm::udbc::value::Value;
+
+/// 记录的 span：名称、父 span 与字段
+#[derive(Debug, Default, Clone)]
+struct SpanData {
+    name: String,
+    parent: Option<u64>,
+    fields: HashMap<String, String>,
+}
+
+/// 记录 span 与事件的最小 Subscriber
+#[derive(Default)]
+struct Capture {
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, SpanData>>,
+    /// 事件消息及其所在的 span
+    events: Mutex<Vec<(String, Option<u64>)>>,
+    stack: Mutex<Vec<u64>>,
+}
+
+struct Fields<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+/// 线程内安装的 Subscriber，与测试共享记录
+struct Capturing(Arc<Capture>);
+
+impl Subscriber for Capturing {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let id = self.0.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let parent = match attrs.parent() {
+            Some(parent) => Some(parent.into_u64()),
+            None if attrs.is_contextual() => self.0.stack.lock().unwrap().last().copied(),
+            None => None,
+        };
+        let mut data = SpanData {
+            name: attrs.metadata().name().to_string(),
+            parent,
+            ..Default::default()
+        };
+        attrs.record(&mut Fields(&mut data.fields));
+        self.0.spans.lock().unwrap().insert(id, data);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = self.0.spans.lock().unwrap();
+        let data = spans.get_mut(&span.into_u64()).unwrap();
+        values.record(&mut Fields(&mut data.fields));
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = HashMap::new();
+        event.record(&mut Fields(&mut fields));
+        let current = self.0.stack.lock().unwrap().last().copied();
+        self.0
+            .events
+            .lock()
+            .unwrap()
+            .push((fields.remove("message").unwrap_or_default(), current));
+    }
+
+    fn enter(&self, span: &Id) {
+        self.0.stack.lock().unwrap().push(span.into_u64());
+    }
+
+    fn exit(&self, _: &Id) {
+        self.0.stack.lock().unwrap().pop();
+    }
+}
+
+fn spans_named(capture: &Capture, name: &str) -> Vec<(u64, SpanData)> {
+    let mut spans: Vec<(u64, SpanData)> = capture
+        .spans
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, s)| s.name == name)
+        .map(|(id, s)| (*id, s.clone()))
+        .collect();
+    spans.sort_by_key(|(id, _)| *id);
+    spans
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_statement_and_transaction_spans() {
+    let capture = Arc::new(Capture::default());
+    let _guard = tracing::subscriber::set_default(Capturing(capture.clone()));
+    // 查询返回一行，更新一律失败
+    let driver = MockDriver::new("orders_db")
+        .with_rows(vec![row([("id", Value::I64(1))])])
+        .with_execute(|_| Err(DbError::Query("table is read only".to_string())));
+    let session = Session::new(Arc::new(driver));
+
+    let rows: Vec<HashMap<String, i64>> = session
+        .query_named(
+            "order.find",
+            "SELECT id FROM orders WHERE id = #{id}",
+            &HashMap::from([("id", 1)]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert!(
+        session
+            .execute("DELETE FROM orders", &HashMap::<String, i64>::new())
+            .await
+            .is_err()
+    );
+
+    let statements = spans_named(&capture, "uorm.statement");
+    assert_eq!(statements.len(), 2);
+    let (query_id, query) = &statements[0];
+    assert_eq!(query.fields["op"], "query");
+    assert_eq!(query.fields["db.name"], "orders_db");
+    assert_eq!(query.fields["sql_id"], "order.find");
+    assert_eq!(query.fields["rows"], "1");
+    assert_eq!(query.fields["fingerprint"].len(), 16);
+    assert!(query.fields.contains_key("elapsed_ms"));
+    let (execute_id, execute) = &statements[1];
+    assert_eq!(execute.fields["op"], "execute");
+    assert_eq!(execute.fields["sql_id"], "-");
+    assert!(!execute.fields.contains_key("rows"));
+
+    let events = capture.events.lock().unwrap().clone();
+    assert!(events.contains(&("statement finished".to_string(), Some(*query_id))));
+    assert!(events.contains(&("statement failed".to_string(), Some(*execute_id))));
+
+    // 事务内的语句是 `uorm.transaction` span 的子 span
+    let mut tx = session.begin().await.unwrap();
+    tx.query("SELECT id FROM orders", &HashMap::<String, i64>::new())
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let (tx_id, tx_span) = spans_named(&capture, "uorm.transaction").remove(0);
+    assert_eq!(tx_span.fields["db.name"], "orders_db");
+    let (_, in_tx) = spans_named(&capture, "uorm.statement").remove(2);
+    assert_eq!(in_tx.parent, Some(tx_id));
+    let events = capture.events.lock().unwrap().clone();
+    assert!(events.contains(&("transaction committed".to_string(), Some(tx_id))));
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::{Log, MockDriver};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use uorm::error::DbError;
+use uorm::transaction::TransactionContext;
+use uorm::udbc::ConnectionOptions;
+use uorm::udbc::driver::TransactionLimits;
+
+fn driver(max_duration: Option<Duration>) -> (Arc<MockDriver>, Log) {
+    let driver = MockDriver::new("mock").with_transaction_limits(TransactionLimits {
+        warn_after: Some(Duration::from_millis(10)),
+        max_duration,
+    });
+    let log = driver.log();
+    (Arc::new(driver), log)
+}
+
+fn rollbacks(log: &Log) -> usize {
+    log.lock()
+        .unwrap()
+        .iter()
+        .filter(|call| call.sql == "ROLLBACK")
+        .count()
+}
+
+#[tokio::test]
+async fn test_transaction_exceeding_max_duration_is_poisoned() {
+    let (driver, log) = driver(Some(Duration::from_millis(30)));
+    let mut tx = TransactionContext::begin(driver).await.unwrap();
+    tx.execute("UPDATE t SET a = 1", &()).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(tx.is_poisoned());
+    assert_eq!(rollbacks(&log), 1);
+
+    let err = tx.execute("UPDATE t SET a = 2", &()).await.unwrap_err();
+    assert!(matches!(err, DbError::TransactionAborted(_)));
+    assert!(tx.commit().await.is_err());
+    tx.rollback().await.unwrap();
+    assert_eq!(rollbacks(&log), 1);
+}
+
+#[tokio::test]
+async fn test_warn_only_keeps_transaction_usable() {
+    let (driver, log) = driver(None);
+    let mut tx = TransactionContext::begin(driver).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    tx.execute("UPDATE t SET a = 1", &()).await.unwrap();
+    tx.commit().await.unwrap();
+    assert!(!tx.is_poisoned());
+    assert_eq!(rollbacks(&log), 0);
+}
+
+#[tokio::test]
+async fn test_finished_transaction_releases_connection() {
+    // 监视任务不应让连接在整个 max_duration 内无法归还
+    let (driver, log) = driver(Some(Duration::from_secs(60)));
+    let counters = driver.counters();
+    let mut tx = TransactionContext::begin(driver).await.unwrap();
+    tx.execute("UPDATE t SET a = 1", &()).await.unwrap();
+    tx.commit().await.unwrap();
+    drop(tx);
+    assert_eq!(counters.released(), 1);
+    assert_eq!(rollbacks(&log), 0);
+}
+
+#[test]
+fn test_options_to_limits() {
+    let options = ConnectionOptions {
+        warn_after: 5,
+        ..Default::default()
+    };
+    assert_eq!(
+        options.transaction_limits(),
+        TransactionLimits {
+            warn_after: Some(Duration::from_secs(5)),
+            max_duration: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_history_records_statements() {
+    let driver = Arc::new(MockDriver::new("mock"));
+    let mut tx = TransactionContext::begin(driver).await.unwrap();
+    tx.execute("UPDATE t SET a = #{a}", &HashMap::from([("a", 1)]))
+        .await
+        .unwrap();
+    tx.execute("UPDATE t SET a = #{a}", &HashMap::from([("a", 2)]))
+        .await
+        .unwrap();
+    tx.query("SELECT a FROM t", &()).await.unwrap();
+
+    let history = tx.history();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0].sql, "UPDATE t SET a = ?");
+    assert_ne!(history[0].params_digest, history[1].params_digest);
+    assert!(history.iter().all(|r| r.ok));
+    assert!(
+        history[2]
+            .to_string()
+            .starts_with("SELECT a FROM t [params=")
+    );
+    tx.rollback().await.unwrap();
+}
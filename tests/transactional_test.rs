@@ -0,0 +1,91 @@
+mod common;
+
+use common::{Log, MockDriver};
+use uorm::driver_manager::UORM;
+use uorm::error::DbError;
+use uorm::transactional;
+
+fn register(name: &'static str) -> Log {
+    let driver = MockDriver::new(name);
+    let log = driver.log();
+    UORM.register(driver).unwrap();
+    log
+}
+
+/// 连接上的调用记录，形如 `0:BEGIN`
+fn events(log: &Log) -> Vec<String> {
+    common::take(log)
+        .into_iter()
+        .map(|call| format!("{}:{}", call.conn, call.sql))
+        .collect()
+}
+
+async fn insert(db: &str, tag: &str) -> Result<(), DbError> {
+    let session = UORM.session(db).unwrap();
+    session.execute(&format!("INSERT {}", tag), &()).await?;
+    Ok(())
+}
+
+#[transactional(db = "tx_savepoint")]
+async fn save(tag: &'static str, fail: bool) -> Result<(), DbError> {
+    insert("tx_savepoint", tag).await?;
+    if fail {
+        return Err(DbError::General(format!("{} failed", tag)));
+    }
+    Ok(())
+}
+
+#[transactional(db = "tx_savepoint")]
+async fn save_all() -> Result<(), DbError> {
+    insert("tx_savepoint", "outer").await?;
+    save("a", false).await?;
+    // 内层失败只回滚到保存点，外层继续并提交
+    assert!(save("b", true).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nested_call_uses_savepoint() {
+    let log = register("tx_savepoint");
+    save_all().await.unwrap();
+    assert_eq!(
+        events(&log),
+        [
+            "0:BEGIN",
+            "0:INSERT outer",
+            "0:SAVEPOINT uorm_sp_1",
+            "0:INSERT a",
+            "0:RELEASE SAVEPOINT uorm_sp_1",
+            "0:SAVEPOINT uorm_sp_2",
+            "0:INSERT b",
+            "0:ROLLBACK TO SAVEPOINT uorm_sp_2",
+            "0:COMMIT",
+        ]
+    );
+
+    // 最外层调用失败时回滚整个事务
+    assert!(save("c", true).await.is_err());
+    assert_eq!(events(&log), ["1:BEGIN", "1:INSERT c", "1:ROLLBACK"]);
+}
+
+#[transactional(db = "tx_join", nested = "join")]
+async fn join(tag: &'static str) -> Result<(), DbError> {
+    insert("tx_join", tag).await
+}
+
+#[transactional(db = "tx_join")]
+async fn join_all() -> Result<u32, DbError> {
+    join("a").await?;
+    join("b").await?;
+    Ok(2)
+}
+
+#[tokio::test]
+async fn test_nested_call_joins() {
+    let log = register("tx_join");
+    assert_eq!(join_all().await.unwrap(), 2);
+    assert_eq!(
+        events(&log),
+        ["0:BEGIN", "0:INSERT a", "0:INSERT b", "0:COMMIT"]
+    );
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::{Log, MockDriver, row, take_sql};
+use futures_util::TryStreamExt;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Once};
+use uorm::mapper_loader;
+use uorm::transaction::TransactionContext;
+use uorm::udbc::value::Value;
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    id: i64,
+}
+
+fn load() {
+    static LOAD: Once = Once::new();
+    LOAD.call_once(|| {
+        mapper_loader::load_assets(vec![(
+            "mem://cursor.xml",
+            r#"<mapper namespace="cursor">
+                <select id="users">SELECT id FROM users WHERE id > #{min} ORDER BY id;</select>
+            </mapper>"#,
+        )])
+        .unwrap();
+    });
+}
+
+async fn begin(rows: i64) -> (TransactionContext, Log) {
+    load();
+    // 返回 `rows` 行数据，按 SQL 末尾的 `LIMIT n OFFSET m` 分页
+    let driver = MockDriver::new("cursor").with_query(move |call| {
+        assert_eq!(call.args, [("min".to_string(), Value::I32(0))]);
+        let words: Vec<&str> = call.sql.split_whitespace().collect();
+        let limit: i64 = words[words.len() - 3].parse().unwrap();
+        let offset: i64 = words[words.len() - 1].parse().unwrap();
+        Ok((offset + 1..=(offset + limit).min(rows))
+            .map(|id| row([("id", Value::I64(id))]))
+            .collect())
+    });
+    let log = driver.log();
+    (
+        TransactionContext::begin(Arc::new(driver)).await.unwrap(),
+        log,
+    )
+}
+
+#[tokio::test]
+async fn test_cursor_reads_in_chunks() {
+    let (tx, log) = begin(5).await;
+    let mut cursor = tx
+        .cursor::<Row, _>("cursor.users", &HashMap::from([("min", 0)]), 2)
+        .unwrap();
+    let mut chunks = Vec::new();
+    while let Some(rows) = cursor.next_chunk().await.unwrap() {
+        chunks.push(rows.iter().map(|r| r.id).collect::<Vec<_>>());
+    }
+    assert_eq!(chunks, [vec![1, 2], vec![3, 4], vec![5]]);
+    // 最后一批不足 chunk_size，不再多查一次
+    assert_eq!(
+        take_sql(&log),
+        [
+            "BEGIN",
+            "SELECT id FROM users WHERE id > ? ORDER BY id LIMIT 2 OFFSET 0",
+            "SELECT id FROM users WHERE id > ? ORDER BY id LIMIT 2 OFFSET 2",
+            "SELECT id FROM users WHERE id > ? ORDER BY id LIMIT 2 OFFSET 4",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_cursor_stream() {
+    let (tx, log) = begin(4).await;
+    let ids: Vec<i64> = tx
+        .cursor::<Row, _>("cursor.users", &HashMap::from([("min", 0)]), 2)
+        .unwrap()
+        .into_stream()
+        .map_ok(|r| r.id)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(ids, [1, 2, 3, 4]);
+    assert_eq!(log.lock().unwrap().len(), 4);
+
+    assert!(
+        tx.cursor::<Row, _>("cursor.users", &HashMap::from([("min", 0)]), 0)
+            .is_err()
+    );
+    assert!(tx.cursor::<Row, _>("cursor.missing", &(), 10).is_err());
+}
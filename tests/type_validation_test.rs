@@ -0,0 +1,40 @@
+use serde::Deserialize;
+use uorm::mapper_loader;
+use uorm::type_registry::register_type;
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Order {
+    id: i64,
+    amount: i64,
+}
+
+const XML: &str = r#"<mapper namespace="order">
+    <select id="getById" parameterType="long" resultType="Order">
+        SELECT id, amount FROM orders WHERE id = #{id}
+    </select>
+    <update id="setAmount" parameterType="Order">
+        UPDATE orders SET amount = #{total} WHERE id = #{id}
+    </update>
+    <delete id="purge" parameterType="OrderFilter">
+        DELETE FROM orders WHERE id = #{id}
+    </delete>
+</mapper>"#;
+
+#[test]
+fn test_validate_types() {
+    mapper_loader::load_assets(vec![("order.xml", XML)]).unwrap();
+    let mapper = mapper_loader::find_mapper("order.getById", "mysql").unwrap();
+    assert_eq!(mapper.parameter_type.as_deref(), Some("long"));
+    assert_eq!(mapper.result_type.as_deref(), Some("Order"));
+
+    register_type::<Order>("Order");
+    let err = mapper_loader::validate_types().unwrap_err().to_string();
+    assert!(err.contains("order.setAmount: 参数 'total'"), "{}", err);
+    assert!(
+        err.contains("order.purge: parameterType 'OrderFilter' 未注册"),
+        "{}",
+        err
+    );
+    assert!(!err.contains("order.getById"), "{}", err);
+}
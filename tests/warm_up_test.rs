@@ -0,0 +1,62 @@
+mod common;
+
+use common::MockDriver;
+use uorm::driver_manager::{DriverManager, WarmUpOptions};
+use uorm::mapper_loader;
+use uorm::tpl::cache_stats;
+
+#[tokio::test]
+async fn test_warm_up_opens_connections_and_caches_statements() {
+    mapper_loader::load_assets(vec![(
+        "mem://warm.xml",
+        r#"<mapper namespace="warm">
+            <select id="by_id">SELECT * FROM users WHERE id = #{id}</select>
+            <select id="by_ids">SELECT * FROM users WHERE id IN
+                <foreach item="i" collection="ids" open="(" separator="," close=")">#{i}</foreach>
+            </select>
+        </mapper>"#,
+    )])
+    .unwrap();
+
+    let driver = MockDriver::new("warm");
+    let counters = driver.counters();
+    let manager = DriverManager::new();
+    manager.register(driver).unwrap();
+
+    let before = cache_stats();
+    let report = manager
+        .warm_up(
+            "warm",
+            WarmUpOptions {
+                min_conns: 4,
+                sql_ids: vec!["warm.by_id".to_string(), "warm.by_ids".to_string()],
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.connections, 4);
+    assert_eq!(report.statements, 2);
+    // 连接同时打开，结束后全部归还
+    assert_eq!(counters.peak(), 4);
+    assert_eq!(counters.open(), 0);
+    // 两条语句都已解析进缓存
+    assert_eq!(cache_stats().misses - before.misses, 2);
+
+    let err = manager
+        .warm_up(
+            "warm",
+            WarmUpOptions {
+                sql_ids: vec!["warm.missing".to_string()],
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("warm.missing"), "{}", err);
+    assert!(
+        manager
+            .warm_up("nope", WarmUpOptions::default())
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "actix")]
+
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::{App, HttpRequest, HttpResponse, test, web};
+use common::{Log, MockDriver};
+use std::sync::Arc;
+use uorm::executor::session::Session;
+use uorm::web::actix::{DbSession, transaction};
+
+/// 连接上的调用记录，形如 `0:BEGIN`
+fn events(log: &Log) -> Vec<String> {
+    log.lock()
+        .unwrap()
+        .iter()
+        .map(|call| format!("{}:{}", call.conn, call.sql))
+        .collect()
+}
+
+/// 写入一行后按请求路径返回状态码，`/error` 返回处理错误
+async fn handler(
+    DbSession(session): DbSession,
+    req: HttpRequest,
+) -> actix_web::Result<HttpResponse> {
+    session
+        .execute("INSERT INTO t VALUES (1)", &())
+        .await
+        .unwrap();
+    match req.path() {
+        "/ok" => Ok(HttpResponse::Created().finish()),
+        "/conflict" => Ok(HttpResponse::Conflict().finish()),
+        _ => Err(actix_web::error::ErrorBadRequest("bad input")),
+    }
+}
+
+/// 以新的连接池调用一次应用，返回状态码与连接上的调用记录
+fn call(path: &str) -> (StatusCode, Vec<String>) {
+    actix_web::rt::System::new().block_on(async {
+        let driver = MockDriver::new("web");
+        let log = driver.log();
+        let app = test::init_service(
+            App::new()
+                .app_data(Session::new(Arc::new(driver)))
+                .wrap(actix_web::middleware::from_fn(transaction))
+                .default_service(web::to(handler)),
+        )
+        .await;
+        let response =
+            test::call_service(&app, test::TestRequest::post().uri(path).to_request()).await;
+        let events = events(&log);
+        (response.status(), events)
+    })
+}
+
+#[test]
+fn test_commit_on_success() {
+    let (status, log) = call("/ok");
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(log, ["0:BEGIN", "0:INSERT INTO t VALUES (1)", "0:COMMIT"]);
+}
+
+#[test]
+fn test_rollback_on_error() {
+    for path in ["/conflict", "/error"] {
+        let (status, log) = call(path);
+        assert!(status.is_client_error(), "{}: {}", path, status);
+        assert_eq!(
+            log,
+            ["0:BEGIN", "0:INSERT INTO t VALUES (1)", "0:ROLLBACK"],
+            "{}",
+            path
+        );
+    }
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "axum")]
+
+mod common;
+
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Extension, Router};
+use common::{Log, MockDriver};
+use std::sync::Arc;
+use tower::ServiceExt;
+use uorm::executor::session::Session;
+use uorm::web::axum::{DbSession, transaction};
+
+/// 连接上的调用记录，形如 `0:BEGIN`
+fn events(log: &Log) -> Vec<String> {
+    log.lock()
+        .unwrap()
+        .iter()
+        .map(|call| format!("{}:{}", call.conn, call.sql))
+        .collect()
+}
+
+/// 写入一行后按请求路径返回状态码
+async fn handler(DbSession(session): DbSession, request: Request) -> StatusCode {
+    session
+        .execute("INSERT INTO t VALUES (1)", &())
+        .await
+        .unwrap();
+    match request.uri().path() {
+        "/ok" => StatusCode::CREATED,
+        _ => StatusCode::CONFLICT,
+    }
+}
+
+fn app() -> (Router, Log) {
+    let driver = MockDriver::new("web");
+    let log = driver.log();
+    let router = Router::new()
+        .route("/ok", post(handler))
+        .route("/fail", post(handler))
+        .layer(axum::middleware::from_fn(transaction))
+        .layer(Extension(Session::new(Arc::new(driver))));
+    (router, log)
+}
+
+async fn call(router: Router, path: &str) -> StatusCode {
+    let request = Request::post(path).body(Body::empty()).unwrap();
+    router.oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_commit_on_success() {
+    let (router, log) = app();
+    assert_eq!(call(router, "/ok").await, StatusCode::CREATED);
+    assert_eq!(
+        events(&log),
+        ["0:BEGIN", "0:INSERT INTO t VALUES (1)", "0:COMMIT"]
+    );
+}
+
+#[tokio::test]
+async fn test_rollback_on_error_status() {
+    let (router, log) = app();
+    assert_eq!(call(router, "/fail").await, StatusCode::CONFLICT);
+    assert_eq!(
+        events(&log),
+        ["0:BEGIN", "0:INSERT INTO t VALUES (1)", "0:ROLLBACK"]
+    );
+}
+
+#[tokio::test]
+async fn test_session_without_transaction() {
+    let driver = MockDriver::new("web");
+    let log = driver.log();
+    let router = Router::new()
+        .route("/ok", post(handler))
+        .layer(Extension(Session::new(Arc::new(driver))));
+    assert_eq!(call(router, "/ok").await, StatusCode::CREATED);
+    assert_eq!(events(&log), ["0:INSERT INTO t VALUES (1)"]);
+}
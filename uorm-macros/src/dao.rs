@@ -0,0 +1,450 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+use std::env;
+use std::path::PathBuf;
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Token, Type};
+
+/// 宏参数：dao!("path/to/mapper.xml") 或 dao!("path/to/mapper.xml", name = "UserDao")
+struct DaoArgs {
+    path: LitStr,
+    name: Option<LitStr>,
+}
+
+impl Parse for DaoArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let mut name = None;
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            if !input.is_empty() {
+                let key: Ident = input.parse()?;
+                if key != "name" {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "dao! 仅支持 name = \"...\" 参数",
+                    ));
+                }
+                input.parse::<Token![=]>()?;
+                name = Some(input.parse()?);
+                let _ = input.parse::<Option<Token![,]>>()?;
+            }
+        }
+        Ok(Self { path, name })
+    }
+}
+
+/// XML 中声明的单条语句
+struct Statement {
+    kind: String,
+    id: String,
+    parameter_type: Option<String>,
+    result_type: Option<String>,
+    /// 语句中 `#{}`/`${}`、`<if test>` 与 `<for collection>` 引用的顶层参数名
+    /// （不含 `<for item>` 循环变量；各 databaseType 变体合并，按出现顺序去重）
+    params: Vec<String>,
+}
+
+/// 读取的 Mapper 文件概要
+struct MapperDecl {
+    namespace: String,
+    statements: Vec<Statement>,
+}
+
+pub fn dao_impl(input: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(input as DaoArgs);
+    match expand(&args) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(args: &DaoArgs) -> syn::Result<proc_macro2::TokenStream> {
+    let span = args.path.span();
+    let manifest_dir =
+        env::var("CARGO_MANIFEST_DIR").expect("编译环境异常：未设置 CARGO_MANIFEST_DIR 环境变量");
+    let path = PathBuf::from(manifest_dir).join(args.path.value());
+    let content = std::fs::read_to_string(&path).map_err(|e| {
+        syn::Error::new(
+            span,
+            format!("读取 mapper 文件失败 {}: {}", path.display(), e),
+        )
+    })?;
+    let decl = parse_mapper(&content).map_err(|e| syn::Error::new(span, e))?;
+
+    let trait_name = match &args.name {
+        Some(name) => Ident::new(&name.value(), name.span()),
+        None => {
+            let last = decl.namespace.rsplit('.').next().unwrap_or(&decl.namespace);
+            format_ident!("{}Dao", to_pascal_case(last))
+        }
+    };
+    let impl_name = format_ident!("{}Impl", trait_name);
+
+    let mut trait_items = Vec::new();
+    let mut impl_items = Vec::new();
+    for stmt in &decl.statements {
+        let (decl_sig, body) = method(&decl.namespace, stmt, span)?;
+        let doc = format!("`{}.{}`（<{}>）", decl.namespace, stmt.id, stmt.kind);
+        trait_items.push(quote! {
+            #[doc = #doc]
+            #decl_sig;
+        });
+        impl_items.push(quote! {
+            #decl_sig { #body }
+        });
+    }
+
+    let path_str = path.to_string_lossy().to_string();
+    let trait_doc = format!("由 `{}` 生成的 DAO 接口", args.path.value());
+    let impl_doc = format!("[`{}`] 基于 Mapper 的实现", trait_name);
+    Ok(quote! {
+        // 引用文件内容，使 XML 变更触发重新编译
+        const _: &str = include_str!(#path_str);
+
+        #[doc = #trait_doc]
+        #[::uorm::async_trait::async_trait]
+        pub trait #trait_name {
+            #(#trait_items)*
+        }
+
+        #[doc = #impl_doc]
+        pub struct #impl_name {
+            mapper: ::uorm::executor::mapper::Mapper,
+        }
+
+        impl #impl_name {
+            pub fn new(mapper: ::uorm::executor::mapper::Mapper) -> Self {
+                Self { mapper }
+            }
+
+            /// 使用已注册的数据库创建
+            pub fn from_db(db_name: &str) -> ::core::result::Result<Self, ::uorm::error::DbError> {
+                ::uorm::driver_manager::UORM
+                    .mapper(db_name)
+                    .map(Self::new)
+                    .ok_or_else(|| {
+                        ::uorm::error::DbError::Database(format!("Database not registered: {}", db_name))
+                    })
+            }
+        }
+
+        #[::uorm::async_trait::async_trait]
+        impl #trait_name for #impl_name {
+            #(#impl_items)*
+        }
+    })
+}
+
+/// 生成单个语句对应的方法签名与实现
+fn method(
+    namespace: &str,
+    stmt: &Statement,
+    span: Span,
+) -> syn::Result<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
+    let name = Ident::new(&to_snake_case(&stmt.id), span);
+    let sql_id = LitStr::new(&format!("{}.{}", namespace, stmt.id), span);
+
+    let (generics, param_ty) = match &stmt.parameter_type {
+        Some(t) => (quote! {}, rust_type(t, true, span)?),
+        None => (
+            quote! { <P: ::uorm::serde::Serialize + Sync> },
+            quote! { P },
+        ),
+    };
+    // 标量参数没有字段可供 `#{name}` 解析，以语句引用的参数名包装成单键对象
+    let wrap = match stmt.parameter_type.as_deref() {
+        Some(t) if is_scalar(t) => match stmt.params.as_slice() {
+            [] => quote! {},
+            [name] => quote! {
+                let param = &::uorm::executor::mapper::named_args(vec![
+                    (#name, ::uorm::udbc::serializer::to_value(param)),
+                ]);
+            },
+            names => {
+                return Err(syn::Error::new(
+                    span,
+                    format!(
+                        "<{} id=\"{}\"> 的 parameterType `{}` 为标量，但语句引用了多个参数: {}",
+                        stmt.kind,
+                        stmt.id,
+                        t,
+                        names.join(", ")
+                    ),
+                ));
+            }
+        },
+        _ => quote! {},
+    };
+    let result_ty = stmt
+        .result_type
+        .as_deref()
+        .map(|t| rust_type(t, false, span))
+        .transpose()?;
+
+    let (ret, body) = match stmt.kind.as_str() {
+        "select" => {
+            let r = result_ty.ok_or_else(|| {
+                syn::Error::new(span, format!("<select id=\"{}\"> 缺少 resultType", stmt.id))
+            })?;
+            (
+                quote! { ::std::vec::Vec<#r> },
+                quote! { self.mapper.list(#sql_id, param).await },
+            )
+        }
+        "insert" => {
+            let r = result_ty.unwrap_or_else(|| quote! { u64 });
+            (r, quote! { self.mapper.create(#sql_id, param).await })
+        }
+        "delete" => (
+            quote! { u64 },
+            quote! { self.mapper.delete(#sql_id, param).await },
+        ),
+        _ => (
+            quote! { u64 },
+            quote! { self.mapper.update(#sql_id, param).await },
+        ),
+    };
+
+    let sig = quote! {
+        async fn #name #generics(&self, param: &#param_ty)
+            -> ::core::result::Result<#ret, ::uorm::error::DbError>
+    };
+    Ok((sig, quote! { #wrap #body }))
+}
+
+/// parameterType 是否为标量（MyBatis 别名或 Rust 基本类型）
+fn is_scalar(name: &str) -> bool {
+    matches!(
+        name,
+        "int"
+            | "integer"
+            | "long"
+            | "short"
+            | "byte"
+            | "double"
+            | "float"
+            | "boolean"
+            | "string"
+            | "i8"
+            | "i16"
+            | "i32"
+            | "i64"
+            | "u8"
+            | "u16"
+            | "u32"
+            | "u64"
+            | "f32"
+            | "f64"
+            | "bool"
+            | "String"
+            | "str"
+    )
+}
+
+/// 记录参数路径的根名称；`<for>` 循环变量不是语句参数
+fn push_param(path: &str, scope: &[String], params: &mut Vec<String>) {
+    let name = path.split(['.', '[']).next().unwrap_or_default().trim();
+    if !name.is_empty() && !scope.iter().any(|s| s == name) && !params.iter().any(|p| p == name) {
+        params.push(name.to_string());
+    }
+}
+
+/// 收集 `<if test>` 表达式各比较两侧引用的参数名，字面量（null、布尔、字符串、数字）除外
+fn collect_test_params(test: &str, scope: &[String], params: &mut Vec<String>) {
+    for atom in test.split(" or ").flat_map(|part| part.split(" and ")) {
+        for operand in atom.split(['!', '=', '>', '<']) {
+            let operand = operand.trim();
+            let literal = matches!(operand, "null" | "true" | "false")
+                || operand.starts_with(['\'', '"'])
+                || operand.parse::<f64>().is_ok();
+            if !literal {
+                push_param(operand, scope, params);
+            }
+        }
+    }
+}
+
+/// 收集 `#{...}`、`${...}` 中引用的顶层参数名
+fn collect_params(text: &str, scope: &[String], params: &mut Vec<String>) {
+    let mut rest = text;
+    while let Some(start) = rest.find(['#', '$']) {
+        rest = &rest[start + 1..];
+        let Some(inner) = rest.strip_prefix('{') else {
+            continue;
+        };
+        let Some(end) = inner.find('}') else {
+            break;
+        };
+        let expr = inner[..end].split(',').next().unwrap_or_default();
+        push_param(expr, scope, params);
+        rest = &inner[end + 1..];
+    }
+}
+
+/// 将 parameterType/resultType 转换为 Rust 类型，支持常见的 MyBatis 别名
+fn rust_type(name: &str, param: bool, span: Span) -> syn::Result<proc_macro2::TokenStream> {
+    let alias = match name {
+        "int" | "integer" => "i32",
+        "long" => "i64",
+        "short" => "i16",
+        "byte" => "i8",
+        "double" => "f64",
+        "float" => "f32",
+        "boolean" => "bool",
+        "string" => "String",
+        "map" if param => "::std::collections::HashMap<String, ::uorm::udbc::value::Value>",
+        other => other,
+    };
+    let ty: Type = syn::parse_str(alias)
+        .map_err(|e| syn::Error::new(span, format!("无法解析类型 `{}`: {}", name, e)))?;
+    Ok(quote! { #ty })
+}
+
+/// 读取 Mapper 根节点的命名空间与各语句属性（忽略 <sql> 片段）
+fn parse_mapper(content: &str) -> Result<MapperDecl, String> {
+    let mut reader = Reader::from_str(content);
+    let mut namespace = None;
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut depth = 0usize;
+    // 正在读取的语句在 statements 中的下标
+    let mut current: Option<usize> = None;
+    // 当前语句内外层 `<for>` 的循环变量
+    let mut scope: Vec<String> = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("XML 解析失败: {}", e))?;
+        let (start, empty) = match &event {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(e) => {
+                depth = depth.saturating_sub(1);
+                if depth == 1 {
+                    current = None;
+                    scope.clear();
+                } else if current.is_some() && e.local_name().as_ref() == b"for" {
+                    scope.pop();
+                }
+                continue;
+            }
+            Event::Text(t) => {
+                if let Some(i) = current {
+                    collect_params(
+                        &String::from_utf8_lossy(t.as_ref()),
+                        &scope,
+                        &mut statements[i].params,
+                    );
+                }
+                continue;
+            }
+            Event::CData(t) => {
+                if let Some(i) = current {
+                    collect_params(
+                        &String::from_utf8_lossy(t.as_ref()),
+                        &scope,
+                        &mut statements[i].params,
+                    );
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        let tag = String::from_utf8_lossy(start.local_name().as_ref()).to_string();
+        if let Some(i) = current {
+            // 语句内的嵌套元素：条件与循环集合同样引用参数
+            let params = &mut statements[i].params;
+            if let Some(test) = attr(start, "test")? {
+                collect_test_params(&test, &scope, params);
+            }
+            if let Some(collection) = attr(start, "collection")? {
+                push_param(&collection, &scope, params);
+            }
+            if tag == "for"
+                && !empty
+                && let Some(item) = attr(start, "item")?
+            {
+                scope.push(item);
+            }
+        }
+        if depth == 0 && tag == "mapper" {
+            namespace = attr(start, "namespace")?;
+        } else if depth == 1 && matches!(tag.as_str(), "select" | "insert" | "update" | "delete") {
+            let id = attr(start, "id")?.ok_or_else(|| format!("<{}> 缺少 id 属性", tag))?;
+            // 同一 ID 的多个 databaseType 变体只生成一个方法
+            if seen.insert(id.clone()) {
+                statements.push(Statement {
+                    kind: tag,
+                    id: id.clone(),
+                    parameter_type: attr(start, "parameterType")?,
+                    result_type: attr(start, "resultType")?,
+                    params: Vec::new(),
+                });
+            }
+            if !empty {
+                current = statements.iter().position(|s| s.id == id);
+            }
+        }
+        if !empty {
+            depth += 1;
+        }
+    }
+
+    let namespace = namespace.ok_or("缺少 <mapper namespace=\"...\"> 根节点")?;
+    Ok(MapperDecl {
+        namespace,
+        statements,
+    })
+}
+
+fn attr(start: &BytesStart, key: &str) -> Result<Option<String>, String> {
+    for a in start.attributes() {
+        let a = a.map_err(|e| format!("XML 属性解析失败: {}", e))?;
+        if a.key.as_ref() == key.as_bytes() {
+            let v = a
+                .unescape_value()
+                .map_err(|e| format!("XML 属性解析失败: {}", e))?;
+            return Ok(Some(v.trim().to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// findById -> find_by_id
+fn to_snake_case(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 4);
+    for (i, c) in s.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 && !out.ends_with('_') {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else if c == '-' {
+            out.push('_');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// user_mapper -> UserMapper
+fn to_pascal_case(s: &str) -> String {
+    s.split(['_', '-'])
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let mut chars = p.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,120 @@
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Ident, parse_macro_input};
+
+const REPRS: &[&str] = &["i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64"];
+
+pub fn int_enum_impl(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(name.span(), "IntEnum 只能用于枚举"));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(name.span(), "IntEnum 不支持泛型枚举"));
+    }
+    let repr = repr_type(input)?;
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new(
+                variant.ident.span(),
+                "IntEnum 的变体不能带字段",
+            ));
+        }
+        variants.push(&variant.ident);
+    }
+
+    let serialize_fn = format_ident!("serialize_{}", repr);
+    let name_str = name.to_string();
+    Ok(quote! {
+        impl ::uorm::serde::Serialize for #name {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: ::uorm::serde::Serializer,
+            {
+                let code: #repr = match self {
+                    #( Self::#variants => Self::#variants as #repr, )*
+                };
+                serializer.#serialize_fn(code)
+            }
+        }
+
+        impl<'de> ::uorm::serde::Deserialize<'de> for #name {
+            fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+            where
+                D: ::uorm::serde::Deserializer<'de>,
+            {
+                struct CodeVisitor;
+
+                impl CodeVisitor {
+                    fn variant<E: ::uorm::serde::de::Error>(code: i128) -> ::std::result::Result<#name, E> {
+                        #( if code == (#name::#variants as #repr) as i128 {
+                            return ::std::result::Result::Ok(#name::#variants);
+                        } )*
+                        ::std::result::Result::Err(E::custom(::std::format!(
+                            "unknown {} code: {}", #name_str, code
+                        )))
+                    }
+                }
+
+                impl<'de> ::uorm::serde::de::Visitor<'de> for CodeVisitor {
+                    type Value = #name;
+
+                    fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                        ::std::write!(f, "an integer code of {}", #name_str)
+                    }
+
+                    fn visit_i64<E: ::uorm::serde::de::Error>(self, v: i64) -> ::std::result::Result<#name, E> {
+                        Self::variant(v as i128)
+                    }
+
+                    fn visit_u64<E: ::uorm::serde::de::Error>(self, v: u64) -> ::std::result::Result<#name, E> {
+                        Self::variant(v as i128)
+                    }
+
+                    // 文本协议或 DECIMAL 列返回的数字字符串
+                    fn visit_str<E: ::uorm::serde::de::Error>(self, v: &str) -> ::std::result::Result<#name, E> {
+                        match v.trim().parse::<i128>() {
+                            ::std::result::Result::Ok(code) => Self::variant(code),
+                            ::std::result::Result::Err(_) => ::std::result::Result::Err(
+                                E::invalid_value(::uorm::serde::de::Unexpected::Str(v), &self),
+                            ),
+                        }
+                    }
+                }
+
+                deserializer.deserialize_i64(CodeVisitor)
+            }
+        }
+    })
+}
+
+/// 取 `#[repr(...)]` 中的整数类型
+fn repr_type(input: &DeriveInput) -> syn::Result<Ident> {
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        let mut found = None;
+        attr.parse_nested_meta(|meta| {
+            if let Some(ident) = meta.path.get_ident()
+                && REPRS.iter().any(|r| ident == r)
+            {
+                found = Some(ident.clone());
+            }
+            Ok(())
+        })?;
+        if let Some(repr) = found {
+            return Ok(repr);
+        }
+    }
+    Err(syn::Error::new(
+        input.ident.span(),
+        "IntEnum 需要 #[repr(i8/i16/i32/i64/u8/u16/u32/u64)]",
+    ))
+}
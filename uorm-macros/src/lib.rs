@@ -1,7 +1,93 @@
 mod assets;
+mod dao;
+mod int_enum;
+mod sql;
+mod sql_map;
+mod transactional;
 use proc_macro::TokenStream;
 
 #[proc_macro]
 pub fn mapper_assets(input: TokenStream) -> TokenStream {
     assets::mapper_assets_impl(input)
 }
+
+/// 在编译期读取 mapper XML，生成 DAO trait 及其实现
+///
+/// 每个 `<select>/<insert>/<update>/<delete>` 生成一个 async 方法（ID 转为 snake_case），
+/// 参数类型取自 `parameterType`（缺省为泛型），`<select>` 返回 `Vec<resultType>`，
+/// `<insert>` 返回 `resultType`（缺省 `u64`），更新/删除返回受影响行数。
+/// trait 名默认取命名空间末段加 `Dao` 后缀，可用 `name = "..."` 覆盖；
+/// XML 本身仍需通过 `mapper_assets!` 或 `mapper_loader::load` 注册。
+#[proc_macro]
+pub fn dao(input: TokenStream) -> TokenStream {
+    dao::dao_impl(input)
+}
+
+/// 将无函数体的 async 函数声明绑定到 mapper 语句
+///
+/// 根据返回值 `Result<T, E>` 中的 `T` 推断调用方式：`Vec<_>` 查询列表，
+/// `Option<_>` 查询至多一行，`u64`/`()` 执行更新，其余类型（包括 `Self`）查询单行。
+/// 可通过 `db = "..."` 指定数据库（缺省为语句绑定的 `<mapper datasource>`，再缺省为默认库）、
+/// `op = "..."` 显式指定调用方式。带 `self` 接收者的方法以 `self` 的字段作为参数根对象，
+/// 其余参数按名称并入。
+#[proc_macro_attribute]
+pub fn sql(attr: TokenStream, item: TokenStream) -> TokenStream {
+    sql::sql_impl(attr, item)
+}
+
+/// 在事务中执行 async 函数：返回 `Ok` 时提交，返回 `Err` 时回滚
+///
+/// ```ignore
+/// #[uorm::transactional(db = "app")]
+/// async fn transfer(from: i64, to: i64, amount: i64) -> Result<(), DbError> {
+///     let mapper = UORM.mapper("app").unwrap();
+///     mapper.update("account.debit", &(from, amount)).await?;
+///     mapper.update("account.credit", &(to, amount)).await?;
+///     Ok(())
+/// }
+/// ```
+///
+/// 函数返回 `Result<T, E>`，且 `E: From<DbError>`；函数体内对同一连接池的
+/// `Session`/`Mapper` 调用都在该事务中执行。`db` 缺省为默认连接池。
+/// 已处于事务中时（如被另一个 `#[transactional]` 函数调用），按 `nested` 执行：
+/// `"savepoint"`（默认）在保存点中执行，失败时只回滚到保存点，外层可处理错误后继续；
+/// `"join"` 直接加入外层事务。
+#[proc_macro_attribute]
+pub fn transactional(attr: TokenStream, item: TokenStream) -> TokenStream {
+    transactional::transactional_impl(attr, item)
+}
+
+/// 在 Rust 代码中定义 mapper 语句，程序启动时注册，效果与等价的 XML 文件相同
+///
+/// ```ignore
+/// uorm::sql_map! {
+///     namespace user;
+///     select find_by_id = "SELECT * FROM users WHERE id = #{id}";
+///     update rename = "UPDATE users SET name = #{name} WHERE id = #{id}";
+/// }
+/// ```
+///
+/// 语句类型为 `select`/`insert`/`update`/`delete`，内容按模板语法解析（可使用 `<if>` 等标签）；
+/// 命名空间包含 `.` 时写作字符串字面量。
+#[proc_macro]
+pub fn sql_map(input: TokenStream) -> TokenStream {
+    sql_map::sql_map_impl(input)
+}
+
+/// 为 `#[repr(整数)]` 的无字段枚举实现 `Serialize`/`Deserialize`，按整数值绑定与读取
+///
+/// ```ignore
+/// #[derive(uorm::IntEnum)]
+/// #[repr(u8)]
+/// enum Status {
+///     Active = 1,
+///     Disabled = 2,
+/// }
+/// ```
+///
+/// 以 `#[repr]` 类型序列化（对应 TINYINT 等整数列），反序列化时接受整数或数字字符串，
+/// 值不对应任何变体时报错 `unknown Status code: 9`。
+#[proc_macro_derive(IntEnum)]
+pub fn int_enum(input: TokenStream) -> TokenStream {
+    int_enum::int_enum_impl(input)
+}
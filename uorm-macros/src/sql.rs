@@ -0,0 +1,267 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::{
+    Attribute, Block, Expr, ExprAssign, ExprLit, FnArg, GenericArgument, Lit, LitStr, Pat,
+    PathArguments, ReturnType, Signature, Token, Type, Visibility,
+};
+
+/// 被标注的函数声明：允许以 `;` 结尾而不带函数体（模块级或 impl 块内）
+struct SqlFn {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    sig: Signature,
+    body: Option<Block>,
+}
+
+impl Parse for SqlFn {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let sig = input.parse()?;
+        let body = if input.peek(Token![;]) {
+            input.parse::<Token![;]>()?;
+            None
+        } else {
+            Some(input.parse()?)
+        };
+        Ok(Self {
+            attrs,
+            vis,
+            sig,
+            body,
+        })
+    }
+}
+
+/// 根据返回值类型推断的 Mapper 调用方式
+enum Op {
+    /// `Vec<T>` -> Mapper::list
+    List,
+    /// `Option<T>` -> Mapper::find
+    Find,
+    /// 其他类型（包括 `Self`）-> Mapper::get
+    Get,
+    /// `u64` -> Mapper::update，返回受影响行数
+    Update,
+    /// `()` -> Mapper::update，忽略返回值
+    Unit,
+    /// 显式指定 op = "create"
+    Create,
+}
+
+/// 解析后的属性参数：#[sql("ns.id", db = "name", op = "list")]
+struct SqlArgs {
+    sql_id: LitStr,
+    db: Option<LitStr>,
+    op: Option<LitStr>,
+}
+
+fn parse_args(attr: TokenStream) -> syn::Result<SqlArgs> {
+    let exprs = Punctuated::<Expr, Token![,]>::parse_terminated.parse(attr)?;
+    let mut sql_id = None;
+    let mut db = None;
+    let mut op = None;
+    for expr in exprs {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            }) if sql_id.is_none() => sql_id = Some(s),
+            Expr::Assign(ExprAssign { left, right, .. }) => {
+                let key = match left.as_ref() {
+                    Expr::Path(p) if p.path.get_ident().is_some() => {
+                        p.path.get_ident().unwrap().to_string()
+                    }
+                    other => {
+                        return Err(syn::Error::new_spanned(other, "期望形如 key = \"value\""));
+                    }
+                };
+                let value = match right.as_ref() {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(s), ..
+                    }) => s.clone(),
+                    other => return Err(syn::Error::new_spanned(other, "属性值必须是字符串")),
+                };
+                match key.as_str() {
+                    "id" => sql_id = Some(value),
+                    "db" => db = Some(value),
+                    "op" => op = Some(value),
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            left,
+                            format!("未知的 #[sql] 参数: {}", key),
+                        ));
+                    }
+                }
+            }
+            other => return Err(syn::Error::new_spanned(other, "无法识别的 #[sql] 参数")),
+        }
+    }
+    let sql_id = sql_id.ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            "#[sql] 需要 SQL ID，例如 #[sql(\"user.findById\")]",
+        )
+    })?;
+    Ok(SqlArgs { sql_id, db, op })
+}
+
+/// 取 `Result<T, E>` 中的 `T`
+fn result_ok_type(output: &ReturnType) -> Option<&Type> {
+    let ReturnType::Type(_, ty) = output else {
+        return None;
+    };
+    let Type::Path(tp) = ty.as_ref() else {
+        return None;
+    };
+    let seg = tp.path.segments.last()?;
+    if seg.ident != "Result" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &seg.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(t) => Some(t),
+        _ => None,
+    }
+}
+
+fn infer_op(ty: &Type) -> Op {
+    match ty {
+        Type::Tuple(t) if t.elems.is_empty() => Op::Unit,
+        Type::Path(tp) => match tp.path.segments.last() {
+            Some(seg) if seg.ident == "Vec" => Op::List,
+            Some(seg) if seg.ident == "Option" => Op::Find,
+            Some(seg) if seg.ident == "u64" && tp.path.segments.len() == 1 => Op::Update,
+            _ => Op::Get,
+        },
+        _ => Op::Get,
+    }
+}
+
+pub fn sql_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    match expand(attr, item) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(attr: TokenStream, item: TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let args = parse_args(attr)?;
+    let func: SqlFn = syn::parse(item)?;
+    if let Some(body) = &func.body {
+        return Err(syn::Error::new_spanned(
+            body,
+            "#[sql] 函数不能包含函数体，函数体由宏生成",
+        ));
+    }
+    let sig = &func.sig;
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new_spanned(
+            sig.fn_token,
+            "#[sql] 函数必须是 async fn",
+        ));
+    }
+    let ok_ty = result_ok_type(&sig.output).ok_or_else(|| {
+        syn::Error::new_spanned(&sig.output, "#[sql] 函数的返回值必须是 Result<T, E>")
+    })?;
+
+    let op = match &args.op {
+        None => infer_op(ok_ty),
+        Some(op) => match op.value().as_str() {
+            "list" => Op::List,
+            "find" => Op::Find,
+            "get" => Op::Get,
+            "update" | "delete" if matches!(infer_op(ok_ty), Op::Unit) => Op::Unit,
+            "update" | "delete" => Op::Update,
+            "create" => Op::Create,
+            other => {
+                return Err(syn::Error::new_spanned(
+                    op,
+                    format!(
+                        "未知的 op: {}（可选 list/find/get/create/update/delete）",
+                        other
+                    ),
+                ));
+            }
+        },
+    };
+
+    // 收集参数名；`self` 接收者单独作为模板根对象
+    let mut names = Vec::new();
+    let mut idents = Vec::new();
+    let mut has_receiver = false;
+    for input in &sig.inputs {
+        match input {
+            FnArg::Receiver(_) => has_receiver = true,
+            FnArg::Typed(pt) => match pt.pat.as_ref() {
+                Pat::Ident(pi) => {
+                    names.push(LitStr::new(&pi.ident.to_string(), pi.ident.span()));
+                    idents.push(pi.ident.clone());
+                }
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        other,
+                        "#[sql] 函数参数必须是简单标识符",
+                    ));
+                }
+            },
+        }
+    }
+
+    let sql_id = &args.sql_id;
+    // 未指定 db 时使用语句绑定的连接池（`route` 或 `<mapper datasource>`）
+    let db = match &args.db {
+        Some(db) => quote! { #db },
+        None => quote! {
+            ::uorm::mapper_loader::statement_datasource(#sql_id)
+                .unwrap_or_else(|| ::uorm::udbc::DEFAULT_DB_NAME.to_string())
+        },
+    };
+    let mapper = format_ident!("__uorm_mapper");
+    let call_args = quote! { #sql_id, &__uorm_args };
+    let call = match op {
+        Op::List => quote! { ::core::result::Result::Ok(#mapper.list(#call_args).await?) },
+        Op::Find => quote! { ::core::result::Result::Ok(#mapper.find(#call_args).await?) },
+        Op::Get => quote! { ::core::result::Result::Ok(#mapper.get(#call_args).await?) },
+        Op::Create => quote! { ::core::result::Result::Ok(#mapper.create(#call_args).await?) },
+        Op::Update => quote! { ::core::result::Result::Ok(#mapper.update(#call_args).await?) },
+        Op::Unit => quote! {
+            #mapper.update(#call_args).await?;
+            ::core::result::Result::Ok(())
+        },
+    };
+
+    let named = quote! {
+        vec![#( (#names, ::uorm::udbc::serializer::to_value(&#idents)) ),*]
+    };
+    let root = if has_receiver {
+        quote! {
+            ::uorm::executor::mapper::receiver_args(
+                ::uorm::udbc::serializer::to_value(&self),
+                #named,
+            )
+        }
+    } else {
+        quote! { ::uorm::executor::mapper::named_args(#named) }
+    };
+
+    let attrs = &func.attrs;
+    let vis = &func.vis;
+    // 通过 `Ok(...?)` 将 DbError 转换为调用方声明的错误类型
+    Ok(quote! {
+        #(#attrs)*
+        #[allow(clippy::needless_question_mark)]
+        #vis #sig {
+            let __uorm_db = #db;
+            let #mapper = ::uorm::driver_manager::UORM.mapper(&__uorm_db).ok_or_else(|| {
+                ::uorm::error::DbError::Database(format!("Database not registered: {}", __uorm_db))
+            })?;
+            let __uorm_args = #root;
+            #call
+        }
+    })
+}
@@ -0,0 +1,108 @@
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Token};
+
+/// `namespace user;` 之后跟若干条 `select find_by_id = "...";`
+struct SqlMap {
+    namespace: LitStr,
+    statements: Vec<Statement>,
+}
+
+struct Statement {
+    kind: Ident,
+    id: Ident,
+    sql: LitStr,
+}
+
+const KINDS: &[&str] = &["select", "insert", "update", "delete"];
+
+impl Parse for SqlMap {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let keyword: Ident = input.parse()?;
+        if keyword != "namespace" {
+            return Err(syn::Error::new(
+                keyword.span(),
+                "sql_map! 需以 `namespace <名称>;` 开头",
+            ));
+        }
+        // 命名空间可写作标识符，含 `.` 等字符时写作字符串
+        let namespace = if input.peek(LitStr) {
+            input.parse()?
+        } else {
+            let ident: Ident = input.parse()?;
+            LitStr::new(&ident.to_string(), ident.span())
+        };
+        input.parse::<Token![;]>()?;
+
+        let mut statements = Vec::new();
+        let mut ids = HashSet::new();
+        while !input.is_empty() {
+            let kind: Ident = input.parse()?;
+            if !KINDS.iter().any(|k| kind == k) {
+                return Err(syn::Error::new(
+                    kind.span(),
+                    "语句类型必须是 select、insert、update 或 delete",
+                ));
+            }
+            let id: Ident = input.parse()?;
+            if !ids.insert(id.to_string()) {
+                return Err(syn::Error::new(id.span(), format!("重复的语句 ID: {}", id)));
+            }
+            input.parse::<Token![=]>()?;
+            let sql: LitStr = input.parse()?;
+            input.parse::<Token![;]>()?;
+            statements.push(Statement { kind, id, sql });
+        }
+        Ok(SqlMap {
+            namespace,
+            statements,
+        })
+    }
+}
+
+/// 转义 XML 属性值
+fn escape_attr(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// 以 CDATA 包裹语句内容；内容中的 `]]>` 拆到两个 CDATA 段中
+fn cdata(s: &str) -> String {
+    format!("<![CDATA[{}]]>", s.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+pub fn sql_map_impl(input: TokenStream) -> TokenStream {
+    let map = syn::parse_macro_input!(input as SqlMap);
+    let namespace = map.namespace.value();
+
+    // 生成等价的 mapper XML，注册时与 XML 文件走同一套解析与校验
+    let mut xml = format!("<mapper namespace=\"{}\">", escape_attr(&namespace));
+    for stmt in &map.statements {
+        xml.push_str(&format!(
+            "<{kind} id=\"{id}\">{sql}</{kind}>",
+            kind = stmt.kind,
+            id = stmt.id,
+            sql = cdata(&stmt.sql.value()),
+        ));
+    }
+    xml.push_str("</mapper>");
+
+    let source = format!("sql_map://{}", namespace);
+    let mut hasher = DefaultHasher::new();
+    xml.hash(&mut hasher);
+    let fn_name = format_ident!("__uorm_register_sql_map_{}", hasher.finish());
+
+    let output = quote! {
+        #[uorm::ctor::ctor]
+        fn #fn_name() {
+            let _ = uorm::mapper_loader::load_assets(vec![(#source, #xml)]);
+        }
+    };
+    output.into()
+}
@@ -0,0 +1,92 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{Expr, ExprAssign, ExprLit, ItemFn, Lit, LitStr, Token, parse_macro_input};
+
+/// 解析后的属性参数：#[transactional(db = "name", nested = "savepoint")]
+struct TxArgs {
+    db: Option<LitStr>,
+    nested: Option<LitStr>,
+}
+
+fn parse_args(attr: TokenStream) -> syn::Result<TxArgs> {
+    let exprs = Punctuated::<Expr, Token![,]>::parse_terminated.parse(attr)?;
+    let mut args = TxArgs {
+        db: None,
+        nested: None,
+    };
+    for expr in exprs {
+        let Expr::Assign(ExprAssign { left, right, .. }) = &expr else {
+            return Err(syn::Error::new_spanned(expr, "期望形如 key = \"value\""));
+        };
+        let key = match left.as_ref() {
+            Expr::Path(p) if p.path.get_ident().is_some() => {
+                p.path.get_ident().unwrap().to_string()
+            }
+            other => return Err(syn::Error::new_spanned(other, "期望形如 key = \"value\"")),
+        };
+        let value = match right.as_ref() {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            }) => s.clone(),
+            other => return Err(syn::Error::new_spanned(other, "属性值必须是字符串")),
+        };
+        match key.as_str() {
+            "db" => args.db = Some(value),
+            "nested" => {
+                if !matches!(value.value().as_str(), "savepoint" | "join") {
+                    return Err(syn::Error::new_spanned(
+                        value,
+                        "nested 只能是 \"savepoint\" 或 \"join\"",
+                    ));
+                }
+                args.nested = Some(value);
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    left,
+                    format!("未知的 #[transactional] 参数: {}", key),
+                ));
+            }
+        }
+    }
+    Ok(args)
+}
+
+pub fn transactional_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = match parse_args(attr) {
+        Ok(args) => args,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let func = parse_macro_input!(item as ItemFn);
+    if func.sig.asyncness.is_none() {
+        return syn::Error::new_spanned(func.sig.fn_token, "#[transactional] 只能用于 async 函数")
+            .to_compile_error()
+            .into();
+    }
+
+    let db = match &args.db {
+        Some(db) => quote! { #db },
+        None => quote! { ::uorm::udbc::DEFAULT_DB_NAME },
+    };
+    let nested = match args.nested.as_ref().map(LitStr::value).as_deref() {
+        Some("join") => quote! { ::uorm::executor::session::Nested::Join },
+        _ => quote! { ::uorm::executor::session::Nested::Savepoint },
+    };
+    let attrs = &func.attrs;
+    let vis = &func.vis;
+    let sig = &func.sig;
+    let body = &func.block;
+    // 函数体放入 async 块，其中的 `return` 与 `?` 作用于事务内的执行结果
+    quote! {
+        #(#attrs)*
+        #vis #sig {
+            let __uorm_session = ::uorm::driver_manager::UORM.session(#db).ok_or_else(|| {
+                ::uorm::error::DbError::Database(format!("Database not registered: {}", #db))
+            })?;
+            __uorm_session.transactional(#nested, async move #body).await
+        }
+    }
+    .into()
+}